@@ -0,0 +1,45 @@
+struct CameraUniform {
+    view_projection: mat4x4<f32>,
+    viewport_size: vec2<f32>,
+    output_gamma: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct BillboardInput {
+    @location(0) center: vec3<f32>,
+    /* in pixels, picked on the cpu */
+    @location(1) radius: f32,
+    /* alpha is the blend weight against the sphere */
+    @location(2) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) corner: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32, billboard: BillboardInput) -> VertexOutput {
+    var out: VertexOutput;
+    /* triangle strip quad, corners in [-1, 1] */
+    let corner = vec2<f32>(f32(vertex_index & 1u) * 2.0 - 1.0, f32(vertex_index >> 1u) * 2.0 - 1.0);
+    let clip = camera.view_projection * vec4<f32>(billboard.center, 1.0);
+    /* ndc spans two units per viewport, so the radius in pixels doubles */
+    let offset = corner * 2.0 * billboard.radius / camera.viewport_size * clip.w;
+    out.clip_position = vec4<f32>(clip.xy + offset, clip.z, clip.w);
+    out.corner = corner;
+    out.color = billboard.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    /* solid core with a soft rim */
+    let coverage = 1.0 - smoothstep(0.5, 1.0, length(in.corner));
+    let alpha = in.color.a * coverage;
+    /* premultiplied */
+    return vec4<f32>(pow(in.color.rgb, vec3<f32>(1.0 / camera.output_gamma)) * alpha, alpha);
+}
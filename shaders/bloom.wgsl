@@ -0,0 +1,75 @@
+struct BloomUniform {
+    threshold: f32,
+    /* width of the soft transition below the threshold */
+    knee: f32,
+    intensity: f32,
+};
+
+@group(0) @binding(0)
+var source_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> bloom: BloomUniform;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0);
+    out.uv = vec2<f32>(corner.x, 1.0 - corner.y);
+    return out;
+}
+
+fn source_texel_size() -> vec2<f32> {
+    return 1.0 / vec2<f32>(textureDimensions(source_texture));
+}
+
+/* four bilinear taps between texels, a 4x4 box of the source */
+fn box_sample(uv: vec2<f32>) -> vec3<f32> {
+    let offset = source_texel_size().xyxy * vec4<f32>(-1.0, -1.0, 1.0, 1.0);
+    return (textureSample(source_texture, source_sampler, uv + offset.xy).rgb
+        + textureSample(source_texture, source_sampler, uv + offset.zy).rgb
+        + textureSample(source_texture, source_sampler, uv + offset.xw).rgb
+        + textureSample(source_texture, source_sampler, uv + offset.zw).rgb) * 0.25;
+}
+
+/* keeps only what is brighter than the threshold, with a quadratic knee so it does not pop */
+@fragment
+fn fs_prefilter(in: VertexOutput) -> @location(0) vec4<f32> {
+    let color = box_sample(in.uv);
+    let brightness = max(color.r, max(color.g, color.b));
+    let knee = max(bloom.knee, 1e-4);
+    let soft = clamp(brightness - bloom.threshold + knee, 0.0, 2.0 * knee);
+    let contribution = max(soft * soft / (4.0 * knee), brightness - bloom.threshold) / max(brightness, 1e-4);
+    return vec4<f32>(color * contribution, 1.0);
+}
+
+@fragment
+fn fs_downsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(box_sample(in.uv), 1.0);
+}
+
+/* 3x3 tent, added onto the next larger level by the blend state */
+@fragment
+fn fs_upsample(in: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = source_texel_size();
+    var color = vec3<f32>(0.0);
+    for (var y = -1; y <= 1; y++) {
+        for (var x = -1; x <= 1; x++) {
+            let weight = f32((2 - abs(x)) * (2 - abs(y))) / 16.0;
+            color += textureSample(source_texture, source_sampler, in.uv + vec2<f32>(f32(x), f32(y)) * texel).rgb * weight;
+        }
+    }
+    return vec4<f32>(color, 1.0);
+}
+
+@fragment
+fn fs_composite(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(textureSample(source_texture, source_sampler, in.uv).rgb * bloom.intensity, 1.0);
+}
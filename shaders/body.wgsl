@@ -0,0 +1,114 @@
+struct CameraUniform {
+    view_projection: mat4x4<f32>,
+    viewport_size: vec2<f32>,
+    /* 1.0 on srgb surfaces, otherwise the gamma the fragment output is encoded with */
+    output_gamma: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+/* white texel for bodies without a texture, the instance color carries their flat color */
+@group(1) @binding(0)
+var body_texture: texture_2d<f32>;
+@group(1) @binding(1)
+var body_sampler: sampler;
+
+struct VertexInput {
+    @location(0) position: vec3<f32>,
+    @location(4) uv: vec2<f32>,
+};
+
+struct InstanceInput {
+    @location(1) center: vec3<f32>,
+    @location(2) radius: f32,
+    @location(3) color: vec4<f32>,
+    /* 0 for lit bodies, radiance multiplier for stars */
+    @location(5) emission: f32,
+    /* towards the dominant star, picked on the cpu */
+    @location(6) light_direction: vec3<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+    @location(1) color: vec4<f32>,
+    @location(2) uv: vec2<f32>,
+    @location(3) emission: f32,
+    @location(4) light_direction: vec3<f32>,
+};
+
+/* keeps the night side readable */
+const AMBIENT: f32 = 0.15;
+
+@vertex
+fn vs_main(vertex: VertexInput, instance: InstanceInput) -> VertexOutput {
+    var out: VertexOutput;
+    /* the instance center is relative to the eye, so this stays small near the camera */
+    let relative_position = instance.center + vertex.position * instance.radius;
+    out.clip_position = camera.view_projection * vec4<f32>(relative_position, 1.0);
+    /* the mesh is a unit sphere, so positions double as normals */
+    out.normal = vertex.position;
+    out.color = instance.color;
+    out.uv = vertex.uv;
+    out.emission = instance.emission;
+    out.light_direction = instance.light_direction;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let diffuse = max(dot(normalize(in.normal), normalize(in.light_direction)), 0.0);
+    let albedo = in.color * textureSample(body_texture, body_sampler, in.uv);
+    var color = albedo.rgb * (AMBIENT + (1.0 - AMBIENT) * diffuse);
+    if in.emission > 0.0 {
+        color = albedo.rgb * in.emission;
+    }
+    return vec4<f32>(pow(color, vec3<f32>(1.0 / camera.output_gamma)), albedo.a);
+}
+
+/* debug render modes, see DebugRenderMode */
+
+/* grid of the uv sphere, set by the pipeline */
+override SPHERE_STACKS: f32 = 24.0;
+override SPHERE_SECTORS: f32 = 48.0;
+/* in pixels */
+const WIREFRAME_LINE_WIDTH: f32 = 1.0;
+/* added per fragment, ten layers saturate */
+const OVERDRAW_INCREMENT: vec3<f32> = vec3<f32>(0.1, 0.06, 0.02);
+
+fn encode_output(color: vec3<f32>) -> vec3<f32> {
+    return pow(color, vec3<f32>(1.0 / camera.output_gamma));
+}
+
+/* drawn with polygon lines */
+@fragment
+fn fs_flat(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(encode_output(in.color.rgb), 1.0);
+}
+
+/* without polygon lines. every grid cell is split along the diagonal from its upper right to
+its lower left corner, which gives the barycentric coordinates of the fragment */
+@fragment
+fn fs_wireframe(in: VertexOutput) -> @location(0) vec4<f32> {
+    let cell = fract(in.uv * vec2<f32>(SPHERE_SECTORS, SPHERE_STACKS));
+    var barycentric = vec3<f32>(cell.x, cell.y, 1.0 - cell.x - cell.y);
+    if cell.x + cell.y > 1.0 {
+        barycentric = vec3<f32>(1.0 - cell.x, 1.0 - cell.y, cell.x + cell.y - 1.0);
+    }
+    let edge = barycentric / max(fwidth(barycentric), vec3<f32>(1e-6));
+    if min(edge.x, min(edge.y, edge.z)) > WIREFRAME_LINE_WIDTH {
+        discard;
+    }
+    return vec4<f32>(encode_output(in.color.rgb), 1.0);
+}
+
+@fragment
+fn fs_normals(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(encode_output(normalize(in.normal) * 0.5 + 0.5), 1.0);
+}
+
+@fragment
+fn fs_overdraw(in: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(OVERDRAW_INCREMENT, 1.0);
+}
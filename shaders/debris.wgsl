@@ -0,0 +1,40 @@
+struct CameraUniform {
+    view_projection: mat4x4<f32>,
+    viewport_size: vec2<f32>,
+    output_gamma: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct DebrisInput {
+    @location(0) center: vec3<f32>,
+    /* diameter in pixels, perspective is applied on the cpu */
+    @location(1) size: f32,
+    @location(2) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) corner: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32, particle: DebrisInput) -> VertexOutput {
+    var out: VertexOutput;
+    /* triangle strip quad, corners in [-1, 1] */
+    let corner = vec2<f32>(f32(vertex_index & 1u) * 2.0 - 1.0, f32(vertex_index >> 1u) * 2.0 - 1.0);
+    let clip = camera.view_projection * vec4<f32>(particle.center, 1.0);
+    out.clip_position = vec4<f32>(clip.xy + corner * particle.size / camera.viewport_size * clip.w, clip.z, clip.w);
+    out.corner = corner;
+    out.color = particle.color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let falloff = 1.0 - smoothstep(0.0, 1.0, length(in.corner));
+    let color = in.color.rgb * in.color.a * falloff;
+    return vec4<f32>(pow(color, vec3<f32>(1.0 / camera.output_gamma)), 1.0);
+}
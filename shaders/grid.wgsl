@@ -0,0 +1,90 @@
+struct CameraUniform {
+    view_projection: mat4x4<f32>,
+    viewport_size: vec2<f32>,
+    output_gamma: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct GridInput {
+    @location(0) center: vec3<f32>,
+    @location(1) extent: f32,
+    @location(2) offset: vec2<f32>,
+    @location(3) axis_origin: vec2<f32>,
+    @location(4) spacing: f32,
+    @location(5) fine_alpha: f32,
+    @location(6) fade_distance: f32,
+    @location(7) axes: f32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    /* in the plane, from the point below the eye */
+    @location(0) local: vec2<f32>,
+    @location(1) @interpolate(flat) offset: vec2<f32>,
+    @location(2) @interpolate(flat) axis_origin: vec2<f32>,
+    /* spacing, fine alpha, fade distance, axes */
+    @location(3) @interpolate(flat) tier: vec4<f32>,
+};
+
+const LINE_COLOR: vec3<f32> = vec3<f32>(0.45, 0.5, 0.6);
+const X_AXIS_COLOR: vec3<f32> = vec3<f32>(0.9, 0.25, 0.25);
+const Z_AXIS_COLOR: vec3<f32> = vec3<f32>(0.25, 0.45, 0.95);
+const LINE_ALPHA: f32 = 0.35;
+const AXIS_ALPHA: f32 = 0.8;
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32, grid: GridInput) -> VertexOutput {
+    var out: VertexOutput;
+    /* triangle strip quad, corners in [-1, 1] */
+    let corner = vec2<f32>(f32(vertex_index & 1u) * 2.0 - 1.0, f32(vertex_index >> 1u) * 2.0 - 1.0);
+    let local = corner * grid.extent;
+    out.clip_position = camera.view_projection * vec4<f32>(grid.center + vec3<f32>(local.x, 0.0, local.y), 1.0);
+    out.local = local;
+    out.offset = grid.offset;
+    out.axis_origin = grid.axis_origin;
+    out.tier = vec4<f32>(grid.spacing, grid.fine_alpha, grid.fade_distance, grid.axes);
+    return out;
+}
+
+/* antialiased lines one pixel wide, faded out before the cells get small enough to alias */
+fn grid_lines(position: vec2<f32>, spacing: f32) -> f32 {
+    let coordinate = position / spacing;
+    let cells_per_pixel = fwidth(coordinate);
+    let distance = abs(fract(coordinate - 0.5) - 0.5) / cells_per_pixel;
+    let line = 1.0 - min(min(distance.x, distance.y), 1.0);
+    return line * (1.0 - smoothstep(0.15, 0.4, max(cells_per_pixel.x, cells_per_pixel.y)));
+}
+
+/* the distance in pixels to the line where the coordinate is zero */
+fn axis_line(coordinate: f32) -> f32 {
+    return 1.0 - min(abs(coordinate) / fwidth(coordinate), 1.0);
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let spacing = in.tier.x;
+    let position = in.local + in.offset;
+    let lines = max(grid_lines(position, spacing) * in.tier.y, grid_lines(position, spacing * 10.0));
+    let fade = 1.0 - smoothstep(0.5, 1.0, length(in.local) / in.tier.z);
+
+    /* derivatives are taken outside the branch, which depends on a per fragment input */
+    let axis = in.local - in.axis_origin;
+    let x_axis = axis_line(axis.y) * AXIS_ALPHA;
+    let z_axis = axis_line(axis.x) * AXIS_ALPHA;
+    var color = vec4<f32>(LINE_COLOR, lines * LINE_ALPHA);
+    if in.tier.w > 0.5 {
+        if x_axis > color.a {
+            color = vec4<f32>(X_AXIS_COLOR, x_axis);
+        }
+        if z_axis > color.a {
+            color = vec4<f32>(Z_AXIS_COLOR, z_axis);
+        }
+    }
+    let alpha = color.a * fade;
+    if alpha <= 0.0 {
+        discard;
+    }
+    return vec4<f32>(pow(color.rgb, vec3<f32>(1.0 / camera.output_gamma)), alpha);
+}
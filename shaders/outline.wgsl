@@ -0,0 +1,77 @@
+struct CameraUniform {
+    view_projection: mat4x4<f32>,
+    viewport_size: vec2<f32>,
+    output_gamma: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct OutlineInput {
+    @location(1) center: vec3<f32>,
+    /* world radius of the hull, inner radius in pixels of the ring */
+    @location(2) radius: f32,
+    @location(3) color: vec4<f32>,
+};
+
+struct HullOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+struct RingOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    /* from the center in pixels, the length is taken per fragment */
+    @location(1) offset: vec2<f32>,
+    @location(2) @interpolate(flat) inner_radius: f32,
+};
+
+/* matches OUTLINE_PIXELS */
+const OUTLINE_PIXELS: f32 = 2.5;
+
+fn encode_output(color: vec4<f32>, coverage: f32) -> vec4<f32> {
+    let alpha = color.a * coverage;
+    /* premultiplied */
+    return vec4<f32>(pow(color.rgb, vec3<f32>(1.0 / camera.output_gamma)) * alpha, alpha);
+}
+
+@vertex
+fn vs_hull(@location(0) position: vec3<f32>, outline: OutlineInput) -> HullOutput {
+    var out: HullOutput;
+    out.clip_position = camera.view_projection * vec4<f32>(outline.center + position * outline.radius, 1.0);
+    out.color = outline.color;
+    return out;
+}
+
+@fragment
+fn fs_hull(in: HullOutput) -> @location(0) vec4<f32> {
+    return encode_output(in.color, 1.0);
+}
+
+@vertex
+fn vs_ring(@builtin(vertex_index) vertex_index: u32, outline: OutlineInput) -> RingOutput {
+    var out: RingOutput;
+    /* triangle strip quad, corners in [-1, 1] */
+    let corner = vec2<f32>(f32(vertex_index & 1u) * 2.0 - 1.0, f32(vertex_index >> 1u) * 2.0 - 1.0);
+    /* one extra pixel for the antialiased outer edge */
+    let outer_radius = outline.radius + OUTLINE_PIXELS + 1.0;
+    let clip = camera.view_projection * vec4<f32>(outline.center, 1.0);
+    /* ndc spans two units per viewport, so the radius in pixels doubles */
+    let offset = corner * 2.0 * outer_radius / camera.viewport_size * clip.w;
+    out.clip_position = vec4<f32>(clip.xy + offset, clip.z, clip.w);
+    out.color = outline.color;
+    out.offset = corner * outer_radius;
+    out.inner_radius = outline.radius;
+    return out;
+}
+
+@fragment
+fn fs_ring(in: RingOutput) -> @location(0) vec4<f32> {
+    let distance = length(in.offset) - in.inner_radius;
+    let coverage = clamp(distance + 0.5, 0.0, 1.0) * clamp(OUTLINE_PIXELS + 0.5 - distance, 0.0, 1.0);
+    if coverage <= 0.0 {
+        discard;
+    }
+    return encode_output(in.color, coverage);
+}
@@ -0,0 +1,70 @@
+struct CameraUniform {
+    view_projection: mat4x4<f32>,
+    viewport_size: vec2<f32>,
+    output_gamma: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+/* radial strip, u runs from the inner to the outer radius */
+@group(1) @binding(0)
+var ring_texture: texture_2d<f32>;
+@group(1) @binding(1)
+var ring_sampler: sampler;
+
+struct VertexInput {
+    /* cosine and sine of the angle around the ring, then 0 on the inner and 1 on the outer edge */
+    @location(0) position: vec3<f32>,
+};
+
+struct InstanceInput {
+    @location(1) center: vec3<f32>,
+    @location(2) inner_radius: f32,
+    @location(3) axis_u: vec3<f32>,
+    @location(4) outer_radius: f32,
+    @location(5) axis_v: vec3<f32>,
+    @location(6) opacity: f32,
+    @location(7) light_direction: vec3<f32>,
+    /* 0 for lit rings */
+    @location(8) emission: f32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+    @location(1) radial: f32,
+    @location(2) opacity: f32,
+    @location(3) light_direction: vec3<f32>,
+    @location(4) emission: f32,
+};
+
+/* matches the bodies so the rings never get darker than the night side */
+const AMBIENT: f32 = 0.15;
+
+@vertex
+fn vs_main(vertex: VertexInput, instance: InstanceInput) -> VertexOutput {
+    var out: VertexOutput;
+    let radius = mix(instance.inner_radius, instance.outer_radius, vertex.position.z);
+    let offset = (instance.axis_u * vertex.position.x + instance.axis_v * vertex.position.y) * radius;
+    out.clip_position = camera.view_projection * vec4<f32>(instance.center + offset, 1.0);
+    out.normal = cross(instance.axis_u, instance.axis_v);
+    out.radial = vertex.position.z;
+    out.opacity = instance.opacity;
+    out.light_direction = instance.light_direction;
+    out.emission = instance.emission;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    /* the body sampler repeats u, keep the edges from wrapping into each other */
+    let sample = textureSample(ring_texture, ring_sampler, vec2<f32>(clamp(in.radial, 0.002, 0.998), 0.5));
+    /* double sided, both faces catch the light the same */
+    let diffuse = abs(dot(normalize(in.normal), normalize(in.light_direction)));
+    var color = sample.rgb * (AMBIENT + (1.0 - AMBIENT) * diffuse);
+    if in.emission > 0.0 {
+        color = sample.rgb * in.emission;
+    }
+    return vec4<f32>(pow(color, vec3<f32>(1.0 / camera.output_gamma)), sample.a * in.opacity);
+}
@@ -0,0 +1,43 @@
+struct CameraUniform {
+    view_projection: mat4x4<f32>,
+    viewport_size: vec2<f32>,
+    output_gamma: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct StarInput {
+    @location(0) direction: vec3<f32>,
+    @location(1) size: f32,
+    @location(2) brightness: f32,
+};
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) corner: vec2<f32>,
+    @location(1) brightness: f32,
+};
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex_index: u32, star: StarInput) -> VertexOutput {
+    var out: VertexOutput;
+    /* triangle strip quad, corners in [-1, 1] */
+    let corner = vec2<f32>(f32(vertex_index & 1u) * 2.0 - 1.0, f32(vertex_index >> 1u) * 2.0 - 1.0);
+    /* w = 0 puts the star at infinity, only the rotation of the view applies */
+    var clip = camera.view_projection * vec4<f32>(star.direction, 0.0);
+    /* the pass does not test depth, 0 is the infinite far plane of the reversed-z projection */
+    clip.z = 0.0;
+    clip = vec4<f32>(clip.xy + corner * star.size / camera.viewport_size * clip.w, clip.z, clip.w);
+    out.clip_position = clip;
+    out.corner = corner;
+    out.brightness = star.brightness;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let falloff = 1.0 - smoothstep(0.0, 1.0, length(in.corner));
+    let intensity = pow(in.brightness * falloff, 1.0 / camera.output_gamma);
+    return vec4<f32>(vec3<f32>(intensity), 1.0);
+}
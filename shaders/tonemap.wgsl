@@ -0,0 +1,57 @@
+struct TonemapUniform {
+    exposure: f32,
+    /* 0 aces, 1 reinhard */
+    curve: u32,
+    output_gamma: f32,
+    /* adjustable, applied before the output encoding */
+    gamma: f32,
+};
+
+@group(0) @binding(0)
+var hdr_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var hdr_sampler: sampler;
+@group(0) @binding(2)
+var<uniform> tonemap: TonemapUniform;
+
+struct VertexOutput {
+    @builtin(position) clip_position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+};
+
+/* one triangle covering the screen, no vertex buffer */
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> VertexOutput {
+    let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.clip_position = vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0);
+    out.uv = vec2<f32>(corner.x, 1.0 - corner.y);
+    return out;
+}
+
+/* narkowicz fit of the aces filmic curve */
+fn aces(color: vec3<f32>) -> vec3<f32> {
+    let a = 2.51;
+    let b = 0.03;
+    let c = 2.43;
+    let d = 0.59;
+    let e = 0.14;
+    return clamp((color * (a * color + b)) / (color * (c * color + d) + e), vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+fn reinhard(color: vec3<f32>) -> vec3<f32> {
+    return color / (color + vec3<f32>(1.0));
+}
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+    let hdr = textureSample(hdr_texture, hdr_sampler, in.uv).rgb * tonemap.exposure;
+    var mapped: vec3<f32>;
+    if tonemap.curve == 1u {
+        mapped = reinhard(hdr);
+    } else {
+        mapped = aces(hdr);
+    }
+    mapped = pow(mapped, vec3<f32>(1.0 / tonemap.gamma));
+    return vec4<f32>(pow(mapped, vec3<f32>(1.0 / tonemap.output_gamma)), 1.0);
+}
@@ -1,149 +1,782 @@
-use std::sync::{Arc, MutexGuard};
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
-use anyhow::{Ok, Result};
+use anyhow::{Context, Ok, Result, anyhow};
 use getset::{Getters, Setters};
-use imgui::{FontConfig, FontSource, TextureId, Ui};
-use imgui_wgpu::TextureConfig;
+use glam::DVec2;
 use pollster::FutureExt;
-use tracing::{info, warn};
-use wgpu::{InstanceFlags, Surface, SurfaceConfiguration, naga::FastHashMap};
+use tracing::{Level, error, info, level_filters::LevelFilter, warn};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use wgpu::{InstanceFlags, Surface, SurfaceConfiguration, SurfaceError};
 use winit::{
     application::ApplicationHandler,
-    dpi::LogicalSize,
-    event::Event,
-    event_loop::{EventLoop, EventLoopProxy},
-    keyboard::{self, KeyCode},
-    window::{Window, WindowAttributes},
+    dpi::{LogicalSize, PhysicalPosition, PhysicalSize},
+    event::{Event, MouseButton},
+    event_loop::{ControlFlow, EventLoop, EventLoopProxy},
+    keyboard::{self, ModifiersState},
+    window::{Fullscreen, Icon, Window, WindowAttributes},
+};
+
+use crate::{
+    cli::{self, LaunchOptions},
+    crash,
+    graphics::{
+        self, RetainedGraphicsState, SimulationGraphcisInterface, SurfaceFailureTracker,
+        SurfaceRecovery,
+        billboards::BillboardPipeline,
+        body_texture::BodyTextureCache,
+        camera::{self, Camera, CameraBinding, CameraController, ViewportClick},
+        capture::ScreenshotCapture,
+        debris::{DebrisPipeline, DebrisSystem},
+        device_loss::DeviceLossMonitor,
+        exit_dialog::ExitChoice,
+        file_menu::FileAction,
+        fonts::{FontConfiguration, Script, UiFonts},
+        gizmos::GizmoSettings,
+        grid::{GridPipeline, GridSettings},
+        horizons_dialog::HorizonsRequest,
+        import_dialog::BodyImport,
+        outline::OutlinePipeline,
+        picking,
+        pipeline::{BodyPipeline, DebugRenderMode},
+        post::BloomPipeline,
+        rings::RingPipeline,
+        save_slots_window::SaveSlotRequest,
+        scenario_browser::ScenarioChoice,
+        scenario_dialog::{self, PendingScenario},
+        spawn_dialog::BodySpawn,
+        stats::{FrameWindow, GpuTimer, StatsAggregator},
+        surface,
+        targets::{self, RenderTargets},
+        texture_loader::{DecodedTexture, TextureLoader},
+        textures::{
+            EMBEDDED_ICON, ICON_ASSET_PATH, ICON_TEXTURE_KEY, TextureHandle, TextureManager,
+        },
+        title_bar::WindowAction,
+        toasts::ToastSeverity,
+        tonemap::{self, TonemapPipeline},
+        toolbar,
+        trail::{TrailPipeline, TrailSettings},
+        ui::UiState,
+        viewport::{self, Viewport},
+    },
+    headless,
+    input::{InputAction, InputState, KeyBinding},
+    localization,
+    logging::{LogBuffer, LogLayer},
+    platform::{
+        self,
+        gamepad::{GamepadEvent, GamepadWatcher},
+    },
+    scenario::{self, ScenarioPreset},
+    simulation::{
+        BodyEdit, BodyId, SimulationBody, SimulationEvent, SimulationPhysicsObject,
+        SimulationState,
+        recording::TrajectoryRecording,
+        thread::{
+            HiddenPolicy, MAXIMUM_CATCH_UP, SimulationCommand, SimulationSnapshot,
+            SimulationThreadHandle,
+        },
+    },
+    storage::{
+        self, AssetResolver, SaveSlotKind, Scenario, ScenarioFormat, ScenarioMetadata, Settings,
+        Thumbnail, WindowGeometry,
+    },
 };
 
-use crate::graphics::{self, SimulationGraphcisInterface};
+mod environment;
+mod saving;
+
+/* simulated seconds per wall second at startup */
+pub const DEFAULT_SIMULATION_WARP: f64 = 3600.;
+pub const SIMULATION_WARP_STEP: f64 = 10.;
+pub const DEFAULT_WINDOW_SIZE: LogicalSize<u32> = LogicalSize::new(1200, 600);
+/* the toolbar, the inspector and the viewport still fit side by side */
+pub const MINIMUM_WINDOW_SIZE: LogicalSize<u32> = LogicalSize::new(960, 540);
+/* the design asset, embedded so the window never goes without it */
+pub const WINDOW_ICON: &[u8] = include_bytes!("../design/Hintergrund.png");
+/* the asset is scaled down to this, the platforms pick their smaller sizes from it */
+pub const WINDOW_ICON_SIZE: u32 = 256;
+/* moving or resizing writes the settings file once the window rests this long */
+pub const WINDOW_GEOMETRY_SAVE_DELAY: Duration = Duration::from_secs(1);
+/* the first frame after the window idled would otherwise jump the camera held by a stick */
+pub const GAMEPAD_MAXIMUM_FRAME_TIME: Duration = Duration::from_millis(100);
 
 pub enum ApplicationSimulationEvent {
-    ApplicationDrawImguiGraphics(MutexGuard<'static, &'static mut Ui>),
+    /* sent by the simulation thread after publishing a snapshot, doubles as redraw wake-up */
+    SimulationSnapshotPublished(Vec<SimulationEvent>),
+    ScreenshotSaved(PathBuf),
+    SelectionChanged(Option<BodyId>),
+    /* edited in the settings window, applied and saved on the main thread */
+    SettingsChanged(Settings),
+    /* from the settings window, forgets where the windows were moved */
+    UiLayoutReset,
+    /* exposure or gamma set in the display menu, remembered in the settings */
+    DisplayAdjusted,
+    /* from the gamepad thread, the sticks are read once per frame */
+    GamepadChanged(GamepadEvent),
+    /* from the custom title bar of the undecorated window */
+    WindowRequested(WindowAction),
+    /* pause, step and warp from the toolbar */
+    ControlRequested(SimulationCommand),
+    /* from the inspector and the add body window, forwarded to the simulation thread */
+    BodyEdited(BodyId, BodyEdit),
+    BodyDeleted(BodyId),
+    BodySpawned(BodySpawn),
+    /* a table to append from the import window */
+    BodiesImported(BodyImport),
+    /* vector tables to merge into a new scenario from the horizons window */
+    HorizonsImportRequested(HorizonsRequest),
+    /* from the file menu, the dialog is shown on another thread */
+    FileDialogRequested(FileAction),
+    /* none when the dialog was cancelled */
+    FileChosen {
+        action: FileAction,
+        chosen: Option<PathBuf>,
+    },
+    /* from the thread writing the scenario chosen in save as */
+    ScenarioSavedAs(Result<PathBuf, String>),
+    /* from the file menu, answered by opening the properties with the running metadata */
+    ScenarioPropertiesRequested,
+    ScenarioPropertiesChanged(ScenarioMetadata),
+    /* texture keys and images of the scenario browser, uploaded on the main thread */
+    ThumbnailsRequested(Vec<(String, Thumbnail)>),
+    /* picked in the scenario browser, loaded like a dropped file */
+    ScenarioRequested(ScenarioChoice),
+    /* a dropped scenario whose replacing the edited one was confirmed */
+    ScenarioConfirmed(PendingScenario),
+    /* answered in the dialog asking about the edits on quitting */
+    ExitChosen(ExitChoice),
+    /* from the thread writing the scenario before quitting */
+    ScenarioSaved(Result<PathBuf, String>),
+    /* from the thread writing a quicksave, an autosave or a save of the saves window */
+    StateSaved {
+        kind: SaveSlotKind,
+        saved: Result<PathBuf, String>,
+    },
+    /* chosen in the dialog offered after an unclean shutdown */
+    AutosaveRestored(PathBuf),
+    /* a slot of the saves window written into or loaded */
+    SaveSlotRequested(SaveSlotRequest),
+    /* from the trajectories window, written on another thread */
+    TrajectoriesExportRequested {
+        recording: Arc<Mutex<TrajectoryRecording>>,
+        path: PathBuf,
+    },
+    /* from the thread writing the trajectories */
+    TrajectoriesExported(Result<PathBuf, String>),
+    /* decoded by the texture loader thread, uploaded on the main thread */
+    TextureDecoded(DecodedTexture),
+    /* file name of a wgsl file that changed on disk */
+    #[cfg(feature = "shader-hot-reload")]
+    ShaderChanged(String),
+    /* the loaded scenario file was saved, sent once a burst of writes settled */
+    #[cfg(feature = "scenario-hot-reload")]
+    ScenarioChanged,
+    /* sent from the device lost callback, the next redraw rebuilds the graphics interface */
+    DeviceLost,
 }
 
-#[derive(derive_new::new, Setters, Getters)]
+#[derive(Setters, Getters)]
 pub struct ApplicationSimulationInterface<'w> {
     pub winit_window_handle: Arc<Window>,
+    /* none only while a rebuild after a device loss failed, retried on every redraw */
     pub graphics_interface: Option<SimulationGraphcisInterface<'w>>,
+    /* state of the lost interface until a rebuild succeeds */
+    pub retained_graphics_state: Option<RetainedGraphicsState>,
     pub imgui_context: imgui::Context,
     pub imgui_platform: imgui_winit_support::WinitPlatform,
     pub imgui_renderer: imgui_wgpu::Renderer,
     pub event_proxy: EventLoopProxy<ApplicationSimulationEvent>,
-    pub texture_map: FastHashMap<&'static str, TextureId>,
+    pub texture_manager: TextureManager,
+    pub simulation_thread: SimulationThreadHandle,
+    /* as saved in the settings file */
+    pub settings: Settings,
+    pub ui_fonts: UiFonts,
+    /* start of the previous redraw, the frame delta is measured between two of them */
+    pub last_redraw: Option<Instant>,
+    /* when the next frame is drawn, requested once the event loop runs out of events. none
+    while nothing is drawn continuously, like when hidden */
+    pub next_frame: Option<Instant>,
+    pub modifiers: ModifiersState,
+    /* mouse and held keys for the scene, consumed once per frame */
+    pub input_state: InputState,
+    /* filled by the tracing layer, shown in the log window */
+    pub log_buffer: LogBuffer,
+    /* inner size before entering fullscreen, restored when leaving it */
+    pub windowed_size: Option<PhysicalSize<u32>>,
+    /* moved or resized since, remembered in the settings once it rests */
+    pub geometry_changed: Option<Instant>,
+    /* several dropped files arrive as one event each before the next redraw, only the first loads */
+    pub file_dropped: bool,
+    /* shown in the status bar, the file name or the name of the built-in scene */
+    pub scenario_label: String,
+    /* the file the running scenario was loaded from, none for the built-in scene */
+    pub scenario_path: Option<PathBuf>,
+    /* the unix time of simulation time zero, from the epoch of the running scenario */
+    pub simulation_epoch: i64,
+    /* what describes the running scenario, written with it when it is saved */
+    pub scenario_metadata: ScenarioMetadata,
+    /* changed in the scenario properties since the scenario was loaded, asked about on quitting
+    like edited bodies */
+    pub metadata_edited: bool,
+    /* a quicksave or an autosave is being written */
+    pub state_saving: bool,
+    /* a file dialog is shown, the file menu opens no second one */
+    pub file_dialog_open: bool,
+    /* or the start, the interval is wall time */
+    pub last_autosave: Instant,
+    /* from the command line, kept for the adapter override of a rebuild */
+    pub launch_options: LaunchOptions,
+    /* fully covered by other windows, as last reported */
+    pub occluded: bool,
+    /* minimized or covered since, nothing is rendered meanwhile */
+    pub hidden_since: Option<Instant>,
+    /* paused by the hidden policy, resumed when the window shows again */
+    pub paused_while_hidden: bool,
+    /* none when the controllers could not be read */
+    pub gamepad_watcher: Option<GamepadWatcher>,
+    /* only held to keep watching, none when the shader directory could not be watched */
+    /* why the event loop was left early, returned from execute */
+    pub fatal_error: Option<anyhow::Error>,
+    #[cfg(feature = "shader-hot-reload")]
+    pub _shader_watcher: Option<graphics::shaders::ShaderWatcher>,
+    /* like the shader watcher, follows the scenario path */
+    #[cfg(feature = "scenario-hot-reload")]
+    pub _scenario_watcher: Option<scenario::ScenarioWatcher>,
 }
 
 pub fn execute() -> Result<()> {
-    tracing_subscriber::fmt()
-        .with_thread_names(true)
-        .with_ansi(true)
-        .with_file(true)
+    /* before anything is initialized, a typo must not open a window first */
+    let launch_options = match cli::parse(std::env::args().skip(1)) {
+        Result::Ok(cli::Command::Launch(launch_options)) => launch_options,
+        Result::Ok(cli::Command::Help) => {
+            println!("{}", cli::USAGE);
+            return Ok(());
+        }
+        /* before logging too, the schema alone goes to stdout for redirecting into a file */
+        Result::Ok(cli::Command::DumpSchema) => {
+            println!("{}", storage::scenario_schema_json()?);
+            return Ok(());
+        }
+        Result::Ok(cli::Command::ReportI18n(language)) => {
+            print!("{}", localization::report(&language));
+            return Ok(());
+        }
+        Err(error) => {
+            eprintln!("{}\n\n{}", error, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+    let log_buffer = LogBuffer::default();
+    crash::install_panic_hook(log_buffer.clone());
+    tracing_subscriber::registry()
+        .with(LevelFilter::INFO)
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_thread_names(true)
+                .with_ansi(true)
+                .with_file(true),
+        )
+        .with(LogLayer::new(log_buffer.clone()))
         .init();
     info!("Executing SpaceS simulation application...");
-    self::enable_event_loop()?;
+    if launch_options.headless {
+        let settings = storage::load_settings(&scenario::settings_path(&launch_options));
+        return headless::run(&launch_options, &settings);
+    }
+    self::enable_event_loop(log_buffer, launch_options)?;
     Ok(())
 }
 
-pub fn enable_event_loop() -> Result<()> {
+pub fn enable_event_loop(log_buffer: LogBuffer, launch_options: LaunchOptions) -> Result<()> {
     let event_loop: EventLoop<ApplicationSimulationEvent> = EventLoop::with_user_event().build()?;
+    let settings = storage::load_settings(&scenario::settings_path(&launch_options));
+    localization::set_language(&localization::effective_language(&settings.language));
+    let unclean_exit = storage::begin_session()
+        .inspect_err(|error| warn!("Crash recovery is unavailable: {:#}", error))
+        .unwrap_or(false);
+    let window_icon = self::window_icon();
+    /* shown once the remembered geometry is restored, it would jump otherwise */
+    let window_attributes = WindowAttributes::default()
+        .with_active(true)
+        .with_visible(false)
+        .with_inner_size(DEFAULT_WINDOW_SIZE)
+        .with_min_inner_size(MINIMUM_WINDOW_SIZE)
+        .with_decorations(false)
+        .with_resizable(true)
+        .with_title(localization::tr("window-title"))
+        .with_window_icon(window_icon.clone());
+    /* the taskbar and alt-tab take their own, larger icon on windows */
+    #[cfg(target_os = "windows")]
+    let window_attributes = {
+        use winit::platform::windows::WindowAttributesExtWindows;
+        window_attributes.with_taskbar_icon(window_icon)
+    };
+    #[allow(deprecated)]
     let window = Arc::new(
         event_loop
-            .create_window(
-                WindowAttributes::default()
-                    .with_active(true)
-                    .with_inner_size(LogicalSize::new(1200, 600))
-                    .with_decorations(false)
-                    .with_resizable(false)
-                    .with_title("SpaceS"),
-            )
+            .create_window(window_attributes)
             .expect("Failed to construct main window."),
     );
-    let graphics_interface = ApplicationSimulationInterface::on_enable_interface(window.clone())?;
+    let windowed_size = self::restore_window_geometry(&window, &settings.window);
+    window.set_visible(true);
+    let launch_scenario = scenario::launch_scenario(&launch_options)?;
+    let mut graphics_interface = ApplicationSimulationInterface::on_enable_interface(
+        window.clone(),
+        &event_loop,
+        &settings,
+        launch_options.gpu.as_deref(),
+    )?;
 
     let mut imgui_context = imgui::Context::create();
-    imgui_context.set_ini_filename(None);
-    let mut imgui_platform = imgui_winit_support::WinitPlatform::new(&mut imgui_context);
-    imgui_platform.attach_window(
-        imgui_context.io_mut(),
-        &window,
-        imgui_winit_support::HiDpiMode::Default,
-    );
+    let imgui_platform = self::configure_imgui_context(&mut imgui_context, &window, &settings);
 
-    imgui_context
-        .fonts()
-        .add_font(&[FontSource::DefaultFontData {
-            config: Some(FontConfig {
-                size_pixels: 13.,
-                ..Default::default()
-            }),
-        }]);
-
-    let renderer_config = imgui_wgpu::RendererConfig {
-        texture_format: graphics_interface.surface_configuration.format,
-        ..Default::default()
-    };
-
-    let mut imgui_renderer = imgui_wgpu::Renderer::new(
+    let mut ui_fonts = UiFonts::new(&imgui_context);
+    ui_fonts.build(
         &mut imgui_context,
-        &graphics_interface.gpu_interface,
-        &graphics_interface.gpu_queue,
-        renderer_config,
+        self::font_configuration(&settings, &imgui_platform),
     );
 
-    let tex_load = Arc::new(
-        graphics::write_image_from_path_msaa_off(
-            &graphics_interface.surface_configuration,
-            &graphics_interface.gpu_interface,
-            &graphics_interface.gpu_queue,
-            "design/Hintergrund.png".parse()?,
-        )
-        .expect("failed to write texture to gpu."),
+    let mut imgui_renderer =
+        self::create_imgui_renderer(&mut imgui_context, &mut graphics_interface);
+
+    let mut texture_manager = TextureManager::default();
+    let icon = self::load_icon(
+        &mut texture_manager,
+        &graphics_interface,
+        &mut imgui_renderer,
     );
+    texture_manager.alias(ICON_TEXTURE_KEY, &icon);
 
-    let mut texture_map = FastHashMap::default();
-    let icon_texture_id = imgui_renderer.textures.insert(imgui_wgpu::Texture::new(
-        &graphics_interface.gpu_interface,
-        &imgui_renderer,
-        TextureConfig {
-            size: tex_load.size(),
-            label: Some("ila"),
-            format: Some(tex_load.format()),
-            usage: tex_load.usage(),
-            mip_level_count: tex_load.mip_level_count(),
-            sample_count: tex_load.sample_count(),
-            dimension: tex_load.dimension(),
-            sampler_desc: wgpu::SamplerDescriptor {
-                label: Some("Image Sampler"),
-                address_mode_u: wgpu::AddressMode::ClampToEdge,
-                address_mode_v: wgpu::AddressMode::ClampToEdge,
-                address_mode_w: wgpu::AddressMode::ClampToEdge,
-                mag_filter: wgpu::FilterMode::Linear,
-                min_filter: wgpu::FilterMode::Linear,
-                mipmap_filter: wgpu::FilterMode::Nearest,
-                ..Default::default()
-            },
-        },
-    ));
-    info!("load icon :[{:?}]", icon_texture_id);
-    texture_map.insert("tex.icon", icon_texture_id);
+    let launch_assets = AssetResolver::for_scenario(launch_options.scenario.as_deref());
+    for warning in launch_scenario.warnings(&launch_assets) {
+        graphics_interface
+            .ui
+            .toasts
+            .push(ToastSeverity::Warning, warning.to_string(), Vec::new());
+    }
+    let simulation_state = scenario::simulation_state(&launch_scenario.objects, &settings);
+    let (scene_center, scene_radius) = camera::bounding_sphere(&simulation_state.bodies);
+    graphics_interface
+        .camera
+        .frame_bounding_sphere(scene_center, scene_radius);
 
-    let mut application = ApplicationSimulationInterface::new(
-        window,
-        Some(graphics_interface),
+    let texture_proxy = event_loop.create_proxy();
+    graphics_interface
+        .body_textures
+        .set_loader(TextureLoader::spawn(move |decoded| {
+            let _ = texture_proxy.send_event(ApplicationSimulationEvent::TextureDecoded(decoded));
+        })?);
+    graphics_interface.body_textures.set_assets(launch_assets);
+
+    #[cfg(feature = "shader-hot-reload")]
+    let shader_watcher = {
+        let shader_proxy = event_loop.create_proxy();
+        graphics::shaders::ShaderWatcher::spawn(move |file_name| {
+            let _ = shader_proxy.send_event(ApplicationSimulationEvent::ShaderChanged(file_name));
+        })
+        .inspect_err(|error| warn!("Shader hot reloading is unavailable: {:#}", error))
+        .ok()
+    };
+
+    let gamepad_proxy = event_loop.create_proxy();
+    let gamepad_watcher = GamepadWatcher::spawn(move |event| {
+        let _ = gamepad_proxy.send_event(ApplicationSimulationEvent::GamepadChanged(event));
+    })
+    .inspect_err(|error| warn!("Gamepads are unavailable: {:#}", error))
+    .ok();
+
+    let simulation_thread = self::spawn_simulation_thread(
+        simulation_state,
+        launch_options.warp.unwrap_or(DEFAULT_SIMULATION_WARP),
+        event_loop.create_proxy(),
+    )?;
+
+    let mut application = ApplicationSimulationInterface {
+        winit_window_handle: window,
+        graphics_interface: Some(graphics_interface),
+        retained_graphics_state: None,
         imgui_context,
         imgui_platform,
         imgui_renderer,
-        event_loop.create_proxy(),
-        texture_map,
-    );
+        event_proxy: event_loop.create_proxy(),
+        texture_manager,
+        simulation_thread,
+        settings,
+        ui_fonts,
+        last_redraw: None,
+        next_frame: None,
+        modifiers: ModifiersState::empty(),
+        input_state: InputState::default(),
+        log_buffer,
+        windowed_size,
+        geometry_changed: None,
+        file_dropped: false,
+        scenario_label: scenario::launch_scenario_label(&launch_options),
+        scenario_path: launch_options.scenario.clone(),
+        simulation_epoch: launch_scenario.epoch_seconds(),
+        scenario_metadata: launch_scenario.metadata,
+        metadata_edited: false,
+        state_saving: false,
+        file_dialog_open: false,
+        last_autosave: Instant::now(),
+        launch_options,
+        occluded: false,
+        hidden_since: None,
+        paused_while_hidden: false,
+        gamepad_watcher,
+        fatal_error: None,
+        #[cfg(feature = "shader-hot-reload")]
+        _shader_watcher: shader_watcher,
+        #[cfg(feature = "scenario-hot-reload")]
+        _scenario_watcher: None,
+    };
+    #[cfg(feature = "scenario-hot-reload")]
+    application.watch_scenario();
+    if unclean_exit
+        && let Some((path, saved_at)) = storage::newest_autosave()
+        && let Some(graphics_interface) = application.graphics_interface.as_mut()
+    {
+        info!(
+            "The previous session did not shut down, offering {}",
+            path.display()
+        );
+        graphics_interface.ui.restore_dialog.offer(path, saved_at);
+    }
 
     event_loop.run_app(&mut application)?;
-    Ok(())
+    match application.fatal_error.take() {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
 }
 
 impl<'a> ApplicationHandler<ApplicationSimulationEvent> for ApplicationSimulationInterface<'a> {
     fn resumed(&mut self, _event_loop: &winit::event_loop::ActiveEventLoop) {}
 
+    /* the frame limiter waits in the event loop, the simulation thread keeps its own clock.
+    the window geometry is saved here as well once it rests */
+    fn about_to_wait(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
+        let now = Instant::now();
+        if self
+            .geometry_changed
+            .is_some_and(|since| now - since >= WINDOW_GEOMETRY_SAVE_DELAY)
+        {
+            self.geometry_changed = None;
+            self.remember_window_geometry();
+        }
+        if self
+            .next_autosave()
+            .is_some_and(|next_autosave| now >= next_autosave)
+        {
+            self.autosave();
+        }
+        let next_frame = match self.next_frame {
+            Some(next_frame) if now >= next_frame => {
+                self.next_frame = None;
+                self.winit_window_handle.request_redraw();
+                None
+            }
+            next_frame => next_frame,
+        };
+        let wake_up = next_frame
+            .into_iter()
+            .chain(
+                self.geometry_changed
+                    .map(|since| since + WINDOW_GEOMETRY_SAVE_DELAY),
+            )
+            .chain(self.next_autosave())
+            .min();
+        event_loop.set_control_flow(wake_up.map_or(ControlFlow::Wait, ControlFlow::WaitUntil));
+    }
+
+    fn user_event(
+        &mut self,
+        event_loop: &winit::event_loop::ActiveEventLoop,
+        event: ApplicationSimulationEvent,
+    ) {
+        match event {
+            ApplicationSimulationEvent::SimulationSnapshotPublished(events) => {
+                let snapshot = self.simulation_thread.snapshot();
+                events.iter().for_each(|event| {
+                    let description = scenario::log_simulation_event(
+                        &snapshot.bodies,
+                        snapshot.simulation_time,
+                        event,
+                    );
+                    if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                        if let Some((level, message)) = description {
+                            let severity = match level {
+                                Level::WARN => ToastSeverity::Warning,
+                                _ => ToastSeverity::Info,
+                            };
+                            graphics_interface
+                                .ui
+                                .toasts
+                                .push(severity, message, event.bodies());
+                        }
+                        graphics_interface.debris.spawn_burst(&snapshot, event);
+                        /* the simulation drops the trail, the rest refers to the body from here */
+                        if let SimulationEvent::BodyRemoved(id) = event {
+                            graphics_interface.forget_body(*id);
+                        }
+                    }
+                });
+                if self.hidden_since.is_none() {
+                    self.winit_window_handle.request_redraw();
+                }
+                if let Some(duration) = self.launch_options.duration
+                    && snapshot.simulation_time >= duration
+                {
+                    info!("Simulated the requested {} s, quitting", duration);
+                    self.shutdown(event_loop);
+                }
+            }
+            ApplicationSimulationEvent::ScreenshotSaved(path) => {
+                info!("Screenshot saved to {}", path.display());
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface.ui.toasts.status(localization::tr_args(
+                        "toast-screenshot-saved",
+                        &[("path", path.display().to_string().into())],
+                    ));
+                }
+            }
+            ApplicationSimulationEvent::SelectionChanged(selection) => {
+                match selection {
+                    Some(id) => info!(
+                        "Selected {}",
+                        scenario::body_display_name(&self.simulation_thread.snapshot().bodies, id)
+                    ),
+                    None => info!("Selection cleared"),
+                }
+                self.simulation_thread
+                    .send(SimulationCommand::SetPlottedBody(selection));
+            }
+            ApplicationSimulationEvent::SettingsChanged(settings) => self.apply_settings(settings),
+            ApplicationSimulationEvent::UiLayoutReset => self.reset_ui_layout(),
+            ApplicationSimulationEvent::DisplayAdjusted => self.remember_graphics_settings(),
+            ApplicationSimulationEvent::GamepadChanged(event) => {
+                self.on_gamepad_event(event, event_loop)
+            }
+            ApplicationSimulationEvent::ControlRequested(command) => {
+                self.simulation_thread.send(command);
+            }
+            ApplicationSimulationEvent::BodyEdited(id, edit) => {
+                self.simulation_thread
+                    .send(SimulationCommand::EditBody(id, edit));
+            }
+            ApplicationSimulationEvent::BodyDeleted(id) => {
+                info!(
+                    "Deleting {}",
+                    scenario::body_display_name(&self.simulation_thread.snapshot().bodies, id)
+                );
+                self.simulation_thread
+                    .send(SimulationCommand::RemoveBody(id));
+            }
+            ApplicationSimulationEvent::BodySpawned(spawn) => {
+                info!("Adding {}", spawn.object.id_name);
+                self.simulation_thread.send(SimulationCommand::AddBody {
+                    object: Box::new(spawn.object),
+                    relative_to: spawn.relative_to,
+                });
+            }
+            ApplicationSimulationEvent::BodiesImported(import) => self.import_bodies(import),
+            ApplicationSimulationEvent::HorizonsImportRequested(request) => {
+                self.import_horizons(request)
+            }
+            ApplicationSimulationEvent::FileDialogRequested(action) => {
+                self.show_file_dialog(action)
+            }
+            ApplicationSimulationEvent::FileChosen { action, chosen } => {
+                self.file_dialog_open = false;
+                if let Some(path) = chosen {
+                    self.open_chosen_file(action, path);
+                }
+            }
+            ApplicationSimulationEvent::ScenarioSavedAs(saved) => {
+                match saved {
+                    Result::Ok(path) => {
+                        info!("Saved the scenario to {}", path.display());
+                        self.simulation_thread.send(SimulationCommand::MarkSaved);
+                        self.metadata_edited = false;
+                        self.scenario_label = scenario_dialog::file_name(&path);
+                        self.scenario_path = Some(path);
+                        if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                            graphics_interface
+                                .ui
+                                .toasts
+                                .status(localization::tr("toast-scenario-saved"));
+                        }
+                    }
+                    Err(error) => {
+                        warn!("{}", error);
+                        if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                            graphics_interface.ui.toasts.error(error);
+                        }
+                    }
+                }
+                #[cfg(feature = "scenario-hot-reload")]
+                self.watch_scenario();
+                self.winit_window_handle.request_redraw();
+            }
+            ApplicationSimulationEvent::ScenarioPropertiesRequested => {
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface
+                        .ui
+                        .scenario_properties
+                        .edit(self.scenario_metadata.clone());
+                }
+            }
+            ApplicationSimulationEvent::ScenarioPropertiesChanged(metadata) => {
+                self.metadata_edited |= metadata != self.scenario_metadata;
+                self.scenario_metadata = metadata;
+            }
+            ApplicationSimulationEvent::ThumbnailsRequested(thumbnails) => {
+                self.load_thumbnails(thumbnails)
+            }
+            ApplicationSimulationEvent::ScenarioRequested(ScenarioChoice::File(path)) => {
+                self.open_scenario(path)
+            }
+            ApplicationSimulationEvent::ScenarioRequested(ScenarioChoice::Preset(index)) => {
+                if let Some(preset) = scenario::SCENARIO_PRESETS.get(index) {
+                    self.open_preset(preset);
+                }
+            }
+            ApplicationSimulationEvent::ScenarioConfirmed(pending) => self.load_scenario(pending),
+            ApplicationSimulationEvent::ExitChosen(ExitChoice::Discard) => {
+                self.shutdown(event_loop)
+            }
+            ApplicationSimulationEvent::ExitChosen(ExitChoice::Save) => self.save_before_exit(),
+            ApplicationSimulationEvent::ScenarioSaved(Result::Ok(path)) => {
+                info!("Saved the scenario to {}", path.display());
+                self.shutdown(event_loop);
+            }
+            ApplicationSimulationEvent::ScenarioSaved(Err(error)) => {
+                warn!("{}", error);
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface.ui.exit_dialog.save_failed(error);
+                }
+                self.winit_window_handle.request_redraw();
+            }
+            ApplicationSimulationEvent::StateSaved { kind, saved } => {
+                self.state_saving = false;
+                let Some(graphics_interface) = self.graphics_interface.as_mut() else {
+                    return;
+                };
+                match saved {
+                    Result::Ok(path) => {
+                        info!("Saved the state to {}", path.display());
+                        graphics_interface.ui.toasts.status(match kind {
+                            SaveSlotKind::Autosave => localization::tr("toast-autosaved"),
+                            SaveSlotKind::Quicksave => localization::tr("toast-quicksaved"),
+                            SaveSlotKind::Named => localization::tr_args(
+                                "toast-saved-slot",
+                                &[(
+                                    "name",
+                                    path.file_stem()
+                                        .unwrap_or_default()
+                                        .to_string_lossy()
+                                        .into_owned()
+                                        .into(),
+                                )],
+                            ),
+                        });
+                    }
+                    Err(error) => {
+                        warn!("{}", error);
+                        graphics_interface.ui.toasts.error(error);
+                    }
+                }
+                self.winit_window_handle.request_redraw();
+            }
+            ApplicationSimulationEvent::TrajectoriesExportRequested { recording, path } => {
+                self.export_trajectories(recording, path)
+            }
+            ApplicationSimulationEvent::TrajectoriesExported(exported) => {
+                let Some(graphics_interface) = self.graphics_interface.as_mut() else {
+                    return;
+                };
+                match exported {
+                    Result::Ok(path) => {
+                        info!("Exported the trajectories to {}", path.display());
+                        graphics_interface.ui.toasts.status(localization::tr_args(
+                            "toast-trajectories-exported",
+                            &[("path", path.display().to_string().into())],
+                        ));
+                    }
+                    Err(error) => {
+                        warn!("{}", error);
+                        graphics_interface.ui.toasts.error(error);
+                    }
+                }
+                self.winit_window_handle.request_redraw();
+            }
+            ApplicationSimulationEvent::AutosaveRestored(path) => {
+                match storage::load_state(&path) {
+                    Result::Ok(save_game) => self.restore_state(save_game),
+                    Err(error) => {
+                        warn!("Failed to restore the autosave: {:#}", error);
+                        if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                            graphics_interface.ui.toasts.error(localization::tr_args(
+                                "toast-autosave-restore-failed",
+                                &[("error", format!("{:#}", error).into())],
+                            ));
+                        }
+                    }
+                }
+            }
+            ApplicationSimulationEvent::SaveSlotRequested(SaveSlotRequest::Save(name)) => {
+                self.save_to_slot(&name)
+            }
+            ApplicationSimulationEvent::SaveSlotRequested(SaveSlotRequest::Load(path)) => {
+                self.load_state_file(&path)
+            }
+            ApplicationSimulationEvent::TextureDecoded(decoded) => {
+                let Some(graphics_interface) = self.graphics_interface.as_mut() else {
+                    return;
+                };
+                graphics_interface.body_textures.finish(
+                    &graphics_interface.gpu_interface,
+                    &graphics_interface.gpu_queue,
+                    decoded,
+                );
+                let progress = graphics_interface.body_textures.progress();
+                if progress.is_complete() {
+                    info!("Loaded {} of {} textures", progress.loaded, progress.total);
+                }
+                self.winit_window_handle.request_redraw();
+            }
+            #[cfg(feature = "shader-hot-reload")]
+            ApplicationSimulationEvent::ShaderChanged(file_name) => {
+                let Some(graphics_interface) = self.graphics_interface.as_mut() else {
+                    return;
+                };
+                graphics_interface.reload_shader(&file_name);
+                self.winit_window_handle.request_redraw();
+            }
+            #[cfg(feature = "scenario-hot-reload")]
+            ApplicationSimulationEvent::ScenarioChanged => self.reload_scenario(),
+            ApplicationSimulationEvent::WindowRequested(action) => {
+                let window = &self.winit_window_handle;
+                match action {
+                    WindowAction::Drag => {
+                        if let Err(error) = window.drag_window() {
+                            warn!("Failed to drag the window: {error}");
+                        }
+                    }
+                    WindowAction::Minimize => window.set_minimized(true),
+                    WindowAction::ToggleMaximize => window.set_maximized(!window.is_maximized()),
+                    WindowAction::Close => self.request_exit(event_loop),
+                }
+            }
+            ApplicationSimulationEvent::DeviceLost => self.winit_window_handle.request_redraw(),
+        }
+    }
+
     fn window_event(
         &mut self,
         event_loop: &winit::event_loop::ActiveEventLoop,
@@ -151,7 +784,7 @@ impl<'a> ApplicationHandler<ApplicationSimulationEvent> for ApplicationSimulatio
         event: winit::event::WindowEvent,
     ) {
         let abstract_deprecated_event: Event<()> = winit::event::Event::WindowEvent {
-            window_id: window_id,
+            window_id,
             event: event.clone(),
         };
         self.imgui_platform.handle_event(
@@ -159,24 +792,147 @@ impl<'a> ApplicationHandler<ApplicationSimulationEvent> for ApplicationSimulatio
             &self.winit_window_handle,
             &abstract_deprecated_event,
         );
+        /* imgui always wants the mouse over its windows, the viewport image hands it back */
+        let io = self.imgui_context.io();
+        let want_capture_mouse = io.want_capture_mouse
+            && self
+                .graphics_interface
+                .as_ref()
+                .is_none_or(|graphics_interface| !graphics_interface.viewport.hovered());
+        self.input_state
+            .handle_window_event(&event, io.want_capture_keyboard, want_capture_mouse);
         match event {
+            winit::event::WindowEvent::CloseRequested => self.request_exit(event_loop),
+            winit::event::WindowEvent::HoveredFile(path) => {
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface
+                        .ui
+                        .scenario_dialog
+                        .set_hovered_file(Some(path));
+                }
+                self.winit_window_handle.request_redraw();
+            }
+            winit::event::WindowEvent::HoveredFileCancelled => {
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface.ui.scenario_dialog.set_hovered_file(None);
+                }
+                self.winit_window_handle.request_redraw();
+            }
+            winit::event::WindowEvent::DroppedFile(path) => {
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface.ui.scenario_dialog.set_hovered_file(None);
+                }
+                match self.file_dropped {
+                    true => warn!(
+                        "Only the first dropped file is loaded, ignoring {}",
+                        path.display()
+                    ),
+                    false => {
+                        self.file_dropped = true;
+                        self.open_dropped_file(path);
+                    }
+                }
+                self.winit_window_handle.request_redraw();
+            }
+            winit::event::WindowEvent::Occluded(occluded) => {
+                self.occluded = occluded;
+                let hidden = self.is_hidden();
+                self.set_hidden(hidden);
+            }
+            winit::event::WindowEvent::Moved(_) => {
+                self.geometry_changed = Some(Instant::now());
+            }
+            winit::event::WindowEvent::Resized(size) => {
+                self.geometry_changed = Some(Instant::now());
+                let hidden = self.is_hidden();
+                self.set_hidden(hidden);
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface.resize(size.width, size.height);
+                }
+                /* a paused simulation sends no snapshots, the old frame would stay stretched */
+                self.winit_window_handle.request_redraw();
+            }
+            winit::event::WindowEvent::ScaleFactorChanged { .. } => {
+                let size = self.winit_window_handle.inner_size();
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface.resize(size.width, size.height);
+                }
+                /* the platform already picked up the new factor while handling the event */
+                self.update_fonts();
+            }
             winit::event::WindowEvent::RedrawRequested => {
-                match graphics::render(
+                self.file_dropped = false;
+                /* a minimized window has no drawable surface. no next frame is requested, the
+                window is redrawn once it shows again */
+                let size = self.winit_window_handle.inner_size();
+                if size.width == 0 || size.height == 0 || self.hidden_since.is_some() {
+                    /* the time spent minimized is not a frame */
+                    self.last_redraw = None;
+                    return;
+                }
+                let now = Instant::now();
+                /* redraws requested by events before the limit allows one wait for the
+                scheduled frame */
+                if self.next_frame.is_some_and(|next_frame| now < next_frame) {
+                    return;
+                }
+                let frame_delta = self.last_redraw.replace(now).map(|last| now - last);
+                if self
+                    .graphics_interface
+                    .as_ref()
+                    .is_none_or(|graphics_interface| graphics_interface.device_loss.is_lost())
+                {
+                    self.rebuild_graphics_interface();
+                }
+                let Some(graphics_interface) = self.graphics_interface.as_mut() else {
+                    return;
+                };
+                if let Some(frame_delta) = frame_delta {
+                    graphics_interface.frame_window.record(frame_delta);
+                }
+                /* catches up on a resize that raced with the previous frame */
+                graphics_interface.resize(size.width, size.height);
+                self.consume_input(frame_delta.unwrap_or_default());
+                let Some(graphics_interface) = self.graphics_interface.as_mut() else {
+                    return;
+                };
+                let snapshot = self.simulation_thread.snapshot();
+                let result = graphics::render(
                     self.winit_window_handle.clone(),
-                    self.graphics_interface.as_ref().unwrap(),
+                    graphics_interface,
                     &mut self.imgui_context,
                     &mut self.imgui_platform,
                     &mut self.imgui_renderer,
                     &mut self.event_proxy,
-                    &self.texture_map,
-                ) {
-                    Result::Ok(_) => {}
-                    Err(_) => {}
+                    &self.texture_manager,
+                    &snapshot,
+                    &self.settings,
+                    &self.log_buffer,
+                    &self.scenario_label,
+                    self.simulation_epoch,
+                );
+                self.next_frame =
+                    Some(self.frame_interval().map_or(now, |interval| now + interval));
+                let (result, actions) = match result {
+                    Result::Ok(actions) => (Ok(()), actions),
+                    Err(error) => (Err(error), Vec::new()),
                 };
+                self.on_render_result(result, event_loop);
+                /* in the order the ui asked for them, like the events they are */
+                actions
+                    .into_iter()
+                    .for_each(|action| self.user_event(event_loop, action));
+            }
+            winit::event::WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
             }
             winit::event::WindowEvent::KeyboardInput { event, .. } => {
-                if let keyboard::PhysicalKey::Code(key_code) = event.physical_key {
-                    self.on_key_input(key_code, event_loop);
+                /* the toggles would flip back on the release or flicker on a held key */
+                if let keyboard::PhysicalKey::Code(key_code) = event.physical_key
+                    && event.state.is_pressed()
+                    && !event.repeat
+                {
+                    self.on_key_input(KeyBinding::new(key_code, self.modifiers), event_loop);
                 }
             }
             _ => {}
@@ -185,13 +941,27 @@ impl<'a> ApplicationHandler<ApplicationSimulationEvent> for ApplicationSimulatio
 }
 
 impl ApplicationSimulationInterface<'_> {
-    pub fn on_enable_interface<'a>(window: Arc<Window>) -> Result<SimulationGraphcisInterface<'a>> {
-        self::ApplicationSimulationInterface::enable_graphics_interface(window)
+    pub fn on_enable_interface<'a>(
+        window: Arc<Window>,
+        event_loop: &EventLoop<ApplicationSimulationEvent>,
+        settings: &Settings,
+        gpu: Option<&str>,
+    ) -> Result<SimulationGraphcisInterface<'a>> {
+        self::ApplicationSimulationInterface::enable_graphics_interface(
+            window,
+            event_loop.create_proxy(),
+            settings,
+            gpu,
+        )
     }
 
-    /* initializes the graphics interface for the simulation */
+    /* initializes the graphics interface for the simulation, also used to rebuild it after a
+    device loss. gpu overrides SPACES_GPU */
     pub fn enable_graphics_interface<'a>(
         window: Arc<Window>,
+        event_proxy: EventLoopProxy<ApplicationSimulationEvent>,
+        settings: &Settings,
+        gpu: Option<&str>,
     ) -> Result<SimulationGraphcisInterface<'a>> {
         let backend_instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
             backends: wgpu::Backends::PRIMARY,
@@ -200,20 +970,22 @@ impl ApplicationSimulationInterface<'_> {
         });
         graphics::display_evailable_graphic_adapters(&backend_instance);
         let surface: Surface<'_> = backend_instance.create_surface(window.clone()).unwrap();
-        let graphics_adapter = backend_instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::HighPerformance,
-                force_fallback_adapter: false,
-                compatible_surface: Some(&surface),
-            })
-            .block_on()?;
+        let graphics_adapter =
+            environment::select_graphics_adapter(&backend_instance, &surface, gpu)?;
+        crash::set_adapter(&graphics_adapter.get_info());
         match graphics_adapter.get_info().device_type {
             wgpu::DeviceType::IntegratedGpu
             | wgpu::DeviceType::VirtualGpu
             | wgpu::DeviceType::Cpu => {
                 warn!(
-                    "Your graphics card may not be capable enough to run the simulation on PowerPreference::HighPerformance. [{:?}]",
-                    graphics_adapter.get_info().device_type
+                    "{}",
+                    localization::tr_args(
+                        "warning-weak-adapter",
+                        &[(
+                            "device",
+                            format!("{:?}", graphics_adapter.get_info().device_type).into()
+                        )],
+                    )
                 );
             }
             _ => {}
@@ -221,7 +993,13 @@ impl ApplicationSimulationInterface<'_> {
         let interface = graphics_adapter
             .request_device(&wgpu::DeviceDescriptor {
                 label: Some("logical adapter interface"),
-                required_features: wgpu::Features::empty(),
+                /* optional, pass timings fall back to the cpu, the wireframe mode to edges
+                found in the shader and compressed textures to a decode without them */
+                required_features: graphics_adapter.features()
+                    & (wgpu::Features::TIMESTAMP_QUERY
+                        | wgpu::Features::POLYGON_MODE_LINE
+                        | wgpu::Features::TEXTURE_COMPRESSION_BC
+                        | wgpu::Features::TEXTURE_COMPRESSION_ASTC),
                 required_limits: if cfg!(target_arch = "wasm32") {
                     wgpu::Limits::downlevel_webgl2_defaults()
                 } else {
@@ -231,46 +1009,1450 @@ impl ApplicationSimulationInterface<'_> {
                 trace: wgpu::Trace::Off,
             })
             .block_on()?;
+        let device_loss = DeviceLossMonitor::watch(&interface.0, move |_| {
+            let _ = event_proxy.send_event(ApplicationSimulationEvent::DeviceLost);
+        });
         let surface_caps = surface.get_capabilities(&graphics_adapter);
-        let surface_format = surface_caps
-            .formats
-            .iter()
-            .find(|f| f.is_srgb())
-            .copied()
-            .unwrap();
+        let surface_format = surface::select_surface_format(&surface_caps.formats)?;
+        let present_preference =
+            environment::present_preference_from_env(settings.present_preference);
+        if surface_format.manual_gamma_correction {
+            warn!(
+                "No sRGB surface format available, using {:?} with gamma correction in the shaders",
+                surface_format.format
+            );
+        }
         let surface_configuration = SurfaceConfiguration {
+            /* screenshots draw the ui again into a texture of their own */
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface_format,
+            format: surface_format.format,
             width: window.inner_size().width,
             height: window.inner_size().height,
-            present_mode: surface_caps.present_modes.first().unwrap().clone(),
+            present_mode: surface::select_present_mode(
+                present_preference,
+                &surface_caps.present_modes,
+            )
+            .ok_or_else(|| anyhow!("The surface does not expose any present mode"))?,
             desired_maximum_frame_latency: 1,
-            alpha_mode: surface_caps.alpha_modes.first().unwrap().clone(),
+            alpha_mode: surface::select_by_preference(
+                &surface::ALPHA_MODE_PREFERENCE,
+                &surface_caps.alpha_modes,
+            )
+            .ok_or_else(|| anyhow!("The surface does not expose any alpha mode"))?,
             view_formats: vec![],
         };
         surface.configure(&interface.0, &surface_configuration);
+        info!(
+            "Presenting with {:?} ({})",
+            surface_configuration.present_mode, present_preference
+        );
+        let tonemap_settings = environment::tonemap_settings_from_env(
+            settings.tonemapping.then_some(settings.tonemap),
+        );
+        let hdr = match tonemap_settings {
+            Some(_) if tonemap::supports_hdr(&graphics_adapter) => true,
+            Some(_) => {
+                warn!(
+                    "{:?} cannot be filtered on this adapter, rendering without tonemapping",
+                    tonemap::HDR_FORMAT
+                );
+                false
+            }
+            None => false,
+        };
+        let tonemap_settings = tonemap_settings.unwrap_or(settings.tonemap).clamped();
+        let scene_format = match hdr {
+            true => tonemap::HDR_FORMAT,
+            false => surface_configuration.format,
+        };
+        let sample_count = targets::supported_sample_count(
+            &graphics_adapter,
+            &[scene_format, targets::DEPTH_FORMAT],
+            settings.sample_count,
+        );
+        /* starts at the window size, the viewport window resizes it on the first frames */
+        let mut viewport = Viewport::new(
+            &interface.0,
+            surface_configuration.format,
+            surface_configuration.width,
+            surface_configuration.height,
+        );
+        viewport.set_render_scale(environment::render_scale_from_env(settings.render_scale));
+        let render_targets = RenderTargets::new(&interface.0, &viewport, sample_count, hdr);
+        let bloom_settings = environment::bloom_settings_from_env(settings.bloom);
+        let bloom_pipeline = render_targets.hdr_view.as_ref().map(|hdr_view| {
+            BloomPipeline::new(
+                &interface.0,
+                hdr_view,
+                viewport.width,
+                viewport.height,
+                &bloom_settings,
+            )
+        });
+        let tonemap_pipeline = render_targets.hdr_view.as_ref().map(|hdr_view| {
+            TonemapPipeline::new(
+                &interface.0,
+                surface_configuration.format,
+                hdr_view,
+                &tonemap_settings,
+                surface_format.manual_gamma_correction,
+            )
+        });
+        let camera = Camera::new(viewport.width, viewport.height);
+        let camera_binding = CameraBinding::new(
+            &interface.0,
+            &camera,
+            surface_format.manual_gamma_correction && !hdr,
+        );
+        let body_textures = BodyTextureCache::new(&interface.0, &interface.1);
+        let body_pipeline = BodyPipeline::new(
+            &interface.0,
+            render_targets.color_format,
+            sample_count,
+            &camera_binding.bind_group_layout,
+            &body_textures.bind_group_layout,
+        );
+        let billboard_pipeline = BillboardPipeline::new(
+            &interface.0,
+            render_targets.color_format,
+            sample_count,
+            &camera_binding.bind_group_layout,
+        );
+        let outline_pipeline = OutlinePipeline::new(
+            &interface.0,
+            render_targets.color_format,
+            sample_count,
+            &camera_binding.bind_group_layout,
+        );
+        let grid_pipeline = GridPipeline::new(
+            &interface.0,
+            render_targets.color_format,
+            sample_count,
+            &camera_binding.bind_group_layout,
+        );
+        let ring_pipeline = RingPipeline::new(
+            &interface.0,
+            render_targets.color_format,
+            sample_count,
+            &camera_binding.bind_group_layout,
+            &body_textures.bind_group_layout,
+        );
+        let trail_pipeline = TrailPipeline::new(
+            &interface.0,
+            render_targets.color_format,
+            sample_count,
+            &camera_binding.bind_group_layout,
+        );
+        let debris_pipeline = DebrisPipeline::new(
+            &interface.0,
+            render_targets.color_format,
+            sample_count,
+            &camera_binding.bind_group_layout,
+        );
+        let gpu_timer = GpuTimer::new(&interface.0, &interface.1);
+        if gpu_timer.is_none() {
+            info!("Timestamp queries are unavailable, pass timings are measured on the cpu");
+        }
 
-        Ok(SimulationGraphcisInterface::new(
-            surface,
-            graphics_adapter,
-            interface.0,
-            interface.1,
+        let mut graphics_interface = SimulationGraphcisInterface {
+            application_surface: surface,
+            gpu_handle: graphics_adapter,
+            gpu_interface: interface.0,
+            gpu_queue: interface.1,
+            device_loss,
             surface_configuration,
-        ))
+            manual_gamma_correction: surface_format.manual_gamma_correction,
+            present_preference,
+            camera,
+            camera_controller: CameraController::default(),
+            selected_body: None,
+            camera_binding,
+            body_pipeline,
+            debug_render_mode: DebugRenderMode::default(),
+            billboard_settings: environment::billboard_settings_from_env(settings.billboards),
+            billboard_pipeline,
+            outline_pipeline,
+            ring_pipeline,
+            body_textures,
+            lighting_enabled: environment::lighting_enabled_from_env(settings.lighting),
+            starfield_configuration: environment::starfield_configuration_from_env(
+                settings.starfield,
+            ),
+            starfield_pipeline: None,
+            trail_settings: TrailSettings::default(),
+            label_settings: environment::label_settings_from_env(settings.labels.clone()),
+            gizmo_settings: GizmoSettings::default(),
+            ui: UiState::default(),
+            grid_settings: GridSettings::default(),
+            grid_pipeline,
+            trail_pipeline,
+            debris: DebrisSystem::default(),
+            debris_pipeline,
+            tonemap_settings,
+            tonemap_pipeline,
+            bloom_settings,
+            bloom_pipeline,
+            viewport,
+            render_targets,
+            surface_failures: SurfaceFailureTracker::default(),
+            render_stats: StatsAggregator::default(),
+            frame_window: FrameWindow::default(),
+            overlay_visible: false,
+            gpu_timer,
+            screenshot_capture: ScreenshotCapture::default(),
+        };
+        graphics_interface.starfield_pipeline = graphics_interface.create_starfield_pipeline();
+        Ok(graphics_interface)
+    }
+
+    /* the camera and the picking take what the scene got of the mouse since the last frame, and
+    the sticks of a gamepad over the frame time */
+    pub fn consume_input(&mut self, frame_delta: Duration) {
+        let Some(graphics_interface) = self.graphics_interface.as_mut() else {
+            return;
+        };
+        let viewport_height = graphics_interface.viewport.height as f32;
+        let click = graphics_interface.camera_controller.update(
+            &mut graphics_interface.camera,
+            &self.input_state,
+            viewport_height,
+        );
+        if let Some(gamepad_watcher) = self.gamepad_watcher.as_ref() {
+            let axes = gamepad_watcher
+                .axes()
+                .with_deadzone(self.settings.gamepad_deadzone);
+            /* a held stick keeps the frames coming like a drag does */
+            if !axes.is_idle() {
+                camera::apply_gamepad(
+                    &mut graphics_interface.camera,
+                    axes,
+                    frame_delta.min(GAMEPAD_MAXIMUM_FRAME_TIME).as_secs_f32(),
+                    self.settings.gamepad_sensitivity,
+                    viewport_height,
+                );
+                self.winit_window_handle.request_redraw();
+            }
+        }
+        let snapshot = self.simulation_thread.snapshot();
+        /* the viewport only reports hovered while imgui hands the mouse to the scene */
+        let dragging = [MouseButton::Left, MouseButton::Middle, MouseButton::Right]
+            .into_iter()
+            .any(|button| self.input_state.is_button_held(button));
+        let hovered = match graphics_interface.viewport.hovered() && !dragging {
+            true => self
+                .input_state
+                .cursor()
+                .and_then(|cursor| self::pick_at(graphics_interface, &snapshot, cursor.into())),
+            false => None,
+        };
+        graphics_interface
+            .ui
+            .body_popup
+            .hover(hovered, Instant::now());
+        self.input_state.end_frame();
+        let Some(ViewportClick { button, cursor }) = click else {
+            return;
+        };
+        let selection = self::pick_at(graphics_interface, &snapshot, cursor);
+        if button == MouseButton::Right {
+            if let Some(id) = selection {
+                graphics_interface.ui.body_popup.open_menu(id);
+            }
+            return;
+        }
+        if selection != graphics_interface.selected_body {
+            graphics_interface.selected_body = selection;
+            let _ = self
+                .event_proxy
+                .send_event(ApplicationSimulationEvent::SelectionChanged(selection));
+        }
+    }
+
+    /* borderless on the monitor the window is on, exclusive modes would change the display */
+    pub fn toggle_fullscreen(&mut self) {
+        let window = &self.winit_window_handle;
+        match window.fullscreen() {
+            Some(_) => {
+                window.set_fullscreen(None);
+                if let Some(size) = self.windowed_size.take() {
+                    let _ = window.request_inner_size(size);
+                }
+            }
+            None => {
+                self.windowed_size = Some(window.inner_size());
+                window.set_fullscreen(Some(Fullscreen::Borderless(window.current_monitor())));
+            }
+        }
+        window.request_redraw();
     }
 
     pub fn on_key_input(
         &mut self,
-        key_code: KeyCode,
+        binding: KeyBinding,
         event_loop: &winit::event_loop::ActiveEventLoop,
     ) {
-        match key_code {
-            KeyCode::Escape => {
-                event_loop.exit();
+        if let Some(graphics_interface) = self.graphics_interface.as_mut()
+            && graphics_interface.ui.settings_window.capture(binding)
+        {
+            return;
+        }
+        /* quitting again while asked quits without saving, also when a save hangs */
+        if self
+            .graphics_interface
+            .as_ref()
+            .is_some_and(|graphics_interface| graphics_interface.ui.exit_dialog.is_open())
+        {
+            if self.settings.keymap.action(binding) == Some(InputAction::Quit) {
+                warn!("Quitting without saving the scenario");
+                self.shutdown(event_loop);
+            }
+            return;
+        }
+        /* typing into a text field */
+        if self.imgui_context.io().want_capture_keyboard {
+            return;
+        }
+        if let Some(action) = self.settings.keymap.action(binding) {
+            self.on_input_action(action, event_loop);
+        }
+    }
+
+    pub fn on_gamepad_event(
+        &mut self,
+        event: GamepadEvent,
+        event_loop: &winit::event_loop::ActiveEventLoop,
+    ) {
+        match event {
+            GamepadEvent::Connected(name) => {
+                info!("Gamepad {} connected", name);
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface
+                        .ui
+                        .toasts
+                        .status(localization::tr("toast-gamepad-connected"));
+                }
+            }
+            GamepadEvent::Disconnected(name) => {
+                info!("Gamepad {} disconnected", name);
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface
+                        .ui
+                        .toasts
+                        .status(localization::tr("toast-gamepad-disconnected"));
+                }
+            }
+            GamepadEvent::Pressed(button) => {
+                if let Some(action) = InputAction::from_gamepad_button(button) {
+                    self.on_input_action(action, event_loop);
+                }
+            }
+            GamepadEvent::Moved => self.winit_window_handle.request_redraw(),
+        }
+    }
+
+    pub fn on_input_action(
+        &mut self,
+        action: InputAction,
+        event_loop: &winit::event_loop::ActiveEventLoop,
+    ) {
+        let snapshot = self.simulation_thread.snapshot();
+        match action {
+            InputAction::Quit => self.request_exit(event_loop),
+            InputAction::TogglePause => self
+                .simulation_thread
+                .send(SimulationCommand::SetPaused(!snapshot.paused)),
+            InputAction::StepOnce => self.simulation_thread.send(SimulationCommand::Step),
+            InputAction::WarpUp => self.simulation_thread.send(SimulationCommand::SetWarp(
+                snapshot.warp * SIMULATION_WARP_STEP,
+            )),
+            InputAction::WarpDown => self.simulation_thread.send(SimulationCommand::SetWarp(
+                snapshot.warp / SIMULATION_WARP_STEP,
+            )),
+            InputAction::Undo => self.simulation_thread.send(SimulationCommand::Undo),
+            InputAction::Redo => self.simulation_thread.send(SimulationCommand::Redo),
+            InputAction::CycleMsaa => {
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    let current = graphics_interface.render_targets.sample_count;
+                    let next = targets::SAMPLE_COUNTS
+                        .into_iter()
+                        .find(|count| *count > current)
+                        .unwrap_or(1);
+                    graphics_interface.set_sample_count(next);
+                }
+                self.remember_graphics_settings();
+            }
+            /* scene resolution, the ui keeps the native one */
+            InputAction::RenderScaleUp | InputAction::RenderScaleDown => {
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    let step = match action {
+                        InputAction::RenderScaleUp => viewport::RENDER_SCALE_STEP,
+                        _ => -viewport::RENDER_SCALE_STEP,
+                    };
+                    let render_scale = graphics_interface.viewport.render_scale() + step;
+                    graphics_interface.set_render_scale(render_scale);
+                }
+                self.remember_graphics_settings();
+            }
+            InputAction::ToggleOverlay => {
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface.overlay_visible = !graphics_interface.overlay_visible;
+                }
+            }
+            InputAction::CycleDebugRenderMode => {
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface.debug_render_mode =
+                        graphics_interface.debug_render_mode.next();
+                    info!("Debug render mode {}", graphics_interface.debug_render_mode);
+                }
+            }
+            InputAction::ToggleFullscreen => self.toggle_fullscreen(),
+            InputAction::Quicksave => self.quicksave(),
+            InputAction::Quickload => self.quickload(),
+            InputAction::Screenshot => {
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface.screenshot_capture.request();
+                }
+            }
+            InputAction::CyclePresentMode => {
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    let next = graphics_interface.present_preference.next();
+                    graphics_interface.set_present_preference(next);
+                }
+                self.remember_graphics_settings();
+            }
+            /* exposure and curve of the tonemapping pass, the ui is not affected */
+            InputAction::ExposureUp | InputAction::ExposureDown => {
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    let steps = if action == InputAction::ExposureUp {
+                        1
+                    } else {
+                        -1
+                    };
+                    graphics_interface.adjust_exposure(steps);
+                }
+                self.remember_graphics_settings();
+            }
+            InputAction::ToggleLighting => {
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface.lighting_enabled = !graphics_interface.lighting_enabled;
+                    info!(
+                        "Lighting {}",
+                        if graphics_interface.lighting_enabled {
+                            "enabled"
+                        } else {
+                            "disabled"
+                        }
+                    );
+                }
+                self.remember_graphics_settings();
+            }
+            InputAction::ToggleBloom => {
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface.toggle_bloom();
+                }
+                self.remember_graphics_settings();
+            }
+            InputAction::CycleTonemapCurve => {
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface.cycle_tonemap_curve();
+                }
+                self.remember_graphics_settings();
+            }
+            InputAction::ToggleTrails => {
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    let trail_settings = &mut graphics_interface.trail_settings;
+                    trail_settings.enabled = !trail_settings.enabled;
+                }
+            }
+            /* hides or shows the trail of the followed body */
+            InputAction::ToggleFollowedTrail => {
+                if let Some(graphics_interface) = self.graphics_interface.as_mut()
+                    && let Some(id) = graphics_interface.camera.follow_target()
+                {
+                    graphics_interface.trail_settings.toggle_body(id);
+                }
+            }
+            InputAction::ToggleGrid => {
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    let grid_settings = &mut graphics_interface.grid_settings;
+                    grid_settings.enabled = !grid_settings.enabled;
+                }
+            }
+            /* the axis lines of the grid */
+            InputAction::ToggleGridAxes => {
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    let grid_settings = &mut graphics_interface.grid_settings;
+                    grid_settings.axes = !grid_settings.axes;
+                }
+            }
+            InputAction::ToggleLabels => {
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    let label_settings = &mut graphics_interface.label_settings;
+                    label_settings.enabled = !label_settings.enabled;
+                }
+                self.remember_graphics_settings();
+            }
+            /* follows the selected body, or frees the camera when nothing is selected */
+            InputAction::FollowSelection => {
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    let selection = graphics_interface.selected_body;
+                    graphics_interface.camera.set_follow_target(selection);
+                }
+            }
+            InputAction::CycleFollow => {
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface
+                        .camera
+                        .cycle_follow_target(&snapshot.bodies);
+                }
+            }
+            InputAction::SimulateDeviceLoss => {
+                if let Some(graphics_interface) = self.graphics_interface.as_ref() {
+                    graphics_interface
+                        .device_loss
+                        .force_loss(&graphics_interface.gpu_interface);
+                    self.winit_window_handle.request_redraw();
+                }
+            }
+        }
+    }
+
+    /* applies what can change while running, the rest is read on the next start */
+    pub fn apply_settings(&mut self, settings: Settings) {
+        if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+            if settings.present_preference != graphics_interface.present_preference {
+                graphics_interface.set_present_preference(settings.present_preference);
+            }
+            graphics_interface.set_sample_count(settings.sample_count);
+            if settings.render_scale != graphics_interface.viewport.render_scale() {
+                graphics_interface.set_render_scale(settings.render_scale);
+            }
+            /* only what was edited, an environment override of the rest stays in effect */
+            if settings.tonemap != self.settings.tonemap {
+                graphics_interface.tonemap_settings = settings.tonemap.clamped();
+            }
+            if settings.bloom != self.settings.bloom {
+                graphics_interface.bloom_settings = settings.bloom;
+            }
+            if settings.lighting != self.settings.lighting {
+                graphics_interface.lighting_enabled = settings.lighting;
+            }
+            if settings.starfield != self.settings.starfield {
+                graphics_interface.set_starfield_configuration(settings.starfield);
+            }
+            if settings.labels != self.settings.labels {
+                graphics_interface.label_settings = settings.labels.clone();
+            }
+            if settings.billboards != self.settings.billboards {
+                graphics_interface.billboard_settings = settings.billboards;
+            }
+        }
+        if settings.remember_ui_layout != self.settings.remember_ui_layout {
+            /* turned off the layout is kept until the application quits, the file stays */
+            self.imgui_context
+                .set_ini_filename(self::ui_layout_file(&settings));
+        }
+        if settings.language != self.settings.language {
+            /* the strings are looked up on every frame, the next one is in the language */
+            localization::set_language(&localization::effective_language(&settings.language));
+            self.winit_window_handle
+                .set_title(&localization::tr("window-title"));
+        }
+        self.settings = settings;
+        self.update_fonts();
+        self.save_settings();
+    }
+
+    /* imgui cannot forget the windows it has seen, so the context is replaced by a fresh one
+    that places them like on the first start */
+    pub fn reset_ui_layout(&mut self) {
+        let path = storage::ui_layout_path();
+        if let Err(error) = std::fs::remove_file(&path)
+            && error.kind() != std::io::ErrorKind::NotFound
+        {
+            warn!("Failed to delete {}: {}", path.display(), error);
+        }
+        /* the old context would write its layout again when it is destroyed */
+        self.imgui_context.set_ini_filename(None);
+        let suspended = imgui::SuspendedContext::create();
+        /* imgui-rs activates a context only while none is current. the old one is destroyed right
+        below, which leaves the new one current */
+        unsafe { imgui::sys::igSetCurrentContext(std::ptr::null_mut()) };
+        let Result::Ok(mut imgui_context) = suspended.activate() else {
+            unreachable!("no imgui context is current");
+        };
+        self.imgui_platform = self::configure_imgui_context(
+            &mut imgui_context,
+            &self.winit_window_handle,
+            &self.settings,
+        );
+        drop(std::mem::replace(&mut self.imgui_context, imgui_context));
+        self.ui_fonts = UiFonts::new(&self.imgui_context);
+        self.ui_fonts.build(
+            &mut self.imgui_context,
+            self::font_configuration(&self.settings, &self.imgui_platform),
+        );
+        if let Some(graphics_interface) = self.graphics_interface.as_ref() {
+            self.imgui_renderer.reload_font_texture(
+                &mut self.imgui_context,
+                &graphics_interface.gpu_interface,
+                &graphics_interface.gpu_queue,
+            );
+        }
+        info!("Reset the ui layout");
+    }
+
+    /* after a change of the font settings or of the window scale factor */
+    pub fn update_fonts(&mut self) {
+        let Some(graphics_interface) = self.graphics_interface.as_ref() else {
+            return;
+        };
+        self.ui_fonts.update(
+            &mut self.imgui_context,
+            &mut self.imgui_renderer,
+            &graphics_interface.gpu_interface,
+            &graphics_interface.gpu_queue,
+            self::font_configuration(&self.settings, &self.imgui_platform),
+        );
+    }
+
+    /* the keyboard shortcuts change the same graphics options as the settings window */
+    pub fn remember_graphics_settings(&mut self) {
+        let Some(graphics_interface) = self.graphics_interface.as_ref() else {
+            return;
+        };
+        let mut settings = self.settings.clone();
+        settings.present_preference = graphics_interface.present_preference;
+        settings.sample_count = graphics_interface.render_targets.sample_count;
+        settings.render_scale = graphics_interface.viewport.render_scale();
+        settings.tonemap = graphics_interface.tonemap_settings;
+        settings.bloom.enabled = graphics_interface.bloom_settings.enabled;
+        settings.lighting = graphics_interface.lighting_enabled;
+        settings.labels.enabled = graphics_interface.label_settings.enabled;
+        if settings != self.settings {
+            self.settings = settings;
+            self.save_settings();
+        }
+    }
+
+    /* minimized the window has no meaningful size, the last one is kept */
+    pub fn remember_window_geometry(&mut self) {
+        let window = &self.winit_window_handle;
+        let size = window.inner_size();
+        if window.is_minimized() == Some(true) || size.width == 0 || size.height == 0 {
+            return;
+        }
+        let mut geometry = self.settings.window.clone();
+        geometry.maximized = window.is_maximized();
+        geometry.fullscreen = window.fullscreen().is_some();
+        if !geometry.maximized && !geometry.fullscreen {
+            geometry.size = Some([size.width, size.height]);
+            /* unavailable on wayland, the compositor places the window */
+            geometry.position = window
+                .outer_position()
+                .ok()
+                .map(|position| [position.x, position.y]);
+        }
+        geometry.monitor = window
+            .current_monitor()
+            .and_then(|monitor| monitor.name())
+            .unwrap_or_default();
+        if geometry != self.settings.window {
+            self.settings.window = geometry;
+            self.save_settings();
+        }
+    }
+
+    pub fn save_settings(&self) {
+        let path = scenario::settings_path(&self.launch_options);
+        if let Err(error) = storage::save_settings(&path, &self.settings) {
+            warn!("{:#}", error);
+        }
+    }
+
+    pub fn on_render_result(
+        &mut self,
+        result: Result<()>,
+        event_loop: &winit::event_loop::ActiveEventLoop,
+    ) {
+        let Some(graphics_interface) = self.graphics_interface.as_mut() else {
+            return;
+        };
+        let error = match result {
+            Result::Ok(()) => {
+                graphics_interface.surface_failures.on_success();
+                return;
+            }
+            Err(error) => error,
+        };
+        let Some(surface_error) = error.downcast_ref::<SurfaceError>() else {
+            warn!("Failed to render frame: {error}");
+            return;
+        };
+        match graphics_interface.surface_failures.on_error(surface_error) {
+            SurfaceRecovery::Reconfigure => {
+                warn!("Surface {surface_error}, reconfiguring");
+                graphics_interface.reconfigure_surface();
+                self.winit_window_handle.request_redraw();
+            }
+            SurfaceRecovery::SkipFrame => {
+                warn!("Skipping frame: {surface_error}");
+                self.winit_window_handle.request_redraw();
+            }
+            SurfaceRecovery::Abort(reason) => {
+                error!("Rendering aborted: {reason}");
+                self.abort(event_loop, anyhow!("Rendering aborted: {reason}"));
+            }
+        }
+    }
+
+    /* replaces a lost device with a new interface on the same window, the simulation keeps
+    running. the ui textures are uploaded again into a new imgui renderer */
+    pub fn rebuild_graphics_interface(&mut self) {
+        if let Some(graphics_interface) = self.graphics_interface.take() {
+            self.retained_graphics_state = Some(graphics_interface.into_retained_state());
+        }
+        let Some(mut graphics_interface) = Self::enable_graphics_interface(
+            self.winit_window_handle.clone(),
+            self.event_proxy.clone(),
+            &self.settings,
+            self.launch_options.gpu.as_deref(),
+        )
+        .inspect_err(|error| {
+            error!(
+                "Failed to rebuild the graphics interface, retrying on the next frame: {:#}",
+                error
+            )
+        })
+        .ok() else {
+            return;
+        };
+        if let Some(state) = self.retained_graphics_state.take() {
+            graphics_interface.restore(state);
+        }
+        self.imgui_renderer =
+            self::create_imgui_renderer(&mut self.imgui_context, &mut graphics_interface);
+        self.texture_manager.reupload(
+            &graphics_interface.gpu_interface,
+            &graphics_interface.gpu_queue,
+            &mut self.imgui_renderer,
+        );
+        self.graphics_interface = Some(graphics_interface);
+        info!("Rebuilt the graphics interface after a device loss");
+    }
+
+    /* a file dropped onto the window. a broken file only shows an error, the running scenario is
+    kept until replacing its edits is confirmed */
+    pub fn open_scenario(&mut self, path: PathBuf) {
+        let Some(graphics_interface) = self.graphics_interface.as_mut() else {
+            return;
+        };
+        let scenario = match storage::load_scenario(&path) {
+            Result::Ok(scenario) => scenario,
+            Err(error) => {
+                warn!("{:#}", error);
+                graphics_interface
+                    .ui
+                    .scenario_dialog
+                    .show_error(format!("{:#}", error));
+                return;
+            }
+        };
+        self.offer_scenario(PendingScenario {
+            path: Some(path),
+            scenario,
+        });
+    }
+
+    /* a table of bodies is imported into the running scenario, a horizons table goes to the
+    horizons window, anything else replaces the scenario */
+    pub fn open_dropped_file(&mut self, path: PathBuf) {
+        if storage::is_horizons_table(&path) {
+            if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                graphics_interface.ui.horizons_dialog.offer(path);
+            }
+            return;
+        }
+        let is_table = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"));
+        match (is_table, self.graphics_interface.as_mut()) {
+            (true, Some(graphics_interface)) => graphics_interface.ui.import_dialog.offer(path),
+            (true, None) => {}
+            (false, _) => self.open_scenario(path),
+        }
+    }
+
+    /* appended to the running bodies as one undo step, the table is read right away since even
+    large ones parse in a moment */
+    pub fn import_bodies(&mut self, import: BodyImport) {
+        let objects = match storage::import_csv(&import.path, &import.mapping) {
+            Result::Ok(objects) => objects,
+            Err(error) => {
+                warn!("{:#}", error);
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface.ui.toasts.error(format!("{:#}", error));
+                }
+                return;
+            }
+        };
+        let source = import
+            .path
+            .file_name()
+            .unwrap_or(import.path.as_os_str())
+            .to_string_lossy()
+            .into_owned();
+        info!("Importing {} bodies from {}", objects.len(), source);
+        if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+            let key = match import.mapping.skip_invalid_rows {
+                true => "toast-bodies-imported-skipping",
+                false => "toast-bodies-imported",
+            };
+            graphics_interface.ui.toasts.status(localization::tr_args(
+                key,
+                &[
+                    ("count", objects.len().into()),
+                    ("source", source.as_str().into()),
+                ],
+            ));
+        }
+        if !objects.is_empty() {
+            self.simulation_thread
+                .send(SimulationCommand::ImportBodies { objects, source });
+        }
+    }
+
+    /* the merged tables are offered like a preset, they have no file until saved */
+    pub fn import_horizons(&mut self, request: HorizonsRequest) {
+        let scenario = request
+            .paths
+            .iter()
+            .map(|path| {
+                storage::import_horizons(
+                    path,
+                    Some(SimulationPhysicsObject::new(
+                        request.mass as f32,
+                        request.radius as f32,
+                    )),
+                )
+            })
+            .collect::<Result<Vec<_>>>()
+            .and_then(storage::merge_horizons);
+        let scenario = match scenario {
+            Result::Ok(scenario) => scenario,
+            Err(error) => {
+                warn!("{:#}", error);
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface.ui.toasts.error(format!("{:#}", error));
+                }
+                return;
+            }
+        };
+        info!(
+            "Imported {} bodies from horizons at {}",
+            scenario.objects.len(),
+            scenario.epoch.as_deref().unwrap_or_default()
+        );
+        self.offer_scenario(PendingScenario {
+            path: None,
+            scenario,
+        });
+    }
+
+    /* like a file, unsaved edits are asked about first */
+    pub fn open_preset(&mut self, preset: &ScenarioPreset) {
+        self.offer_scenario(PendingScenario {
+            path: None,
+            scenario: preset.scenario(),
+        });
+    }
+
+    /* loads right away unless the running scenario was edited */
+    pub fn offer_scenario(&mut self, pending: PendingScenario) {
+        let Some(graphics_interface) = self.graphics_interface.as_mut() else {
+            return;
+        };
+        match self.simulation_thread.snapshot().dirty {
+            true => graphics_interface.ui.scenario_dialog.confirm(pending),
+            false => self.load_scenario(pending),
+        }
+    }
+
+    /* the old thread is joined with its state, the new one keeps the warp and pause of the old */
+    pub fn load_scenario(&mut self, pending: PendingScenario) {
+        let snapshot = self.simulation_thread.snapshot();
+        let simulation_state =
+            scenario::simulation_state(&pending.scenario.objects, &self.settings);
+        let textures = simulation_state
+            .bodies
+            .iter()
+            .filter_map(|body| body.appearance.texture.clone())
+            .collect();
+        let (scene_center, scene_radius) = camera::bounding_sphere(&simulation_state.bodies);
+        let body_count = simulation_state.bodies.len();
+        let simulation_thread = match self::spawn_simulation_thread(
+            simulation_state,
+            snapshot.warp,
+            self.event_proxy.clone(),
+        ) {
+            Result::Ok(simulation_thread) => simulation_thread,
+            Err(error) => {
+                warn!("Failed to load {}: {:#}", pending.label(), error);
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface.ui.toasts.error(localization::tr_args(
+                        "toast-scenario-load-failed",
+                        &[
+                            ("scenario", pending.label().into()),
+                            ("error", format!("{:#}", error).into()),
+                        ],
+                    ));
+                }
+                return;
+            }
+        };
+        if snapshot.paused {
+            simulation_thread.send(SimulationCommand::SetPaused(true));
+        }
+        if let Err(error) =
+            std::mem::replace(&mut self.simulation_thread, simulation_thread).shutdown()
+        {
+            warn!("Unclean simulation shutdown: {error}");
+        }
+        let assets = AssetResolver::for_scenario(pending.path.as_deref());
+        if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+            graphics_interface.forget_scenario(&textures, assets.clone());
+            /* the ui textures of the old scenario go with it, the window icon stays */
+            let mut kept = textures.clone();
+            kept.insert(ICON_TEXTURE_KEY.to_string());
+            self.texture_manager
+                .unload_except(&mut self.imgui_renderer, &kept);
+            graphics_interface.ui.scenario_browser.forget_thumbnails();
+            graphics_interface.ui.save_slots_window.forget_thumbnails();
+            graphics_interface
+                .camera
+                .frame_bounding_sphere(scene_center, scene_radius);
+        }
+        if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+            for warning in pending.scenario.warnings(&assets) {
+                graphics_interface.ui.toasts.push(
+                    ToastSeverity::Warning,
+                    warning.to_string(),
+                    Vec::new(),
+                );
+            }
+        }
+        info!("Loaded {} with {} bodies", pending.label(), body_count);
+        self.scenario_label = pending.label();
+        self.scenario_path = pending.path;
+        self.simulation_epoch = pending.scenario.epoch_seconds();
+        self.scenario_metadata = pending.scenario.metadata;
+        self.metadata_edited = false;
+        #[cfg(feature = "scenario-hot-reload")]
+        self.watch_scenario();
+        self.winit_window_handle.request_redraw();
+    }
+
+    /* replaces the watcher of the previous scenario, keeps running unwatched when watching fails */
+    #[cfg(feature = "scenario-hot-reload")]
+    pub fn watch_scenario(&mut self) {
+        let event_proxy = self.event_proxy.clone();
+        self._scenario_watcher = self.scenario_path.as_ref().and_then(|path| {
+            scenario::ScenarioWatcher::spawn(path, move || {
+                let _ = event_proxy.send_event(ApplicationSimulationEvent::ScenarioChanged);
+            })
+            .inspect_err(|error| warn!("Scenario hot reloading is unavailable: {:#}", error))
+            .ok()
+        });
+    }
+
+    /* a file that does not parse keeps the running state, the error names its line and column */
+    #[cfg(feature = "scenario-hot-reload")]
+    pub fn reload_scenario(&mut self) {
+        let Some(path) = self.scenario_path.clone() else {
+            return;
+        };
+        let scenario = match storage::load_scenario_as(&path, self.scenario_format(&path)) {
+            Result::Ok(scenario) => scenario,
+            Err(error) => {
+                warn!("Keeping the running scenario, {:#}", error);
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface.ui.toasts.error(format!("{:#}", error));
+                }
+                return;
+            }
+        };
+        match self.launch_options.live_tweak {
+            true => {
+                self.simulation_thread
+                    .send(SimulationCommand::TweakBodies(scenario.objects));
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface.ui.toasts.status(localization::tr_args(
+                        "toast-scenario-tweaked",
+                        &[("path", path.display().to_string().into())],
+                    ));
+                }
+            }
+            false => {
+                self.load_scenario(PendingScenario {
+                    path: Some(path),
+                    scenario,
+                });
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface
+                        .ui
+                        .toasts
+                        .status(localization::tr("toast-scenario-reloaded"));
+                }
+            }
+        }
+    }
+
+    /* an unedited scenario, or one without a window to ask in, quits right away */
+    pub fn request_exit(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
+        let scenario_path = self
+            .scenario_path
+            .clone()
+            .unwrap_or_else(storage::default_scenario_path);
+        match (
+            self.simulation_thread.snapshot().dirty || self.metadata_edited,
+            self.graphics_interface.as_mut(),
+        ) {
+            (true, Some(graphics_interface)) => {
+                graphics_interface.ui.exit_dialog.request(scenario_path);
+                self.winit_window_handle.request_redraw();
+            }
+            _ => self.shutdown(event_loop),
+        }
+    }
+
+    /* written on its own thread so the window keeps answering, and can be quit, while saving */
+    pub fn save_before_exit(&mut self) {
+        let Some(graphics_interface) = self.graphics_interface.as_mut() else {
+            return;
+        };
+        if graphics_interface.ui.exit_dialog.is_saving() {
+            return;
+        }
+        graphics_interface.ui.exit_dialog.set_saving();
+        let path = graphics_interface.ui.exit_dialog.scenario_path().clone();
+        self.write_scenario(path, ApplicationSimulationEvent::ScenarioSaved);
+    }
+
+    /* the running bodies with the metadata of the scenario, the result is sent as the event */
+    fn write_scenario(
+        &mut self,
+        path: PathBuf,
+        saved_event: fn(Result<PathBuf, String>) -> ApplicationSimulationEvent,
+    ) {
+        let snapshot = self.simulation_thread.snapshot();
+        /* the bodies are where they are now, which is when the saved scenario starts */
+        let mut scenario = Scenario {
+            metadata: self.scenario_metadata.clone(),
+            epoch: Some(toolbar::format_date(
+                snapshot.simulation_time,
+                self.simulation_epoch,
+            )),
+            ..Scenario::new(
+                snapshot
+                    .bodies
+                    .iter()
+                    .map(SimulationBody::to_object)
+                    .collect(),
+            )
+        };
+        let format = self.scenario_format(&path);
+        /* the fields of other versions and the units come from the file the scenario was
+        loaded from */
+        let loaded_from = self.scenario_path.clone();
+        self.scenario_metadata.touch();
+        let event_proxy = self.event_proxy.clone();
+        std::thread::spawn(move || {
+            if let Some(loaded_from) = loaded_from
+                && let Result::Ok(previous) = storage::load_scenario_as(&loaded_from, format)
+            {
+                scenario.unknown_fields = previous.unknown_fields;
+                scenario.units = previous.units;
+            }
+            let saved = storage::save_scenario_as(&path, &mut scenario, format)
+                .map(|()| path)
+                .map_err(|error| format!("Failed to save the scenario: {error:#}"));
+            let _ = event_proxy.send_event(saved_event(saved));
+        });
+        self.winit_window_handle.request_redraw();
+    }
+
+    /* shown on its own thread, the window keeps drawing while it is open */
+    pub fn show_file_dialog(&mut self, action: FileAction) {
+        if self.file_dialog_open {
+            return;
+        }
+        self.file_dialog_open = true;
+        let directory = self
+            .settings
+            .last_directory
+            .clone()
+            .filter(|directory| directory.is_dir())
+            .unwrap_or_else(storage::scenarios_directory);
+        let default_name = match self.scenario_path.as_deref() {
+            Some(path) => scenario_dialog::file_name(path),
+            None => storage::default_scenario_path()
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+        };
+        let event_proxy = self.event_proxy.clone();
+        std::thread::spawn(move || {
+            let filters = action.filters();
+            let chosen = match action {
+                FileAction::SaveAs => platform::pick_save_file(
+                    &action.title(),
+                    Some(&directory),
+                    &default_name,
+                    &filters,
+                ),
+                FileAction::Open | FileAction::LoadState => {
+                    platform::pick_open_file(&action.title(), Some(&directory), &filters)
+                }
+            };
+            let _ =
+                event_proxy.send_event(ApplicationSimulationEvent::FileChosen { action, chosen });
+        });
+    }
+
+    /* the next dialog opens where this file is */
+    pub fn open_chosen_file(&mut self, action: FileAction, path: PathBuf) {
+        if let Some(directory) = path.parent()
+            && self.settings.last_directory.as_deref() != Some(directory)
+        {
+            self.settings.last_directory = Some(directory.to_path_buf());
+            self.save_settings();
+        }
+        match action {
+            FileAction::Open => self.open_dropped_file(path),
+            FileAction::LoadState => self.load_state_file(&path),
+            FileAction::SaveAs => {
+                /* the write would reload the scenario when it goes to the watched file */
+                #[cfg(feature = "scenario-hot-reload")]
+                {
+                    self._scenario_watcher = None;
+                }
+                self.write_scenario(path, ApplicationSimulationEvent::ScenarioSavedAs);
+            }
+        }
+    }
+
+    /* small images, decoded right away. one that fails is logged and left out of the list */
+    pub fn load_thumbnails(&mut self, thumbnails: Vec<(String, Thumbnail)>) {
+        let Some(graphics_interface) = self.graphics_interface.as_ref() else {
+            return;
+        };
+        for (key, thumbnail) in thumbnails {
+            let bytes = match thumbnail {
+                Thumbnail::File(path) => std::fs::read(&path)
+                    .with_context(|| format!("Failed to read the thumbnail {}", path.display())),
+                Thumbnail::Png(bytes) => Ok(bytes),
+            };
+            let loaded = bytes.and_then(|bytes| {
+                self.texture_manager.load_from_memory(
+                    &graphics_interface.gpu_interface,
+                    &graphics_interface.gpu_queue,
+                    &mut self.imgui_renderer,
+                    &key,
+                    &bytes,
+                )
+            });
+            if let Err(error) = loaded {
+                warn!("{:#}", error);
+            }
+        }
+        self.winit_window_handle.request_redraw();
+    }
+
+    /* the rows recorded up to now, the recording goes on meanwhile if it is running */
+    pub fn export_trajectories(
+        &mut self,
+        recording: Arc<Mutex<TrajectoryRecording>>,
+        path: PathBuf,
+    ) {
+        let event_proxy = self.event_proxy.clone();
+        std::thread::spawn(move || {
+            let export = recording.lock().unwrap().export();
+            let exported = export
+                .and_then(|export| export.write(&path))
+                .map(|()| path)
+                .map_err(|error| format!("Failed to export the trajectories: {error:#}"));
+            let _ =
+                event_proxy.send_event(ApplicationSimulationEvent::TrajectoriesExported(exported));
+        });
+    }
+
+    /* --format only applies to the scenario it was given with */
+    fn scenario_format(&self, path: &Path) -> Option<ScenarioFormat> {
+        self.launch_options
+            .scenario_format
+            .filter(|_| self.launch_options.scenario.as_deref() == Some(path))
+    }
+
+    /* between two frame starts, none without a limit */
+    pub fn frame_interval(&self) -> Option<Duration> {
+        match self.settings.frame_rate_limit {
+            storage::UNLIMITED_FRAME_RATE => None,
+            limit => Some(Duration::from_secs_f64(
+                1. / limit.max(storage::MINIMUM_FRAME_RATE_LIMIT) as f64,
+            )),
+        }
+    }
+
+    /* some platforms report minimizing as a resize to nothing */
+    pub fn is_hidden(&self) -> bool {
+        let size = self.winit_window_handle.inner_size();
+        self.occluded
+            || size.width == 0
+            || size.height == 0
+            || self.winit_window_handle.is_minimized() == Some(true)
+    }
+
+    /* hiding applies the hidden policy, showing again resumes and fast-forwards */
+    pub fn set_hidden(&mut self, hidden: bool) {
+        if hidden == self.hidden_since.is_some() {
+            return;
+        }
+        let snapshot = self.simulation_thread.snapshot();
+        let policy = self.settings.hidden_policy;
+        let Some(since) = self.hidden_since.take() else {
+            self.hidden_since = Some(Instant::now());
+            let pauses = matches!(policy, HiddenPolicy::Pause | HiddenPolicy::CatchUp);
+            self.paused_while_hidden = pauses && !snapshot.paused;
+            if self.paused_while_hidden {
+                self.simulation_thread
+                    .send(SimulationCommand::SetPaused(true));
+            }
+            info!("Window hidden, rendering suspended");
+            return;
+        };
+        if std::mem::take(&mut self.paused_while_hidden) {
+            self.simulation_thread
+                .send(SimulationCommand::SetPaused(false));
+            if policy == HiddenPolicy::CatchUp {
+                let hidden = since.elapsed().min(MAXIMUM_CATCH_UP);
+                self.simulation_thread.send(SimulationCommand::CatchUp(
+                    hidden.as_secs_f64() * snapshot.warp,
+                ));
             }
-            _ => {}
         }
+        /* the time spent hidden is not a frame */
+        self.last_redraw = None;
+        info!("Window shown after {:.1} s", since.elapsed().as_secs_f64());
+        self.winit_window_handle.request_redraw();
+    }
+
+    /* joins the simulation thread before the event loop goes away */
+    pub fn shutdown(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
+        self.stop(event_loop);
+        storage::end_session();
+    }
+
+    /* the session is not ended, so the next launch offers the autosave like after a crash */
+    pub fn abort(&mut self, event_loop: &winit::event_loop::ActiveEventLoop, error: anyhow::Error) {
+        self.stop(event_loop);
+        self.fatal_error = Some(error);
+    }
+
+    fn stop(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
+        self.remember_window_geometry();
+        if let Err(error) = self.simulation_thread.shutdown() {
+            warn!("Unclean simulation shutdown: {error}");
+        }
+        event_loop.exit();
+    }
+}
+
+fn spawn_simulation_thread(
+    simulation_state: SimulationState,
+    warp: f64,
+    snapshot_proxy: EventLoopProxy<ApplicationSimulationEvent>,
+) -> Result<SimulationThreadHandle> {
+    SimulationThreadHandle::spawn(simulation_state, warp, move |events| {
+        let _ = snapshot_proxy.send_event(ApplicationSimulationEvent::SimulationSnapshotPublished(
+            events,
+        ));
+    })
+}
+
+/* the body under a cursor position in window pixels */
+fn pick_at(
+    graphics_interface: &SimulationGraphcisInterface,
+    snapshot: &SimulationSnapshot,
+    cursor: (f32, f32),
+) -> Option<BodyId> {
+    let (x, y) = graphics_interface.viewport.to_viewport(cursor)?;
+    picking::pick_body(
+        &graphics_interface.camera,
+        DVec2::new(x as f64, y as f64),
+        &snapshot.bodies,
+        &graphics_interface.billboard_settings,
+    )
+}
+
+fn font_configuration(
+    settings: &Settings,
+    imgui_platform: &imgui_winit_support::WinitPlatform,
+) -> FontConfiguration {
+    FontConfiguration {
+        font: settings.font.clone(),
+        fallback_font: settings.fallback_font.clone(),
+        script: Script::of_language(&localization::active_language()),
+        size: settings.font_size,
+        ui_scale: settings.ui_scale,
+        hidpi_factor: imgui_platform.hidpi_factor() as f32,
     }
+}
+
+/* registers the viewport image with the new renderer, the font atlas is uploaded on creation */
+/* everything of the context except the fonts, also for the fresh one of a layout reset */
+fn configure_imgui_context(
+    imgui_context: &mut imgui::Context,
+    window: &Window,
+    settings: &Settings,
+) -> imgui_winit_support::WinitPlatform {
+    imgui_context.set_ini_filename(self::ui_layout_file(settings));
+    if !cfg!(target_os = "windows") {
+        imgui_context.set_clipboard_backend(platform::CommandClipboard::default());
+    }
+    let io = imgui_context.io_mut();
+    /* dragging inside the viewport image orbits the camera instead of moving the window */
+    io.config_windows_move_from_title_bar_only = true;
+    /* the arrows, tab and enter move between the widgets of a focused window */
+    io.config_flags |= imgui::ConfigFlags::NAV_ENABLE_KEYBOARD;
+    let mut imgui_platform = imgui_winit_support::WinitPlatform::new(imgui_context);
+    imgui_platform.attach_window(
+        imgui_context.io_mut(),
+        window,
+        imgui_winit_support::HiDpiMode::Default,
+    );
+    imgui_platform
+}
 
-    pub fn imgui_graphical_interface() {}
+/* none keeps the layout in memory only, also when the config directory cannot be written */
+fn ui_layout_file(settings: &Settings) -> Option<PathBuf> {
+    if !settings.remember_ui_layout {
+        return None;
+    }
+    let path = storage::ui_layout_path();
+    storage::prepare_ui_layout_path(&path)
+        .inspect_err(|error| warn!("The ui layout is not remembered: {:#}", error))
+        .ok()?;
+    Some(path)
+}
+
+fn create_imgui_renderer(
+    imgui_context: &mut imgui::Context,
+    graphics_interface: &mut SimulationGraphcisInterface,
+) -> imgui_wgpu::Renderer {
+    let renderer_config = imgui_wgpu::RendererConfig {
+        texture_format: graphics_interface.surface_configuration.format,
+        ..Default::default()
+    };
+    let mut imgui_renderer = imgui_wgpu::Renderer::new(
+        imgui_context,
+        &graphics_interface.gpu_interface,
+        &graphics_interface.gpu_queue,
+        renderer_config,
+    );
+    graphics_interface
+        .viewport
+        .register(&graphics_interface.gpu_interface, &mut imgui_renderer);
+    imgui_renderer
+}
+
+/* before the window is shown. the remembered monitor, or the primary one when it is gone, must
+contain the whole window. returns the size to leave fullscreen to */
+fn restore_window_geometry(
+    window: &Window,
+    geometry: &WindowGeometry,
+) -> Option<PhysicalSize<u32>> {
+    let monitor = window
+        .available_monitors()
+        .find(|monitor| {
+            !geometry.monitor.is_empty() && monitor.name().as_deref() == Some(&geometry.monitor)
+        })
+        .or_else(|| window.primary_monitor())
+        .or_else(|| window.current_monitor());
+    let mut size = window.inner_size();
+    if let Some([width, height]) = geometry.size {
+        size = PhysicalSize::new(width, height);
+        if let Some(monitor) = monitor.as_ref() {
+            size.width = size.width.min(monitor.size().width);
+            size.height = size.height.min(monitor.size().height);
+        }
+        let _ = window.request_inner_size(size);
+    }
+    if let (Some([x, y]), Some(monitor)) = (geometry.position, monitor.as_ref()) {
+        let (origin, extent) = (monitor.position(), monitor.size());
+        let clamp = |position: i32, origin: i32, extent: u32, length: u32| {
+            let last = origin as i64 + extent.saturating_sub(length) as i64;
+            (position as i64).clamp(origin as i64, last) as i32
+        };
+        window.set_outer_position(PhysicalPosition::new(
+            clamp(x, origin.x, extent.width, size.width),
+            clamp(y, origin.y, extent.height, size.height),
+        ));
+    }
+    if geometry.maximized {
+        window.set_maximized(true);
+    }
+    if !geometry.fullscreen {
+        return None;
+    }
+    window.set_fullscreen(Some(Fullscreen::Borderless(monitor)));
+    Some(size)
+}
+
+/* none when the image does not decode, the window then shows the platform default */
+fn window_icon() -> Option<Icon> {
+    let image = match image::load_from_memory(WINDOW_ICON) {
+        Result::Ok(image) => image,
+        Err(error) => {
+            warn!("Failed to decode the window icon: {error}");
+            return None;
+        }
+    };
+    let image = match image.width().max(image.height()) > WINDOW_ICON_SIZE {
+        true => image.resize(
+            WINDOW_ICON_SIZE,
+            WINDOW_ICON_SIZE,
+            image::imageops::FilterType::Triangle,
+        ),
+        false => image,
+    }
+    .into_rgba8();
+    let (width, height) = image.dimensions();
+    Icon::from_rgba(image.into_raw(), width, height)
+        .inspect_err(|error| warn!("Failed to create the window icon: {error}"))
+        .ok()
+}
+
+/* prefers the installed asset, the embedded copy keeps startup working from any directory */
+fn load_icon(
+    texture_manager: &mut TextureManager,
+    graphics_interface: &SimulationGraphcisInterface,
+    imgui_renderer: &mut imgui_wgpu::Renderer,
+) -> TextureHandle {
+    let device = &graphics_interface.gpu_interface;
+    let queue = &graphics_interface.gpu_queue;
+    let path = platform::asset_path(ICON_ASSET_PATH);
+    texture_manager
+        .load(device, queue, imgui_renderer, &path)
+        .or_else(|error| {
+            warn!("{:#}, using the embedded icon", error);
+            texture_manager.load_from_memory(
+                device,
+                queue,
+                imgui_renderer,
+                "embedded:icon",
+                EMBEDDED_ICON,
+            )
+        })
+        .expect("the embedded icon is a valid image")
 }
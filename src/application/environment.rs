@@ -0,0 +1,219 @@
+use anyhow::{Ok, Result};
+use pollster::FutureExt;
+use tracing::{info, warn};
+use wgpu::Surface;
+
+use crate::graphics::{
+    adapter::{self, AdapterChoiceReason, AdapterSelector},
+    billboards::BillboardSettings,
+    labels::LabelSettings,
+    post::BloomSettings,
+    starfield::StarfieldConfiguration,
+    surface::PresentPreference,
+    tonemap::{TonemapCurve, TonemapSettings},
+    viewport,
+};
+
+/* --gpu or SPACES_GPU=<index>|<name substring> overrides the device type scoring, the driver's
+own preference is only asked when no enumerated adapter can present to the surface */
+pub fn select_graphics_adapter(
+    instance: &wgpu::Instance,
+    surface: &Surface<'_>,
+    gpu: Option<&str>,
+) -> Result<wgpu::Adapter> {
+    let selector = gpu
+        .map(str::to_string)
+        .or_else(|| std::env::var("SPACES_GPU").ok())
+        .and_then(|value| AdapterSelector::parse(&value));
+    let mut adapters = instance.enumerate_adapters(wgpu::Backends::all());
+    let candidates: Vec<(wgpu::AdapterInfo, bool)> = adapters
+        .iter()
+        .map(|adapter| (adapter.get_info(), adapter.is_surface_supported(surface)))
+        .collect();
+    let choice = adapter::choose_adapter(&candidates, selector.as_ref());
+    if let Some(selector) = selector.as_ref()
+        && choice.is_none_or(|choice| choice.reason != AdapterChoiceReason::Selected)
+    {
+        warn!(
+            "The adapter override [{:?}] matches no adapter that can present, falling back to scoring",
+            selector
+        );
+    }
+    let Some(choice) = choice else {
+        warn!("No enumerated adapter can present to the surface, asking the driver");
+        return Ok(instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                force_fallback_adapter: false,
+                compatible_surface: Some(surface),
+            })
+            .block_on()?);
+    };
+    let (info, _) = &candidates[choice.index];
+    info!(
+        "Using adapter {} [{}] ({:?}, {:?}), {}",
+        choice.index, info.name, info.device_type, info.backend, choice.reason
+    );
+    Ok(adapters.swap_remove(choice.index))
+}
+
+/* SPACES_TONEMAP=aces|reinhard, or off for the direct path without an hdr target. takes
+precedence over the settings like the other variables below, none is the direct path */
+pub fn tonemap_settings_from_env(fallback: Option<TonemapSettings>) -> Option<TonemapSettings> {
+    let Result::Ok(value) = std::env::var("SPACES_TONEMAP") else {
+        return fallback;
+    };
+    if value.eq_ignore_ascii_case("off") {
+        return None;
+    }
+    let mut settings = fallback.unwrap_or_default();
+    match TonemapCurve::from_name(&value) {
+        Some(curve) => settings.curve = curve,
+        None => warn!(
+            "Invalid SPACES_TONEMAP curve [{}], using {}",
+            value, settings.curve
+        ),
+    }
+    Some(settings)
+}
+
+/* SPACES_LIGHTING=off draws every body unlit, on lights them */
+pub fn lighting_enabled_from_env(fallback: bool) -> bool {
+    match std::env::var("SPACES_LIGHTING") {
+        Result::Ok(value) if value.eq_ignore_ascii_case("off") => false,
+        Result::Ok(value) if value.eq_ignore_ascii_case("on") => true,
+        _ => fallback,
+    }
+}
+
+/* SPACES_BLOOM=off, or <intensity>[,<threshold>] */
+pub fn bloom_settings_from_env(fallback: BloomSettings) -> BloomSettings {
+    let mut settings = fallback;
+    let Result::Ok(value) = std::env::var("SPACES_BLOOM") else {
+        return settings;
+    };
+    if value.eq_ignore_ascii_case("off") {
+        settings.enabled = false;
+        return settings;
+    }
+    let mut fields = value.split(',').map(str::trim);
+    let intensity = fields.next().map(str::parse::<f32>);
+    let threshold = fields.next().map(str::parse::<f32>);
+    match (intensity, threshold) {
+        (Some(Result::Ok(intensity)), None) if intensity >= 0. => settings.intensity = intensity,
+        (Some(Result::Ok(intensity)), Some(Result::Ok(threshold)))
+            if intensity >= 0. && threshold > 0. =>
+        {
+            settings.intensity = intensity;
+            settings.threshold = threshold;
+        }
+        _ => warn!(
+            "Invalid SPACES_BLOOM value [{}], using intensity {} and threshold {}",
+            value, settings.intensity, settings.threshold
+        ),
+    }
+    settings.enabled = true;
+    settings
+}
+
+/* SPACES_LABELS=off, or a comma separated list of body tags whose labels are hidden */
+pub fn label_settings_from_env(fallback: LabelSettings) -> LabelSettings {
+    let mut settings = fallback;
+    let Result::Ok(value) = std::env::var("SPACES_LABELS") else {
+        return settings;
+    };
+    if value.eq_ignore_ascii_case("off") {
+        settings.enabled = false;
+        return settings;
+    }
+    settings.enabled = true;
+    settings.hidden_tags = value
+        .split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect();
+    settings
+}
+
+/* SPACES_MINIMUM_BODY_PIXELS=<radius>, 0 lets distant bodies vanish */
+pub fn billboard_settings_from_env(fallback: BillboardSettings) -> BillboardSettings {
+    let mut settings = fallback;
+    let Result::Ok(value) = std::env::var("SPACES_MINIMUM_BODY_PIXELS") else {
+        return settings;
+    };
+    match value.trim().parse::<f32>() {
+        Result::Ok(pixels) if pixels >= 0. => settings.minimum_pixels = pixels,
+        _ => warn!(
+            "Invalid SPACES_MINIMUM_BODY_PIXELS [{}], using {}",
+            value, settings.minimum_pixels
+        ),
+    }
+    settings
+}
+
+/* SPACES_RENDER_SCALE=<scale>, between 0.5 and 2 */
+pub fn render_scale_from_env(fallback: f32) -> f32 {
+    let Result::Ok(value) = std::env::var("SPACES_RENDER_SCALE") else {
+        return fallback;
+    };
+    match value.trim().parse::<f32>() {
+        Result::Ok(scale)
+            if (viewport::MINIMUM_RENDER_SCALE..=viewport::MAXIMUM_RENDER_SCALE)
+                .contains(&scale) =>
+        {
+            scale
+        }
+        _ => {
+            warn!(
+                "Invalid SPACES_RENDER_SCALE [{}], using {}",
+                value, fallback
+            );
+            fallback
+        }
+    }
+}
+
+/* SPACES_STARFIELD=off, or <count>[,<seed>] */
+pub fn starfield_configuration_from_env(
+    fallback: StarfieldConfiguration,
+) -> StarfieldConfiguration {
+    let mut configuration = fallback;
+    let Result::Ok(value) = std::env::var("SPACES_STARFIELD") else {
+        return configuration;
+    };
+    if value.eq_ignore_ascii_case("off") {
+        configuration.enabled = false;
+        return configuration;
+    }
+    let mut fields = value.split(',').map(str::trim);
+    let star_count = fields.next().map(str::parse::<u32>);
+    let seed = fields.next().map(str::parse::<u64>);
+    match (star_count, seed) {
+        (Some(Result::Ok(star_count)), None) => configuration.star_count = star_count,
+        (Some(Result::Ok(star_count)), Some(Result::Ok(seed))) => {
+            configuration.star_count = star_count;
+            configuration.seed = seed;
+        }
+        _ => warn!(
+            "Invalid SPACES_STARFIELD value [{}], using {} stars",
+            value, configuration.star_count
+        ),
+    }
+    configuration.enabled = true;
+    configuration
+}
+
+/* SPACES_PRESENT=<mode> */
+pub fn present_preference_from_env(fallback: PresentPreference) -> PresentPreference {
+    let Result::Ok(value) = std::env::var("SPACES_PRESENT") else {
+        return fallback;
+    };
+    PresentPreference::from_name(&value).unwrap_or_else(|| {
+        warn!(
+            "Invalid SPACES_PRESENT mode [{}], using {}",
+            value, fallback
+        );
+        fallback
+    })
+}
@@ -0,0 +1,227 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use tracing::{info, warn};
+
+use crate::{
+    application::{ApplicationSimulationEvent, ApplicationSimulationInterface},
+    localization, scenario,
+    simulation::thread::SimulationCommand,
+    storage::{self, AssetResolver, SaveGame, SaveSlotKind, ScenarioMetadata},
+};
+
+impl ApplicationSimulationInterface<'_> {
+    /* into the oldest quicksave slot. a second quicksave is ignored until the first is written */
+    pub fn quicksave(&mut self) {
+        if !self.state_saving {
+            self.save_state(
+                storage::quicksave_path(storage::next_quicksave_slot(), self.settings.save_format),
+                SaveSlotKind::Quicksave,
+            );
+        }
+    }
+
+    /* a slot of the saves window, a save of the name in the other format is replaced as well */
+    pub fn save_to_slot(&mut self, name: &str) {
+        let refused = match (storage::is_save_slot_name(name), self.state_saving) {
+            (false, _) => Some(localization::tr_args(
+                "toast-save-name-invalid",
+                &[("name", format!("{:?}", name.trim()).into())],
+            )),
+            (true, true) => Some(localization::tr("toast-save-busy")),
+            (true, false) => None,
+        };
+        if let Some(refused) = refused {
+            warn!("{}", refused);
+            if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                graphics_interface.ui.toasts.error(refused);
+            }
+            return;
+        }
+        self.save_state(
+            storage::save_slot_path(name, self.settings.save_format),
+            SaveSlotKind::Named,
+        );
+    }
+
+    /* a slot of the saves window or a file picked in the file menu, without asking */
+    pub fn load_state_file(&mut self, path: &Path) {
+        match storage::load_state(path) {
+            Result::Ok(save_game) => self.restore_state(save_game),
+            Err(error) => {
+                warn!("Failed to load the state: {:#}", error);
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface.ui.toasts.error(localization::tr_args(
+                        "toast-state-load-failed",
+                        &[("error", format!("{:#}", error).into())],
+                    ));
+                }
+            }
+        }
+    }
+
+    /* into the oldest slot. skipped while another save is written, it is retried once that one
+    is done */
+    pub fn autosave(&mut self) {
+        if self.state_saving
+            || self
+                .graphics_interface
+                .as_ref()
+                .is_some_and(|graphics_interface| graphics_interface.ui.exit_dialog.is_saving())
+        {
+            return;
+        }
+        self.last_autosave = Instant::now();
+        self.save_state(
+            storage::autosave_path(storage::next_autosave_slot(), self.settings.save_format),
+            SaveSlotKind::Autosave,
+        );
+    }
+
+    /* the latest snapshot is shared with the thread, which builds and writes the save so the
+    frame does not wait for it. the next frame is captured as the thumbnail of the slot */
+    fn save_state(&mut self, path: PathBuf, kind: SaveSlotKind) {
+        let Some(graphics_interface) = self.graphics_interface.as_mut() else {
+            return;
+        };
+        graphics_interface
+            .screenshot_capture
+            .request_thumbnail(storage::thumbnail_path(&path));
+        let snapshot = self.simulation_thread.snapshot();
+        let camera = graphics_interface.camera.pose();
+        let scenario_label = self.scenario_label.clone();
+        let scenario_path = self.scenario_path.clone();
+        let epoch = self.simulation_epoch;
+        let save_format = self.settings.save_format;
+        self.state_saving = true;
+        let event_proxy = self.event_proxy.clone();
+        std::thread::spawn(move || {
+            let save_game = SaveGame {
+                version: storage::SAVE_GAME_VERSION,
+                scenario_label,
+                scenario_path,
+                simulation_time: snapshot.simulation_time,
+                warp: snapshot.warp,
+                paused: snapshot.paused,
+                dirty: snapshot.dirty,
+                epoch,
+                camera,
+                bodies: snapshot.bodies.clone(),
+            };
+            let saved = storage::save_state(&path, &save_game, Some(save_format))
+                .map(|()| {
+                    storage::remove_other_formats(&path);
+                    path
+                })
+                .map_err(|error| {
+                    let key = match kind {
+                        SaveSlotKind::Autosave => "toast-autosave-failed",
+                        SaveSlotKind::Quicksave => "toast-quicksave-failed",
+                        SaveSlotKind::Named => "toast-save-failed",
+                    };
+                    localization::tr_args(key, &[("error", format!("{error:#}").into())])
+                });
+            let _ = event_proxy.send_event(ApplicationSimulationEvent::StateSaved { kind, saved });
+        });
+    }
+
+    /* none while autosaving is off or a save is being written, which wakes the loop itself */
+    pub fn next_autosave(&self) -> Option<Instant> {
+        match self.settings.autosave_interval {
+            0 => None,
+            _ if self.state_saving => None,
+            minutes => Some(self.last_autosave + Duration::from_secs(minutes as u64 * 60)),
+        }
+    }
+
+    /* replaces the running state without asking, the quicksave is what was asked for */
+    pub fn quickload(&mut self) {
+        let loaded = storage::latest_quicksave()
+            .context("There is no quicksave yet")
+            .and_then(|path| storage::load_state(&path));
+        match loaded {
+            Result::Ok(save_game) => self.restore_state(save_game),
+            Err(error) => {
+                warn!("Failed to quickload: {:#}", error);
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface.ui.toasts.error(localization::tr_args(
+                        "toast-quickload-failed",
+                        &[("error", format!("{:#}", error).into())],
+                    ));
+                }
+            }
+        }
+    }
+
+    /* like loading a scenario, with the time, warp, pause and camera of the save */
+    pub fn restore_state(&mut self, save_game: SaveGame) {
+        let simulation_state = scenario::restored_state(&save_game, &self.settings);
+        let textures = simulation_state
+            .bodies
+            .iter()
+            .filter_map(|body| body.appearance.texture.clone())
+            .collect();
+        let simulation_thread = match super::spawn_simulation_thread(
+            simulation_state,
+            save_game.warp,
+            self.event_proxy.clone(),
+        ) {
+            Result::Ok(simulation_thread) => simulation_thread,
+            Err(error) => {
+                warn!("Failed to quickload: {:#}", error);
+                if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+                    graphics_interface.ui.toasts.error(localization::tr_args(
+                        "toast-quickload-failed",
+                        &[("error", format!("{:#}", error).into())],
+                    ));
+                }
+                return;
+            }
+        };
+        if save_game.paused {
+            simulation_thread.send(SimulationCommand::SetPaused(true));
+        }
+        if let Err(error) =
+            std::mem::replace(&mut self.simulation_thread, simulation_thread).shutdown()
+        {
+            warn!("Unclean simulation shutdown: {error}");
+        }
+        if let Some(graphics_interface) = self.graphics_interface.as_mut() {
+            graphics_interface.forget_scenario(
+                &textures,
+                AssetResolver::for_scenario(save_game.scenario_path.as_deref()),
+            );
+            graphics_interface.camera.set_pose(save_game.camera);
+            graphics_interface
+                .ui
+                .toasts
+                .status(localization::tr("toast-quickloaded"));
+        }
+        info!(
+            "Restored {} at T+{} s with {} bodies",
+            save_game.scenario_label,
+            save_game.simulation_time,
+            save_game.bodies.len()
+        );
+        /* the save holds no metadata, it is read from the scenario file again */
+        self.scenario_metadata = save_game
+            .scenario_path
+            .as_deref()
+            .and_then(|path| storage::load_scenario(path).ok())
+            .map(|scenario| scenario.metadata)
+            .unwrap_or_else(|| ScenarioMetadata {
+                name: save_game.scenario_label.clone(),
+                ..ScenarioMetadata::default()
+            });
+        self.metadata_edited = false;
+        self.scenario_label = save_game.scenario_label;
+        self.scenario_path = save_game.scenario_path;
+        self.simulation_epoch = save_game.epoch;
+        #[cfg(feature = "scenario-hot-reload")]
+        self.watch_scenario();
+        self.winit_window_handle.request_redraw();
+    }
+}
@@ -0,0 +1,262 @@
+use std::path::PathBuf;
+
+use crate::storage::ScenarioFormat;
+
+pub const USAGE: &str = "\
+Usage: spaces [options] [scenario]
+
+Arguments:
+  [scenario]          yaml, json, toml or ron scenario loaded instead of the
+                      built-in scene
+
+Options:
+  --headless          runs the simulation at full speed without a window, needs
+                      --duration
+  --duration <secs>   simulated seconds after which the application quits
+  --out <path>        csv file the headless run writes the body states to
+  --sample-interval <secs>
+                      simulated seconds between two rows of --out, a thousandth
+                      of the duration by default
+  --warp <x>          simulated seconds per wall second at startup, ignored
+                      headless
+  --format <name>     yaml, json, toml or ron, the format of the scenario when its
+                      extension does not tell
+  --live-tweak        a changed scenario file only updates the masses, radii and
+                      appearances, the bodies keep their simulated motion
+  --gpu <name>        adapter index or name substring, like SPACES_GPU
+  --settings <path>   settings file read and written instead of the default
+  --dump-schema       prints the json schema of the scenario files
+  --report-i18n <language>
+                      prints the messages the translation to the language lacks
+                      or no longer needs against english
+  -h, --help          prints this help";
+
+/* what the application was started with, passed down from execute */
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LaunchOptions {
+    pub scenario: Option<PathBuf>,
+    /* overrides the extension of the scenario */
+    pub scenario_format: Option<ScenarioFormat>,
+    pub headless: bool,
+    pub duration: Option<f64>,
+    pub out: Option<PathBuf>,
+    pub sample_interval: Option<f64>,
+    pub warp: Option<f64>,
+    /* instead of reloading a changed scenario file from scratch */
+    pub live_tweak: bool,
+    pub gpu: Option<String>,
+    pub settings: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Launch(LaunchOptions),
+    Help,
+    DumpSchema,
+    /* the language, like de */
+    ReportI18n(String),
+}
+
+/* the arguments without the program name. values follow their option either as the next
+argument or after an equals sign, everything after -- is positional */
+pub fn parse(arguments: impl IntoIterator<Item = String>) -> Result<Command, String> {
+    let mut options = LaunchOptions::default();
+    let mut arguments = arguments.into_iter();
+    let mut positional_only = false;
+    while let Some(argument) = arguments.next() {
+        if positional_only || !argument.starts_with('-') || argument == "-" {
+            if options.scenario.is_some() {
+                return Err(format!("Unexpected argument {:?}", argument));
+            }
+            options.scenario = Some(PathBuf::from(argument));
+            continue;
+        }
+        let (name, inline_value) = match argument.split_once('=') {
+            Some((name, value)) => (name.to_string(), Some(value.to_string())),
+            None => (argument, None),
+        };
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| arguments.next())
+                .ok_or_else(|| format!("{} needs a value", name))
+        };
+        match name.as_str() {
+            "--" => positional_only = true,
+            "-h" | "--help" => return Ok(Command::Help),
+            "--dump-schema" => return Ok(Command::DumpSchema),
+            "--report-i18n" => return Ok(Command::ReportI18n(value()?)),
+            "--headless" => options.headless = true,
+            "--live-tweak" => options.live_tweak = true,
+            "--duration" => {
+                let duration = self::parse_number(&name, &value()?)?;
+                if duration <= 0. {
+                    return Err(format!("{} must be positive", name));
+                }
+                options.duration = Some(duration);
+            }
+            "--out" => options.out = Some(PathBuf::from(value()?)),
+            "--sample-interval" => {
+                let interval = self::parse_number(&name, &value()?)?;
+                if interval <= 0. {
+                    return Err(format!("{} must be positive", name));
+                }
+                options.sample_interval = Some(interval);
+            }
+            "--warp" => {
+                let warp = self::parse_number(&name, &value()?)?;
+                if warp < 0. {
+                    return Err(format!("{} must not be negative", name));
+                }
+                options.warp = Some(warp);
+            }
+            "--format" => {
+                let format = value()?;
+                options.scenario_format =
+                    Some(ScenarioFormat::from_name(&format).ok_or_else(|| {
+                        format!("{} expects yaml, json, toml or ron, got {:?}", name, format)
+                    })?);
+            }
+            "--gpu" => options.gpu = Some(value()?),
+            "--settings" => options.settings = Some(PathBuf::from(value()?)),
+            _ => return Err(format!("Unknown option {}", name)),
+        }
+        if inline_value.is_some() && matches!(name.as_str(), "--" | "--headless" | "--live-tweak") {
+            return Err(format!("{} takes no value", name));
+        }
+    }
+    if options.headless && options.duration.is_none() {
+        return Err("--headless needs --duration, nothing would end the run".to_string());
+    }
+    if !options.headless && (options.out.is_some() || options.sample_interval.is_some()) {
+        return Err("--out and --sample-interval need --headless".to_string());
+    }
+    if let Some(out) = options.out.as_ref()
+        && !out
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("csv"))
+    {
+        return Err(format!(
+            "--out writes csv, {} is not a .csv file",
+            out.display()
+        ));
+    }
+    if options.scenario_format.is_some() && options.scenario.is_none() {
+        return Err("--format needs a scenario".to_string());
+    }
+    Ok(Command::Launch(options))
+}
+
+fn parse_number(name: &str, value: &str) -> Result<f64, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|number| number.is_finite())
+        .ok_or_else(|| format!("{} expects a number, got {:?}", name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_line(line: &str) -> Result<Command, String> {
+        parse(line.split_whitespace().map(str::to_string))
+    }
+
+    fn launch(line: &str) -> LaunchOptions {
+        match self::parse_line(line) {
+            Ok(Command::Launch(options)) => options,
+            other => panic!("{:?} parsed to {:?}", line, other),
+        }
+    }
+
+    #[test]
+    fn no_arguments_launch_the_built_in_scene() {
+        assert_eq!(self::launch(""), LaunchOptions::default());
+    }
+
+    #[test]
+    fn options_take_separate_or_inline_values() {
+        let options = self::launch(
+            "--headless --duration 86400 --out=runs/earth.csv --sample-interval=3600 \
+             --gpu Radeon --settings=other.yml --live-tweak orbit.scenario --format json",
+        );
+        assert_eq!(
+            options,
+            LaunchOptions {
+                scenario: Some(PathBuf::from("orbit.scenario")),
+                scenario_format: Some(ScenarioFormat::Json),
+                headless: true,
+                duration: Some(86400.),
+                out: Some(PathBuf::from("runs/earth.csv")),
+                sample_interval: Some(3600.),
+                warp: None,
+                live_tweak: true,
+                gpu: Some("Radeon".to_string()),
+                settings: Some(PathBuf::from("other.yml")),
+            }
+        );
+        assert_eq!(self::launch("--warp 0").warp, Some(0.));
+    }
+
+    #[test]
+    fn commands_stop_the_parsing() {
+        assert_eq!(self::parse_line("--help --bogus"), Ok(Command::Help));
+        assert_eq!(self::parse_line("-h"), Ok(Command::Help));
+        assert_eq!(self::parse_line("--dump-schema"), Ok(Command::DumpSchema));
+        assert_eq!(
+            self::parse_line("--report-i18n=de"),
+            Ok(Command::ReportI18n("de".to_string()))
+        );
+    }
+
+    #[test]
+    fn everything_after_the_separator_is_positional() {
+        assert_eq!(
+            self::launch("-- --headless").scenario,
+            Some(PathBuf::from("--headless"))
+        );
+        assert_eq!(self::launch("-").scenario, Some(PathBuf::from("-")));
+    }
+
+    #[test]
+    fn invalid_command_lines_are_explained() {
+        let cases = [
+            ("a.yml b.yml", "Unexpected argument \"b.yml\""),
+            ("--bogus", "Unknown option --bogus"),
+            ("--duration", "--duration needs a value"),
+            ("--duration ten", "--duration expects a number, got \"ten\""),
+            ("--duration inf", "--duration expects a number, got \"inf\""),
+            ("--headless --duration 0", "--duration must be positive"),
+            (
+                "--headless --duration 1 --sample-interval -1",
+                "--sample-interval must be positive",
+            ),
+            ("--warp -2", "--warp must not be negative"),
+            (
+                "--format xml a",
+                "--format expects yaml, json, toml or ron, got \"xml\"",
+            ),
+            ("--headless=yes", "--headless takes no value"),
+            (
+                "--headless",
+                "--headless needs --duration, nothing would end the run",
+            ),
+            ("--out a.csv", "--out and --sample-interval need --headless"),
+            (
+                "--headless --duration 1 --out a.txt",
+                "--out writes csv, a.txt is not a .csv file",
+            ),
+            ("--format yaml", "--format needs a scenario"),
+        ];
+        for (line, error) in cases {
+            assert_eq!(self::parse_line(line), Err(error.to_string()), "{}", line);
+        }
+    }
+
+    #[test]
+    fn csv_extension_is_case_insensitive() {
+        let options = self::launch("--headless --duration 1 --out A.CSV");
+        assert_eq!(options.out, Some(PathBuf::from("A.CSV")));
+    }
+}
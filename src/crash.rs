@@ -0,0 +1,260 @@
+use std::{
+    backtrace::Backtrace,
+    fmt::Write,
+    panic::PanicHookInfo,
+    path::PathBuf,
+    sync::{Mutex, PoisonError, TryLockError},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+
+use crate::{
+    logging::{LogBuffer, LogRecord},
+    platform,
+};
+
+/* the most recent log lines written into a crash report */
+pub const CRASH_LOG_LINES: usize = 200;
+const CRASH_DIALOG_TITLE: &str = "SpaceS crashed";
+
+/* the adapter of the current graphics interface, none until one was built */
+static ADAPTER_DESCRIPTION: Mutex<Option<String>> = Mutex::new(None);
+
+/* what a crash report is written from, gathered by the panic hook */
+pub struct CrashReport {
+    pub message: String,
+    pub location: Option<String>,
+    pub thread: Option<String>,
+    pub backtrace: String,
+    pub adapter: Option<String>,
+    pub log: Vec<LogRecord>,
+    /* seconds since the unix epoch */
+    pub timestamp: u64,
+}
+
+/* set whenever a graphics interface is built, also after a device loss */
+pub fn set_adapter(info: &wgpu::AdapterInfo) {
+    *ADAPTER_DESCRIPTION
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner) = Some(self::describe_adapter(info));
+}
+
+/* keeps the default hook printing to stderr, then writes the report and points the user at it.
+nothing in here may panic, a panicking hook aborts without any report */
+pub fn install_panic_hook(log_buffer: LogBuffer) {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        let report = self::collect(info, &log_buffer);
+        match self::write_report(&report) {
+            Ok(path) => platform::show_error_dialog(
+                CRASH_DIALOG_TITLE,
+                &format!(
+                    "SpaceS stopped because of an internal error:\n{}\n\nA crash report was written to {}",
+                    report.message,
+                    path.display()
+                ),
+            ),
+            Err(error) => {
+                eprintln!("Failed to write the crash report: {error:#}");
+                platform::show_error_dialog(
+                    CRASH_DIALOG_TITLE,
+                    &format!(
+                        "SpaceS stopped because of an internal error:\n{}",
+                        report.message
+                    ),
+                );
+            }
+        }
+    }));
+}
+
+/* the locks are only tried, the panicking thread may hold one of them */
+fn collect(info: &PanicHookInfo<'_>, log_buffer: &LogBuffer) -> CrashReport {
+    let payload = info.payload();
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic payload".to_string());
+    let adapter = match ADAPTER_DESCRIPTION.try_lock() {
+        Ok(adapter) => adapter.clone(),
+        Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner().clone(),
+        Err(TryLockError::WouldBlock) => None,
+    };
+    CrashReport {
+        message,
+        location: info.location().map(|location| location.to_string()),
+        thread: std::thread::current().name().map(str::to_string),
+        backtrace: Backtrace::force_capture().to_string(),
+        adapter,
+        log: self::log_tail(log_buffer),
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+    }
+}
+
+/* the last CRASH_LOG_LINES records, none while another thread holds the buffer */
+fn log_tail(log_buffer: &LogBuffer) -> Vec<LogRecord> {
+    log_buffer
+        .try_records()
+        .map(|records| {
+            let skipped = records.len().saturating_sub(CRASH_LOG_LINES);
+            records.iter().skip(skipped).cloned().collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn format_report(report: &CrashReport) -> String {
+    let mut text = String::new();
+    let _ = writeln!(text, "SpaceS {} crash report", env!("CARGO_PKG_VERSION"));
+    let _ = writeln!(text, "time: {} (unix seconds)", report.timestamp);
+    let _ = writeln!(
+        text,
+        "thread: {}",
+        report.thread.as_deref().unwrap_or("unnamed")
+    );
+    let _ = writeln!(
+        text,
+        "location: {}",
+        report.location.as_deref().unwrap_or("unknown")
+    );
+    let _ = writeln!(
+        text,
+        "adapter: {}",
+        report
+            .adapter
+            .as_deref()
+            .unwrap_or("none, the graphics were not initialized")
+    );
+    let _ = writeln!(text, "\nmessage:\n{}", report.message);
+    let _ = writeln!(text, "\nbacktrace:\n{}", report.backtrace.trim_end());
+    let _ = writeln!(text, "\nlast {} log lines:", report.log.len());
+    for record in &report.log {
+        let _ = writeln!(
+            text,
+            "{} {:>5} {}: {}",
+            record.timestamp, record.level, record.target, record.message
+        );
+    }
+    text
+}
+
+fn write_report(report: &CrashReport) -> Result<PathBuf> {
+    let path = platform::crash_reports_directory()?.join(format!("crash-{}.txt", report.timestamp));
+    std::fs::write(&path, self::format_report(report))
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+fn describe_adapter(info: &wgpu::AdapterInfo) -> String {
+    format!(
+        "{} ({:?}, {:?}, vendor 0x{:04X}, device 0x{:04X}, driver {} {})",
+        info.name,
+        info.backend,
+        info.device_type,
+        info.vendor,
+        info.device,
+        info.driver,
+        info.driver_info
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing::Level;
+
+    use super::*;
+
+    fn record(message: &str) -> LogRecord {
+        LogRecord {
+            level: Level::WARN,
+            target: "simulation::graphics".to_string(),
+            message: message.to_string(),
+            timestamp: "14:03:07.250".to_string(),
+        }
+    }
+
+    fn report() -> CrashReport {
+        CrashReport {
+            message: "index out of bounds".to_string(),
+            location: Some("src/graphics.rs:10:5".to_string()),
+            thread: Some("main".to_string()),
+            backtrace: "   0: simulation::main\n".to_string(),
+            adapter: Some("llvmpipe".to_string()),
+            log: vec![self::record("Surface lost")],
+            timestamp: 1_700_000_000,
+        }
+    }
+
+    #[test]
+    fn report_lists_every_section() {
+        let text = format_report(&self::report());
+        let expected = format!(
+            "SpaceS {} crash report\n\
+             time: 1700000000 (unix seconds)\n\
+             thread: main\n\
+             location: src/graphics.rs:10:5\n\
+             adapter: llvmpipe\n\
+             \n\
+             message:\n\
+             index out of bounds\n\
+             \n\
+             backtrace:\n   \
+             0: simulation::main\n\
+             \n\
+             last 1 log lines:\n\
+             14:03:07.250  WARN simulation::graphics: Surface lost\n",
+            env!("CARGO_PKG_VERSION")
+        );
+        assert_eq!(text, expected);
+    }
+
+    #[test]
+    fn missing_fields_are_named() {
+        let report = CrashReport {
+            location: None,
+            thread: None,
+            adapter: None,
+            log: Vec::new(),
+            ..self::report()
+        };
+        let text = format_report(&report);
+        assert!(text.contains("thread: unnamed\n"));
+        assert!(text.contains("location: unknown\n"));
+        assert!(text.contains("adapter: none, the graphics were not initialized\n"));
+        assert!(text.ends_with("last 0 log lines:\n"));
+    }
+
+    #[test]
+    fn only_the_last_log_lines_are_kept() {
+        let buffer = LogBuffer::default();
+        (0..CRASH_LOG_LINES + 10).for_each(|index| buffer.push(self::record(&index.to_string())));
+        let tail = log_tail(&buffer);
+        assert_eq!(tail.len(), CRASH_LOG_LINES);
+        assert_eq!(tail[0].message, "10");
+        /* a buffer locked by the panicking thread is left out */
+        let _records = buffer.records();
+        assert!(log_tail(&buffer).is_empty());
+    }
+
+    #[test]
+    fn adapter_description_has_the_ids_in_hex() {
+        let info = wgpu::AdapterInfo {
+            name: "AMD Radeon RX 6600".to_string(),
+            vendor: 0x1002,
+            device: 0x73ff,
+            device_type: wgpu::DeviceType::DiscreteGpu,
+            driver: "radv".to_string(),
+            driver_info: "Mesa 24.0".to_string(),
+            backend: wgpu::Backend::Vulkan,
+        };
+        assert_eq!(
+            describe_adapter(&info),
+            "AMD Radeon RX 6600 (Vulkan, DiscreteGpu, vendor 0x1002, device 0x73FF, driver radv Mesa 24.0)"
+        );
+    }
+}
@@ -1,27 +1,651 @@
-use std::{fmt::Debug, ops::Deref, path::PathBuf, sync::Arc};
+use std::{fmt::Debug, ops::Deref, path::PathBuf, sync::Arc, time::Instant};
 
 use anyhow::{Ok, Result};
 use colored::Colorize;
 use getset::Getters;
-use image::GenericImageView;
-use imgui::TextureId;
 use strum::{EnumIter, IntoEnumIterator};
-use tracing::info;
+use tracing::{info, warn};
 use wgpu::{
-    Backends, Device, Extent3d, Queue, SurfaceConfiguration, Texture, TextureDescriptor,
-    naga::FastHashMap,
+    Backends, Device, Extent3d, Queue, SurfaceConfiguration, SurfaceError, Texture,
+    TextureDescriptor, TextureView, naga::FastHashSet, util::DeviceExt,
 };
 use winit::{event_loop::EventLoopProxy, window::Window};
 
-use crate::application::ApplicationSimulationEvent;
+use crate::{
+    application::ApplicationSimulationEvent,
+    logging::LogBuffer,
+    simulation::{BodyId, thread::SimulationSnapshot},
+    storage::{AssetResolver, Settings},
+};
+
+pub mod about_window;
+pub mod adapter;
+pub mod billboards;
+pub mod body_popup;
+pub mod body_texture;
+pub mod camera;
+pub mod capture;
+pub mod debris;
+pub mod device_loss;
+pub mod exit_dialog;
+pub mod file_menu;
+pub mod fonts;
+pub mod gizmos;
+pub mod grid;
+pub mod horizons_dialog;
+pub mod import_dialog;
+pub mod inspector;
+pub mod ktx2;
+pub mod labels;
+pub mod log_window;
+pub mod mipmap;
+pub mod outline;
+pub mod overlay;
+pub mod picking;
+pub mod pipeline;
+pub mod plots_window;
+pub mod post;
+pub mod restore_dialog;
+pub mod rings;
+pub mod save_slots_window;
+pub mod scenario_browser;
+pub mod scenario_dialog;
+pub mod scenario_properties;
+pub mod settings_window;
+pub mod shaders;
+pub mod spawn_dialog;
+pub mod starfield;
+pub mod stats;
+pub mod status_bar;
+pub mod surface;
+pub mod targets;
+pub mod texture_loader;
+pub mod textures;
+pub mod title_bar;
+pub mod toasts;
+pub mod tonemap;
+pub mod toolbar;
+pub mod trail;
+pub mod trajectory_window;
+pub mod ui;
+pub mod viewport;
 
-#[derive(Getters, derive_new::new)]
+#[derive(Getters)]
 pub struct SimulationGraphcisInterface<'window> {
     pub application_surface: wgpu::Surface<'window>,
-    pub _gpu_handle: wgpu::Adapter,
+    pub gpu_handle: wgpu::Adapter,
     pub gpu_interface: wgpu::Device,
     pub gpu_queue: wgpu::Queue,
+    /* set once the device is lost, the application then rebuilds the interface */
+    pub device_loss: device_loss::DeviceLossMonitor,
     pub surface_configuration: SurfaceConfiguration,
+    /* set when the surface format is not srgb, forwarded to the shaders via the camera uniform */
+    pub manual_gamma_correction: bool,
+    pub present_preference: surface::PresentPreference,
+    pub camera: camera::Camera,
+    pub camera_controller: camera::CameraController,
+    /* body picked with the mouse, read by the ui and the follow camera */
+    pub selected_body: Option<BodyId>,
+    pub camera_binding: camera::CameraBinding,
+    pub body_pipeline: pipeline::BodyPipeline,
+    pub debug_render_mode: pipeline::DebugRenderMode,
+    /* stand in for spheres too small on screen, picking uses the same minimum size */
+    pub billboard_settings: billboards::BillboardSettings,
+    pub billboard_pipeline: billboards::BillboardPipeline,
+    /* rim around the selected body, a ring around its dot while it is drawn as billboard */
+    pub outline_pipeline: outline::OutlinePipeline,
+    /* shares the body texture cache for ring strips and generated bands */
+    pub ring_pipeline: rings::RingPipeline,
+    pub body_textures: body_texture::BodyTextureCache,
+    /* off draws every body full-bright, for debugging textures and colors */
+    pub lighting_enabled: bool,
+    pub starfield_configuration: starfield::StarfieldConfiguration,
+    /* none while the starfield is disabled */
+    pub starfield_pipeline: Option<starfield::StarfieldPipeline>,
+    pub trail_settings: trail::TrailSettings,
+    pub trail_pipeline: trail::TrailPipeline,
+    /* collision bursts, fed from the simulation events */
+    pub debris: debris::DebrisSystem,
+    pub debris_pipeline: debris::DebrisPipeline,
+    pub label_settings: labels::LabelSettings,
+    pub gizmo_settings: gizmos::GizmoSettings,
+    pub ui: ui::UiState,
+    /* reference plane in the ecliptic, off by default */
+    pub grid_settings: grid::GridSettings,
+    pub grid_pipeline: grid::GridPipeline,
+    pub tonemap_settings: tonemap::TonemapSettings,
+    /* none on the direct path, the scene then renders straight into the swapchain */
+    pub tonemap_pipeline: Option<tonemap::TonemapPipeline>,
+    pub bloom_settings: post::BloomSettings,
+    /* needs the hdr target, none together with the tonemapping pipeline */
+    pub bloom_pipeline: Option<post::BloomPipeline>,
+    /* offscreen scene color shown inside an imgui window */
+    pub viewport: viewport::Viewport,
+    pub render_targets: targets::RenderTargets,
+    pub surface_failures: SurfaceFailureTracker,
+    pub render_stats: stats::StatsAggregator,
+    /* time between redraws, fed by the event loop */
+    pub frame_window: stats::FrameWindow,
+    pub overlay_visible: bool,
+    /* none without timestamp query support, render_stats then falls back to cpu timings */
+    pub gpu_timer: Option<stats::GpuTimer>,
+    pub screenshot_capture: capture::ScreenshotCapture,
+}
+
+/* what survives a device loss, everything on the gpu is rebuilt from scratch */
+pub struct RetainedGraphicsState {
+    camera: camera::Camera,
+    camera_controller: camera::CameraController,
+    selected_body: Option<BodyId>,
+    debug_render_mode: pipeline::DebugRenderMode,
+    present_preference: surface::PresentPreference,
+    sample_count: u32,
+    render_scale: f32,
+    billboard_settings: billboards::BillboardSettings,
+    lighting_enabled: bool,
+    starfield_configuration: starfield::StarfieldConfiguration,
+    trail_settings: trail::TrailSettings,
+    debris: debris::DebrisSystem,
+    label_settings: labels::LabelSettings,
+    gizmo_settings: gizmos::GizmoSettings,
+    overlay_visible: bool,
+    ui: ui::UiState,
+    grid_settings: grid::GridSettings,
+    tonemap_settings: tonemap::TonemapSettings,
+    bloom_settings: post::BloomSettings,
+    texture_loader: Option<texture_loader::TextureLoader>,
+    assets: AssetResolver,
+}
+
+/* a freshly compiled pipeline, only swapped in once its shader validated */
+#[cfg(feature = "shader-hot-reload")]
+enum ShaderRebuild {
+    Body(pipeline::BodyPipeline),
+    Billboard(billboards::BillboardPipeline),
+    Ring(rings::RingPipeline),
+    Trail(trail::TrailPipeline),
+    Debris(debris::DebrisPipeline),
+    Outline(outline::OutlinePipeline),
+    Grid(grid::GridPipeline),
+    Starfield(starfield::StarfieldPipeline),
+    Tonemap(tonemap::TonemapPipeline),
+    Bloom(post::BloomPipeline),
+}
+
+impl SimulationGraphcisInterface<'_> {
+    /* drops the interface, the surface included so a replacement can attach to the same window */
+    pub fn into_retained_state(mut self) -> RetainedGraphicsState {
+        RetainedGraphicsState {
+            texture_loader: self.body_textures.take_loader(),
+            assets: self.body_textures.assets().clone(),
+            camera: self.camera,
+            camera_controller: self.camera_controller,
+            selected_body: self.selected_body,
+            debug_render_mode: self.debug_render_mode,
+            present_preference: self.present_preference,
+            sample_count: self.render_targets.sample_count,
+            render_scale: self.viewport.render_scale(),
+            billboard_settings: self.billboard_settings,
+            lighting_enabled: self.lighting_enabled,
+            starfield_configuration: self.starfield_configuration,
+            trail_settings: self.trail_settings,
+            debris: self.debris,
+            label_settings: self.label_settings,
+            gizmo_settings: self.gizmo_settings,
+            overlay_visible: self.overlay_visible,
+            ui: self.ui,
+            grid_settings: self.grid_settings,
+            tonemap_settings: self.tonemap_settings,
+            bloom_settings: self.bloom_settings,
+        }
+    }
+
+    /* applies the state of the lost interface to a freshly built one */
+    pub fn restore(&mut self, state: RetainedGraphicsState) {
+        self.camera = state.camera;
+        self.camera
+            .set_viewport_size(self.viewport.width, self.viewport.height);
+        self.camera_controller = state.camera_controller;
+        self.selected_body = state.selected_body;
+        self.viewport.set_render_scale(state.render_scale);
+        self.debug_render_mode = state.debug_render_mode;
+        self.billboard_settings = state.billboard_settings;
+        self.lighting_enabled = state.lighting_enabled;
+        self.trail_settings = state.trail_settings;
+        self.debris = state.debris;
+        self.label_settings = state.label_settings;
+        self.gizmo_settings = state.gizmo_settings;
+        self.overlay_visible = state.overlay_visible;
+        self.ui = state.ui;
+        self.grid_settings = state.grid_settings;
+        self.tonemap_settings = state.tonemap_settings;
+        self.bloom_settings = state.bloom_settings;
+        if let Some(loader) = state.texture_loader {
+            self.body_textures.set_loader(loader);
+        }
+        self.body_textures.set_assets(state.assets);
+        if state.present_preference != self.present_preference {
+            self.set_present_preference(state.present_preference);
+        }
+        self.starfield_configuration = state.starfield_configuration;
+        self.set_sample_count(state.sample_count);
+        self.starfield_pipeline = self.create_starfield_pipeline();
+    }
+
+    /* zero sizes (minimized) are skipped, wgpu refuses to configure them */
+    pub fn reconfigure_surface(&self) {
+        if self.surface_configuration.width == 0 || self.surface_configuration.height == 0 {
+            return;
+        }
+        self.application_surface
+            .configure(&self.gpu_interface, &self.surface_configuration);
+    }
+
+    /* switches vsync behaviour at runtime by reconfiguring the surface */
+    pub fn set_present_preference(&mut self, preference: surface::PresentPreference) {
+        let capabilities = self.application_surface.get_capabilities(&self.gpu_handle);
+        let Some(present_mode) =
+            surface::select_present_mode(preference, &capabilities.present_modes)
+        else {
+            warn!("The surface does not expose any present mode");
+            return;
+        };
+        self.present_preference = preference;
+        self.surface_configuration.present_mode = present_mode;
+        self.reconfigure_surface();
+        info!("Presenting with {:?} ({})", present_mode, preference);
+    }
+
+    /* rebuilds the attachments and every pipeline that bakes in the sample count */
+    pub fn set_sample_count(&mut self, requested: u32) {
+        let sample_count = targets::supported_sample_count(
+            &self.gpu_handle,
+            &[self.render_targets.color_format, targets::DEPTH_FORMAT],
+            requested,
+        );
+        if sample_count == self.render_targets.sample_count {
+            return;
+        }
+        self.recreate_render_targets(sample_count);
+        self.body_pipeline = pipeline::BodyPipeline::new(
+            &self.gpu_interface,
+            self.render_targets.color_format,
+            sample_count,
+            &self.camera_binding.bind_group_layout,
+            &self.body_textures.bind_group_layout,
+        );
+        self.billboard_pipeline = billboards::BillboardPipeline::new(
+            &self.gpu_interface,
+            self.render_targets.color_format,
+            sample_count,
+            &self.camera_binding.bind_group_layout,
+        );
+        self.ring_pipeline = rings::RingPipeline::new(
+            &self.gpu_interface,
+            self.render_targets.color_format,
+            sample_count,
+            &self.camera_binding.bind_group_layout,
+            &self.body_textures.bind_group_layout,
+        );
+        self.trail_pipeline = trail::TrailPipeline::new(
+            &self.gpu_interface,
+            self.render_targets.color_format,
+            sample_count,
+            &self.camera_binding.bind_group_layout,
+        );
+        self.debris_pipeline = debris::DebrisPipeline::new(
+            &self.gpu_interface,
+            self.render_targets.color_format,
+            sample_count,
+            &self.camera_binding.bind_group_layout,
+        );
+        self.outline_pipeline = outline::OutlinePipeline::new(
+            &self.gpu_interface,
+            self.render_targets.color_format,
+            sample_count,
+            &self.camera_binding.bind_group_layout,
+        );
+        self.grid_pipeline = grid::GridPipeline::new(
+            &self.gpu_interface,
+            self.render_targets.color_format,
+            sample_count,
+            &self.camera_binding.bind_group_layout,
+        );
+        self.starfield_pipeline = self.create_starfield_pipeline();
+        info!("Rendering with {}x MSAA", sample_count);
+    }
+
+    pub fn create_starfield_pipeline(&self) -> Option<starfield::StarfieldPipeline> {
+        self.starfield_configuration.enabled.then(|| {
+            starfield::StarfieldPipeline::new(
+                &self.gpu_interface,
+                self.render_targets.color_format,
+                self.render_targets.sample_count,
+                &self.camera_binding.bind_group_layout,
+                &self.starfield_configuration,
+            )
+        })
+    }
+
+    /* the stars are generated into their buffer once, another count or seed builds it again */
+    pub fn set_starfield_configuration(
+        &mut self,
+        configuration: starfield::StarfieldConfiguration,
+    ) {
+        self.starfield_configuration = configuration;
+        self.starfield_pipeline = self.create_starfield_pipeline();
+    }
+
+    /* rebuilds the pipelines compiled from the changed file, a shader that fails
+    validation is logged and the previous pipeline keeps rendering */
+    #[cfg(feature = "shader-hot-reload")]
+    pub fn reload_shader(&mut self, file_name: &str) {
+        self.gpu_interface
+            .push_error_scope(wgpu::ErrorFilter::Validation);
+        let rebuild = match file_name {
+            name if name == shaders::BODY_SHADER.file_name => {
+                let body_pipeline = pipeline::BodyPipeline::new(
+                    &self.gpu_interface,
+                    self.render_targets.color_format,
+                    self.render_targets.sample_count,
+                    &self.camera_binding.bind_group_layout,
+                    &self.body_textures.bind_group_layout,
+                );
+                Some(ShaderRebuild::Body(body_pipeline))
+            }
+            name if name == shaders::BILLBOARD_SHADER.file_name => {
+                let billboard_pipeline = billboards::BillboardPipeline::new(
+                    &self.gpu_interface,
+                    self.render_targets.color_format,
+                    self.render_targets.sample_count,
+                    &self.camera_binding.bind_group_layout,
+                );
+                Some(ShaderRebuild::Billboard(billboard_pipeline))
+            }
+            name if name == shaders::RING_SHADER.file_name => {
+                let ring_pipeline = rings::RingPipeline::new(
+                    &self.gpu_interface,
+                    self.render_targets.color_format,
+                    self.render_targets.sample_count,
+                    &self.camera_binding.bind_group_layout,
+                    &self.body_textures.bind_group_layout,
+                );
+                Some(ShaderRebuild::Ring(ring_pipeline))
+            }
+            name if name == shaders::TRAIL_SHADER.file_name => {
+                let trail_pipeline = trail::TrailPipeline::new(
+                    &self.gpu_interface,
+                    self.render_targets.color_format,
+                    self.render_targets.sample_count,
+                    &self.camera_binding.bind_group_layout,
+                );
+                Some(ShaderRebuild::Trail(trail_pipeline))
+            }
+            name if name == shaders::DEBRIS_SHADER.file_name => {
+                let debris_pipeline = debris::DebrisPipeline::new(
+                    &self.gpu_interface,
+                    self.render_targets.color_format,
+                    self.render_targets.sample_count,
+                    &self.camera_binding.bind_group_layout,
+                );
+                Some(ShaderRebuild::Debris(debris_pipeline))
+            }
+            name if name == shaders::OUTLINE_SHADER.file_name => {
+                let outline_pipeline = outline::OutlinePipeline::new(
+                    &self.gpu_interface,
+                    self.render_targets.color_format,
+                    self.render_targets.sample_count,
+                    &self.camera_binding.bind_group_layout,
+                );
+                Some(ShaderRebuild::Outline(outline_pipeline))
+            }
+            name if name == shaders::GRID_SHADER.file_name => {
+                let grid_pipeline = grid::GridPipeline::new(
+                    &self.gpu_interface,
+                    self.render_targets.color_format,
+                    self.render_targets.sample_count,
+                    &self.camera_binding.bind_group_layout,
+                );
+                Some(ShaderRebuild::Grid(grid_pipeline))
+            }
+            name if name == shaders::STARFIELD_SHADER.file_name => self
+                .create_starfield_pipeline()
+                .map(ShaderRebuild::Starfield),
+            name if name == shaders::TONEMAP_SHADER.file_name => {
+                self.render_targets.hdr_view.as_ref().map(|hdr_view| {
+                    ShaderRebuild::Tonemap(tonemap::TonemapPipeline::new(
+                        &self.gpu_interface,
+                        self.surface_configuration.format,
+                        hdr_view,
+                        &self.tonemap_settings,
+                        self.manual_gamma_correction,
+                    ))
+                })
+            }
+            name if name == shaders::BLOOM_SHADER.file_name => {
+                self.render_targets.hdr_view.as_ref().map(|hdr_view| {
+                    ShaderRebuild::Bloom(post::BloomPipeline::new(
+                        &self.gpu_interface,
+                        hdr_view,
+                        self.viewport.width,
+                        self.viewport.height,
+                        &self.bloom_settings,
+                    ))
+                })
+            }
+            _ => None,
+        };
+        if let Some(error) = pollster::block_on(self.gpu_interface.pop_error_scope()) {
+            tracing::error!("Keeping the previous {} pipeline: {}", file_name, error);
+            return;
+        }
+        let Some(rebuild) = rebuild else {
+            return;
+        };
+        match rebuild {
+            ShaderRebuild::Body(body_pipeline) => self.body_pipeline = body_pipeline,
+            ShaderRebuild::Billboard(billboard_pipeline) => {
+                self.billboard_pipeline = billboard_pipeline
+            }
+            ShaderRebuild::Ring(ring_pipeline) => self.ring_pipeline = ring_pipeline,
+            ShaderRebuild::Trail(trail_pipeline) => self.trail_pipeline = trail_pipeline,
+            ShaderRebuild::Debris(debris_pipeline) => self.debris_pipeline = debris_pipeline,
+            ShaderRebuild::Outline(outline_pipeline) => self.outline_pipeline = outline_pipeline,
+            ShaderRebuild::Grid(grid_pipeline) => self.grid_pipeline = grid_pipeline,
+            ShaderRebuild::Starfield(starfield_pipeline) => {
+                self.starfield_pipeline = Some(starfield_pipeline)
+            }
+            ShaderRebuild::Tonemap(tonemap_pipeline) => {
+                self.tonemap_pipeline = Some(tonemap_pipeline)
+            }
+            ShaderRebuild::Bloom(bloom_pipeline) => self.bloom_pipeline = Some(bloom_pipeline),
+        }
+        info!("Reloaded {}", file_name);
+    }
+
+    /* a deleted body leaves no selection, follow target or hidden trail behind */
+    pub fn forget_body(&mut self, id: BodyId) {
+        if self.selected_body == Some(id) {
+            self.selected_body = None;
+        }
+        if self.camera.follow_target() == Some(id) {
+            self.camera.set_follow_target(None);
+        }
+        self.trail_settings.hidden_bodies.remove(&id);
+    }
+
+    /* a freshly loaded scenario starts its ids over, nothing of the old bodies may stay. its
+    textures are looked up with the given resolver from now on */
+    pub fn forget_scenario(&mut self, textures: &FastHashSet<String>, assets: AssetResolver) {
+        self.selected_body = None;
+        self.camera.set_follow_target(None);
+        self.trail_settings.hidden_bodies.clear();
+        self.debris = debris::DebrisSystem::default();
+        self.body_textures.set_assets(assets);
+        self.body_textures.retain(textures);
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width == 0
+            || height == 0
+            || (self.surface_configuration.width == width
+                && self.surface_configuration.height == height)
+        {
+            return;
+        }
+        self.surface_configuration.width = width;
+        self.surface_configuration.height = height;
+        self.application_surface
+            .configure(&self.gpu_interface, &self.surface_configuration);
+    }
+
+    /* the scene attachments and the camera follow the viewport window, not the surface */
+    pub fn resize_viewport(
+        &mut self,
+        imgui_renderer: &mut imgui_wgpu::Renderer,
+        width: u32,
+        height: u32,
+    ) {
+        self.viewport
+            .resize(&self.gpu_interface, imgui_renderer, width, height);
+        self.recreate_render_targets(self.render_targets.sample_count);
+        self.camera
+            .set_viewport_size(self.viewport.width, self.viewport.height);
+    }
+
+    /* the tonemapping pass samples the hdr target, so its bind group follows the targets */
+    fn recreate_render_targets(&mut self, sample_count: u32) {
+        self.render_targets = targets::RenderTargets::new(
+            &self.gpu_interface,
+            &self.viewport,
+            sample_count,
+            self.tonemap_pipeline.is_some(),
+        );
+        if let (Some(tonemap_pipeline), Some(hdr_view)) = (
+            self.tonemap_pipeline.as_mut(),
+            self.render_targets.hdr_view.as_ref(),
+        ) {
+            tonemap_pipeline.rebind(&self.gpu_interface, hdr_view);
+        }
+        if let (Some(bloom_pipeline), Some(hdr_view)) = (
+            self.bloom_pipeline.as_mut(),
+            self.render_targets.hdr_view.as_ref(),
+        ) {
+            bloom_pipeline.resize(
+                &self.gpu_interface,
+                hdr_view,
+                self.viewport.width,
+                self.viewport.height,
+            );
+        }
+    }
+
+    pub fn set_render_scale(&mut self, render_scale: f32) {
+        self.viewport.set_render_scale(render_scale);
+        info!("Render scale {:.2}x", self.viewport.render_scale());
+    }
+
+    /* the blur chain stays allocated, so this is safe to flip between frames */
+    pub fn toggle_bloom(&mut self) {
+        if self.bloom_pipeline.is_none() {
+            warn!("Bloom needs the hdr path, which is not available");
+            return;
+        }
+        self.bloom_settings.enabled = !self.bloom_settings.enabled;
+        info!(
+            "Bloom {}",
+            if self.bloom_settings.enabled {
+                "enabled"
+            } else {
+                "disabled"
+            }
+        );
+    }
+
+    /* the scene shaders only encode gamma when they write the swapchain directly */
+    pub fn scene_gamma_correction(&self) -> bool {
+        self.manual_gamma_correction && self.tonemap_pipeline.is_none()
+    }
+
+    /* BACKGROUND_COLOR in whatever the scene target holds: hdr values that survive the
+    tonemapping unchanged, linear for srgb views or the encoded value when the shaders encode */
+    pub fn background_clear_color(&self) -> wgpu::Color {
+        let linear = BACKGROUND_COLOR.map(self::srgb_to_linear);
+        let [r, g, b] = match (self.tonemap_pipeline.as_ref(), self.manual_gamma_correction) {
+            (Some(_), _) => linear.map(|value| self.tonemap_settings.scene_value(value)),
+            (None, true) => BACKGROUND_COLOR.map(|value| value as f64 / 255.),
+            (None, false) => linear,
+        };
+        wgpu::Color { r, g, b, a: 1. }
+    }
+
+    pub fn adjust_exposure(&mut self, steps: i32) {
+        if self.tonemap_pipeline.is_none() {
+            warn!("Exposure only applies to the hdr path, which is not available");
+            return;
+        }
+        self.tonemap_settings.adjust_exposure(steps);
+        info!("Exposure {:.2}", self.tonemap_settings.exposure);
+    }
+
+    pub fn cycle_tonemap_curve(&mut self) {
+        if self.tonemap_pipeline.is_none() {
+            warn!("Tonemapping is not available, rendering directly into the swapchain");
+            return;
+        }
+        self.tonemap_settings.curve = self.tonemap_settings.curve.next();
+        info!("Tonemapping with {}", self.tonemap_settings.curve);
+    }
+}
+
+/* srgb, behind everything the scene draws */
+pub const BACKGROUND_COLOR: [u8; 3] = [32, 31, 34];
+
+pub fn srgb_to_linear(value: u8) -> f64 {
+    let value = value as f64 / 255.;
+    match value <= 0.04045 {
+        true => value / 12.92,
+        false => ((value + 0.055) / 1.055).powf(2.4),
+    }
+}
+
+/* consecutive acquire failures after which rendering gives up instead of spinning */
+pub const MAXIMUM_CONSECUTIVE_SURFACE_FAILURES: u32 = 60;
+
+#[derive(Debug, PartialEq)]
+pub enum SurfaceRecovery {
+    Reconfigure,
+    SkipFrame,
+    Abort(String),
+}
+
+#[derive(Debug, Default)]
+pub struct SurfaceFailureTracker {
+    consecutive_failures: u32,
+}
+
+impl SurfaceFailureTracker {
+    pub fn on_success(&mut self) {
+        self.consecutive_failures = 0;
+    }
+
+    /* decides how the render loop reacts to a failed surface acquire */
+    pub fn on_error(&mut self, error: &SurfaceError) -> SurfaceRecovery {
+        self.consecutive_failures += 1;
+        if self.consecutive_failures >= MAXIMUM_CONSECUTIVE_SURFACE_FAILURES {
+            return SurfaceRecovery::Abort(format!(
+                "surface failed {} frames in a row, last error: {}",
+                self.consecutive_failures, error
+            ));
+        }
+        match error {
+            SurfaceError::Lost | SurfaceError::Outdated => SurfaceRecovery::Reconfigure,
+            SurfaceError::Timeout | SurfaceError::Other => SurfaceRecovery::SkipFrame,
+            SurfaceError::OutOfMemory => {
+                SurfaceRecovery::Abort("out of memory while acquiring the surface".to_string())
+            }
+        }
+    }
 }
 
 pub fn display_evailable_graphic_adapters(instance: &wgpu::Instance) {
@@ -35,9 +659,10 @@ pub fn display_evailable_graphic_adapters(instance: &wgpu::Instance) {
     instance
         .enumerate_adapters(Backends::all())
         .iter()
-        .for_each(|gpu_handle| {
+        .enumerate()
+        .for_each(|(index, gpu_handle)| {
             let info = gpu_handle.get_info();
-            info!(" + GPU Handle: [{}]", info.name.yellow());
+            info!(" + GPU Handle {}: [{}]", index, info.name.yellow());
             info!(" +-----------------------------------");
             info!(" + ");
             PhysicalAdapterProperty::iter()
@@ -62,54 +687,288 @@ pub enum PhysicalAdapterProperty {
 }
 
 fn display_adapter_property(adapter: &wgpu::Adapter, property: PhysicalAdapterProperty) -> String {
-    let information: Box<dyn Debug>;
-    match property {
-        PhysicalAdapterProperty::Vendor => information = Box::new(adapter.get_info().vendor),
-        PhysicalAdapterProperty::DeviceType => {
-            information = Box::new(adapter.get_info().device_type)
-        }
-        PhysicalAdapterProperty::Backend => information = Box::new(adapter.get_info().backend),
-        PhysicalAdapterProperty::Features => information = Box::new(adapter.features()),
-        PhysicalAdapterProperty::Limits => information = Box::new(adapter.limits()),
-        PhysicalAdapterProperty::Integrated => {
-            information = Box::new(adapter.get_info().device_type)
-        }
-    };
     format!(
-        " + {} : of Adapter: [{:?}]",
+        " + {} : of Adapter: [{}]",
         property.to_string().yellow(),
-        information.deref()
+        self::adapter_property_value(adapter, property)
     )
     .to_string()
 }
 
+/* without the terminal colors, also for the about window */
+pub fn adapter_property_value(
+    adapter: &wgpu::Adapter,
+    property: PhysicalAdapterProperty,
+) -> String {
+    let information: Box<dyn Debug> = match property {
+        PhysicalAdapterProperty::Vendor => Box::new(adapter.get_info().vendor),
+        PhysicalAdapterProperty::DeviceType => Box::new(adapter.get_info().device_type),
+        PhysicalAdapterProperty::Backend => Box::new(adapter.get_info().backend),
+        PhysicalAdapterProperty::Features => Box::new(adapter.features()),
+        PhysicalAdapterProperty::Limits => Box::new(adapter.limits()),
+        PhysicalAdapterProperty::Integrated => Box::new(adapter.get_info().device_type),
+    };
+    format!("{:?}", information.deref())
+}
+
+/* returns what the ui asked for in this frame, for the application to apply */
+#[allow(clippy::too_many_arguments)]
 pub fn render(
     window_handle: Arc<Window>,
-    graphics_interface: &SimulationGraphcisInterface,
+    graphics_interface: &mut SimulationGraphcisInterface,
     imgui_context: &mut imgui::Context,
     imgui_winit_platform: &mut imgui_winit_support::WinitPlatform,
     imgui_renderer: &mut imgui_wgpu::Renderer,
-    _event_proxy: &mut EventLoopProxy<ApplicationSimulationEvent>,
-    texture_map: &FastHashMap<&'static str, TextureId>,
-) -> Result<()> {
-    window_handle.request_redraw();
+    event_proxy: &mut EventLoopProxy<ApplicationSimulationEvent>,
+    texture_manager: &textures::TextureManager,
+    snapshot: &SimulationSnapshot,
+    settings: &Settings,
+    log_buffer: &LogBuffer,
+    scenario_label: &str,
+    epoch: i64,
+) -> Result<Vec<ApplicationSimulationEvent>> {
+    let frame_start = Instant::now();
+    graphics_interface
+        .screenshot_capture
+        .poll(&graphics_interface.gpu_interface, event_proxy);
+    if let Some(gpu_timer) = graphics_interface.gpu_timer.as_mut() {
+        gpu_timer
+            .poll(&graphics_interface.gpu_interface)
+            .into_iter()
+            .for_each(|timings| graphics_interface.render_stats.record_gpu_timings(timings));
+    }
+
+    /* acquired before the imgui frame starts so a failed acquire leaves no frame half built */
+    let output = graphics_interface
+        .application_surface
+        .get_current_texture()?;
+    if let Some((width, height)) = graphics_interface.viewport.take_resize(Instant::now()) {
+        graphics_interface.resize_viewport(imgui_renderer, width, height);
+    }
+
+    graphics_interface.camera.update_follow(&snapshot.bodies);
+    let scene_gamma_correction = graphics_interface.scene_gamma_correction();
+    graphics_interface.camera_binding.update(
+        &graphics_interface.gpu_queue,
+        &mut graphics_interface.camera,
+        scene_gamma_correction,
+    );
+    if let Some(tonemap_pipeline) = graphics_interface.tonemap_pipeline.as_ref() {
+        tonemap_pipeline.write_uniform(
+            &graphics_interface.gpu_queue,
+            &graphics_interface.tonemap_settings,
+            graphics_interface.manual_gamma_correction,
+        );
+    }
+    let bloom_pipeline = graphics_interface
+        .bloom_pipeline
+        .as_ref()
+        .filter(|_| graphics_interface.bloom_settings.enabled);
+    if let Some(bloom_pipeline) = bloom_pipeline {
+        bloom_pipeline.write_uniform(
+            &graphics_interface.gpu_queue,
+            &graphics_interface.bloom_settings,
+        );
+    }
+    /* everything is uploaded relative to the eye, see Camera::view_matrix */
+    let eye = graphics_interface.camera.position();
+    let body_entries = snapshot
+        .bodies
+        .iter()
+        .enumerate()
+        .map(|(index, body)| {
+            let texture = body.appearance.texture.clone().filter(|path| {
+                graphics_interface.body_textures.resolve(
+                    &graphics_interface.gpu_interface,
+                    &graphics_interface.gpu_queue,
+                    path,
+                )
+            });
+            let color = match texture {
+                Some(_) => [1.0; 4],
+                None => pipeline::body_color(body, index),
+            };
+            let shading =
+                pipeline::body_shading(body, &snapshot.bodies, graphics_interface.lighting_enabled);
+            (
+                texture,
+                pipeline::BodyInstance::from_body(body, eye, color, shading),
+            )
+        })
+        .collect();
+    let (instances, batches) = pipeline::batch_by_texture(body_entries);
+    graphics_interface.body_pipeline.write_instances(
+        &graphics_interface.gpu_interface,
+        &graphics_interface.gpu_queue,
+        &instances,
+        batches,
+    );
+    let billboard_instances: Vec<billboards::BillboardInstance> = snapshot
+        .bodies
+        .iter()
+        .enumerate()
+        .filter_map(|(index, body)| {
+            let shading =
+                pipeline::body_shading(body, &snapshot.bodies, graphics_interface.lighting_enabled);
+            billboards::BillboardInstance::from_body(
+                &graphics_interface.camera,
+                body,
+                pipeline::body_color(body, index),
+                shading,
+                &graphics_interface.billboard_settings,
+            )
+        })
+        .collect();
+    graphics_interface.billboard_pipeline.write_instances(
+        &graphics_interface.gpu_interface,
+        &graphics_interface.gpu_queue,
+        &billboard_instances,
+    );
+    let outline = graphics_interface
+        .selected_body
+        .and_then(|id| snapshot.bodies.iter().find(|body| body.id == id))
+        .and_then(|body| {
+            outline::selection_outline(
+                &graphics_interface.camera,
+                body,
+                &graphics_interface.billboard_settings,
+            )
+        });
+    graphics_interface
+        .outline_pipeline
+        .write_outline(&graphics_interface.gpu_queue, outline);
+    let grid = graphics_interface.grid_settings.enabled.then(|| {
+        grid::GridInstance::from_camera(
+            &graphics_interface.camera,
+            &graphics_interface.grid_settings,
+        )
+    });
+    graphics_interface
+        .grid_pipeline
+        .write_grid(&graphics_interface.gpu_queue, grid);
+    let mut ring_entries: Vec<(String, rings::RingInstance)> = snapshot
+        .bodies
+        .iter()
+        .filter_map(|body| {
+            let definition = body.appearance.rings.as_ref()?;
+            let texture = rings::resolve_texture(
+                &mut graphics_interface.body_textures,
+                &graphics_interface.gpu_interface,
+                &graphics_interface.gpu_queue,
+                definition,
+            );
+            let shading =
+                pipeline::body_shading(body, &snapshot.bodies, graphics_interface.lighting_enabled);
+            Some((
+                texture,
+                rings::RingInstance::from_body(body, definition, eye, shading),
+            ))
+        })
+        .collect();
+    rings::sort_back_to_front(&mut ring_entries);
+    graphics_interface.ring_pipeline.write_instances(
+        &graphics_interface.gpu_interface,
+        &graphics_interface.gpu_queue,
+        ring_entries,
+    );
+    /* trails are drawn in the frame of the followed body, inertial otherwise */
+    let (trail_vertices, trail_strips) = trail::build_trail_vertices(
+        &snapshot.bodies,
+        &snapshot.trails,
+        graphics_interface.camera.follow_target(),
+        eye,
+        &graphics_interface.trail_settings,
+    );
+    graphics_interface.trail_pipeline.write_vertices(
+        &graphics_interface.gpu_interface,
+        &graphics_interface.gpu_queue,
+        &trail_vertices,
+        trail_strips,
+    );
+
+    graphics_interface.debris.retire(snapshot.simulation_time);
+    let debris_instances = graphics_interface
+        .debris
+        .instances(&graphics_interface.camera, snapshot.simulation_time);
+    graphics_interface
+        .debris_pipeline
+        .write_instances(&graphics_interface.gpu_queue, &debris_instances);
 
     /* imgui stuf */
     imgui_winit_platform
         .prepare_frame(imgui_context.io_mut(), &window_handle)
         .unwrap();
     let ui = imgui_context.frame();
-    ui.main_menu_bar(|| {
-        ui.image_button(
-            "str_id",
-            texture_map.get("tex.icon").unwrap().clone(),
-            mint::Vector2 { x: 64., y: 64. },
-        );
-    });
+    let label_candidates = labels::label_candidates(
+        &graphics_interface.camera,
+        &snapshot.bodies,
+        &graphics_interface.label_settings,
+    );
+    let gizmo_arrows = graphics_interface
+        .selected_body
+        .and_then(|id| {
+            let body = snapshot.bodies.iter().find(|body| body.id == id)?;
+            Some(gizmos::body_arrows(
+                &graphics_interface.camera,
+                body,
+                &gizmos::BodyVectors::of(&snapshot.bodies, id)?,
+                &graphics_interface.gizmo_settings,
+            ))
+        })
+        .unwrap_or_default();
+    let mut frame_context = ui::FrameContext {
+        snapshot,
+        texture_manager,
+        settings,
+        log_buffer,
+        scenario_label,
+        epoch,
+        adapter: &graphics_interface.gpu_handle,
+        surface_configuration: &graphics_interface.surface_configuration,
+        camera: &mut graphics_interface.camera,
+        selected_body: &mut graphics_interface.selected_body,
+        gizmo_settings: &mut graphics_interface.gizmo_settings,
+        tonemap_settings: graphics_interface
+            .tonemap_pipeline
+            .is_some()
+            .then_some(&mut graphics_interface.tonemap_settings),
+        render_stats: graphics_interface.render_stats.stats(),
+        frame_pacing: graphics_interface.frame_window.pacing(),
+        viewport_size: (
+            graphics_interface.viewport.width,
+            graphics_interface.viewport.height,
+        ),
+        render_scale: graphics_interface.viewport.render_scale(),
+        active_sample_count: graphics_interface.render_targets.sample_count,
+        window_maximized: window_handle.is_maximized(),
+        overlay_visible: graphics_interface.overlay_visible,
+        frame_start,
+        actions: Vec::new(),
+    };
+    let menu_bar_height = ui::draw_menu_bar(ui, &mut graphics_interface.ui, &mut frame_context);
+    ui::draw_status_bar(ui, &graphics_interface.ui, &frame_context);
+    /* fills the window between the menu and the status bar until it is moved or resized */
+    let display_size = ui.io().display_size;
+    graphics_interface.viewport.draw(
+        ui,
+        [0., menu_bar_height],
+        [
+            display_size[0],
+            display_size[1] - menu_bar_height - status_bar::height(ui),
+        ],
+        |viewport| {
+            labels::draw(ui, viewport, &label_candidates);
+            gizmos::draw(ui, viewport, &gizmo_arrows);
+        },
+    );
+    ui::draw(
+        ui,
+        &mut graphics_interface.ui,
+        &mut frame_context,
+        menu_bar_height,
+    );
+    let actions = frame_context.actions;
 
-    let output = graphics_interface
-        .application_surface
-        .get_current_texture()?;
     let view = output
         .texture
         .create_view(&wgpu::TextureViewDescriptor::default());
@@ -119,85 +978,515 @@ pub fn render(
             .create_command_encoder(&wgpu::CommandEncoderDescriptor {
                 label: Some("Command Encode"),
             });
+    if let Some(gpu_timer) = graphics_interface.gpu_timer.as_mut() {
+        gpu_timer.begin_frame();
+    }
+    let mut cpu_timings = stats::PassTimings::default();
+    let mut draw_counts = stats::DrawCounts::default();
+    let msaa_color_view = graphics_interface.render_targets.msaa_color_view.as_ref();
+    /* the scene lands in the hdr target when tonemapping, in the viewport otherwise */
+    let viewport_view = graphics_interface.viewport.view.clone();
+    let scene_view = graphics_interface
+        .render_targets
+        .hdr_view
+        .as_ref()
+        .unwrap_or(&viewport_view);
+    let background_color = graphics_interface.background_clear_color();
+    let pass_start = Instant::now();
     {
+        let _span = tracing::trace_span!("object pass").entered();
         let mut object_render_pass =
             command_ecoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("Default object Render Pass"),
                 color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
+                    view: msaa_color_view.unwrap_or(scene_view),
+                    resolve_target: msaa_color_view.map(|_| scene_view),
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(background_color),
+                        store: match msaa_color_view {
+                            Some(_) => wgpu::StoreOp::Discard,
+                            None => wgpu::StoreOp::Store,
+                        },
+                    },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &graphics_interface.render_targets.depth_view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(targets::DEPTH_CLEAR_VALUE),
+                        store: wgpu::StoreOp::Discard,
+                    }),
+                    stencil_ops: None,
+                }),
+                occlusion_query_set: None,
+                timestamp_writes: graphics_interface.gpu_timer.as_mut().and_then(|gpu_timer| {
+                    gpu_timer.timestamp_writes(stats::TimedPass::Object, true, true)
+                }),
+            });
+
+        if let Some(starfield_pipeline) = graphics_interface.starfield_pipeline.as_ref() {
+            starfield_pipeline.draw(
+                &mut object_render_pass,
+                &graphics_interface.camera_binding.bind_group,
+            );
+            draw_counts.add(starfield_pipeline.draw_counts());
+        }
+        graphics_interface.body_pipeline.draw(
+            &mut object_render_pass,
+            &graphics_interface.camera_binding.bind_group,
+            &graphics_interface.body_textures,
+            graphics_interface.debug_render_mode,
+        );
+        /* after the opaque spheres so they hide the plane behind them */
+        graphics_interface.grid_pipeline.draw(
+            &mut object_render_pass,
+            &graphics_interface.camera_binding.bind_group,
+        );
+        graphics_interface.billboard_pipeline.draw(
+            &mut object_render_pass,
+            &graphics_interface.camera_binding.bind_group,
+        );
+        graphics_interface.outline_pipeline.draw(
+            &mut object_render_pass,
+            &graphics_interface.camera_binding.bind_group,
+        );
+        graphics_interface.trail_pipeline.draw(
+            &mut object_render_pass,
+            &graphics_interface.camera_binding.bind_group,
+        );
+        /* last, blended over the opaque bodies they surround */
+        graphics_interface.ring_pipeline.draw(
+            &mut object_render_pass,
+            &graphics_interface.camera_binding.bind_group,
+            &graphics_interface.body_textures,
+        );
+        graphics_interface.debris_pipeline.draw(
+            &mut object_render_pass,
+            &graphics_interface.camera_binding.bind_group,
+        );
+        draw_counts.add(graphics_interface.body_pipeline.draw_counts());
+        draw_counts.add(graphics_interface.billboard_pipeline.draw_counts());
+        draw_counts.add(graphics_interface.outline_pipeline.draw_counts());
+        draw_counts.add(graphics_interface.grid_pipeline.draw_counts());
+        draw_counts.add(graphics_interface.trail_pipeline.draw_counts());
+        draw_counts.add(graphics_interface.ring_pipeline.draw_counts());
+        draw_counts.add(graphics_interface.debris_pipeline.draw_counts());
+    }
+    cpu_timings.set(stats::TimedPass::Object, pass_start.elapsed());
+    let pass_start = Instant::now();
+    let _post_span = tracing::trace_span!("post processing").entered();
+    /* the post timestamps start on the first bloom pass and end on the tonemapping pass */
+    let bloom_encoded = match (
+        bloom_pipeline,
+        graphics_interface.render_targets.hdr_view.as_ref(),
+    ) {
+        (Some(bloom_pipeline), Some(hdr_view)) => {
+            bloom_pipeline.encode(
+                &mut command_ecoder,
+                hdr_view,
+                graphics_interface.gpu_timer.as_mut().and_then(|gpu_timer| {
+                    gpu_timer.timestamp_writes(stats::TimedPass::Post, true, false)
+                }),
+            );
+            draw_counts.add(bloom_pipeline.draw_counts());
+            true
+        }
+        _ => false,
+    };
+    if let Some(tonemap_pipeline) = graphics_interface.tonemap_pipeline.as_ref() {
+        let mut tonemap_render_pass =
+            command_ecoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Tonemap Render Pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &viewport_view,
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        /* rgb(32, 31, 34) */
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 3.2 / 255.,
-                            g: 3.1 / 255.,
-                            b: 3.4 / 255.,
-                            a: 1.0,
-                        }),
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
                         store: wgpu::StoreOp::Store,
                     },
                 })],
                 depth_stencil_attachment: None,
                 occlusion_query_set: None,
-                timestamp_writes: None,
+                timestamp_writes: graphics_interface.gpu_timer.as_mut().and_then(|gpu_timer| {
+                    gpu_timer.timestamp_writes(stats::TimedPass::Post, !bloom_encoded, true)
+                }),
             });
+        tonemap_pipeline.draw(&mut tonemap_render_pass);
+        draw_counts.add(stats::DrawCounts::new(1, 1));
+    }
+    drop(_post_span);
+    cpu_timings.set(stats::TimedPass::Post, pass_start.elapsed());
+    let pass_start = Instant::now();
+    {
+        let _span = tracing::trace_span!("imgui pass").entered();
+        /* imgui draws the whole window including the viewport image, after tonemapping so
+        exposure never touches the ui */
+        let mut imgui_render_pass = command_ecoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Imgui Render Pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    /* nothing else writes the swapchain anymore */
+                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            occlusion_query_set: None,
+            timestamp_writes: graphics_interface.gpu_timer.as_mut().and_then(|gpu_timer| {
+                gpu_timer.timestamp_writes(stats::TimedPass::Imgui, true, true)
+            }),
+        });
 
         imgui_winit_platform.prepare_render(ui, &window_handle);
         let imgui_data_buf = imgui_context.render();
+        let imgui_draw_calls = imgui_data_buf
+            .draw_lists()
+            .flat_map(|draw_list| draw_list.commands())
+            .filter(|command| matches!(command, imgui::DrawCmd::Elements { .. }))
+            .count() as u32;
+        draw_counts.add(stats::DrawCounts::new(imgui_draw_calls, imgui_draw_calls));
 
         imgui_renderer.render(
-            &imgui_data_buf,
+            imgui_data_buf,
             &graphics_interface.gpu_queue,
             &graphics_interface.gpu_interface,
-            &mut object_render_pass,
+            &mut imgui_render_pass,
         )?;
+        drop(imgui_render_pass);
+
+        /* the same ui again for a screenshot, into a texture that can be read back */
+        let screenshot_view = graphics_interface.screenshot_capture.screenshot_view(
+            &graphics_interface.gpu_interface,
+            output.texture.format(),
+            output.texture.width(),
+            output.texture.height(),
+        );
+        if let Some(screenshot_view) = screenshot_view {
+            let mut screenshot_render_pass =
+                command_ecoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Screenshot Render Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &screenshot_view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    occlusion_query_set: None,
+                    timestamp_writes: None,
+                });
+            imgui_renderer.render(
+                imgui_data_buf,
+                &graphics_interface.gpu_queue,
+                &graphics_interface.gpu_interface,
+                &mut screenshot_render_pass,
+            )?;
+        }
     }
 
+    cpu_timings.set(stats::TimedPass::Imgui, pass_start.elapsed());
+
+    graphics_interface.screenshot_capture.encode_copy(
+        &graphics_interface.gpu_interface,
+        &mut command_ecoder,
+        graphics_interface.viewport.texture(),
+    );
+    if let Some(gpu_timer) = graphics_interface.gpu_timer.as_mut() {
+        gpu_timer.encode_resolve(&mut command_ecoder);
+    }
     graphics_interface
         .gpu_queue
         .submit(std::iter::once(command_ecoder.finish()));
+    graphics_interface.screenshot_capture.after_submit();
+    if let Some(gpu_timer) = graphics_interface.gpu_timer.as_mut() {
+        gpu_timer.after_submit();
+    }
     output.present();
 
-    Ok(())
+    graphics_interface
+        .render_stats
+        .record_frame(frame_start.elapsed(), cpu_timings, draw_counts);
+    let render_stats = graphics_interface.render_stats.stats();
+    tracing::trace!(
+        frame_ms = render_stats.frame_time.as_secs_f64() * 1e3,
+        object_ms = render_stats.pass_timings.get(stats::TimedPass::Object).as_secs_f64() * 1e3,
+        post_ms = render_stats.pass_timings.get(stats::TimedPass::Post).as_secs_f64() * 1e3,
+        imgui_ms = render_stats.pass_timings.get(stats::TimedPass::Imgui).as_secs_f64() * 1e3,
+        timing_source = %render_stats.timing_source,
+        draw_calls = render_stats.draw_counts.draw_calls,
+        instances = render_stats.draw_counts.instances,
+        "render stats"
+    );
+
+    Ok(actions)
 }
 
-/* this should be called in the init application state */
-pub fn write_image_from_path_msaa_off(
-    surface_conf: &SurfaceConfiguration,
+/* images are uploaded as decoded by image::to_rgba8. the texture used to take the surface format,
+which is Bgra8UnormSrgb on many machines and swapped red and blue, so it is fixed to rgba now */
+pub const IMAGE_TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
+/* rows of buffer to texture copies have to start on COPY_BYTES_PER_ROW_ALIGNMENT */
+pub fn padded_bytes_per_row(width: u32, bytes_per_pixel: u32) -> u32 {
+    let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    (width * bytes_per_pixel).div_ceil(alignment) * alignment
+}
+
+/* copies tightly packed rows into a buffer with padded_bytes_per_row stride */
+pub fn pad_rows(data: &[u8], unpadded_bytes_per_row: u32, padded_bytes_per_row: u32) -> Vec<u8> {
+    if unpadded_bytes_per_row == padded_bytes_per_row {
+        return data.to_vec();
+    }
+    let rows = data.len() / unpadded_bytes_per_row.max(1) as usize;
+    let mut padded = vec![0; rows * padded_bytes_per_row as usize];
+    data.chunks_exact(unpadded_bytes_per_row as usize)
+        .zip(padded.chunks_exact_mut(padded_bytes_per_row as usize))
+        .for_each(|(row, target)| target[..row.len()].copy_from_slice(row));
+    padded
+}
+
+/* loads an image as an srgb rgba texture independent of the surface format, for sampling in the scene.
+ui icons are drawn at a fixed size and skip the mip chain. ktx2 files keep their own format */
+pub fn write_image_from_path(
     device: &Device,
     queue: &Queue,
     path: PathBuf,
+    generate_mipmaps: bool,
 ) -> Result<Texture> {
-    let image_load = image::open(path.clone())?;
-    let size = Extent3d {
-        width: image_load.dimensions().0,
-        height: image_load.dimensions().1,
-        depth_or_array_layers: 1,
-    };
+    let label = path.display().to_string();
+    let data = texture_loader::decode(
+        texture_loader::TextureSource::Path(path),
+        generate_mipmaps,
+        device.features(),
+    )?;
+    Ok(self::upload_texture_data(device, queue, &label, &data))
+}
+
+pub fn upload_texture_data(
+    device: &Device,
+    queue: &Queue,
+    label: &str,
+    data: &texture_loader::TextureData,
+) -> Texture {
+    match data {
+        texture_loader::TextureData::Rgba(levels) => {
+            self::upload_image_levels(device, queue, label, levels)
+        }
+        texture_loader::TextureData::Ktx2(texture) => {
+            self::upload_ktx2_levels(device, queue, label, texture)
+        }
+    }
+}
+
+/* the levels are uploaded as stored, compressed or not */
+pub fn upload_ktx2_levels(
+    device: &Device,
+    queue: &Queue,
+    label: &str,
+    texture: &ktx2::Ktx2Texture,
+) -> Texture {
+    device.create_texture_with_data(
+        queue,
+        &TextureDescriptor {
+            label: Some(label),
+            size: Extent3d {
+                width: texture.width,
+                height: texture.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: texture.levels.len() as u32,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: texture.format.texture_format(),
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        },
+        wgpu::util::TextureDataOrder::LayerMajor,
+        &texture.data(),
+    )
+}
+
+/* uploads a decoded mip chain, level 0 first */
+pub fn upload_image_levels(
+    device: &Device,
+    queue: &Queue,
+    label: &str,
+    levels: &[image::RgbaImage],
+) -> Texture {
+    let (width, height) = levels
+        .first()
+        .map(|image| image.dimensions())
+        .unwrap_or((1, 1));
     let texture = device.create_texture(&TextureDescriptor {
-        label: Some(path.to_str().unwrap()),
-        size: size.clone(),
-        mip_level_count: 1,
+        label: Some(label),
+        size: Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: levels.len().max(1) as u32,
         sample_count: 1,
         dimension: wgpu::TextureDimension::D2,
-        format: surface_conf.format.clone(),
+        format: IMAGE_TEXTURE_FORMAT,
         usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
         view_formats: &[],
     });
-    queue.write_texture(
-        wgpu::TexelCopyTextureInfo {
-            texture: &texture,
-            mip_level: 0,
-            origin: wgpu::Origin3d::ZERO,
-            aspect: wgpu::TextureAspect::All,
-        },
-        &image_load.to_rgba8(),
-        wgpu::TexelCopyBufferLayout {
-            offset: 0,
-            bytes_per_row: Some(4 * image_load.dimensions().0),
-            rows_per_image: Some(image_load.dimensions().1),
-        },
-        size,
-    );
-    Ok(texture)
+    levels.iter().enumerate().for_each(|(level, image)| {
+        let (level_width, level_height) = image.dimensions();
+        let bytes_per_row = self::padded_bytes_per_row(level_width, 4);
+        queue.write_texture(
+            wgpu::TexelCopyTextureInfo {
+                texture: &texture,
+                mip_level: level as u32,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            &self::pad_rows(image, 4 * level_width, bytes_per_row),
+            wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(bytes_per_row),
+                rows_per_image: Some(level_height),
+            },
+            Extent3d {
+                width: level_width,
+                height: level_height,
+                depth_or_array_layers: 1,
+            },
+        );
+    });
+    texture
+}
+
+pub fn write_image_from_path_with_view(
+    device: &Device,
+    queue: &Queue,
+    path: PathBuf,
+    generate_mipmaps: bool,
+) -> Result<(Texture, TextureView)> {
+    let texture = self::write_image_from_path(device, queue, path, generate_mipmaps)?;
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    Ok((texture, view))
+}
+
+/* this should be called in the init application state, single sampled without mips for the ui */
+pub fn write_image_from_path_msaa_off(
+    device: &Device,
+    queue: &Queue,
+    path: PathBuf,
+) -> Result<(Texture, TextureView)> {
+    self::write_image_from_path_with_view(device, queue, path, false)
+}
+
+/* same as write_image_from_path_msaa_off for encoded bytes, used for assets embedded in the binary */
+pub fn write_image_from_memory_msaa_off(
+    device: &Device,
+    queue: &Queue,
+    label: &str,
+    bytes: &[u8],
+) -> Result<(Texture, TextureView)> {
+    let data = texture_loader::decode(
+        texture_loader::TextureSource::Memory(bytes.to_vec()),
+        false,
+        device.features(),
+    )?;
+    let texture = self::upload_texture_data(device, queue, label, &data);
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    Ok((texture, view))
+}
+
+/* a device without a surface for the tests that need the gpu, none on machines without any
+adapter so those tests pass vacuously */
+#[cfg(test)]
+pub fn headless_device() -> Option<(Device, Queue)> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+    let adapter =
+        pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
+            .ok()?;
+    pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default())).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lost_and_outdated_surfaces_are_reconfigured() {
+        let mut tracker = SurfaceFailureTracker::default();
+        assert_eq!(
+            tracker.on_error(&SurfaceError::Lost),
+            SurfaceRecovery::Reconfigure
+        );
+        assert_eq!(
+            tracker.on_error(&SurfaceError::Outdated),
+            SurfaceRecovery::Reconfigure
+        );
+        assert_eq!(
+            tracker.on_error(&SurfaceError::Timeout),
+            SurfaceRecovery::SkipFrame
+        );
+        assert_eq!(
+            tracker.on_error(&SurfaceError::Other),
+            SurfaceRecovery::SkipFrame
+        );
+    }
+
+    #[test]
+    fn out_of_memory_aborts_right_away() {
+        let mut tracker = SurfaceFailureTracker::default();
+        assert!(matches!(
+            tracker.on_error(&SurfaceError::OutOfMemory),
+            SurfaceRecovery::Abort(_)
+        ));
+    }
+
+    #[test]
+    fn endless_failures_abort_and_a_frame_resets_them() {
+        let mut tracker = SurfaceFailureTracker::default();
+        for _ in 1..MAXIMUM_CONSECUTIVE_SURFACE_FAILURES {
+            assert_eq!(
+                tracker.on_error(&SurfaceError::Timeout),
+                SurfaceRecovery::SkipFrame
+            );
+        }
+        tracker.on_success();
+        for _ in 1..MAXIMUM_CONSECUTIVE_SURFACE_FAILURES {
+            assert_eq!(
+                tracker.on_error(&SurfaceError::Outdated),
+                SurfaceRecovery::Reconfigure
+            );
+        }
+        let SurfaceRecovery::Abort(reason) = tracker.on_error(&SurfaceError::Outdated) else {
+            panic!("the tracker kept reconfiguring");
+        };
+        assert!(reason.contains(&MAXIMUM_CONSECUTIVE_SURFACE_FAILURES.to_string()));
+    }
+
+    #[test]
+    fn rows_are_padded_to_the_next_alignment() {
+        let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        assert_eq!(padded_bytes_per_row(1, 4), alignment);
+        assert_eq!(padded_bytes_per_row(64, 4), alignment);
+        assert_eq!(padded_bytes_per_row(65, 4), 2 * alignment);
+        assert_eq!(padded_bytes_per_row(100, 3), 2 * alignment);
+        assert_eq!(padded_bytes_per_row(0, 4), 0);
+    }
+
+    #[test]
+    fn padding_keeps_each_row_at_its_start() {
+        let data: Vec<u8> = (0..12).collect();
+        let padded = pad_rows(&data, 6, 8);
+        assert_eq!(padded, [0, 1, 2, 3, 4, 5, 0, 0, 6, 7, 8, 9, 10, 11, 0, 0]);
+        assert_eq!(pad_rows(&data, 6, 6), data);
+    }
+
+    /* regression: images were uploaded in the surface format, on a Bgra8UnormSrgb surface the
+    decoded rgba bytes came out with red and blue swapped */
+    #[test]
+    fn images_are_uploaded_as_rgba_whatever_the_surface() {
+        assert_eq!(IMAGE_TEXTURE_FORMAT, wgpu::TextureFormat::Rgba8UnormSrgb);
+        let image = image::RgbaImage::from_pixel(1, 1, image::Rgba([255, 0, 0, 255]));
+        assert_eq!(image.as_raw()[..3], [255, 0, 0]);
+    }
 }
@@ -0,0 +1,94 @@
+use imgui::Condition;
+use wgpu::SurfaceConfiguration;
+
+use crate::graphics::{self, PhysicalAdapterProperty};
+
+use crate::localization;
+
+pub const ABOUT_WINDOW_TITLE: &str = "About SpaceS";
+pub const ABOUT_WINDOW_SIZE: [f32; 2] = [480., 260.];
+/* the limits are too long to read, the rest of the adapter log is repeated */
+const ADAPTER_PROPERTIES: [PhysicalAdapterProperty; 4] = [
+    PhysicalAdapterProperty::Vendor,
+    PhysicalAdapterProperty::DeviceType,
+    PhysicalAdapterProperty::Backend,
+    PhysicalAdapterProperty::Features,
+];
+
+#[derive(Debug, Default)]
+pub struct AboutWindow {
+    pub open: bool,
+}
+
+impl AboutWindow {
+    pub fn draw_menu_item(&mut self, ui: &imgui::Ui) {
+        if ui.menu_item(localization::tr("menu-about")) {
+            self.open = true;
+        }
+    }
+
+    pub fn draw(
+        &mut self,
+        ui: &imgui::Ui,
+        adapter: &wgpu::Adapter,
+        surface_configuration: &SurfaceConfiguration,
+        language: &str,
+    ) {
+        if !self.open {
+            return;
+        }
+        let mut open = true;
+        ui.window(localization::title("window-about", ABOUT_WINDOW_TITLE))
+            .opened(&mut open)
+            .size(ABOUT_WINDOW_SIZE, Condition::FirstUseEver)
+            .build(|| {
+                let diagnostics = self::diagnostics(adapter, surface_configuration, language);
+                if ui.button("Copy diagnostics") {
+                    ui.set_clipboard_text(&diagnostics);
+                }
+                ui.separator();
+                let _wrap = ui.push_text_wrap_pos();
+                diagnostics.lines().for_each(|line| ui.text(line));
+            });
+        self.open = open;
+    }
+}
+
+/* plain text for bug reports, one property per line */
+pub fn diagnostics(
+    adapter: &wgpu::Adapter,
+    surface_configuration: &SurfaceConfiguration,
+    language: &str,
+) -> String {
+    let info = adapter.get_info();
+    let mut lines = vec![
+        format!(
+            "SpaceS {} ({})",
+            env!("CARGO_PKG_VERSION"),
+            env!("SPACES_GIT_HASH")
+        ),
+        format!("Adapter: {}", info.name),
+        format!(
+            "Driver: {}",
+            [info.driver.as_str(), info.driver_info.as_str()]
+                .into_iter()
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ")
+        ),
+    ];
+    lines.extend(ADAPTER_PROPERTIES.into_iter().map(|property| {
+        let label = property.to_string();
+        format!(
+            "{}: {}",
+            label,
+            graphics::adapter_property_value(adapter, property)
+        )
+    }));
+    lines.extend([
+        format!("Surface format: {:?}", surface_configuration.format),
+        format!("Present mode: {:?}", surface_configuration.present_mode),
+        format!("Language: {}", language),
+    ]);
+    lines.join("\n")
+}
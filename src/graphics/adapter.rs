@@ -0,0 +1,202 @@
+use wgpu::{AdapterInfo, DeviceType};
+
+/* SPACES_GPU, an index into the adapter list printed on startup or part of an adapter name */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AdapterSelector {
+    Index(usize),
+    /* case insensitive substring */
+    Name(String),
+}
+
+impl AdapterSelector {
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.is_empty() {
+            return None;
+        }
+        Some(match value.parse::<usize>() {
+            Ok(index) => AdapterSelector::Index(index),
+            Err(_) => AdapterSelector::Name(value.to_lowercase()),
+        })
+    }
+
+    fn matches(&self, index: usize, info: &AdapterInfo) -> bool {
+        match self {
+            AdapterSelector::Index(selected) => *selected == index,
+            AdapterSelector::Name(name) => info.name.to_lowercase().contains(name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum_macros::Display)]
+pub enum AdapterChoiceReason {
+    #[strum(to_string = "selected by SPACES_GPU")]
+    Selected,
+    /* nothing was selected, or the selection matched no usable adapter */
+    #[strum(to_string = "best device type")]
+    Scored,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdapterChoice {
+    /* into the candidates passed to choose_adapter */
+    pub index: usize,
+    pub reason: AdapterChoiceReason,
+}
+
+/* discrete > integrated > virtual > cpu, unknown types rank between virtual and cpu */
+pub fn device_type_score(device_type: DeviceType) -> u32 {
+    match device_type {
+        DeviceType::DiscreteGpu => 4,
+        DeviceType::IntegratedGpu => 3,
+        DeviceType::VirtualGpu => 2,
+        DeviceType::Other => 1,
+        DeviceType::Cpu => 0,
+    }
+}
+
+/* candidates are every enumerated adapter with whether it can present to the surface, the
+incompatible ones are never chosen. ties keep the enumeration order */
+pub fn choose_adapter(
+    candidates: &[(AdapterInfo, bool)],
+    selector: Option<&AdapterSelector>,
+) -> Option<AdapterChoice> {
+    let selected = selector.and_then(|selector| {
+        candidates
+            .iter()
+            .enumerate()
+            .find(|(index, (info, compatible))| *compatible && selector.matches(*index, info))
+    });
+    if let Some((index, _)) = selected {
+        return Some(AdapterChoice {
+            index,
+            reason: AdapterChoiceReason::Selected,
+        });
+    }
+    candidates
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, compatible))| *compatible)
+        .rev()
+        .max_by_key(|(_, (info, _))| self::device_type_score(info.device_type))
+        .map(|(index, _)| AdapterChoice {
+            index,
+            reason: AdapterChoiceReason::Scored,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(name: &str, device_type: DeviceType) -> AdapterInfo {
+        AdapterInfo {
+            name: name.to_string(),
+            vendor: 0,
+            device: 0,
+            device_type,
+            driver: String::new(),
+            driver_info: String::new(),
+            backend: wgpu::Backend::Vulkan,
+        }
+    }
+
+    fn candidates() -> Vec<(AdapterInfo, bool)> {
+        vec![
+            (self::info("llvmpipe (LLVM 19.1.0)", DeviceType::Cpu), true),
+            (
+                self::info("Intel(R) UHD Graphics 630", DeviceType::IntegratedGpu),
+                true,
+            ),
+            (
+                self::info("NVIDIA GeForce RTX 3070", DeviceType::DiscreteGpu),
+                false,
+            ),
+            (
+                self::info("AMD Radeon RX 6600", DeviceType::DiscreteGpu),
+                true,
+            ),
+            (
+                self::info("AMD Radeon RX 7900", DeviceType::DiscreteGpu),
+                true,
+            ),
+        ]
+    }
+
+    #[test]
+    fn selectors_parse_indices_and_names() {
+        assert_eq!(
+            AdapterSelector::parse(" 2 "),
+            Some(AdapterSelector::Index(2))
+        );
+        assert_eq!(
+            AdapterSelector::parse("Radeon"),
+            Some(AdapterSelector::Name("radeon".to_string()))
+        );
+        assert_eq!(AdapterSelector::parse("  "), None);
+    }
+
+    #[test]
+    fn best_compatible_device_type_wins_in_enumeration_order() {
+        let choice = choose_adapter(&self::candidates(), None).unwrap();
+        assert_eq!(choice.index, 3);
+        assert_eq!(choice.reason, AdapterChoiceReason::Scored);
+        let integrated_and_cpu = &self::candidates()[..2];
+        assert_eq!(choose_adapter(integrated_and_cpu, None).unwrap().index, 1);
+    }
+
+    #[test]
+    fn selection_by_index_or_name() {
+        let candidates = self::candidates();
+        let selected =
+            |value: &str| choose_adapter(&candidates, AdapterSelector::parse(value).as_ref());
+        assert_eq!(
+            selected("0"),
+            Some(AdapterChoice {
+                index: 0,
+                reason: AdapterChoiceReason::Selected,
+            })
+        );
+        assert_eq!(selected("intel").unwrap().index, 1);
+        /* the first match of a name, here both radeons */
+        assert_eq!(selected("RADEON").unwrap().index, 3);
+        assert_eq!(selected("rx 7900").unwrap().index, 4);
+    }
+
+    #[test]
+    fn unusable_selection_falls_back_to_the_score() {
+        let candidates = self::candidates();
+        for value in ["2", "geforce", "99", "matrox"] {
+            let choice = choose_adapter(&candidates, AdapterSelector::parse(value).as_ref());
+            assert_eq!(
+                choice,
+                Some(AdapterChoice {
+                    index: 3,
+                    reason: AdapterChoiceReason::Scored,
+                }),
+                "{}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn no_compatible_adapter_is_none() {
+        let candidates = vec![(self::info("NVIDIA", DeviceType::DiscreteGpu), false)];
+        assert_eq!(choose_adapter(&candidates, None), None);
+        assert_eq!(choose_adapter(&[], None), None);
+    }
+
+    #[test]
+    fn device_types_are_ranked() {
+        let ranked = [
+            DeviceType::DiscreteGpu,
+            DeviceType::IntegratedGpu,
+            DeviceType::VirtualGpu,
+            DeviceType::Other,
+            DeviceType::Cpu,
+        ]
+        .map(device_type_score);
+        assert!(ranked.windows(2).all(|pair| pair[0] > pair[1]));
+    }
+}
@@ -0,0 +1,242 @@
+use bytemuck::{Pod, Zeroable};
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+use wgpu::{Device, Queue, TextureFormat};
+
+use crate::{
+    graphics::{
+        camera::Camera,
+        pipeline::{BODY_RADIUS_RENDER_SCALE, BodyShading},
+        shaders,
+        stats::DrawCounts,
+        targets::{DEPTH_COMPARE, DEPTH_FORMAT},
+    },
+    simulation::SimulationBody,
+};
+
+pub const DEFAULT_MINIMUM_BODY_PIXELS: f32 = 2.;
+pub const MAXIMUM_MINIMUM_BODY_PIXELS: f32 = 16.;
+/* the dot fades in while the projected radius shrinks from this multiple of the minimum down to
+the minimum, a pure function of the size so nothing pops at the boundary */
+pub const BILLBOARD_BLEND_RANGE: f64 = 2.;
+/* stars keep a wider glow than the planets */
+pub const STAR_HALO_SCALE: f32 = 2.5;
+pub const INITIAL_BILLBOARD_CAPACITY: usize = 16;
+/* matches the body shader so the dot is as bright as the sphere it replaces */
+const AMBIENT: f32 = 0.15;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct BillboardSettings {
+    /* radius in viewport pixels bodies never shrink below, 0 lets them vanish */
+    #[serde(rename = "minimum pixels")]
+    pub minimum_pixels: f32,
+}
+
+impl Default for BillboardSettings {
+    fn default() -> Self {
+        Self {
+            minimum_pixels: DEFAULT_MINIMUM_BODY_PIXELS,
+        }
+    }
+}
+
+impl BillboardSettings {
+    /* smallest radius the body is drawn with, also what picking tests against */
+    pub fn visual_pixels(&self, body: &SimulationBody) -> f32 {
+        match body.appearance.emissive {
+            true => self.minimum_pixels * STAR_HALO_SCALE,
+            false => self.minimum_pixels,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct BillboardInstance {
+    /* relative to the eye */
+    pub center: [f32; 3],
+    /* in viewport pixels */
+    pub radius: f32,
+    /* alpha is the blend weight, 0 outside the blend range */
+    pub color: [f32; 4],
+}
+
+impl BillboardInstance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32, 2 => Float32x4];
+
+    /* none while the sphere is large enough on its own. the dot never gets smaller than the
+    sphere, whose depth hides the part of the dot it covers */
+    pub fn from_body(
+        camera: &Camera,
+        body: &SimulationBody,
+        color: [f32; 4],
+        shading: BodyShading,
+        settings: &BillboardSettings,
+    ) -> Option<Self> {
+        let projected = self::projected_radius(camera, body)?;
+        let weight = self::billboard_weight(projected, settings.minimum_pixels as f64);
+        if weight <= 0. {
+            return None;
+        }
+        let relative = body.position - camera.position();
+        let brightness = match shading {
+            BodyShading::Unlit { emission } => emission,
+            /* lit fraction of the disk seen from the camera */
+            BodyShading::Lit { light_direction } => {
+                let view_direction = (-relative).as_vec3().normalize_or(Vec3::Z);
+                let phase = (1. + view_direction.dot(light_direction)) * 0.5;
+                AMBIENT + (1. - AMBIENT) * phase
+            }
+        };
+        Some(Self {
+            center: relative.as_vec3().to_array(),
+            radius: settings.visual_pixels(body).max(projected as f32),
+            color: [
+                color[0] * brightness,
+                color[1] * brightness,
+                color[2] * brightness,
+                weight,
+            ],
+        })
+    }
+}
+
+/* radius of the rendered sphere in viewport pixels, none at the eye */
+pub fn projected_radius(camera: &Camera, body: &SimulationBody) -> Option<f64> {
+    let distance = (body.position - camera.position()).length();
+    if distance <= 0. {
+        return None;
+    }
+    let focal_length =
+        camera.viewport_size()[1] as f64 * 0.5 / (camera.field_of_view() as f64 * 0.5).tan();
+    Some(body.radius * BODY_RADIUS_RENDER_SCALE * focal_length / distance)
+}
+
+/* 1 at or below the minimum, 0 from BILLBOARD_BLEND_RANGE times the minimum on */
+pub fn billboard_weight(projected_radius: f64, minimum_pixels: f64) -> f32 {
+    if minimum_pixels <= 0. {
+        return 0.;
+    }
+    let range = minimum_pixels * (BILLBOARD_BLEND_RANGE - 1.);
+    let t = ((projected_radius - minimum_pixels) / range).clamp(0., 1.);
+    (1. - t * t * (3. - 2. * t)) as f32
+}
+
+pub struct BillboardPipeline {
+    pub render_pipeline: wgpu::RenderPipeline,
+    pub instance_buffer: wgpu::Buffer,
+    pub instance_capacity: usize,
+    pub instance_count: u32,
+}
+
+impl BillboardPipeline {
+    pub fn new(
+        device: &Device,
+        color_format: TextureFormat,
+        sample_count: u32,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let shader = shaders::BILLBOARD_SHADER.module(device);
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("billboard pipeline layout"),
+            bind_group_layouts: &[camera_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("billboard pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<BillboardInstance>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &BillboardInstance::ATTRIBUTES,
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: color_format,
+                    blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            /* at the depth of the body center, behind the front of its own sphere */
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: DEPTH_COMPARE,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            render_pipeline,
+            instance_buffer: self::create_instance_buffer(device, INITIAL_BILLBOARD_CAPACITY),
+            instance_capacity: INITIAL_BILLBOARD_CAPACITY,
+            instance_count: 0,
+        }
+    }
+
+    /* same growth policy as the body instances */
+    pub fn write_instances(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        instances: &[BillboardInstance],
+    ) {
+        if instances.len() > self.instance_capacity {
+            self.instance_capacity = instances.len().max(self.instance_capacity * 2);
+            self.instance_buffer = self::create_instance_buffer(device, self.instance_capacity);
+        }
+        queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(instances));
+        self.instance_count = instances.len() as u32;
+    }
+
+    pub fn draw(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        camera_bind_group: &wgpu::BindGroup,
+    ) {
+        if self.instance_count == 0 {
+            return;
+        }
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+        render_pass.draw(0..4, 0..self.instance_count);
+    }
+
+    pub fn draw_counts(&self) -> DrawCounts {
+        match self.instance_count {
+            0 => DrawCounts::default(),
+            billboards => DrawCounts::new(1, billboards),
+        }
+    }
+}
+
+fn create_instance_buffer(device: &Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("billboard instance buffer"),
+        size: (capacity * std::mem::size_of::<BillboardInstance>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
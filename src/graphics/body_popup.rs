@@ -0,0 +1,141 @@
+use std::time::{Duration, Instant};
+
+use crate::{
+    graphics::camera::Camera,
+    localization::{self, Quantity},
+    simulation::{BodyId, SimulationBody, thread::SimulationSnapshot},
+};
+
+/* the cursor rests this long on a body before its tooltip shows, so it does not flicker while
+the mouse passes over the scene */
+pub const BODY_TOOLTIP_DELAY: Duration = Duration::from_millis(500);
+const BODY_MENU_POPUP: &str = "##body menu";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyMenuAction {
+    /* frames the body once, the camera stays free */
+    Focus(BodyId),
+    Follow(BodyId),
+    /* selects the body, which opens the inspector */
+    Edit(BodyId),
+    Delete(BodyId),
+}
+
+/* the tooltip of the body under the cursor and the context menu of a right clicked body */
+#[derive(Debug, Default)]
+pub struct BodyPopup {
+    /* under the cursor in the viewport, and since when */
+    hovered: Option<(BodyId, Instant)>,
+    menu_body: Option<BodyId>,
+    /* the popup is opened in the frame after the click */
+    open_menu: bool,
+}
+
+impl BodyPopup {
+    /* none while the cursor is over nothing, over imgui or dragging */
+    pub fn hover(&mut self, body: Option<BodyId>, now: Instant) {
+        if self.hovered.map(|(id, _)| id) != body {
+            self.hovered = body.map(|id| (id, now));
+        }
+    }
+
+    pub fn open_menu(&mut self, body: BodyId) {
+        self.menu_body = Some(body);
+        self.open_menu = true;
+    }
+
+    pub fn draw(
+        &mut self,
+        ui: &imgui::Ui,
+        snapshot: &SimulationSnapshot,
+        camera: &Camera,
+        now: Instant,
+    ) -> Option<BodyMenuAction> {
+        let find = |id| snapshot.bodies.iter().find(|body| body.id == id);
+        if std::mem::take(&mut self.open_menu) {
+            ui.open_popup(BODY_MENU_POPUP);
+        }
+        let mut action = None;
+        let menu_open = ui
+            .begin_popup(BODY_MENU_POPUP)
+            .map(|_popup| {
+                /* the body may be deleted while the menu is open */
+                let Some(body) = self.menu_body.and_then(find) else {
+                    ui.close_current_popup();
+                    return;
+                };
+                ui.text(&body.id_name);
+                ui.separator();
+                if ui.menu_item(localization::tr("body-menu-focus")) {
+                    action = Some(BodyMenuAction::Focus(body.id));
+                }
+                if ui.menu_item(localization::tr("body-menu-follow")) {
+                    action = Some(BodyMenuAction::Follow(body.id));
+                }
+                if ui.menu_item(localization::tr("body-menu-edit")) {
+                    action = Some(BodyMenuAction::Edit(body.id));
+                }
+                if ui.menu_item(localization::tr("body-menu-delete")) {
+                    action = Some(BodyMenuAction::Delete(body.id));
+                }
+                ui.separator();
+                if ui.menu_item(localization::tr("body-menu-copy-state")) {
+                    ui.set_clipboard_text(self::format_state_vector(
+                        body,
+                        snapshot.simulation_time,
+                    ));
+                }
+            })
+            .is_some();
+
+        if !menu_open
+            && let Some((id, since)) = self.hovered
+            && now.duration_since(since) >= BODY_TOOLTIP_DELAY
+            && let Some(body) = find(id)
+        {
+            let followed = camera
+                .follow_target()
+                .filter(|followed| *followed != id)
+                .and_then(find);
+            let language = localization::active_language();
+            let quantity = |value: f64, quantity: Quantity| {
+                localization::format_quantity(value, quantity, &language)
+            };
+            ui.tooltip(|| {
+                ui.text(&body.id_name);
+                ui.text(format!("Mass: {}", quantity(body.mass, Quantity::Mass)));
+                ui.text(format!(
+                    "Speed: {}",
+                    quantity(body.velocity.length(), Quantity::Speed)
+                ));
+                ui.text(format!(
+                    "Distance to camera: {}",
+                    quantity(camera.position().distance(body.position), Quantity::Length)
+                ));
+                if let Some(followed) = followed {
+                    ui.text(format!(
+                        "Distance to {}: {}",
+                        followed.id_name,
+                        quantity(followed.position.distance(body.position), Quantity::Length)
+                    ));
+                }
+            });
+        }
+        action
+    }
+}
+
+/* one line each, full precision so it can be pasted into a scenario */
+pub fn format_state_vector(body: &SimulationBody, simulation_time: f64) -> String {
+    format!(
+        "{} at t = {} s\nposition: [{:e}, {:e}, {:e}] m\nvelocity: [{:e}, {:e}, {:e}] m/s",
+        body.id_name,
+        simulation_time,
+        body.position.x,
+        body.position.y,
+        body.position.z,
+        body.velocity.x,
+        body.velocity.y,
+        body.velocity.z
+    )
+}
@@ -0,0 +1,275 @@
+use tracing::{info, warn};
+use wgpu::{
+    Device, Queue,
+    naga::{FastHashMap, FastHashSet},
+    util::DeviceExt,
+};
+
+use crate::{
+    graphics::texture_loader::{
+        DecodedTexture, LoadingProgress, TextureData, TextureLoader, TextureSource,
+    },
+    storage::AssetResolver,
+};
+
+enum BodyTexture {
+    /* decoding on the loader thread, drawn with the placeholder */
+    Loading,
+    Ready(wgpu::BindGroup),
+    /* remembered so a broken path is not retried every frame */
+    Failed,
+}
+
+/* sampled bodies go through this bind group (group 1), untextured ones get a white texel */
+pub struct BodyTextureCache {
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub sampler: wgpu::Sampler,
+    /* 1x1 white, used for untextured bodies and as placeholder while a texture decodes */
+    pub fallback_bind_group: wgpu::BindGroup,
+    /* keyed by the scenario path */
+    textures: FastHashMap<String, BodyTexture>,
+    /* without a loader textures are decoded synchronously on first use */
+    loader: Option<TextureLoader>,
+    /* of the running scenario, the keys are the paths as written in it */
+    assets: AssetResolver,
+}
+
+impl BodyTextureCache {
+    pub fn new(device: &Device, queue: &Queue) -> Self {
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("body texture bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        /* longitude wraps around the seam, latitude stops at the poles */
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("body texture sampler"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let white = device.create_texture_with_data(
+            queue,
+            &wgpu::TextureDescriptor {
+                label: Some("body fallback texture"),
+                size: wgpu::Extent3d {
+                    width: 1,
+                    height: 1,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: 1,
+                dimension: wgpu::TextureDimension::D2,
+                format: wgpu::TextureFormat::Rgba8UnormSrgb,
+                usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                view_formats: &[],
+            },
+            wgpu::util::TextureDataOrder::LayerMajor,
+            &[255; 4],
+        );
+        let fallback_bind_group = self::create_bind_group(
+            device,
+            &bind_group_layout,
+            &sampler,
+            &white,
+            "body fallback texture bind group",
+        );
+        Self {
+            bind_group_layout,
+            sampler,
+            fallback_bind_group,
+            textures: FastHashMap::default(),
+            loader: None,
+            assets: AssetResolver::default(),
+        }
+    }
+
+    pub fn set_loader(&mut self, loader: TextureLoader) {
+        self.loader = Some(loader);
+    }
+
+    /* hands the loader thread over to the cache of a rebuilt device, loads still in flight are
+    cancelled and requested again on first use */
+    pub fn take_loader(&mut self) -> Option<TextureLoader> {
+        let mut loader = self.loader.take()?;
+        loader.cancel();
+        Some(loader)
+    }
+
+    pub fn assets(&self) -> &AssetResolver {
+        &self.assets
+    }
+
+    /* a scenario from another directory may mean other files by the same paths, every texture
+    is dropped then */
+    pub fn set_assets(&mut self, assets: AssetResolver) {
+        if assets != self.assets {
+            self.clear();
+            self.assets = assets;
+        }
+    }
+
+    /* starts loading the texture the first time a path is seen, returns whether it can be sampled yet */
+    pub fn resolve(&mut self, device: &Device, queue: &Queue, path: &str) -> bool {
+        if let Some(texture) = self.textures.get(path) {
+            return matches!(texture, BodyTexture::Ready(_));
+        }
+        if let Some(loader) = self.loader.as_mut() {
+            loader.request(
+                path.to_string(),
+                TextureSource::Path(self.assets.resolve(path)),
+                true,
+                device.features(),
+            );
+            self.textures.insert(path.to_string(), BodyTexture::Loading);
+            return false;
+        }
+        let texture = self.upload(
+            device,
+            queue,
+            path,
+            super::texture_loader::decode(
+                TextureSource::Path(self.assets.resolve(path)),
+                true,
+                device.features(),
+            ),
+        );
+        let loaded = matches!(texture, BodyTexture::Ready(_));
+        self.textures.insert(path.to_string(), texture);
+        loaded
+    }
+
+    /* uploads a texture decoded by the loader, results of a cancelled load are dropped */
+    pub fn finish(&mut self, device: &Device, queue: &Queue, decoded: DecodedTexture) {
+        let Some(loader) = self.loader.as_mut() else {
+            return;
+        };
+        if !loader.accept(&decoded) || !self.textures.contains_key(&decoded.key) {
+            return;
+        }
+        let texture = self.upload(
+            device,
+            queue,
+            &decoded.key,
+            decoded.data.map_err(anyhow::Error::msg),
+        );
+        self.textures.insert(decoded.key, texture);
+    }
+
+    /* images built at runtime, like procedural ring bands. the key must not collide with an
+    asset path, existing keys are kept */
+    pub fn insert_generated(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        key: &str,
+        image: impl FnOnce() -> image::RgbaImage,
+    ) {
+        if self.textures.contains_key(key) {
+            return;
+        }
+        let texture = self.upload(device, queue, key, Ok(TextureData::Rgba(vec![image()])));
+        self.textures.insert(key.to_string(), texture);
+    }
+
+    /* drops every body texture, loads still in flight are cancelled */
+    pub fn clear(&mut self) {
+        if let Some(loader) = self.loader.as_mut() {
+            loader.cancel();
+        }
+        self.textures.clear();
+    }
+
+    /* drops the textures of paths not in the set, loads still in flight with them. generated
+    images are dropped as well, their users insert them again */
+    pub fn retain(&mut self, paths: &FastHashSet<String>) {
+        self.textures.retain(|key, _| paths.contains(key));
+    }
+
+    /* for a loading screen, complete when nothing is being decoded */
+    pub fn progress(&self) -> LoadingProgress {
+        self.loader
+            .as_ref()
+            .map(TextureLoader::progress)
+            .unwrap_or_default()
+    }
+
+    pub fn bind_group(&self, path: Option<&str>) -> &wgpu::BindGroup {
+        match path.and_then(|path| self.textures.get(path)) {
+            Some(BodyTexture::Ready(bind_group)) => bind_group,
+            _ => &self.fallback_bind_group,
+        }
+    }
+
+    fn upload(
+        &self,
+        device: &Device,
+        queue: &Queue,
+        path: &str,
+        data: anyhow::Result<TextureData>,
+    ) -> BodyTexture {
+        match data {
+            Ok(data) => {
+                let texture = super::upload_texture_data(device, queue, path, &data);
+                info!("Loaded body texture {} as {:?}", path, texture.format());
+                BodyTexture::Ready(self::create_bind_group(
+                    device,
+                    &self.bind_group_layout,
+                    &self.sampler,
+                    &texture,
+                    path,
+                ))
+            }
+            Err(error) => {
+                warn!(
+                    "Failed to load body texture {} ({:#}), using the flat color",
+                    path, error
+                );
+                BodyTexture::Failed
+            }
+        }
+    }
+}
+
+fn create_bind_group(
+    device: &Device,
+    layout: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+    texture: &wgpu::Texture,
+    label: &str,
+) -> wgpu::BindGroup {
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some(label),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+        ],
+    })
+}
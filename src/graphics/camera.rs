@@ -0,0 +1,722 @@
+use bytemuck::{Pod, Zeroable};
+use glam::{DVec3, Mat4, Vec3};
+use serde::{Deserialize, Serialize};
+use wgpu::{Device, Queue, util::DeviceExt};
+use winit::{event::MouseButton, keyboard::KeyCode};
+
+use tracing::info;
+
+use crate::{
+    graphics::pipeline::BODY_RADIUS_RENDER_SCALE,
+    input::InputState,
+    platform::gamepad::GamepadAxes,
+    simulation::{BodyId, SimulationBody},
+};
+
+pub const DEFAULT_FIELD_OF_VIEW: f32 = 45f32.to_radians();
+/* initial view looks down onto the ecliptic plane from this elevation */
+pub const DEFAULT_FRAMING_ELEVATION: f32 = 35f32.to_radians();
+pub const FRAMING_MARGIN: f32 = 1.15;
+pub const MANUAL_OUTPUT_GAMMA: f32 = 2.2;
+pub const ORBIT_RADIANS_PER_PIXEL: f32 = 0.005;
+/* one scroll line changes the distance by e^ZOOM_PER_SCROLL_LINE */
+pub const ZOOM_PER_SCROLL_LINE: f32 = 0.15;
+pub const MINIMUM_ORBIT_DISTANCE: f32 = 1.;
+/* keeps the view direction away from the up vector so look_at never flips */
+pub const MAXIMUM_ELEVATION: f32 = std::f32::consts::FRAC_PI_2 - 0.01;
+/* the near plane follows the orbit distance, there is no far plane (reversed-z, see targets) */
+pub const NEAR_PLANE_FRACTION: f32 = 1e-3;
+/* at full deflection and a sensitivity of one */
+pub const GAMEPAD_ORBIT_RADIANS_PER_SECOND: f32 = 2.;
+pub const GAMEPAD_PAN_PIXELS_PER_SECOND: f32 = 800.;
+pub const GAMEPAD_ZOOM_LINES_PER_SECOND: f32 = 6.;
+
+/* orbit camera, the position is derived from the focus point and a spherical offset.
+the focus point stays in f64 world space, rendering happens relative to the eye */
+#[derive(Debug, Clone)]
+pub struct Camera {
+    target: DVec3,
+    distance: f32,
+    azimuth: f32,
+    elevation: f32,
+    up: Vec3,
+    field_of_view: f32,
+    near: f32,
+    aspect_ratio: f32,
+    viewport_size: [f32; 2],
+    follow_target: Option<BodyId>,
+    /* user pan relative to the followed body */
+    follow_offset: DVec3,
+    dirty: bool,
+}
+
+/* what a quicksave keeps of the camera, the projection follows the viewport */
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct CameraPose {
+    #[serde(rename = "target")]
+    pub target: DVec3,
+    #[serde(rename = "distance")]
+    pub distance: f32,
+    #[serde(rename = "azimuth")]
+    pub azimuth: f32,
+    #[serde(rename = "elevation")]
+    pub elevation: f32,
+    #[serde(rename = "follow", default, skip_serializing_if = "Option::is_none")]
+    pub follow_target: Option<BodyId>,
+    #[serde(rename = "follow offset", default)]
+    pub follow_offset: DVec3,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct CameraUniform {
+    /* eye at the origin, positions are uploaded relative to Camera::position */
+    pub view_projection: [[f32; 4]; 4],
+    pub viewport_size: [f32; 2],
+    /* 1.0 for srgb surfaces, 2.2 when the shaders encode gamma manually */
+    pub output_gamma: f32,
+    pub _padding: f32,
+}
+
+/* gpu side of the camera, consumed as bind group 0 by the scene pipelines */
+pub struct CameraBinding {
+    pub buffer: wgpu::Buffer,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self {
+            target: DVec3::ZERO,
+            distance: 2.,
+            azimuth: 0.,
+            elevation: DEFAULT_FRAMING_ELEVATION,
+            up: Vec3::Y,
+            field_of_view: DEFAULT_FIELD_OF_VIEW,
+            near: 0.01,
+            aspect_ratio: 1.,
+            viewport_size: [1., 1.],
+            follow_target: None,
+            follow_offset: DVec3::ZERO,
+            dirty: true,
+        }
+    }
+}
+
+impl Camera {
+    pub fn new(width: u32, height: u32) -> Self {
+        let mut camera = Self::default();
+        camera.set_viewport_size(width, height);
+        camera
+    }
+
+    /* world position of the eye, kept in f64 so large coordinates do not jitter */
+    pub fn position(&self) -> DVec3 {
+        self.target
+            + (self::orbit_offset(self.azimuth, self.elevation).as_dvec3() * self.distance as f64)
+    }
+
+    pub fn set_look_at(&mut self, position: DVec3, target: DVec3) {
+        let (azimuth, elevation, distance) = self::orbit_angles((position - target).as_vec3());
+        self.target = target;
+        self.azimuth = azimuth;
+        self.elevation = elevation.clamp(-MAXIMUM_ELEVATION, MAXIMUM_ELEVATION);
+        self.distance = distance.max(MINIMUM_ORBIT_DISTANCE);
+        self.dirty = true;
+    }
+
+    pub fn pose(&self) -> CameraPose {
+        CameraPose {
+            target: self.target,
+            distance: self.distance,
+            azimuth: self.azimuth,
+            elevation: self.elevation,
+            follow_target: self.follow_target,
+            follow_offset: self.follow_offset,
+        }
+    }
+
+    /* clamped like orbiting and zooming, a followed body that is gone frees the camera on the
+    next update_follow */
+    pub fn set_pose(&mut self, pose: CameraPose) {
+        self.target = pose.target;
+        self.azimuth = pose.azimuth.rem_euclid(std::f32::consts::TAU);
+        self.elevation = pose.elevation.clamp(-MAXIMUM_ELEVATION, MAXIMUM_ELEVATION);
+        self.distance = pose.distance.max(MINIMUM_ORBIT_DISTANCE);
+        self.follow_target = pose.follow_target;
+        self.follow_offset = pose.follow_offset;
+        self.set_near_plane(self.distance * NEAR_PLANE_FRACTION);
+    }
+
+    pub fn orbit(&mut self, delta_azimuth: f32, delta_elevation: f32) {
+        self.azimuth = (self.azimuth + delta_azimuth).rem_euclid(std::f32::consts::TAU);
+        self.elevation =
+            (self.elevation + delta_elevation).clamp(-MAXIMUM_ELEVATION, MAXIMUM_ELEVATION);
+        self.dirty = true;
+    }
+
+    /* moves the focus point in the view plane so the point under the cursor follows it */
+    pub fn pan(&mut self, delta_x: f32, delta_y: f32, viewport_height: f32) {
+        let world_per_pixel =
+            2. * self.distance * (self.field_of_view * 0.5).tan() / viewport_height.max(1.);
+        let forward = -self::orbit_offset(self.azimuth, self.elevation);
+        let right = forward.cross(self.up).normalize();
+        let up = right.cross(forward);
+        let delta = ((up * delta_y - right * delta_x) * world_per_pixel).as_dvec3();
+        if self.follow_target.is_some() {
+            self.follow_offset += delta;
+        }
+        self.target += delta;
+        self.dirty = true;
+    }
+
+    /* from the eye to the focus point */
+    pub fn distance(&self) -> f32 {
+        self.distance
+    }
+
+    pub fn field_of_view(&self) -> f32 {
+        self.field_of_view
+    }
+
+    /* physical pixels of the viewport texture the camera renders to */
+    pub fn viewport_size(&self) -> [f32; 2] {
+        self.viewport_size
+    }
+
+    pub fn follow_target(&self) -> Option<BodyId> {
+        self.follow_target
+    }
+
+    pub fn set_follow_target(&mut self, target: Option<BodyId>) {
+        self.follow_target = target;
+        self.follow_offset = DVec3::ZERO;
+    }
+
+    /* free camera -> first body -> ... -> last body -> free camera */
+    pub fn cycle_follow_target(&mut self, bodies: &[SimulationBody]) {
+        let next = match self.follow_target {
+            None => bodies.first(),
+            Some(current) => bodies.iter().skip_while(|body| body.id != current).nth(1),
+        };
+        self.set_follow_target(next.map(|body| body.id));
+        match next {
+            Some(body) => info!("Camera follows {}", body.id_name),
+            None => info!("Camera is free"),
+        }
+    }
+
+    /* re-centers the focus on the followed body, must run before the view matrix is built */
+    pub fn update_follow(&mut self, bodies: &[SimulationBody]) {
+        let Some(id) = self.follow_target else {
+            return;
+        };
+        match bodies.iter().find(|body| body.id == id) {
+            Some(body) => {
+                let target = body.position + self.follow_offset;
+                if target != self.target {
+                    self.target = target;
+                    self.dirty = true;
+                }
+            }
+            None => {
+                info!(
+                    "Followed body {:?} no longer exists, falling back to free camera",
+                    id
+                );
+                self.set_follow_target(None);
+            }
+        }
+    }
+
+    /* exponential zoom, positive lines move towards the focus point but never through it */
+    pub fn zoom(&mut self, lines: f32) {
+        self.distance =
+            (self.distance * (-lines * ZOOM_PER_SCROLL_LINE).exp()).max(MINIMUM_ORBIT_DISTANCE);
+        self.set_near_plane(self.distance * NEAR_PLANE_FRACTION);
+    }
+
+    pub fn set_near_plane(&mut self, near: f32) {
+        self.near = near.max(f32::MIN_POSITIVE);
+        self.dirty = true;
+    }
+
+    /* follows the viewport texture, not the window */
+    pub fn set_viewport_size(&mut self, width: u32, height: u32) {
+        self.viewport_size = [width.max(1) as f32, height.max(1) as f32];
+        self.aspect_ratio = self.viewport_size[0] / self.viewport_size[1];
+        self.dirty = true;
+    }
+
+    /* rotation only, the translation is applied in f64 when positions are made eye relative */
+    pub fn view_matrix(&self) -> Mat4 {
+        Mat4::look_to_rh(
+            Vec3::ZERO,
+            -self::orbit_offset(self.azimuth, self.elevation),
+            self.up,
+        )
+    }
+
+    /* reversed-z with an infinite far plane: depth is 1 at the near plane and falls towards 0,
+    which spreads the float precision evenly over astronomical distances */
+    pub fn projection_matrix(&self) -> Mat4 {
+        Mat4::perspective_infinite_reverse_rh(self.field_of_view, self.aspect_ratio, self.near)
+    }
+
+    pub fn view_projection(&self) -> Mat4 {
+        self.projection_matrix() * self.view_matrix()
+    }
+
+    /* places the camera so the whole sphere fits the vertical field of view */
+    pub fn frame_bounding_sphere(&mut self, center: DVec3, radius: f32) {
+        let radius = radius.max(f32::MIN_POSITIVE);
+        let distance = radius * FRAMING_MARGIN / (self.field_of_view * 0.5).sin();
+        let direction = Vec3::new(
+            0.,
+            DEFAULT_FRAMING_ELEVATION.sin(),
+            DEFAULT_FRAMING_ELEVATION.cos(),
+        );
+        self.set_look_at(center + (direction * distance).as_dvec3(), center);
+        self.set_near_plane(distance * NEAR_PLANE_FRACTION);
+    }
+
+    /* like framing, but from the current direction and without following */
+    pub fn focus(&mut self, center: DVec3, radius: f32) {
+        let radius = radius.max(f32::MIN_POSITIVE);
+        let distance = radius * FRAMING_MARGIN / (self.field_of_view * 0.5).sin();
+        let direction = self::orbit_offset(self.azimuth, self.elevation);
+        self.set_follow_target(None);
+        self.set_look_at(center + (direction * distance).as_dvec3(), center);
+        self.set_near_plane(self.distance * NEAR_PLANE_FRACTION);
+    }
+}
+
+/* unit vector from the focus point towards the camera, azimuth 0 looks along -z */
+pub fn orbit_offset(azimuth: f32, elevation: f32) -> Vec3 {
+    Vec3::new(
+        elevation.cos() * azimuth.sin(),
+        elevation.sin(),
+        elevation.cos() * azimuth.cos(),
+    )
+}
+
+/* inverse of orbit_offset, returns (azimuth, elevation, distance) */
+pub fn orbit_angles(offset: Vec3) -> (f32, f32, f32) {
+    let distance = offset.length();
+    if distance == 0. {
+        return (0., 0., 0.);
+    }
+    let elevation = (offset.y / distance).clamp(-1., 1.).asin();
+    let azimuth = offset.x.atan2(offset.z).rem_euclid(std::f32::consts::TAU);
+    (azimuth, elevation, distance)
+}
+
+/* cursor travel below which a left press and release counts as a click instead of an orbit */
+pub const CLICK_DRAG_THRESHOLD: f32 = 4.;
+/* orbit, pan and zoom slow down to this while shift is held, for fine framing */
+pub const PRECISE_CONTROL_FACTOR: f32 = 0.2;
+
+/* a press and release that did not turn into an orbit or a pan */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewportClick {
+    pub button: MouseButton,
+    /* physical window pixels */
+    pub cursor: (f32, f32),
+}
+
+/* mouse controls for the orbit (left), pan (middle/right) and zoom (wheel) */
+#[derive(Debug, Default)]
+pub struct CameraController {
+    /* travel since the button went down, None while it is up */
+    left_drag: Option<f32>,
+    right_drag: Option<f32>,
+}
+
+impl CameraController {
+    /* once per frame with what the scene got of the mouse. returns a left or right click that
+    did not turn into an orbit or a pan */
+    pub fn update(
+        &mut self,
+        camera: &mut Camera,
+        input: &InputState,
+        viewport_height: f32,
+    ) -> Option<ViewportClick> {
+        let delta = input.cursor_delta();
+        for (button, drag) in [
+            (MouseButton::Left, &mut self.left_drag),
+            (MouseButton::Right, &mut self.right_drag),
+        ] {
+            if input.was_pressed(button) {
+                *drag = Some(0.);
+            }
+            if let Some(travel) = drag.as_mut() {
+                *travel += delta.length();
+            }
+        }
+        let factor =
+            match input.is_key_held(KeyCode::ShiftLeft) || input.is_key_held(KeyCode::ShiftRight) {
+                true => PRECISE_CONTROL_FACTOR,
+                false => 1.,
+            };
+        if delta != glam::Vec2::ZERO {
+            if input.is_button_held(MouseButton::Left) {
+                camera.orbit(
+                    -delta.x * ORBIT_RADIANS_PER_PIXEL * factor,
+                    delta.y * ORBIT_RADIANS_PER_PIXEL * factor,
+                );
+            } else if input.is_button_held(MouseButton::Middle)
+                || input.is_button_held(MouseButton::Right)
+            {
+                camera.pan(delta.x * factor, delta.y * factor, viewport_height);
+            }
+        }
+        if input.scroll_lines() != 0. {
+            camera.zoom(input.scroll_lines() * factor);
+        }
+        let mut click = None;
+        for (button, drag) in [
+            (MouseButton::Left, &mut self.left_drag),
+            (MouseButton::Right, &mut self.right_drag),
+        ] {
+            if input.is_button_held(button) {
+                continue;
+            }
+            let drag = drag.take();
+            if input.was_released(button)
+                && drag.is_some_and(|travel| travel < CLICK_DRAG_THRESHOLD)
+            {
+                click = click.or(input.cursor().map(|cursor| ViewportClick {
+                    button,
+                    cursor: cursor.into(),
+                }));
+            }
+        }
+        click
+    }
+}
+
+/* the left stick orbits like a left drag, the right one pans, the right trigger zooms in and the
+left one out. the axes are past the deadzone, elapsed is the frame time in seconds */
+pub fn apply_gamepad(
+    camera: &mut Camera,
+    axes: GamepadAxes,
+    elapsed: f32,
+    sensitivity: f32,
+    viewport_height: f32,
+) {
+    let scale = elapsed * sensitivity;
+    let orbit = axes.left_stick * GAMEPAD_ORBIT_RADIANS_PER_SECOND * scale;
+    if orbit != glam::Vec2::ZERO {
+        camera.orbit(-orbit.x, orbit.y);
+    }
+    let pan = axes.right_stick * GAMEPAD_PAN_PIXELS_PER_SECOND * scale;
+    if pan != glam::Vec2::ZERO {
+        camera.pan(pan.x, pan.y, viewport_height);
+    }
+    let zoom = (axes.right_trigger - axes.left_trigger) * GAMEPAD_ZOOM_LINES_PER_SECOND * scale;
+    if zoom != 0. {
+        camera.zoom(zoom);
+    }
+}
+
+/* sphere centered on the axis aligned bounds of all bodies, enclosing their inflated radii */
+pub fn bounding_sphere(bodies: &[SimulationBody]) -> (DVec3, f32) {
+    if bodies.is_empty() {
+        return (DVec3::ZERO, 1.);
+    }
+    let (minimum, maximum) = bodies.iter().fold(
+        (DVec3::splat(f64::MAX), DVec3::splat(f64::MIN)),
+        |(minimum, maximum), body| (minimum.min(body.position), maximum.max(body.position)),
+    );
+    let center = (minimum + maximum) * 0.5;
+    let radius = bodies
+        .iter()
+        .map(|body| body.position.distance(center) + body.radius * BODY_RADIUS_RENDER_SCALE)
+        .fold(0., f64::max);
+    (center, radius as f32)
+}
+
+impl CameraUniform {
+    pub fn new(camera: &Camera, manual_gamma_correction: bool) -> Self {
+        Self {
+            view_projection: camera.view_projection().to_cols_array_2d(),
+            viewport_size: camera.viewport_size,
+            output_gamma: if manual_gamma_correction {
+                MANUAL_OUTPUT_GAMMA
+            } else {
+                1.
+            },
+            _padding: 0.,
+        }
+    }
+}
+
+impl CameraBinding {
+    pub fn new(device: &Device, camera: &Camera, manual_gamma_correction: bool) -> Self {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("camera uniform buffer"),
+            contents: bytemuck::bytes_of(&CameraUniform::new(camera, manual_gamma_correction)),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("camera bind group layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("camera bind group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+        });
+        Self {
+            buffer,
+            bind_group_layout,
+            bind_group,
+        }
+    }
+
+    /* only touches the gpu when the camera changed since the last upload */
+    pub fn update(&self, queue: &Queue, camera: &mut Camera, manual_gamma_correction: bool) {
+        if !camera.dirty {
+            return;
+        }
+        queue.write_buffer(
+            &self.buffer,
+            0,
+            bytemuck::bytes_of(&CameraUniform::new(camera, manual_gamma_correction)),
+        );
+        camera.dirty = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::{
+        dpi::PhysicalPosition,
+        event::{DeviceId, ElementState, WindowEvent},
+    };
+
+    use super::*;
+
+    const EPSILON: f32 = 1e-4;
+
+    /* normalized device coordinates of a world point, relative to the eye like the shaders */
+    fn project(camera: &Camera, point: DVec3) -> Vec3 {
+        let relative = (point - camera.position()).as_vec3();
+        camera.view_projection().project_point3(relative)
+    }
+
+    #[test]
+    fn target_projects_to_the_screen_center() {
+        let mut camera = Camera::new(1920, 1080);
+        let target = DVec3::new(1.496e11, 1e6, -3e9);
+        camera.set_look_at(target + DVec3::new(4e7, 2e7, 1e7), target);
+        let center = self::project(&camera, target);
+        assert!(
+            center.x.abs() < EPSILON && center.y.abs() < EPSILON,
+            "{}",
+            center
+        );
+    }
+
+    #[test]
+    fn depth_is_reversed() {
+        let mut camera = Camera::new(800, 600);
+        camera.set_look_at(DVec3::new(0., 0., 100.), DVec3::ZERO);
+        camera.set_near_plane(1.);
+        let forward = (DVec3::ZERO - camera.position()).normalize();
+        let near = self::project(&camera, camera.position() + forward);
+        let far = self::project(&camera, camera.position() + forward * 1e12);
+        assert!((near.z - 1.).abs() < EPSILON, "{}", near.z);
+        assert!(far.z > 0. && far.z < EPSILON, "{}", far.z);
+    }
+
+    #[test]
+    fn aspect_ratio_follows_the_viewport() {
+        let mut camera = Camera::new(2000, 1000);
+        camera.set_look_at(DVec3::new(0., 0., 10.), DVec3::ZERO);
+        /* a point on the edge of the vertical field of view */
+        let edge = DVec3::new(0., 10. * (camera.field_of_view() * 0.5).tan() as f64, 0.);
+        assert!((self::project(&camera, edge).y - 1.).abs() < EPSILON);
+        let sideways = DVec3::new(edge.y, 0., 0.);
+        assert!((self::project(&camera, sideways).x - 0.5).abs() < EPSILON);
+    }
+
+    #[test]
+    fn framing_fits_the_sphere() {
+        let mut camera = Camera::new(800, 800);
+        camera.frame_bounding_sphere(DVec3::new(5., 0., 0.), 2.);
+        assert!(
+            (camera.position().distance(DVec3::new(5., 0., 0.)) as f32 - camera.distance()).abs()
+                < EPSILON
+        );
+        assert!(camera.distance() * (camera.field_of_view() * 0.5).sin() > 2.);
+    }
+
+    #[test]
+    fn orbit_angles_round_trip() {
+        for azimuth in [0., 0.5, 2., 3.5, 6.] {
+            for elevation in [-1.5, -0.3, 0., 0.7, 1.5] {
+                let offset = orbit_offset(azimuth, elevation) * 7.;
+                let (round_azimuth, round_elevation, distance) = orbit_angles(offset);
+                assert!(
+                    (round_azimuth - azimuth).abs() < EPSILON,
+                    "{} {}",
+                    azimuth,
+                    elevation
+                );
+                assert!((round_elevation - elevation).abs() < EPSILON);
+                assert!((distance - 7.).abs() < EPSILON);
+            }
+        }
+        assert_eq!(orbit_angles(Vec3::ZERO), (0., 0., 0.));
+    }
+
+    #[test]
+    fn orbit_wraps_and_stops_short_of_the_poles() {
+        let mut camera = Camera::default();
+        camera.orbit(std::f32::consts::TAU + 1., 10.);
+        assert!((camera.pose().azimuth - 1.).abs() < EPSILON);
+        assert_eq!(camera.pose().elevation, MAXIMUM_ELEVATION);
+        camera.orbit(0., -20.);
+        assert_eq!(camera.pose().elevation, -MAXIMUM_ELEVATION);
+    }
+
+    #[test]
+    fn zoom_never_passes_the_focus() {
+        let mut camera = Camera::default();
+        camera.set_look_at(DVec3::new(0., 0., 100.), DVec3::ZERO);
+        camera.zoom(1.);
+        assert!((camera.distance() - 100. * (-ZOOM_PER_SCROLL_LINE).exp()).abs() < EPSILON);
+        camera.zoom(1e4);
+        assert_eq!(camera.distance(), MINIMUM_ORBIT_DISTANCE);
+    }
+
+    /* the point under the cursor moves with it, a drag across the whole height pans one view
+    height at the focus distance */
+    #[test]
+    fn pan_moves_in_the_view_plane() {
+        let mut camera = Camera::new(100, 100);
+        camera.set_look_at(DVec3::new(0., 0., 10.), DVec3::ZERO);
+        camera.pan(0., 100., 100.);
+        let height = 2. * 10. * (camera.field_of_view() * 0.5).tan() as f64;
+        assert!(camera.pose().target.distance(DVec3::new(0., height, 0.)) < 1e-4);
+    }
+
+    fn mouse(input: &mut InputState, event: WindowEvent) {
+        input.handle_window_event(&event, false, false);
+    }
+
+    fn press(input: &mut InputState, state: ElementState) {
+        self::mouse(
+            input,
+            WindowEvent::MouseInput {
+                device_id: DeviceId::dummy(),
+                state,
+                button: MouseButton::Left,
+            },
+        );
+    }
+
+    fn move_to(input: &mut InputState, x: f64, y: f64) {
+        self::mouse(
+            input,
+            WindowEvent::CursorMoved {
+                device_id: DeviceId::dummy(),
+                position: PhysicalPosition::new(x, y),
+            },
+        );
+    }
+
+    #[test]
+    fn short_drag_is_a_click_and_a_long_one_orbits() {
+        let (mut camera, mut controller, mut input) = (
+            Camera::new(100, 100),
+            CameraController::default(),
+            InputState::default(),
+        );
+        self::move_to(&mut input, 10., 10.);
+        self::press(&mut input, ElementState::Pressed);
+        self::move_to(&mut input, 11., 10.);
+        self::press(&mut input, ElementState::Released);
+        let click = controller.update(&mut camera, &input, 100.);
+        assert_eq!(
+            click,
+            Some(ViewportClick {
+                button: MouseButton::Left,
+                cursor: (11., 10.)
+            })
+        );
+        input.end_frame();
+        let azimuth = camera.pose().azimuth;
+        self::press(&mut input, ElementState::Pressed);
+        controller.update(&mut camera, &input, 100.);
+        input.end_frame();
+        self::move_to(&mut input, 61., 10.);
+        assert_eq!(controller.update(&mut camera, &input, 100.), None);
+        input.end_frame();
+        self::press(&mut input, ElementState::Released);
+        assert_eq!(controller.update(&mut camera, &input, 100.), None);
+        let expected = (azimuth - 50. * ORBIT_RADIANS_PER_PIXEL).rem_euclid(std::f32::consts::TAU);
+        assert!((camera.pose().azimuth - expected).abs() < EPSILON);
+    }
+
+    /* the earth around the sun, followed after a pan so it sits off center */
+    #[test]
+    fn followed_body_keeps_its_screen_position() {
+        let mut state = crate::simulation::SimulationState::from_objects(
+            &crate::simulation::sun_earth_moon_objects()[..2],
+            3600.,
+        );
+        let earth = state.bodies[1].id;
+        let mut camera = Camera::new(1280, 720);
+        camera.set_look_at(
+            state.bodies[1].position + DVec3::new(0., 3e7, 4e7),
+            state.bodies[1].position,
+        );
+        camera.set_follow_target(Some(earth));
+        camera.pan(200., -100., 720.);
+        camera.update_follow(&state.bodies);
+        let screen =
+            |camera: &Camera, bodies: &[SimulationBody]| self::project(camera, bodies[1].position);
+        let first = screen(&camera, &state.bodies);
+        assert!(first.x.abs() > 0.1 && first.y.abs() > 0.1, "{}", first);
+        for _ in 0..24 * 30 {
+            state.step();
+            camera.update_follow(&state.bodies);
+            let current = screen(&camera, &state.bodies);
+            assert!(
+                current.distance(first) < EPSILON,
+                "{} moved to {}",
+                first,
+                current
+            );
+        }
+        assert!(
+            state.bodies[1]
+                .position
+                .distance(DVec3::new(1.496e11, 0., 0.))
+                > 1e10
+        );
+    }
+
+    #[test]
+    fn follow_ends_with_the_body() {
+        let mut camera = Camera::default();
+        camera.set_follow_target(Some(BodyId(3)));
+        camera.update_follow(&[]);
+        assert_eq!(camera.follow_target(), None);
+    }
+}
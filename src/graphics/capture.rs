@@ -0,0 +1,373 @@
+use std::{
+    collections::VecDeque,
+    io::Cursor,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result, anyhow};
+use tracing::{info, warn};
+use wgpu::{BufferAsyncError, Device, TextureFormat};
+use winit::event_loop::EventLoopProxy;
+
+use crate::{application::ApplicationSimulationEvent, platform, storage};
+
+/* the width of the thumbnail of a save in pixels, the height follows the viewport */
+pub const SAVE_THUMBNAIL_WIDTH: u32 = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CaptureLayout {
+    pub width: u32,
+    pub height: u32,
+    pub bytes_per_pixel: u32,
+    pub padded_bytes_per_row: u32,
+}
+
+impl CaptureLayout {
+    /* texture to buffer copies need every row aligned to COPY_BYTES_PER_ROW_ALIGNMENT */
+    pub fn new(width: u32, height: u32, bytes_per_pixel: u32) -> Self {
+        Self {
+            width,
+            height,
+            bytes_per_pixel,
+            padded_bytes_per_row: super::padded_bytes_per_row(width, bytes_per_pixel),
+        }
+    }
+
+    pub fn unpadded_bytes_per_row(&self) -> u32 {
+        self.width * self.bytes_per_pixel
+    }
+
+    pub fn buffer_size(&self) -> u64 {
+        self.padded_bytes_per_row as u64 * self.height as u64
+    }
+}
+
+/* drops the alignment bytes at the end of every row */
+pub fn strip_row_padding(data: &[u8], layout: &CaptureLayout) -> Vec<u8> {
+    let row = layout.unpadded_bytes_per_row() as usize;
+    data.chunks(layout.padded_bytes_per_row as usize)
+        .take(layout.height as usize)
+        .flat_map(|padded| &padded[..row])
+        .copied()
+        .collect()
+}
+
+/* only 8 bit four channel formats are written, bgra is swizzled after stripping */
+fn is_bgra(format: TextureFormat) -> Option<bool> {
+    match format {
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => Some(false),
+        TextureFormat::Bgra8Unorm | TextureFormat::Bgra8UnormSrgb => Some(true),
+        _ => None,
+    }
+}
+
+/* what a readback is written to */
+#[derive(Debug, Clone, PartialEq)]
+pub enum CaptureTarget {
+    /* the presented frame with the ui, into the screenshots directory */
+    Screenshot,
+    /* the scene without the ui, scaled down to a png next to a save */
+    Thumbnail(PathBuf),
+}
+
+struct PendingCapture {
+    target: CaptureTarget,
+    buffer: wgpu::Buffer,
+    layout: CaptureLayout,
+    bgra: bool,
+    mapped: Option<Receiver<Result<(), BufferAsyncError>>>,
+}
+
+/* copies a presented frame into a readback buffer and writes it out once the gpu is done,
+the render loop never waits on the map. one readback at a time, the others wait for later
+frames */
+#[derive(Default)]
+pub struct ScreenshotCapture {
+    requested: VecDeque<CaptureTarget>,
+    pending: Option<PendingCapture>,
+    /* the ui of a screenshot frame is drawn a second time into this, a surface can rarely be
+    copied from */
+    frame: Option<wgpu::Texture>,
+}
+
+impl ScreenshotCapture {
+    pub fn request(&mut self) {
+        self.requested.push_back(CaptureTarget::Screenshot);
+    }
+
+    /* of the next frame, written to the path once it is read back */
+    pub fn request_thumbnail(&mut self, path: PathBuf) {
+        self.requested.push_back(CaptureTarget::Thumbnail(path));
+    }
+
+    /* where this frame draws its ui once more when a screenshot is due, of the format and size
+    of the surface so the imgui pipeline fits it */
+    pub fn screenshot_view(
+        &mut self,
+        device: &Device,
+        format: TextureFormat,
+        width: u32,
+        height: u32,
+    ) -> Option<wgpu::TextureView> {
+        if self.pending.is_some() || self.requested.front() != Some(&CaptureTarget::Screenshot) {
+            return None;
+        }
+        let frame = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("screenshot frame"),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+        let view = frame.create_view(&wgpu::TextureViewDescriptor::default());
+        self.frame = Some(frame);
+        Some(view)
+    }
+
+    /* records the copy into the frame's encoder, the scene of a thumbnail needs COPY_SRC usage */
+    pub fn encode_copy(
+        &mut self,
+        device: &Device,
+        encoder: &mut wgpu::CommandEncoder,
+        scene: &wgpu::Texture,
+    ) {
+        if self.pending.is_some() {
+            return;
+        }
+        let Some(target) = self.requested.pop_front() else {
+            return;
+        };
+        /* only kept until the copy is recorded, the gpu holds on to it until it is done */
+        let frame = self.frame.take();
+        let (texture, name) = match (&target, frame.as_ref()) {
+            (CaptureTarget::Screenshot, Some(frame)) => (frame, "screenshots"),
+            (CaptureTarget::Screenshot, None) => {
+                warn!("The frame was not drawn for the screenshot, it is skipped");
+                return;
+            }
+            (CaptureTarget::Thumbnail(_), _) => (scene, "save thumbnails"),
+        };
+        if !texture.usage().contains(wgpu::TextureUsages::COPY_SRC) {
+            warn!(
+                "The texture does not support COPY_SRC, {} are unavailable",
+                name
+            );
+            return;
+        }
+        let Some(bgra) = self::is_bgra(texture.format()) else {
+            warn!(
+                "The {} of {:?} textures are not supported",
+                name,
+                texture.format()
+            );
+            return;
+        };
+        let layout = CaptureLayout::new(texture.width(), texture.height(), 4);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("screenshot readback buffer"),
+            size: layout.buffer_size(),
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(layout.padded_bytes_per_row),
+                    rows_per_image: Some(layout.height),
+                },
+            },
+            texture.size(),
+        );
+        self.pending = Some(PendingCapture {
+            target,
+            buffer,
+            layout,
+            bgra,
+            mapped: None,
+        });
+    }
+
+    /* mapping has to wait until the copy is submitted */
+    pub fn after_submit(&mut self) {
+        let Some(pending) = self.pending.as_mut() else {
+            return;
+        };
+        if pending.mapped.is_some() {
+            return;
+        }
+        let (sender, receiver) = mpsc::channel();
+        pending
+            .buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+        pending.mapped = Some(receiver);
+    }
+
+    /* picks up a finished readback, encoding and writing the png happens off the render thread */
+    pub fn poll(
+        &mut self,
+        device: &Device,
+        event_proxy: &EventLoopProxy<ApplicationSimulationEvent>,
+    ) {
+        let Some(receiver) = self
+            .pending
+            .as_ref()
+            .and_then(|pending| pending.mapped.as_ref())
+        else {
+            return;
+        };
+        let _ = device.poll(wgpu::PollType::Poll);
+        let result = match receiver.try_recv() {
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => Err(anyhow!("screenshot readback was dropped")),
+            Ok(result) => result.context("Failed to map the screenshot buffer"),
+        };
+        let pending = self.pending.take().unwrap();
+        if let Err(error) = result {
+            warn!("{error:#}");
+            return;
+        }
+        let mut pixels = self::strip_row_padding(
+            &pending.buffer.slice(..).get_mapped_range(),
+            &pending.layout,
+        );
+        pending.buffer.unmap();
+        if pending.bgra {
+            pixels
+                .chunks_exact_mut(4)
+                .for_each(|pixel| pixel.swap(0, 2));
+        }
+
+        let layout = pending.layout;
+        let event_proxy = event_proxy.clone();
+        std::thread::spawn(move || match pending.target {
+            CaptureTarget::Screenshot => match self::write_png(pixels, &layout) {
+                Ok(path) => {
+                    let _ =
+                        event_proxy.send_event(ApplicationSimulationEvent::ScreenshotSaved(path));
+                }
+                Err(error) => warn!("Failed to save screenshot: {error:#}"),
+            },
+            CaptureTarget::Thumbnail(path) => match self::write_thumbnail(pixels, &layout, &path) {
+                Ok(()) => info!("Wrote the thumbnail {}", path.display()),
+                Err(error) => warn!("Failed to write the thumbnail: {error:#}"),
+            },
+        });
+    }
+}
+
+fn write_png(pixels: Vec<u8>, layout: &CaptureLayout) -> Result<PathBuf> {
+    let image = image::RgbaImage::from_raw(layout.width, layout.height, pixels)
+        .ok_or_else(|| anyhow!("screenshot buffer does not match its dimensions"))?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let path = platform::screenshots_directory()?.join(format!("screenshot-{timestamp}.png"));
+    image
+        .save_with_format(&path, image::ImageFormat::Png)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/* the save list reads it while it may be written, so it is replaced in one rename */
+fn write_thumbnail(pixels: Vec<u8>, layout: &CaptureLayout, path: &Path) -> Result<()> {
+    let image = image::RgbaImage::from_raw(layout.width, layout.height, pixels)
+        .ok_or_else(|| anyhow!("thumbnail buffer does not match its dimensions"))?;
+    let width = SAVE_THUMBNAIL_WIDTH.min(layout.width);
+    let height = (layout.height as u64 * width as u64 / layout.width as u64).max(1) as u32;
+    let thumbnail = image::imageops::thumbnail(&image, width, height);
+    let mut bytes = Cursor::new(Vec::new());
+    thumbnail
+        .write_to(&mut bytes, image::ImageFormat::Png)
+        .context("Failed to encode the thumbnail")?;
+    storage::write_atomically(path, bytes.get_ref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* rows of the pixel index in every byte, followed by padding of 0xff */
+    fn padded_rows(layout: &CaptureLayout) -> Vec<u8> {
+        (0..layout.height)
+            .flat_map(|row| {
+                (0..layout.padded_bytes_per_row).map(move |byte| {
+                    match byte < layout.unpadded_bytes_per_row() {
+                        true => (row * layout.width + byte / layout.bytes_per_pixel) as u8,
+                        false => 0xff,
+                    }
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn rows_are_padded_to_the_copy_alignment() {
+        let layout = CaptureLayout::new(3, 2, 4);
+        assert_eq!(layout.unpadded_bytes_per_row(), 12);
+        assert_eq!(
+            layout.padded_bytes_per_row,
+            wgpu::COPY_BYTES_PER_ROW_ALIGNMENT
+        );
+        assert_eq!(
+            layout.buffer_size(),
+            2 * wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as u64
+        );
+        let aligned = CaptureLayout::new(64, 1, 4);
+        assert_eq!(
+            aligned.padded_bytes_per_row,
+            aligned.unpadded_bytes_per_row()
+        );
+    }
+
+    #[test]
+    fn padding_is_stripped_from_every_row() {
+        let layout = CaptureLayout::new(5, 3, 4);
+        let pixels = strip_row_padding(&self::padded_rows(&layout), &layout);
+        assert_eq!(pixels.len(), (5 * 3 * 4) as usize);
+        let expected: Vec<u8> = (0..15u8).flat_map(|pixel| [pixel; 4]).collect();
+        assert_eq!(pixels, expected);
+    }
+
+    #[test]
+    fn unpadded_rows_stay_as_they_are() {
+        let layout = CaptureLayout::new(64, 2, 4);
+        let data = self::padded_rows(&layout);
+        assert_eq!(strip_row_padding(&data, &layout), data);
+    }
+
+    #[test]
+    fn only_eight_bit_formats_are_written() {
+        assert_eq!(is_bgra(TextureFormat::Bgra8UnormSrgb), Some(true));
+        assert_eq!(is_bgra(TextureFormat::Rgba8Unorm), Some(false));
+        assert_eq!(is_bgra(TextureFormat::Rgba16Float), None);
+    }
+
+    #[test]
+    fn thumbnail_keeps_the_aspect_ratio() {
+        let layout = CaptureLayout::new(1024, 512, 4);
+        let path = std::env::temp_dir().join(format!("thumbnail-{}.png", std::process::id()));
+        write_thumbnail(vec![128; 1024 * 512 * 4], &layout, &path).unwrap();
+        let thumbnail = image::open(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            (thumbnail.width(), thumbnail.height()),
+            (SAVE_THUMBNAIL_WIDTH, 128)
+        );
+    }
+}
@@ -0,0 +1,279 @@
+use std::{collections::VecDeque, f64::consts::PI};
+
+use bytemuck::{Pod, Zeroable};
+use glam::DVec3;
+use wgpu::{Device, Queue, TextureFormat};
+
+use crate::{
+    graphics::{
+        camera::Camera,
+        pipeline::BODY_RADIUS_RENDER_SCALE,
+        shaders,
+        starfield::StarRandom,
+        stats::DrawCounts,
+        targets::{DEPTH_COMPARE, DEPTH_FORMAT},
+    },
+    simulation::{SimulationEvent, thread::SimulationSnapshot},
+};
+
+/* live particles over all bursts, the oldest are dropped first. also the instance capacity */
+pub const MAXIMUM_DEBRIS_PARTICLES: usize = 4096;
+pub const DEBRIS_BURST_PARTICLES: u32 = 256;
+/* wall seconds at the warp of the impact, stored as simulation time so a burst freezes while
+the simulation is paused */
+pub const DEBRIS_LIFETIME: f64 = 2.;
+/* distance the fastest debris covers over its lifetime, in rendered radii of the smaller body */
+pub const DEBRIS_SPREAD: f64 = 2.5;
+/* initial particle diameter, in rendered radii of the smaller body */
+pub const DEBRIS_SIZE: f64 = 0.15;
+/* half angle of the cones around the collision normal the debris is thrown into */
+pub const DEBRIS_CONE_ANGLE: f64 = 0.9;
+/* far above the bloom threshold at first, fading with the particle */
+pub const DEBRIS_COLOR: [f32; 3] = [4., 2.2, 0.9];
+pub const MINIMUM_DEBRIS_PIXEL_SIZE: f32 = 1.5;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct DebrisInstance {
+    /* relative to the eye */
+    pub center: [f32; 3],
+    /* billboard diameter in pixels */
+    pub size: f32,
+    pub color: [f32; 4],
+}
+
+impl DebrisInstance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32, 2 => Float32x4];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct DebrisParticle {
+    origin: DVec3,
+    velocity: DVec3,
+    spawned: f64,
+    lifetime: f64,
+    size: f64,
+}
+
+impl DebrisParticle {
+    /* in [0, 1] while alive */
+    fn age(&self, simulation_time: f64) -> f64 {
+        (simulation_time - self.spawned) / self.lifetime
+    }
+}
+
+/* purely visual, particles move ballistically on the cpu and never reach the simulation.
+their state is a function of the simulation time, so pausing freezes them in place */
+#[derive(Debug, Default)]
+pub struct DebrisSystem {
+    particles: VecDeque<DebrisParticle>,
+    bursts: u64,
+}
+
+impl DebrisSystem {
+    /* ignores every other event and collisions of bodies missing from the snapshot */
+    pub fn spawn_burst(&mut self, snapshot: &SimulationSnapshot, event: &SimulationEvent) {
+        let SimulationEvent::BodiesCollided {
+            first,
+            second,
+            point,
+            normal,
+            ..
+        } = event
+        else {
+            return;
+        };
+        let find = |id| snapshot.bodies.iter().find(|body| body.id == id);
+        let (Some(first), Some(second)) = (find(*first), find(*second)) else {
+            return;
+        };
+        /* debris travels on with the pair instead of being left behind on its orbit */
+        let total_mass = (first.mass + second.mass).max(f64::MIN_POSITIVE);
+        let base_velocity =
+            (first.velocity * first.mass + second.velocity * second.mass) / total_mass;
+        let scale = first.radius.min(second.radius) * BODY_RADIUS_RENDER_SCALE;
+        let lifetime = DEBRIS_LIFETIME * snapshot.warp.max(f64::MIN_POSITIVE);
+        let mut random = StarRandom(self.bursts ^ snapshot.simulation_time.to_bits());
+        self.bursts += 1;
+        (0..DEBRIS_BURST_PARTICLES).for_each(|index| {
+            let side = match index % 2 {
+                0 => *normal,
+                _ => -*normal,
+            };
+            let direction = self::sample_cone(&mut random, side, DEBRIS_CONE_ANGLE);
+            let speed = scale * DEBRIS_SPREAD / lifetime * (0.3 + 0.7 * random.next_f32() as f64);
+            if self.particles.len() == MAXIMUM_DEBRIS_PARTICLES {
+                self.particles.pop_front();
+            }
+            self.particles.push_back(DebrisParticle {
+                origin: *point,
+                velocity: base_velocity + direction * speed,
+                spawned: snapshot.simulation_time,
+                lifetime: lifetime * (0.6 + 0.4 * random.next_f32() as f64),
+                size: scale * DEBRIS_SIZE * (0.5 + random.next_f32() as f64),
+            });
+        });
+    }
+
+    /* drops burnt out particles, and all of them when the simulation time jumped back */
+    pub fn retire(&mut self, simulation_time: f64) {
+        self.particles
+            .retain(|particle| (0. ..1.).contains(&particle.age(simulation_time)));
+    }
+
+    /* shrinking and fading with age, sized in pixels for the current camera */
+    pub fn instances(&self, camera: &Camera, simulation_time: f64) -> Vec<DebrisInstance> {
+        let eye = camera.position();
+        let focal_length =
+            camera.viewport_size()[1] as f64 * 0.5 / (camera.field_of_view() as f64 * 0.5).tan();
+        self.particles
+            .iter()
+            .filter_map(|particle| {
+                let age = particle.age(simulation_time).clamp(0., 1.);
+                let relative = particle.origin
+                    + particle.velocity * (simulation_time - particle.spawned)
+                    - eye;
+                let distance = relative.length();
+                if age >= 1. || distance <= 0. {
+                    return None;
+                }
+                let fade = (1. - age) as f32;
+                let size = (particle.size * (1. - age) * focal_length / distance) as f32;
+                Some(DebrisInstance {
+                    center: relative.as_vec3().to_array(),
+                    size: size.max(MINIMUM_DEBRIS_PIXEL_SIZE),
+                    color: [
+                        DEBRIS_COLOR[0] * fade,
+                        DEBRIS_COLOR[1] * fade * fade,
+                        DEBRIS_COLOR[2] * fade * fade,
+                        fade,
+                    ],
+                })
+            })
+            .collect()
+    }
+}
+
+/* uniform over the spherical cap around the axis */
+fn sample_cone(random: &mut StarRandom, axis: DVec3, half_angle: f64) -> DVec3 {
+    let cos_theta = 1. - random.next_f32() as f64 * (1. - half_angle.cos());
+    let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+    let phi = 2. * PI * random.next_f32() as f64;
+    let (tangent, bitangent) = axis.any_orthonormal_pair();
+    axis * cos_theta + (tangent * phi.cos() + bitangent * phi.sin()) * sin_theta
+}
+
+pub struct DebrisPipeline {
+    pub render_pipeline: wgpu::RenderPipeline,
+    /* allocated once for MAXIMUM_DEBRIS_PARTICLES and rewritten every frame */
+    pub instance_buffer: wgpu::Buffer,
+    pub instance_count: u32,
+}
+
+impl DebrisPipeline {
+    pub fn new(
+        device: &Device,
+        color_format: TextureFormat,
+        sample_count: u32,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let shader = shaders::DEBRIS_SHADER.module(device);
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("debris pipeline layout"),
+            bind_group_layouts: &[camera_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("debris pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<DebrisInstance>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &DebrisInstance::ATTRIBUTES,
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                /* additive like the stars, the order of the particles does not matter */
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: color_format,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::One,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent::REPLACE,
+                    }),
+                    write_mask: wgpu::ColorWrites::COLOR,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            /* hidden behind the bodies, but never hiding anything itself */
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: DEPTH_COMPARE,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            render_pipeline,
+            instance_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("debris instance buffer"),
+                size: (MAXIMUM_DEBRIS_PARTICLES * std::mem::size_of::<DebrisInstance>())
+                    as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            instance_count: 0,
+        }
+    }
+
+    pub fn write_instances(&mut self, queue: &Queue, instances: &[DebrisInstance]) {
+        let instances = &instances[..instances.len().min(MAXIMUM_DEBRIS_PARTICLES)];
+        if !instances.is_empty() {
+            queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(instances));
+        }
+        self.instance_count = instances.len() as u32;
+    }
+
+    pub fn draw(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        camera_bind_group: &wgpu::BindGroup,
+    ) {
+        if self.instance_count == 0 {
+            return;
+        }
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+        render_pass.draw(0..4, 0..self.instance_count);
+    }
+
+    pub fn draw_counts(&self) -> DrawCounts {
+        match self.instance_count {
+            0 => DrawCounts::default(),
+            particles => DrawCounts::new(1, particles),
+        }
+    }
+}
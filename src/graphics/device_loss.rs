@@ -0,0 +1,46 @@
+use std::sync::{
+    Arc,
+    atomic::{AtomicBool, Ordering},
+};
+
+use tracing::{error, warn};
+use wgpu::Device;
+
+/* a lost device is never usable again, the application rebuilds the whole graphics interface.
+the callbacks run on whichever thread polls the device */
+#[derive(Debug, Clone, Default)]
+pub struct DeviceLossMonitor {
+    lost: Arc<AtomicBool>,
+}
+
+impl DeviceLossMonitor {
+    /* replaces the default uncaptured error handler, which panics, with logging. errors after
+    the loss are the expected fallout of the frames still in flight and stay quiet */
+    pub fn watch(device: &Device, on_lost: impl Fn(String) + Send + 'static) -> Self {
+        let monitor = Self::default();
+        let lost = monitor.lost.clone();
+        device.set_device_lost_callback(move |reason, message| {
+            warn!("The gpu device was lost ({:?}): {}", reason, message);
+            lost.store(true, Ordering::Release);
+            on_lost(message);
+        });
+        let lost = monitor.lost.clone();
+        device.on_uncaptured_error(Box::new(move |gpu_error| {
+            if !lost.load(Ordering::Acquire) {
+                error!("Uncaptured gpu error: {}", gpu_error);
+            }
+        }));
+        monitor
+    }
+
+    pub fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::Acquire)
+    }
+
+    /* for the simulate device loss action, marks the device lost at once so no frame renders with it in between */
+    pub fn force_loss(&self, device: &Device) {
+        warn!("Forcing a gpu device loss");
+        self.lost.store(true, Ordering::Release);
+        device.destroy();
+    }
+}
@@ -0,0 +1,89 @@
+use std::path::PathBuf;
+
+use crate::localization;
+
+const EXIT_POPUP_TITLE: &str = "Quit SpaceS?";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitChoice {
+    Save,
+    Discard,
+}
+
+/* asks before the edits of the running scenario are lost on quitting */
+#[derive(Debug, Default)]
+pub struct ExitDialog {
+    open: bool,
+    /* saved to when chosen, the file the scenario was loaded from or the default one */
+    scenario_path: PathBuf,
+    /* the popup is opened in the frame after the request */
+    open_popup: bool,
+    /* a save was started, the application quits once it finished */
+    saving: bool,
+    error: Option<String>,
+}
+
+impl ExitDialog {
+    pub fn request(&mut self, scenario_path: PathBuf) {
+        self.open = true;
+        self.scenario_path = scenario_path;
+        self.open_popup = true;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn is_saving(&self) -> bool {
+        self.saving
+    }
+
+    pub fn set_saving(&mut self) {
+        self.saving = true;
+        self.error = None;
+    }
+
+    /* back to the choice, discarding stays possible */
+    pub fn save_failed(&mut self, error: String) {
+        self.saving = false;
+        self.error = Some(error);
+    }
+
+    pub fn scenario_path(&self) -> &PathBuf {
+        &self.scenario_path
+    }
+
+    pub fn draw(&mut self, ui: &imgui::Ui) -> Option<ExitChoice> {
+        let scenario_path = &self.scenario_path;
+        if std::mem::take(&mut self.open_popup) {
+            ui.open_popup(localization::title("popup-quit", EXIT_POPUP_TITLE));
+        }
+        let mut choice = None;
+        ui.modal_popup(localization::title("popup-quit", EXIT_POPUP_TITLE), || {
+            if self.saving {
+                ui.text(format!("Saving to {}...", scenario_path.display()));
+                ui.text_disabled("Press Escape again to quit without waiting");
+                return;
+            }
+            ui.text("The scenario was edited since it was loaded.");
+            ui.text(format!("Save it to {}?", scenario_path.display()));
+            if let Some(error) = self.error.as_ref() {
+                ui.text_colored([1., 0.35, 0.3, 1.], error);
+            }
+            if ui.button("Save") {
+                choice = Some(ExitChoice::Save);
+            }
+            ui.same_line();
+            if ui.button("Discard") {
+                choice = Some(ExitChoice::Discard);
+            }
+            ui.same_line();
+            if ui.button("Cancel") {
+                self.open = false;
+                self.error = None;
+                ui.close_current_popup();
+            }
+        });
+        choice
+    }
+}
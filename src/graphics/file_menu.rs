@@ -0,0 +1,69 @@
+use strum::IntoEnumIterator;
+
+use crate::{
+    localization,
+    platform::FileFilter,
+    storage::{SCENARIO_EXTENSIONS, SaveFormat, ScenarioFormat},
+};
+
+/* what a file dialog is shown for, its result comes back with the same action */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileAction {
+    /* a scenario, or a table of bodies to import */
+    Open,
+    /* a quicksave or an autosave, or a copy of one */
+    LoadState,
+    SaveAs,
+}
+
+impl FileAction {
+    pub fn title(&self) -> String {
+        match self {
+            FileAction::Open => localization::tr("file-dialog-open"),
+            FileAction::LoadState => localization::tr("file-dialog-load-state"),
+            FileAction::SaveAs => localization::tr("file-dialog-save-as"),
+        }
+    }
+
+    /* the first one is selected when the dialog opens */
+    pub fn filters(&self) -> Vec<FileFilter> {
+        let all_files = FileFilter::new("All files", &["*"]);
+        match self {
+            FileAction::Open => vec![
+                FileFilter::new("Scenarios", &SCENARIO_EXTENSIONS),
+                FileFilter::new("Body tables", &["csv"]),
+                FileFilter::new("Horizons tables", &["txt", "csv"]),
+                all_files,
+            ],
+            FileAction::LoadState => vec![
+                FileFilter::new(
+                    "Saves",
+                    &SaveFormat::iter()
+                        .flat_map(|format| format.extensions())
+                        .copied()
+                        .collect::<Vec<_>>(),
+                ),
+                all_files,
+            ],
+            /* one per format, the extension of a name typed without one follows the first */
+            FileAction::SaveAs => ScenarioFormat::iter()
+                .map(|format| FileFilter::new(format!("{} scenario", format), format.extensions()))
+                .collect(),
+        }
+    }
+}
+
+/* the items opening a file dialog, the rest of the file menu belongs to the windows */
+pub fn draw_items(ui: &imgui::Ui) -> Option<FileAction> {
+    let mut action = None;
+    if ui.menu_item(localization::tr("menu-open")) {
+        action = Some(FileAction::Open);
+    }
+    if ui.menu_item(localization::tr("menu-save-as")) {
+        action = Some(FileAction::SaveAs);
+    }
+    if ui.menu_item(localization::tr("menu-load-state")) {
+        action = Some(FileAction::LoadState);
+    }
+    action
+}
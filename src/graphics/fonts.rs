@@ -0,0 +1,212 @@
+use std::path::PathBuf;
+
+use imgui::{FontConfig, FontGlyphRanges, FontSource};
+use tracing::{info, warn};
+use wgpu::{Device, Queue};
+
+use crate::platform;
+
+/* ttf and otf files in here can be picked in the settings */
+pub const FONTS_DIRECTORY: &str = "assets/fonts";
+pub const DEFAULT_FONT_SIZE: f32 = 13.;
+pub const MINIMUM_FONT_SIZE: f32 = 8.;
+pub const MAXIMUM_FONT_SIZE: f32 = 32.;
+/* sfnt versions of truetype and opentype files, anything else would fail the atlas build */
+const FONT_MAGIC: [[u8; 4]; 3] = [[0, 1, 0, 0], *b"OTTO", *b"true"];
+/* basic latin, latin-1 and greek with its extensions, zero terminated */
+static GREEK_GLYPH_RANGES: [u32; 5] = [0x0020, 0x00FF, 0x0370, 0x03FF, 0];
+
+/* the glyphs a language needs on top of latin, languages of the same script share an atlas */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Script {
+    Latin,
+    Cyrillic,
+    Greek,
+    Japanese,
+    Chinese,
+    Korean,
+    Thai,
+    Vietnamese,
+}
+
+impl Script {
+    /* of a language code like ja or pt-BR */
+    pub fn of_language(language: &str) -> Self {
+        let primary = language
+            .split(['-', '_'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match primary.as_str() {
+            "ru" | "uk" | "be" | "bg" | "sr" | "mk" | "kk" => Script::Cyrillic,
+            "el" => Script::Greek,
+            "ja" => Script::Japanese,
+            "zh" => Script::Chinese,
+            "ko" => Script::Korean,
+            "th" => Script::Thai,
+            "vi" => Script::Vietnamese,
+            _ => Script::Latin,
+        }
+    }
+
+    /* none when the default ranges cover it */
+    pub fn glyph_ranges(self) -> Option<FontGlyphRanges> {
+        Some(match self {
+            Script::Latin => return None,
+            Script::Cyrillic => FontGlyphRanges::cyrillic(),
+            Script::Greek => FontGlyphRanges::from_slice(&GREEK_GLYPH_RANGES),
+            Script::Japanese => FontGlyphRanges::japanese(),
+            Script::Chinese => FontGlyphRanges::chinese_simplified_common(),
+            Script::Korean => FontGlyphRanges::korean(),
+            Script::Thai => FontGlyphRanges::thai(),
+            Script::Vietnamese => FontGlyphRanges::vietnamese(),
+        })
+    }
+}
+
+/* what the atlas is rasterized for, a change of any field rebuilds it */
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontConfiguration {
+    /* file name in FONTS_DIRECTORY, the embedded font when empty or unreadable */
+    pub font: String,
+    /* merged on top for the glyphs of the language the font lacks, the font itself is asked
+    for them when empty */
+    pub fallback_font: String,
+    /* of the language, switching to one of the same script keeps the atlas */
+    pub script: Script,
+    /* logical pixels before the ui scale */
+    pub size: f32,
+    pub ui_scale: f32,
+    /* of the window, from winit */
+    pub hidpi_factor: f32,
+}
+
+/* owns the font atlas and the style sizes. the glyphs are rasterized at the physical pixel size,
+so text stays crisp at any ui scale and on hidpi displays instead of being stretched */
+pub struct UiFonts {
+    /* the unscaled style, scale_all_sizes is applied to a copy of it on every rebuild */
+    base_style: imgui::Style,
+    configuration: Option<FontConfiguration>,
+}
+
+impl UiFonts {
+    pub fn new(context: &imgui::Context) -> Self {
+        Self {
+            base_style: *context.style(),
+            configuration: None,
+        }
+    }
+
+    /* builds the atlas the renderer uploads when it is created */
+    pub fn build(&mut self, context: &mut imgui::Context, configuration: FontConfiguration) {
+        let pixels = configuration.size * configuration.ui_scale * configuration.hidpi_factor;
+        let data = self::read_font(&configuration.font);
+        let fallback_data = match configuration.fallback_font.is_empty() {
+            true => None,
+            false => self::read_font(&configuration.fallback_font),
+        };
+        let mut sources = vec![match &data {
+            Some(data) => FontSource::TtfData {
+                data,
+                size_pixels: pixels,
+                config: None,
+            },
+            None => FontSource::DefaultFontData {
+                config: Some(FontConfig {
+                    size_pixels: pixels,
+                    ..Default::default()
+                }),
+            },
+        }];
+        /* only the ranges of the active language, a full cjk atlas takes tens of megabytes */
+        if let Some(glyph_ranges) = configuration.script.glyph_ranges() {
+            match fallback_data.as_ref().or(data.as_ref()) {
+                Some(glyphs) => sources.push(FontSource::TtfData {
+                    data: glyphs,
+                    size_pixels: pixels,
+                    config: Some(FontConfig {
+                        glyph_ranges,
+                        ..Default::default()
+                    }),
+                }),
+                None => warn!(
+                    "No font file for the {:?} glyphs, their text shows as '?'",
+                    configuration.script
+                ),
+            }
+        }
+        let fonts = context.fonts();
+        fonts.clear();
+        fonts.add_font(&sources);
+        context.io_mut().font_global_scale = 1. / configuration.hidpi_factor;
+        let style = context.style_mut();
+        *style = self.base_style;
+        style.scale_all_sizes(configuration.ui_scale);
+        info!(
+            "Built the ui font atlas at {:.1} px ({})",
+            pixels,
+            match data {
+                Some(_) => configuration.font.as_str(),
+                None => "embedded",
+            }
+        );
+        self.configuration = Some(configuration);
+    }
+
+    /* rebuilds and re-uploads the atlas when the configuration changed, between two frames */
+    pub fn update(
+        &mut self,
+        context: &mut imgui::Context,
+        renderer: &mut imgui_wgpu::Renderer,
+        device: &Device,
+        queue: &Queue,
+        configuration: FontConfiguration,
+    ) {
+        if self.configuration.as_ref() == Some(&configuration) {
+            return;
+        }
+        self.build(context, configuration);
+        renderer.reload_font_texture(context, device, queue);
+    }
+}
+
+/* file names in FONTS_DIRECTORY, sorted */
+pub fn available_fonts() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(platform::asset_path(FONTS_DIRECTORY)) else {
+        return Vec::new();
+    };
+    let mut fonts = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| {
+            let name = name.to_ascii_lowercase();
+            name.ends_with(".ttf") || name.ends_with(".otf")
+        })
+        .collect::<Vec<_>>();
+    fonts.sort();
+    fonts
+}
+
+fn read_font(font: &str) -> Option<Vec<u8>> {
+    if font.is_empty() {
+        return None;
+    }
+    let path: PathBuf = platform::asset_path(FONTS_DIRECTORY).join(font);
+    let data = std::fs::read(&path)
+        .inspect_err(|error| {
+            warn!(
+                "Failed to read the font {}, using the embedded one: {}",
+                path.display(),
+                error
+            )
+        })
+        .ok()?;
+    if !FONT_MAGIC.iter().any(|magic| data.starts_with(magic)) {
+        warn!(
+            "{} is not a truetype or opentype font, using the embedded one",
+            path.display()
+        );
+        return None;
+    }
+    Some(data)
+}
@@ -0,0 +1,142 @@
+use glam::{DVec2, DVec3};
+
+use crate::{
+    graphics::{camera::Camera, labels, viewport::Viewport},
+    simulation::{self, BodyId, SimulationBody},
+};
+
+/* arrows grow by this many viewport pixels per decade of magnitude above the reference */
+pub const GIZMO_PIXELS_PER_DECADE: f64 = 24.;
+pub const GIZMO_MAXIMUM_LENGTH: f64 = 240.;
+pub const VELOCITY_REFERENCE: f64 = 1.;
+pub const ACCELERATION_REFERENCE: f64 = 1e-6;
+pub const VELOCITY_COLOR: [f32; 4] = [0.35, 0.9, 0.45, 1.];
+pub const ACCELERATION_COLOR: [f32; 4] = [1., 0.55, 0.2, 1.];
+pub const GIZMO_THICKNESS: f32 = 2.;
+pub const ARROWHEAD_LENGTH: f32 = 8.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GizmoSettings {
+    pub velocity: bool,
+    pub acceleration: bool,
+}
+
+impl Default for GizmoSettings {
+    fn default() -> Self {
+        Self {
+            velocity: true,
+            acceleration: true,
+        }
+    }
+}
+
+/* live vectors of the selected body, also shown numerically by the inspector */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BodyVectors {
+    pub velocity: DVec3,
+    pub acceleration: DVec3,
+}
+
+impl BodyVectors {
+    pub fn of(bodies: &[SimulationBody], id: BodyId) -> Option<Self> {
+        let index = bodies.iter().position(|body| body.id == id)?;
+        Some(Self {
+            velocity: bodies[index].velocity,
+            acceleration: simulation::acceleration_on(bodies, index),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GizmoArrow {
+    /* viewport pixels */
+    pub start: DVec2,
+    pub end: DVec2,
+    pub color: [f32; 4],
+}
+
+/* screen length of a vector, logarithmic so velocities and tiny accelerations both stay visible */
+pub fn arrow_length(magnitude: f64, reference: f64) -> f64 {
+    (GIZMO_PIXELS_PER_DECADE * (1. + magnitude / reference).log10()).min(GIZMO_MAXIMUM_LENGTH)
+}
+
+/* the world direction is projected through a short step from the body, zero vectors and
+directions that leave the view frustum draw nothing */
+pub fn project_arrow(
+    camera: &Camera,
+    position: DVec3,
+    vector: DVec3,
+    reference: f64,
+    color: [f32; 4],
+) -> Option<GizmoArrow> {
+    let direction = vector.try_normalize()?;
+    let view_projection = camera.view_projection().as_dmat4();
+    let viewport_size = DVec2::from_array(camera.viewport_size().map(f64::from));
+    let eye = camera.position();
+    let step = (position - eye).length() * 1e-3;
+    let start = labels::project_to_viewport(view_projection, position - eye, viewport_size)?;
+    let ahead = labels::project_to_viewport(
+        view_projection,
+        position + direction * step - eye,
+        viewport_size,
+    )?;
+    let screen_direction = (ahead - start).try_normalize()?;
+    Some(GizmoArrow {
+        start,
+        end: start + screen_direction * self::arrow_length(vector.length(), reference),
+        color,
+    })
+}
+
+pub fn body_arrows(
+    camera: &Camera,
+    body: &SimulationBody,
+    vectors: &BodyVectors,
+    settings: &GizmoSettings,
+) -> Vec<GizmoArrow> {
+    [
+        (
+            settings.velocity,
+            vectors.velocity,
+            VELOCITY_REFERENCE,
+            VELOCITY_COLOR,
+        ),
+        (
+            settings.acceleration,
+            vectors.acceleration,
+            ACCELERATION_REFERENCE,
+            ACCELERATION_COLOR,
+        ),
+    ]
+    .into_iter()
+    .filter(|(enabled, ..)| *enabled)
+    .filter_map(|(_, vector, reference, color)| {
+        self::project_arrow(camera, body.position, vector, reference, color)
+    })
+    .collect()
+}
+
+/* called inside the viewport window like the labels */
+pub fn draw(ui: &imgui::Ui, viewport: &Viewport, arrows: &[GizmoArrow]) {
+    let draw_list = ui.get_window_draw_list();
+    arrows.iter().for_each(|arrow| {
+        let start = viewport.to_ui([arrow.start.x as f32, arrow.start.y as f32]);
+        let end = viewport.to_ui([arrow.end.x as f32, arrow.end.y as f32]);
+        draw_list
+            .add_line(start, end, arrow.color)
+            .thickness(GIZMO_THICKNESS)
+            .build();
+        let Some(back) = (DVec2::from_array(start.map(f64::from))
+            - DVec2::from_array(end.map(f64::from)))
+        .try_normalize() else {
+            return;
+        };
+        [0.5f64, -0.5].into_iter().for_each(|angle| {
+            let side = DVec2::from_angle(angle).rotate(back).as_vec2() * ARROWHEAD_LENGTH;
+            draw_list
+                .add_line(end, [end[0] + side.x, end[1] + side.y], arrow.color)
+                .thickness(GIZMO_THICKNESS)
+                .build();
+        });
+    });
+}
@@ -0,0 +1,199 @@
+use bytemuck::{Pod, Zeroable};
+use wgpu::{Device, Queue, TextureFormat};
+
+use crate::graphics::{
+    camera::Camera,
+    shaders,
+    stats::DrawCounts,
+    targets::{DEPTH_COMPARE, DEPTH_FORMAT},
+};
+
+/* about this many cells of the fine tier span the camera distance */
+pub const GRID_CELLS_PER_DISTANCE: f64 = 10.;
+/* the plane reaches and fades out at this multiple of the camera distance */
+pub const GRID_FADE_DISTANCE: f64 = 30.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GridSettings {
+    pub enabled: bool,
+    /* the x axis in red and the z axis in blue through the origin */
+    pub axes: bool,
+}
+
+impl Default for GridSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            axes: true,
+        }
+    }
+}
+
+/* spacing of the fine tier and its alpha. the coarse tier is ten times wider and always
+opaque, the fine one fades out over a decade of distance and then becomes the coarse one of
+the next tier, so the lines never jump */
+pub fn grid_tier(distance: f64) -> (f64, f32) {
+    let level = (distance.max(f64::MIN_POSITIVE) / GRID_CELLS_PER_DISTANCE).log10();
+    let tier = level.floor();
+    /* fract keeps the sign of negative levels */
+    (10f64.powf(tier), (1. - (level - tier)) as f32)
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct GridInstance {
+    /* point of the plane below the eye, relative to the eye */
+    pub center: [f32; 3],
+    /* half size of the square plane */
+    pub extent: f32,
+    /* the world position of the center, wrapped into one coarse cell so it stays precise */
+    pub offset: [f32; 2],
+    /* the world origin relative to the center */
+    pub axis_origin: [f32; 2],
+    pub spacing: f32,
+    pub fine_alpha: f32,
+    pub fade_distance: f32,
+    /* 1 draws the axes */
+    pub axes: f32,
+}
+
+impl GridInstance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 8] = wgpu::vertex_attr_array![
+        0 => Float32x3,
+        1 => Float32,
+        2 => Float32x2,
+        3 => Float32x2,
+        4 => Float32,
+        5 => Float32,
+        6 => Float32,
+        7 => Float32,
+    ];
+
+    /* the plane is the ecliptic (y = 0) of the scenario */
+    pub fn from_camera(camera: &Camera, settings: &GridSettings) -> Self {
+        let eye = camera.position();
+        let distance = camera.distance() as f64;
+        let (spacing, fine_alpha) = self::grid_tier(distance);
+        let fade_distance = distance * GRID_FADE_DISTANCE;
+        let coarse_spacing = spacing * 10.;
+        Self {
+            center: [0., -eye.y as f32, 0.],
+            extent: fade_distance as f32,
+            offset: [
+                eye.x.rem_euclid(coarse_spacing) as f32,
+                eye.z.rem_euclid(coarse_spacing) as f32,
+            ],
+            axis_origin: [-eye.x as f32, -eye.z as f32],
+            spacing: spacing as f32,
+            fine_alpha,
+            fade_distance: fade_distance as f32,
+            axes: settings.axes as u32 as f32,
+        }
+    }
+}
+
+/* a translucent plane tested against the bodies without writing depth. it only exists on the
+gpu, picking never sees it */
+pub struct GridPipeline {
+    pub render_pipeline: wgpu::RenderPipeline,
+    /* a single instance, rewritten every frame */
+    pub instance_buffer: wgpu::Buffer,
+    pub visible: bool,
+}
+
+impl GridPipeline {
+    pub fn new(
+        device: &Device,
+        color_format: TextureFormat,
+        sample_count: u32,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let shader = shaders::GRID_SHADER.module(device);
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("grid pipeline layout"),
+            bind_group_layouts: &[camera_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("grid pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<GridInstance>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &GridInstance::ATTRIBUTES,
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: color_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: DEPTH_COMPARE,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            render_pipeline,
+            instance_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("grid instance buffer"),
+                size: std::mem::size_of::<GridInstance>() as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            visible: false,
+        }
+    }
+
+    /* none hides the grid */
+    pub fn write_grid(&mut self, queue: &Queue, grid: Option<GridInstance>) {
+        self.visible = grid.is_some_and(|instance| {
+            queue.write_buffer(&self.instance_buffer, 0, bytemuck::bytes_of(&instance));
+            true
+        });
+    }
+
+    pub fn draw(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        camera_bind_group: &wgpu::BindGroup,
+    ) {
+        if !self.visible {
+            return;
+        }
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+        render_pass.draw(0..4, 0..1);
+    }
+
+    pub fn draw_counts(&self) -> DrawCounts {
+        match self.visible {
+            true => DrawCounts::new(1, 1),
+            false => DrawCounts::default(),
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use std::path::PathBuf;
+
+use imgui::Condition;
+
+use crate::localization;
+
+pub const HORIZONS_WINDOW_TITLE: &str = "Import from Horizons";
+pub const HORIZONS_WINDOW_SIZE: [f32; 2] = [460., 280.];
+
+/* vector tables merged into a new scenario, the mass and radius are for the bodies other than
+the sun, the planets and the moon */
+pub struct HorizonsRequest {
+    pub paths: Vec<PathBuf>,
+    pub mass: f64,
+    pub radius: f64,
+}
+
+/* the horizons window, one table per body and line */
+pub struct HorizonsDialog {
+    pub open: bool,
+    pub paths: String,
+    pub mass: f64,
+    pub radius: f64,
+}
+
+impl Default for HorizonsDialog {
+    fn default() -> Self {
+        Self {
+            open: false,
+            paths: String::new(),
+            mass: 0.,
+            radius: 1e3,
+        }
+    }
+}
+
+impl HorizonsDialog {
+    pub fn draw_menu_item(&mut self, ui: &imgui::Ui) {
+        if ui.menu_item(localization::tr("menu-import-horizons")) {
+            self.open = true;
+        }
+    }
+
+    /* a dropped table is added to the list, the others of the scenario may still follow */
+    pub fn offer(&mut self, path: PathBuf) {
+        let path = path.display().to_string();
+        if !self.paths.lines().any(|line| line.trim() == path) {
+            if !self.paths.is_empty() && !self.paths.ends_with('\n') {
+                self.paths.push('\n');
+            }
+            self.paths += &path;
+        }
+        self.open = true;
+    }
+
+    /* returns the tables to merge once per press of the create button */
+    pub fn draw(&mut self, ui: &imgui::Ui) -> Option<HorizonsRequest> {
+        if !self.open {
+            return None;
+        }
+        let mut open = true;
+        let request = ui
+            .window(localization::title(
+                "window-horizons",
+                HORIZONS_WINDOW_TITLE,
+            ))
+            .opened(&mut open)
+            .size(HORIZONS_WINDOW_SIZE, Condition::FirstUseEver)
+            .build(|| self.draw_fields(ui))
+            .flatten();
+        self.open &= open;
+        request
+    }
+
+    fn draw_fields(&mut self, ui: &imgui::Ui) -> Option<HorizonsRequest> {
+        ui.input_text_multiline("Tables", &mut self.paths, [0., ui.text_line_height() * 5.])
+            .build();
+        ui.text_disabled("Vector tables of one epoch and center, as text or csv, one per line");
+        ui.separator();
+        ui.text("Other bodies than the sun, the planets and the moon");
+        ui.input_scalar("Mass (kg)", &mut self.mass)
+            .display_format("%.6e")
+            .build();
+        ui.input_scalar("Radius (m)", &mut self.radius)
+            .display_format("%.4e")
+            .build();
+        ui.separator();
+        let paths = self
+            .paths
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(PathBuf::from)
+            .collect::<Vec<_>>();
+        let valid = !paths.is_empty() && self.mass >= 0. && self.radius > 0.;
+        let _disabled = ui.begin_disabled(!valid);
+        if !ui.button("Create scenario") {
+            return None;
+        }
+        Some(HorizonsRequest {
+            paths,
+            mass: self.mass,
+            radius: self.radius,
+        })
+    }
+}
@@ -0,0 +1,95 @@
+use std::path::PathBuf;
+
+use imgui::Condition;
+
+use crate::storage::{self, CsvField, CsvMapping};
+
+use crate::localization;
+
+pub const IMPORT_WINDOW_TITLE: &str = "Import bodies";
+pub const IMPORT_WINDOW_SIZE: [f32; 2] = [420., 260.];
+
+/* a table of bodies to append to the running scenario */
+pub struct BodyImport {
+    pub path: PathBuf,
+    pub mapping: CsvMapping,
+}
+
+/* the import window, its fields survive closing it so a fixed table is quick to import again */
+#[derive(Default)]
+pub struct ImportDialog {
+    pub open: bool,
+    pub path: String,
+    pub skip_invalid_rows: bool,
+    /* the header of each field in the order of CsvField::ALL, detected when empty */
+    pub columns: [String; CsvField::ALL.len()],
+}
+
+impl ImportDialog {
+    pub fn draw_menu_item(&mut self, ui: &imgui::Ui) {
+        if ui.menu_item(localization::tr("menu-import-csv")) {
+            self.open = true;
+        }
+    }
+
+    /* a dropped table is not imported right away, the columns may need mapping first */
+    pub fn offer(&mut self, path: PathBuf) {
+        self.path = path.display().to_string();
+        self.open = true;
+    }
+
+    /* returns the table to import once per press of the import button */
+    pub fn draw(&mut self, ui: &imgui::Ui) -> Option<BodyImport> {
+        if !self.open {
+            return None;
+        }
+        let mut open = true;
+        let import = ui
+            .window(localization::title("window-import", IMPORT_WINDOW_TITLE))
+            .opened(&mut open)
+            .size(IMPORT_WINDOW_SIZE, Condition::FirstUseEver)
+            .build(|| self.draw_fields(ui))
+            .flatten();
+        self.open &= open;
+        import
+    }
+
+    fn draw_fields(&mut self, ui: &imgui::Ui) -> Option<BodyImport> {
+        ui.input_text("File", &mut self.path)
+            .hint(
+                storage::scenarios_directory()
+                    .join("bodies.csv")
+                    .display()
+                    .to_string(),
+            )
+            .build();
+        ui.text_disabled(
+            "A header row names the columns, a unit may follow like mass_solar or x_au",
+        );
+        ui.checkbox("Skip invalid rows", &mut self.skip_invalid_rows);
+        if ui.collapsing_header("Columns", imgui::TreeNodeFlags::empty()) {
+            for (field, header) in CsvField::ALL.iter().zip(self.columns.iter_mut()) {
+                ui.input_text(field.to_string(), header)
+                    .hint("detected")
+                    .build();
+            }
+        }
+        let _disabled = ui.begin_disabled(self.path.trim().is_empty());
+        if !ui.button("Import") {
+            return None;
+        }
+        let columns = CsvField::ALL
+            .iter()
+            .zip(self.columns.iter())
+            .filter(|(_, header)| !header.trim().is_empty())
+            .map(|(field, header)| (*field, header.trim().to_string()))
+            .collect();
+        Some(BodyImport {
+            path: PathBuf::from(self.path.trim()),
+            mapping: CsvMapping {
+                columns,
+                skip_invalid_rows: self.skip_invalid_rows,
+            },
+        })
+    }
+}
@@ -0,0 +1,254 @@
+use glam::DVec3;
+use imgui::{Condition, Drag};
+
+use crate::{
+    graphics::gizmos::{BodyVectors, GizmoSettings},
+    localization::{self, Quantity},
+    simulation::{BodyEdit, SimulationBody},
+};
+
+pub const INSPECTOR_WINDOW_TITLE: &str = "Inspector";
+pub const INSPECTOR_WINDOW_SIZE: [f32; 2] = [340., 420.];
+/* masses from a pebble to a galaxy core, dragged as a power of ten */
+pub const MASS_EXPONENT_RANGE: (f64, f64) = (0., 42.);
+/* fraction of the value a dragged pixel changes, so every scale drags alike */
+pub const RELATIVE_DRAG_SPEED: f64 = 0.005;
+const DELETE_POPUP_TITLE: &str = "Delete body?";
+
+pub enum InspectorAction {
+    /* unfinished edits of a drag or of typing merge into one undo step until FinishEdit */
+    Edit { edit: BodyEdit, finished: bool },
+    FinishEdit,
+    /* confirmed in a popup */
+    Delete,
+}
+
+/* live readout and editor of the selected body, nothing is drawn without a selection. at most
+one action is returned per frame, the simulation applies it before its next step */
+pub fn draw(
+    ui: &imgui::Ui,
+    body: Option<(&SimulationBody, BodyVectors)>,
+    dirty: bool,
+    gizmo_settings: &mut GizmoSettings,
+) -> Option<InspectorAction> {
+    let (body, vectors) = body?;
+    let display_size = ui.io().display_size;
+    ui.window(localization::title(
+        "window-inspector",
+        INSPECTOR_WINDOW_TITLE,
+    ))
+    .position(
+        [
+            display_size[0] - INSPECTOR_WINDOW_SIZE[0] - 16.,
+            display_size[1] - INSPECTOR_WINDOW_SIZE[1] - 16.,
+        ],
+        Condition::FirstUseEver,
+    )
+    .size(INSPECTOR_WINDOW_SIZE, Condition::FirstUseEver)
+    .build(|| {
+        let mut edit = None;
+        let mut name = body.id_name.clone();
+        if ui.input_text("Name", &mut name).build() && !name.is_empty() {
+            edit = Some(self::unfinished(BodyEdit::Name(name)));
+        }
+        self::finish_on_release(ui, &mut edit);
+        if dirty {
+            ui.text_disabled("Edited since the scenario was loaded");
+        }
+        ui.separator();
+        edit = self::draw_physics(ui, body).or(edit);
+        ui.separator();
+        edit = self::draw_appearance(ui, body).or(edit);
+        ui.separator();
+        ui.text(format!(
+            "Speed: {}",
+            localization::format_quantity(
+                vectors.velocity.length(),
+                Quantity::Speed,
+                &localization::active_language()
+            )
+        ));
+        ui.text(format!(
+            "Acceleration: {:.3e} m/s^2",
+            vectors.acceleration.length()
+        ));
+        ui.checkbox("Velocity vector", &mut gizmo_settings.velocity);
+        ui.checkbox("Acceleration vector", &mut gizmo_settings.acceleration);
+        ui.separator();
+        if ui.button("Delete") {
+            ui.open_popup(localization::title("popup-delete-body", DELETE_POPUP_TITLE));
+        }
+        let deleted = ui.modal_popup(
+            localization::title("popup-delete-body", DELETE_POPUP_TITLE),
+            || {
+                ui.text(format!("{} is removed from the simulation.", body.id_name));
+                let deleted = ui.button("Delete");
+                ui.same_line();
+                if deleted || ui.button("Cancel") {
+                    ui.close_current_popup();
+                }
+                deleted
+            },
+        );
+        match deleted {
+            Some(true) => Some(InspectorAction::Delete),
+            _ => edit,
+        }
+    })
+    .flatten()
+}
+
+fn draw_physics(ui: &imgui::Ui, body: &SimulationBody) -> Option<InspectorAction> {
+    let mut edit = None;
+    let mut mass = body.mass;
+    if ui
+        .input_scalar("Mass (kg)", &mut mass)
+        .display_format("%.6e")
+        .enter_returns_true(true)
+        .build()
+    {
+        edit = Some(self::finished(BodyEdit::Mass(mass)));
+    }
+    let mut exponent = body.mass.max(1.).log10();
+    if Drag::new("Mass exponent")
+        .range(MASS_EXPONENT_RANGE.0, MASS_EXPONENT_RANGE.1)
+        .speed(0.005)
+        .display_format("10^%.3f kg")
+        .build(ui, &mut exponent)
+    {
+        edit = Some(self::unfinished(BodyEdit::Mass(10f64.powf(exponent))));
+    }
+    self::finish_on_release(ui, &mut edit);
+    if ui.button("Halve mass") {
+        edit = Some(self::finished(BodyEdit::Mass(body.mass * 0.5)));
+    }
+    ui.same_line();
+    if ui.button("Double mass") {
+        edit = Some(self::finished(BodyEdit::Mass(body.mass * 2.)));
+    }
+
+    let mut radius = body.radius;
+    if Drag::new("Radius (m)")
+        .range(0., f64::MAX)
+        .speed(self::relative_speed(body.radius, 1.))
+        .display_format("%.4e")
+        .build(ui, &mut radius)
+    {
+        edit = Some(self::unfinished(BodyEdit::Radius(radius)));
+    }
+    self::finish_on_release(ui, &mut edit);
+    let mut position = body.position.to_array();
+    if Drag::new("Position (m)")
+        .speed(self::relative_speed(body.position.length(), 1.))
+        .display_format("%.4e")
+        .build_array(ui, &mut position)
+    {
+        edit = Some(self::unfinished(BodyEdit::Position(DVec3::from_array(
+            position,
+        ))));
+    }
+    self::finish_on_release(ui, &mut edit);
+    let mut velocity = body.velocity.to_array();
+    if Drag::new("Velocity (m/s)")
+        .speed(self::relative_speed(body.velocity.length(), 0.01))
+        .display_format("%.4e")
+        .build_array(ui, &mut velocity)
+    {
+        edit = Some(self::unfinished(BodyEdit::Velocity(DVec3::from_array(
+            velocity,
+        ))));
+    }
+    self::finish_on_release(ui, &mut edit);
+    edit
+}
+
+/* the text fields apply on enter, a texture path is not loaded while it is being typed */
+fn draw_appearance(ui: &imgui::Ui, body: &SimulationBody) -> Option<InspectorAction> {
+    let mut appearance = body.appearance.clone();
+    let mut changed = false;
+    /* everything but the spin axis drag is a single step */
+    let mut finished = true;
+
+    let mut tags = appearance.tags.join(", ");
+    if ui
+        .input_text("Tags", &mut tags)
+        .hint("star, planet, moon")
+        .enter_returns_true(true)
+        .build()
+    {
+        appearance.tags = self::parse_tags(&tags);
+        changed = true;
+    }
+    let mut texture = appearance.texture.clone().unwrap_or_default();
+    if ui
+        .input_text("Texture", &mut texture)
+        .hint("flat color")
+        .enter_returns_true(true)
+        .build()
+    {
+        let texture = texture.trim();
+        appearance.texture = (!texture.is_empty()).then(|| texture.to_string());
+        changed = true;
+    }
+
+    let mut custom_spin_axis = appearance.spin_axis.is_some();
+    if ui.checkbox("Spin axis", &mut custom_spin_axis) {
+        appearance.spin_axis = custom_spin_axis.then_some([0., 1., 0.]);
+        changed = true;
+    }
+    let mut released = false;
+    if let Some(spin_axis) = appearance.spin_axis.as_mut() {
+        ui.same_line();
+        if Drag::new("##spin axis")
+            .range(-1., 1.)
+            .speed(0.01)
+            .display_format("%.2f")
+            .build_array(ui, spin_axis)
+        {
+            changed = true;
+            finished = false;
+        }
+        released = ui.is_item_deactivated_after_edit();
+    }
+    match changed {
+        true => Some(InspectorAction::Edit {
+            edit: BodyEdit::Appearance(appearance),
+            finished,
+        }),
+        false => released.then_some(InspectorAction::FinishEdit),
+    }
+}
+
+/* comma separated, blanks are dropped */
+pub fn parse_tags(tags: &str) -> Vec<String> {
+    tags.split(',')
+        .map(str::trim)
+        .filter(|tag| !tag.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn finished(edit: BodyEdit) -> InspectorAction {
+    InspectorAction::Edit {
+        edit,
+        finished: true,
+    }
+}
+
+fn unfinished(edit: BodyEdit) -> InspectorAction {
+    InspectorAction::Edit {
+        edit,
+        finished: false,
+    }
+}
+
+/* the last widget was a drag or a text field that has just been let go of */
+fn finish_on_release(ui: &imgui::Ui, action: &mut Option<InspectorAction>) {
+    if action.is_none() && ui.is_item_deactivated_after_edit() {
+        *action = Some(InspectorAction::FinishEdit);
+    }
+}
+
+fn relative_speed(magnitude: f64, minimum: f64) -> f32 {
+    (magnitude * RELATIVE_DRAG_SPEED).max(minimum) as f32
+}
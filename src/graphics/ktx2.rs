@@ -0,0 +1,323 @@
+use ::ktx2::{Format, Reader};
+use anyhow::{Context, Result, anyhow, bail, ensure};
+use wgpu::{AstcBlock, AstcChannel, Features, TextureFormat};
+
+/* first bytes of every ktx2 file */
+pub const IDENTIFIER: [u8; 12] = [
+    0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+/* bc7 and astc 4x4 both store 4x4 pixels in 16 bytes */
+const BLOCK_SIZE: usize = 16;
+
+/* the texture2ddecoder functions, a whole level into packed pixels */
+type Decoder = fn(&[u8], usize, usize, &mut [u32]) -> Result<(), &'static str>;
+
+/* the vkFormat values planet maps come in */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ktx2Format {
+    Rgba8 { srgb: bool },
+    Bc7 { srgb: bool },
+    Astc4x4 { srgb: bool },
+}
+
+impl Ktx2Format {
+    fn from_format(format: Format) -> Option<Self> {
+        Some(match format {
+            Format::R8G8B8A8_UNORM => Ktx2Format::Rgba8 { srgb: false },
+            Format::R8G8B8A8_SRGB => Ktx2Format::Rgba8 { srgb: true },
+            Format::BC7_UNORM_BLOCK => Ktx2Format::Bc7 { srgb: false },
+            Format::BC7_SRGB_BLOCK => Ktx2Format::Bc7 { srgb: true },
+            Format::ASTC_4x4_UNORM_BLOCK => Ktx2Format::Astc4x4 { srgb: false },
+            Format::ASTC_4x4_SRGB_BLOCK => Ktx2Format::Astc4x4 { srgb: true },
+            _ => return None,
+        })
+    }
+
+    pub fn texture_format(self) -> TextureFormat {
+        match self {
+            Ktx2Format::Rgba8 { srgb: false } => TextureFormat::Rgba8Unorm,
+            Ktx2Format::Rgba8 { srgb: true } => TextureFormat::Rgba8UnormSrgb,
+            Ktx2Format::Bc7 { srgb: false } => TextureFormat::Bc7RgbaUnorm,
+            Ktx2Format::Bc7 { srgb: true } => TextureFormat::Bc7RgbaUnormSrgb,
+            Ktx2Format::Astc4x4 { srgb } => TextureFormat::Astc {
+                block: AstcBlock::B4x4,
+                channel: match srgb {
+                    true => AstcChannel::UnormSrgb,
+                    false => AstcChannel::Unorm,
+                },
+            },
+        }
+    }
+
+    /* what the device has to support to sample the payload as is */
+    pub fn required_features(self) -> Features {
+        match self {
+            Ktx2Format::Rgba8 { .. } => Features::empty(),
+            Ktx2Format::Bc7 { .. } => Features::TEXTURE_COMPRESSION_BC,
+            Ktx2Format::Astc4x4 { .. } => Features::TEXTURE_COMPRESSION_ASTC,
+        }
+    }
+
+    /* bytes of a level, the compressed formats use 16 byte blocks of 4x4 pixels */
+    fn level_size(self, width: u32, height: u32) -> usize {
+        match self {
+            Ktx2Format::Rgba8 { .. } => width as usize * height as usize * 4,
+            Ktx2Format::Bc7 { .. } | Ktx2Format::Astc4x4 { .. } => {
+                width.div_ceil(4) as usize * height.div_ceil(4) as usize * BLOCK_SIZE
+            }
+        }
+    }
+}
+
+/* a single 2d image with its mip chain as stored in the file */
+#[derive(Debug, Clone)]
+pub struct Ktx2Texture {
+    pub format: Ktx2Format,
+    pub width: u32,
+    pub height: u32,
+    /* level 0 first, tightly packed */
+    pub levels: Vec<Vec<u8>>,
+}
+
+impl Ktx2Texture {
+    /* decodes bc7 and astc into rgba8 when the device cannot sample them */
+    pub fn for_features(self, features: Features) -> Result<Self> {
+        if features.contains(self.format.required_features()) {
+            return Ok(self);
+        }
+        let (decode, srgb): (Decoder, _) = match self.format {
+            Ktx2Format::Rgba8 { .. } => return Ok(self),
+            Ktx2Format::Bc7 { srgb } => (texture2ddecoder::decode_bc7, srgb),
+            Ktx2Format::Astc4x4 { srgb } => (texture2ddecoder::decode_astc_4_4, srgb),
+        };
+        let levels = self
+            .levels
+            .iter()
+            .enumerate()
+            .map(|(level, data)| {
+                let (width, height) = self::level_dimensions(self.width, self.height, level);
+                let mut pixels = vec![0; width as usize * height as usize];
+                decode(data, width as usize, height as usize, &mut pixels).map_err(|error| {
+                    anyhow!(
+                        "Failed to decode {:?} level {}: {}",
+                        self.format,
+                        level,
+                        error
+                    )
+                })?;
+                /* the decoder packs every pixel as bgra */
+                Ok(pixels
+                    .into_iter()
+                    .flat_map(|pixel| {
+                        let [blue, green, red, alpha] = pixel.to_le_bytes();
+                        [red, green, blue, alpha]
+                    })
+                    .collect())
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            format: Ktx2Format::Rgba8 { srgb },
+            levels,
+            ..self
+        })
+    }
+
+    /* every level after another, the layout create_texture_with_data expects */
+    pub fn data(&self) -> Vec<u8> {
+        self.levels.concat()
+    }
+}
+
+pub fn is_ktx2(bytes: &[u8]) -> bool {
+    bytes.starts_with(&IDENTIFIER)
+}
+
+pub fn level_dimensions(width: u32, height: u32, level: usize) -> (u32, u32) {
+    ((width >> level).max(1), (height >> level).max(1))
+}
+
+/* only what a planet map needs, a 2d texture without layers, faces or supercompression */
+pub fn parse(bytes: &[u8]) -> Result<Ktx2Texture> {
+    ensure!(self::is_ktx2(bytes), "Not a ktx2 file");
+    let reader = Reader::new(bytes).context("Malformed ktx2 file")?;
+    let header = reader.header();
+    let (width, height) = (header.pixel_width, header.pixel_height);
+
+    let format = header
+        .format
+        .and_then(Ktx2Format::from_format)
+        .with_context(|| {
+            format!(
+                "Unsupported ktx2 vkFormat {}",
+                header.format.map_or(0, |format| format.value())
+            )
+        })?;
+    ensure!(height > 0, "1d ktx2 textures are not supported");
+    ensure!(
+        header.pixel_depth == 0,
+        "3d ktx2 textures are not supported"
+    );
+    ensure!(
+        header.layer_count <= 1,
+        "Array ktx2 textures are not supported"
+    );
+    ensure!(
+        header.face_count == 1,
+        "Cube map ktx2 textures are not supported"
+    );
+    if let Some(scheme) = header.supercompression_scheme {
+        bail!(
+            "Supercompressed ktx2 textures are not supported (scheme {})",
+            scheme.value()
+        );
+    }
+    if !matches!(format, Ktx2Format::Rgba8 { .. }) {
+        ensure!(
+            width % 4 == 0 && height % 4 == 0,
+            "Compressed ktx2 size {}x{} is not a multiple of the block size",
+            width,
+            height
+        );
+    }
+    /* 0 asks the loader to generate the mips, which is not possible for compressed data */
+    ensure!(
+        reader.levels().len() <= 32 - width.max(height).leading_zeros() as usize,
+        "More ktx2 levels ({}) than the size allows",
+        reader.levels().len()
+    );
+
+    let levels = reader
+        .levels()
+        .enumerate()
+        .map(|(level, data)| {
+            let (level_width, level_height) = self::level_dimensions(width, height, level);
+            let expected = format.level_size(level_width, level_height);
+            ensure!(
+                data.data.len() == expected,
+                "Ktx2 level {} holds {} bytes instead of {}",
+                level,
+                data.data.len(),
+                expected
+            );
+            Ok(data.data.to_vec())
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Ktx2Texture {
+        format,
+        width,
+        height,
+        levels,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    /* a void extent astc block, every pixel opaque red */
+    const RED_ASTC_BLOCK: [u8; BLOCK_SIZE] = [
+        0xFC, 0xFD, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00, 0xFF,
+        0xFF,
+    ];
+
+    fn fixture(name: &str) -> Vec<u8> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join(name);
+        std::fs::read(path).unwrap()
+    }
+
+    #[test]
+    fn rgba8_levels_are_read_in_order() {
+        /* the file stores the smallest level first */
+        let texture = parse(&self::fixture("rgba8_mips.ktx2")).unwrap();
+        assert_eq!(texture.format, Ktx2Format::Rgba8 { srgb: true });
+        assert_eq!((texture.width, texture.height), (4, 2));
+        assert_eq!(
+            texture.levels,
+            [vec![1; 4 * 2 * 4], vec![2; 2 * 4], vec![3; 4]]
+        );
+        assert_eq!(texture.data().len(), 32 + 8 + 4);
+    }
+
+    #[test]
+    fn compressed_levels_round_up_to_whole_blocks() {
+        /* 8x4, 4x2 and 2x1 all take whole 4x4 blocks */
+        let texture = parse(&self::fixture("bc7_mips.ktx2")).unwrap();
+        assert_eq!(texture.format, Ktx2Format::Bc7 { srgb: false });
+        let sizes: Vec<usize> = texture.levels.iter().map(Vec::len).collect();
+        assert_eq!(sizes, [2 * BLOCK_SIZE, BLOCK_SIZE, BLOCK_SIZE]);
+        assert_eq!(
+            texture.format.required_features(),
+            Features::TEXTURE_COMPRESSION_BC
+        );
+    }
+
+    #[test]
+    fn bc7_is_decoded_without_the_bc_feature() {
+        let texture = parse(&self::fixture("bc7_mips.ktx2")).unwrap();
+        let kept = texture
+            .clone()
+            .for_features(Features::TEXTURE_COMPRESSION_BC)
+            .unwrap();
+        assert_eq!(kept.format, Ktx2Format::Bc7 { srgb: false });
+        /* the fixture is opaque white */
+        let decoded = texture.for_features(Features::empty()).unwrap();
+        assert_eq!(decoded.format, Ktx2Format::Rgba8 { srgb: false });
+        assert_eq!(
+            decoded.levels,
+            [vec![255; 8 * 4 * 4], vec![255; 4 * 2 * 4], vec![255; 2 * 4]]
+        );
+    }
+
+    #[test]
+    fn astc_is_decoded_without_the_astc_feature() {
+        let texture = Ktx2Texture {
+            format: Ktx2Format::Astc4x4 { srgb: true },
+            width: 4,
+            height: 4,
+            levels: vec![RED_ASTC_BLOCK.to_vec(), RED_ASTC_BLOCK.to_vec()],
+        };
+        let decoded = texture.for_features(Features::empty()).unwrap();
+        assert_eq!(decoded.format, Ktx2Format::Rgba8 { srgb: true });
+        assert_eq!(decoded.levels[0], [255, 0, 0, 255].repeat(16));
+        assert_eq!(decoded.levels[1], [255, 0, 0, 255].repeat(4));
+    }
+
+    #[test]
+    fn malformed_files_are_rejected() {
+        let error = |bytes: &[u8]| parse(bytes).unwrap_err().to_string();
+        /* a header field of the fixture, counted after the identifier */
+        let patched = |field: usize, value: u32| {
+            let mut bytes = self::fixture("rgba8_mips.ktx2");
+            let offset = IDENTIFIER.len() + field * 4;
+            bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+            bytes
+        };
+        assert_eq!(error(b"PNG"), "Not a ktx2 file");
+        assert_eq!(error(&IDENTIFIER), "Malformed ktx2 file");
+        assert_eq!(error(&patched(0, 23)), "Unsupported ktx2 vkFormat 23");
+        assert_eq!(
+            error(&patched(2, 8)),
+            "Ktx2 level 0 holds 32 bytes instead of 64"
+        );
+        let mut truncated = self::fixture("rgba8_mips.ktx2");
+        truncated.truncate(truncated.len() - 1);
+        assert_eq!(error(&truncated), "Malformed ktx2 file");
+        assert_eq!(
+            error(&patched(0, 145)),
+            "Compressed ktx2 size 4x2 is not a multiple of the block size"
+        );
+        assert_eq!(
+            error(&patched(6, 6)),
+            "Cube map ktx2 textures are not supported"
+        );
+        assert_eq!(
+            error(&patched(2, 1)),
+            "More ktx2 levels (3) than the size allows"
+        );
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::BTreeSet;
+
+use glam::{DMat4, DVec2, DVec3, DVec4};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    graphics::{camera::Camera, viewport::Viewport},
+    simulation::SimulationBody,
+};
+
+pub const LABEL_MARKER_RADIUS: f32 = 2.5;
+/* from the marker to the top left corner of the text, in ui pixels */
+pub const LABEL_TEXT_OFFSET: [f32; 2] = [6., -7.];
+pub const LABEL_TEXT_COLOR: [f32; 4] = [0.9, 0.9, 0.92, 1.];
+pub const LABEL_MARKER_COLOR: [f32; 4] = [0.9, 0.9, 0.92, 0.8];
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct LabelSettings {
+    #[serde(rename = "enabled")]
+    pub enabled: bool,
+    /* bodies carrying any of these tags are not labeled, sorted so the settings file is stable */
+    #[serde(rename = "hidden tags")]
+    pub hidden_tags: BTreeSet<String>,
+}
+
+impl Default for LabelSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            hidden_tags: BTreeSet::new(),
+        }
+    }
+}
+
+impl LabelSettings {
+    pub fn shows(&self, body: &SimulationBody) -> bool {
+        self.enabled
+            && !body
+                .appearance
+                .tags
+                .iter()
+                .any(|tag| self.hidden_tags.contains(tag))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LabelCandidate {
+    pub text: String,
+    /* projected body center in viewport pixels */
+    pub anchor: DVec2,
+    /* decides which label survives an overlap */
+    pub radius: f64,
+}
+
+/* projects an eye relative position into viewport pixels with the origin top left.
+none behind the camera or outside the viewport */
+pub fn project_to_viewport(
+    view_projection: DMat4,
+    relative_position: DVec3,
+    viewport_size: DVec2,
+) -> Option<DVec2> {
+    let clip = view_projection * DVec4::from((relative_position, 1.));
+    if clip.w <= 0. {
+        return None;
+    }
+    let ndc = clip.truncate() / clip.w;
+    if ndc.x.abs() > 1. || ndc.y.abs() > 1. {
+        return None;
+    }
+    Some(DVec2::new(
+        (ndc.x + 1.) * 0.5 * viewport_size.x,
+        (1. - ndc.y) * 0.5 * viewport_size.y,
+    ))
+}
+
+/* visible labels, largest bodies first so they win the de-cluttering */
+pub fn label_candidates(
+    camera: &Camera,
+    bodies: &[SimulationBody],
+    settings: &LabelSettings,
+) -> Vec<LabelCandidate> {
+    if !settings.enabled {
+        return Vec::new();
+    }
+    let view_projection = camera.view_projection().as_dmat4();
+    let viewport_size = DVec2::from_array(camera.viewport_size().map(f64::from));
+    let eye = camera.position();
+    let mut candidates: Vec<LabelCandidate> = bodies
+        .iter()
+        .filter(|body| settings.shows(body))
+        .filter_map(|body| {
+            let anchor =
+                self::project_to_viewport(view_projection, body.position - eye, viewport_size)?;
+            Some(LabelCandidate {
+                text: body.id_name.clone(),
+                anchor,
+                radius: body.radius,
+            })
+        })
+        .collect();
+    candidates.sort_by(|left, right| right.radius.total_cmp(&left.radius));
+    candidates
+}
+
+/* keeps every rect that does not overlap an earlier kept one, rects are [x, y, width, height] */
+pub fn declutter(rects: &[[f32; 4]]) -> Vec<bool> {
+    let mut kept: Vec<[f32; 4]> = Vec::new();
+    rects
+        .iter()
+        .map(|rect| {
+            let overlaps = kept.iter().any(|other| {
+                rect[0] < other[0] + other[2]
+                    && other[0] < rect[0] + rect[2]
+                    && rect[1] < other[1] + other[3]
+                    && other[1] < rect[1] + rect[3]
+            });
+            if !overlaps {
+                kept.push(*rect);
+            }
+            !overlaps
+        })
+        .collect()
+}
+
+/* called inside the viewport window, its draw list lies over the scene image and under every
+other window. the background draw list would end up behind the viewport window itself */
+pub fn draw(ui: &imgui::Ui, viewport: &Viewport, candidates: &[LabelCandidate]) {
+    let placed: Vec<([f32; 2], [f32; 4])> = candidates
+        .iter()
+        .map(|candidate| {
+            let marker = viewport.to_ui([candidate.anchor.x as f32, candidate.anchor.y as f32]);
+            let size = ui.calc_text_size(&candidate.text);
+            let rect = [
+                marker[0] + LABEL_TEXT_OFFSET[0],
+                marker[1] + LABEL_TEXT_OFFSET[1],
+                size[0],
+                size[1],
+            ];
+            (marker, rect)
+        })
+        .collect();
+    let rects: Vec<[f32; 4]> = placed.iter().map(|(_, rect)| *rect).collect();
+    let draw_list = ui.get_window_draw_list();
+    candidates
+        .iter()
+        .zip(placed.iter())
+        .zip(self::declutter(&rects))
+        .filter(|(_, kept)| *kept)
+        .for_each(|((candidate, (marker, rect)), _)| {
+            draw_list
+                .add_circle(*marker, LABEL_MARKER_RADIUS, LABEL_MARKER_COLOR)
+                .filled(true)
+                .build();
+            draw_list.add_text([rect[0], rect[1]], LABEL_TEXT_COLOR, &candidate.text);
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{BodyAppearance, BodyId};
+
+    const VIEWPORT: DVec2 = DVec2::new(800., 600.);
+
+    /* looking down -z from 10 units, the view plane spans tan(fov / 2) * 10 up and down */
+    fn camera() -> Camera {
+        let mut camera = Camera::new(VIEWPORT.x as u32, VIEWPORT.y as u32);
+        camera.set_look_at(DVec3::new(0., 0., 10.), DVec3::ZERO);
+        camera
+    }
+
+    fn body(name: &str, position: DVec3, radius: f64, tags: &[&str]) -> SimulationBody {
+        SimulationBody {
+            id: BodyId(0),
+            id_name: name.to_string(),
+            mass: 1.,
+            radius,
+            position,
+            velocity: DVec3::ZERO,
+            appearance: BodyAppearance {
+                tags: tags.iter().map(|tag| tag.to_string()).collect(),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn points_project_to_known_pixels() {
+        let camera = self::camera();
+        let view_projection = camera.view_projection().as_dmat4();
+        let relative = |point: DVec3| point - camera.position();
+        let center = project_to_viewport(view_projection, relative(DVec3::ZERO), VIEWPORT);
+        assert!(center.unwrap().distance(VIEWPORT / 2.) < 1e-3);
+        /* on the top edge of the field of view */
+        let half_height = 10. * (camera.field_of_view() as f64 / 2.).tan();
+        let top = project_to_viewport(
+            view_projection,
+            relative(DVec3::new(0., half_height * 0.999, 0.)),
+            VIEWPORT,
+        )
+        .unwrap();
+        assert!(top.y.abs() < 1. && (top.x - 400.).abs() < 1e-3, "{}", top);
+        let right = project_to_viewport(
+            view_projection,
+            relative(DVec3::new(half_height / 2., 0., 0.)),
+            VIEWPORT,
+        )
+        .unwrap();
+        /* half of the half height to the side is 3/8 of the wider half width */
+        assert!((right.x - (400. + 400. * 0.375)).abs() < 1e-3, "{}", right);
+    }
+
+    #[test]
+    fn points_behind_or_outside_are_clipped() {
+        let camera = self::camera();
+        let view_projection = camera.view_projection().as_dmat4();
+        let relative = |point: DVec3| point - camera.position();
+        assert_eq!(
+            project_to_viewport(view_projection, relative(DVec3::new(0., 0., 20.)), VIEWPORT),
+            None
+        );
+        assert_eq!(
+            project_to_viewport(
+                view_projection,
+                relative(DVec3::new(0., 100., 0.)),
+                VIEWPORT
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn candidates_are_filtered_and_sorted_by_size() {
+        let camera = self::camera();
+        let bodies = [
+            self::body("Moon", DVec3::new(1., 0., 0.), 1.7e6, &["moon"]),
+            self::body("Probe", DVec3::new(-1., 0., 0.), 5., &["spacecraft"]),
+            self::body("Earth", DVec3::ZERO, 6.4e6, &["planet"]),
+            self::body("Behind", DVec3::new(0., 0., 50.), 1e9, &[]),
+        ];
+        let mut settings = LabelSettings::default();
+        settings.hidden_tags.insert("spacecraft".to_string());
+        let names: Vec<String> = label_candidates(&camera, &bodies, &settings)
+            .into_iter()
+            .map(|candidate| candidate.text)
+            .collect();
+        assert_eq!(names, ["Earth", "Moon"]);
+        settings.enabled = false;
+        assert!(label_candidates(&camera, &bodies, &settings).is_empty());
+    }
+
+    #[test]
+    fn overlapping_labels_after_the_first_are_dropped() {
+        let kept = declutter(&[
+            [0., 0., 10., 10.],
+            [5., 5., 10., 10.],
+            [10., 0., 10., 10.],
+            [20., 20., 1., 1.],
+            [9., 9., 2., 2.],
+        ]);
+        assert_eq!(kept, [true, false, true, true, false]);
+    }
+}
@@ -0,0 +1,132 @@
+use imgui::{Condition, ListClipper};
+use tracing::Level;
+
+use crate::logging::{LogBuffer, LogRecord};
+
+use crate::localization;
+
+pub const LOG_WINDOW_TITLE: &str = "Log";
+pub const LOG_WINDOW_SIZE: [f32; 2] = [640., 300.];
+/* the logging pipeline passes nothing beyond info, see application::execute */
+const LEVELS: [Level; 3] = [Level::ERROR, Level::WARN, Level::INFO];
+
+#[derive(Debug)]
+pub struct LogWindow {
+    pub open: bool,
+    /* most verbose level shown */
+    level: Level,
+    search: String,
+    auto_scroll: bool,
+}
+
+impl Default for LogWindow {
+    fn default() -> Self {
+        Self {
+            open: false,
+            level: Level::INFO,
+            search: String::new(),
+            auto_scroll: true,
+        }
+    }
+}
+
+impl LogWindow {
+    pub fn draw_menu_item(&mut self, ui: &imgui::Ui) {
+        if ui.menu_item(localization::tr("menu-log")) {
+            self.open = true;
+        }
+    }
+
+    pub fn draw(&mut self, ui: &imgui::Ui, buffer: &LogBuffer) {
+        if !self.open {
+            return;
+        }
+        let mut open = true;
+        ui.window(localization::title("window-log", LOG_WINDOW_TITLE))
+            .opened(&mut open)
+            .size(LOG_WINDOW_SIZE, Condition::FirstUseEver)
+            .build(|| {
+                ui.set_next_item_width(90.);
+                if let Some(_combo) = ui.begin_combo("Level", self.level.as_str()) {
+                    LEVELS.into_iter().for_each(|level| {
+                        if ui
+                            .selectable_config(level.as_str())
+                            .selected(level == self.level)
+                            .build()
+                        {
+                            self.level = level;
+                        }
+                    });
+                }
+                ui.same_line();
+                ui.set_next_item_width(200.);
+                ui.input_text("Search", &mut self.search).build();
+                ui.same_line();
+                ui.checkbox("Auto-scroll", &mut self.auto_scroll);
+
+                let records = buffer.records();
+                let search = self.search.to_lowercase();
+                let shown = records
+                    .iter()
+                    .filter(|record| record.level <= self.level)
+                    .filter(|record| {
+                        search.is_empty()
+                            || record.message.to_lowercase().contains(&search)
+                            || record.target.to_lowercase().contains(&search)
+                    })
+                    .collect::<Vec<_>>();
+                ui.same_line();
+                if ui.button("Copy") {
+                    let text = shown
+                        .iter()
+                        .map(|record| self::format_record(record))
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                    ui.set_clipboard_text(text);
+                }
+                ui.same_line();
+                let clear = ui.button("Clear");
+                ui.separator();
+
+                ui.child_window("##log lines")
+                    .horizontal_scrollbar(true)
+                    .build(|| {
+                        let mut clipper = ListClipper::new(shown.len() as i32).begin(ui);
+                        while clipper.step() {
+                            for index in clipper.display_start()..clipper.display_end() {
+                                let record = shown[index as usize];
+                                ui.text_colored(
+                                    self::level_color(record.level),
+                                    self::format_record(record),
+                                );
+                            }
+                        }
+                        /* only while at the bottom, so scrolling up to read stays put */
+                        if self.auto_scroll && ui.scroll_y() >= ui.scroll_max_y() {
+                            ui.set_scroll_here_y_with_ratio(1.);
+                        }
+                    });
+                drop(records);
+                if clear {
+                    buffer.clear();
+                }
+            });
+        self.open = open;
+    }
+}
+
+pub fn format_record(record: &LogRecord) -> String {
+    format!(
+        "{} {:>5} {}: {}",
+        record.timestamp, record.level, record.target, record.message
+    )
+}
+
+fn level_color(level: Level) -> [f32; 4] {
+    match level {
+        Level::ERROR => [1., 0.35, 0.3, 1.],
+        Level::WARN => [1., 0.8, 0.3, 1.],
+        Level::INFO => [0.85, 0.85, 0.85, 1.],
+        _ => [0.55, 0.55, 0.55, 1.],
+    }
+}
@@ -0,0 +1,86 @@
+use image::{RgbaImage, imageops::FilterType};
+
+/* full chain down to 1x1, floor(log2(largest side)) + 1 */
+pub fn mip_level_count(width: u32, height: u32) -> u32 {
+    u32::BITS - width.max(height).max(1).leading_zeros()
+}
+
+/* each level halves with floor division and never drops below one texel */
+pub fn mip_level_size(width: u32, height: u32, level: u32) -> (u32, u32) {
+    (
+        width.checked_shr(level).unwrap_or(0).max(1),
+        height.checked_shr(level).unwrap_or(0).max(1),
+    )
+}
+
+/* downsampled on the cpu from the previous level, level 0 is the image itself.
+filtering happens on the stored (srgb) values, close enough for planet maps */
+pub fn generate_mip_chain(image: RgbaImage) -> Vec<RgbaImage> {
+    let (width, height) = image.dimensions();
+    let level_count = self::mip_level_count(width, height);
+    let mut levels = Vec::with_capacity(level_count as usize);
+    levels.push(image);
+    for level in 1..level_count {
+        let (level_width, level_height) = self::mip_level_size(width, height, level);
+        let previous = levels.last().unwrap();
+        levels.push(image::imageops::resize(
+            previous,
+            level_width,
+            level_height,
+            FilterType::Triangle,
+        ));
+    }
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn level_count_reaches_one_texel() {
+        let cases = [
+            ((1, 1), 1),
+            ((2, 2), 2),
+            ((256, 256), 9),
+            ((2048, 1024), 12),
+            ((1000, 10), 10),
+            ((3, 1000), 10),
+            ((0, 0), 1),
+        ];
+        for ((width, height), count) in cases {
+            assert_eq!(
+                mip_level_count(width, height),
+                count,
+                "{}x{}",
+                width,
+                height
+            );
+        }
+    }
+
+    #[test]
+    fn level_sizes_halve_and_stop_at_one() {
+        assert_eq!(mip_level_size(2048, 1024, 0), (2048, 1024));
+        assert_eq!(mip_level_size(2048, 1024, 1), (1024, 512));
+        assert_eq!(mip_level_size(2048, 1024, 10), (2, 1));
+        assert_eq!(mip_level_size(2048, 1024, 11), (1, 1));
+        assert_eq!(mip_level_size(1000, 10, 3), (125, 1));
+        assert_eq!(mip_level_size(7, 5, 40), (1, 1));
+    }
+
+    #[test]
+    fn chain_has_every_level_at_its_size() {
+        let image = RgbaImage::from_pixel(20, 6, image::Rgba([200, 100, 50, 255]));
+        let levels = generate_mip_chain(image);
+        assert_eq!(levels.len(), mip_level_count(20, 6) as usize);
+        for (level, image) in levels.iter().enumerate() {
+            assert_eq!(image.dimensions(), mip_level_size(20, 6, level as u32));
+        }
+        /* a flat color stays the same color */
+        assert_eq!(
+            levels.last().unwrap().get_pixel(0, 0).0,
+            [200, 100, 50, 255]
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use bytemuck::{Pod, Zeroable};
+use wgpu::{Device, Queue, TextureFormat, util::DeviceExt};
+
+use crate::{
+    graphics::{
+        billboards::{self, BillboardSettings},
+        camera::Camera,
+        pipeline::{self, BODY_RADIUS_RENDER_SCALE, SPHERE_SECTORS, SPHERE_STACKS},
+        shaders,
+        stats::DrawCounts,
+        targets::{DEPTH_COMPARE, DEPTH_FORMAT},
+    },
+    simulation::SimulationBody,
+};
+
+/* rim width in viewport pixels, independent of the distance */
+pub const OUTLINE_PIXELS: f32 = 2.5;
+pub const OUTLINE_COLOR: [f32; 4] = [1., 0.75, 0.2, 1.];
+
+/* the hull hugs a resolved sphere, the ring surrounds the dot of a body drawn as billboard */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutlineShape {
+    Hull,
+    Ring,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct OutlineInstance {
+    /* relative to the eye */
+    pub center: [f32; 3],
+    /* rendered radius grown by the rim for the hull, inner radius in pixels for the ring */
+    pub radius: f32,
+    pub color: [f32; 4],
+}
+
+impl OutlineInstance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![1 => Float32x3, 2 => Float32, 3 => Float32x4];
+}
+
+/* none when the camera sits inside the body. the ring takes over as soon as the dot shows, its
+inner edge follows the dot or the sphere, whichever is larger */
+pub fn selection_outline(
+    camera: &Camera,
+    body: &SimulationBody,
+    settings: &BillboardSettings,
+) -> Option<(OutlineShape, OutlineInstance)> {
+    let projected = billboards::projected_radius(camera, body)?;
+    let relative = body.position - camera.position();
+    let center = relative.as_vec3().to_array();
+    if billboards::billboard_weight(projected, settings.minimum_pixels as f64) > 0. {
+        let instance = OutlineInstance {
+            center,
+            radius: settings.visual_pixels(body).max(projected as f32),
+            color: OUTLINE_COLOR,
+        };
+        return Some((OutlineShape::Ring, instance));
+    }
+    let radius = body.radius * BODY_RADIUS_RENDER_SCALE;
+    let distance = relative.length();
+    if distance <= radius {
+        return None;
+    }
+    /* world size of a pixel at the body, the projected radius scales linearly with the radius */
+    let pixel = radius / projected;
+    let instance = OutlineInstance {
+        center,
+        radius: (radius + OUTLINE_PIXELS as f64 * pixel) as f32,
+        color: OUTLINE_COLOR,
+    };
+    Some((OutlineShape::Hull, instance))
+}
+
+/* inverted hull, the back faces of a grown sphere behind the body show up as its rim */
+pub struct OutlinePipeline {
+    pub hull_pipeline: wgpu::RenderPipeline,
+    pub ring_pipeline: wgpu::RenderPipeline,
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: wgpu::Buffer,
+    pub index_count: u32,
+    /* a single instance, rewritten every frame */
+    pub instance_buffer: wgpu::Buffer,
+    pub shape: Option<OutlineShape>,
+}
+
+impl OutlinePipeline {
+    pub fn new(
+        device: &Device,
+        color_format: TextureFormat,
+        sample_count: u32,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let shader = shaders::OUTLINE_SHADER.module(device);
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("outline pipeline layout"),
+            bind_group_layouts: &[camera_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let instance_layout = wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<OutlineInstance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &OutlineInstance::ATTRIBUTES,
+        };
+        let create_pipeline = |label: &str,
+                               entry_points: (&str, &str),
+                               buffers: &[wgpu::VertexBufferLayout],
+                               primitive: wgpu::PrimitiveState| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(label),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some(entry_points.0),
+                    compilation_options: Default::default(),
+                    buffers,
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some(entry_points.1),
+                    compilation_options: Default::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: color_format,
+                        blend: Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive,
+                /* hidden by the body itself and by everything in front of it */
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: DEPTH_FORMAT,
+                    depth_write_enabled: false,
+                    depth_compare: DEPTH_COMPARE,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: wgpu::MultisampleState {
+                    count: sample_count,
+                    ..Default::default()
+                },
+                multiview: None,
+                cache: None,
+            })
+        };
+        let hull_pipeline = create_pipeline(
+            "outline hull pipeline",
+            ("vs_hull", "fs_hull"),
+            &[
+                wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<pipeline::SphereVertex>()
+                        as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x3],
+                },
+                instance_layout.clone(),
+            ],
+            wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Front),
+                ..Default::default()
+            },
+        );
+        let ring_pipeline = create_pipeline(
+            "outline ring pipeline",
+            ("vs_ring", "fs_ring"),
+            &[instance_layout],
+            wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+        );
+
+        let (vertices, indices) = pipeline::build_uv_sphere(SPHERE_STACKS, SPHERE_SECTORS);
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("outline hull vertex buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("outline hull index buffer"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        Self {
+            hull_pipeline,
+            ring_pipeline,
+            vertex_buffer,
+            index_buffer,
+            index_count: indices.len() as u32,
+            instance_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("outline instance buffer"),
+                size: std::mem::size_of::<OutlineInstance>() as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }),
+            shape: None,
+        }
+    }
+
+    /* none clears the outline, for no selection or a selected body that is gone */
+    pub fn write_outline(
+        &mut self,
+        queue: &Queue,
+        outline: Option<(OutlineShape, OutlineInstance)>,
+    ) {
+        self.shape = outline.map(|(shape, instance)| {
+            queue.write_buffer(&self.instance_buffer, 0, bytemuck::bytes_of(&instance));
+            shape
+        });
+    }
+
+    pub fn draw(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        camera_bind_group: &wgpu::BindGroup,
+    ) {
+        let Some(shape) = self.shape else {
+            return;
+        };
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        match shape {
+            OutlineShape::Hull => {
+                render_pass.set_pipeline(&self.hull_pipeline);
+                render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+                render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+                render_pass
+                    .set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                render_pass.draw_indexed(0..self.index_count, 0, 0..1);
+            }
+            OutlineShape::Ring => {
+                render_pass.set_pipeline(&self.ring_pipeline);
+                render_pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+                render_pass.draw(0..4, 0..1);
+            }
+        }
+    }
+
+    pub fn draw_counts(&self) -> DrawCounts {
+        match self.shape {
+            Some(_) => DrawCounts::new(1, 1),
+            None => DrawCounts::default(),
+        }
+    }
+}
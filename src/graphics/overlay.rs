@@ -0,0 +1,34 @@
+use imgui::Condition;
+
+use crate::{graphics::stats::FramePacing, simulation::thread::SimulationSnapshot};
+
+pub const OVERLAY_MARGIN: f32 = 10.;
+pub const OVERLAY_BACKGROUND_ALPHA: f32 = 0.35;
+
+/* top left corner below the menu bar, without decoration and transparent to the mouse */
+pub fn draw(ui: &imgui::Ui, top: f32, pacing: &FramePacing, snapshot: &SimulationSnapshot) {
+    ui.window("##performance overlay")
+        .position([OVERLAY_MARGIN, top + OVERLAY_MARGIN], Condition::Always)
+        .bg_alpha(OVERLAY_BACKGROUND_ALPHA)
+        .no_decoration()
+        .always_auto_resize(true)
+        .focus_on_appearing(false)
+        .save_settings(false)
+        .no_nav()
+        .no_inputs()
+        .build(|| {
+            ui.text(format!(
+                "{:.0} fps ({:.2} ms)",
+                pacing.fps,
+                pacing.average_frame_time.as_secs_f64() * 1e3
+            ));
+            ui.text(format!("1% low: {:.0} fps", pacing.low_fps));
+            /* both rates are averages, a paused simulation steps zero times */
+            let steps_per_frame = match pacing.fps > 0. {
+                true => snapshot.steps_per_second / pacing.fps,
+                false => 0.,
+            };
+            ui.text(format!("{:.1} steps/frame", steps_per_frame));
+            ui.text(format!("{} bodies", snapshot.bodies.len()));
+        });
+}
@@ -0,0 +1,206 @@
+use glam::{DMat4, DVec2, DVec3, DVec4};
+
+use crate::{
+    graphics::{billboards::BillboardSettings, camera::Camera, pipeline::BODY_RADIUS_RENDER_SCALE},
+    simulation::{BodyId, SimulationBody},
+};
+
+/* bodies smaller than this on screen are still hit within this many pixels of their center */
+pub const MINIMUM_PICK_RADIUS_PIXELS: f64 = 8.;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    pub origin: DVec3,
+    /* normalized */
+    pub direction: DVec3,
+}
+
+/* unprojects a cursor position in physical pixels through the near plane (depth 1 in reversed-z)
+and a point behind it, the far plane sits at infinity and cannot be unprojected.
+done in f64 since the matrices span astronomical distances */
+pub fn screen_ray(view_projection: DMat4, cursor: DVec2, viewport_size: DVec2) -> Option<Ray> {
+    let inverse = view_projection.inverse();
+    let ndc = DVec2::new(
+        2. * cursor.x / viewport_size.x - 1.,
+        1. - 2. * cursor.y / viewport_size.y,
+    );
+    let unproject = |depth: f64| {
+        let point = inverse * DVec4::new(ndc.x, ndc.y, depth, 1.);
+        point.truncate() / point.w
+    };
+    let near = unproject(1.);
+    let direction = (unproject(0.5) - near).try_normalize()?;
+    Some(Ray {
+        origin: near,
+        direction,
+    })
+}
+
+/* distance along the ray to the first intersection in front of the origin */
+pub fn ray_sphere_intersection(ray: &Ray, center: DVec3, radius: f64) -> Option<f64> {
+    let offset = ray.origin - center;
+    let half_b = offset.dot(ray.direction);
+    let c = offset.length_squared() - radius * radius;
+    let discriminant = half_b * half_b - c;
+    if discriminant < 0. {
+        return None;
+    }
+    let root = discriminant.sqrt();
+    [-half_b - root, -half_b + root]
+        .into_iter()
+        .find(|distance| *distance >= 0.)
+}
+
+/* nearest body under the cursor, tested against the inflated render radius or the billboard
+drawn in place of a small sphere */
+pub fn pick_body(
+    camera: &Camera,
+    cursor: DVec2,
+    bodies: &[SimulationBody],
+    billboard_settings: &BillboardSettings,
+) -> Option<BodyId> {
+    let viewport_size = DVec2::from_array(camera.viewport_size().map(f64::from));
+    /* the view matrix is eye relative, the ray is moved back into world space */
+    let mut ray = self::screen_ray(camera.view_projection().as_dmat4(), cursor, viewport_size)?;
+    ray.origin += camera.position();
+    /* world size of one pixel per unit of distance from the camera */
+    let pixel_angle = 2. * (camera.field_of_view() as f64 / 2.).tan() / viewport_size.y;
+    bodies
+        .iter()
+        .filter_map(|body| {
+            let distance = ray.origin.distance(body.position);
+            let minimum_pixels =
+                MINIMUM_PICK_RADIUS_PIXELS.max(billboard_settings.visual_pixels(body) as f64);
+            let radius = (body.radius * BODY_RADIUS_RENDER_SCALE)
+                .max(distance * pixel_angle * minimum_pixels);
+            self::ray_sphere_intersection(&ray, body.position, radius).map(|hit| (hit, body.id))
+        })
+        .min_by(|(left, _), (right, _)| left.total_cmp(right))
+        .map(|(_, id)| id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::BodyAppearance;
+
+    const EPSILON: f64 = 1e-9;
+
+    fn ray(origin: DVec3, direction: DVec3) -> Ray {
+        Ray {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    fn body(id: u32, position: DVec3, radius: f64) -> SimulationBody {
+        SimulationBody {
+            id: BodyId(id),
+            id_name: format!("Body {}", id),
+            mass: 1.,
+            radius,
+            position,
+            velocity: DVec3::ZERO,
+            appearance: BodyAppearance::default(),
+        }
+    }
+
+    #[test]
+    fn ray_hits_the_near_side_of_the_sphere() {
+        let ray = self::ray(DVec3::new(0., 0., 10.), DVec3::NEG_Z);
+        let hit = ray_sphere_intersection(&ray, DVec3::ZERO, 2.).unwrap();
+        assert!((hit - 8.).abs() < EPSILON);
+    }
+
+    #[test]
+    fn ray_from_inside_hits_the_far_side() {
+        let ray = self::ray(DVec3::ZERO, DVec3::X);
+        let hit = ray_sphere_intersection(&ray, DVec3::ZERO, 3.).unwrap();
+        assert!((hit - 3.).abs() < EPSILON);
+    }
+
+    #[test]
+    fn ray_misses_spheres_beside_and_behind_it() {
+        let ray = self::ray(DVec3::new(0., 0., 10.), DVec3::NEG_Z);
+        assert_eq!(
+            ray_sphere_intersection(&ray, DVec3::new(3., 0., 0.), 2.),
+            None
+        );
+        assert_eq!(
+            ray_sphere_intersection(&ray, DVec3::new(0., 0., 20.), 2.),
+            None
+        );
+        let grazing = ray_sphere_intersection(&ray, DVec3::new(2., 0., 0.), 2.).unwrap();
+        assert!((grazing - 10.).abs() < EPSILON);
+    }
+
+    #[test]
+    fn screen_center_unprojects_along_the_view() {
+        let mut camera = Camera::new(800, 600);
+        camera.set_look_at(DVec3::new(0., 0., 50.), DVec3::ZERO);
+        let ray = screen_ray(
+            camera.view_projection().as_dmat4(),
+            DVec2::new(400., 300.),
+            DVec2::new(800., 600.),
+        )
+        .unwrap();
+        assert!(
+            ray.direction.distance(DVec3::NEG_Z) < 1e-6,
+            "{}",
+            ray.direction
+        );
+        assert!(ray.origin.length() < 1., "the near plane is at the eye");
+    }
+
+    #[test]
+    fn screen_corner_unprojects_to_the_edge_of_the_field_of_view() {
+        let mut camera = Camera::new(600, 600);
+        camera.set_look_at(DVec3::new(0., 0., 50.), DVec3::ZERO);
+        let ray = screen_ray(
+            camera.view_projection().as_dmat4(),
+            DVec2::new(300., 0.),
+            DVec2::new(600., 600.),
+        )
+        .unwrap();
+        let angle = ray.direction.angle_between(DVec3::NEG_Z);
+        assert!((angle - camera.field_of_view() as f64 / 2.).abs() < 1e-6);
+        assert!(ray.direction.y > 0.);
+    }
+
+    #[test]
+    fn nearest_body_under_the_cursor_is_picked() {
+        let mut camera = Camera::new(800, 600);
+        camera.set_look_at(DVec3::new(0., 0., 1e4), DVec3::ZERO);
+        let bodies = [
+            self::body(0, DVec3::new(0., 0., -500.), 10.),
+            self::body(1, DVec3::new(0., 0., 500.), 10.),
+        ];
+        let settings = BillboardSettings::default();
+        let center = DVec2::new(400., 300.);
+        assert_eq!(
+            pick_body(&camera, center, &bodies, &settings),
+            Some(BodyId(1))
+        );
+        assert_eq!(
+            pick_body(&camera, DVec2::new(10., 10.), &bodies, &settings),
+            None
+        );
+    }
+
+    /* a body far below a pixel is still hit within the minimum radius of its center */
+    #[test]
+    fn tiny_bodies_are_hit_within_the_minimum_radius() {
+        let mut camera = Camera::new(800, 600);
+        camera.set_look_at(DVec3::new(0., 0., 1e9), DVec3::ZERO);
+        let bodies = [self::body(0, DVec3::ZERO, 1.)];
+        let settings = BillboardSettings { minimum_pixels: 0. };
+        let offset = MINIMUM_PICK_RADIUS_PIXELS - 2.;
+        let near_miss = DVec2::new(400. + offset, 300.);
+        assert_eq!(
+            pick_body(&camera, near_miss, &bodies, &settings),
+            Some(BodyId(0))
+        );
+        let far_miss = DVec2::new(400. + MINIMUM_PICK_RADIUS_PIXELS + 2., 300.);
+        assert_eq!(pick_body(&camera, far_miss, &bodies, &settings), None);
+    }
+}
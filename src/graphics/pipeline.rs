@@ -0,0 +1,455 @@
+use std::{f32::consts::PI, ops::Range};
+
+use bytemuck::{Pod, Zeroable};
+use glam::{DVec3, Vec3};
+use strum::IntoEnumIterator;
+use wgpu::{Device, Queue, TextureFormat, util::DeviceExt};
+
+use crate::{
+    graphics::{
+        body_texture::BodyTextureCache,
+        shaders,
+        stats::DrawCounts,
+        targets::{DEPTH_COMPARE, DEPTH_FORMAT},
+    },
+    simulation::SimulationBody,
+};
+
+pub const SPHERE_STACKS: u32 = 24;
+pub const SPHERE_SECTORS: u32 = 48;
+pub const INITIAL_INSTANCE_CAPACITY: usize = 16;
+/* real radii are invisible next to orbital distances, bodies are drawn inflated by this factor */
+pub const BODY_RADIUS_RENDER_SCALE: f64 = 20.;
+/* key light for scenes without any emissive body */
+pub const DEFAULT_LIGHT_DIRECTION: Vec3 = Vec3::new(0.4, 0.8, 0.45);
+
+const BODY_COLOR_PALETTE: [[f32; 4]; 6] = [
+    [1.0, 0.85, 0.45, 1.0],
+    [0.35, 0.6, 1.0, 1.0],
+    [0.75, 0.75, 0.72, 1.0],
+    [0.9, 0.45, 0.3, 1.0],
+    [0.55, 0.85, 0.55, 1.0],
+    [0.8, 0.55, 0.9, 1.0],
+];
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct SphereVertex {
+    pub position: [f32; 3],
+    /* equirectangular, u follows the longitude and v runs from the north to the south pole */
+    pub uv: [f32; 2],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct BodyInstance {
+    pub center: [f32; 3],
+    pub radius: f32,
+    pub color: [f32; 4],
+    /* radiance multiplier of unlit bodies, 0 for lit bodies */
+    pub emission: f32,
+    /* normalized, towards the star lighting this body */
+    pub light_direction: [f32; 3],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BodyShading {
+    /* stars, and every body while lighting is disabled */
+    Unlit { emission: f32 },
+    Lit { light_direction: Vec3 },
+}
+
+/* keeps emissive bodies well above the bloom threshold after shading */
+pub const EMISSIVE_INTENSITY: f32 = 6.;
+
+/* consecutive instances sharing one texture bind group */
+#[derive(Debug, Clone, PartialEq)]
+pub struct BodyBatch {
+    pub texture: Option<String>,
+    pub instances: Range<u32>,
+}
+
+/* how the body meshes are shaded, for chasing mesh and instancing bugs */
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Default, strum_macros::EnumIter, strum_macros::Display,
+)]
+pub enum DebugRenderMode {
+    #[default]
+    #[strum(to_string = "shaded")]
+    Shaded,
+    /* polygon lines where the adapter supports them, triangle edges found in the shader otherwise */
+    #[strum(to_string = "wireframe")]
+    Wireframe,
+    /* world space normal as color */
+    #[strum(to_string = "normals")]
+    Normals,
+    /* every fragment adds the same amount, ignoring depth */
+    #[strum(to_string = "overdraw")]
+    Overdraw,
+}
+
+impl DebugRenderMode {
+    fn index(&self) -> usize {
+        match self {
+            DebugRenderMode::Shaded => 0,
+            DebugRenderMode::Wireframe => 1,
+            DebugRenderMode::Normals => 2,
+            DebugRenderMode::Overdraw => 3,
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        DebugRenderMode::iter()
+            .cycle()
+            .skip_while(|mode| mode != self)
+            .nth(1)
+            .unwrap_or(*self)
+    }
+}
+
+pub struct BodyPipeline {
+    /* one per DebugRenderMode, all compiled up front so switching modes never hitches */
+    pub render_pipelines: Vec<wgpu::RenderPipeline>,
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: wgpu::Buffer,
+    pub index_count: u32,
+    pub instance_buffer: wgpu::Buffer,
+    pub instance_capacity: usize,
+    pub instance_count: u32,
+    pub batches: Vec<BodyBatch>,
+}
+
+impl BodyInstance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 5] = wgpu::vertex_attr_array![
+        1 => Float32x3, 2 => Float32, 3 => Float32x4, 5 => Float32, 6 => Float32x3
+    ];
+
+    /* the color tints the texture, textured bodies are passed white.
+    the center is made relative to the eye in f64 before it is narrowed to f32 */
+    pub fn from_body(
+        body: &SimulationBody,
+        eye: DVec3,
+        color: [f32; 4],
+        shading: BodyShading,
+    ) -> Self {
+        let (emission, light_direction) = match shading {
+            BodyShading::Unlit { emission } => (emission, DEFAULT_LIGHT_DIRECTION.normalize()),
+            BodyShading::Lit { light_direction } => (0., light_direction),
+        };
+        Self {
+            center: (body.position - eye).as_vec3().to_array(),
+            radius: (body.radius * BODY_RADIUS_RENDER_SCALE) as f32,
+            color,
+            emission,
+            light_direction: light_direction.to_array(),
+        }
+    }
+}
+
+/* stars glow, everything else is lit by the star delivering the most flux (luminosity over
+squared distance), or by the default key light when the scene has none */
+pub fn body_shading(
+    body: &SimulationBody,
+    bodies: &[SimulationBody],
+    lighting_enabled: bool,
+) -> BodyShading {
+    if body.appearance.emissive {
+        return BodyShading::Unlit {
+            emission: EMISSIVE_INTENSITY,
+        };
+    }
+    if !lighting_enabled {
+        return BodyShading::Unlit { emission: 1. };
+    }
+    let light_direction = self::dominant_light(body, bodies)
+        .map(|direction| direction.as_vec3())
+        .unwrap_or(DEFAULT_LIGHT_DIRECTION.normalize());
+    BodyShading::Lit { light_direction }
+}
+
+/* direction from the body towards its dominant star */
+pub fn dominant_light(body: &SimulationBody, bodies: &[SimulationBody]) -> Option<DVec3> {
+    bodies
+        .iter()
+        .filter(|star| star.appearance.emissive && star.id != body.id)
+        .filter_map(|star| {
+            let offset = star.position - body.position;
+            let flux = star.appearance.luminosity.unwrap_or(1.) / offset.length_squared();
+            Some((flux, offset.try_normalize()?))
+        })
+        .max_by(|(left, _), (right, _)| left.total_cmp(right))
+        .map(|(_, direction)| direction)
+}
+
+/* the scenario color, or a palette color assigned by draw order so trails and spheres match */
+pub fn body_color(body: &SimulationBody, index: usize) -> [f32; 4] {
+    match body.appearance.color {
+        Some([r, g, b]) => [r, g, b, 1.0],
+        None => BODY_COLOR_PALETTE[index % BODY_COLOR_PALETTE.len()],
+    }
+}
+
+/* sorts instances by texture so every texture is bound once per frame */
+pub fn batch_by_texture(
+    mut entries: Vec<(Option<String>, BodyInstance)>,
+) -> (Vec<BodyInstance>, Vec<BodyBatch>) {
+    entries.sort_by(|(left, _), (right, _)| left.cmp(right));
+    let mut batches: Vec<BodyBatch> = Vec::new();
+    let instances = entries
+        .into_iter()
+        .enumerate()
+        .map(|(index, (texture, instance))| {
+            let index = index as u32;
+            match batches.last_mut() {
+                Some(batch) if batch.texture == texture => batch.instances.end = index + 1,
+                _ => batches.push(BodyBatch {
+                    texture,
+                    instances: index..index + 1,
+                }),
+            }
+            instance
+        })
+        .collect();
+    (instances, batches)
+}
+
+/* unit uv-sphere, counter clockwise winding seen from outside */
+pub fn build_uv_sphere(stacks: u32, sectors: u32) -> (Vec<SphereVertex>, Vec<u32>) {
+    let mut vertices = Vec::with_capacity(((stacks + 1) * (sectors + 1)) as usize);
+    for stack in 0..=stacks {
+        let polar = PI / 2. - PI * stack as f32 / stacks as f32;
+        for sector in 0..=sectors {
+            let azimuth = 2. * PI * sector as f32 / sectors as f32;
+            vertices.push(SphereVertex {
+                position: [
+                    polar.cos() * azimuth.cos(),
+                    polar.sin(),
+                    -polar.cos() * azimuth.sin(),
+                ],
+                uv: [sector as f32 / sectors as f32, stack as f32 / stacks as f32],
+            });
+        }
+    }
+    let mut indices = Vec::with_capacity((stacks * sectors * 6) as usize);
+    for stack in 0..stacks {
+        let upper = stack * (sectors + 1);
+        let lower = upper + sectors + 1;
+        for sector in 0..sectors {
+            if stack != 0 {
+                indices.extend_from_slice(&[upper + sector, lower + sector, upper + sector + 1]);
+            }
+            if stack != stacks - 1 {
+                indices.extend_from_slice(&[
+                    upper + sector + 1,
+                    lower + sector,
+                    lower + sector + 1,
+                ]);
+            }
+        }
+    }
+    (vertices, indices)
+}
+
+impl BodyPipeline {
+    pub fn new(
+        device: &Device,
+        color_format: TextureFormat,
+        sample_count: u32,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+        texture_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let shader = shaders::BODY_SHADER.module(device);
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("body pipeline layout"),
+            bind_group_layouts: &[camera_bind_group_layout, texture_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let line_polygons = device
+            .features()
+            .contains(wgpu::Features::POLYGON_MODE_LINE);
+        let render_pipelines = DebugRenderMode::iter()
+            .map(|mode| {
+                self::create_render_pipeline(
+                    device,
+                    &layout,
+                    &shader,
+                    color_format,
+                    sample_count,
+                    mode,
+                    line_polygons,
+                )
+            })
+            .collect();
+
+        let (vertices, indices) = self::build_uv_sphere(SPHERE_STACKS, SPHERE_SECTORS);
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("sphere vertex buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("sphere index buffer"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        Self {
+            render_pipelines,
+            vertex_buffer,
+            index_buffer,
+            index_count: indices.len() as u32,
+            instance_buffer: self::create_instance_buffer(device, INITIAL_INSTANCE_CAPACITY),
+            instance_capacity: INITIAL_INSTANCE_CAPACITY,
+            instance_count: 0,
+            batches: Vec::new(),
+        }
+    }
+
+    /* the instance buffer grows geometrically and is only re-created when it overflows */
+    pub fn write_instances(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        instances: &[BodyInstance],
+        batches: Vec<BodyBatch>,
+    ) {
+        if instances.len() > self.instance_capacity {
+            self.instance_capacity = instances.len().max(self.instance_capacity * 2);
+            self.instance_buffer = self::create_instance_buffer(device, self.instance_capacity);
+        }
+        queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(instances));
+        self.instance_count = instances.len() as u32;
+        self.batches = batches;
+    }
+
+    pub fn draw(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        camera_bind_group: &wgpu::BindGroup,
+        textures: &BodyTextureCache,
+        mode: DebugRenderMode,
+    ) {
+        if self.instance_count == 0 {
+            return;
+        }
+        render_pass.set_pipeline(&self.render_pipelines[mode.index()]);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        self.batches.iter().for_each(|batch| {
+            render_pass.set_bind_group(1, textures.bind_group(batch.texture.as_deref()), &[]);
+            render_pass.draw_indexed(0..self.index_count, 0, batch.instances.clone());
+        });
+    }
+
+    /* one draw per texture batch */
+    pub fn draw_counts(&self) -> DrawCounts {
+        match self.instance_count {
+            0 => DrawCounts::default(),
+            instances => DrawCounts::new(self.batches.len() as u32, instances),
+        }
+    }
+}
+
+fn create_render_pipeline(
+    device: &Device,
+    layout: &wgpu::PipelineLayout,
+    shader: &wgpu::ShaderModule,
+    color_format: TextureFormat,
+    sample_count: u32,
+    mode: DebugRenderMode,
+    line_polygons: bool,
+) -> wgpu::RenderPipeline {
+    let (fragment_entry_point, polygon_mode) = match mode {
+        DebugRenderMode::Shaded => ("fs_main", wgpu::PolygonMode::Fill),
+        DebugRenderMode::Wireframe if line_polygons => ("fs_flat", wgpu::PolygonMode::Line),
+        DebugRenderMode::Wireframe => ("fs_wireframe", wgpu::PolygonMode::Fill),
+        DebugRenderMode::Normals => ("fs_normals", wgpu::PolygonMode::Fill),
+        DebugRenderMode::Overdraw => ("fs_overdraw", wgpu::PolygonMode::Fill),
+    };
+    let overdraw = mode == DebugRenderMode::Overdraw;
+    let blend = match overdraw {
+        true => wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+            alpha: wgpu::BlendComponent::REPLACE,
+        },
+        false => wgpu::BlendState::REPLACE,
+    };
+    /* the edge shading finds the triangle of a fragment from its position in the uv grid */
+    let constants = [
+        ("SPHERE_STACKS", SPHERE_STACKS as f64),
+        ("SPHERE_SECTORS", SPHERE_SECTORS as f64),
+    ];
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(&format!("body pipeline ({mode})")),
+        layout: Some(layout),
+        vertex: wgpu::VertexState {
+            module: shader,
+            entry_point: Some("vs_main"),
+            compilation_options: Default::default(),
+            buffers: &[
+                wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<SphereVertex>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &wgpu::vertex_attr_array![0 => Float32x3, 4 => Float32x2],
+                },
+                wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<BodyInstance>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &BodyInstance::ATTRIBUTES,
+                },
+            ],
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: shader,
+            entry_point: Some(fragment_entry_point),
+            compilation_options: wgpu::PipelineCompilationOptions {
+                constants: &constants,
+                ..Default::default()
+            },
+            targets: &[Some(wgpu::ColorTargetState {
+                format: color_format,
+                blend: Some(blend),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: Some(wgpu::Face::Back),
+            polygon_mode,
+            ..Default::default()
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: DEPTH_FORMAT,
+            depth_write_enabled: !overdraw,
+            depth_compare: match overdraw {
+                true => wgpu::CompareFunction::Always,
+                false => DEPTH_COMPARE,
+            },
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            ..Default::default()
+        },
+        multiview: None,
+        cache: None,
+    })
+}
+
+fn create_instance_buffer(device: &Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("body instance buffer"),
+        size: (capacity * std::mem::size_of::<BodyInstance>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
@@ -0,0 +1,133 @@
+use imgui::Condition;
+use strum::IntoEnumIterator;
+use wgpu::naga::FastHashSet;
+
+use crate::simulation::{
+    SimulationBody,
+    plots::{PLOT_CAPACITY, PlotSeries},
+    thread::SimulationSnapshot,
+};
+
+use crate::localization;
+
+pub const PLOTS_WINDOW_TITLE: &str = "Plots";
+pub const PLOTS_WINDOW_SIZE: [f32; 2] = [440., 420.];
+pub const PLOT_HEIGHT: f32 = 70.;
+
+#[derive(Debug, Default)]
+pub struct PlotsWindow {
+    pub open: bool,
+    hidden_series: FastHashSet<PlotSeries>,
+}
+
+impl PlotsWindow {
+    pub fn draw_menu_item(&mut self, ui: &imgui::Ui) {
+        if ui.menu_item(localization::tr("menu-plots")) {
+            self.open = true;
+        }
+    }
+
+    /* every series is scaled to its own range */
+    pub fn draw(&mut self, ui: &imgui::Ui, snapshot: &SimulationSnapshot) {
+        if !self.open {
+            return;
+        }
+        let plots = &snapshot.plots;
+        let body = plots
+            .body()
+            .and_then(|id| snapshot.bodies.iter().find(|body| body.id == id));
+        let mut open = true;
+        ui.window(localization::title("window-plots", PLOTS_WINDOW_TITLE))
+            .opened(&mut open)
+            .size(PLOTS_WINDOW_SIZE, Condition::FirstUseEver)
+            .build(|| {
+                for (index, series) in PlotSeries::iter().enumerate() {
+                    if index > 0 {
+                        ui.same_line();
+                    }
+                    let mut shown = !self.hidden_series.contains(&series);
+                    if ui.checkbox(self::short_name(series), &mut shown) {
+                        match shown {
+                            true => self.hidden_series.remove(&series),
+                            false => self.hidden_series.insert(series),
+                        };
+                    }
+                }
+                ui.text_disabled(format!(
+                    "{} samples at most, the oldest are dropped",
+                    PLOT_CAPACITY
+                ));
+                ui.separator();
+                for series in PlotSeries::iter() {
+                    if self.hidden_series.contains(&series) {
+                        continue;
+                    }
+                    self::draw_series(ui, series, plots.series(series).samples(), body);
+                }
+            });
+        self.open = open;
+    }
+}
+
+/* conserved quantities only drift in their last digits, they are drawn relative to the oldest
+sample so the drift survives the conversion to f32 */
+fn draw_series<'a>(
+    ui: &imgui::Ui,
+    series: PlotSeries,
+    samples: impl ExactSizeIterator<Item = &'a f64> + DoubleEndedIterator,
+    body: Option<&SimulationBody>,
+) {
+    let title = match (series.per_body(), body) {
+        (true, Some(body)) => format!("{} of {}", series, body.id_name),
+        _ => series.to_string(),
+    };
+    ui.text(&title);
+    let samples = samples.copied().collect::<Vec<_>>();
+    let (Some(&oldest), Some(&latest)) = (samples.first(), samples.last()) else {
+        ui.text_disabled(match series.per_body() && body.is_none() {
+            true => "Select a body",
+            false => "No samples yet",
+        });
+        return;
+    };
+    let reference = match series.conserved() {
+        true => oldest,
+        false => 0.,
+    };
+    let values = samples
+        .iter()
+        .map(|sample| (sample - reference) as f32)
+        .collect::<Vec<_>>();
+    let (minimum, maximum) = values.iter().fold(
+        (f32::INFINITY, f32::NEG_INFINITY),
+        |(minimum, maximum), value| (minimum.min(*value), maximum.max(*value)),
+    );
+    /* a flat line sits in the middle instead of on the border */
+    let margin = match maximum > minimum {
+        true => (maximum - minimum) * 0.05,
+        false => minimum.abs().max(1.) * 0.05,
+    };
+    let overlay = match series.conserved() {
+        true => format!(
+            "{:.6e}, {:+.3e} relative",
+            latest,
+            (latest - oldest) / oldest.abs().max(f64::MIN_POSITIVE)
+        ),
+        false => format!("{:.6e}", latest),
+    };
+    ui.plot_lines(format!("##{}", title), &values)
+        .graph_size([ui.content_region_avail()[0], PLOT_HEIGHT])
+        .scale_min(minimum - margin)
+        .scale_max(maximum + margin)
+        .overlay_text(overlay)
+        .build();
+}
+
+fn short_name(series: PlotSeries) -> &'static str {
+    match series {
+        PlotSeries::TotalEnergy => "Energy",
+        PlotSeries::Momentum => "Momentum",
+        PlotSeries::BodySpeed => "Speed",
+        PlotSeries::BodyAltitude => "Altitude",
+    }
+}
@@ -0,0 +1,366 @@
+use serde::{Deserialize, Serialize};
+use wgpu::{Device, Queue, util::DeviceExt};
+
+use crate::graphics::{mipmap, shaders, stats::DrawCounts, tonemap::HDR_FORMAT};
+
+/* the first level is half the surface, each further one halves again */
+pub const BLOOM_LEVEL_COUNT: u32 = 5;
+/* linear hdr values above this glow, lit bodies stay below it */
+pub const DEFAULT_BLOOM_THRESHOLD: f32 = 1.;
+pub const DEFAULT_BLOOM_INTENSITY: f32 = 0.6;
+/* bounds of the settings window, a threshold of 0 would make everything glow */
+pub const MAXIMUM_BLOOM_INTENSITY: f32 = 4.;
+pub const MINIMUM_BLOOM_THRESHOLD: f32 = 0.1;
+pub const MAXIMUM_BLOOM_THRESHOLD: f32 = 10.;
+/* soft knee below the threshold, as a fraction of it */
+pub const BLOOM_KNEE_FRACTION: f32 = 0.5;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct BloomSettings {
+    #[serde(rename = "enabled")]
+    pub enabled: bool,
+    #[serde(rename = "threshold")]
+    pub threshold: f32,
+    #[serde(rename = "intensity")]
+    pub intensity: f32,
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            threshold: DEFAULT_BLOOM_THRESHOLD,
+            intensity: DEFAULT_BLOOM_INTENSITY,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct BloomUniform {
+    pub threshold: f32,
+    pub knee: f32,
+    pub intensity: f32,
+    pub _padding: f32,
+}
+
+impl BloomUniform {
+    pub fn new(settings: &BloomSettings) -> Self {
+        Self {
+            threshold: settings.threshold,
+            knee: settings.threshold * BLOOM_KNEE_FRACTION,
+            intensity: settings.intensity,
+            _padding: 0.,
+        }
+    }
+}
+
+/* thresholds the hdr target, blurs it down and up a mip chain and adds the result back.
+the chain is kept between frames and only recreated with the render targets */
+pub struct BloomPipeline {
+    pub prefilter_pipeline: wgpu::RenderPipeline,
+    pub downsample_pipeline: wgpu::RenderPipeline,
+    pub upsample_pipeline: wgpu::RenderPipeline,
+    pub composite_pipeline: wgpu::RenderPipeline,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub sampler: wgpu::Sampler,
+    pub uniform_buffer: wgpu::Buffer,
+    /* one render target view per blur level */
+    pub level_views: Vec<wgpu::TextureView>,
+    /* samples the hdr target, input of the prefilter */
+    pub hdr_bind_group: wgpu::BindGroup,
+    /* samples the level with the same index */
+    pub level_bind_groups: Vec<wgpu::BindGroup>,
+}
+
+impl BloomPipeline {
+    pub fn new(
+        device: &Device,
+        hdr_view: &wgpu::TextureView,
+        width: u32,
+        height: u32,
+        settings: &BloomSettings,
+    ) -> Self {
+        let shader = shaders::BLOOM_SHADER.module(device);
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("bloom bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("bloom sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("bloom uniform buffer"),
+            contents: bytemuck::bytes_of(&BloomUniform::new(settings)),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("bloom pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let additive = wgpu::BlendState {
+            color: wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::One,
+                dst_factor: wgpu::BlendFactor::One,
+                operation: wgpu::BlendOperation::Add,
+            },
+            alpha: wgpu::BlendComponent::OVER,
+        };
+        let create_pipeline = |entry_point: &str, blend: Option<wgpu::BlendState>| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(entry_point),
+                layout: Some(&layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: Some("vs_main"),
+                    compilation_options: Default::default(),
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: Some(entry_point),
+                    compilation_options: Default::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: HDR_FORMAT,
+                        blend,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                multiview: None,
+                cache: None,
+            })
+        };
+        let prefilter_pipeline = create_pipeline("fs_prefilter", None);
+        let downsample_pipeline = create_pipeline("fs_downsample", None);
+        let upsample_pipeline = create_pipeline("fs_upsample", Some(additive));
+        let composite_pipeline = create_pipeline("fs_composite", Some(additive));
+        let hdr_bind_group = self::create_bind_group(
+            device,
+            &bind_group_layout,
+            &sampler,
+            &uniform_buffer,
+            hdr_view,
+        );
+
+        let mut bloom = Self {
+            prefilter_pipeline,
+            downsample_pipeline,
+            upsample_pipeline,
+            composite_pipeline,
+            bind_group_layout,
+            sampler,
+            uniform_buffer,
+            level_views: Vec::new(),
+            hdr_bind_group,
+            level_bind_groups: Vec::new(),
+        };
+        bloom.resize(device, hdr_view, width, height);
+        bloom
+    }
+
+    /* recreates the blur chain for a new surface size or hdr target */
+    pub fn resize(
+        &mut self,
+        device: &Device,
+        hdr_view: &wgpu::TextureView,
+        width: u32,
+        height: u32,
+    ) {
+        let (base_width, base_height) = mipmap::mip_level_size(width, height, 1);
+        let level_count = BLOOM_LEVEL_COUNT.min(mipmap::mip_level_count(base_width, base_height));
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("bloom texture"),
+            size: wgpu::Extent3d {
+                width: base_width,
+                height: base_height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: HDR_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        self.level_views = (0..level_count)
+            .map(|level| {
+                texture.create_view(&wgpu::TextureViewDescriptor {
+                    label: Some("bloom level"),
+                    base_mip_level: level,
+                    mip_level_count: Some(1),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        self.level_bind_groups = self
+            .level_views
+            .iter()
+            .map(|view| {
+                self::create_bind_group(
+                    device,
+                    &self.bind_group_layout,
+                    &self.sampler,
+                    &self.uniform_buffer,
+                    view,
+                )
+            })
+            .collect();
+        self.hdr_bind_group = self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.sampler,
+            &self.uniform_buffer,
+            hdr_view,
+        );
+    }
+
+    pub fn write_uniform(&self, queue: &Queue, settings: &BloomSettings) {
+        queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::bytes_of(&BloomUniform::new(settings)),
+        );
+    }
+
+    /* runs after the object pass and before tonemapping, adds the glow onto the hdr target.
+    the timestamp writes go to the first of the passes */
+    pub fn encode(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        hdr_view: &wgpu::TextureView,
+        timestamp_writes: Option<wgpu::RenderPassTimestampWrites>,
+    ) {
+        let level_count = self.level_views.len();
+        self::fullscreen_pass(
+            encoder,
+            &self.level_views[0],
+            wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+            &self.prefilter_pipeline,
+            &self.hdr_bind_group,
+            timestamp_writes,
+        );
+        for level in 1..level_count {
+            self::fullscreen_pass(
+                encoder,
+                &self.level_views[level],
+                wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                &self.downsample_pipeline,
+                &self.level_bind_groups[level - 1],
+                None,
+            );
+        }
+        for level in (0..level_count - 1).rev() {
+            self::fullscreen_pass(
+                encoder,
+                &self.level_views[level],
+                wgpu::LoadOp::Load,
+                &self.upsample_pipeline,
+                &self.level_bind_groups[level + 1],
+                None,
+            );
+        }
+        self::fullscreen_pass(
+            encoder,
+            hdr_view,
+            wgpu::LoadOp::Load,
+            &self.composite_pipeline,
+            &self.level_bind_groups[0],
+            None,
+        );
+    }
+
+    /* prefilter, downsamples, upsamples and composite */
+    pub fn draw_counts(&self) -> DrawCounts {
+        let passes = 2 * self.level_views.len() as u32;
+        DrawCounts::new(passes, passes)
+    }
+}
+
+fn fullscreen_pass(
+    encoder: &mut wgpu::CommandEncoder,
+    target: &wgpu::TextureView,
+    load: wgpu::LoadOp<wgpu::Color>,
+    pipeline: &wgpu::RenderPipeline,
+    bind_group: &wgpu::BindGroup,
+    timestamp_writes: Option<wgpu::RenderPassTimestampWrites>,
+) {
+    let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Bloom Render Pass"),
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: target,
+            resolve_target: None,
+            ops: wgpu::Operations {
+                load,
+                store: wgpu::StoreOp::Store,
+            },
+        })],
+        depth_stencil_attachment: None,
+        occlusion_query_set: None,
+        timestamp_writes,
+    });
+    pass.set_pipeline(pipeline);
+    pass.set_bind_group(0, bind_group, &[]);
+    pass.draw(0..3, 0..1);
+}
+
+fn create_bind_group(
+    device: &Device,
+    layout: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+    uniform_buffer: &wgpu::Buffer,
+    source_view: &wgpu::TextureView,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("bloom bind group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(source_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: uniform_buffer.as_entire_binding(),
+            },
+        ],
+    })
+}
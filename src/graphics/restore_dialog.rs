@@ -0,0 +1,72 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use crate::localization;
+
+const RESTORE_POPUP_TITLE: &str = "Restore autosave?";
+
+/* offered on the start after a session that did not shut down cleanly */
+#[derive(Default)]
+pub struct RestoreDialog {
+    autosave: Option<(PathBuf, SystemTime)>,
+    /* opened in the first frame */
+    open_popup: bool,
+}
+
+impl RestoreDialog {
+    pub fn offer(&mut self, path: PathBuf, saved_at: SystemTime) {
+        self.autosave = Some((path, saved_at));
+        self.open_popup = true;
+    }
+
+    /* returns the autosave once restoring it is chosen, declining starts as usual */
+    pub fn draw(&mut self, ui: &imgui::Ui) -> Option<PathBuf> {
+        if std::mem::take(&mut self.open_popup) {
+            ui.open_popup(localization::title(
+                "popup-restore-autosave",
+                RESTORE_POPUP_TITLE,
+            ));
+        }
+
+        let mut chosen = None;
+        ui.modal_popup(
+            localization::title("popup-restore-autosave", RESTORE_POPUP_TITLE),
+            || {
+                let Some((_, saved_at)) = self.autosave.as_ref() else {
+                    ui.close_current_popup();
+                    return;
+                };
+                let age = SystemTime::now()
+                    .duration_since(*saved_at)
+                    .unwrap_or_default();
+                ui.text("SpaceS did not shut down cleanly last time.");
+                ui.text(format!(
+                    "Restore the simulation autosaved {}?",
+                    self::format_age(age)
+                ));
+                if ui.button("Restore") {
+                    chosen = self.autosave.take().map(|(path, _)| path);
+                    ui.close_current_popup();
+                }
+                ui.same_line();
+                if ui.button("Start fresh") {
+                    self.autosave = None;
+                    ui.close_current_popup();
+                }
+            },
+        );
+        chosen
+    }
+}
+
+fn format_age(age: Duration) -> String {
+    match age.as_secs() {
+        0..60 => "less than a minute ago".to_string(),
+        60..120 => "a minute ago".to_string(),
+        seconds if seconds < 3600 * 2 => format!("{} minutes ago", seconds / 60),
+        seconds if seconds < 86400 * 2 => format!("{} hours ago", seconds / 3600),
+        seconds => format!("{} days ago", seconds / 86400),
+    }
+}
@@ -0,0 +1,327 @@
+use std::f32::consts::PI;
+
+use bytemuck::{Pod, Zeroable};
+use glam::{DVec3, Vec3};
+use wgpu::{Device, Queue, TextureFormat, util::DeviceExt};
+
+use crate::{
+    graphics::{
+        body_texture::BodyTextureCache,
+        pipeline::{BODY_RADIUS_RENDER_SCALE, BodyShading, DEFAULT_LIGHT_DIRECTION},
+        shaders,
+        stats::DrawCounts,
+        targets::{DEPTH_COMPARE, DEPTH_FORMAT},
+    },
+    simulation::{RingDefinition, SimulationBody},
+};
+
+pub const RING_SEGMENTS: u32 = 128;
+pub const INITIAL_RING_CAPACITY: usize = 4;
+pub const RING_BAND_TEXTURE_WIDTH: u32 = 256;
+/* pale inner ring, a bright wide ring, a near empty division and a dimmer outer ring */
+pub const DEFAULT_RING_BANDS: [[f32; 4]; 7] = [
+    [0.45, 0.4, 0.32, 0.25],
+    [0.6, 0.53, 0.42, 0.55],
+    [0.85, 0.77, 0.62, 0.95],
+    [0.8, 0.72, 0.58, 0.9],
+    [0.3, 0.27, 0.22, 0.05],
+    [0.7, 0.64, 0.52, 0.7],
+    [0.6, 0.55, 0.45, 0.45],
+];
+/* generated band strips share the body texture cache, asset paths never start with this */
+const BAND_TEXTURE_KEY_PREFIX: &str = "procedural:rings:";
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct RingVertex {
+    /* cosine and sine of the angle, then 0 on the inner and 1 on the outer edge */
+    pub position: [f32; 3],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct RingInstance {
+    pub center: [f32; 3],
+    pub inner_radius: f32,
+    /* orthonormal pair spanning the equatorial plane */
+    pub axis_u: [f32; 3],
+    pub outer_radius: f32,
+    pub axis_v: [f32; 3],
+    pub opacity: f32,
+    pub light_direction: [f32; 3],
+    /* radiance multiplier of unlit rings, 0 for lit rings */
+    pub emission: f32,
+}
+
+impl RingInstance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 8] = wgpu::vertex_attr_array![
+        1 => Float32x3, 2 => Float32, 3 => Float32x3, 4 => Float32,
+        5 => Float32x3, 6 => Float32, 7 => Float32x3, 8 => Float32
+    ];
+
+    /* rebuilt every frame from the body, so the rings follow its position and spin axis */
+    pub fn from_body(
+        body: &SimulationBody,
+        rings: &RingDefinition,
+        eye: DVec3,
+        shading: BodyShading,
+    ) -> Self {
+        let (axis_u, axis_v) = self::equatorial_basis(body.appearance.spin_axis);
+        let radius = (body.radius * BODY_RADIUS_RENDER_SCALE) as f32;
+        let (emission, light_direction) = match shading {
+            BodyShading::Unlit { emission } => (emission, DEFAULT_LIGHT_DIRECTION.normalize()),
+            BodyShading::Lit { light_direction } => (0., light_direction),
+        };
+        Self {
+            center: (body.position - eye).as_vec3().to_array(),
+            inner_radius: radius * rings.inner_radius,
+            axis_u: axis_u.to_array(),
+            outer_radius: radius * rings.outer_radius,
+            axis_v: axis_v.to_array(),
+            opacity: rings.opacity.clamp(0., 1.),
+            light_direction: light_direction.to_array(),
+            emission,
+        }
+    }
+}
+
+/* plane perpendicular to the spin axis, a missing or zero axis points along +y */
+pub fn equatorial_basis(spin_axis: Option<[f32; 3]>) -> (Vec3, Vec3) {
+    let axis = spin_axis
+        .and_then(|axis| Vec3::from_array(axis).try_normalize())
+        .unwrap_or(Vec3::Y);
+    axis.any_orthonormal_pair()
+}
+
+/* translucent, so they are drawn after everything opaque and farthest first */
+pub fn sort_back_to_front(entries: &mut [(String, RingInstance)]) {
+    let distance = |instance: &RingInstance| Vec3::from_array(instance.center).length_squared();
+    entries.sort_by(|(_, left), (_, right)| distance(right).total_cmp(&distance(left)));
+}
+
+/* the texture the rings are drawn with, their bands are generated on first use and also
+stand in while a ring texture is still loading */
+pub fn resolve_texture(
+    textures: &mut BodyTextureCache,
+    device: &Device,
+    queue: &Queue,
+    rings: &RingDefinition,
+) -> String {
+    if let Some(path) = rings.texture.as_ref()
+        && textures.resolve(device, queue, path)
+    {
+        return path.clone();
+    }
+    let bands = match rings.bands.is_empty() {
+        true => DEFAULT_RING_BANDS.as_slice(),
+        false => rings.bands.as_slice(),
+    };
+    let key = format!("{}{:?}", BAND_TEXTURE_KEY_PREFIX, bands);
+    textures.insert_generated(device, queue, &key, || self::build_band_image(bands));
+    key
+}
+
+/* linear interpolation between the stops, encoded for the srgb image format */
+pub fn build_band_image(bands: &[[f32; 4]]) -> image::RgbaImage {
+    image::RgbaImage::from_fn(RING_BAND_TEXTURE_WIDTH, 1, |x, _| {
+        let Some(last) = bands.len().checked_sub(1) else {
+            return image::Rgba([255; 4]);
+        };
+        let position = x as f32 / (RING_BAND_TEXTURE_WIDTH - 1) as f32 * last as f32;
+        let index = (position.floor() as usize).min(last);
+        let (from, to) = (bands[index], bands[(index + 1).min(last)]);
+        let t = position - index as f32;
+        let channel = |channel: usize| from[channel] + (to[channel] - from[channel]) * t;
+        image::Rgba([
+            self::encode_srgb(channel(0)),
+            self::encode_srgb(channel(1)),
+            self::encode_srgb(channel(2)),
+            (channel(3).clamp(0., 1.) * 255.).round() as u8,
+        ])
+    })
+}
+
+fn encode_srgb(linear: f32) -> u8 {
+    let linear = linear.clamp(0., 1.);
+    let encoded = match linear <= 0.003_130_8 {
+        true => linear * 12.92,
+        false => 1.055 * linear.powf(1. / 2.4) - 0.055,
+    };
+    (encoded * 255.).round() as u8
+}
+
+/* unit annulus, every segment is a quad between the inner and the outer edge */
+pub fn build_annulus(segments: u32) -> (Vec<RingVertex>, Vec<u32>) {
+    let vertices = (0..=segments)
+        .flat_map(|segment| {
+            let angle = 2. * PI * segment as f32 / segments as f32;
+            [0., 1.].map(|radial| RingVertex {
+                position: [angle.cos(), angle.sin(), radial],
+            })
+        })
+        .collect();
+    let indices = (0..segments)
+        .flat_map(|segment| {
+            let inner = segment * 2;
+            [inner, inner + 1, inner + 2, inner + 2, inner + 1, inner + 3]
+        })
+        .collect();
+    (vertices, indices)
+}
+
+pub struct RingPipeline {
+    pub render_pipeline: wgpu::RenderPipeline,
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: wgpu::Buffer,
+    pub index_count: u32,
+    pub instance_buffer: wgpu::Buffer,
+    pub instance_capacity: usize,
+    /* texture key of every instance, in draw order */
+    pub textures: Vec<String>,
+}
+
+impl RingPipeline {
+    pub fn new(
+        device: &Device,
+        color_format: TextureFormat,
+        sample_count: u32,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+        texture_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let shader = shaders::RING_SHADER.module(device);
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("ring pipeline layout"),
+            bind_group_layouts: &[camera_bind_group_layout, texture_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("ring pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[
+                    wgpu::VertexBufferLayout {
+                        array_stride: std::mem::size_of::<RingVertex>() as wgpu::BufferAddress,
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &wgpu::vertex_attr_array![0 => Float32x3],
+                    },
+                    wgpu::VertexBufferLayout {
+                        array_stride: std::mem::size_of::<RingInstance>() as wgpu::BufferAddress,
+                        step_mode: wgpu::VertexStepMode::Instance,
+                        attributes: &RingInstance::ATTRIBUTES,
+                    },
+                ],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: color_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            /* double sided, visible from both poles */
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                cull_mode: None,
+                ..Default::default()
+            },
+            /* the planet is already in the depth buffer and hides the far half of its rings,
+            the near half passes the test and blends over it */
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: DEPTH_COMPARE,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        let (vertices, indices) = self::build_annulus(RING_SEGMENTS);
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("ring vertex buffer"),
+            contents: bytemuck::cast_slice(&vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("ring index buffer"),
+            contents: bytemuck::cast_slice(&indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        Self {
+            render_pipeline,
+            vertex_buffer,
+            index_buffer,
+            index_count: indices.len() as u32,
+            instance_buffer: self::create_instance_buffer(device, INITIAL_RING_CAPACITY),
+            instance_capacity: INITIAL_RING_CAPACITY,
+            textures: Vec::new(),
+        }
+    }
+
+    /* entries are expected sorted, see sort_back_to_front */
+    pub fn write_instances(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        entries: Vec<(String, RingInstance)>,
+    ) {
+        let (textures, instances): (Vec<String>, Vec<RingInstance>) = entries.into_iter().unzip();
+        if instances.len() > self.instance_capacity {
+            self.instance_capacity = instances.len().max(self.instance_capacity * 2);
+            self.instance_buffer = self::create_instance_buffer(device, self.instance_capacity);
+        }
+        queue.write_buffer(&self.instance_buffer, 0, bytemuck::cast_slice(&instances));
+        self.textures = textures;
+    }
+
+    /* one draw per ring to keep the back to front order across textures */
+    pub fn draw(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        camera_bind_group: &wgpu::BindGroup,
+        textures: &BodyTextureCache,
+    ) {
+        if self.textures.is_empty() {
+            return;
+        }
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, self.instance_buffer.slice(..));
+        render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+        self.textures
+            .iter()
+            .enumerate()
+            .for_each(|(index, texture)| {
+                let index = index as u32;
+                render_pass.set_bind_group(1, textures.bind_group(Some(texture)), &[]);
+                render_pass.draw_indexed(0..self.index_count, 0, index..index + 1);
+            });
+    }
+
+    pub fn draw_counts(&self) -> DrawCounts {
+        let rings = self.textures.len() as u32;
+        DrawCounts::new(rings, rings)
+    }
+}
+
+fn create_instance_buffer(device: &Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("ring instance buffer"),
+        size: (capacity * std::mem::size_of::<RingInstance>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
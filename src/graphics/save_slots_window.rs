@@ -0,0 +1,373 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use imgui::Condition;
+use wgpu::naga::FastHashSet;
+
+use crate::{
+    graphics::{scenario_browser::THUMBNAIL_HEIGHT, textures::TextureManager, toolbar},
+    localization,
+    storage::{self, SaveSlot, SaveSlotKind, Thumbnail},
+};
+
+pub const SAVE_SLOTS_TITLE: &str = "Saves";
+pub const SAVE_SLOTS_SIZE: [f32; 2] = [540., 460.];
+/* like the scenario browser, a quicksave or a save written meanwhile shows up on its own */
+pub const SAVE_SLOTS_RESCAN_INTERVAL: Duration = Duration::from_secs(1);
+const CONFIRM_POPUP_TITLE: &str = "Replace save?";
+const DELETE_POPUP_TITLE: &str = "Delete save?";
+
+/* what the application is asked for, the slots are renamed and deleted by the window */
+#[derive(Debug, Clone, PartialEq)]
+pub enum SaveSlotRequest {
+    /* the running state into the slot of the name, replacing the one there */
+    Save(String),
+    Load(PathBuf),
+}
+
+/* read from the save when first drawn */
+#[derive(Debug, Clone)]
+struct SlotSummary {
+    scenario_label: String,
+    simulation_time: f64,
+    body_count: usize,
+}
+
+#[derive(Debug)]
+struct ListedSlot {
+    slot: SaveSlot,
+    /* the error of a file that is not a valid save otherwise */
+    summary: Option<Result<SlotSummary, String>>,
+    /* a new key once the thumbnail is written again */
+    thumbnail_key: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct SaveSlotsWindow {
+    pub open: bool,
+    slots: Vec<ListedSlot>,
+    scanned: Option<Instant>,
+    /* of the slot a save is started into */
+    new_name: String,
+    /* the slot edited in place and the name typed so far */
+    renaming: Option<(PathBuf, String)>,
+    /* asked about before the save is replaced or removed */
+    overwriting: Option<String>,
+    deleting: Option<PathBuf>,
+    open_overwrite_popup: bool,
+    open_delete_popup: bool,
+    error: Option<String>,
+    /* the thumbnail keys asked for, each is loaded once even when it fails */
+    requested_thumbnails: FastHashSet<String>,
+    thumbnail_requests: Vec<(String, Thumbnail)>,
+}
+
+impl SaveSlotsWindow {
+    pub fn draw_menu_item(&mut self, ui: &imgui::Ui) {
+        if ui.menu_item(localization::tr("menu-saves")) {
+            self.open = true;
+            self.scanned = None;
+        }
+    }
+
+    /* their textures were unloaded, they are asked for again when shown */
+    pub fn forget_thumbnails(&mut self) {
+        self.requested_thumbnails.clear();
+    }
+
+    /* the thumbnails to upload since the last call */
+    pub fn take_thumbnail_requests(&mut self) -> Vec<(String, Thumbnail)> {
+        std::mem::take(&mut self.thumbnail_requests)
+    }
+
+    pub fn draw(
+        &mut self,
+        ui: &imgui::Ui,
+        now: Instant,
+        texture_manager: &TextureManager,
+    ) -> Option<SaveSlotRequest> {
+        if !self.open {
+            return None;
+        }
+        if self
+            .scanned
+            .is_none_or(|scanned| now - scanned >= SAVE_SLOTS_RESCAN_INTERVAL)
+        {
+            self.rescan();
+            self.scanned = Some(now);
+        }
+        let mut open = true;
+        let mut request = None;
+        ui.window(localization::title("window-saves", SAVE_SLOTS_TITLE))
+            .opened(&mut open)
+            .size(SAVE_SLOTS_SIZE, Condition::FirstUseEver)
+            .build(|| {
+                let _wrap = ui.push_text_wrap_pos();
+                ui.input_text("Name", &mut self.new_name).build();
+                ui.same_line();
+                let name = self.new_name.trim().to_string();
+                {
+                    let _disabled = ui.begin_disabled(!storage::is_save_slot_name(&name));
+                    if ui.button("Save") {
+                        match self.slots.iter().any(|listed| listed.slot.name == name) {
+                            true => {
+                                self.overwriting = Some(name);
+                                self.open_overwrite_popup = true;
+                            }
+                            false => {
+                                request = Some(SaveSlotRequest::Save(name));
+                                self.new_name.clear();
+                            }
+                        }
+                    }
+                }
+                ui.text_disabled(localization::tr("window-saves.hint"));
+                if let Some(error) = self.error.as_ref() {
+                    ui.text_colored([1., 0.35, 0.3, 1.], error);
+                }
+                ui.separator();
+                if self.slots.is_empty() {
+                    ui.text_disabled(format!(
+                        "Saves in {} are listed here",
+                        storage::saves_directory().display()
+                    ));
+                }
+                for index in 0..self.slots.len() {
+                    let _id = ui.push_id(self.slots[index].slot.path.to_string_lossy());
+                    if let Some(chosen) = self.draw_slot(ui, index, texture_manager) {
+                        request = Some(chosen);
+                    }
+                    ui.separator();
+                }
+                if let Some(chosen) = self.draw_overwrite_popup(ui) {
+                    request = Some(chosen);
+                }
+                self.draw_delete_popup(ui);
+            });
+        self.open = open;
+        request
+    }
+
+    fn draw_slot(
+        &mut self,
+        ui: &imgui::Ui,
+        index: usize,
+        texture_manager: &TextureManager,
+    ) -> Option<SaveSlotRequest> {
+        let mut request = None;
+        let listed = &mut self.slots[index];
+        let summary = listed
+            .summary
+            .get_or_insert_with(|| self::read_summary(&listed.slot.path))
+            .clone();
+        let thumbnail_size = [THUMBNAIL_HEIGHT * 16. / 9., THUMBNAIL_HEIGHT];
+        match listed
+            .thumbnail_key
+            .as_ref()
+            .and_then(|key| texture_manager.get(key))
+        {
+            Some(texture) => imgui::Image::new(texture.texture_id, thumbnail_size).build(ui),
+            None => {
+                if let (Some(key), Some(path)) = (
+                    listed.thumbnail_key.as_ref(),
+                    listed.slot.thumbnail.as_ref(),
+                ) && self.requested_thumbnails.insert(key.clone())
+                {
+                    self.thumbnail_requests
+                        .push((key.clone(), Thumbnail::File(path.clone())));
+                }
+                ui.dummy(thumbnail_size);
+            }
+        }
+        ui.same_line();
+        let slot = listed.slot.clone();
+        ui.group(|| {
+            let kind = match slot.kind {
+                SaveSlotKind::Named => String::new(),
+                SaveSlotKind::Quicksave => " (quicksave)".to_string(),
+                SaveSlotKind::Autosave => " (autosave)".to_string(),
+            };
+            ui.text(format!("{}{}", slot.name, kind));
+            if let Some(modified) = slot.modified {
+                ui.same_line();
+                ui.text_disabled(format!("saved {}", self::format_saved(modified)));
+            }
+            match summary.as_ref() {
+                Result::Ok(summary) => ui.text(format!(
+                    "{} at T+{}, {} bodies",
+                    summary.scenario_label,
+                    toolbar::format_elapsed(summary.simulation_time),
+                    summary.body_count
+                )),
+                Err(error) => {
+                    ui.text_disabled("Cannot be read");
+                    if ui.is_item_hovered() {
+                        ui.tooltip_text(error);
+                    }
+                }
+            }
+            if let Some((path, name)) = self.renaming.as_mut()
+                && *path == slot.path
+            {
+                let entered = ui
+                    .input_text("##name", name)
+                    .enter_returns_true(true)
+                    .build();
+                ui.same_line();
+                let valid = storage::is_save_slot_name(name);
+                let renamed = {
+                    let _disabled = ui.begin_disabled(!valid);
+                    ui.button("Rename") || (entered && valid)
+                };
+                if renamed {
+                    self.error = storage::rename_save_slot(path, name)
+                        .err()
+                        .map(|error| format!("{:#}", error));
+                    self.renaming = None;
+                    self.scanned = None;
+                }
+                ui.same_line();
+                if ui.button("Cancel") {
+                    self.renaming = None;
+                }
+                return;
+            }
+            {
+                let _disabled = ui.begin_disabled(summary.is_err());
+                if ui.button("Load") {
+                    request = Some(SaveSlotRequest::Load(slot.path.clone()));
+                }
+            }
+            if slot.kind == SaveSlotKind::Named {
+                ui.same_line();
+                if ui.button("Overwrite") {
+                    self.overwriting = Some(slot.name.clone());
+                    self.open_overwrite_popup = true;
+                }
+            }
+            ui.same_line();
+            if ui.button("Rename") {
+                self.renaming = Some((slot.path.clone(), slot.name.clone()));
+            }
+            ui.same_line();
+            if ui.button("Delete") {
+                self.deleting = Some(slot.path.clone());
+                self.open_delete_popup = true;
+            }
+        });
+        request
+    }
+
+    fn draw_overwrite_popup(&mut self, ui: &imgui::Ui) -> Option<SaveSlotRequest> {
+        if std::mem::take(&mut self.open_overwrite_popup) {
+            ui.open_popup(localization::title(
+                "popup-replace-save",
+                CONFIRM_POPUP_TITLE,
+            ));
+        }
+        let mut request = None;
+        ui.modal_popup(
+            localization::title("popup-replace-save", CONFIRM_POPUP_TITLE),
+            || {
+                let Some(name) = self.overwriting.clone() else {
+                    ui.close_current_popup();
+                    return;
+                };
+                ui.text(format!("{} is replaced by the running simulation.", name));
+                if ui.button("Overwrite") {
+                    request = Some(SaveSlotRequest::Save(name));
+                    self.overwriting = None;
+                    self.new_name.clear();
+                    ui.close_current_popup();
+                }
+                ui.same_line();
+                if ui.button("Cancel") {
+                    self.overwriting = None;
+                    ui.close_current_popup();
+                }
+            },
+        );
+        request
+    }
+
+    fn draw_delete_popup(&mut self, ui: &imgui::Ui) {
+        if std::mem::take(&mut self.open_delete_popup) {
+            ui.open_popup(localization::title("popup-delete-save", DELETE_POPUP_TITLE));
+        }
+        ui.modal_popup(
+            localization::title("popup-delete-save", DELETE_POPUP_TITLE),
+            || {
+                let Some(path) = self.deleting.clone() else {
+                    ui.close_current_popup();
+                    return;
+                };
+                ui.text(format!("{} is deleted from the disk.", path.display()));
+                if ui.button("Delete") {
+                    self.error = storage::delete_save_slot(&path)
+                        .err()
+                        .map(|error| format!("{:#}", error));
+                    self.deleting = None;
+                    self.scanned = None;
+                    ui.close_current_popup();
+                }
+                ui.same_line();
+                if ui.button("Cancel") {
+                    self.deleting = None;
+                    ui.close_current_popup();
+                }
+            },
+        );
+    }
+
+    /* keeps the summaries of the saves that did not change */
+    fn rescan(&mut self) {
+        self.slots = storage::list_save_slots()
+            .into_iter()
+            .map(|slot| {
+                let summary = self
+                    .slots
+                    .iter_mut()
+                    .find(|listed| listed.slot.path == slot.path)
+                    .filter(|listed| listed.slot.modified == slot.modified)
+                    .and_then(|listed| listed.summary.take());
+                let thumbnail_key = slot.thumbnail.as_ref().map(|path| {
+                    format!(
+                        "save thumbnail:{}:{:?}",
+                        path.display(),
+                        path.metadata()
+                            .and_then(|metadata| metadata.modified())
+                            .ok()
+                            .and_then(|modified| modified
+                                .duration_since(SystemTime::UNIX_EPOCH)
+                                .ok())
+                    )
+                });
+                ListedSlot {
+                    slot,
+                    summary,
+                    thumbnail_key,
+                }
+            })
+            .collect();
+    }
+}
+
+fn read_summary(path: &Path) -> Result<SlotSummary, String> {
+    let save_game = storage::load_state(path).map_err(|error| format!("{:#}", error))?;
+    Ok(SlotSummary {
+        scenario_label: save_game.scenario_label,
+        simulation_time: save_game.simulation_time,
+        body_count: save_game.bodies.len(),
+    })
+}
+
+/* in utc like the dates of the simulation */
+fn format_saved(modified: SystemTime) -> String {
+    let unix_seconds = modified
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    format!("{} UTC", toolbar::format_date(0., unix_seconds))
+}
@@ -0,0 +1,321 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use imgui::Condition;
+use tracing::warn;
+use wgpu::naga::FastHashSet;
+
+use crate::{
+    graphics::textures::TextureManager,
+    localization, platform,
+    scenario::{self, SCENARIO_PRESETS},
+    storage::{self, Thumbnail},
+};
+
+pub const SCENARIO_BROWSER_TITLE: &str = "Scenarios";
+pub const SCENARIO_BROWSER_SIZE: [f32; 2] = [480., 420.];
+/* the directory is listed again this often while the window is open, a saved file shows up
+without a refresh button */
+pub const SCENARIO_RESCAN_INTERVAL: Duration = Duration::from_secs(1);
+const DELETE_POPUP_TITLE: &str = "Delete scenario?";
+/* the thumbnails in the list are this high, at 16:9 like a screenshot of the window */
+pub const THUMBNAIL_HEIGHT: f32 = 48.;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScenarioChoice {
+    /* index into SCENARIO_PRESETS */
+    Preset(usize),
+    File(PathBuf),
+}
+
+/* what the list shows of a file, the bodies are only counted */
+#[derive(Debug, Clone)]
+struct ScenarioSummary {
+    name: String,
+    author: String,
+    description: String,
+    modified: Option<String>,
+    body_count: usize,
+    /* the texture key and the image, loaded by the application once asked for */
+    thumbnail: Option<(String, Thumbnail)>,
+}
+
+#[derive(Debug)]
+struct ScenarioFile {
+    path: PathBuf,
+    /* the summary is read again once the file changes */
+    modified: Option<SystemTime>,
+    /* parsed when first drawn, the error of a corrupt file otherwise */
+    summary: Option<Result<ScenarioSummary, String>>,
+}
+
+#[derive(Debug, Default)]
+pub struct ScenarioBrowser {
+    pub open: bool,
+    files: Vec<ScenarioFile>,
+    scanned: Option<Instant>,
+    /* asked about before the file is removed */
+    deleting: Option<PathBuf>,
+    open_popup: bool,
+    error: Option<String>,
+    /* the thumbnail keys asked for, each is loaded once even when it fails */
+    requested_thumbnails: FastHashSet<String>,
+    thumbnail_requests: Vec<(String, Thumbnail)>,
+}
+
+impl ScenarioBrowser {
+    pub fn draw_menu_item(&mut self, ui: &imgui::Ui) {
+        if ui.menu_item(localization::tr("menu-scenarios")) {
+            self.open = true;
+            self.scanned = None;
+        }
+    }
+
+    /* their textures were unloaded, they are asked for again when shown */
+    pub fn forget_thumbnails(&mut self) {
+        self.requested_thumbnails.clear();
+    }
+
+    /* the thumbnails to upload since the last call */
+    pub fn take_thumbnail_requests(&mut self) -> Vec<(String, Thumbnail)> {
+        std::mem::take(&mut self.thumbnail_requests)
+    }
+
+    /* returns the scenario to load, loaded like a dropped file */
+    pub fn draw(
+        &mut self,
+        ui: &imgui::Ui,
+        now: Instant,
+        texture_manager: &TextureManager,
+    ) -> Option<ScenarioChoice> {
+        if !self.open {
+            return None;
+        }
+        if self
+            .scanned
+            .is_none_or(|scanned| now - scanned >= SCENARIO_RESCAN_INTERVAL)
+        {
+            self.rescan();
+            self.scanned = Some(now);
+        }
+        let mut open = true;
+        let mut choice = None;
+        ui.window(localization::title(
+            "window-scenarios",
+            SCENARIO_BROWSER_TITLE,
+        ))
+        .opened(&mut open)
+        .size(SCENARIO_BROWSER_SIZE, Condition::FirstUseEver)
+        .build(|| {
+            let _wrap = ui.push_text_wrap_pos();
+            ui.text("Built-in");
+            for (index, preset) in SCENARIO_PRESETS.iter().enumerate() {
+                let _id = ui.push_id_usize(index);
+                if ui.button("Load") {
+                    choice = Some(ScenarioChoice::Preset(index));
+                }
+                ui.same_line();
+                ui.text(format!(
+                    "{} by {}, {} bodies",
+                    preset.name,
+                    scenario::PRESET_AUTHOR,
+                    preset.scenario().objects.len()
+                ));
+                ui.text_disabled(preset.description);
+            }
+            ui.separator();
+
+            let directory = storage::scenarios_directory();
+            ui.text("Saved");
+            if self.files.is_empty() {
+                ui.text_disabled(format!(
+                    "Scenarios saved to {} are listed here",
+                    directory.display()
+                ));
+            }
+            if let Some(error) = self.error.as_ref() {
+                ui.text_colored([1., 0.35, 0.3, 1.], error);
+            }
+            for file in self.files.iter_mut() {
+                let _id = ui.push_id(file.path.to_string_lossy());
+                let summary = file
+                    .summary
+                    .get_or_insert_with(|| self::read_summary(&file.path, file.modified));
+                {
+                    let _disabled = ui.begin_disabled(summary.is_err());
+                    if ui.button("Load") {
+                        choice = Some(ScenarioChoice::File(file.path.clone()));
+                    }
+                }
+                ui.same_line();
+                if ui.button("Reveal")
+                    && let Err(error) = platform::reveal_in_file_manager(&file.path)
+                {
+                    self.error = Some(format!("{:#}", error));
+                }
+                ui.same_line();
+                if ui.button("Delete") {
+                    self.deleting = Some(file.path.clone());
+                    self.open_popup = true;
+                }
+                ui.same_line();
+                let file_name = self::file_name(&file.path);
+                match summary {
+                    Result::Ok(summary) => {
+                        if let Some((key, thumbnail)) = summary.thumbnail.as_ref() {
+                            match texture_manager.get(key) {
+                                Some(texture) => {
+                                    imgui::Image::new(
+                                        texture.texture_id,
+                                        [THUMBNAIL_HEIGHT * 16. / 9., THUMBNAIL_HEIGHT],
+                                    )
+                                    .build(ui);
+                                    ui.same_line();
+                                }
+                                None if self.requested_thumbnails.insert(key.clone()) => {
+                                    self.thumbnail_requests
+                                        .push((key.clone(), thumbnail.clone()));
+                                }
+                                None => {}
+                            }
+                        }
+                        /* the name of a file without one is its file name already */
+                        let unnamed = file
+                            .path
+                            .file_stem()
+                            .is_some_and(|stem| stem.to_string_lossy() == summary.name);
+                        let name = match unnamed || summary.name.is_empty() {
+                            true => file_name,
+                            false => format!("{} ({})", summary.name, file_name),
+                        };
+                        let author = match summary.author.is_empty() {
+                            true => String::new(),
+                            false => format!(" by {}", summary.author),
+                        };
+                        ui.text(format!("{}{}, {} bodies", name, author, summary.body_count));
+                        if let Some(modified) = summary.modified.as_ref() {
+                            ui.same_line();
+                            ui.text_disabled(format!("modified {}", modified));
+                        }
+                        if !summary.description.is_empty() {
+                            ui.text_disabled(&summary.description);
+                        }
+                    }
+                    Err(error) => {
+                        ui.text_disabled(format!("{}, cannot be read", file_name));
+                        if ui.is_item_hovered() {
+                            ui.tooltip_text(&*error);
+                        }
+                    }
+                }
+            }
+            self.draw_delete_popup(ui);
+        });
+        self.open = open;
+        choice
+    }
+
+    fn draw_delete_popup(&mut self, ui: &imgui::Ui) {
+        if std::mem::take(&mut self.open_popup) {
+            ui.open_popup(localization::title(
+                "popup-delete-scenario",
+                DELETE_POPUP_TITLE,
+            ));
+        }
+        ui.modal_popup(
+            localization::title("popup-delete-scenario", DELETE_POPUP_TITLE),
+            || {
+                let Some(path) = self.deleting.clone() else {
+                    ui.close_current_popup();
+                    return;
+                };
+                ui.text(format!("{} is deleted from the disk.", path.display()));
+                if ui.button("Delete") {
+                    self.error = std::fs::remove_file(&path)
+                        .err()
+                        .map(|error| format!("Failed to delete {}: {}", path.display(), error));
+                    self.deleting = None;
+                    self.scanned = None;
+                    ui.close_current_popup();
+                }
+                ui.same_line();
+                if ui.button("Cancel") {
+                    self.deleting = None;
+                    ui.close_current_popup();
+                }
+            },
+        );
+    }
+
+    /* keeps the summaries of the files that did not change */
+    fn rescan(&mut self) {
+        let Result::Ok(entries) = std::fs::read_dir(storage::scenarios_directory()) else {
+            self.files.clear();
+            return;
+        };
+        let mut files: Vec<ScenarioFile> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && storage::is_scenario_path(path))
+            .map(|path| {
+                let modified = path
+                    .metadata()
+                    .and_then(|metadata| metadata.modified())
+                    .ok();
+                let summary = self
+                    .files
+                    .iter_mut()
+                    .find(|file| file.path == path && file.modified == modified)
+                    .and_then(|file| file.summary.take());
+                ScenarioFile {
+                    path,
+                    modified,
+                    summary,
+                }
+            })
+            .collect();
+        files.sort_by(|first, second| first.path.cmp(&second.path));
+        self.files = files;
+    }
+}
+
+/* a thumbnail that cannot be read only leaves out the image, the error is logged */
+fn read_summary(path: &Path, modified: Option<SystemTime>) -> Result<ScenarioSummary, String> {
+    let scenario = storage::load_scenario(path).map_err(|error| format!("{:#}", error))?;
+    let metadata = scenario.metadata;
+    /* a new key once the file changed, the image may have changed with it */
+    let key = format!(
+        "thumbnail:{}:{:?}",
+        path.display(),
+        modified.and_then(|modified| modified.duration_since(SystemTime::UNIX_EPOCH).ok())
+    );
+    let thumbnail = match metadata.thumbnail(path) {
+        Some(Result::Ok(thumbnail)) => Some((key, thumbnail)),
+        Some(Err(error)) => {
+            warn!(
+                "The thumbnail of {} cannot be read: {:#}",
+                path.display(),
+                error
+            );
+            None
+        }
+        None => None,
+    };
+    Ok(ScenarioSummary {
+        name: metadata.name,
+        author: metadata.author,
+        description: metadata.description,
+        modified: metadata.modified,
+        body_count: scenario.objects.len(),
+        thumbnail,
+    })
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
@@ -0,0 +1,124 @@
+use std::path::{Path, PathBuf};
+
+use crate::{localization, storage::Scenario};
+
+const CONFIRM_POPUP_TITLE: &str = "Replace scenario?";
+const ERROR_POPUP_TITLE: &str = "Cannot load scenario";
+const DROP_HINT_BACKGROUND: [f32; 4] = [0.1, 0.3, 0.6, 0.35];
+
+/* a parsed scenario, loaded once the edits of the running one may be discarded */
+pub struct PendingScenario {
+    /* none for a built-in scene */
+    pub path: Option<PathBuf>,
+    pub scenario: Scenario,
+}
+
+impl PendingScenario {
+    /* the file name, or the name of a built-in scene */
+    pub fn label(&self) -> String {
+        match self.path.as_ref() {
+            Some(path) => self::file_name(path),
+            None => self.scenario.metadata.name.clone(),
+        }
+    }
+}
+
+/* the feedback around a scenario file dropped onto the window */
+#[derive(Default)]
+pub struct ScenarioDialog {
+    /* dragged over the window, not dropped yet */
+    hovered_file: Option<PathBuf>,
+    pending: Option<PendingScenario>,
+    error: Option<String>,
+    /* the popups are opened in the frame after the event */
+    open_popup: Option<&'static str>,
+}
+
+impl ScenarioDialog {
+    pub fn set_hovered_file(&mut self, path: Option<PathBuf>) {
+        self.hovered_file = path;
+    }
+
+    pub fn confirm(&mut self, pending: PendingScenario) {
+        self.pending = Some(pending);
+        self.open_popup = Some(CONFIRM_POPUP_TITLE);
+    }
+
+    pub fn show_error(&mut self, error: String) {
+        self.error = Some(error);
+        self.open_popup = Some(ERROR_POPUP_TITLE);
+    }
+
+    /* returns the pending scenario once replacing the edited one is confirmed */
+    pub fn draw(&mut self, ui: &imgui::Ui) -> Option<PendingScenario> {
+        if let Some(path) = self.hovered_file.as_ref() {
+            self::draw_drop_hint(ui, path);
+        }
+        /* a popup is found by the id after ###, the label in front of it is left out */
+        if let Some(title) = self.open_popup.take() {
+            ui.open_popup(format!("###{}", title));
+        }
+
+        let mut confirmed = None;
+        ui.modal_popup(
+            localization::title("popup-replace-scenario", CONFIRM_POPUP_TITLE),
+            || {
+                let Some(pending) = self.pending.as_ref() else {
+                    ui.close_current_popup();
+                    return;
+                };
+                ui.text(format!(
+                    "The edits of the running scenario are lost when loading {}.",
+                    pending.label()
+                ));
+                if ui.button("Load") {
+                    confirmed = self.pending.take();
+                    ui.close_current_popup();
+                }
+                ui.same_line();
+                if ui.button("Cancel") {
+                    self.pending = None;
+                    ui.close_current_popup();
+                }
+            },
+        );
+        ui.modal_popup(
+            localization::title("popup-cannot-load-scenario", ERROR_POPUP_TITLE),
+            || {
+                ui.text(self.error.as_deref().unwrap_or_default());
+                if ui.button("Ok") {
+                    self.error = None;
+                    ui.close_current_popup();
+                }
+            },
+        );
+        confirmed
+    }
+}
+
+/* tints the whole window, every part of it takes the drop */
+fn draw_drop_hint(ui: &imgui::Ui, path: &Path) {
+    let display_size = ui.io().display_size;
+    let draw_list = ui.get_foreground_draw_list();
+    draw_list
+        .add_rect([0., 0.], display_size, DROP_HINT_BACKGROUND)
+        .filled(true)
+        .build();
+    let text = format!("Drop to load {}", self::file_name(path));
+    let text_size = ui.calc_text_size(&text);
+    draw_list.add_text(
+        [
+            (display_size[0] - text_size[0]) * 0.5,
+            (display_size[1] - text_size[1]) * 0.5,
+        ],
+        [1., 1., 1., 1.],
+        text,
+    );
+}
+
+pub fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
@@ -0,0 +1,78 @@
+use imgui::Condition;
+
+use crate::storage::{EMBEDDED_THUMBNAIL_PREFIX, ScenarioMetadata};
+
+use crate::localization;
+
+pub const SCENARIO_PROPERTIES_TITLE: &str = "Scenario properties";
+pub const SCENARIO_PROPERTIES_SIZE: [f32; 2] = [420., 280.];
+
+/* edits what describes the running scenario, written with it when it is saved */
+#[derive(Debug, Default)]
+pub struct ScenarioProperties {
+    open: bool,
+    metadata: ScenarioMetadata,
+    thumbnail: String,
+}
+
+impl ScenarioProperties {
+    /* the application answers with edit, it holds the metadata of the running scenario */
+    pub fn draw_menu_item(&self, ui: &imgui::Ui) -> bool {
+        ui.menu_item(localization::tr("menu-scenario-properties"))
+    }
+
+    pub fn edit(&mut self, metadata: ScenarioMetadata) {
+        self.thumbnail = metadata.thumbnail.clone().unwrap_or_default();
+        self.metadata = metadata;
+        self.open = true;
+    }
+
+    /* returns the edited metadata once per press of the apply button */
+    pub fn draw(&mut self, ui: &imgui::Ui) -> Option<ScenarioMetadata> {
+        if !self.open {
+            return None;
+        }
+        let mut open = true;
+        let applied = ui
+            .window(localization::title(
+                "window-scenario-properties",
+                SCENARIO_PROPERTIES_TITLE,
+            ))
+            .opened(&mut open)
+            .size(SCENARIO_PROPERTIES_SIZE, Condition::FirstUseEver)
+            .build(|| self.draw_fields(ui))
+            .flatten();
+        self.open &= open;
+        applied
+    }
+
+    fn draw_fields(&mut self, ui: &imgui::Ui) -> Option<ScenarioMetadata> {
+        let metadata = &mut self.metadata;
+        ui.input_text("Name", &mut metadata.name).build();
+        ui.input_text("Author", &mut metadata.author).build();
+        ui.input_text_multiline(
+            "Description",
+            &mut metadata.description,
+            [0., ui.text_line_height() * 4.],
+        )
+        .build();
+        ui.input_text("Thumbnail", &mut self.thumbnail)
+            .hint("thumbnail.png")
+            .build();
+        ui.text_disabled(format!(
+            "An image next to the scenario file, or a png starting with {}",
+            EMBEDDED_THUMBNAIL_PREFIX
+        ));
+        ui.text_disabled(format!(
+            "Created {}, modified {}",
+            metadata.created.as_deref().unwrap_or("when first saved"),
+            metadata.modified.as_deref().unwrap_or("when saved")
+        ));
+        if !ui.button("Apply") {
+            return None;
+        }
+        metadata.thumbnail =
+            Some(self.thumbnail.trim().to_string()).filter(|path| !path.is_empty());
+        Some(metadata.clone())
+    }
+}
@@ -0,0 +1,399 @@
+use imgui::Condition;
+use strum::IntoEnumIterator;
+use winit::keyboard::KeyCode;
+
+use crate::{
+    graphics::{
+        billboards, fonts, labels::LabelSettings, post, starfield, surface::PresentPreference,
+        targets, tonemap::TonemapCurve, viewport,
+    },
+    input::{InputAction, KeyBinding, Keymap},
+    localization,
+    simulation::{Integrator, thread::HiddenPolicy},
+    storage::{self, SaveFormat, Settings},
+};
+
+pub const SETTINGS_WINDOW_TITLE: &str = "Settings";
+pub const SETTINGS_WINDOW_SIZE: [f32; 2] = [360., 420.];
+const KEY_BUTTON_WIDTH: f32 = 140.;
+const CONFLICT_COLOR: [f32; 4] = [1., 0.4, 0.3, 1.];
+
+#[derive(Debug, Default)]
+pub struct SettingsWindow {
+    pub open: bool,
+    /* listed when the window opens, not on every frame */
+    fonts: Vec<String>,
+    languages: Vec<localization::AvailableLanguage>,
+    /* waiting for the key press that replaces the keys of the action */
+    rebinding: Option<InputAction>,
+    captured: Option<KeyBinding>,
+    /* the text of the hidden label tags while it is typed, a trailing comma would otherwise be
+    parsed away on every key */
+    label_tags: Option<String>,
+    /* the button only asks, the application owns the imgui context to reset */
+    layout_reset: bool,
+}
+
+impl SettingsWindow {
+    pub fn draw_menu_item(&mut self, ui: &imgui::Ui) {
+        if ui.menu_item(localization::tr("menu-settings")) {
+            self.open = true;
+            self.fonts = fonts::available_fonts();
+            self.languages = localization::available_languages();
+        }
+    }
+
+    /* returns the edited settings once per change, the caller applies and saves them. the
+    active sample count differs from the requested one when the adapter lacks it */
+    pub fn draw(
+        &mut self,
+        ui: &imgui::Ui,
+        settings: &Settings,
+        active_sample_count: u32,
+    ) -> Option<Settings> {
+        if !self.open {
+            return None;
+        }
+        let mut open = true;
+        let mut edited = settings.clone();
+        ui.window(localization::title(
+            "window-settings",
+            SETTINGS_WINDOW_TITLE,
+        ))
+        .opened(&mut open)
+        .size(SETTINGS_WINDOW_SIZE, Condition::FirstUseEver)
+        .build(|| {
+            ui.text("Graphics");
+            self::combo(
+                ui,
+                "Present mode",
+                &mut edited.present_preference,
+                PresentPreference::iter(),
+            );
+            self::combo(
+                ui,
+                "MSAA",
+                &mut edited.sample_count,
+                targets::SAMPLE_COUNTS.into_iter(),
+            );
+            if active_sample_count != edited.sample_count {
+                ui.same_line();
+                ui.text_disabled(format!("using {}x", active_sample_count));
+            }
+            ui.slider_config(
+                "Render scale",
+                viewport::MINIMUM_RENDER_SCALE,
+                viewport::MAXIMUM_RENDER_SCALE,
+            )
+            .display_format("%.2fx")
+            .build(&mut edited.render_scale);
+            ui.slider_config(
+                "UI scale",
+                storage::MINIMUM_UI_SCALE,
+                storage::MAXIMUM_UI_SCALE,
+            )
+            .display_format("%.2fx")
+            .build(&mut edited.ui_scale);
+            self.font_combo(ui, "Font", "Embedded", &mut edited.font);
+            self.font_combo(ui, "Fallback font", "None", &mut edited.fallback_font);
+            ui.slider_config(
+                "Font size",
+                fonts::MINIMUM_FONT_SIZE,
+                fonts::MAXIMUM_FONT_SIZE,
+            )
+            .display_format("%.0f px")
+            .build(&mut edited.font_size);
+            if ui
+                .input_scalar("Frame rate limit", &mut edited.frame_rate_limit)
+                .step(10)
+                .build()
+                && edited.frame_rate_limit != storage::UNLIMITED_FRAME_RATE
+            {
+                edited.frame_rate_limit = edited
+                    .frame_rate_limit
+                    .max(storage::MINIMUM_FRAME_RATE_LIMIT);
+            }
+            ui.text_disabled("frames per second, 0 is unlimited");
+            ui.separator();
+
+            ui.text("Rendering");
+            ui.checkbox("Tonemapping", &mut edited.tonemapping);
+            if edited.tonemapping != settings.tonemapping {
+                ui.same_line();
+                ui.text_disabled("on the next start");
+            }
+            self::combo(
+                ui,
+                "Tonemap curve",
+                &mut edited.tonemap.curve,
+                TonemapCurve::iter(),
+            );
+            ui.checkbox("Lighting", &mut edited.lighting);
+            ui.checkbox("Bloom", &mut edited.bloom.enabled);
+            ui.slider_config("Bloom intensity", 0., post::MAXIMUM_BLOOM_INTENSITY)
+                .display_format("%.2f")
+                .build(&mut edited.bloom.intensity);
+            ui.slider_config(
+                "Bloom threshold",
+                post::MINIMUM_BLOOM_THRESHOLD,
+                post::MAXIMUM_BLOOM_THRESHOLD,
+            )
+            .flags(imgui::SliderFlags::LOGARITHMIC)
+            .display_format("%.2f")
+            .build(&mut edited.bloom.threshold);
+            ui.checkbox("Starfield", &mut edited.starfield.enabled);
+            if ui
+                .input_scalar("Star count", &mut edited.starfield.star_count)
+                .step(500)
+                .build()
+            {
+                edited.starfield.star_count = edited
+                    .starfield
+                    .star_count
+                    .min(starfield::MAXIMUM_STAR_COUNT);
+            }
+            ui.input_scalar("Star seed", &mut edited.starfield.seed)
+                .build();
+            ui.checkbox("Labels", &mut edited.labels.enabled);
+            self.label_tags_input(ui, &mut edited.labels);
+            ui.text_disabled("comma separated, like moon, spacecraft");
+            ui.slider_config(
+                "Minimum body size",
+                0.,
+                billboards::MAXIMUM_MINIMUM_BODY_PIXELS,
+            )
+            .display_format("%.1f px")
+            .build(&mut edited.billboards.minimum_pixels);
+            ui.separator();
+
+            ui.text("Interface");
+            self.language_combo(ui, &mut edited.language);
+            ui.checkbox("Remember UI layout", &mut edited.remember_ui_layout);
+            if ui.button("Reset UI layout") {
+                self.layout_reset = true;
+            }
+            ui.separator();
+
+            ui.text("Gamepad");
+            ui.slider_config("Deadzone", 0., storage::MAXIMUM_GAMEPAD_DEADZONE)
+                .display_format("%.2f")
+                .build(&mut edited.gamepad_deadzone);
+            ui.slider_config(
+                "Sensitivity",
+                storage::MINIMUM_GAMEPAD_SENSITIVITY,
+                storage::MAXIMUM_GAMEPAD_SENSITIVITY,
+            )
+            .display_format("%.2fx")
+            .build(&mut edited.gamepad_sensitivity);
+            ui.separator();
+
+            ui.text("Simulation");
+            self::combo(ui, "Integrator", &mut edited.integrator, Integrator::iter());
+            let mut trail_length = edited.trail_length as u32;
+            if ui
+                .input_scalar("Trail length", &mut trail_length)
+                .step(256)
+                .build()
+            {
+                edited.trail_length = trail_length.max(2) as usize;
+            }
+            ui.input_scalar("Autosave (min)", &mut edited.autosave_interval)
+                .step(1)
+                .build();
+            ui.text_disabled("0 turns autosaving off");
+            self::combo(
+                ui,
+                "Save format",
+                &mut edited.save_format,
+                SaveFormat::iter(),
+            );
+            self::combo(
+                ui,
+                "When hidden",
+                &mut edited.hidden_policy,
+                HiddenPolicy::iter(),
+            );
+            ui.separator();
+
+            if ui.collapsing_header("Keys", imgui::TreeNodeFlags::empty()) {
+                self.draw_keymap(ui, &mut edited.keymap);
+            }
+            if ui.button("Reset to defaults") {
+                edited = Settings {
+                    window: edited.window.clone(),
+                    last_directory: edited.last_directory.clone(),
+                    ..Settings::default()
+                };
+            }
+        });
+        self.open = open;
+        if !open {
+            self.rebinding = None;
+        }
+        (edited != *settings).then_some(edited)
+    }
+
+    /* true once after the reset button was pressed */
+    pub fn take_layout_reset(&mut self) -> bool {
+        std::mem::take(&mut self.layout_reset)
+    }
+
+    /* feeds a key press to a pending rebind. true while one waits, so the key triggers nothing
+    else, escape cancels it */
+    pub fn capture(&mut self, binding: KeyBinding) -> bool {
+        if self.rebinding.is_none() {
+            return false;
+        }
+        if KeyBinding::is_modifier(binding.key) {
+            return true;
+        }
+        match binding == KeyBinding::key(KeyCode::Escape) {
+            true => self.rebinding = None,
+            false => self.captured = Some(binding),
+        }
+        true
+    }
+
+    /* a click on the keys of an action waits for the next key press to replace them */
+    fn draw_keymap(&mut self, ui: &imgui::Ui, keymap: &mut Keymap) {
+        if let (Some(action), Some(binding)) = (self.rebinding, self.captured.take()) {
+            keymap.bind(action, vec![binding]);
+            self.rebinding = None;
+        }
+        let conflicts = keymap.conflicts();
+        for action in InputAction::iter() {
+            let _id = ui.push_id_usize(action as usize);
+            let bindings = keymap.bindings(action);
+            let label = match (self.rebinding == Some(action), bindings.is_empty()) {
+                (true, _) => "Press a key".to_string(),
+                (false, true) => "Unbound".to_string(),
+                (false, false) => bindings
+                    .iter()
+                    .map(KeyBinding::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            };
+            if ui.button_with_size(label, [KEY_BUTTON_WIDTH, 0.]) {
+                self.rebinding = Some(action);
+                self.captured = None;
+            }
+            ui.same_line();
+            {
+                let _disabled = ui.begin_disabled(bindings.is_empty());
+                if ui.small_button("Unbind") {
+                    keymap.bind(action, Vec::new());
+                }
+            }
+            ui.same_line();
+            ui.text(action.to_string());
+            let others = conflicts
+                .iter()
+                .filter(|(_, actions)| actions.contains(&action))
+                .flat_map(|(binding, actions)| {
+                    actions
+                        .iter()
+                        .filter(|other| **other != action)
+                        .map(move |other| format!("{} is also {}", binding, other))
+                })
+                .collect::<Vec<_>>();
+            if !others.is_empty() {
+                ui.text_colored(CONFLICT_COLOR, others.join(", "));
+            }
+        }
+    }
+
+    fn label_tags_input(&mut self, ui: &imgui::Ui, labels: &mut LabelSettings) {
+        let mut text = self.label_tags.take().unwrap_or_else(|| {
+            let tags: Vec<&str> = labels.hidden_tags.iter().map(String::as_str).collect();
+            tags.join(", ")
+        });
+        if ui.input_text("Hidden label tags", &mut text).build() {
+            labels.hidden_tags = text
+                .split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(str::to_string)
+                .collect();
+        }
+        if ui.is_item_active() {
+            self.label_tags = Some(text);
+        }
+    }
+
+    /* by the names of the bundles, a language set by hand without one is listed by its code.
+    the empty code follows the system */
+    fn language_combo(&self, ui: &imgui::Ui, language: &mut String) {
+        let label = match language.is_empty() {
+            true => format!("System ({})", localization::active_language()),
+            false => self
+                .languages
+                .iter()
+                .find(|available| available.code == *language)
+                .map_or_else(
+                    || language.clone(),
+                    |available| format!("{} ({})", available.name, available.code),
+                ),
+        };
+        let Some(_combo) = ui.begin_combo("Language", label) else {
+            return;
+        };
+        if ui
+            .selectable_config("System")
+            .selected(language.is_empty())
+            .build()
+        {
+            language.clear();
+        }
+        for available in &self.languages {
+            if ui
+                .selectable_config(format!("{} ({})", available.name, available.code))
+                .selected(available.code == *language)
+                .build()
+            {
+                *language = available.code.clone();
+            }
+        }
+    }
+
+    /* the empty file name stands for the choice without a file */
+    fn font_combo(&self, ui: &imgui::Ui, label: &str, without_file: &str, font: &mut String) {
+        let font_name = |name: &str| match name.is_empty() {
+            true => without_file.to_string(),
+            false => name.to_string(),
+        };
+        let Some(_combo) = ui.begin_combo(label, font_name(font)) else {
+            return;
+        };
+        std::iter::once("")
+            .chain(self.fonts.iter().map(String::as_str))
+            .for_each(|name| {
+                if ui
+                    .selectable_config(font_name(name))
+                    .selected(name == font.as_str())
+                    .build()
+                {
+                    *font = name.to_string();
+                }
+            });
+    }
+}
+
+fn combo<T: PartialEq + ToString>(
+    ui: &imgui::Ui,
+    label: &str,
+    value: &mut T,
+    choices: impl Iterator<Item = T>,
+) {
+    let Some(_combo) = ui.begin_combo(label, value.to_string()) else {
+        return;
+    };
+    choices.for_each(|choice| {
+        if ui
+            .selectable_config(choice.to_string())
+            .selected(choice == *value)
+            .build()
+        {
+            *value = choice;
+        }
+    });
+}
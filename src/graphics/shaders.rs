@@ -0,0 +1,135 @@
+use std::borrow::Cow;
+
+#[cfg(feature = "shader-hot-reload")]
+use anyhow::{Context, Result};
+#[cfg(feature = "shader-hot-reload")]
+use tracing::warn;
+
+#[cfg(feature = "shader-hot-reload")]
+use crate::platform;
+
+/* relative to the executable, see platform::asset_path */
+#[cfg(feature = "shader-hot-reload")]
+pub const SHADER_DIRECTORY: &str = "shaders";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShaderAsset {
+    pub file_name: &'static str,
+    /* compiled in, used by release builds and when the file cannot be read */
+    embedded: &'static str,
+}
+
+pub const BODY_SHADER: ShaderAsset = ShaderAsset {
+    file_name: "body.wgsl",
+    embedded: include_str!("../../shaders/body.wgsl"),
+};
+pub const BILLBOARD_SHADER: ShaderAsset = ShaderAsset {
+    file_name: "billboard.wgsl",
+    embedded: include_str!("../../shaders/billboard.wgsl"),
+};
+pub const TRAIL_SHADER: ShaderAsset = ShaderAsset {
+    file_name: "trail.wgsl",
+    embedded: include_str!("../../shaders/trail.wgsl"),
+};
+pub const STARFIELD_SHADER: ShaderAsset = ShaderAsset {
+    file_name: "starfield.wgsl",
+    embedded: include_str!("../../shaders/starfield.wgsl"),
+};
+pub const DEBRIS_SHADER: ShaderAsset = ShaderAsset {
+    file_name: "debris.wgsl",
+    embedded: include_str!("../../shaders/debris.wgsl"),
+};
+pub const RING_SHADER: ShaderAsset = ShaderAsset {
+    file_name: "rings.wgsl",
+    embedded: include_str!("../../shaders/rings.wgsl"),
+};
+pub const GRID_SHADER: ShaderAsset = ShaderAsset {
+    file_name: "grid.wgsl",
+    embedded: include_str!("../../shaders/grid.wgsl"),
+};
+pub const OUTLINE_SHADER: ShaderAsset = ShaderAsset {
+    file_name: "outline.wgsl",
+    embedded: include_str!("../../shaders/outline.wgsl"),
+};
+pub const TONEMAP_SHADER: ShaderAsset = ShaderAsset {
+    file_name: "tonemap.wgsl",
+    embedded: include_str!("../../shaders/tonemap.wgsl"),
+};
+pub const BLOOM_SHADER: ShaderAsset = ShaderAsset {
+    file_name: "bloom.wgsl",
+    embedded: include_str!("../../shaders/bloom.wgsl"),
+};
+
+impl ShaderAsset {
+    /* the file on disk with hot reloading, the embedded copy otherwise */
+    #[cfg(feature = "shader-hot-reload")]
+    pub fn source(&self) -> Cow<'static, str> {
+        let path = platform::asset_path(SHADER_DIRECTORY).join(self.file_name);
+        match std::fs::read_to_string(&path) {
+            Ok(source) => Cow::Owned(source),
+            Err(error) => {
+                warn!(
+                    "Failed to read {} ({}), using the embedded shader",
+                    path.display(),
+                    error
+                );
+                Cow::Borrowed(self.embedded)
+            }
+        }
+    }
+
+    #[cfg(not(feature = "shader-hot-reload"))]
+    pub fn source(&self) -> Cow<'static, str> {
+        Cow::Borrowed(self.embedded)
+    }
+
+    pub fn module(&self, device: &wgpu::Device) -> wgpu::ShaderModule {
+        device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some(self.file_name),
+            source: wgpu::ShaderSource::Wgsl(self.source()),
+        })
+    }
+}
+
+/* watches the shader directory and reports the file names of changed shaders */
+#[cfg(feature = "shader-hot-reload")]
+pub struct ShaderWatcher {
+    _watcher: notify::RecommendedWatcher,
+}
+
+#[cfg(feature = "shader-hot-reload")]
+impl ShaderWatcher {
+    pub fn spawn<N>(notifier: N) -> Result<Self>
+    where
+        N: Fn(String) + Send + 'static,
+    {
+        use notify::Watcher;
+
+        let directory = platform::asset_path(SHADER_DIRECTORY);
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(error) => return warn!("Shader watcher error: {}", error),
+                };
+                if !(event.kind.is_modify() || event.kind.is_create()) {
+                    return;
+                }
+                event
+                    .paths
+                    .iter()
+                    .filter(|path| {
+                        path.extension()
+                            .is_some_and(|extension| extension == "wgsl")
+                    })
+                    .filter_map(|path| path.file_name()?.to_str())
+                    .for_each(|file_name| notifier(file_name.to_string()));
+            })
+            .context("Failed to create the shader watcher")?;
+        watcher
+            .watch(&directory, notify::RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch {}", directory.display()))?;
+        tracing::info!("Watching {} for shader changes", directory.display());
+        Ok(Self { _watcher: watcher })
+    }
+}
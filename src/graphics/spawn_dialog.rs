@@ -0,0 +1,167 @@
+use glam::DVec3;
+use imgui::{Condition, Drag};
+
+use crate::{
+    graphics::inspector,
+    localization,
+    simulation::{
+        BodyAppearance, BodyId, GRAVITATIONAL_CONSTANT, SimulationBody,
+        SimulationEnterConfiguration, SimulationObject, SimulationPhysicsObject,
+    },
+};
+
+pub const SPAWN_WINDOW_TITLE: &str = "Add body";
+pub const SPAWN_WINDOW_SIZE: [f32; 2] = [340., 300.];
+/* above the surface of the parent, in parent radii */
+pub const DEFAULT_ORBIT_ALTITUDE: f64 = 2.;
+
+/* a new body and the body its enter values are relative to */
+pub struct BodySpawn {
+    pub object: SimulationObject,
+    pub relative_to: Option<BodyId>,
+}
+
+/* the add body window, its fields survive closing it so similar bodies are quick to add */
+pub struct SpawnDialog {
+    pub open: bool,
+    pub name: String,
+    pub mass: f64,
+    pub radius: f64,
+    pub tags: String,
+    /* a circular orbit in the ecliptic plane around the selected body */
+    pub orbit_selected: bool,
+    pub altitude: f64,
+    /* absolute when not orbiting */
+    pub position: [f64; 3],
+    pub velocity: [f64; 3],
+}
+
+impl Default for SpawnDialog {
+    fn default() -> Self {
+        Self {
+            open: false,
+            name: "Moon".to_string(),
+            mass: 7.342e22,
+            radius: 1.7374e6,
+            tags: "moon".to_string(),
+            orbit_selected: true,
+            altitude: DEFAULT_ORBIT_ALTITUDE,
+            position: [0.; 3],
+            velocity: [0.; 3],
+        }
+    }
+}
+
+impl SpawnDialog {
+    pub fn draw_menu_item(&mut self, ui: &imgui::Ui) {
+        if ui.menu_item(localization::tr("menu-add-body")) {
+            self.open = true;
+        }
+    }
+
+    /* returns the body to add once per press of the add button */
+    pub fn draw(&mut self, ui: &imgui::Ui, selected: Option<&SimulationBody>) -> Option<BodySpawn> {
+        if !self.open {
+            return None;
+        }
+        let mut open = true;
+        let spawn = ui
+            .window(localization::title("window-add-body", SPAWN_WINDOW_TITLE))
+            .opened(&mut open)
+            .position([16., 96.], Condition::FirstUseEver)
+            .size(SPAWN_WINDOW_SIZE, Condition::FirstUseEver)
+            .build(|| self.draw_fields(ui, selected))
+            .flatten();
+        self.open &= open;
+        spawn
+    }
+
+    fn draw_fields(
+        &mut self,
+        ui: &imgui::Ui,
+        selected: Option<&SimulationBody>,
+    ) -> Option<BodySpawn> {
+        ui.input_text("Name", &mut self.name).build();
+        ui.input_scalar("Mass (kg)", &mut self.mass)
+            .display_format("%.6e")
+            .build();
+        ui.input_scalar("Radius (m)", &mut self.radius)
+            .display_format("%.4e")
+            .build();
+        ui.input_text("Tags", &mut self.tags)
+            .hint("star, planet, moon")
+            .build();
+        ui.separator();
+
+        ui.checkbox("Orbit the selected body", &mut self.orbit_selected);
+        let parent = selected.filter(|_| self.orbit_selected);
+        match (self.orbit_selected, parent) {
+            (true, Some(parent)) => {
+                Drag::new("Altitude (radii)")
+                    .range(0., f64::MAX)
+                    .speed(0.01)
+                    .display_format("%.2f")
+                    .build(ui, &mut self.altitude);
+                let (_, velocity) = self.circular_orbit(parent);
+                ui.text(format!(
+                    "Around {} at {:.3e} m/s",
+                    parent.id_name,
+                    velocity.length()
+                ));
+            }
+            (true, None) => ui.text_disabled("Select a body to orbit"),
+            (false, _) => {
+                Drag::new("Position (m)")
+                    .speed(1e6)
+                    .display_format("%.4e")
+                    .build_array(ui, &mut self.position);
+                Drag::new("Velocity (m/s)")
+                    .speed(10.)
+                    .display_format("%.4e")
+                    .build_array(ui, &mut self.velocity);
+            }
+        }
+        ui.separator();
+
+        let valid = !self.name.trim().is_empty()
+            && self.mass >= 0.
+            && self.radius > 0.
+            && (!self.orbit_selected || parent.is_some());
+        let _disabled = ui.begin_disabled(!valid);
+        if !ui.button("Add") {
+            return None;
+        }
+        let (position, velocity) = match parent {
+            Some(parent) => self.circular_orbit(parent),
+            None => (
+                DVec3::from_array(self.position),
+                DVec3::from_array(self.velocity),
+            ),
+        };
+        let object = SimulationObject {
+            appearance: BodyAppearance {
+                tags: inspector::parse_tags(&self.tags),
+                ..Default::default()
+            },
+            ..SimulationObject::new(
+                self.name.trim().to_string(),
+                SimulationPhysicsObject::new(self.mass as f32, self.radius as f32),
+                SimulationEnterConfiguration::new(
+                    velocity.as_vec3().to_array(),
+                    position.as_vec3().to_array(),
+                ),
+            )
+        };
+        Some(BodySpawn {
+            object,
+            relative_to: parent.map(|parent| parent.id),
+        })
+    }
+
+    /* offset and velocity relative to the parent, prograde like the planets of the scenarios */
+    fn circular_orbit(&self, parent: &SimulationBody) -> (DVec3, DVec3) {
+        let distance = parent.radius * (1. + self.altitude);
+        let speed = (GRAVITATIONAL_CONSTANT * (parent.mass + self.mass) / distance).sqrt();
+        (DVec3::X * distance, DVec3::Z * speed)
+    }
+}
@@ -0,0 +1,191 @@
+use std::f32::consts::PI;
+
+use bytemuck::{Pod, Zeroable};
+use serde::{Deserialize, Serialize};
+use wgpu::{Device, TextureFormat, util::DeviceExt};
+
+use crate::graphics::{shaders, stats::DrawCounts, targets::DEPTH_FORMAT};
+
+pub const DEFAULT_STAR_COUNT: u32 = 4000;
+/* what the settings window allows, the instance buffer is built in one piece */
+pub const MAXIMUM_STAR_COUNT: u32 = 200_000;
+pub const DEFAULT_STARFIELD_SEED: u64 = 0x5EED_5A4C;
+/* billboard diameters in pixels, the brightest stars get the largest ones */
+pub const MINIMUM_STAR_SIZE: f32 = 1.5;
+pub const MAXIMUM_STAR_SIZE: f32 = 4.5;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct StarfieldConfiguration {
+    #[serde(rename = "enabled")]
+    pub enabled: bool,
+    #[serde(rename = "star count")]
+    pub star_count: u32,
+    #[serde(rename = "seed")]
+    pub seed: u64,
+}
+
+impl Default for StarfieldConfiguration {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            star_count: DEFAULT_STAR_COUNT,
+            seed: DEFAULT_STARFIELD_SEED,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct StarInstance {
+    pub direction: [f32; 3],
+    pub size: f32,
+    pub brightness: f32,
+}
+
+impl StarInstance {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32, 2 => Float32];
+}
+
+/* splitmix64, enough for a reproducible sky without pulling in a rng crate. also seeds the
+debris bursts */
+pub struct StarRandom(pub u64);
+
+impl StarRandom {
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut value = self.0;
+        value = (value ^ (value >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        value ^ (value >> 31)
+    }
+
+    /* uniform in [0, 1) */
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+/* uniformly distributed directions, brightness skewed so only a few stars stand out */
+pub fn generate_stars(count: u32, seed: u64) -> Vec<StarInstance> {
+    let mut random = StarRandom(seed);
+    (0..count)
+        .map(|_| {
+            let height = 2. * random.next_f32() - 1.;
+            let azimuth = 2. * PI * random.next_f32();
+            let ring = (1. - height * height).sqrt();
+            let magnitude = random.next_f32().powi(4);
+            StarInstance {
+                direction: [ring * azimuth.cos(), height, ring * azimuth.sin()],
+                size: MINIMUM_STAR_SIZE + (MAXIMUM_STAR_SIZE - MINIMUM_STAR_SIZE) * magnitude,
+                brightness: 0.25 + 0.75 * magnitude,
+            }
+        })
+        .collect()
+}
+
+pub struct StarfieldPipeline {
+    pub render_pipeline: wgpu::RenderPipeline,
+    pub instance_buffer: wgpu::Buffer,
+    pub star_count: u32,
+}
+
+impl StarfieldPipeline {
+    pub fn new(
+        device: &Device,
+        color_format: TextureFormat,
+        sample_count: u32,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+        configuration: &StarfieldConfiguration,
+    ) -> Self {
+        let shader = shaders::STARFIELD_SHADER.module(device);
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("starfield pipeline layout"),
+            bind_group_layouts: &[camera_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("starfield pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<StarInstance>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &StarInstance::ATTRIBUTES,
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: color_format,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::One,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent::REPLACE,
+                    }),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                ..Default::default()
+            },
+            /* drawn first without touching depth, so every body ends up in front */
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: wgpu::CompareFunction::Always,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        let stars = self::generate_stars(configuration.star_count, configuration.seed);
+        let instance_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("starfield instance buffer"),
+            contents: bytemuck::cast_slice(&stars),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        Self {
+            render_pipeline,
+            instance_buffer,
+            star_count: stars.len() as u32,
+        }
+    }
+
+    pub fn draw(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        camera_bind_group: &wgpu::BindGroup,
+    ) {
+        if self.star_count == 0 {
+            return;
+        }
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.instance_buffer.slice(..));
+        render_pass.draw(0..4, 0..self.star_count);
+    }
+
+    pub fn draw_counts(&self) -> DrawCounts {
+        match self.star_count {
+            0 => DrawCounts::default(),
+            stars => DrawCounts::new(1, stars),
+        }
+    }
+}
@@ -0,0 +1,516 @@
+use std::{
+    collections::VecDeque,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    time::Duration,
+};
+
+use strum::IntoEnumIterator;
+use tracing::warn;
+use wgpu::{BufferAsyncError, Device, Queue};
+
+/* frames averaged for the displayed timings */
+pub const STATS_HISTORY_LENGTH: usize = 60;
+/* wall time the frame pacing of the overlay is measured over */
+pub const FRAME_WINDOW: Duration = Duration::from_secs(2);
+/* readbacks in flight before timestamps are skipped for a frame, the render loop never waits */
+pub const TIMESTAMP_READBACK_COUNT: usize = 3;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, strum_macros::EnumIter, strum_macros::Display,
+)]
+pub enum TimedPass {
+    #[strum(to_string = "object")]
+    Object,
+    /* bloom and tonemapping */
+    #[strum(to_string = "post")]
+    Post,
+    #[strum(to_string = "imgui")]
+    Imgui,
+}
+
+impl TimedPass {
+    pub const COUNT: usize = 3;
+
+    fn index(&self) -> usize {
+        match self {
+            TimedPass::Object => 0,
+            TimedPass::Post => 1,
+            TimedPass::Imgui => 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, strum_macros::Display)]
+pub enum TimingSource {
+    /* encoding time on the cpu, used without Features::TIMESTAMP_QUERY */
+    #[default]
+    #[strum(to_string = "cpu")]
+    Cpu,
+    #[strum(to_string = "gpu")]
+    Gpu,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PassTimings([Duration; TimedPass::COUNT]);
+
+impl PassTimings {
+    pub fn get(&self, pass: TimedPass) -> Duration {
+        self.0[pass.index()]
+    }
+
+    pub fn set(&mut self, pass: TimedPass, duration: Duration) {
+        self.0[pass.index()] = duration;
+    }
+
+    pub fn total(&self) -> Duration {
+        self.0.iter().sum()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DrawCounts {
+    pub draw_calls: u32,
+    pub instances: u32,
+}
+
+impl DrawCounts {
+    pub fn new(draw_calls: u32, instances: u32) -> Self {
+        Self {
+            draw_calls,
+            instances,
+        }
+    }
+
+    pub fn add(&mut self, other: DrawCounts) {
+        self.draw_calls += other.draw_calls;
+        self.instances += other.instances;
+    }
+}
+
+/* what the ui and the trace output read, updated once per frame */
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RenderStats {
+    /* cpu time of the whole render call, averaged */
+    pub frame_time: Duration,
+    /* averaged, gpu timings lag a few frames behind */
+    pub pass_timings: PassTimings,
+    pub timing_source: TimingSource,
+    /* of the latest frame */
+    pub draw_counts: DrawCounts,
+}
+
+/* rolling averages over STATS_HISTORY_LENGTH frames, gpu timings replace the cpu ones as soon
+as the first readback arrives */
+#[derive(Debug, Default)]
+pub struct StatsAggregator {
+    frame_times: VecDeque<Duration>,
+    cpu_timings: VecDeque<PassTimings>,
+    gpu_timings: VecDeque<PassTimings>,
+    draw_counts: DrawCounts,
+}
+
+impl StatsAggregator {
+    pub fn record_frame(
+        &mut self,
+        frame_time: Duration,
+        cpu_timings: PassTimings,
+        draw_counts: DrawCounts,
+    ) {
+        self::push_bounded(&mut self.frame_times, frame_time);
+        self::push_bounded(&mut self.cpu_timings, cpu_timings);
+        self.draw_counts = draw_counts;
+    }
+
+    pub fn record_gpu_timings(&mut self, timings: PassTimings) {
+        self::push_bounded(&mut self.gpu_timings, timings);
+    }
+
+    pub fn stats(&self) -> RenderStats {
+        let (timings, timing_source) = match self.gpu_timings.is_empty() {
+            true => (&self.cpu_timings, TimingSource::Cpu),
+            false => (&self.gpu_timings, TimingSource::Gpu),
+        };
+        let mut pass_timings = PassTimings::default();
+        TimedPass::iter().for_each(|pass| {
+            pass_timings.set(
+                pass,
+                self::average(timings.iter().map(|timing| timing.get(pass))),
+            );
+        });
+        RenderStats {
+            frame_time: self::average(self.frame_times.iter().copied()),
+            pass_timings,
+            timing_source,
+            draw_counts: self.draw_counts,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FramePacing {
+    pub fps: f64,
+    pub average_frame_time: Duration,
+    /* fps of the slowest one percent of the frames */
+    pub low_fps: f64,
+    pub frame_count: usize,
+}
+
+/* time between redraws over the last FRAME_WINDOW, so a hitch stays visible for seconds
+instead of flickering by in a single frame */
+#[derive(Debug, Default)]
+pub struct FrameWindow {
+    deltas: VecDeque<Duration>,
+    total: Duration,
+}
+
+impl FrameWindow {
+    pub fn record(&mut self, delta: Duration) {
+        self.deltas.push_back(delta);
+        self.total += delta;
+        /* a single frame longer than the window is kept, there is nothing else to show */
+        while self.deltas.len() > 1 && self.total > FRAME_WINDOW {
+            let oldest = self.deltas.pop_front().expect("more than one delta");
+            self.total -= oldest;
+        }
+    }
+
+    pub fn pacing(&self) -> FramePacing {
+        let frame_count = self.deltas.len();
+        if frame_count == 0 || self.total.is_zero() {
+            return FramePacing::default();
+        }
+        let mut slowest = self.deltas.iter().copied().collect::<Vec<_>>();
+        slowest.sort_unstable_by(|left, right| right.cmp(left));
+        slowest.truncate(frame_count.div_ceil(100));
+        let low_frame_time = self::average(slowest.into_iter());
+        FramePacing {
+            fps: frame_count as f64 / self.total.as_secs_f64(),
+            average_frame_time: self.total / frame_count as u32,
+            low_fps: 1. / low_frame_time.as_secs_f64().max(f64::MIN_POSITIVE),
+            frame_count,
+        }
+    }
+}
+
+fn push_bounded<T>(history: &mut VecDeque<T>, value: T) {
+    if history.len() == STATS_HISTORY_LENGTH {
+        history.pop_front();
+    }
+    history.push_back(value);
+}
+
+fn average(durations: impl ExactSizeIterator<Item = Duration>) -> Duration {
+    let count = durations.len() as u32;
+    if count == 0 {
+        return Duration::ZERO;
+    }
+    durations.sum::<Duration>() / count
+}
+
+enum ReadbackState {
+    Idle,
+    /* resolve and copy are recorded, mapping waits for the submit */
+    Encoded,
+    Mapping(Receiver<Result<(), BufferAsyncError>>),
+}
+
+struct TimestampReadback {
+    buffer: wgpu::Buffer,
+    state: ReadbackState,
+    /* passes that actually ran, the others resolve to unwritten queries */
+    written: [bool; TimedPass::COUNT],
+}
+
+/* two timestamps per timed pass, resolved into a ring of readback buffers */
+pub struct GpuTimer {
+    query_set: wgpu::QuerySet,
+    resolve_buffer: wgpu::Buffer,
+    readbacks: Vec<TimestampReadback>,
+    /* nanoseconds per timestamp tick */
+    period: f32,
+    /* readback recording this frame, none when every buffer is still in flight */
+    current: Option<usize>,
+}
+
+impl GpuTimer {
+    /* none without Features::TIMESTAMP_QUERY, the cpu timings are used instead */
+    pub fn new(device: &Device, queue: &Queue) -> Option<Self> {
+        if !device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            return None;
+        }
+        let query_count = 2 * TimedPass::COUNT as u32;
+        let size = query_count as u64 * wgpu::QUERY_SIZE as u64;
+        let query_set = device.create_query_set(&wgpu::QuerySetDescriptor {
+            label: Some("pass timestamps"),
+            ty: wgpu::QueryType::Timestamp,
+            count: query_count,
+        });
+        let resolve_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("timestamp resolve buffer"),
+            size,
+            usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readbacks = (0..TIMESTAMP_READBACK_COUNT)
+            .map(|_| TimestampReadback {
+                buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("timestamp readback buffer"),
+                    size,
+                    usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                }),
+                state: ReadbackState::Idle,
+                written: [false; TimedPass::COUNT],
+            })
+            .collect();
+        Some(Self {
+            query_set,
+            resolve_buffer,
+            readbacks,
+            period: queue.get_timestamp_period(),
+            current: None,
+        })
+    }
+
+    pub fn begin_frame(&mut self) {
+        self.current = self
+            .readbacks
+            .iter()
+            .position(|readback| matches!(readback.state, ReadbackState::Idle));
+        if let Some(readback) = self.current.map(|index| &mut self.readbacks[index]) {
+            readback.written = [false; TimedPass::COUNT];
+        }
+    }
+
+    /* a pass spanning several render passes asks for the beginning on its first and the end on
+    its last one */
+    pub fn timestamp_writes(
+        &mut self,
+        pass: TimedPass,
+        beginning: bool,
+        end: bool,
+    ) -> Option<wgpu::RenderPassTimestampWrites<'_>> {
+        let readback = &mut self.readbacks[self.current?];
+        if end {
+            readback.written[pass.index()] = true;
+        }
+        let first = 2 * pass.index() as u32;
+        Some(wgpu::RenderPassTimestampWrites {
+            query_set: &self.query_set,
+            beginning_of_pass_write_index: beginning.then_some(first),
+            end_of_pass_write_index: end.then_some(first + 1),
+        })
+    }
+
+    pub fn encode_resolve(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        let Some(index) = self.current else {
+            return;
+        };
+        let query_count = 2 * TimedPass::COUNT as u32;
+        encoder.resolve_query_set(&self.query_set, 0..query_count, &self.resolve_buffer, 0);
+        encoder.copy_buffer_to_buffer(
+            &self.resolve_buffer,
+            0,
+            &self.readbacks[index].buffer,
+            0,
+            self.resolve_buffer.size(),
+        );
+        self.readbacks[index].state = ReadbackState::Encoded;
+    }
+
+    pub fn after_submit(&mut self) {
+        let Some(readback) = self.current.take().map(|index| &mut self.readbacks[index]) else {
+            return;
+        };
+        if !matches!(readback.state, ReadbackState::Encoded) {
+            return;
+        }
+        let (sender, receiver) = mpsc::channel();
+        readback
+            .buffer
+            .slice(..)
+            .map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+        readback.state = ReadbackState::Mapping(receiver);
+    }
+
+    /* collects every finished readback without blocking */
+    pub fn poll(&mut self, device: &Device) -> Vec<PassTimings> {
+        let _ = device.poll(wgpu::PollType::Poll);
+        let period = self.period as f64;
+        self.readbacks
+            .iter_mut()
+            .filter_map(|readback| {
+                let ReadbackState::Mapping(receiver) = &readback.state else {
+                    return None;
+                };
+                let result = match receiver.try_recv() {
+                    Err(TryRecvError::Empty) => return None,
+                    Err(TryRecvError::Disconnected) => Err(None),
+                    Ok(result) => result.map_err(Some),
+                };
+                readback.state = ReadbackState::Idle;
+                if let Err(error) = result {
+                    warn!("Failed to read back pass timestamps: {:?}", error);
+                    return None;
+                }
+                let ticks: Vec<u64> =
+                    bytemuck::pod_collect_to_vec(&readback.buffer.slice(..).get_mapped_range());
+                readback.buffer.unmap();
+                let mut timings = PassTimings::default();
+                TimedPass::iter()
+                    .filter(|pass| readback.written[pass.index()])
+                    .for_each(|pass| {
+                        let first = 2 * pass.index();
+                        let elapsed = ticks[first + 1].saturating_sub(ticks[first]);
+                        timings.set(pass, Duration::from_nanos((elapsed as f64 * period) as u64));
+                    });
+                Some(timings)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn milliseconds(value: u64) -> Duration {
+        Duration::from_millis(value)
+    }
+
+    fn timings(object: u64, post: u64, imgui: u64) -> PassTimings {
+        let mut timings = PassTimings::default();
+        timings.set(TimedPass::Object, self::milliseconds(object));
+        timings.set(TimedPass::Post, self::milliseconds(post));
+        timings.set(TimedPass::Imgui, self::milliseconds(imgui));
+        timings
+    }
+
+    #[test]
+    fn cpu_timings_are_averaged() {
+        let mut aggregator = StatsAggregator::default();
+        aggregator.record_frame(
+            self::milliseconds(10),
+            self::timings(4, 2, 1),
+            DrawCounts::new(3, 30),
+        );
+        aggregator.record_frame(
+            self::milliseconds(20),
+            self::timings(8, 4, 3),
+            DrawCounts::new(5, 50),
+        );
+        let stats = aggregator.stats();
+        assert_eq!(stats.frame_time, self::milliseconds(15));
+        assert_eq!(stats.pass_timings, self::timings(6, 3, 2));
+        assert_eq!(stats.pass_timings.total(), self::milliseconds(11));
+        assert_eq!(stats.timing_source, TimingSource::Cpu);
+        /* the counts are the ones of the latest frame */
+        assert_eq!(stats.draw_counts, DrawCounts::new(5, 50));
+    }
+
+    #[test]
+    fn gpu_timings_replace_the_cpu_ones() {
+        let mut aggregator = StatsAggregator::default();
+        aggregator.record_frame(
+            self::milliseconds(10),
+            self::timings(4, 2, 1),
+            DrawCounts::default(),
+        );
+        aggregator.record_gpu_timings(self::timings(1, 1, 1));
+        let stats = aggregator.stats();
+        assert_eq!(stats.timing_source, TimingSource::Gpu);
+        assert_eq!(stats.pass_timings, self::timings(1, 1, 1));
+    }
+
+    #[test]
+    fn history_keeps_the_latest_frames() {
+        let mut aggregator = StatsAggregator::default();
+        for _ in 0..STATS_HISTORY_LENGTH {
+            aggregator.record_frame(
+                self::milliseconds(100),
+                PassTimings::default(),
+                DrawCounts::default(),
+            );
+        }
+        for _ in 0..STATS_HISTORY_LENGTH {
+            aggregator.record_frame(
+                self::milliseconds(10),
+                PassTimings::default(),
+                DrawCounts::default(),
+            );
+        }
+        assert_eq!(aggregator.stats().frame_time, self::milliseconds(10));
+    }
+
+    #[test]
+    fn empty_aggregator_reports_zero() {
+        assert_eq!(StatsAggregator::default().stats(), RenderStats::default());
+    }
+
+    #[test]
+    fn draw_counts_add_up() {
+        let mut counts = DrawCounts::new(1, 10);
+        counts.add(DrawCounts::new(2, 20));
+        assert_eq!(counts, DrawCounts::new(3, 30));
+    }
+
+    #[test]
+    fn frame_window_measures_steady_frames() {
+        let mut window = FrameWindow::default();
+        (0..120).for_each(|_| window.record(self::milliseconds(10)));
+        let pacing = window.pacing();
+        assert_eq!(pacing.frame_count, 120);
+        assert!((pacing.fps - 100.).abs() < 1e-9);
+        assert_eq!(pacing.average_frame_time, self::milliseconds(10));
+        assert!((pacing.low_fps - 100.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn frame_window_forgets_frames_older_than_the_window() {
+        let mut window = FrameWindow::default();
+        (0..100).for_each(|_| window.record(self::milliseconds(50)));
+        /* 2 s of 50 ms frames is 40 of them */
+        assert_eq!(window.pacing().frame_count, 40);
+        (0..200).for_each(|_| window.record(self::milliseconds(10)));
+        let pacing = window.pacing();
+        assert_eq!(pacing.frame_count, 200);
+        assert_eq!(pacing.average_frame_time, self::milliseconds(10));
+    }
+
+    #[test]
+    fn frame_window_keeps_a_single_long_frame() {
+        let mut window = FrameWindow::default();
+        window.record(self::milliseconds(10));
+        window.record(Duration::from_secs(5));
+        let pacing = window.pacing();
+        assert_eq!(pacing.frame_count, 1);
+        assert!((pacing.fps - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn low_fps_is_the_slowest_percent() {
+        let mut window = FrameWindow::default();
+        /* 198 frames of 9 ms and two hitches, the slowest percent is the two hitches */
+        (0..99).for_each(|_| window.record(self::milliseconds(9)));
+        window.record(self::milliseconds(40));
+        (0..99).for_each(|_| window.record(self::milliseconds(9)));
+        window.record(self::milliseconds(60));
+        let pacing = window.pacing();
+        assert_eq!(pacing.frame_count, 200);
+        assert!((pacing.low_fps - 20.).abs() < 1e-9, "{}", pacing.low_fps);
+        /* a single frame is its own slowest percent */
+        let mut window = FrameWindow::default();
+        window.record(self::milliseconds(25));
+        assert!((window.pacing().low_fps - 40.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn empty_frame_window_reports_zero() {
+        assert_eq!(FrameWindow::default().pacing(), FramePacing::default());
+        let mut window = FrameWindow::default();
+        window.record(Duration::ZERO);
+        assert_eq!(window.pacing(), FramePacing::default());
+    }
+}
@@ -0,0 +1,79 @@
+use glam::DVec3;
+use imgui::{Condition, StyleVar, WindowFlags};
+
+use crate::{
+    graphics::toolbar,
+    localization::{self, Quantity},
+    simulation::{SimulationBody, thread::SimulationSnapshot},
+};
+
+const STATUS_BAR_PADDING: [f32; 2] = [8., 3.];
+const MESSAGE_COLOR: [f32; 4] = [0.55, 0.85, 1., 1.];
+
+/* one line, so the viewport can end above it before it is drawn */
+pub fn height(ui: &imgui::Ui) -> f32 {
+    ui.text_line_height() + STATUS_BAR_PADDING[1] * 2.
+}
+
+/* pinned along the bottom edge of the window, it follows every resize. the scenario carries a
+star while it has unsaved edits */
+pub fn draw(
+    ui: &imgui::Ui,
+    scenario_label: &str,
+    snapshot: &SimulationSnapshot,
+    selected: Option<&SimulationBody>,
+    camera_position: DVec3,
+    message: Option<&str>,
+) {
+    let display_size = ui.io().display_size;
+    let height = self::height(ui);
+    let _padding = ui.push_style_var(StyleVar::WindowPadding(STATUS_BAR_PADDING));
+    let _rounding = ui.push_style_var(StyleVar::WindowRounding(0.));
+    ui.window("##status bar")
+        .position([0., display_size[1] - height], Condition::Always)
+        .size([display_size[0], height], Condition::Always)
+        .flags(
+            WindowFlags::NO_DECORATION
+                | WindowFlags::NO_MOVE
+                | WindowFlags::NO_NAV
+                | WindowFlags::NO_FOCUS_ON_APPEARING
+                | WindowFlags::NO_SAVED_SETTINGS
+                | WindowFlags::NO_SCROLL_WITH_MOUSE,
+        )
+        .build(|| {
+            let mut parts = vec![
+                format!(
+                    "{}{}",
+                    scenario_label,
+                    if snapshot.dirty { "*" } else { "" }
+                ),
+                /* the warp also as the simulated time per second, like 1 d/s */
+                format!(
+                    "T+{} at {}x ({}/s)",
+                    toolbar::format_elapsed(snapshot.simulation_time),
+                    snapshot.warp,
+                    localization::format_quantity(
+                        snapshot.warp,
+                        Quantity::Time,
+                        &localization::active_language()
+                    )
+                ),
+            ];
+            if let Some(body) = selected {
+                parts.push(format!(
+                    "{}, {} away",
+                    body.id_name,
+                    localization::format_quantity(
+                        body.position.distance(camera_position),
+                        Quantity::Length,
+                        &localization::active_language()
+                    )
+                ));
+            }
+            ui.text(parts.join(" | "));
+            if let Some(message) = message {
+                ui.same_line();
+                ui.text_colored(MESSAGE_COLOR, format!("| {}", message));
+            }
+        });
+}
@@ -0,0 +1,235 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use strum::{EnumIter, IntoEnumIterator};
+use wgpu::{CompositeAlphaMode, PresentMode, TextureFormat};
+
+pub const DEFAULT_PRESENT_PREFERENCE: PresentPreference = PresentPreference::VSync;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, EnumIter, strum_macros::Display, Serialize, Deserialize,
+)]
+pub enum PresentPreference {
+    #[strum(to_string = "VSync")]
+    #[serde(rename = "vsync")]
+    VSync,
+    #[strum(to_string = "Adaptive VSync")]
+    #[serde(rename = "adaptive")]
+    Adaptive,
+    #[strum(to_string = "Uncapped")]
+    #[serde(rename = "uncapped")]
+    Uncapped,
+}
+
+impl PresentPreference {
+    /* fifo is guaranteed by every surface and closes each list */
+    pub fn present_mode_order(&self) -> &'static [PresentMode] {
+        match self {
+            PresentPreference::VSync => &[PresentMode::Fifo],
+            PresentPreference::Adaptive => &[
+                PresentMode::FifoRelaxed,
+                PresentMode::Mailbox,
+                PresentMode::Fifo,
+            ],
+            PresentPreference::Uncapped => &[
+                PresentMode::Immediate,
+                PresentMode::Mailbox,
+                PresentMode::FifoRelaxed,
+                PresentMode::Fifo,
+            ],
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "vsync" | "fifo" => Some(PresentPreference::VSync),
+            "adaptive" => Some(PresentPreference::Adaptive),
+            "uncapped" | "immediate" => Some(PresentPreference::Uncapped),
+            _ => None,
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        PresentPreference::iter()
+            .cycle()
+            .skip_while(|preference| preference != self)
+            .nth(1)
+            .unwrap_or(*self)
+    }
+}
+
+pub fn select_present_mode(
+    preference: PresentPreference,
+    available: &[PresentMode],
+) -> Option<PresentMode> {
+    self::select_by_preference(preference.present_mode_order(), available)
+}
+
+pub const ALPHA_MODE_PREFERENCE: [CompositeAlphaMode; 5] = [
+    CompositeAlphaMode::Opaque,
+    CompositeAlphaMode::Auto,
+    CompositeAlphaMode::Inherit,
+    CompositeAlphaMode::PreMultiplied,
+    CompositeAlphaMode::PostMultiplied,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurfaceFormatSelection {
+    pub format: TextureFormat,
+    /* the surface does not encode srgb, shaders have to apply the gamma curve themselves */
+    pub manual_gamma_correction: bool,
+}
+
+/* prefers an srgb format, otherwise takes the first one the surface lists */
+pub fn select_surface_format(formats: &[TextureFormat]) -> Result<SurfaceFormatSelection> {
+    if let Some(format) = formats.iter().find(|format| format.is_srgb()) {
+        return Ok(SurfaceFormatSelection {
+            format: *format,
+            manual_gamma_correction: false,
+        });
+    }
+    formats
+        .first()
+        .map(|format| SurfaceFormatSelection {
+            format: *format,
+            manual_gamma_correction: true,
+        })
+        .ok_or_else(|| anyhow!("The surface does not expose any texture format"))
+}
+
+/* first entry of the preference list the surface supports, falls back to its first mode */
+pub fn select_by_preference<T: Copy + PartialEq>(preference: &[T], available: &[T]) -> Option<T> {
+    preference
+        .iter()
+        .find(|candidate| available.contains(candidate))
+        .or(available.first())
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn srgb_format_is_preferred() {
+        let selection = select_surface_format(&[
+            TextureFormat::Bgra8Unorm,
+            TextureFormat::Rgba16Float,
+            TextureFormat::Bgra8UnormSrgb,
+        ])
+        .unwrap();
+        assert_eq!(
+            selection,
+            SurfaceFormatSelection {
+                format: TextureFormat::Bgra8UnormSrgb,
+                manual_gamma_correction: false
+            }
+        );
+    }
+
+    #[test]
+    fn linear_formats_correct_the_gamma_manually() {
+        let selection =
+            select_surface_format(&[TextureFormat::Rgba8Unorm, TextureFormat::Bgra8Unorm]).unwrap();
+        assert_eq!(
+            selection,
+            SurfaceFormatSelection {
+                format: TextureFormat::Rgba8Unorm,
+                manual_gamma_correction: true
+            }
+        );
+    }
+
+    #[test]
+    fn no_format_is_an_error() {
+        assert!(select_surface_format(&[]).is_err());
+    }
+
+    #[test]
+    fn alpha_mode_falls_back_to_the_first_one() {
+        assert_eq!(
+            select_by_preference(
+                &ALPHA_MODE_PREFERENCE,
+                &[
+                    CompositeAlphaMode::PostMultiplied,
+                    CompositeAlphaMode::Opaque
+                ]
+            ),
+            Some(CompositeAlphaMode::Opaque)
+        );
+        assert_eq!(select_by_preference(&[1, 2], &[7, 8]), Some(7));
+        assert_eq!(select_by_preference::<i32>(&[1, 2], &[]), None);
+    }
+
+    #[test]
+    fn present_modes_follow_the_preference() {
+        let everything = [
+            PresentMode::Fifo,
+            PresentMode::FifoRelaxed,
+            PresentMode::Mailbox,
+            PresentMode::Immediate,
+        ];
+        let cases = [
+            (PresentPreference::VSync, &everything[..], PresentMode::Fifo),
+            (
+                PresentPreference::Adaptive,
+                &everything[..],
+                PresentMode::FifoRelaxed,
+            ),
+            (
+                PresentPreference::Uncapped,
+                &everything[..],
+                PresentMode::Immediate,
+            ),
+            /* a typical wayland surface */
+            (
+                PresentPreference::Uncapped,
+                &[PresentMode::Fifo, PresentMode::Mailbox][..],
+                PresentMode::Mailbox,
+            ),
+            (
+                PresentPreference::Adaptive,
+                &[PresentMode::Fifo, PresentMode::Immediate][..],
+                PresentMode::Fifo,
+            ),
+            (
+                PresentPreference::VSync,
+                &[PresentMode::Immediate][..],
+                PresentMode::Immediate,
+            ),
+        ];
+        for (preference, available, expected) in cases {
+            assert_eq!(
+                select_present_mode(preference, available),
+                Some(expected),
+                "{} on {:?}",
+                preference,
+                available
+            );
+        }
+    }
+
+    #[test]
+    fn every_preference_ends_in_fifo() {
+        for preference in PresentPreference::iter() {
+            assert_eq!(
+                preference.present_mode_order().last(),
+                Some(&PresentMode::Fifo)
+            );
+        }
+    }
+
+    #[test]
+    fn preferences_cycle_and_parse() {
+        assert_eq!(PresentPreference::VSync.next(), PresentPreference::Adaptive);
+        assert_eq!(PresentPreference::Uncapped.next(), PresentPreference::VSync);
+        assert_eq!(
+            PresentPreference::from_name("Immediate"),
+            Some(PresentPreference::Uncapped)
+        );
+        assert_eq!(
+            PresentPreference::from_name("fifo"),
+            Some(PresentPreference::VSync)
+        );
+        assert_eq!(PresentPreference::from_name("triple"), None);
+    }
+}
@@ -0,0 +1,93 @@
+use tracing::warn;
+use wgpu::{Adapter, Device, TextureFormat};
+
+use crate::graphics::{tonemap::HDR_FORMAT, viewport::Viewport};
+
+/* shared by every pipeline that draws into the object pass */
+pub const DEPTH_FORMAT: TextureFormat = TextureFormat::Depth32Float;
+/* reversed-z, the far end of the depth range is 0 and nearer fragments win with larger depth */
+pub const DEPTH_CLEAR_VALUE: f32 = 0.;
+pub const DEPTH_COMPARE: wgpu::CompareFunction = wgpu::CompareFunction::GreaterEqual;
+pub const DEFAULT_SAMPLE_COUNT: u32 = 4;
+pub const SAMPLE_COUNTS: [u32; 4] = [1, 2, 4, 8];
+
+/* offscreen attachments sized after the viewport, recreated whenever it is resized */
+pub struct RenderTargets {
+    pub sample_count: u32,
+    /* format the scene pipelines render into, the hdr format or the viewport format */
+    pub color_format: TextureFormat,
+    /* multisampled color target resolved into the hdr target or the viewport, None at 1x */
+    pub msaa_color_view: Option<wgpu::TextureView>,
+    /* single sampled scene color read by the tonemapping pass, None on the direct path */
+    pub hdr_view: Option<wgpu::TextureView>,
+    pub depth_view: wgpu::TextureView,
+}
+
+impl RenderTargets {
+    pub fn new(device: &Device, viewport: &Viewport, sample_count: u32, hdr: bool) -> Self {
+        let size = wgpu::Extent3d {
+            width: viewport.width,
+            height: viewport.height,
+            depth_or_array_layers: 1,
+        };
+        let color_format = match hdr {
+            true => HDR_FORMAT,
+            false => viewport.format,
+        };
+        let create_view = |label: &str, format: TextureFormat, sample_count: u32, usage| {
+            device
+                .create_texture(&wgpu::TextureDescriptor {
+                    label: Some(label),
+                    size,
+                    mip_level_count: 1,
+                    sample_count,
+                    dimension: wgpu::TextureDimension::D2,
+                    format,
+                    usage,
+                    view_formats: &[],
+                })
+                .create_view(&wgpu::TextureViewDescriptor::default())
+        };
+        let attachment = wgpu::TextureUsages::RENDER_ATTACHMENT;
+        Self {
+            sample_count,
+            color_format,
+            msaa_color_view: (sample_count > 1)
+                .then(|| create_view("msaa color texture", color_format, sample_count, attachment)),
+            hdr_view: hdr.then(|| {
+                create_view(
+                    "hdr color texture",
+                    HDR_FORMAT,
+                    1,
+                    attachment | wgpu::TextureUsages::TEXTURE_BINDING,
+                )
+            }),
+            depth_view: create_view("depth texture", DEPTH_FORMAT, sample_count, attachment),
+        }
+    }
+}
+
+/* largest sample count not above the request that every given format supports */
+pub fn supported_sample_count(adapter: &Adapter, formats: &[TextureFormat], requested: u32) -> u32 {
+    let supported = SAMPLE_COUNTS
+        .iter()
+        .rev()
+        .copied()
+        .filter(|count| *count <= requested)
+        .find(|count| {
+            formats.iter().all(|format| {
+                adapter
+                    .get_texture_format_features(*format)
+                    .flags
+                    .sample_count_supported(*count)
+            })
+        })
+        .unwrap_or(1);
+    if supported != requested {
+        warn!(
+            "{}x MSAA is not supported for {:?}, falling back to {}x",
+            requested, formats, supported
+        );
+    }
+    supported
+}
@@ -0,0 +1,289 @@
+use std::{
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Sender},
+    },
+    thread::JoinHandle,
+};
+
+use anyhow::{Context, Result};
+use image::RgbaImage;
+use tracing::warn;
+
+use crate::graphics::{
+    ktx2::{self, Ktx2Texture},
+    mipmap,
+};
+
+#[derive(Debug, Clone)]
+pub enum TextureSource {
+    Path(PathBuf),
+    /* encoded image bytes, ktx2, png or anything else the image crate detects */
+    Memory(Vec<u8>),
+}
+
+#[derive(Debug)]
+pub enum TextureData {
+    /* full mip chain of an srgb image, level 0 first */
+    Rgba(Vec<RgbaImage>),
+    /* levels straight from a ktx2 file, still compressed where the device can sample it */
+    Ktx2(Ktx2Texture),
+}
+
+struct DecodeRequest {
+    key: String,
+    source: TextureSource,
+    generate_mipmaps: bool,
+    /* of the device the texture is uploaded to, decides whether compressed data is decoded */
+    features: wgpu::Features,
+    generation: u64,
+}
+
+/* decoded on the worker, uploaded on the main thread */
+#[derive(Debug)]
+pub struct DecodedTexture {
+    pub key: String,
+    pub generation: u64,
+    /* ready to upload, or the decode error */
+    pub data: Result<TextureData, String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoadingProgress {
+    pub loaded: usize,
+    pub total: usize,
+}
+
+impl LoadingProgress {
+    pub fn is_complete(&self) -> bool {
+        self.loaded >= self.total
+    }
+}
+
+/* single decode worker, results go through the notifier (the event loop proxy in the application) */
+pub struct TextureLoader {
+    requests: Option<Sender<DecodeRequest>>,
+    /* bumped on cancel, queued and finished work of older generations is dropped */
+    generation: Arc<AtomicU64>,
+    progress: LoadingProgress,
+    join_handle: Option<JoinHandle<()>>,
+}
+
+impl TextureLoader {
+    pub fn spawn<N>(mut notifier: N) -> Result<Self>
+    where
+        N: FnMut(DecodedTexture) + Send + 'static,
+    {
+        let (requests, receiver) = mpsc::channel::<DecodeRequest>();
+        let generation = Arc::new(AtomicU64::new(0));
+        let worker_generation = generation.clone();
+        let join_handle = std::thread::Builder::new()
+            .name("texture decoder".to_string())
+            .spawn(move || {
+                while let Ok(request) = receiver.recv() {
+                    if request.generation != worker_generation.load(Ordering::Acquire) {
+                        continue;
+                    }
+                    let data =
+                        self::decode(request.source, request.generate_mipmaps, request.features)
+                            .map_err(|error| format!("{error:#}"));
+                    notifier(DecodedTexture {
+                        key: request.key,
+                        generation: request.generation,
+                        data,
+                    });
+                }
+            })
+            .context("Failed to spawn the texture decoder thread")?;
+        Ok(Self {
+            requests: Some(requests),
+            generation,
+            progress: LoadingProgress::default(),
+            join_handle: Some(join_handle),
+        })
+    }
+
+    pub fn request(
+        &mut self,
+        key: String,
+        source: TextureSource,
+        generate_mipmaps: bool,
+        features: wgpu::Features,
+    ) {
+        let request = DecodeRequest {
+            key,
+            source,
+            generate_mipmaps,
+            features,
+            generation: self.generation.load(Ordering::Acquire),
+        };
+        match self
+            .requests
+            .as_ref()
+            .map(|requests| requests.send(request))
+        {
+            Some(Ok(())) => self.progress.total += 1,
+            _ => warn!("Texture decoder is no longer running, request dropped"),
+        }
+    }
+
+    /* counts the result towards the progress, false for results of a cancelled generation */
+    pub fn accept(&mut self, decoded: &DecodedTexture) -> bool {
+        if decoded.generation != self.generation.load(Ordering::Acquire) {
+            return false;
+        }
+        self.progress.loaded += 1;
+        true
+    }
+
+    /* drops everything in flight, used when the scenario that requested it goes away */
+    pub fn cancel(&mut self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.progress = LoadingProgress::default();
+    }
+
+    pub fn progress(&self) -> LoadingProgress {
+        self.progress
+    }
+}
+
+impl Drop for TextureLoader {
+    fn drop(&mut self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.requests.take();
+        if let Some(join_handle) = self.join_handle.take()
+            && join_handle.join().is_err()
+        {
+            warn!("Texture decoder thread panicked");
+        }
+    }
+}
+
+/* ktx2 files are picked by extension or identifier and keep the mip chain they bring along */
+pub fn decode(
+    source: TextureSource,
+    generate_mipmaps: bool,
+    features: wgpu::Features,
+) -> Result<TextureData> {
+    let image = match source {
+        TextureSource::Path(path)
+            if path
+                .extension()
+                .is_some_and(|extension| extension.eq_ignore_ascii_case("ktx2")) =>
+        {
+            let texture = std::fs::read(&path)
+                .map_err(anyhow::Error::from)
+                .and_then(|bytes| ktx2::parse(&bytes))
+                .and_then(|texture| texture.for_features(features))
+                .with_context(|| format!("Failed to decode {}", path.display()))?;
+            return Ok(TextureData::Ktx2(texture));
+        }
+        TextureSource::Memory(bytes) if ktx2::is_ktx2(&bytes) => {
+            let texture = ktx2::parse(&bytes)
+                .and_then(|texture| texture.for_features(features))
+                .context("Failed to decode embedded ktx2 texture")?;
+            return Ok(TextureData::Ktx2(texture));
+        }
+        TextureSource::Path(path) => {
+            image::open(&path).with_context(|| format!("Failed to decode {}", path.display()))?
+        }
+        TextureSource::Memory(bytes) => {
+            image::load_from_memory(&bytes).context("Failed to decode embedded image")?
+        }
+    }
+    .to_rgba8();
+    Ok(TextureData::Rgba(match generate_mipmaps {
+        true => mipmap::generate_mip_chain(image),
+        false => vec![image],
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{io::Cursor, sync::mpsc::Receiver, time::Duration};
+
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Cursor::new(Vec::new());
+        RgbaImage::from_pixel(width, height, image::Rgba([1, 2, 3, 255]))
+            .write_to(&mut bytes, image::ImageFormat::Png)
+            .unwrap();
+        bytes.into_inner()
+    }
+
+    fn loader() -> (TextureLoader, Receiver<DecodedTexture>) {
+        let (sender, receiver) = mpsc::channel();
+        let loader = TextureLoader::spawn(move |decoded| {
+            let _ = sender.send(decoded);
+        })
+        .unwrap();
+        (loader, receiver)
+    }
+
+    fn next(receiver: &Receiver<DecodedTexture>) -> DecodedTexture {
+        receiver.recv_timeout(Duration::from_secs(5)).unwrap()
+    }
+
+    #[test]
+    fn decoded_images_come_back_with_their_mip_chain() {
+        let (mut loader, receiver) = self::loader();
+        let source = TextureSource::Memory(self::png(8, 4));
+        loader.request(
+            "planet".to_string(),
+            source.clone(),
+            true,
+            wgpu::Features::empty(),
+        );
+        loader.request("icon".to_string(), source, false, wgpu::Features::empty());
+        assert_eq!(
+            loader.progress(),
+            LoadingProgress {
+                loaded: 0,
+                total: 2
+            }
+        );
+
+        let planet = self::next(&receiver);
+        assert_eq!(planet.key, "planet");
+        assert!(loader.accept(&planet));
+        let Ok(TextureData::Rgba(levels)) = planet.data else {
+            panic!("the planet was not decoded: {:?}", planet.data);
+        };
+        assert_eq!(levels.len(), 4);
+        assert_eq!(levels[0].get_pixel(0, 0).0, [1, 2, 3, 255]);
+
+        let icon = self::next(&receiver);
+        assert!(loader.accept(&icon));
+        assert!(matches!(icon.data, Ok(TextureData::Rgba(levels)) if levels.len() == 1));
+        assert!(loader.progress().is_complete());
+    }
+
+    #[test]
+    fn broken_images_report_their_error() {
+        let (mut loader, receiver) = self::loader();
+        let source = TextureSource::Memory(b"not an image".to_vec());
+        loader.request("broken".to_string(), source, true, wgpu::Features::empty());
+        let decoded = self::next(&receiver);
+        assert!(decoded.data.is_err());
+        assert!(loader.accept(&decoded));
+    }
+
+    #[test]
+    fn cancelled_results_are_not_accepted() {
+        let (mut loader, receiver) = self::loader();
+        loader.request(
+            "stale".to_string(),
+            TextureSource::Memory(self::png(2, 2)),
+            false,
+            wgpu::Features::empty(),
+        );
+        let decoded = self::next(&receiver);
+        loader.cancel();
+        assert!(!loader.accept(&decoded));
+        assert_eq!(loader.progress(), LoadingProgress::default());
+        assert!(loader.progress().is_complete());
+    }
+}
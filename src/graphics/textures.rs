@@ -0,0 +1,345 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{Context, Result};
+use imgui::TextureId;
+use imgui_wgpu::RawTextureConfig;
+use tracing::{info, warn};
+use wgpu::{
+    Device, Queue,
+    naga::{FastHashMap, FastHashSet},
+};
+
+use crate::graphics::texture_loader::TextureSource;
+
+/* alias of the menu bar icon */
+pub const ICON_TEXTURE_KEY: &str = "tex.icon";
+/* looked up through platform::asset_path */
+pub const ICON_ASSET_PATH: &str = "design/Hintergrund.png";
+/* shown when the icon asset is not installed next to the binary */
+pub const EMBEDDED_ICON: &[u8] = include_bytes!("../../design/fallback_icon.png");
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TextureHandle {
+    /* canonical path of the source image */
+    pub key: String,
+    pub texture_id: TextureId,
+    /* what the source was uploaded as, compressed ktx2 formats included */
+    pub format: wgpu::TextureFormat,
+}
+
+struct UiTexture {
+    texture_id: TextureId,
+    format: wgpu::TextureFormat,
+    /* kept to upload the texture again after a device loss */
+    source: TextureSource,
+}
+
+/* owns every ui texture through its imgui registration, entries live until unloaded */
+#[derive(Default)]
+pub struct TextureManager {
+    entries: FastHashMap<String, UiTexture>,
+    /* requested paths and names pointing at canonical keys */
+    aliases: FastHashMap<String, String>,
+}
+
+impl TextureManager {
+    /* loading the same image twice, even through a different relative path, returns the first
+    handle. ktx2 files are uploaded in their own format, decoded when the device cannot sample it */
+    pub fn load(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        renderer: &mut imgui_wgpu::Renderer,
+        path: impl AsRef<Path>,
+    ) -> Result<TextureHandle> {
+        let path = path.as_ref();
+        let key = self::canonical_key(path)?;
+        if let Some(handle) = self.get(&key) {
+            self.aliases.insert(path.display().to_string(), key);
+            return Ok(handle);
+        }
+        let (texture, view) =
+            super::write_image_from_path_msaa_off(device, queue, PathBuf::from(path))
+                .with_context(|| format!("Failed to load texture {}", path.display()))?;
+        let source = TextureSource::Path(path.to_path_buf());
+        let handle = self.register(device, renderer, key, source, texture, view);
+        self.aliases
+            .insert(path.display().to_string(), handle.key.clone());
+        Ok(handle)
+    }
+
+    /* for images compiled into the binary, the key names the image instead of a path */
+    pub fn load_from_memory(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        renderer: &mut imgui_wgpu::Renderer,
+        key: &str,
+        bytes: &[u8],
+    ) -> Result<TextureHandle> {
+        if let Some(handle) = self.get(key) {
+            return Ok(handle);
+        }
+        let (texture, view) = super::write_image_from_memory_msaa_off(device, queue, key, bytes)
+            .with_context(|| format!("Failed to load texture {}", key))?;
+        let source = TextureSource::Memory(bytes.to_vec());
+        Ok(self.register(device, renderer, key.to_string(), source, texture, view))
+    }
+
+    fn register(
+        &mut self,
+        device: &Device,
+        renderer: &mut imgui_wgpu::Renderer,
+        key: String,
+        source: TextureSource,
+        texture: wgpu::Texture,
+        view: wgpu::TextureView,
+    ) -> TextureHandle {
+        let format = texture.format();
+        let texture_id = self::insert_into_renderer(device, renderer, &key, texture, view);
+        info!("Loaded texture {} as {:?} ({:?})", key, texture_id, format);
+        self.entries.insert(
+            key.clone(),
+            UiTexture {
+                texture_id,
+                format,
+                source,
+            },
+        );
+        TextureHandle {
+            key,
+            texture_id,
+            format,
+        }
+    }
+
+    /* uploads every texture again into the renderer of a rebuilt device, keys and aliases stay
+    valid while handles taken before hold stale texture ids. textures whose source went missing
+    are dropped */
+    pub fn reupload(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        renderer: &mut imgui_wgpu::Renderer,
+    ) {
+        self.entries.retain(|key, entry| {
+            let uploaded = match &entry.source {
+                TextureSource::Path(path) => {
+                    super::write_image_from_path_msaa_off(device, queue, path.clone())
+                }
+                TextureSource::Memory(bytes) => {
+                    super::write_image_from_memory_msaa_off(device, queue, key, bytes)
+                }
+            };
+            match uploaded {
+                Ok((texture, view)) => {
+                    /* the new device may support other compressed formats */
+                    entry.format = texture.format();
+                    entry.texture_id =
+                        self::insert_into_renderer(device, renderer, key, texture, view);
+                    true
+                }
+                Err(error) => {
+                    warn!("Failed to upload texture {} again: {:#}", key, error);
+                    false
+                }
+            }
+        });
+        let entries = &self.entries;
+        self.aliases
+            .retain(|_, target| entries.contains_key(target));
+        info!("Uploaded {} ui textures again", self.entries.len());
+    }
+
+    /* gives an already loaded texture an additional lookup name */
+    pub fn alias(&mut self, name: impl Into<String>, handle: &TextureHandle) {
+        self.aliases.insert(name.into(), handle.key.clone());
+    }
+
+    /* accepts canonical keys, the path a texture was loaded with, or an alias */
+    pub fn get(&self, key: &str) -> Option<TextureHandle> {
+        let key = self.aliases.get(key).map(String::as_str).unwrap_or(key);
+        self.entries.get(key).map(|entry| TextureHandle {
+            key: key.to_string(),
+            texture_id: entry.texture_id,
+            format: entry.format,
+        })
+    }
+
+    /* drops the gpu texture, the imgui registration and every alias, returns whether it was loaded */
+    pub fn unload(&mut self, renderer: &mut imgui_wgpu::Renderer, key: &str) -> bool {
+        let key = self
+            .aliases
+            .get(key)
+            .cloned()
+            .unwrap_or_else(|| key.to_string());
+        let Some(entry) = self.entries.remove(&key) else {
+            return false;
+        };
+        renderer.textures.remove(entry.texture_id);
+        self.aliases.retain(|_, target| *target != key);
+        info!("Unloaded texture {}", key);
+        true
+    }
+
+    /* unloads every texture but the kept ones, given by key or alias. returns how many went */
+    pub fn unload_except(
+        &mut self,
+        renderer: &mut imgui_wgpu::Renderer,
+        kept: &FastHashSet<String>,
+    ) -> usize {
+        let unloaded: Vec<String> = {
+            let kept: FastHashSet<&str> = kept
+                .iter()
+                .map(|key| self.aliases.get(key).unwrap_or(key).as_str())
+                .collect();
+            self.entries
+                .keys()
+                .filter(|key| !kept.contains(key.as_str()))
+                .cloned()
+                .collect()
+        };
+        for key in &unloaded {
+            self.unload(renderer, key);
+        }
+        unloaded.len()
+    }
+}
+
+/* the sampler is linear, ui textures are never mipmapped */
+fn insert_into_renderer(
+    device: &Device,
+    renderer: &mut imgui_wgpu::Renderer,
+    key: &str,
+    texture: wgpu::Texture,
+    view: wgpu::TextureView,
+) -> TextureId {
+    let size = texture.size();
+    let texture = imgui_wgpu::Texture::from_raw_parts(
+        device,
+        renderer,
+        Arc::new(texture),
+        Arc::new(view),
+        None,
+        Some(&RawTextureConfig {
+            label: Some(key),
+            sampler_desc: wgpu::SamplerDescriptor {
+                label: Some("ui texture sampler"),
+                mag_filter: wgpu::FilterMode::Linear,
+                min_filter: wgpu::FilterMode::Linear,
+                ..Default::default()
+            },
+        }),
+        size,
+    );
+    renderer.textures.insert(texture)
+}
+
+fn canonical_key(path: &Path) -> Result<String> {
+    let canonical = std::fs::canonicalize(path)
+        .with_context(|| format!("Texture {} does not exist", path.display()))?;
+    Ok(canonical.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* a scratch directory of the test holding a small png */
+    fn write_image(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("textures-{}", std::process::id()));
+        std::fs::create_dir_all(directory.join("nested")).unwrap();
+        let path = directory.join(name);
+        image::RgbaImage::from_pixel(4, 2, image::Rgba([10, 20, 30, 255]))
+            .save(&path)
+            .unwrap();
+        path
+    }
+
+    /* one test for all of it, imgui allows a single context per process at a time */
+    #[test]
+    fn loads_are_deduplicated_and_unloads_bookkept() {
+        let Some((device, queue)) = crate::graphics::headless_device() else {
+            eprintln!("No adapter, skipping the texture manager test");
+            return;
+        };
+        let mut imgui = imgui::Context::create();
+        let mut renderer = imgui_wgpu::Renderer::new(
+            &mut imgui,
+            &device,
+            &queue,
+            imgui_wgpu::RendererConfig::default(),
+        );
+        let mut manager = TextureManager::default();
+        let path = self::write_image("planet.png");
+        let detour = path
+            .parent()
+            .unwrap()
+            .join("nested")
+            .join("..")
+            .join("planet.png");
+
+        let first = manager.load(&device, &queue, &mut renderer, &path).unwrap();
+        let second = manager
+            .load(&device, &queue, &mut renderer, &detour)
+            .unwrap();
+        assert_eq!(first, second);
+        assert_eq!(first.format, crate::graphics::IMAGE_TEXTURE_FORMAT);
+        assert_eq!(manager.entries.len(), 1);
+        assert_eq!(
+            manager.get(&detour.display().to_string()),
+            Some(first.clone())
+        );
+
+        manager.alias("tex.planet", &first);
+        assert_eq!(manager.get("tex.planet"), Some(first.clone()));
+        let remembered = renderer.textures.get(first.texture_id).is_some();
+        assert!(remembered);
+
+        let embedded = manager
+            .load_from_memory(
+                &device,
+                &queue,
+                &mut renderer,
+                ICON_TEXTURE_KEY,
+                EMBEDDED_ICON,
+            )
+            .unwrap();
+        let again = manager
+            .load_from_memory(&device, &queue, &mut renderer, ICON_TEXTURE_KEY, &[])
+            .unwrap();
+        assert_eq!(embedded, again);
+        assert_ne!(embedded.texture_id, first.texture_id);
+
+        assert!(manager.unload(&mut renderer, "tex.planet"));
+        assert_eq!(manager.get(&first.key), None);
+        assert_eq!(manager.get(&detour.display().to_string()), None);
+        assert!(renderer.textures.get(first.texture_id).is_none());
+        assert!(!manager.unload(&mut renderer, &first.key));
+        assert_eq!(manager.get(ICON_TEXTURE_KEY), Some(embedded.clone()));
+
+        /* what a new scenario does not reference goes, the icon is kept through its alias */
+        let planet = manager.load(&device, &queue, &mut renderer, &path).unwrap();
+        manager.alias("tex.icon.alias", &embedded);
+        let kept = FastHashSet::from_iter(["tex.icon.alias".to_string()]);
+        assert_eq!(manager.unload_except(&mut renderer, &kept), 1);
+        assert!(renderer.textures.get(planet.texture_id).is_none());
+        assert_eq!(manager.get(ICON_TEXTURE_KEY), Some(embedded));
+        assert_eq!(manager.unload_except(&mut renderer, &kept), 0);
+
+        assert!(
+            manager
+                .load(
+                    &device,
+                    &queue,
+                    &mut renderer,
+                    path.with_file_name("missing.png")
+                )
+                .is_err()
+        );
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,47 @@
+/* what the title bar asks of the undecorated winit window */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WindowAction {
+    /* hands the pressed button to the window manager, which moves the window until released */
+    Drag,
+    Minimize,
+    ToggleMaximize,
+    Close,
+}
+
+/* the window buttons at the right end of the main menu bar, drawn last so every widget of the
+bar is known when deciding whether a press lands on the empty part of it */
+pub fn draw(ui: &imgui::Ui, maximized: bool) -> Option<WindowAction> {
+    let mut action = None;
+    let size = ui.frame_height();
+    let spacing = ui.clone_style().item_spacing[0];
+    let buttons_width = 3. * size + 2. * spacing;
+    ui.same_line_with_pos((ui.window_size()[0] - buttons_width).max(ui.cursor_pos()[0]));
+    let buttons = [
+        ("_##minimize", "Minimize", WindowAction::Minimize),
+        match maximized {
+            true => ("][##maximize", "Restore", WindowAction::ToggleMaximize),
+            false => ("[]##maximize", "Maximize", WindowAction::ToggleMaximize),
+        },
+        ("X##close", "Close", WindowAction::Close),
+    ];
+    for (index, (label, tooltip, button_action)) in buttons.into_iter().enumerate() {
+        if index > 0 {
+            ui.same_line();
+        }
+        if ui.button_with_size(label, [size, size]) {
+            action = Some(button_action);
+        }
+        if ui.is_item_hovered() {
+            ui.tooltip_text(tooltip);
+        }
+    }
+    /* text is not an item imgui can hover, so the clock and the stats drag as well */
+    if action.is_none() && ui.is_window_hovered() && !ui.is_any_item_hovered() {
+        if ui.is_mouse_double_clicked(imgui::MouseButton::Left) {
+            action = Some(WindowAction::ToggleMaximize);
+        } else if ui.is_mouse_clicked(imgui::MouseButton::Left) {
+            action = Some(WindowAction::Drag);
+        }
+    }
+    action
+}
@@ -0,0 +1,142 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use imgui::{Condition, MouseButton, StyleVar, WindowFlags};
+
+use crate::simulation::BodyId;
+
+/* how long a message stays unless clicked away, the last second fades it out */
+pub const TOAST_DURATION: Duration = Duration::from_secs(6);
+pub const TOAST_FADE: Duration = Duration::from_secs(1);
+/* how long the status bar shows a message */
+pub const STATUS_MESSAGE_DURATION: Duration = Duration::from_secs(5);
+/* older messages are dropped when more arrive at once */
+pub const MAXIMUM_TOASTS: usize = 4;
+const TOAST_MARGIN: f32 = 12.;
+const TOAST_WIDTH: f32 = 420.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastSeverity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl ToastSeverity {
+    fn color(self) -> [f32; 4] {
+        match self {
+            ToastSeverity::Info => [1., 1., 1., 1.],
+            ToastSeverity::Warning => [1., 0.8, 0.35, 1.],
+            ToastSeverity::Error => [1., 0.45, 0.4, 1.],
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Toast {
+    severity: ToastSeverity,
+    text: String,
+    /* framed when the toast is clicked, like the two bodies of a collision */
+    bodies: Vec<BodyId>,
+    /* the same message again while it shows only counts up */
+    count: u32,
+    since: Instant,
+}
+
+/* short messages stacked in the bottom right corner, for feedback that must not block. the
+quieter ones go to the status bar instead, one at a time */
+#[derive(Debug, Default)]
+pub struct Toasts {
+    toasts: VecDeque<Toast>,
+    /* replaced by the next one */
+    status: Option<(String, Instant)>,
+}
+
+impl Toasts {
+    pub fn status(&mut self, text: impl Into<String>) {
+        self.status = Some((text.into(), Instant::now()));
+    }
+
+    pub fn status_message(&self, now: Instant) -> Option<&str> {
+        let (text, since) = self.status.as_ref()?;
+        (now.duration_since(*since) < STATUS_MESSAGE_DURATION).then_some(text.as_str())
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        self.push(ToastSeverity::Error, text.into(), Vec::new());
+    }
+
+    /* a duplicate of a shown message restarts its lifetime instead of stacking */
+    pub fn push(&mut self, severity: ToastSeverity, text: String, bodies: Vec<BodyId>) {
+        let now = Instant::now();
+        if let Some(toast) = self
+            .toasts
+            .iter_mut()
+            .find(|toast| toast.severity == severity && toast.text == text)
+        {
+            toast.count += 1;
+            toast.since = now;
+            toast.bodies = bodies;
+            return;
+        }
+        if self.toasts.len() == MAXIMUM_TOASTS {
+            self.toasts.pop_front();
+        }
+        self.toasts.push_back(Toast {
+            severity,
+            text,
+            bodies,
+            count: 1,
+            since: now,
+        });
+    }
+
+    /* the newest at the bottom, above the status bar. a click dismisses the toast and returns
+    its bodies, if it has any, so the camera can frame them */
+    pub fn draw(&mut self, ui: &imgui::Ui, now: Instant, bottom: f32) -> Option<Vec<BodyId>> {
+        self.toasts
+            .retain(|toast| now.duration_since(toast.since) < TOAST_DURATION);
+        let display_size = ui.io().display_size;
+        let mut bottom = bottom - TOAST_MARGIN;
+        let mut clicked = None;
+        for (index, toast) in self.toasts.iter().enumerate().rev() {
+            let remaining = TOAST_DURATION.saturating_sub(now.duration_since(toast.since));
+            let alpha = (remaining.as_secs_f32() / TOAST_FADE.as_secs_f32()).min(1.);
+            let _alpha = ui.push_style_var(StyleVar::Alpha(alpha));
+            let (height, hovered) = ui
+                .window(format!("##toast {}", index))
+                .position([display_size[0] - TOAST_MARGIN, bottom], Condition::Always)
+                .position_pivot([1., 1.])
+                .size_constraints([TOAST_WIDTH, 0.], [TOAST_WIDTH, f32::MAX])
+                .bg_alpha(0.85)
+                .flags(
+                    WindowFlags::NO_DECORATION
+                        | WindowFlags::NO_NAV
+                        | WindowFlags::NO_FOCUS_ON_APPEARING
+                        | WindowFlags::NO_SAVED_SETTINGS
+                        | WindowFlags::ALWAYS_AUTO_RESIZE,
+                )
+                .build(|| {
+                    let _wrap = ui.push_text_wrap_pos_with_pos(ui.cursor_pos()[0] + TOAST_WIDTH);
+                    let text = match toast.count {
+                        1 => toast.text.clone(),
+                        count => format!("{}x {}", count, toast.text),
+                    };
+                    ui.text_colored(toast.severity.color(), text);
+                    if !toast.bodies.is_empty() {
+                        ui.text_disabled("Click to look at it");
+                    }
+                    (ui.window_size()[1], ui.is_window_hovered())
+                })
+                .unwrap_or_default();
+            if hovered && ui.is_mouse_clicked(MouseButton::Left) {
+                clicked = Some(index);
+            }
+            bottom -= height + TOAST_MARGIN * 0.5;
+        }
+        let toast = self.toasts.remove(clicked?)?;
+        (!toast.bodies.is_empty()).then_some(toast.bodies)
+    }
+}
@@ -0,0 +1,387 @@
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use wgpu::{Adapter, Device, Queue, TextureFormat, util::DeviceExt};
+
+use crate::graphics::{camera::MANUAL_OUTPUT_GAMMA, shaders};
+
+use crate::localization;
+
+/* the scene is rendered into this when tonemapping is available */
+pub const HDR_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+pub const DEFAULT_EXPOSURE: f32 = 1.;
+/* a quarter stop per key press */
+pub const EXPOSURE_STEP: f32 = 1.189_207_1;
+pub const MINIMUM_EXPOSURE: f32 = 1. / 64.;
+pub const MAXIMUM_EXPOSURE: f32 = 64.;
+/* on top of the output encoding, above 1 lifts the midtones */
+pub const DEFAULT_GAMMA: f32 = 1.;
+pub const MINIMUM_GAMMA: f32 = 0.5;
+pub const MAXIMUM_GAMMA: f32 = 3.;
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    strum_macros::EnumIter,
+    strum_macros::Display,
+)]
+pub enum TonemapCurve {
+    #[default]
+    #[serde(rename = "aces")]
+    #[strum(to_string = "ACES")]
+    Aces,
+    #[serde(rename = "reinhard")]
+    #[strum(to_string = "Reinhard")]
+    Reinhard,
+}
+
+impl TonemapCurve {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "aces" => Some(TonemapCurve::Aces),
+            "reinhard" => Some(TonemapCurve::Reinhard),
+            _ => None,
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        TonemapCurve::iter()
+            .cycle()
+            .skip_while(|curve| curve != self)
+            .nth(1)
+            .unwrap_or(*self)
+    }
+
+    /* the scene value the curve maps to a display value in [0, 1), for colors that have to
+    come out exactly */
+    pub fn invert(&self, mapped: f64) -> f64 {
+        let mapped = mapped.clamp(0., 0.999);
+        match self {
+            TonemapCurve::Reinhard => mapped / (1. - mapped),
+            /* the positive root of the rational fit in tonemap.wgsl */
+            TonemapCurve::Aces => {
+                let (a, b, c, d, e) = (2.51, 0.03, 2.43, 0.59, 0.14);
+                let quadratic = a - c * mapped;
+                let linear = b - d * mapped;
+                let constant = -e * mapped;
+                (-linear + (linear * linear - 4. * quadratic * constant).sqrt()) / (2. * quadratic)
+            }
+        }
+    }
+
+    /* matches the branch in tonemap.wgsl */
+    fn shader_index(&self) -> u32 {
+        match self {
+            TonemapCurve::Aces => 0,
+            TonemapCurve::Reinhard => 1,
+        }
+    }
+}
+
+/* kept on the interface so it survives the pipeline being rebuilt, the settings file starts from
+it */
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct TonemapSettings {
+    #[serde(rename = "curve")]
+    pub curve: TonemapCurve,
+    #[serde(rename = "exposure")]
+    pub exposure: f32,
+    #[serde(rename = "gamma")]
+    pub gamma: f32,
+}
+
+impl Default for TonemapSettings {
+    fn default() -> Self {
+        Self {
+            curve: TonemapCurve::default(),
+            exposure: DEFAULT_EXPOSURE,
+            gamma: DEFAULT_GAMMA,
+        }
+    }
+}
+
+impl TonemapSettings {
+    /* within the slider bounds, a hand edited settings file may hold a zero exposure */
+    pub fn clamped(self) -> Self {
+        Self {
+            exposure: self.exposure.clamp(MINIMUM_EXPOSURE, MAXIMUM_EXPOSURE),
+            gamma: self.gamma.clamp(MINIMUM_GAMMA, MAXIMUM_GAMMA),
+            ..self
+        }
+    }
+
+    /* positive steps brighten, exposure is clamped to [MINIMUM_EXPOSURE, MAXIMUM_EXPOSURE] */
+    pub fn adjust_exposure(&mut self, steps: i32) {
+        self.exposure =
+            (self.exposure * EXPOSURE_STEP.powi(steps)).clamp(MINIMUM_EXPOSURE, MAXIMUM_EXPOSURE);
+    }
+
+    /* the hdr value that leaves the pass as the given linear color, so the background keeps
+    its color whatever the exposure, gamma and curve */
+    pub fn scene_value(&self, linear: f64) -> f64 {
+        self.curve.invert(linear.powf(self.gamma as f64)) / self.exposure as f64
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct TonemapUniform {
+    pub exposure: f32,
+    pub curve: u32,
+    /* the scene stays linear in the hdr target, gamma is only applied here */
+    pub output_gamma: f32,
+    pub gamma: f32,
+}
+
+impl TonemapUniform {
+    pub fn new(settings: &TonemapSettings, manual_gamma_correction: bool) -> Self {
+        Self {
+            exposure: settings.exposure,
+            curve: settings.curve.shader_index(),
+            output_gamma: if manual_gamma_correction {
+                MANUAL_OUTPUT_GAMMA
+            } else {
+                1.
+            },
+            gamma: settings.gamma,
+        }
+    }
+}
+
+/* sliders for the menu bar, they only touch the scene, the ui is drawn after the pass. true once
+a slider is let go or the values are reset, for the caller to remember them */
+pub fn draw_menu(ui: &imgui::Ui, settings: Option<&mut TonemapSettings>) -> bool {
+    let mut adjusted = false;
+    ui.menu(localization::tr("menu-display"), || {
+        let Some(settings) = settings else {
+            ui.text_disabled("Exposure and gamma need the hdr path");
+            return;
+        };
+        ui.slider_config("Exposure", MINIMUM_EXPOSURE, MAXIMUM_EXPOSURE)
+            .flags(imgui::SliderFlags::LOGARITHMIC)
+            .display_format("%.2f")
+            .build(&mut settings.exposure);
+        adjusted |= ui.is_item_deactivated_after_edit();
+        ui.slider_config("Gamma", MINIMUM_GAMMA, MAXIMUM_GAMMA)
+            .display_format("%.2f")
+            .build(&mut settings.gamma);
+        adjusted |= ui.is_item_deactivated_after_edit();
+        if ui.button("Reset") {
+            settings.exposure = DEFAULT_EXPOSURE;
+            settings.gamma = DEFAULT_GAMMA;
+            adjusted = true;
+        }
+    });
+    adjusted
+}
+
+/* the fullscreen pass samples the hdr target, so it has to be filterable and renderable */
+pub fn supports_hdr(adapter: &Adapter) -> bool {
+    let features = adapter.get_texture_format_features(HDR_FORMAT);
+    features
+        .allowed_usages
+        .contains(wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING)
+        && features
+            .flags
+            .contains(wgpu::TextureFormatFeatureFlags::FILTERABLE)
+}
+
+/* maps the hdr target into the viewport texture, which imgui then samples */
+pub struct TonemapPipeline {
+    pub render_pipeline: wgpu::RenderPipeline,
+    pub bind_group_layout: wgpu::BindGroupLayout,
+    pub sampler: wgpu::Sampler,
+    pub uniform_buffer: wgpu::Buffer,
+    /* references the hdr view, recreated with the render targets */
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl TonemapPipeline {
+    pub fn new(
+        device: &Device,
+        surface_format: TextureFormat,
+        hdr_view: &wgpu::TextureView,
+        settings: &TonemapSettings,
+        manual_gamma_correction: bool,
+    ) -> Self {
+        let shader = shaders::TONEMAP_SHADER.module(device);
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("tonemap bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("tonemap sampler"),
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+        let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("tonemap uniform buffer"),
+            contents: bytemuck::bytes_of(&TonemapUniform::new(settings, manual_gamma_correction)),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("tonemap pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("tonemap pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: surface_format,
+                    blend: None,
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+            cache: None,
+        });
+        let bind_group = self::create_bind_group(
+            device,
+            &bind_group_layout,
+            &sampler,
+            &uniform_buffer,
+            hdr_view,
+        );
+
+        Self {
+            render_pipeline,
+            bind_group_layout,
+            sampler,
+            uniform_buffer,
+            bind_group,
+        }
+    }
+
+    pub fn rebind(&mut self, device: &Device, hdr_view: &wgpu::TextureView) {
+        self.bind_group = self::create_bind_group(
+            device,
+            &self.bind_group_layout,
+            &self.sampler,
+            &self.uniform_buffer,
+            hdr_view,
+        );
+    }
+
+    pub fn write_uniform(
+        &self,
+        queue: &Queue,
+        settings: &TonemapSettings,
+        manual_gamma_correction: bool,
+    ) {
+        queue.write_buffer(
+            &self.uniform_buffer,
+            0,
+            bytemuck::bytes_of(&TonemapUniform::new(settings, manual_gamma_correction)),
+        );
+    }
+
+    pub fn draw(&self, pass: &mut wgpu::RenderPass) {
+        pass.set_pipeline(&self.render_pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
+
+fn create_bind_group(
+    device: &Device,
+    layout: &wgpu::BindGroupLayout,
+    sampler: &wgpu::Sampler,
+    uniform_buffer: &wgpu::Buffer,
+    hdr_view: &wgpu::TextureView,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("tonemap bind group"),
+        layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(hdr_view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: wgpu::BindingResource::Sampler(sampler),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: uniform_buffer.as_entire_binding(),
+            },
+        ],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_from_a_file_are_clamped() {
+        let settings = TonemapSettings {
+            curve: TonemapCurve::Reinhard,
+            exposure: 0.,
+            gamma: 10.,
+        }
+        .clamped();
+        assert_eq!(settings.exposure, MINIMUM_EXPOSURE);
+        assert_eq!(settings.gamma, MAXIMUM_GAMMA);
+        assert_eq!(settings.curve, TonemapCurve::Reinhard);
+        let settings = TonemapSettings::default();
+        assert_eq!(settings.clamped(), settings);
+    }
+
+    #[test]
+    fn exposure_steps_stay_within_bounds() {
+        let mut settings = TonemapSettings::default();
+        settings.adjust_exposure(4);
+        assert!((settings.exposure - 2.).abs() < 1e-5);
+        settings.adjust_exposure(-1000);
+        assert_eq!(settings.exposure, MINIMUM_EXPOSURE);
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{
+    input::{InputAction, Keymap},
+    localization,
+    simulation::thread::{SimulationCommand, SimulationSnapshot},
+};
+
+/* choices of the warp dropdown, the bracket keys reach the values in between and beyond */
+pub const WARP_PRESETS: [f64; 6] = [1., 10., 100., 1000., 3600., 10000.];
+/* calendar date at simulation time zero of a scenario without an epoch, 2000-01-01 12:00 utc
+(j2000) */
+pub const SIMULATION_EPOCH_UNIX_SECONDS: i64 = 946_728_000;
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/* the simulation controls in the main menu bar. they only issue commands, the state shown is
+the one of the latest snapshot, so they always agree with the keyboard shortcuts. the epoch is
+the unix time of simulation time zero */
+pub fn draw(
+    ui: &imgui::Ui,
+    snapshot: &SimulationSnapshot,
+    epoch: i64,
+) -> Option<SimulationCommand> {
+    let mut command = None;
+    if ui.button(if snapshot.paused { "Play" } else { "Pause" }) {
+        command = Some(SimulationCommand::SetPaused(!snapshot.paused));
+    }
+    {
+        let _disabled = ui.begin_disabled(!snapshot.paused);
+        if ui.button("Step") {
+            command = Some(SimulationCommand::Step);
+        }
+    }
+    ui.set_next_item_width(90.);
+    if let Some(_combo) = ui.begin_combo("##warp", format!("{}x", snapshot.warp)) {
+        WARP_PRESETS.into_iter().for_each(|warp| {
+            if ui
+                .selectable_config(format!("{}x", warp))
+                .selected(warp == snapshot.warp)
+                .build()
+            {
+                command = Some(SimulationCommand::SetWarp(warp));
+            }
+        });
+    }
+    ui.text(format!(
+        "{} | T+{} | {:.0} steps/s",
+        self::format_date(snapshot.simulation_time, epoch),
+        self::format_elapsed(snapshot.simulation_time),
+        snapshot.steps_per_second,
+    ));
+    command
+}
+
+/* undo and redo of the scenario edits, named after what they would revert */
+pub fn draw_edit_menu(
+    ui: &imgui::Ui,
+    snapshot: &SimulationSnapshot,
+    keymap: &Keymap,
+) -> Option<SimulationCommand> {
+    let mut command = None;
+    ui.menu(localization::tr("menu-edit"), || {
+        let items = [
+            (
+                "undo",
+                &snapshot.undo_description,
+                InputAction::Undo,
+                SimulationCommand::Undo,
+            ),
+            (
+                "redo",
+                &snapshot.redo_description,
+                InputAction::Redo,
+                SimulationCommand::Redo,
+            ),
+        ];
+        for (name, description, action, item_command) in items {
+            let label = match description {
+                Some(description) => localization::tr_args(
+                    &format!("menu-{}-named", name),
+                    &[("action", description.as_str().into())],
+                ),
+                None => localization::tr(&format!("menu-{}", name)),
+            };
+            let shortcut = keymap
+                .bindings(action)
+                .first()
+                .map(ToString::to_string)
+                .unwrap_or_default();
+            if ui
+                .menu_item_config(label)
+                .shortcut(shortcut)
+                .enabled(description.is_some())
+                .build()
+            {
+                command = Some(item_command);
+            }
+        }
+    });
+    command
+}
+
+/* days and a clock, like 12d 03:04:05 */
+pub fn format_elapsed(seconds: f64) -> String {
+    let seconds = seconds.max(0.) as i64;
+    format!(
+        "{}d {:02}:{:02}:{:02}",
+        seconds / SECONDS_PER_DAY,
+        seconds % SECONDS_PER_DAY / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+/* utc calendar date and time of a simulation time, like 2000-01-13 15:04 */
+pub fn format_date(seconds: f64, epoch: i64) -> String {
+    let unix_seconds = epoch + seconds.floor() as i64;
+    let (year, month, day) = self::civil_from_days(unix_seconds.div_euclid(SECONDS_PER_DAY));
+    let time_of_day = unix_seconds.rem_euclid(SECONDS_PER_DAY);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        time_of_day / 3600,
+        time_of_day % 3600 / 60
+    )
+}
+
+/* the unix time of a date written like format_date does, the time of day may be left out */
+pub fn parse_date(date: &str) -> Option<i64> {
+    let (date, time) = date
+        .trim()
+        .split_once(' ')
+        .unwrap_or((date.trim(), "00:00"));
+    let mut fields = date.splitn(3, '-');
+    let year = fields.next()?.parse::<i64>().ok()?;
+    let month = fields.next()?.parse::<i64>().ok()?;
+    let day = fields.next()?.parse::<i64>().ok()?;
+    let (hour, minute) = time.trim().split_once(':')?;
+    let (hour, minute) = (hour.parse::<i64>().ok()?, minute.parse::<i64>().ok()?);
+    if !(1..=12).contains(&month) || !(0..24).contains(&hour) || !(0..60).contains(&minute) {
+        return None;
+    }
+    let days = self::days_from_civil(year, month, day);
+    /* the 31st of april comes back as the 1st of may */
+    if self::civil_from_days(days) != (year, month, day) {
+        return None;
+    }
+    Some(days * SECONDS_PER_DAY + hour * 3600 + minute * 60)
+}
+
+/* day count since 1970-01-01 of a proleptic gregorian date, the inverse of civil_from_days */
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = year - (month <= 2) as i64;
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month_index = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/* proleptic gregorian date of a day count since 1970-01-01 */
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    /* months counted from march, so the leap day is the last of the year */
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
@@ -0,0 +1,239 @@
+use std::ops::Range;
+
+use bytemuck::{Pod, Zeroable};
+use glam::DVec3;
+use wgpu::{
+    Device, Queue, TextureFormat,
+    naga::{FastHashMap, FastHashSet},
+};
+
+use crate::{
+    graphics::{
+        pipeline, shaders,
+        stats::DrawCounts,
+        targets::{DEPTH_COMPARE, DEPTH_FORMAT},
+    },
+    simulation::{BodyId, SimulationBody, trail::Trail},
+};
+
+pub const INITIAL_TRAIL_VERTEX_CAPACITY: usize = 4096;
+/* alpha of the newest segment, older segments fade linearly to zero */
+pub const TRAIL_MAXIMUM_ALPHA: f32 = 0.85;
+
+#[derive(Debug, Clone)]
+pub struct TrailSettings {
+    pub enabled: bool,
+    pub hidden_bodies: FastHashSet<BodyId>,
+}
+
+impl Default for TrailSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            hidden_bodies: FastHashSet::default(),
+        }
+    }
+}
+
+impl TrailSettings {
+    pub fn toggle_body(&mut self, id: BodyId) {
+        if !self.hidden_bodies.remove(&id) {
+            self.hidden_bodies.insert(id);
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct TrailVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 4],
+}
+
+impl TrailVertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x4];
+}
+
+/* one line strip per body, ending at its current position.
+with a reference body every sample is expressed relative to where that body was at the same
+sample, trails are recorded in lockstep so the newest samples of all bodies line up.
+vertices are relative to the eye like the body instances */
+pub fn build_trail_vertices(
+    bodies: &[SimulationBody],
+    trails: &FastHashMap<BodyId, Trail>,
+    reference: Option<BodyId>,
+    eye: DVec3,
+    settings: &TrailSettings,
+) -> (Vec<TrailVertex>, Vec<Range<u32>>) {
+    let mut vertices = Vec::new();
+    let mut strips = Vec::new();
+    if !settings.enabled {
+        return (vertices, strips);
+    }
+    let reference = reference.and_then(|id| {
+        let body = bodies.iter().find(|body| body.id == id)?;
+        let trail = trails.get(&id)?;
+        Some((body, trail.points().copied().collect::<Vec<DVec3>>()))
+    });
+
+    for (index, body) in bodies.iter().enumerate() {
+        if settings.hidden_bodies.contains(&body.id)
+            || reference
+                .as_ref()
+                .is_some_and(|(origin, _)| origin.id == body.id)
+        {
+            continue;
+        }
+        let Some(trail) = trails.get(&body.id) else {
+            continue;
+        };
+        let color = pipeline::body_color(body, index);
+        let first = vertices.len() as u32;
+        let length = trail.len();
+        vertices.extend(trail.points().enumerate().filter_map(|(sample, point)| {
+            let position = match reference.as_ref() {
+                None => *point,
+                Some((origin, origin_trail)) => {
+                    let aligned = (sample + origin_trail.len()).checked_sub(length)?;
+                    *point - origin_trail[aligned] + origin.position
+                }
+            };
+            let alpha = TRAIL_MAXIMUM_ALPHA * (sample + 1) as f32 / (length + 1) as f32;
+            Some(TrailVertex {
+                position: (position - eye).as_vec3().to_array(),
+                color: [color[0], color[1], color[2], alpha],
+            })
+        }));
+        vertices.push(TrailVertex {
+            position: (body.position - eye).as_vec3().to_array(),
+            color: [color[0], color[1], color[2], TRAIL_MAXIMUM_ALPHA],
+        });
+        let last = vertices.len() as u32;
+        if last - first >= 2 {
+            strips.push(first..last);
+        } else {
+            vertices.truncate(first as usize);
+        }
+    }
+    (vertices, strips)
+}
+
+pub struct TrailPipeline {
+    pub render_pipeline: wgpu::RenderPipeline,
+    pub vertex_buffer: wgpu::Buffer,
+    pub vertex_capacity: usize,
+    pub strips: Vec<Range<u32>>,
+}
+
+impl TrailPipeline {
+    pub fn new(
+        device: &Device,
+        color_format: TextureFormat,
+        sample_count: u32,
+        camera_bind_group_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let shader = shaders::TRAIL_SHADER.module(device);
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("trail pipeline layout"),
+            bind_group_layouts: &[camera_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("trail pipeline"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: &[wgpu::VertexBufferLayout {
+                    array_stride: std::mem::size_of::<TrailVertex>() as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Vertex,
+                    attributes: &TrailVertex::ATTRIBUTES,
+                }],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: color_format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::LineStrip,
+                ..Default::default()
+            },
+            /* tested against the bodies but translucent, so it never writes depth */
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: false,
+                depth_compare: DEPTH_COMPARE,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+            multiview: None,
+            cache: None,
+        });
+
+        Self {
+            render_pipeline,
+            vertex_buffer: self::create_vertex_buffer(device, INITIAL_TRAIL_VERTEX_CAPACITY),
+            vertex_capacity: INITIAL_TRAIL_VERTEX_CAPACITY,
+            strips: Vec::new(),
+        }
+    }
+
+    /* same growth policy as the body instances, the buffer is reused between frames */
+    pub fn write_vertices(
+        &mut self,
+        device: &Device,
+        queue: &Queue,
+        vertices: &[TrailVertex],
+        strips: Vec<Range<u32>>,
+    ) {
+        if vertices.len() > self.vertex_capacity {
+            self.vertex_capacity = vertices.len().max(self.vertex_capacity * 2);
+            self.vertex_buffer = self::create_vertex_buffer(device, self.vertex_capacity);
+        }
+        queue.write_buffer(&self.vertex_buffer, 0, bytemuck::cast_slice(vertices));
+        self.strips = strips;
+    }
+
+    pub fn draw(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        camera_bind_group: &wgpu::BindGroup,
+    ) {
+        if self.strips.is_empty() {
+            return;
+        }
+        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_bind_group(0, camera_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        self.strips
+            .iter()
+            .for_each(|strip| render_pass.draw(strip.clone(), 0..1));
+    }
+
+    /* one draw per strip */
+    pub fn draw_counts(&self) -> DrawCounts {
+        let strips = self.strips.len() as u32;
+        DrawCounts::new(strips, strips)
+    }
+}
+
+fn create_vertex_buffer(device: &Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("trail vertex buffer"),
+        size: (capacity * std::mem::size_of::<TrailVertex>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
@@ -0,0 +1,131 @@
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use imgui::Condition;
+
+use crate::{
+    localization,
+    simulation::recording::{DEFAULT_RECORDING_INTERVAL, TrajectoryRecording},
+    storage,
+};
+
+pub const TRAJECTORY_WINDOW_TITLE: &str = "Trajectories";
+pub const TRAJECTORY_WINDOW_SIZE: [f32; 2] = [380., 200.];
+pub const TRAJECTORY_FILE_NAME: &str = "trajectories.csv";
+
+pub enum TrajectoryAction {
+    /* simulation seconds between two samples */
+    Start(f64),
+    Stop,
+    Export {
+        recording: Arc<Mutex<TrajectoryRecording>>,
+        path: PathBuf,
+    },
+}
+
+/* records the body states while the simulation runs and writes them to csv */
+pub struct TrajectoryWindow {
+    pub open: bool,
+    pub interval: f64,
+    pub path: String,
+    /* the running one, or the one stopped last, still exported from here */
+    recording: Option<Arc<Mutex<TrajectoryRecording>>>,
+}
+
+impl Default for TrajectoryWindow {
+    fn default() -> Self {
+        Self {
+            open: false,
+            interval: DEFAULT_RECORDING_INTERVAL,
+            path: storage::scenarios_directory()
+                .join(TRAJECTORY_FILE_NAME)
+                .display()
+                .to_string(),
+            recording: None,
+        }
+    }
+}
+
+impl TrajectoryWindow {
+    pub fn draw_menu_item(&mut self, ui: &imgui::Ui) {
+        if ui.menu_item(localization::tr("menu-export-trajectories")) {
+            self.open = true;
+        }
+    }
+
+    /* the running recording comes with the snapshot, none once it stopped */
+    pub fn draw(
+        &mut self,
+        ui: &imgui::Ui,
+        running: Option<&Arc<Mutex<TrajectoryRecording>>>,
+    ) -> Option<TrajectoryAction> {
+        if let Some(running) = running
+            && !self
+                .recording
+                .as_ref()
+                .is_some_and(|recording| Arc::ptr_eq(recording, running))
+        {
+            self.recording = Some(running.clone());
+        }
+        if !self.open {
+            return None;
+        }
+        let mut open = true;
+        let action = ui
+            .window(localization::title(
+                "window-trajectories",
+                TRAJECTORY_WINDOW_TITLE,
+            ))
+            .opened(&mut open)
+            .size(TRAJECTORY_WINDOW_SIZE, Condition::FirstUseEver)
+            .build(|| self.draw_fields(ui, running.is_some()))
+            .flatten();
+        self.open &= open;
+        action
+    }
+
+    fn draw_fields(&mut self, ui: &imgui::Ui, running: bool) -> Option<TrajectoryAction> {
+        let mut action = None;
+        {
+            let _disabled = ui.begin_disabled(running);
+            ui.input_scalar("Interval (s)", &mut self.interval)
+                .display_format("%.1f")
+                .build();
+            self.interval = self.interval.max(1.);
+        }
+        match running {
+            true if ui.button("Stop") => action = Some(TrajectoryAction::Stop),
+            false if ui.button("Record") => action = Some(TrajectoryAction::Start(self.interval)),
+            _ => {}
+        }
+        if let Some(recording) = self.recording.as_ref() {
+            let recording = recording.lock().unwrap();
+            ui.same_line();
+            ui.text(format!(
+                "{} samples, {} rows every {} s{}",
+                recording.sample_count(),
+                recording.row_count(),
+                recording.interval(),
+                match recording.spilled() {
+                    true => ", partly on disk",
+                    false => "",
+                }
+            ));
+        }
+        ui.separator();
+
+        ui.input_text("File", &mut self.path).build();
+        let _disabled = ui.begin_disabled(self.recording.is_none() || self.path.trim().is_empty());
+        if ui.button("Export")
+            && let Some(recording) = self.recording.clone()
+        {
+            action = Some(TrajectoryAction::Export {
+                recording,
+                path: PathBuf::from(self.path.trim()),
+            });
+        }
+        action
+    }
+}
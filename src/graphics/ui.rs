@@ -0,0 +1,310 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use crate::{
+    application::ApplicationSimulationEvent,
+    graphics::{
+        about_window, body_popup, camera, exit_dialog, file_menu, gizmos, horizons_dialog,
+        import_dialog, inspector, log_window, overlay, pipeline, plots_window, restore_dialog,
+        save_slots_window, scenario_browser, scenario_dialog, scenario_properties, settings_window,
+        spawn_dialog, stats, status_bar, textures, title_bar, toasts, tonemap, toolbar,
+        trajectory_window,
+    },
+    localization,
+    logging::LogBuffer,
+    simulation::{
+        BodyId, SimulationBody,
+        recording::TrajectoryRecording,
+        thread::{SimulationCommand, SimulationSnapshot},
+    },
+    storage::Settings,
+};
+
+/* the windows and their widget state, kept across a device loss. a new window adds its state
+here and its drawing to draw */
+#[derive(Default)]
+pub struct UiState {
+    /* tooltip and context menu of the bodies in the viewport, transient */
+    pub body_popup: body_popup::BodyPopup,
+    pub spawn_dialog: spawn_dialog::SpawnDialog,
+    pub import_dialog: import_dialog::ImportDialog,
+    pub horizons_dialog: horizons_dialog::HorizonsDialog,
+    pub trajectory_window: trajectory_window::TrajectoryWindow,
+    pub settings_window: settings_window::SettingsWindow,
+    pub log_window: log_window::LogWindow,
+    pub plots_window: plots_window::PlotsWindow,
+    pub scenario_dialog: scenario_dialog::ScenarioDialog,
+    pub scenario_browser: scenario_browser::ScenarioBrowser,
+    pub save_slots_window: save_slots_window::SaveSlotsWindow,
+    pub scenario_properties: scenario_properties::ScenarioProperties,
+    pub exit_dialog: exit_dialog::ExitDialog,
+    pub restore_dialog: restore_dialog::RestoreDialog,
+    pub toasts: toasts::Toasts,
+    pub about_window: about_window::AboutWindow,
+}
+
+/* what the ui reads and changes within one frame. everything beyond the camera, the selection
+and the display settings is left to the application through the actions */
+pub struct FrameContext<'a> {
+    pub snapshot: &'a SimulationSnapshot,
+    pub texture_manager: &'a textures::TextureManager,
+    pub settings: &'a Settings,
+    pub log_buffer: &'a LogBuffer,
+    /* file name or preset name of the running scenario */
+    pub scenario_label: &'a str,
+    /* the unix time of simulation time zero */
+    pub epoch: i64,
+    pub adapter: &'a wgpu::Adapter,
+    pub surface_configuration: &'a wgpu::SurfaceConfiguration,
+    pub camera: &'a mut camera::Camera,
+    pub selected_body: &'a mut Option<BodyId>,
+    pub gizmo_settings: &'a mut gizmos::GizmoSettings,
+    /* none on the direct path without tonemapping */
+    pub tonemap_settings: Option<&'a mut tonemap::TonemapSettings>,
+    /* of the previous frames, this one is still being recorded */
+    pub render_stats: stats::RenderStats,
+    pub frame_pacing: stats::FramePacing,
+    pub viewport_size: (u32, u32),
+    pub render_scale: f32,
+    /* differs from the requested one when the adapter lacks it */
+    pub active_sample_count: u32,
+    pub window_maximized: bool,
+    pub overlay_visible: bool,
+    pub frame_start: Instant,
+    /* applied by the application once the frame is submitted */
+    pub actions: Vec<ApplicationSimulationEvent>,
+}
+
+impl FrameContext<'_> {
+    pub fn push(&mut self, action: ApplicationSimulationEvent) {
+        self.actions.push(action);
+    }
+
+    fn control(&mut self, command: SimulationCommand) {
+        self.push(ApplicationSimulationEvent::ControlRequested(command));
+    }
+}
+
+/* returns the height of the menu bar, the viewport fills the window below it */
+pub fn draw_menu_bar(ui: &imgui::Ui, state: &mut UiState, context: &mut FrameContext) -> f32 {
+    let mut menu_bar_height = 0.;
+    ui.main_menu_bar(|| {
+        if let Some(icon) = context.texture_manager.get(textures::ICON_TEXTURE_KEY) {
+            ui.image_button("str_id", icon.texture_id, mint::Vector2 { x: 64., y: 64. });
+        }
+        ui.menu(localization::tr("menu-file"), || {
+            if let Some(action) = file_menu::draw_items(ui) {
+                context.push(ApplicationSimulationEvent::FileDialogRequested(action));
+            }
+            state.save_slots_window.draw_menu_item(ui);
+            ui.separator();
+            state.import_dialog.draw_menu_item(ui);
+            state.horizons_dialog.draw_menu_item(ui);
+            state.trajectory_window.draw_menu_item(ui);
+            ui.separator();
+            if state.scenario_properties.draw_menu_item(ui) {
+                context.push(ApplicationSimulationEvent::ScenarioPropertiesRequested);
+            }
+        });
+        if let Some(command) = toolbar::draw(ui, context.snapshot, context.epoch) {
+            context.control(command);
+        }
+        if let Some(command) =
+            toolbar::draw_edit_menu(ui, context.snapshot, &context.settings.keymap)
+        {
+            context.control(command);
+        }
+        ui.menu(localization::tr("menu-bodies"), || {
+            state.spawn_dialog.draw_menu_item(ui)
+        });
+        state.scenario_browser.draw_menu_item(ui);
+        state.settings_window.draw_menu_item(ui);
+        state.log_window.draw_menu_item(ui);
+        state.plots_window.draw_menu_item(ui);
+        if tonemap::draw_menu(ui, context.tonemap_settings.as_deref_mut()) {
+            context.push(ApplicationSimulationEvent::DisplayAdjusted);
+        }
+        ui.menu(localization::tr("menu-help"), || {
+            state.about_window.draw_menu_item(ui)
+        });
+        let render_stats = &context.render_stats;
+        ui.text(format!(
+            "frame {:.2} ms | passes {:.2} ms ({}) | {} draws, {} instances | {}x{} at {:.2}x",
+            render_stats.frame_time.as_secs_f64() * 1e3,
+            render_stats.pass_timings.total().as_secs_f64() * 1e3,
+            render_stats.timing_source,
+            render_stats.draw_counts.draw_calls,
+            render_stats.draw_counts.instances,
+            context.viewport_size.0,
+            context.viewport_size.1,
+            context.render_scale,
+        ));
+        if let Some(action) = title_bar::draw(ui, context.window_maximized) {
+            context.push(ApplicationSimulationEvent::WindowRequested(action));
+        }
+        menu_bar_height = ui.window_size()[1];
+    });
+    menu_bar_height
+}
+
+pub fn draw_status_bar(ui: &imgui::Ui, state: &UiState, context: &FrameContext) {
+    let snapshot = context.snapshot;
+    let selected = context
+        .selected_body
+        .and_then(|id| snapshot.bodies.iter().find(|body| body.id == id));
+    status_bar::draw(
+        ui,
+        context.scenario_label,
+        snapshot,
+        selected,
+        context.camera.position(),
+        state.toasts.status_message(context.frame_start),
+    );
+}
+
+/* the windows above the viewport */
+pub fn draw(ui: &imgui::Ui, state: &mut UiState, context: &mut FrameContext, menu_bar_height: f32) {
+    let snapshot = context.snapshot;
+    if let Some(action) = state
+        .body_popup
+        .draw(ui, snapshot, context.camera, Instant::now())
+    {
+        /* a deletion goes through the simulation like the one of the inspector */
+        match action {
+            body_popup::BodyMenuAction::Focus(id) => {
+                if let Some(body) = snapshot.bodies.iter().find(|body| body.id == id) {
+                    context.camera.focus(
+                        body.position,
+                        (body.radius * pipeline::BODY_RADIUS_RENDER_SCALE) as f32,
+                    );
+                }
+            }
+            body_popup::BodyMenuAction::Follow(id) => context.camera.set_follow_target(Some(id)),
+            body_popup::BodyMenuAction::Edit(id) => {
+                *context.selected_body = Some(id);
+                context.push(ApplicationSimulationEvent::SelectionChanged(Some(id)));
+            }
+            body_popup::BodyMenuAction::Delete(id) => {
+                context.push(ApplicationSimulationEvent::BodyDeleted(id));
+            }
+        }
+    }
+    if let Some(settings) =
+        state
+            .settings_window
+            .draw(ui, context.settings, context.active_sample_count)
+    {
+        context.push(ApplicationSimulationEvent::SettingsChanged(settings));
+    }
+    if state.settings_window.take_layout_reset() {
+        context.push(ApplicationSimulationEvent::UiLayoutReset);
+    }
+    state.log_window.draw(ui, context.log_buffer);
+    state.plots_window.draw(ui, snapshot);
+    state.about_window.draw(
+        ui,
+        context.adapter,
+        context.surface_configuration,
+        &localization::active_language(),
+    );
+    if let Some(choice) =
+        state
+            .scenario_browser
+            .draw(ui, context.frame_start, context.texture_manager)
+    {
+        context.push(ApplicationSimulationEvent::ScenarioRequested(choice));
+    }
+    if let Some(request) =
+        state
+            .save_slots_window
+            .draw(ui, context.frame_start, context.texture_manager)
+    {
+        context.push(ApplicationSimulationEvent::SaveSlotRequested(request));
+    }
+    let mut thumbnails = state.scenario_browser.take_thumbnail_requests();
+    thumbnails.extend(state.save_slots_window.take_thumbnail_requests());
+    if !thumbnails.is_empty() {
+        context.push(ApplicationSimulationEvent::ThumbnailsRequested(thumbnails));
+    }
+    if let Some(metadata) = state.scenario_properties.draw(ui) {
+        context.push(ApplicationSimulationEvent::ScenarioPropertiesChanged(
+            metadata,
+        ));
+    }
+    if let Some(pending) = state.scenario_dialog.draw(ui) {
+        context.push(ApplicationSimulationEvent::ScenarioConfirmed(pending));
+    }
+    let toasts_bottom = ui.io().display_size[1] - status_bar::height(ui);
+    if let Some(ids) = state.toasts.draw(ui, context.frame_start, toasts_bottom) {
+        /* bodies removed since the toast appeared are left out */
+        let bodies: Vec<SimulationBody> = snapshot
+            .bodies
+            .iter()
+            .filter(|body| ids.contains(&body.id))
+            .cloned()
+            .collect();
+        if !bodies.is_empty() {
+            let (center, radius) = camera::bounding_sphere(&bodies);
+            context.camera.focus(center, radius);
+        }
+    }
+    if let Some(choice) = state.exit_dialog.draw(ui) {
+        context.push(ApplicationSimulationEvent::ExitChosen(choice));
+    }
+    if let Some(path) = state.restore_dialog.draw(ui) {
+        context.push(ApplicationSimulationEvent::AutosaveRestored(path));
+    }
+    if context.overlay_visible {
+        overlay::draw(ui, menu_bar_height, &context.frame_pacing, snapshot);
+    }
+    let selected = context.selected_body.and_then(|id| {
+        let body = snapshot.bodies.iter().find(|body| body.id == id)?;
+        Some((body, gizmos::BodyVectors::of(&snapshot.bodies, id)?))
+    });
+    if let Some(action) = inspector::draw(ui, selected, snapshot.dirty, context.gizmo_settings)
+        && let Some(id) = *context.selected_body
+    {
+        match action {
+            inspector::InspectorAction::Edit { edit, finished } => {
+                context.push(ApplicationSimulationEvent::BodyEdited(id, edit));
+                if finished {
+                    context.control(SimulationCommand::FinishEdit);
+                }
+            }
+            inspector::InspectorAction::FinishEdit => {
+                context.control(SimulationCommand::FinishEdit)
+            }
+            inspector::InspectorAction::Delete => {
+                context.push(ApplicationSimulationEvent::BodyDeleted(id));
+            }
+        }
+    }
+    if let Some(spawn) = state.spawn_dialog.draw(ui, selected.map(|(body, _)| body)) {
+        context.push(ApplicationSimulationEvent::BodySpawned(spawn));
+    }
+    if let Some(import) = state.import_dialog.draw(ui) {
+        context.push(ApplicationSimulationEvent::BodiesImported(import));
+    }
+    if let Some(request) = state.horizons_dialog.draw(ui) {
+        context.push(ApplicationSimulationEvent::HorizonsImportRequested(request));
+    }
+    let recording = context.snapshot.recording.as_ref();
+    match state.trajectory_window.draw(ui, recording) {
+        Some(trajectory_window::TrajectoryAction::Start(interval)) => {
+            let recording = TrajectoryRecording::new(interval, context.snapshot.simulation_time);
+            context.control(SimulationCommand::SetRecording(Some(Arc::new(Mutex::new(
+                recording,
+            )))));
+        }
+        Some(trajectory_window::TrajectoryAction::Stop) => {
+            context.control(SimulationCommand::SetRecording(None))
+        }
+        Some(trajectory_window::TrajectoryAction::Export { recording, path }) => {
+            context
+                .push(ApplicationSimulationEvent::TrajectoriesExportRequested { recording, path });
+        }
+        None => {}
+    }
+}
@@ -0,0 +1,252 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use imgui::{Condition, TextureId};
+use imgui_wgpu::RawTextureConfig;
+use tracing::info;
+use wgpu::{Device, TextureFormat};
+
+use crate::localization;
+
+pub const VIEWPORT_WINDOW_TITLE: &str = "Viewport";
+/* a drag resizes the window every frame, the texture only follows once the size held this long */
+pub const VIEWPORT_RESIZE_DEBOUNCE: Duration = Duration::from_millis(150);
+/* scene resolution relative to the window, the image is scaled with the linear ui sampler */
+pub const DEFAULT_RENDER_SCALE: f32 = 1.;
+pub const MINIMUM_RENDER_SCALE: f32 = 0.5;
+pub const MAXIMUM_RENDER_SCALE: f32 = 2.;
+pub const RENDER_SCALE_STEP: f32 = 0.25;
+
+/* the scene is rendered into this texture and shown as an image inside an imgui window.
+sizes and positions are physical pixels, imgui itself works in logical ones. the texture is
+the window content size times the render scale, the ui stays at native resolution */
+pub struct Viewport {
+    pub format: TextureFormat,
+    pub width: u32,
+    pub height: u32,
+    render_scale: f32,
+    pub view: Arc<wgpu::TextureView>,
+    texture: Arc<wgpu::Texture>,
+    /* none until registered with the imgui renderer */
+    texture_id: Option<TextureId>,
+    /* size the window asks for and since when */
+    pending_size: Option<((u32, u32), Instant)>,
+    /* where the image was drawn in the last frame, in window pixels */
+    image_origin: [f32; 2],
+    image_size: [f32; 2],
+    /* window pixels per ui pixel */
+    ui_scale: [f32; 2],
+    hovered: bool,
+}
+
+impl Viewport {
+    pub fn new(device: &Device, format: TextureFormat, width: u32, height: u32) -> Self {
+        let (texture, view) = self::create_texture(device, format, width, height);
+        Self {
+            format,
+            width: width.max(1),
+            height: height.max(1),
+            render_scale: DEFAULT_RENDER_SCALE,
+            view,
+            texture,
+            texture_id: None,
+            pending_size: None,
+            image_origin: [0.; 2],
+            image_size: [width as f32, height as f32],
+            ui_scale: [1.; 2],
+            hovered: false,
+        }
+    }
+
+    pub fn register(&mut self, device: &Device, renderer: &mut imgui_wgpu::Renderer) {
+        let texture = self.imgui_texture(device, renderer);
+        self.texture_id = Some(match self.texture_id {
+            Some(texture_id) => {
+                renderer.textures.replace(texture_id, texture);
+                texture_id
+            }
+            None => renderer.textures.insert(texture),
+        });
+    }
+
+    pub fn texture(&self) -> &wgpu::Texture {
+        &self.texture
+    }
+
+    /* recreates the texture, the caller rebuilds everything sized after it */
+    pub fn resize(
+        &mut self,
+        device: &Device,
+        renderer: &mut imgui_wgpu::Renderer,
+        width: u32,
+        height: u32,
+    ) {
+        (self.texture, self.view) = self::create_texture(device, self.format, width, height);
+        self.width = width.max(1);
+        self.height = height.max(1);
+        self.register(device, renderer);
+        info!("Viewport resized to {}x{}", self.width, self.height);
+    }
+
+    /* records the size the window wants, a changed size restarts the debounce */
+    pub fn request_size(&mut self, width: u32, height: u32, now: Instant) {
+        let size = (width.max(1), height.max(1));
+        if size == (self.width, self.height) {
+            self.pending_size = None;
+        } else if self.pending_size.is_none_or(|(pending, _)| pending != size) {
+            self.pending_size = Some((size, now));
+        }
+    }
+
+    /* the requested size once it has been stable for VIEWPORT_RESIZE_DEBOUNCE */
+    pub fn take_resize(&mut self, now: Instant) -> Option<(u32, u32)> {
+        let (size, since) = self.pending_size?;
+        if now.duration_since(since) < VIEWPORT_RESIZE_DEBOUNCE {
+            return None;
+        }
+        self.pending_size = None;
+        Some(size)
+    }
+
+    pub fn render_scale(&self) -> f32 {
+        self.render_scale
+    }
+
+    /* clamped, the texture follows on the next resize like a window drag */
+    pub fn set_render_scale(&mut self, render_scale: f32) {
+        self.render_scale = render_scale.clamp(MINIMUM_RENDER_SCALE, MAXIMUM_RENDER_SCALE);
+    }
+
+    pub fn hovered(&self) -> bool {
+        self.hovered
+    }
+
+    /* draws the viewport window, the image keeps the texture aspect ratio while a resize is
+    pending so it never stretches. the overlay runs inside the window right after the image */
+    pub fn draw(
+        &mut self,
+        ui: &imgui::Ui,
+        position: [f32; 2],
+        size: [f32; 2],
+        overlay: impl FnOnce(&Self),
+    ) {
+        let Some(texture_id) = self.texture_id else {
+            return;
+        };
+        let scale = ui.io().display_framebuffer_scale;
+        self.ui_scale = scale;
+        self.hovered = false;
+        let style = ui.push_style_var(imgui::StyleVar::WindowPadding([0.; 2]));
+        ui.window(localization::title(
+            "window-viewport",
+            VIEWPORT_WINDOW_TITLE,
+        ))
+        .position(position, Condition::FirstUseEver)
+        .size(size, Condition::FirstUseEver)
+        .scroll_bar(false)
+        .scrollable(false)
+        /* focused the keys stay the shortcuts of the scene instead of navigating */
+        .nav_inputs(false)
+        .build(|| {
+            let available = ui.content_region_avail();
+            self.request_size(
+                (available[0] * scale[0] * self.render_scale).round() as u32,
+                (available[1] * scale[1] * self.render_scale).round() as u32,
+                Instant::now(),
+            );
+            let aspect_ratio = self.width as f32 / self.height as f32;
+            let fitted = match available[0] / available[1].max(1.) > aspect_ratio {
+                true => [available[1] * aspect_ratio, available[1]],
+                false => [available[0], available[0] / aspect_ratio],
+            };
+            let cursor = ui.cursor_pos();
+            ui.set_cursor_pos([
+                cursor[0] + (available[0] - fitted[0]) * 0.5,
+                cursor[1] + (available[1] - fitted[1]) * 0.5,
+            ]);
+            imgui::Image::new(texture_id, fitted).build(ui);
+            self.hovered = ui.is_item_hovered();
+            let origin = ui.item_rect_min();
+            self.image_origin = [origin[0] * scale[0], origin[1] * scale[1]];
+            self.image_size = [fitted[0] * scale[0], fitted[1] * scale[1]];
+            overlay(self);
+        });
+        style.pop();
+    }
+
+    /* maps a cursor position in window pixels into texture pixels, none outside the image */
+    pub fn to_viewport(&self, cursor: (f32, f32)) -> Option<(f32, f32)> {
+        let local = [
+            (cursor.0 - self.image_origin[0]) / self.image_size[0].max(1.),
+            (cursor.1 - self.image_origin[1]) / self.image_size[1].max(1.),
+        ];
+        local
+            .iter()
+            .all(|coordinate| (0. ..=1.).contains(coordinate))
+            .then(|| (local[0] * self.width as f32, local[1] * self.height as f32))
+    }
+
+    /* maps texture pixels onto the image as drawn, in ui pixels */
+    pub fn to_ui(&self, point: [f32; 2]) -> [f32; 2] {
+        [
+            (self.image_origin[0] + point[0] / self.width as f32 * self.image_size[0])
+                / self.ui_scale[0],
+            (self.image_origin[1] + point[1] / self.height as f32 * self.image_size[1])
+                / self.ui_scale[1],
+        ]
+    }
+
+    fn imgui_texture(
+        &self,
+        device: &Device,
+        renderer: &imgui_wgpu::Renderer,
+    ) -> imgui_wgpu::Texture {
+        imgui_wgpu::Texture::from_raw_parts(
+            device,
+            renderer,
+            self.texture.clone(),
+            self.view.clone(),
+            None,
+            Some(&RawTextureConfig {
+                label: Some(VIEWPORT_WINDOW_TITLE),
+                sampler_desc: wgpu::SamplerDescriptor {
+                    label: Some("viewport sampler"),
+                    mag_filter: wgpu::FilterMode::Linear,
+                    min_filter: wgpu::FilterMode::Linear,
+                    ..Default::default()
+                },
+            }),
+            self.texture.size(),
+        )
+    }
+}
+
+/* rendered by the scene or tonemapping pass and sampled by imgui, in the surface format. read
+back for the thumbnails of saves */
+fn create_texture(
+    device: &Device,
+    format: TextureFormat,
+    width: u32,
+    height: u32,
+) -> (Arc<wgpu::Texture>, Arc<wgpu::TextureView>) {
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("viewport texture"),
+        size: wgpu::Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT
+            | wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::COPY_SRC,
+        view_formats: &[],
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    (Arc::new(texture), Arc::new(view))
+}
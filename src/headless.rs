@@ -0,0 +1,143 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use tracing::{info, warn};
+
+use crate::{
+    cli::LaunchOptions,
+    scenario,
+    simulation::{plots, recording},
+    storage::Settings,
+};
+
+/* wall time between two progress lines */
+pub const HEADLESS_PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+/* rows per body written over the run when no sample interval is given */
+pub const DEFAULT_SAMPLE_COUNT: f64 = 1000.;
+
+/* steps the scenario as fast as possible, without the simulation thread's pacing and without
+touching wgpu, winit or imgui. the body states are sampled into a csv file when one is given */
+pub fn run(launch_options: &LaunchOptions, settings: &Settings) -> Result<()> {
+    let duration = launch_options
+        .duration
+        .context("A headless run needs a duration")?;
+    if launch_options.warp.is_some() {
+        warn!("A headless run is not paced, ignoring --warp");
+    }
+    let sample_interval = launch_options
+        .sample_interval
+        .unwrap_or(duration / DEFAULT_SAMPLE_COUNT);
+    let objects = scenario::launch_scenario(launch_options)?.objects;
+    let mut state = scenario::simulation_state(&objects, settings);
+    let mut output = launch_options
+        .out
+        .as_deref()
+        .map(self::create_output)
+        .transpose()?;
+    let initial_energy = plots::total_energy(&state.bodies);
+    info!(
+        "Running {} bodies headless for {} s",
+        state.bodies.len(),
+        duration
+    );
+
+    let start = Instant::now();
+    let mut last_progress = start;
+    let mut next_sample = state.simulation_time;
+    loop {
+        if let Some(output) = output.as_mut()
+            && state.simulation_time >= next_sample
+        {
+            recording::write_sample(output, state.simulation_time, &state.bodies)?;
+            next_sample += sample_interval;
+        }
+        if state.simulation_time >= duration {
+            break;
+        }
+        state.step();
+        for event in state.drain_events() {
+            scenario::log_simulation_event(&state.bodies, state.simulation_time, &event);
+        }
+        if last_progress.elapsed() >= HEADLESS_PROGRESS_INTERVAL {
+            last_progress = Instant::now();
+            info!(
+                "{:.1} % simulated, {} steps",
+                state.simulation_time / duration * 100.,
+                state.diagnostics.step_count
+            );
+        }
+    }
+    if let Some(mut output) = output {
+        output.flush().context("Failed to write the results")?;
+    }
+
+    let final_energy = plots::total_energy(&state.bodies);
+    info!(
+        "Simulated {:.0} s in {} steps and {:.1} s, relative energy drift {:+.3e}",
+        state.simulation_time,
+        state.diagnostics.step_count,
+        start.elapsed().as_secs_f64(),
+        (final_energy - initial_energy) / initial_energy.abs().max(f64::MIN_POSITIVE)
+    );
+    if let Some(path) = launch_options.out.as_ref() {
+        info!("Wrote the results to {}", path.display());
+    }
+    Ok(())
+}
+
+fn create_output(path: &Path) -> Result<BufWriter<File>> {
+    if let Some(directory) = path
+        .parent()
+        .filter(|directory| !directory.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(directory)
+            .with_context(|| format!("Failed to create {}", directory.display()))?;
+    }
+    let file =
+        File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+    let mut writer = BufWriter::new(file);
+    recording::write_header(&mut writer)?;
+    Ok(writer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headless_run_writes_a_row_per_body_and_sample() {
+        let directory = tempfile::tempdir().unwrap();
+        let out = directory.path().join("runs").join("day.csv");
+        let launch_options = LaunchOptions {
+            headless: true,
+            duration: Some(86400.),
+            out: Some(out.clone()),
+            sample_interval: Some(3600.),
+            ..LaunchOptions::default()
+        };
+        self::run(&launch_options, &Settings::default()).unwrap();
+        let text = std::fs::read_to_string(&out).unwrap();
+        let mut lines = text.lines();
+        assert_eq!(lines.next(), Some(recording::TRAJECTORY_CSV_HEADER));
+        /* the sun, earth and moon at every hour from the start through the end */
+        let rows: Vec<&str> = lines.collect();
+        assert_eq!(rows.len(), 3 * 25);
+        assert!(rows[0].starts_with("0,"));
+        assert!(
+            rows.last().unwrap().starts_with("86400,"),
+            "{}",
+            rows.last().unwrap()
+        );
+    }
+
+    #[test]
+    fn headless_run_needs_a_duration() {
+        let error = self::run(&LaunchOptions::default(), &Settings::default()).unwrap_err();
+        assert_eq!(error.to_string(), "A headless run needs a duration");
+    }
+}
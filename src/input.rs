@@ -0,0 +1,660 @@
+use std::{collections::BTreeMap, fmt::Display, str::FromStr};
+
+use anyhow::{Context, bail};
+use glam::Vec2;
+use serde::{Deserialize, Serialize, de::IntoDeserializer};
+use strum::IntoEnumIterator;
+use wgpu::naga::FastHashSet;
+use winit::{
+    event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent},
+    keyboard::{KeyCode, ModifiersState, PhysicalKey},
+};
+
+use crate::platform::gamepad::GamepadButton;
+
+/* pixels of a touchpad scroll that count as one wheel line */
+pub const SCROLL_PIXELS_PER_LINE: f32 = 40.;
+
+/* what a key press does, the keymap of the settings binds keys to them */
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    strum_macros::EnumIter,
+    strum_macros::Display,
+)]
+pub enum InputAction {
+    #[serde(rename = "quit")]
+    #[strum(to_string = "Quit")]
+    Quit,
+    #[serde(rename = "toggle pause")]
+    #[strum(to_string = "Play or pause")]
+    TogglePause,
+    #[serde(rename = "step once")]
+    #[strum(to_string = "Step once while paused")]
+    StepOnce,
+    #[serde(rename = "warp up")]
+    #[strum(to_string = "Faster warp")]
+    WarpUp,
+    #[serde(rename = "warp down")]
+    #[strum(to_string = "Slower warp")]
+    WarpDown,
+    #[serde(rename = "undo")]
+    #[strum(to_string = "Undo edit")]
+    Undo,
+    #[serde(rename = "redo")]
+    #[strum(to_string = "Redo edit")]
+    Redo,
+    #[serde(rename = "toggle fullscreen")]
+    #[strum(to_string = "Fullscreen")]
+    ToggleFullscreen,
+    #[serde(rename = "quicksave")]
+    #[strum(to_string = "Quicksave")]
+    Quicksave,
+    #[serde(rename = "quickload")]
+    #[strum(to_string = "Quickload")]
+    Quickload,
+    #[serde(rename = "screenshot")]
+    #[strum(to_string = "Screenshot")]
+    Screenshot,
+    #[serde(rename = "toggle overlay")]
+    #[strum(to_string = "Performance overlay")]
+    ToggleOverlay,
+    #[serde(rename = "cycle debug render mode")]
+    #[strum(to_string = "Debug render mode")]
+    CycleDebugRenderMode,
+    #[serde(rename = "cycle msaa")]
+    #[strum(to_string = "Msaa samples")]
+    CycleMsaa,
+    #[serde(rename = "render scale up")]
+    #[strum(to_string = "Higher render scale")]
+    RenderScaleUp,
+    #[serde(rename = "render scale down")]
+    #[strum(to_string = "Lower render scale")]
+    RenderScaleDown,
+    #[serde(rename = "cycle present mode")]
+    #[strum(to_string = "Present mode")]
+    CyclePresentMode,
+    #[serde(rename = "exposure up")]
+    #[strum(to_string = "Higher exposure")]
+    ExposureUp,
+    #[serde(rename = "exposure down")]
+    #[strum(to_string = "Lower exposure")]
+    ExposureDown,
+    #[serde(rename = "cycle tonemap curve")]
+    #[strum(to_string = "Tonemap curve")]
+    CycleTonemapCurve,
+    #[serde(rename = "toggle lighting")]
+    #[strum(to_string = "Lighting")]
+    ToggleLighting,
+    #[serde(rename = "toggle bloom")]
+    #[strum(to_string = "Bloom")]
+    ToggleBloom,
+    #[serde(rename = "toggle trails")]
+    #[strum(to_string = "Trails")]
+    ToggleTrails,
+    #[serde(rename = "toggle followed trail")]
+    #[strum(to_string = "Trail of the followed body")]
+    ToggleFollowedTrail,
+    #[serde(rename = "toggle grid")]
+    #[strum(to_string = "Grid")]
+    ToggleGrid,
+    #[serde(rename = "toggle grid axes")]
+    #[strum(to_string = "Grid axes")]
+    ToggleGridAxes,
+    #[serde(rename = "toggle labels")]
+    #[strum(to_string = "Labels")]
+    ToggleLabels,
+    #[serde(rename = "follow selection")]
+    #[strum(to_string = "Follow the selection")]
+    FollowSelection,
+    #[serde(rename = "cycle follow")]
+    #[strum(to_string = "Follow the next body")]
+    CycleFollow,
+    /* tries the recovery from a device loss, without a key unless one is bound */
+    #[serde(rename = "simulate device loss")]
+    #[strum(to_string = "Simulate a gpu device loss")]
+    SimulateDeviceLoss,
+}
+
+impl InputAction {
+    pub fn default_bindings(self) -> Vec<KeyBinding> {
+        let key = KeyBinding::key;
+        match self {
+            InputAction::Quit => vec![key(KeyCode::Escape)],
+            InputAction::TogglePause => vec![key(KeyCode::Space)],
+            InputAction::StepOnce => vec![key(KeyCode::KeyS)],
+            InputAction::WarpUp => vec![key(KeyCode::BracketRight)],
+            InputAction::WarpDown => vec![key(KeyCode::BracketLeft)],
+            InputAction::Undo => vec![KeyBinding::ctrl(KeyCode::KeyZ)],
+            InputAction::Redo => vec![
+                KeyBinding::ctrl(KeyCode::KeyY),
+                KeyBinding::new(
+                    KeyCode::KeyZ,
+                    ModifiersState::CONTROL | ModifiersState::SHIFT,
+                ),
+            ],
+            InputAction::ToggleFullscreen => vec![
+                key(KeyCode::F11),
+                KeyBinding::alt(KeyCode::Enter),
+                KeyBinding::alt(KeyCode::NumpadEnter),
+            ],
+            InputAction::Quicksave => vec![key(KeyCode::F5)],
+            InputAction::Quickload => vec![key(KeyCode::F9)],
+            InputAction::Screenshot => vec![key(KeyCode::F12)],
+            InputAction::ToggleOverlay => vec![key(KeyCode::F3)],
+            InputAction::CycleDebugRenderMode => vec![key(KeyCode::F4)],
+            InputAction::CycleMsaa => vec![key(KeyCode::KeyM)],
+            InputAction::RenderScaleUp => vec![key(KeyCode::Period)],
+            InputAction::RenderScaleDown => vec![key(KeyCode::Comma)],
+            InputAction::CyclePresentMode => vec![key(KeyCode::KeyV)],
+            InputAction::ExposureUp => vec![key(KeyCode::Equal)],
+            InputAction::ExposureDown => vec![key(KeyCode::Minus)],
+            InputAction::CycleTonemapCurve => vec![key(KeyCode::KeyO)],
+            InputAction::ToggleLighting => vec![key(KeyCode::KeyL)],
+            InputAction::ToggleBloom => vec![key(KeyCode::KeyB)],
+            InputAction::ToggleTrails => vec![key(KeyCode::KeyT)],
+            InputAction::ToggleFollowedTrail => vec![key(KeyCode::KeyY)],
+            InputAction::ToggleGrid => vec![key(KeyCode::KeyG)],
+            InputAction::ToggleGridAxes => vec![key(KeyCode::KeyX)],
+            InputAction::ToggleLabels => vec![key(KeyCode::KeyN)],
+            InputAction::FollowSelection => vec![key(KeyCode::KeyF)],
+            InputAction::CycleFollow => vec![key(KeyCode::Tab)],
+            InputAction::SimulateDeviceLoss => Vec::new(),
+        }
+    }
+
+    /* fixed, unlike the keys. the sticks and triggers move the camera instead */
+    pub fn from_gamepad_button(button: GamepadButton) -> Option<Self> {
+        Some(match button {
+            GamepadButton::South | GamepadButton::Start => InputAction::TogglePause,
+            GamepadButton::East => InputAction::StepOnce,
+            GamepadButton::West => InputAction::WarpDown,
+            GamepadButton::North => InputAction::WarpUp,
+            GamepadButton::LeftShoulder => InputAction::FollowSelection,
+            GamepadButton::RightShoulder => InputAction::CycleFollow,
+            GamepadButton::Select => InputAction::ToggleOverlay,
+        })
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        InputAction::deserialize(name.into_deserializer())
+            .map_err(|_: serde::de::value::Error| ())
+            .ok()
+    }
+}
+
+/* a key with the modifiers that have to be held, others than these keep it from matching */
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(try_from = "String", into = "String")]
+pub struct KeyBinding {
+    pub key: KeyCode,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl KeyBinding {
+    pub fn new(key: KeyCode, modifiers: ModifiersState) -> Self {
+        Self {
+            key,
+            ctrl: modifiers.control_key(),
+            shift: modifiers.shift_key(),
+            alt: modifiers.alt_key(),
+        }
+    }
+
+    pub fn key(key: KeyCode) -> Self {
+        Self::new(key, ModifiersState::empty())
+    }
+
+    pub fn alt(key: KeyCode) -> Self {
+        Self::new(key, ModifiersState::ALT)
+    }
+
+    pub fn ctrl(key: KeyCode) -> Self {
+        Self::new(key, ModifiersState::CONTROL)
+    }
+
+    /* a modifier on its own is never bound, it only ever joins another key */
+    pub fn is_modifier(key: KeyCode) -> bool {
+        matches!(
+            key,
+            KeyCode::ControlLeft
+                | KeyCode::ControlRight
+                | KeyCode::ShiftLeft
+                | KeyCode::ShiftRight
+                | KeyCode::AltLeft
+                | KeyCode::AltRight
+                | KeyCode::SuperLeft
+                | KeyCode::SuperRight
+        )
+    }
+}
+
+/* like Ctrl+Shift+S, letters and digits without the Key and Digit prefixes of winit */
+impl Display for KeyBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let modifiers = [
+            (self.ctrl, "Ctrl+"),
+            (self.shift, "Shift+"),
+            (self.alt, "Alt+"),
+        ];
+        for (held, name) in modifiers {
+            if held {
+                f.write_str(name)?;
+            }
+        }
+        let name = format!("{:?}", self.key);
+        let short = ["Key", "Digit"].into_iter().find_map(|prefix| {
+            name.strip_prefix(prefix)
+                .filter(|rest| rest.chars().count() == 1)
+        });
+        f.write_str(short.unwrap_or(&name))
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let key_name = parts.pop().filter(|name| !name.is_empty());
+        let key_name = key_name.with_context(|| format!("No key in the binding {:?}", text))?;
+        let mut modifiers = ModifiersState::empty();
+        for modifier in parts {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => ModifiersState::CONTROL,
+                "shift" => ModifiersState::SHIFT,
+                "alt" => ModifiersState::ALT,
+                _ => bail!("Unknown modifier {:?} in the binding {:?}", modifier, text),
+            };
+        }
+        /* the winit name, or a letter or digit on its own */
+        let key = [
+            key_name.to_string(),
+            format!("Key{}", key_name.to_ascii_uppercase()),
+        ]
+        .into_iter()
+        .chain(std::iter::once(format!("Digit{}", key_name)))
+        .find_map(|name| {
+            KeyCode::deserialize(name.as_str().into_deserializer())
+                .map_err(|_: serde::de::value::Error| ())
+                .ok()
+        })
+        .with_context(|| format!("Unknown key {:?}", key_name))?;
+        Ok(Self::new(key, modifiers))
+    }
+}
+
+impl TryFrom<String> for KeyBinding {
+    type Error = anyhow::Error;
+
+    fn try_from(text: String) -> Result<Self, Self::Error> {
+        text.parse()
+    }
+}
+
+impl From<KeyBinding> for String {
+    fn from(binding: KeyBinding) -> Self {
+        binding.to_string()
+    }
+}
+
+/* every action with its keys, an empty list leaves the action unbound */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(from = "BTreeMap<String, Vec<String>>")]
+pub struct Keymap(BTreeMap<InputAction, Vec<KeyBinding>>);
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self(
+            InputAction::iter()
+                .map(|action| (action, action.default_bindings()))
+                .collect(),
+        )
+    }
+}
+
+/* actions missing from the file keep their default keys, unknown actions and keys are skipped
+like the unknown fields of the settings, so a keymap written by a newer version still loads */
+impl From<BTreeMap<String, Vec<String>>> for Keymap {
+    fn from(entries: BTreeMap<String, Vec<String>>) -> Self {
+        let mut keymap = Keymap::default();
+        for (name, bindings) in entries {
+            let Some(action) = InputAction::from_name(&name) else {
+                continue;
+            };
+            let bindings = bindings
+                .iter()
+                .filter_map(|binding| binding.parse().ok())
+                .collect();
+            keymap.0.insert(action, bindings);
+        }
+        keymap
+    }
+}
+
+impl Keymap {
+    pub fn bindings(&self, action: InputAction) -> &[KeyBinding] {
+        self.0.get(&action).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /* replaces the keys of the action, other actions on the same key keep it */
+    pub fn bind(&mut self, action: InputAction, bindings: Vec<KeyBinding>) {
+        self.0.insert(action, bindings);
+    }
+
+    /* the first action in declaration order wins a conflict */
+    pub fn action(&self, binding: KeyBinding) -> Option<InputAction> {
+        self.0
+            .iter()
+            .find(|(_, bindings)| bindings.contains(&binding))
+            .map(|(action, _)| *action)
+    }
+
+    /* keys bound to more than one action, with the actions in declaration order */
+    pub fn conflicts(&self) -> Vec<(KeyBinding, Vec<InputAction>)> {
+        let mut actions_by_binding: Vec<(KeyBinding, Vec<InputAction>)> = Vec::new();
+        for (action, bindings) in &self.0 {
+            for binding in bindings {
+                match actions_by_binding
+                    .iter_mut()
+                    .find(|(bound, _)| bound == binding)
+                {
+                    Some((_, actions)) => actions.push(*action),
+                    None => actions_by_binding.push((*binding, vec![*action])),
+                }
+            }
+        }
+        actions_by_binding.retain(|(_, actions)| actions.len() > 1);
+        actions_by_binding
+    }
+}
+
+/* what the keyboard and mouse did to the scene, collected from the window events between two
+frames. presses imgui wants are left out, releases are always taken so nothing stays held */
+#[derive(Debug, Default)]
+pub struct InputState {
+    held_keys: FastHashSet<KeyCode>,
+    held_buttons: FastHashSet<MouseButton>,
+    /* since the previous frame */
+    pressed_buttons: FastHashSet<MouseButton>,
+    released_buttons: FastHashSet<MouseButton>,
+    /* physical window pixels, none while outside the window */
+    cursor: Option<Vec2>,
+    cursor_delta: Vec2,
+    scroll_lines: f32,
+}
+
+impl InputState {
+    pub fn handle_window_event(
+        &mut self,
+        event: &WindowEvent,
+        want_capture_keyboard: bool,
+        want_capture_mouse: bool,
+    ) {
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => {
+                let PhysicalKey::Code(key) = event.physical_key else {
+                    return;
+                };
+                match event.state {
+                    ElementState::Pressed if !want_capture_keyboard => {
+                        self.held_keys.insert(key);
+                    }
+                    ElementState::Pressed => {}
+                    ElementState::Released => {
+                        self.held_keys.remove(&key);
+                    }
+                }
+            }
+            WindowEvent::MouseInput { state, button, .. } => match state {
+                ElementState::Pressed if !want_capture_mouse => {
+                    self.held_buttons.insert(*button);
+                    self.pressed_buttons.insert(*button);
+                }
+                ElementState::Pressed => {}
+                /* only the release of a press that went to the scene */
+                ElementState::Released => {
+                    if self.held_buttons.remove(button) {
+                        self.released_buttons.insert(*button);
+                    }
+                }
+            },
+            WindowEvent::CursorMoved { position, .. } => {
+                let cursor = Vec2::new(position.x as f32, position.y as f32);
+                if let Some(last) = self.cursor.replace(cursor) {
+                    self.cursor_delta += cursor - last;
+                }
+            }
+            WindowEvent::CursorLeft { .. } => self.cursor = None,
+            WindowEvent::MouseWheel { delta, .. } if !want_capture_mouse => {
+                self.scroll_lines += match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(position) => {
+                        position.y as f32 / SCROLL_PIXELS_PER_LINE
+                    }
+                };
+            }
+            /* the releases go to the window that took the focus */
+            WindowEvent::Focused(false) => {
+                self.held_keys.clear();
+                self.held_buttons.clear();
+            }
+            _ => {}
+        }
+    }
+
+    /* after the frame consumed the input, the held keys and buttons carry over */
+    pub fn end_frame(&mut self) {
+        self.pressed_buttons.clear();
+        self.released_buttons.clear();
+        self.cursor_delta = Vec2::ZERO;
+        self.scroll_lines = 0.;
+    }
+
+    pub fn is_key_held(&self, key: KeyCode) -> bool {
+        self.held_keys.contains(&key)
+    }
+
+    pub fn is_button_held(&self, button: MouseButton) -> bool {
+        self.held_buttons.contains(&button)
+    }
+
+    pub fn was_pressed(&self, button: MouseButton) -> bool {
+        self.pressed_buttons.contains(&button)
+    }
+
+    pub fn was_released(&self, button: MouseButton) -> bool {
+        self.released_buttons.contains(&button)
+    }
+
+    pub fn cursor(&self) -> Option<Vec2> {
+        self.cursor
+    }
+
+    pub fn cursor_delta(&self) -> Vec2 {
+        self.cursor_delta
+    }
+
+    pub fn scroll_lines(&self) -> f32 {
+        self.scroll_lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use winit::{dpi::PhysicalPosition, event::DeviceId, event::TouchPhase};
+
+    fn button(state: ElementState, button: MouseButton) -> WindowEvent {
+        WindowEvent::MouseInput {
+            device_id: DeviceId::dummy(),
+            state,
+            button,
+        }
+    }
+
+    fn cursor_moved(x: f64, y: f64) -> WindowEvent {
+        WindowEvent::CursorMoved {
+            device_id: DeviceId::dummy(),
+            position: PhysicalPosition::new(x, y),
+        }
+    }
+
+    fn wheel(delta: MouseScrollDelta) -> WindowEvent {
+        WindowEvent::MouseWheel {
+            device_id: DeviceId::dummy(),
+            delta,
+            phase: TouchPhase::Moved,
+        }
+    }
+
+    #[test]
+    fn cursor_motion_and_scroll_accumulate_until_the_frame_ends() {
+        let mut input = InputState::default();
+        input.handle_window_event(&self::cursor_moved(10., 10.), false, false);
+        /* the first position has nothing to move from */
+        assert_eq!(input.cursor_delta(), Vec2::ZERO);
+        input.handle_window_event(&self::cursor_moved(13., 8.), false, false);
+        input.handle_window_event(&self::cursor_moved(15., 9.), false, false);
+        assert_eq!(input.cursor(), Some(Vec2::new(15., 9.)));
+        assert_eq!(input.cursor_delta(), Vec2::new(5., -1.));
+        input.handle_window_event(
+            &self::wheel(MouseScrollDelta::LineDelta(0., 1.)),
+            false,
+            false,
+        );
+        let pixels = MouseScrollDelta::PixelDelta(PhysicalPosition::new(0., 20.));
+        input.handle_window_event(&self::wheel(pixels), false, false);
+        assert_eq!(input.scroll_lines(), 1.5);
+        input.end_frame();
+        assert_eq!(input.cursor_delta(), Vec2::ZERO);
+        assert_eq!(input.scroll_lines(), 0.);
+        assert_eq!(input.cursor(), Some(Vec2::new(15., 9.)));
+        input.handle_window_event(
+            &WindowEvent::CursorLeft {
+                device_id: DeviceId::dummy(),
+            },
+            false,
+            false,
+        );
+        assert_eq!(input.cursor(), None);
+    }
+
+    #[test]
+    fn presses_last_a_frame_and_holds_carry_over() {
+        let mut input = InputState::default();
+        input.handle_window_event(
+            &self::button(ElementState::Pressed, MouseButton::Left),
+            false,
+            false,
+        );
+        assert!(input.was_pressed(MouseButton::Left));
+        assert!(input.is_button_held(MouseButton::Left));
+        input.end_frame();
+        assert!(!input.was_pressed(MouseButton::Left));
+        assert!(input.is_button_held(MouseButton::Left));
+        input.handle_window_event(
+            &self::button(ElementState::Released, MouseButton::Left),
+            false,
+            false,
+        );
+        assert!(input.was_released(MouseButton::Left));
+        assert!(!input.is_button_held(MouseButton::Left));
+        input.end_frame();
+        assert!(!input.was_released(MouseButton::Left));
+    }
+
+    #[test]
+    fn captured_mouse_input_is_left_to_imgui() {
+        let mut input = InputState::default();
+        input.handle_window_event(
+            &self::button(ElementState::Pressed, MouseButton::Right),
+            false,
+            true,
+        );
+        input.handle_window_event(
+            &self::wheel(MouseScrollDelta::LineDelta(0., 3.)),
+            false,
+            true,
+        );
+        assert!(!input.was_pressed(MouseButton::Right));
+        assert!(!input.is_button_held(MouseButton::Right));
+        assert_eq!(input.scroll_lines(), 0.);
+        /* a release without a press of the scene is imgui's too */
+        input.handle_window_event(
+            &self::button(ElementState::Released, MouseButton::Right),
+            false,
+            false,
+        );
+        assert!(!input.was_released(MouseButton::Right));
+        /* a press of the scene is released even over imgui */
+        input.handle_window_event(
+            &self::button(ElementState::Pressed, MouseButton::Middle),
+            false,
+            false,
+        );
+        input.handle_window_event(
+            &self::button(ElementState::Released, MouseButton::Middle),
+            false,
+            true,
+        );
+        assert!(input.was_released(MouseButton::Middle));
+        assert!(!input.is_button_held(MouseButton::Middle));
+    }
+
+    #[test]
+    fn losing_the_focus_releases_everything() {
+        let mut input = InputState::default();
+        input.handle_window_event(
+            &self::button(ElementState::Pressed, MouseButton::Left),
+            false,
+            false,
+        );
+        input.handle_window_event(&WindowEvent::Focused(false), false, false);
+        assert!(!input.is_button_held(MouseButton::Left));
+        input.handle_window_event(
+            &self::button(ElementState::Pressed, MouseButton::Left),
+            false,
+            false,
+        );
+        input.handle_window_event(&WindowEvent::Focused(true), false, false);
+        assert!(input.is_button_held(MouseButton::Left));
+    }
+
+    #[test]
+    fn f9_quickloads_and_the_device_loss_has_no_key() {
+        let keymap = Keymap::default();
+        assert_eq!(
+            keymap.action(KeyBinding::key(KeyCode::F9)),
+            Some(InputAction::Quickload)
+        );
+        assert!(keymap.bindings(InputAction::SimulateDeviceLoss).is_empty());
+        assert_eq!(keymap.conflicts(), Vec::new());
+    }
+
+    #[test]
+    fn the_device_loss_is_bound_like_any_action() {
+        let keymap = Keymap::from(BTreeMap::from([(
+            "simulate device loss".to_string(),
+            vec!["Ctrl+F9".to_string()],
+        )]));
+        assert_eq!(
+            keymap.action(KeyBinding::ctrl(KeyCode::F9)),
+            Some(InputAction::SimulateDeviceLoss)
+        );
+        assert_eq!(
+            keymap.action(KeyBinding::key(KeyCode::F9)),
+            Some(InputAction::Quickload)
+        );
+    }
+}
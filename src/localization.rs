@@ -0,0 +1,511 @@
+use std::{
+    collections::BTreeSet,
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError, RwLock},
+};
+
+use tracing::{info, warn};
+
+use crate::{
+    platform::{self, locale::LanguageIdentifier},
+    storage::DEFAULT_LANGUAGE,
+};
+
+pub use self::{
+    fluent::{Bundle, Resource},
+    pseudo::PSEUDO_LANGUAGE,
+    quantity::{Quantity, format_quantity},
+    report::report,
+};
+
+mod fluent;
+mod pseudo;
+mod quantity;
+mod report;
+
+/* in the data directory, the messages of a file there override the ones of the embedded file
+of its language and the file may add a language */
+pub const TRANSLATIONS_DIRECTORY_NAME: &str = "translations";
+pub const TRANSLATION_EXTENSION: &str = "ftl";
+/* the messages compiled in, english is complete and the last step of every fallback */
+const EMBEDDED_BUNDLES: [(&str, &str); 2] = [
+    (DEFAULT_LANGUAGE, include_str!("../translations/en.ftl")),
+    ("de", include_str!("../translations/de.ftl")),
+];
+/* the message every bundle names its language with, in the language itself */
+const LANGUAGE_NAME_KEY: &str = "language-name";
+
+/* the language chosen in the settings, english until one is */
+static ACTIVE: RwLock<Option<Localization>> = RwLock::new(None);
+/* the keys a lookup fell back for, each is logged once */
+static MISSING_KEYS: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/* an argument of a message, the variable of the name like { $count } is replaced by it. a
+number selects the plural variant */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Text(String),
+    Number(f64),
+}
+
+impl Display for Value {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Text(text) => formatter.write_str(text),
+            Value::Number(number) => write!(formatter, "{}", number),
+        }
+    }
+}
+
+impl From<&str> for Value {
+    fn from(text: &str) -> Self {
+        Value::Text(text.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(text: String) -> Self {
+        Value::Text(text)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(number: f64) -> Self {
+        Value::Number(number)
+    }
+}
+
+impl From<usize> for Value {
+    fn from(number: usize) -> Self {
+        Value::Number(number as f64)
+    }
+}
+
+impl From<u32> for Value {
+    fn from(number: u32) -> Self {
+        Value::Number(number as f64)
+    }
+}
+
+/* the bundles a lookup goes through, like pt-BR, then pt, then english. parsed once when the
+language is set */
+pub struct Localization {
+    language: String,
+    chain: Vec<(String, Bundle)>,
+}
+
+impl Localization {
+    /* the embedded bundles with the files of the directory over them. a language without any
+    bundle falls back to english */
+    pub fn load(language: &str, directory: Option<&Path>) -> Self {
+        let chain = self::fallback_chain(language)
+            .into_iter()
+            .filter_map(|language| {
+                let resource = self::load_bundle(&language, directory);
+                let bundle = Some(resource)
+                    .filter(|resource| !resource.is_empty())?
+                    .bundle(&language);
+                Some((language, bundle))
+            })
+            .collect();
+        Self {
+            language: language.to_string(),
+            chain,
+        }
+    }
+
+    /* the bundle with the message and its language */
+    pub fn lookup(&self, key: &str) -> Option<(&Bundle, &str)> {
+        self.chain
+            .iter()
+            .find(|(_, bundle)| bundle.has(key))
+            .map(|(language, bundle)| (bundle, language.as_str()))
+    }
+
+    /* the key itself when no bundle has it. a key like window-saves.hint is an attribute of
+    the message */
+    pub fn format(&self, key: &str, arguments: &[(&str, Value)]) -> String {
+        let Some((bundle, found_in)) = self.lookup(key) else {
+            self::report_missing(key, || {
+                warn!("There is no string for {}, the key is shown", key);
+            });
+            return key.to_string();
+        };
+        if found_in != self.language {
+            self::report_missing(key, || {
+                warn!(
+                    "{} is not translated to {}, falling back to {}",
+                    key, self.language, found_in
+                );
+            });
+        }
+        bundle
+            .format(key, arguments)
+            .unwrap_or_else(|| key.to_string())
+    }
+}
+
+/* the translations on disk */
+pub fn translations_directory() -> PathBuf {
+    platform::data_directory().join(TRANSLATIONS_DIRECTORY_NAME)
+}
+
+/* a language to pick in the settings */
+#[derive(Debug, Clone, PartialEq)]
+pub struct AvailableLanguage {
+    /* like de or pt-BR, the file stem */
+    pub code: String,
+    /* like Deutsch, the code when the bundle leaves it out */
+    pub name: String,
+}
+
+/* the embedded languages, the pseudo language and the ones with a file in the translations
+directory, sorted by code. each bundle is parsed for its name, so this is for when the list is opened */
+pub fn available_languages() -> Vec<AvailableLanguage> {
+    let directory = self::translations_directory();
+    self::language_codes(&directory)
+        .into_iter()
+        .map(|code| {
+            let name = self::load_bundle(&code, Some(&directory))
+                .bundle(&code)
+                .format(LANGUAGE_NAME_KEY, &[])
+                .unwrap_or_else(|| code.clone());
+            AvailableLanguage { code, name }
+        })
+        .collect()
+}
+
+/* the language of the settings, or of the system when they leave it empty. a system language
+without a bundle, not even one of the language without its region, is english */
+pub fn effective_language(setting: &str) -> String {
+    if !setting.is_empty() {
+        return setting.to_string();
+    }
+    let codes = self::language_codes(&self::translations_directory());
+    self::system_language(platform::system_locale().as_ref(), &codes)
+}
+
+/* the first language of the chain of the system one that has a bundle */
+fn system_language(system: Option<&LanguageIdentifier>, codes: &[String]) -> String {
+    let Some(system) = system else {
+        info!(
+            "The system language is unknown, the ui is in {}",
+            DEFAULT_LANGUAGE
+        );
+        return DEFAULT_LANGUAGE.to_string();
+    };
+    let speaks_default = system.language == DEFAULT_LANGUAGE;
+    let system = system.to_string();
+    let language = self::fallback_chain(&system)
+        .into_iter()
+        .find(|language| codes.contains(language))
+        .unwrap_or_else(|| DEFAULT_LANGUAGE.to_string());
+    match language == DEFAULT_LANGUAGE && !speaks_default {
+        true => info!(
+            "There is no translation for the system language {}, the ui is in {}",
+            system, language
+        ),
+        false => info!(
+            "The ui is in {} for the system language {}",
+            language, system
+        ),
+    }
+    language
+}
+
+/* the code of the language set last, english before one is */
+pub fn active_language() -> String {
+    ACTIVE
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_ref()
+        .map_or_else(
+            || DEFAULT_LANGUAGE.to_string(),
+            |localization| localization.language.clone(),
+        )
+}
+
+/* replaces the strings of the ui, the next lookup is in the language */
+pub fn set_language(language: &str) {
+    let localization = Localization::load(language, Some(&self::translations_directory()));
+    info!(
+        "Localized to {} through {}",
+        language,
+        localization
+            .chain
+            .iter()
+            .map(|(language, _)| language.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    *ACTIVE.write().unwrap_or_else(PoisonError::into_inner) = Some(localization);
+    MISSING_KEYS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clear();
+}
+
+pub fn tr(key: &str) -> String {
+    self::tr_args(key, &[])
+}
+
+pub fn tr_args(key: &str, arguments: &[(&str, Value)]) -> String {
+    if let Some(localization) = ACTIVE
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_ref()
+    {
+        return localization.format(key, arguments);
+    }
+    ACTIVE
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .get_or_insert_with(|| Localization::load(DEFAULT_LANGUAGE, None))
+        .format(key, arguments)
+}
+
+/* the label of a window or popup with the constant id after ###, imgui keeps its position and
+size across languages */
+pub fn title(key: &str, id: &str) -> String {
+    format!("{}###{}", self::tr(key), id)
+}
+
+/* the language, then the language without its region, then english */
+pub fn fallback_chain(language: &str) -> Vec<String> {
+    let mut chain = vec![language.to_string()];
+    if let Some((base, _)) = language.split_once(['-', '_']) {
+        chain.push(base.to_string());
+    }
+    chain.push(DEFAULT_LANGUAGE.to_string());
+    chain.dedup();
+    chain
+}
+
+/* a message of the language, through the active bundles when it is the active language and
+loaded for the call otherwise */
+fn tr_in(language: &str, key: &str, arguments: &[(&str, Value)]) -> String {
+    if let Some(localization) = ACTIVE
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .as_ref()
+        .filter(|localization| localization.language == language)
+    {
+        return localization.format(key, arguments);
+    }
+    Localization::load(language, Some(&self::translations_directory())).format(key, arguments)
+}
+
+/* of the embedded bundles and the files of the directory, sorted */
+fn language_codes(directory: &Path) -> Vec<String> {
+    let mut codes: Vec<String> = EMBEDDED_BUNDLES
+        .iter()
+        .map(|(language, _)| language.to_string())
+        .chain([PSEUDO_LANGUAGE.to_string()])
+        .collect();
+    match std::fs::read_dir(directory) {
+        Ok(entries) => codes.extend(
+            entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| {
+                    path.is_file()
+                        && path
+                            .extension()
+                            .is_some_and(|extension| extension == TRANSLATION_EXTENSION)
+                })
+                .filter_map(|path| Some(path.file_stem()?.to_string_lossy().into_owned())),
+        ),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+        Err(error) => warn!("Failed to list {}: {}", directory.display(), error),
+    }
+    codes.sort();
+    codes.dedup();
+    codes
+}
+
+/* a file that does not parse is taken without its broken entries, each is warned about */
+fn load_bundle(language: &str, directory: Option<&Path>) -> Resource {
+    let (bundle, problems) = self::read_bundle(language, directory);
+    for problem in problems {
+        warn!("{}", problem);
+    }
+    bundle
+}
+
+/* the bundle and what is wrong with its files. the pseudo language is made from english */
+fn read_bundle(language: &str, directory: Option<&Path>) -> (Resource, Vec<String>) {
+    if language == PSEUDO_LANGUAGE {
+        let (english, problems) = self::read_bundle(DEFAULT_LANGUAGE, directory);
+        return (english.pseudolocalized(), problems);
+    }
+    let mut problems = Vec::new();
+    let mut bundle = EMBEDDED_BUNDLES
+        .iter()
+        .find(|(embedded, _)| *embedded == language)
+        .map(|(_, text)| {
+            self::parse_bundle(
+                text,
+                &format!("the embedded {}.ftl", language),
+                &mut problems,
+            )
+        })
+        .unwrap_or_default();
+    let Some(path) = directory
+        .map(|directory| directory.join(format!("{}.{}", language, TRANSLATION_EXTENSION)))
+    else {
+        return (bundle, problems);
+    };
+    match std::fs::read_to_string(&path) {
+        Ok(text) => bundle.merge(self::parse_bundle(
+            &text,
+            &path.display().to_string(),
+            &mut problems,
+        )),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+        Err(error) => problems.push(format!("Failed to read {}: {}", path.display(), error)),
+    }
+    (bundle, problems)
+}
+
+fn parse_bundle(text: &str, source: &str, problems: &mut Vec<String>) -> Resource {
+    let (bundle, errors) = Resource::parse(text);
+    problems.extend(errors.into_iter().map(|error| {
+        format!(
+            "{} line {}: {}, the entry is left out",
+            source, error.line, error.message
+        )
+    }));
+    bundle
+}
+
+/* runs the log the first time the key is missing */
+fn report_missing(key: &str, log: impl FnOnce()) {
+    if MISSING_KEYS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(key.to_string())
+    {
+        log();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* a language with a region and a bundle of its own next to the one of the language */
+    fn directory() -> tempfile::TempDir {
+        let directory = tempfile::tempdir().unwrap();
+        std::fs::write(
+            directory.path().join("pt-BR.ftl"),
+            "greeting = Oi\nimported = Importado { $count } de { $source }\n",
+        )
+        .unwrap();
+        std::fs::write(
+            directory.path().join("pt.ftl"),
+            "greeting = Olá\nfarewell = Adeus\n",
+        )
+        .unwrap();
+        std::fs::write(directory.path().join("de.ftl"), "menu-file = Akte\n").unwrap();
+        directory
+    }
+
+    #[test]
+    fn the_chain_drops_the_region_then_ends_in_english() {
+        assert_eq!(fallback_chain("pt-BR"), ["pt-BR", "pt", "en"]);
+        assert_eq!(fallback_chain("de_AT"), ["de_AT", "de", "en"]);
+        assert_eq!(fallback_chain("de"), ["de", "en"]);
+        assert_eq!(fallback_chain("en"), ["en"]);
+    }
+
+    #[test]
+    fn a_key_is_looked_up_through_the_chain() {
+        let directory = self::directory();
+        let localization = Localization::load("pt-BR", Some(directory.path()));
+        let found_in = |key: &str| localization.lookup(key).map(|(_, language)| language);
+        assert_eq!(found_in("greeting"), Some("pt-BR"));
+        assert_eq!(found_in("farewell"), Some("pt"));
+        assert_eq!(found_in("menu-file"), Some("en"));
+        assert_eq!(found_in("no-such-key"), None);
+        assert_eq!(localization.format("greeting", &[]), "Oi");
+        assert_eq!(localization.format("farewell", &[]), "Adeus");
+        assert_eq!(localization.format("menu-file", &[]), "File");
+        assert_eq!(localization.format("no-such-key", &[]), "no-such-key");
+        /* without a bundle of any step english is left */
+        let unknown = Localization::load("xx-YY", Some(directory.path()));
+        assert_eq!(unknown.format("menu-file", &[]), "File");
+    }
+
+    #[test]
+    fn a_file_overrides_single_messages_of_the_embedded_bundle() {
+        let directory = self::directory();
+        let german = Localization::load("de", Some(directory.path()));
+        assert_eq!(german.format("menu-file", &[]), "Akte");
+        assert_eq!(german.format(LANGUAGE_NAME_KEY, &[]), "Deutsch");
+        assert_eq!(
+            Localization::load("de", None).format("menu-file", &[]),
+            "Datei"
+        );
+    }
+
+    #[test]
+    fn arguments_are_put_in_by_name() {
+        let directory = self::directory();
+        let localization = Localization::load("pt-BR", Some(directory.path()));
+        assert_eq!(
+            localization.format(
+                "imported",
+                &[
+                    ("source", Value::from("belt.csv")),
+                    ("count", Value::from(3u32))
+                ]
+            ),
+            "Importado 3 de belt.csv"
+        );
+        let english = Localization::load(DEFAULT_LANGUAGE, None);
+        assert_eq!(
+            english.format("toast-saved-slot", &[("name", Value::from("Quicksave 2"))]),
+            "Saved Quicksave 2"
+        );
+        /* a number picks the plural variant */
+        let imported = |count: usize| {
+            english.format(
+                "toast-bodies-imported",
+                &[
+                    ("count", Value::from(count)),
+                    ("source", Value::from("belt.csv")),
+                ],
+            )
+        };
+        assert_eq!(imported(0), "Imported no bodies from belt.csv");
+        assert_eq!(imported(1), "Imported one body from belt.csv");
+        assert_eq!(imported(12), "Imported 12 bodies from belt.csv");
+    }
+
+    #[test]
+    fn a_missing_key_is_logged_once() {
+        let mut logged = 0;
+        for _ in 0..3 {
+            report_missing("test-missing-once", || logged += 1);
+        }
+        assert_eq!(logged, 1);
+    }
+
+    #[test]
+    fn the_system_language_falls_back_to_one_with_a_bundle() {
+        let codes: Vec<String> = ["de", "en", "fr", "pt-BR"]
+            .iter()
+            .map(|code| code.to_string())
+            .collect();
+        let language = |locale: &str| {
+            self::system_language(LanguageIdentifier::parse(locale).as_ref(), &codes)
+        };
+        assert_eq!(language("de_AT.UTF-8"), "de");
+        assert_eq!(language("pt_BR.UTF-8"), "pt-BR");
+        assert_eq!(language("fr-CA"), "fr");
+        assert_eq!(language("xx_YY.UTF-8"), "en");
+        assert_eq!(language("pt_PT.UTF-8"), "en");
+        assert_eq!(language("C"), "en");
+    }
+}
@@ -0,0 +1,274 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+};
+
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue, concurrent::FluentBundle};
+use fluent_syntax::ast;
+use unic_langid::LanguageIdentifier;
+
+use crate::{
+    localization::{
+        Value,
+        pseudo::{self, PSEUDO_CLOSE, PSEUDO_OPEN},
+    },
+    storage::DEFAULT_LANGUAGE,
+};
+
+/* the parsed files of a language, a later one replaces the messages and terms of the same id
+of the earlier ones */
+#[derive(Debug, Clone, Default)]
+pub struct Resource {
+    files: Vec<Arc<FluentResource>>,
+    /* the text of the messages is made longer and odd looking when formatted */
+    pseudo: bool,
+}
+
+/* the entry starting on the line is left out, the ones after it are still read */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+/* the messages of a resource resolved for a language, built once when the language is set. the
+plural categories are the ones of the language */
+pub struct Bundle {
+    bundle: FluentBundle<Arc<FluentResource>>,
+    pseudo: bool,
+}
+
+impl Resource {
+    pub fn parse(source: &str) -> (Self, Vec<ParseError>) {
+        let source = source.strip_prefix('\u{feff}').unwrap_or(source);
+        let (file, errors) = match FluentResource::try_new(source.to_string()) {
+            Ok(file) => (file, Vec::new()),
+            Err((file, errors)) => (file, errors),
+        };
+        let errors = errors
+            .into_iter()
+            .map(|error| {
+                let start = error
+                    .slice
+                    .as_ref()
+                    .map_or(error.pos.start, |slice| slice.start);
+                ParseError {
+                    line: source[..start.min(source.len())].matches('\n').count() + 1,
+                    message: error.kind.to_string(),
+                }
+            })
+            .collect();
+        let resource = Self {
+            files: vec![Arc::new(file)],
+            pseudo: false,
+        };
+        (resource, errors)
+    }
+
+    /* the messages of the other resource replace the ones of the same id */
+    pub fn merge(&mut self, other: Resource) {
+        self.files.extend(other.files);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages().is_empty()
+    }
+
+    /* the messages with their attributes like window-saves.hint and the terms like -brand,
+    sorted */
+    pub fn keys(&self) -> Vec<String> {
+        let mut keys = BTreeSet::new();
+        for (id, message) in self.messages() {
+            if message.value.is_some() {
+                keys.insert(id.to_string());
+            }
+            for attribute in &message.attributes {
+                keys.insert(format!("{}.{}", id, attribute.id.name));
+            }
+        }
+        for file in &self.files {
+            for entry in file.entries() {
+                if let ast::Entry::Term(term) = entry {
+                    keys.insert(format!("-{}", term.id.name));
+                }
+            }
+        }
+        keys.into_iter().collect()
+    }
+
+    /* the pseudo language of the messages. only the text is changed, the placeables and variant
+    keys stay what they are, and each message and attribute gets brackets */
+    pub fn pseudolocalized(&self) -> Resource {
+        Resource {
+            pseudo: true,
+            ..self.clone()
+        }
+    }
+
+    /* an unknown language code resolves its plurals like english */
+    pub fn bundle(&self, language: &str) -> Bundle {
+        let language: LanguageIdentifier = language
+            .parse()
+            .or_else(|_| DEFAULT_LANGUAGE.parse())
+            .unwrap_or_default();
+        let mut bundle = FluentBundle::new_concurrent(vec![language]);
+        /* imgui draws the unicode isolation marks around placeables as boxes */
+        bundle.set_use_isolating(false);
+        if self.pseudo {
+            bundle.set_transform(Some(self::pseudo_text));
+        }
+        for file in &self.files {
+            bundle.add_resource_overriding(file.clone());
+        }
+        Bundle {
+            bundle,
+            pseudo: self.pseudo,
+        }
+    }
+
+    /* the last definition of each message */
+    fn messages(&self) -> HashMap<&str, &ast::Message<&str>> {
+        self.files
+            .iter()
+            .flat_map(|file| file.entries())
+            .filter_map(|entry| match entry {
+                ast::Entry::Message(message) => Some((message.id.name, message)),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl Bundle {
+    /* a message like menu-file, or an attribute of one like window-saves.hint */
+    pub fn has(&self, key: &str) -> bool {
+        self.pattern(key).is_some()
+    }
+
+    /* none without the message. like fluent, what cannot be resolved is shown in braces */
+    pub fn format(&self, key: &str, arguments: &[(&str, Value)]) -> Option<String> {
+        let pattern = self.pattern(key)?;
+        let mut fluent_arguments = FluentArgs::with_capacity(arguments.len());
+        for (name, value) in arguments {
+            let value = match value {
+                Value::Text(text) => FluentValue::from(text.as_str()),
+                Value::Number(number) => FluentValue::from(*number),
+            };
+            fluent_arguments.set(*name, value);
+        }
+        let mut errors = Vec::new();
+        let text = self
+            .bundle
+            .format_pattern(pattern, Some(&fluent_arguments), &mut errors);
+        Some(match self.pseudo {
+            true => format!("{}{}{}", PSEUDO_OPEN, text, PSEUDO_CLOSE),
+            false => text.into_owned(),
+        })
+    }
+
+    fn pattern(&self, key: &str) -> Option<&ast::Pattern<&str>> {
+        let (id, attribute) = self::split_key(key);
+        let message = self.bundle.get_message(id)?;
+        match attribute {
+            Some(attribute) => Some(message.get_attribute(attribute)?.value()),
+            None => message.value(),
+        }
+    }
+}
+
+/* window-saves.hint to the message and its attribute */
+fn split_key(key: &str) -> (&str, Option<&str>) {
+    match key.split_once('.') {
+        Some((id, attribute)) => (id, Some(attribute)),
+        None => (key, None),
+    }
+}
+
+fn pseudo_text(text: &str) -> Cow<'_, str> {
+    Cow::Owned(pseudo::pseudolocalize(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> Resource {
+        let (resource, errors) = Resource::parse(include_str!("../../tests/fixtures/plural.ftl"));
+        assert!(errors.is_empty(), "{:?}", errors);
+        resource
+    }
+
+    #[test]
+    fn a_number_picks_the_plural_category_of_the_language() {
+        let polish = self::fixture().bundle("pl");
+        let imported = |count: f64| {
+            polish
+                .format("bodies-imported", &[("count", Value::from(count))])
+                .unwrap()
+        };
+        assert_eq!(imported(1.), "Zaimportowano 1 ciało");
+        assert_eq!(imported(3.), "Zaimportowano 3 ciała");
+        assert_eq!(imported(5.), "Zaimportowano 5 ciał");
+        assert_eq!(imported(22.), "Zaimportowano 22 ciała");
+        assert_eq!(imported(12.), "Zaimportowano 12 ciał");
+        assert_eq!(imported(1.5), "Zaimportowano 1.5 ciała");
+        /* english has no few or many, everything but one is other */
+        let english = self::fixture().bundle("en");
+        assert_eq!(
+            english
+                .format("bodies-imported", &[("count", Value::from(3u32))])
+                .unwrap(),
+            "Zaimportowano 3 ciała"
+        );
+    }
+
+    #[test]
+    fn an_attribute_is_looked_up_after_a_dot() {
+        let resource = self::fixture();
+        let bundle = resource.bundle("pl");
+        assert_eq!(
+            bundle.format("window-saves", &[]).as_deref(),
+            Some("Zapisy SpaceS")
+        );
+        assert_eq!(
+            bundle
+                .format("window-saves.hint", &[("slot", Value::from("F5"))])
+                .as_deref(),
+            Some("Szybki zapis F5 nadpisuje najstarszy")
+        );
+        assert!(bundle.has("window-saves.hint"));
+        assert!(!bundle.has("window-saves.title"));
+        assert!(!bundle.has("-brand"));
+        assert_eq!(
+            resource.keys(),
+            [
+                "-brand",
+                "bodies-imported",
+                "window-saves",
+                "window-saves.hint"
+            ]
+        );
+    }
+
+    #[test]
+    fn a_broken_entry_is_left_out_with_its_line() {
+        let (resource, errors) = Resource::parse("first = One\n\nbroken = { $count\nafter = Two\n");
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert_eq!(errors[0].line, 3);
+        let bundle = resource.bundle("en");
+        assert_eq!(bundle.format("first", &[]).as_deref(), Some("One"));
+        assert!(!bundle.has("broken"));
+    }
+
+    #[test]
+    fn a_later_file_replaces_whole_messages() {
+        let mut resource = self::fixture();
+        resource.merge(Resource::parse("window-saves = Saves\n").0);
+        let bundle = resource.bundle("pl");
+        assert_eq!(bundle.format("window-saves", &[]).as_deref(), Some("Saves"));
+        assert!(!bundle.has("window-saves.hint"));
+        assert!(!resource.keys().contains(&"window-saves.hint".to_string()));
+        assert!(bundle.has("bodies-imported"));
+    }
+}
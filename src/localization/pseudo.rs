@@ -0,0 +1,105 @@
+/* a language of the english messages made longer and odd looking, a string that shows plainly
+is not looked up and one cut off by a bracket missing would clip a longer translation */
+pub const PSEUDO_LANGUAGE: &str = "en-XA";
+/* around every message and attribute, the end of a clipped string shows where the bracket
+is missing */
+pub const PSEUDO_OPEN: &str = "[";
+pub const PSEUDO_CLOSE: &str = "]";
+/* latin-1 only, the embedded font has no other accented letters */
+const ACCENTS: [(char, char); 20] = [
+    ('a', 'á'),
+    ('c', 'ç'),
+    ('e', 'é'),
+    ('i', 'í'),
+    ('n', 'ñ'),
+    ('o', 'ö'),
+    ('u', 'ü'),
+    ('y', 'ý'),
+    ('A', 'Å'),
+    ('C', 'Ç'),
+    ('E', 'É'),
+    ('I', 'Î'),
+    ('N', 'Ñ'),
+    ('O', 'Ø'),
+    ('U', 'Û'),
+    ('Y', 'Ý'),
+    ('d', 'ð'),
+    ('D', 'Ð'),
+    ('t', 'þ'),
+    ('T', 'Þ'),
+];
+
+/* a text element of a pattern, fluent hands over the text between the placeables only so the
+variables and numbers come out as they are. every third character that is a letter is doubled, about the 30 % a german or french translation is longer */
+pub fn pseudolocalize(text: &str) -> String {
+    let mut pseudo = String::with_capacity(text.len() * 2);
+    for (index, character) in text.chars().enumerate() {
+        let accented = ACCENTS
+            .iter()
+            .find(|(plain, _)| *plain == character)
+            .map_or(character, |(_, accented)| *accented);
+        pseudo.push(accented);
+        if index % 3 == 0 && character.is_ascii_alphabetic() {
+            pseudo.push(accented);
+        }
+    }
+    pseudo
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::localization::{Resource, Value};
+
+    fn bundle() -> crate::localization::Bundle {
+        let source = "-brand = SpaceS\n\
+            about = About { -brand }\n\
+            saved = Saved to { $path }\n\
+            imported =\n    { $count ->\n        [one] Imported one body\n       \
+            *[other] Imported { $count } bodies\n    }\n\
+            window = Saves\n    .hint = Quick save\n";
+        let (resource, errors) = Resource::parse(source);
+        assert!(errors.is_empty(), "{:?}", errors);
+        resource.pseudolocalized().bundle(PSEUDO_LANGUAGE)
+    }
+
+    #[test]
+    fn letters_are_accented_and_every_third_doubled() {
+        assert_eq!(pseudolocalize("Edit"), "ÉÉðíþþ");
+        assert_eq!(pseudolocalize("a b"), "áá b");
+        assert_eq!(pseudolocalize("42 km/s"), "42 kkm/ss");
+        assert_eq!(pseudolocalize(""), "");
+    }
+
+    #[test]
+    fn the_placeables_are_kept() {
+        let bundle = self::bundle();
+        let format =
+            |key: &str, arguments: &[(&str, Value)]| bundle.format(key, arguments).unwrap();
+        assert_eq!(
+            format("saved", &[("path", Value::from("/home/ada/save.yaml"))]),
+            format!("[{}/home/ada/save.yaml]", pseudolocalize("Saved to "))
+        );
+        assert_eq!(
+            format("about", &[]),
+            format!("[{}{}]", pseudolocalize("About "), pseudolocalize("SpaceS"))
+        );
+        /* the variant keys are not text, the plural is still picked */
+        assert_eq!(
+            format("imported", &[("count", Value::from(1u32))]),
+            format!("[{}]", pseudolocalize("Imported one body"))
+        );
+        assert_eq!(
+            format("imported", &[("count", Value::from(12u32))]),
+            format!(
+                "[{}12{}]",
+                pseudolocalize("Imported "),
+                pseudolocalize(" bodies")
+            )
+        );
+        assert_eq!(
+            format("window.hint", &[]),
+            format!("[{}]", pseudolocalize("Quick save"))
+        );
+    }
+}
@@ -0,0 +1,413 @@
+use std::fmt::Display;
+
+use crate::localization::Value;
+
+/* kilograms */
+const EARTH_MASS: f64 = 5.9722e24;
+const SOLAR_MASS: f64 = 1.98847e30;
+/* meters */
+const ASTRONOMICAL_UNIT: f64 = 1.495978707e11;
+const LIGHT_YEAR: f64 = 9.4607304725808e15;
+/* seconds, the julian year of astronomy */
+const YEAR: f64 = 365.25 * 86400.;
+/* outside of these magnitudes of the scaled value the number is written with an exponent */
+const SCIENTIFIC_ABOVE: f64 = 1e7;
+const SCIENTIFIC_BELOW: f64 = 1e-3;
+const SIGNIFICANT_DIGITS: i32 = 3;
+/* what a value that is not a number shows */
+const UNDEFINED: &str = "—";
+
+/* the si quantities of the simulation, each value is given in its base unit */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Quantity {
+    /* kilograms */
+    Mass,
+    /* meters */
+    Length,
+    /* meters per second */
+    Speed,
+    /* seconds */
+    Time,
+}
+
+/* a unit the value is shown in from the magnitude on, in base units, and its size */
+struct Scale {
+    from: f64,
+    size: f64,
+    unit_key: &'static str,
+}
+
+impl Quantity {
+    /* smallest first, the first scale is taken below it too */
+    fn scales(self) -> &'static [Scale] {
+        match self {
+            Quantity::Mass => &[
+                Scale {
+                    from: 0.,
+                    size: 1e-3,
+                    unit_key: "unit-gram",
+                },
+                Scale {
+                    from: 1.,
+                    size: 1.,
+                    unit_key: "unit-kilogram",
+                },
+                Scale {
+                    from: 1e3,
+                    size: 1e3,
+                    unit_key: "unit-tonne",
+                },
+                Scale {
+                    from: 1e-3 * EARTH_MASS,
+                    size: EARTH_MASS,
+                    unit_key: "unit-earth-mass",
+                },
+                Scale {
+                    from: 0.1 * SOLAR_MASS,
+                    size: SOLAR_MASS,
+                    unit_key: "unit-solar-mass",
+                },
+            ],
+            Quantity::Length => &[
+                Scale {
+                    from: 0.,
+                    size: 1e-3,
+                    unit_key: "unit-millimeter",
+                },
+                Scale {
+                    from: 1.,
+                    size: 1.,
+                    unit_key: "unit-meter",
+                },
+                Scale {
+                    from: 1e3,
+                    size: 1e3,
+                    unit_key: "unit-kilometer",
+                },
+                Scale {
+                    from: 0.01 * ASTRONOMICAL_UNIT,
+                    size: ASTRONOMICAL_UNIT,
+                    unit_key: "unit-astronomical-unit",
+                },
+                Scale {
+                    from: 0.1 * LIGHT_YEAR,
+                    size: LIGHT_YEAR,
+                    unit_key: "unit-light-year",
+                },
+            ],
+            Quantity::Speed => &[
+                Scale {
+                    from: 0.,
+                    size: 1.,
+                    unit_key: "unit-meter-per-second",
+                },
+                Scale {
+                    from: 1e3,
+                    size: 1e3,
+                    unit_key: "unit-kilometer-per-second",
+                },
+            ],
+            Quantity::Time => &[
+                Scale {
+                    from: 0.,
+                    size: 1e-3,
+                    unit_key: "unit-millisecond",
+                },
+                Scale {
+                    from: 1.,
+                    size: 1.,
+                    unit_key: "unit-second",
+                },
+                Scale {
+                    from: 60.,
+                    size: 60.,
+                    unit_key: "unit-minute",
+                },
+                Scale {
+                    from: 3600.,
+                    size: 3600.,
+                    unit_key: "unit-hour",
+                },
+                Scale {
+                    from: 86400.,
+                    size: 86400.,
+                    unit_key: "unit-day",
+                },
+                Scale {
+                    from: YEAR,
+                    size: YEAR,
+                    unit_key: "unit-year",
+                },
+            ],
+        }
+    }
+}
+
+/* the number and the unit apart, for a table that aligns them */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormattedQuantity {
+    pub number: String,
+    /* empty for a value that is not a number */
+    pub unit: String,
+}
+
+impl Display for FormattedQuantity {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.unit.is_empty() {
+            true => formatter.write_str(&self.number),
+            false => write!(formatter, "{} {}", self.number, self.unit),
+        }
+    }
+}
+
+/* the separators of the language, a decimal one and one grouping thousands */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Separators {
+    decimal: char,
+    grouping: char,
+}
+
+/* in the unit that keeps the number short, like 1.5 Earth masses or 384,400 km, with three
+significant digits and the separators of the language. the unit is a message of its bundle */
+pub fn format_quantity(value: f64, quantity: Quantity, language: &str) -> FormattedQuantity {
+    if !value.is_finite() {
+        return FormattedQuantity {
+            number: UNDEFINED.to_string(),
+            unit: String::new(),
+        };
+    }
+    let scales = quantity.scales();
+    let scale = scales
+        .iter()
+        .rev()
+        .find(|scale| value.abs() >= scale.from)
+        .unwrap_or(&scales[0]);
+    /* zero and what is too small even for the smallest unit are shown in the base unit */
+    let scale = match value.abs() / scale.size < SCIENTIFIC_BELOW {
+        true => scales
+            .iter()
+            .find(|scale| scale.size == 1.)
+            .unwrap_or(scale),
+        false => scale,
+    };
+    let digits = self::round_digits(value / scale.size);
+    /* the plural of the unit is the one of the number shown, 1.0004 is one Earth mass */
+    let shown = digits.parse::<f64>().unwrap_or_default();
+    FormattedQuantity {
+        number: self::localize_digits(&digits, self::separators(language)),
+        unit: super::tr_in(language, scale.unit_key, &[("value", Value::Number(shown))]),
+    }
+}
+
+/* the significant digits like -1234.5 or 1.23e18, without trailing zeros of the fraction */
+fn round_digits(value: f64) -> String {
+    let magnitude = value.abs();
+    if magnitude == 0. {
+        return "0".to_string();
+    }
+    let sign = if value < 0. { "-" } else { "" };
+    if !(SCIENTIFIC_BELOW..SCIENTIFIC_ABOVE).contains(&magnitude) {
+        let mut exponent = magnitude.log10().floor() as i32;
+        let decimals = (SIGNIFICANT_DIGITS - 1) as usize;
+        let mut mantissa = format!("{:.*}", decimals, magnitude / 10f64.powi(exponent));
+        /* 9.996e4 rounds up to the next power of ten */
+        if mantissa.starts_with("10") {
+            exponent += 1;
+            mantissa = format!("{:.*}", decimals, magnitude / 10f64.powi(exponent));
+        }
+        return format!("{}{}e{}", sign, self::trim_fraction(&mantissa), exponent);
+    }
+    let decimals = (SIGNIFICANT_DIGITS - 1 - magnitude.log10().floor() as i32).clamp(0, 3);
+    let digits = format!("{:.*}", decimals as usize, magnitude);
+    format!("{}{}", sign, self::trim_fraction(&digits))
+}
+
+fn trim_fraction(digits: &str) -> &str {
+    match digits.contains('.') {
+        true => digits.trim_end_matches('0').trim_end_matches('.'),
+        false => digits,
+    }
+}
+
+/* the rounded digits with the separators of the language */
+fn localize_digits(digits: &str, separators: Separators) -> String {
+    let (sign, digits) = match digits.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", digits),
+    };
+    let (digits, exponent) = match digits.split_once('e') {
+        Some((digits, exponent)) => (digits, Some(exponent)),
+        None => (digits, None),
+    };
+    let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let mut localized = String::with_capacity(digits.len() + integer.len() / 3 + 6);
+    localized += sign;
+    for (index, digit) in integer.chars().enumerate() {
+        if index > 0 && (integer.len() - index) % 3 == 0 {
+            localized.push(separators.grouping);
+        }
+        localized.push(digit);
+    }
+    if !fraction.is_empty() {
+        localized.push(separators.decimal);
+        localized += fraction;
+    }
+    if let Some(exponent) = exponent {
+        localized.push('e');
+        localized += exponent;
+    }
+    localized
+}
+
+/* by the language without its region, the ones not listed here write numbers like english */
+fn separators(language: &str) -> Separators {
+    let base = language
+        .split(['-', '_'])
+        .next()
+        .unwrap_or(language)
+        .to_ascii_lowercase();
+    let (decimal, grouping) = match base.as_str() {
+        "de" | "es" | "it" | "nl" | "pt" | "da" | "id" | "tr" | "el" => (',', '.'),
+        /* a no-break space, the narrow one of cldr is missing from most fonts */
+        "fr" | "ru" | "uk" | "pl" | "cs" | "sk" | "sv" | "nb" | "fi" => (',', '\u{a0}'),
+        _ => ('.', ','),
+    };
+    Separators { decimal, grouping }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quantities_are_written_in_each_language() {
+        /* there is no french bundle, its units are the english ones */
+        let table: [(f64, Quantity, [&str; 3]); 13] = [
+            (
+                3.844e8,
+                Quantity::Length,
+                ["384,400 km", "384.400 km", "384\u{a0}400 km"],
+            ),
+            (
+                1.5 * EARTH_MASS,
+                Quantity::Mass,
+                ["1.5 Earth masses", "1,5 Erdmassen", "1,5 Earth masses"],
+            ),
+            (
+                1.0004 * EARTH_MASS,
+                Quantity::Mass,
+                ["1 Earth mass", "1 Erdmasse", "1 Earth mass"],
+            ),
+            (
+                SOLAR_MASS,
+                Quantity::Mass,
+                ["1 solar mass", "1 Sonnenmasse", "1 solar mass"],
+            ),
+            (
+                3_456_789.,
+                Quantity::Mass,
+                ["3,457 t", "3.457 t", "3\u{a0}457 t"],
+            ),
+            (
+                -1234.5,
+                Quantity::Speed,
+                ["-1.23 km/s", "-1,23 km/s", "-1,23 km/s"],
+            ),
+            (1_234_567., Quantity::Time, ["14.3 d", "14,3 d", "14,3 d"]),
+            (
+                2.5e7 * YEAR,
+                Quantity::Time,
+                ["2.5e7 yr", "2,5e7 a", "2,5e7 yr"],
+            ),
+            /* 9.996e7 rounds up to the next power of ten */
+            (
+                9.996e7 * YEAR,
+                Quantity::Time,
+                ["1e8 yr", "1e8 a", "1e8 yr"],
+            ),
+            (
+                1e20 * LIGHT_YEAR,
+                Quantity::Length,
+                ["1e20 ly", "1e20 Lj", "1e20 ly"],
+            ),
+            (0., Quantity::Length, ["0 m", "0 m", "0 m"]),
+            (1e-9, Quantity::Length, ["1e-9 m", "1e-9 m", "1e-9 m"]),
+            (f64::NAN, Quantity::Speed, ["—", "—", "—"]),
+        ];
+        for (value, quantity, expected) in table {
+            for (language, expected) in ["en", "de", "fr"].into_iter().zip(expected) {
+                assert_eq!(
+                    format_quantity(value, quantity, language).to_string(),
+                    expected,
+                    "{} {:?} in {}",
+                    value,
+                    quantity,
+                    language
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn the_number_and_the_unit_come_apart() {
+        let formatted = format_quantity(f64::INFINITY, Quantity::Mass, "de");
+        assert_eq!(formatted.unit, "");
+        let formatted = format_quantity(2.5e10, Quantity::Speed, "de");
+        assert_eq!(
+            (formatted.number.as_str(), formatted.unit.as_str()),
+            ("2,5e7", "km/s")
+        );
+    }
+
+    #[test]
+    fn the_separators_are_the_ones_of_the_language_without_its_region() {
+        let table = [
+            ("en", '.', ','),
+            ("en-GB", '.', ','),
+            ("de", ',', '.'),
+            ("de-AT", ',', '.'),
+            ("de_CH", ',', '.'),
+            ("fr", ',', '\u{a0}'),
+            ("fr-CA", ',', '\u{a0}'),
+            ("PT-br", ',', '.'),
+            ("ja", '.', ','),
+            ("", '.', ','),
+        ];
+        for (language, decimal, grouping) in table {
+            assert_eq!(
+                separators(language),
+                Separators { decimal, grouping },
+                "{}",
+                language
+            );
+        }
+    }
+
+    #[test]
+    fn digits_are_grouped_by_thousands() {
+        let table = [
+            ("0", "0", "0", "0"),
+            ("999", "999", "999", "999"),
+            ("1000", "1,000", "1.000", "1\u{a0}000"),
+            (
+                "-1234567.89",
+                "-1,234,567.89",
+                "-1.234.567,89",
+                "-1\u{a0}234\u{a0}567,89",
+            ),
+            ("0.123", "0.123", "0,123", "0,123"),
+            ("-1.5e-12", "-1.5e-12", "-1,5e-12", "-1,5e-12"),
+        ];
+        for (digits, english, german, french) in table {
+            for (language, expected) in [("en", english), ("de", german), ("fr", french)] {
+                assert_eq!(
+                    localize_digits(digits, separators(language)),
+                    expected,
+                    "{} in {}",
+                    digits,
+                    language
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::{collections::BTreeSet, fmt::Display, path::Path};
+
+use crate::{
+    localization::{Localization, PSEUDO_LANGUAGE},
+    storage::DEFAULT_LANGUAGE,
+};
+
+/* what a translation lacks against english and what it has that english no longer does, for
+--report-i18n */
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranslationReport {
+    pub language: String,
+    /* with the language the ui falls back to for it, which is what the warnings while running
+    would name one by one */
+    pub missing: Vec<(String, Option<String>)>,
+    /* removed or renamed in english since */
+    pub obsolete: Vec<String>,
+    /* the entries of the files that do not parse, they count as missing */
+    pub problems: Vec<String>,
+}
+
+/* of the bundle of the language itself, the embedded one with the file of the translations
+directory over it. a region like de-AT only lists what it translates differently, what it
+lacks falls back to de */
+pub fn report(language: &str) -> TranslationReport {
+    self::report_in(language, &super::translations_directory())
+}
+
+fn report_in(language: &str, directory: &Path) -> TranslationReport {
+    let (english, mut problems) = super::read_bundle(DEFAULT_LANGUAGE, Some(directory));
+    let (bundle, target_problems) = super::read_bundle(language, Some(directory));
+    if language != DEFAULT_LANGUAGE && language != PSEUDO_LANGUAGE {
+        problems.extend(target_problems);
+    }
+    let english_keys = english.keys();
+    let keys: BTreeSet<String> = bundle.keys().into_iter().collect();
+    let localization = Localization::load(language, Some(directory));
+    let missing = english_keys
+        .iter()
+        .filter(|key| !keys.contains(*key))
+        .map(|key| {
+            /* a term is only looked up in the bundle of the message using it */
+            let fallback = match key.starts_with('-') {
+                true => None,
+                false => localization
+                    .lookup(key)
+                    .map(|(_, found_in)| found_in.to_string()),
+            };
+            (key.clone(), fallback)
+        })
+        .collect();
+    let english_keys: BTreeSet<String> = english_keys.into_iter().collect();
+    let obsolete = keys.difference(&english_keys).cloned().collect();
+    TranslationReport {
+        language: language.to_string(),
+        missing,
+        obsolete,
+        problems,
+    }
+}
+
+impl Display for TranslationReport {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            formatter,
+            "Translation of {} against {}",
+            self.language, DEFAULT_LANGUAGE
+        )?;
+        writeln!(formatter, "Missing ({}):", self.missing.len())?;
+        for (key, fallback) in &self.missing {
+            match fallback {
+                Some(fallback) => writeln!(formatter, "  {} (falls back to {})", key, fallback)?,
+                None => writeln!(formatter, "  {} (has no fallback)", key)?,
+            }
+        }
+        writeln!(formatter, "Obsolete ({}):", self.obsolete.len())?;
+        for key in &self.obsolete {
+            writeln!(formatter, "  {}", key)?;
+        }
+        if !self.problems.is_empty() {
+            writeln!(formatter, "Problems ({}):", self.problems.len())?;
+            for problem in &self.problems {
+                writeln!(formatter, "  {}", problem)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /* french has no embedded bundle, so its file is all there is of it */
+    fn directory() -> tempfile::TempDir {
+        let directory = tempfile::tempdir().unwrap();
+        std::fs::write(
+            directory.path().join("fr.ftl"),
+            "menu-file = Fichier\nmenu-removed = Ancien\nbroken = { $count\nmenu-edit = Édition\n",
+        )
+        .unwrap();
+        std::fs::write(directory.path().join("fr-CA.ftl"), "menu-file = Fichier\n").unwrap();
+        directory
+    }
+
+    #[test]
+    fn a_key_english_no_longer_has_is_obsolete() {
+        let directory = self::directory();
+        let report = report_in("fr", directory.path());
+        assert_eq!(report.obsolete, ["menu-removed"]);
+        assert_eq!(report.problems.len(), 1, "{:?}", report.problems);
+        assert!(
+            report.problems[0].contains("line 3"),
+            "{}",
+            report.problems[0]
+        );
+        let text = report.to_string();
+        assert!(text.contains("Obsolete (1):\n  menu-removed\n"), "{}", text);
+    }
+
+    #[test]
+    fn a_key_the_translation_lacks_is_missing_with_its_fallback() {
+        let directory = self::directory();
+        let report = report_in("fr", directory.path());
+        let missing = |key: &str| {
+            report
+                .missing
+                .iter()
+                .find(|(missing, _)| missing == key)
+                .map(|(_, fallback)| fallback.clone())
+        };
+        assert_eq!(missing("menu-file"), None);
+        assert_eq!(missing("broken"), None);
+        assert_eq!(
+            missing("toast-bodies-imported"),
+            Some(Some("en".to_string()))
+        );
+        assert_eq!(missing("-brand"), Some(None));
+        /* a region falls back to its language before english */
+        let report = report_in("fr-CA", directory.path());
+        assert!(report.obsolete.is_empty());
+        assert!(!report.missing.iter().any(|(key, _)| key == "menu-file"));
+        assert!(
+            report
+                .missing
+                .contains(&("menu-edit".to_string(), Some("fr".to_string())))
+        );
+        assert!(
+            report
+                .missing
+                .contains(&("menu-help".to_string(), Some("en".to_string())))
+        );
+    }
+
+    #[test]
+    fn the_embedded_german_is_in_step_with_english() {
+        let directory = tempfile::tempdir().unwrap();
+        let report = report_in("de", directory.path());
+        assert!(report.obsolete.is_empty(), "{:?}", report.obsolete);
+        assert!(report.problems.is_empty(), "{:?}", report.problems);
+    }
+}
@@ -0,0 +1,153 @@
+use std::{
+    collections::VecDeque,
+    fmt::Write,
+    sync::{Arc, Mutex, MutexGuard, PoisonError, TryLockError},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use tracing::{Event, Level, Subscriber, field::Field};
+use tracing_subscriber::{Layer, layer::Context};
+
+/* records kept for the log window, the oldest go first */
+pub const LOG_CAPACITY: usize = 2000;
+/* longer messages are cut, so a single record never holds much */
+pub const MAXIMUM_MESSAGE_LENGTH: usize = 1000;
+const SECONDS_PER_DAY: u64 = 86_400;
+
+#[derive(Debug, Clone)]
+pub struct LogRecord {
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+    /* utc time of day, like 14:03:07.250 */
+    pub timestamp: String,
+}
+
+/* a bounded ring shared between the tracing layer and the ui, cheap to clone */
+#[derive(Debug, Clone, Default)]
+pub struct LogBuffer {
+    records: Arc<Mutex<VecDeque<LogRecord>>>,
+}
+
+impl LogBuffer {
+    pub fn push(&self, record: LogRecord) {
+        let mut records = self.records();
+        if records.len() == LOG_CAPACITY {
+            records.pop_front();
+        }
+        records.push_back(record);
+    }
+
+    /* a panic while the lock was held must not take the logging down with it */
+    pub fn records(&self) -> MutexGuard<'_, VecDeque<LogRecord>> {
+        self.records.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /* none while another thread holds the lock, for the panic hook which must not block */
+    pub fn try_records(&self) -> Option<MutexGuard<'_, VecDeque<LogRecord>>> {
+        match self.records.try_lock() {
+            Ok(records) => Some(records),
+            Err(TryLockError::Poisoned(poisoned)) => Some(poisoned.into_inner()),
+            Err(TryLockError::WouldBlock) => None,
+        }
+    }
+
+    pub fn clear(&self) {
+        self.records().clear();
+    }
+}
+
+/* feeds the log window next to the fmt layer on stdout */
+pub struct LogLayer {
+    buffer: LogBuffer,
+}
+
+impl LogLayer {
+    pub fn new(buffer: LogBuffer) -> Self {
+        Self { buffer }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for LogLayer {
+    fn on_event(&self, event: &Event<'_>, _context: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let mut message = visitor.message;
+        if !visitor.fields.is_empty() {
+            let _ = write!(message, " {}", visitor.fields.trim_start());
+        }
+        /* the adapter listing is colored for the terminal */
+        if message.contains('\x1b') {
+            message = self::strip_ansi_escapes(&message);
+        }
+        if let Some((cut, _)) = message.char_indices().nth(MAXIMUM_MESSAGE_LENGTH) {
+            message.truncate(cut);
+            message.push_str("...");
+        }
+        self.buffer.push(LogRecord {
+            level: *event.metadata().level(),
+            target: event.metadata().target().to_string(),
+            message,
+            timestamp: self::format_time_of_day(SystemTime::now()),
+        });
+    }
+}
+
+/* the message field on its own, the other fields as key=value after it */
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl tracing::field::Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "message" => self.message.push_str(value),
+            name => {
+                let _ = write!(self.fields, " {}={}", name, value);
+            }
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        match field.name() {
+            "message" => {
+                let _ = write!(self.message, "{:?}", value);
+            }
+            name => {
+                let _ = write!(self.fields, " {}={:?}", name, value);
+            }
+        }
+    }
+}
+
+/* drops the select graphic rendition sequences like \x1b[1;31m */
+pub fn strip_ansi_escapes(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut characters = text.chars();
+    while let Some(character) = characters.next() {
+        if character != '\x1b' {
+            stripped.push(character);
+            continue;
+        }
+        if characters.next() == Some('[') {
+            characters
+                .by_ref()
+                .find(|character| character.is_ascii_alphabetic());
+        }
+    }
+    stripped
+}
+
+pub fn format_time_of_day(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs() % SECONDS_PER_DAY;
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60,
+        since_epoch.subsec_millis()
+    )
+}
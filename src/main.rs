@@ -1,9 +1,15 @@
 use anyhow::Result;
 
 mod application;
+mod cli;
+mod crash;
 mod graphics;
+mod headless;
+mod input;
 mod localization;
+mod logging;
 mod platform;
+mod scenario;
 mod simulation;
 mod storage;
 
@@ -0,0 +1,219 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use anyhow::{Context, Result};
+
+pub mod file_dialog;
+pub mod gamepad;
+pub mod locale;
+
+pub use self::{
+    file_dialog::{FileFilter, pick_open_file, pick_save_file},
+    locale::system_locale,
+};
+
+pub const APPLICATION_DIRECTORY_NAME: &str = "SpaceS";
+
+/* per-user data directory following the platform convention, falls back to the working directory */
+pub fn data_directory() -> PathBuf {
+    let base = if cfg!(target_os = "windows") {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        std::env::var_os("HOME").map(|home| {
+            PathBuf::from(home)
+                .join("Library")
+                .join("Application Support")
+        })
+    } else {
+        std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME")
+                    .map(|home| PathBuf::from(home).join(".local").join("share"))
+            })
+    };
+    base.unwrap_or_else(|| PathBuf::from("."))
+        .join(APPLICATION_DIRECTORY_NAME)
+}
+
+/* per-user configuration directory, the data directory on platforms without a separate one */
+pub fn config_directory() -> PathBuf {
+    if cfg!(target_os = "windows") || cfg!(target_os = "macos") {
+        return self::data_directory();
+    }
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(APPLICATION_DIRECTORY_NAME)
+}
+
+/* created on demand so a fresh install does not need any setup */
+pub fn screenshots_directory() -> Result<PathBuf> {
+    let directory = self::data_directory().join("screenshots");
+    std::fs::create_dir_all(&directory)
+        .with_context(|| format!("Failed to create {}", directory.display()))?;
+    Ok(directory)
+}
+
+pub fn crash_reports_directory() -> Result<PathBuf> {
+    let directory = self::data_directory().join("crashes");
+    std::fs::create_dir_all(&directory)
+        .with_context(|| format!("Failed to create {}", directory.display()))?;
+    Ok(directory)
+}
+
+/* a native message box through the tools every desktop ships, without a gui toolkit. blocks until
+it is dismissed, failing to show it is silently ignored */
+pub fn show_error_dialog(title: &str, message: &str) {
+    let mut commands = Vec::new();
+    if cfg!(target_os = "windows") {
+        let mut command = Command::new("powershell");
+        command.args(["-NoProfile", "-Command"]).arg(format!(
+            "Add-Type -AssemblyName PresentationFramework; [System.Windows.MessageBox]::Show('{}', '{}', 'OK', 'Error')",
+            message.replace('\'', "''"),
+            title.replace('\'', "''")
+        ));
+        commands.push(command);
+    } else if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display alert {:?} message {:?} as critical",
+            title, message
+        ));
+        commands.push(command);
+    } else {
+        let mut zenity = Command::new("zenity");
+        zenity
+            .args(["--error", "--no-markup"])
+            .arg(format!("--title={}", title))
+            .arg(format!("--text={}", message));
+        let mut kdialog = Command::new("kdialog");
+        kdialog.args(["--title", title, "--error", message]);
+        let mut xmessage = Command::new("xmessage");
+        xmessage.args(["-center", message]);
+        commands.extend([zenity, kdialog, xmessage]);
+    }
+    /* the next tool is only tried when one is not installed */
+    for mut command in commands {
+        if command.status().is_ok() {
+            return;
+        }
+    }
+}
+
+/* opens the directory of a file with the file selected where the file manager supports it. the
+file manager keeps running on its own */
+pub fn reveal_in_file_manager(path: &Path) -> Result<()> {
+    let mut command = if cfg!(target_os = "windows") {
+        let mut command = Command::new("explorer");
+        command.arg(format!("/select,{}", path.display()));
+        command
+    } else if cfg!(target_os = "macos") {
+        let mut command = Command::new("open");
+        command.arg("-R").arg(path);
+        command
+    } else {
+        let mut command = Command::new("xdg-open");
+        command.arg(path.parent().unwrap_or(path));
+        command
+    };
+    let mut child = command
+        .spawn()
+        .with_context(|| format!("Failed to reveal {}", path.display()))?;
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/* the system clipboard through the tools of the desktop, imgui only reaches the one of windows by
+itself. without any of them installed the text stays inside the application */
+#[derive(Debug, Default)]
+pub struct CommandClipboard {
+    copied: Option<String>,
+}
+
+impl CommandClipboard {
+    /* tried in order, wayland first since xwayland often runs next to it */
+    const PASTE_COMMANDS: [(&str, &[&str]); 4] = [
+        ("pbpaste", &[]),
+        ("wl-paste", &["--no-newline"]),
+        ("xclip", &["-selection", "clipboard", "-out"]),
+        ("xsel", &["--clipboard", "--output"]),
+    ];
+    const COPY_COMMANDS: [(&str, &[&str]); 4] = [
+        ("pbcopy", &[]),
+        ("wl-copy", &[]),
+        ("xclip", &["-selection", "clipboard", "-in"]),
+        ("xsel", &["--clipboard", "--input"]),
+    ];
+}
+
+impl imgui::ClipboardBackend for CommandClipboard {
+    fn get(&mut self) -> Option<String> {
+        Self::PASTE_COMMANDS
+            .iter()
+            .find_map(|(program, arguments)| {
+                let output = Command::new(program)
+                    .args(*arguments)
+                    .stderr(Stdio::null())
+                    .output()
+                    .ok()?;
+                output
+                    .status
+                    .success()
+                    .then(|| String::from_utf8(output.stdout).ok())
+                    .flatten()
+            })
+            .or_else(|| self.copied.clone())
+    }
+
+    fn set(&mut self, value: &str) {
+        self.copied = Some(value.to_string());
+        for (program, arguments) in Self::COPY_COMMANDS {
+            let Ok(mut child) = Command::new(program)
+                .args(arguments)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+            else {
+                continue;
+            };
+            let written = child
+                .stdin
+                .take()
+                .is_some_and(|mut stdin| stdin.write_all(value.as_bytes()).is_ok());
+            /* the tools fork to serve the selection, the waited for process exits right away */
+            if written && child.wait().is_ok_and(|status| status.success()) {
+                return;
+            }
+        }
+    }
+}
+
+/* directory of the running binary, none if the platform cannot tell */
+pub fn executable_directory() -> Option<PathBuf> {
+    let executable = std::env::current_exe().ok()?;
+    Some(executable.parent()?.to_path_buf())
+}
+
+/* relative asset paths are looked up next to the executable and then in its parents,
+which also finds the repository assets from target/<profile> during development.
+absolute paths and lookups without a hit are returned unchanged */
+pub fn asset_path(relative: impl AsRef<Path>) -> PathBuf {
+    let relative = relative.as_ref();
+    if relative.is_absolute() {
+        return relative.to_path_buf();
+    }
+    self::executable_directory()
+        .and_then(|directory| {
+            directory
+                .ancestors()
+                .map(|ancestor| ancestor.join(relative))
+                .find(|candidate| candidate.exists())
+        })
+        .unwrap_or_else(|| relative.to_path_buf())
+}
@@ -0,0 +1,61 @@
+use std::path::{Path, PathBuf};
+
+use rfd::AsyncFileDialog;
+
+/* a choice of the file type list, the extensions without their dot. * matches every file */
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileFilter {
+    pub name: String,
+    pub extensions: Vec<String>,
+}
+
+impl FileFilter {
+    pub fn new(name: impl Into<String>, extensions: &[&str]) -> Self {
+        Self {
+            name: name.into(),
+            extensions: extensions
+                .iter()
+                .map(|extension| extension.to_string())
+                .collect(),
+        }
+    }
+}
+
+/* the native dialogs through rfd, the xdg desktop portal on linux. they wait until closed, so
+they are shown from another thread than the event loop. the async dialogs work from there on
+macos too, where the sync ones need the main thread. none when the dialog was cancelled */
+pub fn pick_open_file(
+    title: &str,
+    directory: Option<&Path>,
+    filters: &[FileFilter],
+) -> Option<PathBuf> {
+    let dialog = self::dialog(title, directory, filters);
+    pollster::block_on(dialog.pick_file()).map(|file| file.path().to_path_buf())
+}
+
+/* the dialog asks before overwriting. a name typed without an extension gets the first one of
+the filters */
+pub fn pick_save_file(
+    title: &str,
+    directory: Option<&Path>,
+    default_name: &str,
+    filters: &[FileFilter],
+) -> Option<PathBuf> {
+    let dialog = self::dialog(title, directory, filters).set_file_name(default_name);
+    let path = pollster::block_on(dialog.save_file()).map(|file| file.path().to_path_buf());
+    let extension = filters.first().and_then(|filter| filter.extensions.first());
+    path.map(|path| match (path.extension(), extension) {
+        (None, Some(extension)) if extension != "*" => path.with_extension(extension),
+        _ => path,
+    })
+}
+
+fn dialog(title: &str, directory: Option<&Path>, filters: &[FileFilter]) -> AsyncFileDialog {
+    let mut dialog = AsyncFileDialog::new().set_title(title);
+    if let Some(directory) = directory {
+        dialog = dialog.set_directory(directory);
+    }
+    filters.iter().fold(dialog, |dialog, filter| {
+        dialog.add_filter(filter.name.as_str(), &filter.extensions)
+    })
+}
@@ -0,0 +1,86 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use glam::Vec2;
+
+#[cfg(not(target_os = "linux"))]
+mod gilrs;
+#[cfg(target_os = "linux")]
+mod joystick;
+
+/* named after their position, the labels differ between the vendors */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GamepadButton {
+    South,
+    East,
+    West,
+    North,
+    LeftShoulder,
+    RightShoulder,
+    Select,
+    Start,
+}
+
+/* sticks in -1 to 1 with y pointing down, triggers in 0 to 1 */
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct GamepadAxes {
+    pub left_stick: Vec2,
+    pub right_stick: Vec2,
+    pub left_trigger: f32,
+    pub right_trigger: f32,
+}
+
+impl GamepadAxes {
+    /* radial for the sticks, what is left beyond the deadzone is stretched to the full range */
+    pub fn with_deadzone(self, deadzone: f32) -> Self {
+        let scale = |value: f32| match value > deadzone {
+            true => (value - deadzone) / (1. - deadzone).max(f32::EPSILON),
+            false => 0.,
+        };
+        let stick = |stick: Vec2| stick.normalize_or_zero() * scale(stick.length().min(1.));
+        Self {
+            left_stick: stick(self.left_stick),
+            right_stick: stick(self.right_stick),
+            left_trigger: scale(self.left_trigger),
+            right_trigger: scale(self.right_trigger),
+        }
+    }
+
+    pub fn is_idle(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum GamepadEvent {
+    /* with the name of the controller, the device path on linux */
+    Connected(String),
+    Disconnected(String),
+    Pressed(GamepadButton),
+    /* a stick or a trigger moved, the new values are read through the watcher */
+    Moved,
+}
+
+/* reads the first controller on its own thread, through the joystick interface on linux and
+gilrs elsewhere. the notifier runs on that thread for every change */
+pub struct GamepadWatcher {
+    axes: Arc<Mutex<GamepadAxes>>,
+}
+
+impl GamepadWatcher {
+    pub fn spawn<N>(notifier: N) -> Result<Self>
+    where
+        N: Fn(GamepadEvent) + Send + 'static,
+    {
+        let axes = Arc::new(Mutex::new(GamepadAxes::default()));
+        #[cfg(target_os = "linux")]
+        self::joystick::spawn(axes.clone(), notifier)?;
+        #[cfg(not(target_os = "linux"))]
+        self::gilrs::spawn(axes.clone(), notifier)?;
+        Ok(Self { axes })
+    }
+
+    pub fn axes(&self) -> GamepadAxes {
+        *self.axes.lock().unwrap()
+    }
+}
@@ -0,0 +1,101 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::{Result, anyhow};
+use gilrs::{Axis, Button, EventType, GamepadId, Gilrs, GilrsBuilder};
+
+use super::{GamepadAxes, GamepadButton, GamepadEvent};
+
+/* gilrs covers windows and macos, the first connected controller is followed */
+pub fn spawn<N>(axes: Arc<Mutex<GamepadAxes>>, notifier: N) -> Result<()>
+where
+    N: Fn(GamepadEvent) + Send + 'static,
+{
+    /* without the default filters, the deadzone of the settings is the only one */
+    let mut gilrs = GilrsBuilder::new()
+        .with_default_filters(false)
+        .build()
+        .map_err(|error| anyhow!("{error}"))?;
+    /* detached like the reader on linux, the process ending stops it */
+    std::thread::Builder::new()
+        .name("gamepad".to_string())
+        .spawn(move || {
+            let mut followed = None;
+            self::follow_first(&gilrs, &mut followed, &notifier);
+            loop {
+                let Some(event) = gilrs.next_event_blocking(None) else {
+                    continue;
+                };
+                match event.event {
+                    EventType::Connected => self::follow_first(&gilrs, &mut followed, &notifier),
+                    EventType::Disconnected if followed == Some(event.id) => {
+                        *axes.lock().unwrap() = GamepadAxes::default();
+                        notifier(GamepadEvent::Disconnected(
+                            gilrs.gamepad(event.id).name().to_string(),
+                        ));
+                        followed = None;
+                        self::follow_first(&gilrs, &mut followed, &notifier);
+                    }
+                    _ if followed != Some(event.id) => {}
+                    EventType::ButtonPressed(button, _) => {
+                        if let Some(button) = self::button(button) {
+                            notifier(GamepadEvent::Pressed(button));
+                        }
+                    }
+                    EventType::ButtonChanged(button, value, _) => {
+                        let mut axes = axes.lock().unwrap();
+                        match button {
+                            Button::LeftTrigger2 => axes.left_trigger = value,
+                            Button::RightTrigger2 => axes.right_trigger = value,
+                            _ => continue,
+                        }
+                        drop(axes);
+                        notifier(GamepadEvent::Moved);
+                    }
+                    EventType::AxisChanged(axis, value, _) => {
+                        /* y points up in gilrs and down here */
+                        let mut axes = axes.lock().unwrap();
+                        match axis {
+                            Axis::LeftStickX => axes.left_stick.x = value,
+                            Axis::LeftStickY => axes.left_stick.y = -value,
+                            Axis::RightStickX => axes.right_stick.x = value,
+                            Axis::RightStickY => axes.right_stick.y = -value,
+                            _ => continue,
+                        }
+                        drop(axes);
+                        notifier(GamepadEvent::Moved);
+                    }
+                    _ => {}
+                }
+            }
+        })?;
+    Ok(())
+}
+
+/* when none is followed yet, also for the controllers connected before the start */
+fn follow_first<N>(gilrs: &Gilrs, followed: &mut Option<GamepadId>, notifier: &N)
+where
+    N: Fn(GamepadEvent),
+{
+    if followed.is_some() {
+        return;
+    }
+    if let Some((id, gamepad)) = gilrs.gamepads().next() {
+        *followed = Some(id);
+        notifier(GamepadEvent::Connected(gamepad.name().to_string()));
+    }
+}
+
+/* the shoulders are the first triggers in gilrs, the analog ones are read as axes */
+fn button(button: Button) -> Option<GamepadButton> {
+    Some(match button {
+        Button::South => GamepadButton::South,
+        Button::East => GamepadButton::East,
+        Button::West => GamepadButton::West,
+        Button::North => GamepadButton::North,
+        Button::LeftTrigger => GamepadButton::LeftShoulder,
+        Button::RightTrigger => GamepadButton::RightShoulder,
+        Button::Select => GamepadButton::Select,
+        Button::Start => GamepadButton::Start,
+        _ => return None,
+    })
+}
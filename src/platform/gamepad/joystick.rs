@@ -0,0 +1,137 @@
+use std::{
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::Result;
+use tracing::debug;
+
+use super::{GamepadAxes, GamepadButton, GamepadEvent};
+
+/* the devices are looked for again this often while none is connected, so a controller plugged
+in later is picked up */
+const GAMEPAD_SCAN_INTERVAL: Duration = Duration::from_secs(1);
+const JOYSTICK_DIRECTORY: &str = "/dev/input";
+const JOYSTICK_EVENT_SIZE: usize = 8;
+const JOYSTICK_EVENT_BUTTON: u8 = 0x01;
+const JOYSTICK_EVENT_AXIS: u8 = 0x02;
+/* the synthetic events reporting the state right after opening */
+const JOYSTICK_EVENT_INIT: u8 = 0x80;
+const AXIS_RANGE: f32 = 32767.;
+
+/* the linux joystick interface, read without the libudev headers gilrs needs here */
+pub fn spawn<N>(axes: Arc<Mutex<GamepadAxes>>, notifier: N) -> Result<()>
+where
+    N: Fn(GamepadEvent) + Send + 'static,
+{
+    /* detached, a blocking read cannot be interrupted and the process ending stops it */
+    std::thread::Builder::new()
+        .name("gamepad".to_string())
+        .spawn(move || {
+            loop {
+                if let Some(path) = self::find_joystick() {
+                    let name = path.display().to_string();
+                    notifier(GamepadEvent::Connected(name.clone()));
+                    if let Err(error) = self::read_joystick(&path, &axes, &notifier) {
+                        debug!("Stopped reading {}: {:#}", name, error);
+                    }
+                    *axes.lock().unwrap() = GamepadAxes::default();
+                    notifier(GamepadEvent::Disconnected(name));
+                }
+                std::thread::sleep(GAMEPAD_SCAN_INTERVAL);
+            }
+        })?;
+    Ok(())
+}
+
+/* the numbering of the xpad driver, which most controllers on linux follow */
+fn button(number: u8) -> Option<GamepadButton> {
+    Some(match number {
+        0 => GamepadButton::South,
+        1 => GamepadButton::East,
+        2 => GamepadButton::West,
+        3 => GamepadButton::North,
+        4 => GamepadButton::LeftShoulder,
+        5 => GamepadButton::RightShoulder,
+        6 => GamepadButton::Select,
+        7 => GamepadButton::Start,
+        _ => return None,
+    })
+}
+
+/* a trigger rests at -1 on most controllers. the rest reported on opening is taken as zero,
+so a pad with a stick on these axes does not zoom on its own */
+fn apply_axis(axes: &mut GamepadAxes, number: u8, value: i16, trigger_rest: f32) {
+    let value = value as f32 / AXIS_RANGE;
+    let trigger = ((value - trigger_rest) / (1. - trigger_rest).max(f32::EPSILON)).max(0.);
+    match number {
+        0 => axes.left_stick.x = value,
+        1 => axes.left_stick.y = value,
+        2 => axes.left_trigger = trigger,
+        3 => axes.right_stick.x = value,
+        4 => axes.right_stick.y = value,
+        5 => axes.right_trigger = trigger,
+        _ => {}
+    }
+}
+
+/* the first js device that can be opened, sorted so the choice is stable */
+fn find_joystick() -> Option<PathBuf> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(JOYSTICK_DIRECTORY)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("js"))
+        })
+        .collect();
+    paths.sort();
+    paths.into_iter().find(|path| File::open(path).is_ok())
+}
+
+/* until the device goes away, which fails the read */
+fn read_joystick<N>(path: &Path, axes: &Mutex<GamepadAxes>, notifier: &N) -> Result<()>
+where
+    N: Fn(GamepadEvent),
+{
+    let mut device = File::open(path)?;
+    let mut event = [0u8; JOYSTICK_EVENT_SIZE];
+    let mut trigger_rest = [-1.; 2];
+    loop {
+        device.read_exact(&mut event)?;
+        /* the timestamp in the first four bytes is not needed */
+        let value = i16::from_ne_bytes([event[4], event[5]]);
+        let kind = event[6];
+        let number = event[7];
+        match kind & !JOYSTICK_EVENT_INIT {
+            JOYSTICK_EVENT_AXIS => {
+                let trigger = match number {
+                    2 => Some(0),
+                    5 => Some(1),
+                    _ => None,
+                };
+                if let Some(trigger) = trigger
+                    && kind & JOYSTICK_EVENT_INIT != 0
+                {
+                    trigger_rest[trigger] = value as f32 / AXIS_RANGE;
+                }
+                let rest = trigger.map_or(-1., |trigger| trigger_rest[trigger]);
+                self::apply_axis(&mut axes.lock().unwrap(), number, value, rest);
+                notifier(GamepadEvent::Moved);
+            }
+            /* the initial state of a held button is no press */
+            JOYSTICK_EVENT_BUTTON if value != 0 && kind & JOYSTICK_EVENT_INIT == 0 => {
+                if let Some(button) = self::button(number) {
+                    notifier(GamepadEvent::Pressed(button));
+                }
+            }
+            JOYSTICK_EVENT_BUTTON => {}
+            _ => debug!("Unknown joystick event type {:#x}", kind),
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use std::fmt::Display;
+
+/* a language with its region, like de-AT, without the script and variants of bcp 47 */
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LanguageIdentifier {
+    /* lowercase, like de */
+    pub language: String,
+    /* uppercase, like AT */
+    pub region: Option<String>,
+}
+
+impl LanguageIdentifier {
+    /* a posix locale like de_AT.UTF-8@euro or a tag like de-AT, none for the C and POSIX
+    locales which name no language */
+    pub fn parse(locale: &str) -> Option<Self> {
+        let tag = locale.split(['.', '@']).next().unwrap_or_default().trim();
+        if tag.is_empty() || tag == "C" || tag == "POSIX" {
+            return None;
+        }
+        let mut subtags = tag.split(['-', '_']);
+        let language = subtags.next()?;
+        if !(2..=3).contains(&language.len())
+            || !language
+                .chars()
+                .all(|character| character.is_ascii_alphabetic())
+        {
+            return None;
+        }
+        /* a script like Hans in zh-Hans-CN is skipped, the region is two letters or three
+        digits */
+        let region = subtags
+            .find(|subtag| {
+                subtag.len() == 2
+                    && subtag
+                        .chars()
+                        .all(|character| character.is_ascii_alphabetic())
+                    || subtag.len() == 3
+                        && subtag.chars().all(|character| character.is_ascii_digit())
+            })
+            .map(str::to_ascii_uppercase);
+        Some(Self {
+            language: language.to_ascii_lowercase(),
+            region,
+        })
+    }
+}
+
+impl Display for LanguageIdentifier {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.region {
+            Some(region) => write!(formatter, "{}-{}", self.language, region),
+            None => formatter.write_str(&self.language),
+        }
+    }
+}
+
+/* the language the system shows its user interface in, through sys-locale. the display
+language on windows and the preferred languages on macos, which the regional format does not
+change. on linux the gettext variables, LANGUAGE with its list ahead of the locale. none when it
+cannot be told */
+pub fn system_locale() -> Option<LanguageIdentifier> {
+    sys_locale::get_locales().find_map(|locale| LanguageIdentifier::parse(&locale))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locales_and_tags_parse_to_the_language_and_region() {
+        let parse = |locale: &str| LanguageIdentifier::parse(locale).map(|id| id.to_string());
+        assert_eq!(parse("de_AT.UTF-8@euro").as_deref(), Some("de-AT"));
+        assert_eq!(parse("zh-Hans-CN").as_deref(), Some("zh-CN"));
+        assert_eq!(parse("es-419").as_deref(), Some("es-419"));
+        assert_eq!(parse("PT").as_deref(), Some("pt"));
+        assert_eq!(parse("C.UTF-8"), None);
+        assert_eq!(parse("POSIX"), None);
+        assert_eq!(parse(""), None);
+    }
+}
@@ -0,0 +1,402 @@
+use std::path::PathBuf;
+#[cfg(feature = "scenario-hot-reload")]
+use std::{path::Path, sync::mpsc, time::Duration};
+
+#[cfg(feature = "scenario-hot-reload")]
+use anyhow::Context;
+use anyhow::Result;
+use tracing::{Level, info, warn};
+
+use crate::{
+    cli::LaunchOptions,
+    simulation::{
+        self, BodyId, Integrator, SimulationBody, SimulationEvent, SimulationObject,
+        SimulationState, adaptive::AdaptiveTimestepConfiguration,
+        error_estimate::ErrorEstimatorConfiguration,
+    },
+    storage::{self, SaveGame, Scenario, ScenarioMetadata, Settings, presets},
+};
+
+/* a burst of writes, like an editor saving through a temporary file, is reloaded once */
+#[cfg(feature = "scenario-hot-reload")]
+pub const SCENARIO_RELOAD_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/* fixed physics timestep in simulation seconds */
+pub const DEFAULT_SIMULATION_TIMESTEP: f64 = 60.;
+/* bounds of the adaptive sub-stepping used during close encounters */
+pub const MINIMUM_ADAPTIVE_TIMESTEP: f64 = 0.01;
+pub const ADAPTIVE_TIMESTEP_SAFETY_FACTOR: f64 = 0.02;
+
+/* a state for the objects of a scenario, configured by the settings */
+pub fn simulation_state(objects: &[SimulationObject], settings: &Settings) -> SimulationState {
+    let mut simulation_state = SimulationState::from_objects(objects, DEFAULT_SIMULATION_TIMESTEP);
+    simulation_state.adaptive_timestep = match settings.integrator {
+        Integrator::Leapfrog => None,
+        Integrator::AdaptiveLeapfrog => Some(AdaptiveTimestepConfiguration::new(
+            MINIMUM_ADAPTIVE_TIMESTEP,
+            DEFAULT_SIMULATION_TIMESTEP,
+            ADAPTIVE_TIMESTEP_SAFETY_FACTOR,
+        )),
+    };
+    simulation_state.trail_configuration.capacity = settings.trail_length;
+    simulation_state.error_estimator = self::error_estimator_from_env();
+    self::log_sphere_of_influence_hierarchy(&simulation_state);
+    simulation_state
+}
+
+/* a quicksave in a state configured like a freshly loaded scenario */
+pub fn restored_state(save_game: &SaveGame, settings: &Settings) -> SimulationState {
+    let mut simulation_state = self::simulation_state(&[], settings);
+    simulation_state.restore(save_game.bodies.clone(), save_game.simulation_time);
+    simulation_state.dirty = save_game.dirty;
+    simulation_state
+}
+
+pub fn settings_path(launch_options: &LaunchOptions) -> PathBuf {
+    launch_options
+        .settings
+        .clone()
+        .unwrap_or_else(storage::settings_path)
+}
+
+/* the scenario file from the command line, a built-in scene without one */
+pub fn launch_scenario(launch_options: &LaunchOptions) -> Result<Scenario> {
+    match launch_options.scenario.as_ref() {
+        Some(path) => storage::load_scenario_as(path, launch_options.scenario_format),
+        None => Ok(self::scene_from_env()),
+    }
+}
+
+/* a scene built into the binary, listed next to the scenario files */
+pub struct ScenarioPreset {
+    /* the value of SPACES_SCENE */
+    pub key: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    /* the bodies, and the epoch of a real system */
+    pub build: fn() -> Scenario,
+}
+
+impl ScenarioPreset {
+    pub fn scenario(&self) -> Scenario {
+        Scenario {
+            metadata: ScenarioMetadata {
+                name: self.name.to_string(),
+                author: PRESET_AUTHOR.to_string(),
+                description: self.description.to_string(),
+                ..ScenarioMetadata::default()
+            },
+            ..(self.build)()
+        }
+    }
+}
+
+pub const PRESET_AUTHOR: &str = "SpaceS";
+/* the first one is the default scene */
+pub const SCENARIO_PRESETS: [ScenarioPreset; 4] = [
+    ScenarioPreset {
+        key: "sun-earth-moon",
+        name: "Sun, Earth and Moon",
+        description: "The moon around the earth around the sun, on the ecliptic.",
+        build: || Scenario::new(simulation::sun_earth_moon_objects()),
+    },
+    ScenarioPreset {
+        key: "extreme-scale",
+        name: "Extreme scale",
+        description: "A five meter probe beside the earth and moon, seen at the scale of the solar system.",
+        build: || Scenario::new(simulation::extreme_scale_objects()),
+    },
+    ScenarioPreset {
+        key: "ringed-planet",
+        name: "Ringed planet",
+        description: "A saturn analogue with rings and its axial tilt next to the sun.",
+        build: || Scenario::new(simulation::ringed_planet_objects()),
+    },
+    ScenarioPreset {
+        key: "solar-system",
+        name: presets::SOLAR_SYSTEM_NAME,
+        description: presets::SOLAR_SYSTEM_DESCRIPTION,
+        build: presets::solar_system,
+    },
+];
+
+/* SPACES_SCENE=sun-earth-moon|extreme-scale|ringed-planet|solar-system, picks one of the
+built-in scenes */
+/* the file name, or the name of the built-in scene an unknown SPACES_SCENE falls back to */
+pub fn launch_scenario_label(launch_options: &LaunchOptions) -> String {
+    match launch_options.scenario.as_ref() {
+        Some(path) => path
+            .file_name()
+            .unwrap_or(path.as_os_str())
+            .to_string_lossy()
+            .into_owned(),
+        None => std::env::var("SPACES_SCENE")
+            .ok()
+            .and_then(|value| self::find_preset(&value))
+            .unwrap_or(&SCENARIO_PRESETS[0])
+            .name
+            .to_string(),
+    }
+}
+
+fn find_preset(key: &str) -> Option<&'static ScenarioPreset> {
+    SCENARIO_PRESETS
+        .iter()
+        .find(|preset| preset.key.eq_ignore_ascii_case(key))
+}
+
+fn scene_from_env() -> Scenario {
+    let Ok(value) = std::env::var("SPACES_SCENE") else {
+        return SCENARIO_PRESETS[0].scenario();
+    };
+    match self::find_preset(&value) {
+        Some(preset) => preset.scenario(),
+        None => {
+            warn!(
+                "Unknown SPACES_SCENE [{}], using {}",
+                value, SCENARIO_PRESETS[0].key
+            );
+            SCENARIO_PRESETS[0].scenario()
+        }
+    }
+}
+
+/* the integration error estimator is opt-in, SPACES_ERROR_ESTIMATE=<tolerance> enables it */
+fn error_estimator_from_env() -> Option<ErrorEstimatorConfiguration> {
+    let value = std::env::var("SPACES_ERROR_ESTIMATE").ok()?;
+    let mut configuration = ErrorEstimatorConfiguration::default();
+    match value.parse::<f64>() {
+        Ok(tolerance) if tolerance > 0. => configuration.tolerance = tolerance,
+        _ => warn!(
+            "Invalid SPACES_ERROR_ESTIMATE tolerance [{}], using {}",
+            value, configuration.tolerance
+        ),
+    }
+    info!(
+        "Integration error estimator enabled (every {} steps, tolerance {:e})",
+        configuration.sample_interval, configuration.tolerance
+    );
+    Some(configuration)
+}
+
+pub fn body_display_name(bodies: &[SimulationBody], id: BodyId) -> String {
+    bodies
+        .iter()
+        .find(|body| body.id == id)
+        .map(|body| body.id_name.clone())
+        .unwrap_or_else(|| format!("{:?}", id))
+}
+
+fn log_sphere_of_influence_hierarchy(state: &SimulationState) {
+    state.bodies.iter().for_each(|body| {
+        match (
+            state.dominant_attractor(body.id),
+            state.sphere_of_influence(body.id),
+        ) {
+            (Some(attractor), Some(radius)) => info!(
+                " + {} (radius {} m) orbits {} with a sphere of influence of {:.3e} m",
+                body.id_name,
+                body.radius,
+                body_display_name(&state.bodies, attractor),
+                radius
+            ),
+            _ => info!(
+                " + {} (radius {} m) is the root attractor",
+                body.id_name, body.radius
+            ),
+        }
+    });
+}
+
+/* the bodies of the state the event was drained from. the description is passed on for the
+toasts */
+pub fn log_simulation_event(
+    bodies: &[SimulationBody],
+    simulation_time: f64,
+    event: &SimulationEvent,
+) -> Option<(Level, String)> {
+    let description = self::describe_simulation_event(bodies, simulation_time, event);
+    match description.as_ref() {
+        Some((Level::WARN, message)) => warn!("{}", message),
+        Some((_, message)) => info!("{}", message),
+        None => {}
+    }
+    description
+}
+
+/* what is told about an event, at the level it is logged with */
+fn describe_simulation_event(
+    bodies: &[SimulationBody],
+    simulation_time: f64,
+    event: &SimulationEvent,
+) -> Option<(Level, String)> {
+    match event {
+        /* already gone from the snapshot, the deletion is logged when requested */
+        SimulationEvent::BodyRemoved(_) => None,
+        SimulationEvent::SphereOfInfluenceExited { body, attractor } => Some((
+            Level::INFO,
+            format!(
+                "{} left the sphere of influence of {}",
+                body_display_name(bodies, *body),
+                body_display_name(bodies, *attractor)
+            ),
+        )),
+        SimulationEvent::SphereOfInfluenceEntered { body, attractor } => Some((
+            Level::INFO,
+            format!(
+                "{} entered the sphere of influence of {}",
+                body_display_name(bodies, *body),
+                body_display_name(bodies, *attractor)
+            ),
+        )),
+        SimulationEvent::IntegrationErrorExceeded {
+            estimate,
+            tolerance,
+        } => Some((
+            Level::WARN,
+            format!(
+                "Integration error estimate {:.3e} exceeds the tolerance {:.3e} at {} s of simulation time, consider a smaller timestep or more warp sub-steps",
+                estimate, tolerance, simulation_time
+            ),
+        )),
+        SimulationEvent::BodiesCollided {
+            first,
+            second,
+            relative_speed,
+            ..
+        } => Some((
+            Level::INFO,
+            format!(
+                "{} collided with {} at {:.3e} m/s",
+                body_display_name(bodies, *first),
+                body_display_name(bodies, *second),
+                relative_speed
+            ),
+        )),
+    }
+}
+
+/* watches the directory of a scenario file, editors often replace the file instead of writing to
+it. the notifier runs on the debouncing thread once the writes settled */
+#[cfg(feature = "scenario-hot-reload")]
+pub struct ScenarioWatcher {
+    _watcher: notify::RecommendedWatcher,
+}
+
+#[cfg(feature = "scenario-hot-reload")]
+impl ScenarioWatcher {
+    pub fn spawn<N>(path: &Path, notifier: N) -> Result<Self>
+    where
+        N: Fn() + Send + 'static,
+    {
+        use notify::Watcher;
+
+        let file_name = path
+            .file_name()
+            .with_context(|| format!("{} is not a file", path.display()))?
+            .to_os_string();
+        let directory = match path.parent() {
+            Some(directory) if !directory.as_os_str().is_empty() => directory.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let (changes, received_changes) = mpsc::channel();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(error) => return warn!("Scenario watcher error: {}", error),
+                };
+                if (event.kind.is_modify() || event.kind.is_create())
+                    && event
+                        .paths
+                        .iter()
+                        .any(|changed| changed.file_name() == Some(file_name.as_os_str()))
+                {
+                    let _ = changes.send(());
+                }
+            })
+            .context("Failed to create the scenario watcher")?;
+        watcher
+            .watch(&directory, notify::RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch {}", directory.display()))?;
+        /* ends with the watcher, which drops the sending side */
+        std::thread::Builder::new()
+            .name("scenario watcher".to_string())
+            .spawn(move || {
+                while received_changes.recv().is_ok() {
+                    loop {
+                        match received_changes.recv_timeout(SCENARIO_RELOAD_DEBOUNCE) {
+                            Ok(()) => continue,
+                            Err(mpsc::RecvTimeoutError::Timeout) => break,
+                            Err(mpsc::RecvTimeoutError::Disconnected) => return,
+                        }
+                    }
+                    notifier();
+                }
+            })
+            .context("Failed to spawn the scenario watcher thread")?;
+        info!("Watching {} for changes", path.display());
+        Ok(Self { _watcher: watcher })
+    }
+}
+
+#[cfg(all(test, feature = "scenario-hot-reload"))]
+mod tests {
+    use super::*;
+
+    /* long enough for a loaded machine, the debounce is a small part of it */
+    const NOTIFICATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn watch(path: &Path) -> (ScenarioWatcher, mpsc::Receiver<()>) {
+        let (notified, notifications) = mpsc::channel();
+        let watcher = ScenarioWatcher::spawn(path, move || {
+            let _ = notified.send(());
+        })
+        .unwrap();
+        (watcher, notifications)
+    }
+
+    #[test]
+    fn every_readable_format_is_watched() {
+        let directory = tempfile::tempdir().unwrap();
+        for extension in storage::SCENARIO_EXTENSIONS {
+            let path = directory.path().join(format!("orbit.{}", extension));
+            assert!(storage::is_scenario_path(&path), "{}", path.display());
+            std::fs::write(&path, "").unwrap();
+            let (_watcher, notifications) = self::watch(&path);
+            std::fs::write(&path, "changed").unwrap();
+            assert_eq!(
+                notifications.recv_timeout(NOTIFICATION_TIMEOUT),
+                Ok(()),
+                "{}",
+                path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn a_file_replaced_through_a_rename_is_reloaded() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("orbit.ron");
+        std::fs::write(&path, "").unwrap();
+        let (_watcher, notifications) = self::watch(&path);
+        let temporary = directory.path().join("orbit.ron.tmp");
+        std::fs::write(&temporary, "changed").unwrap();
+        std::fs::rename(&temporary, &path).unwrap();
+        assert_eq!(notifications.recv_timeout(NOTIFICATION_TIMEOUT), Ok(()));
+    }
+
+    #[test]
+    fn a_sibling_of_another_format_is_ignored() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("orbit.yaml");
+        std::fs::write(&path, "").unwrap();
+        let (_watcher, notifications) = self::watch(&path);
+        std::fs::write(directory.path().join("orbit.json"), "{}").unwrap();
+        assert!(
+            notifications
+                .recv_timeout(SCENARIO_RELOAD_DEBOUNCE * 4)
+                .is_err()
+        );
+    }
+}
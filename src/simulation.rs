@@ -1,4 +1,20 @@
+use std::sync::{Arc, Mutex};
+
+use glam::DVec3;
 use serde::{Deserialize, Serialize};
+use wgpu::naga::{FastHashMap, FastHashSet};
+
+pub mod adaptive;
+pub mod error_estimate;
+pub mod history;
+pub mod plots;
+pub mod recording;
+pub mod soi;
+pub mod thread;
+pub mod trail;
+
+/* newtons constant in SI units (m^3 kg^-1 s^-2) */
+pub const GRAVITATIONAL_CONSTANT: f64 = 6.674_30e-11;
 
 #[derive(Serialize, Deserialize, Debug, derive_new::new)]
 pub struct SimulationObject {
@@ -8,6 +24,68 @@ pub struct SimulationObject {
     pub physics_data: SimulationPhysicsObject,
     #[serde(rename = "enter simulation values")]
     pub enter_configuration: SimulationEnterConfiguration,
+    #[serde(rename = "appearance", default)]
+    #[new(default)]
+    pub appearance: BodyAppearance,
+}
+
+/* purely visual, bodies without a texture are drawn in their flat color */
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct BodyAppearance {
+    /* equirectangular image, relative paths are resolved by storage::AssetResolver */
+    #[serde(rename = "texture", default, skip_serializing_if = "Option::is_none")]
+    pub texture: Option<String>,
+    /* linear rgb, a palette color is picked when missing */
+    #[serde(rename = "color", default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<[f32; 3]>,
+    /* drawn unlit and bright enough to bloom, for stars. emissive bodies light the others */
+    #[serde(
+        rename = "emissive",
+        default,
+        skip_serializing_if = "std::ops::Not::not"
+    )]
+    pub emissive: bool,
+    /* relative to the sun, only read for emissive bodies, 1 when missing */
+    #[serde(
+        rename = "luminosity",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub luminosity: Option<f64>,
+    /* free form categories like star or moon, used to filter labels */
+    #[serde(rename = "tags", default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /* north pole direction, the ecliptic normal (+y) when missing. rings lie in the plane
+    perpendicular to it */
+    #[serde(rename = "spin axis", default, skip_serializing_if = "Option::is_none")]
+    pub spin_axis: Option<[f32; 3]>,
+    #[serde(rename = "rings", default, skip_serializing_if = "Option::is_none")]
+    pub rings: Option<RingDefinition>,
+}
+
+/* flat annulus in the equatorial plane, radii are multiples of the body radius so the rings
+scale with it */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RingDefinition {
+    #[serde(rename = "inner radius")]
+    pub inner_radius: f32,
+    #[serde(rename = "outer radius")]
+    pub outer_radius: f32,
+    /* radial strip, the left edge maps to the inner radius. replaces the bands */
+    #[serde(rename = "texture", default, skip_serializing_if = "Option::is_none")]
+    pub texture: Option<String>,
+    /* linear rgba stops spread evenly from the inner to the outer radius, a default set when
+    empty. a low alpha leaves a gap */
+    #[serde(rename = "bands", default, skip_serializing_if = "Vec::is_empty")]
+    pub bands: Vec<[f32; 4]>,
+    #[serde(rename = "opacity", default = "RingDefinition::default_opacity")]
+    pub opacity: f32,
+}
+
+impl RingDefinition {
+    fn default_opacity() -> f32 {
+        1.
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, derive_new::new)]
@@ -25,3 +103,763 @@ pub struct SimulationEnterConfiguration {
     #[serde(rename = "enter position")]
     pub simulation_enter_position: [f32; 3],
 }
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BodyId(pub u32);
+
+/* how the state advances, the adaptive variant sub-steps close encounters */
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    strum_macros::EnumIter,
+    strum_macros::Display,
+)]
+pub enum Integrator {
+    #[serde(rename = "leapfrog")]
+    #[strum(to_string = "Leapfrog")]
+    Leapfrog,
+    #[default]
+    #[serde(rename = "adaptive leapfrog")]
+    #[strum(to_string = "Adaptive leapfrog")]
+    AdaptiveLeapfrog,
+}
+
+/* runtime representation of a body, values are kept in f64 SI units. saved as it is in a
+quicksave, unlike the f32 of a scenario */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SimulationBody {
+    #[serde(rename = "id")]
+    pub id: BodyId,
+    #[serde(rename = "name")]
+    pub id_name: String,
+    #[serde(rename = "mass")]
+    pub mass: f64,
+    #[serde(rename = "radius")]
+    pub radius: f64,
+    #[serde(rename = "position")]
+    pub position: DVec3,
+    #[serde(rename = "velocity")]
+    pub velocity: DVec3,
+    #[serde(rename = "appearance", default)]
+    pub appearance: BodyAppearance,
+}
+
+/* a single field of a live body changed from the ui, applied before the next step */
+#[derive(Debug, Clone, PartialEq)]
+pub enum BodyEdit {
+    Name(String),
+    Mass(f64),
+    Radius(f64),
+    Position(DVec3),
+    Velocity(DVec3),
+    Appearance(BodyAppearance),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SimulationEvent {
+    /* deleted from the ui or by undoing its addition, nothing may refer to the id any more */
+    BodyRemoved(BodyId),
+    SphereOfInfluenceExited {
+        body: BodyId,
+        attractor: BodyId,
+    },
+    SphereOfInfluenceEntered {
+        body: BodyId,
+        attractor: BodyId,
+    },
+    IntegrationErrorExceeded {
+        estimate: f64,
+        tolerance: f64,
+    },
+    /* first contact of two spheres, from the first towards the second body. there is no
+    collision response yet, the bodies pass through each other */
+    BodiesCollided {
+        first: BodyId,
+        second: BodyId,
+        point: DVec3,
+        normal: DVec3,
+        relative_speed: f64,
+    },
+}
+
+impl SimulationEvent {
+    /* the bodies the event is about, framed by clicking its toast */
+    pub fn bodies(&self) -> Vec<BodyId> {
+        match self {
+            SimulationEvent::BodyRemoved(_) | SimulationEvent::IntegrationErrorExceeded { .. } => {
+                Vec::new()
+            }
+            /* the attractor would usually dwarf the body */
+            SimulationEvent::SphereOfInfluenceExited { body, .. }
+            | SimulationEvent::SphereOfInfluenceEntered { body, .. } => vec![*body],
+            SimulationEvent::BodiesCollided { first, second, .. } => vec![*first, *second],
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SimulationDiagnostics {
+    pub step_count: u64,
+    /* relative position error of the last sampled step, see error_estimate */
+    pub latest_error_estimate: Option<f64>,
+    /* sub-steps and smallest sub-step of the last tick when the adaptive controller is active */
+    pub latest_substep_count: u32,
+    pub latest_smallest_substep: Option<f64>,
+}
+
+pub struct SimulationState {
+    pub bodies: Vec<SimulationBody>,
+    pub simulation_time: f64,
+    /* fixed tick the state advances per step, sub-stepped when adaptive_timestep is set */
+    pub timestep: f64,
+    pub adaptive_timestep: Option<adaptive::AdaptiveTimestepConfiguration>,
+    pub error_estimator: Option<error_estimate::ErrorEstimatorConfiguration>,
+    pub diagnostics: SimulationDiagnostics,
+    pub trail_configuration: trail::TrailConfiguration,
+    /* simulation seconds between two plot samples, independent of the warp */
+    pub plot_sample_interval: f64,
+    /* set by edits, the state no longer matches the scenario it was loaded from */
+    pub dirty: bool,
+    /* shared with snapshots, only copied on write when a new sample is recorded */
+    trails: Arc<FastHashMap<BodyId, trail::Trail>>,
+    next_trail_sample: f64,
+    /* copied on write like the trails */
+    plots: Arc<plots::DiagnosticPlots>,
+    next_plot_sample: f64,
+    next_body_id: u32,
+    attractors: FastHashMap<BodyId, Option<BodyId>>,
+    /* overlapping pairs, lower id first, so a contact is only reported once */
+    contacts: FastHashSet<(BodyId, BodyId)>,
+    events: Vec<SimulationEvent>,
+    error_warning_emitted: bool,
+    /* edits made through the ui */
+    pub history: history::EditHistory,
+    /* shared with the application, which exports it */
+    pub recording: Option<Arc<Mutex<recording::TrajectoryRecording>>>,
+}
+
+impl BodyEdit {
+    /* the same field with the current value of the body, what undoing this edit restores */
+    pub fn read(&self, body: &SimulationBody) -> BodyEdit {
+        match self {
+            BodyEdit::Name(_) => BodyEdit::Name(body.id_name.clone()),
+            BodyEdit::Mass(_) => BodyEdit::Mass(body.mass),
+            BodyEdit::Radius(_) => BodyEdit::Radius(body.radius),
+            BodyEdit::Position(_) => BodyEdit::Position(body.position),
+            BodyEdit::Velocity(_) => BodyEdit::Velocity(body.velocity),
+            BodyEdit::Appearance(_) => BodyEdit::Appearance(body.appearance.clone()),
+        }
+    }
+
+    pub fn same_field(&self, other: &BodyEdit) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+
+    pub fn field_name(&self) -> &'static str {
+        match self {
+            BodyEdit::Name(_) => "name",
+            BodyEdit::Mass(_) => "mass",
+            BodyEdit::Radius(_) => "radius",
+            BodyEdit::Position(_) => "position",
+            BodyEdit::Velocity(_) => "velocity",
+            BodyEdit::Appearance(_) => "appearance",
+        }
+    }
+}
+
+impl SimulationBody {
+    pub fn from_object(id: BodyId, object: &SimulationObject) -> Self {
+        let enter = &object.enter_configuration;
+        Self {
+            id,
+            id_name: object.id_name.clone(),
+            mass: object.physics_data.simulation_body_mass as f64,
+            radius: object.physics_data.simulation_body_radius as f64,
+            position: DVec3::from_array(enter.simulation_enter_position.map(f64::from)),
+            velocity: DVec3::from_array(enter.simulation_enter_speed.map(f64::from)),
+            appearance: object.appearance.clone(),
+        }
+    }
+
+    /* the current state as a scenario body, rounded to the f32 of the file format */
+    pub fn to_object(&self) -> SimulationObject {
+        SimulationObject {
+            id_name: self.id_name.clone(),
+            physics_data: SimulationPhysicsObject::new(self.mass as f32, self.radius as f32),
+            enter_configuration: SimulationEnterConfiguration::new(
+                self.velocity.as_vec3().to_array(),
+                self.position.as_vec3().to_array(),
+            ),
+            appearance: self.appearance.clone(),
+        }
+    }
+}
+
+impl SimulationState {
+    pub fn new(timestep: f64) -> Self {
+        Self {
+            bodies: Vec::new(),
+            simulation_time: 0.,
+            timestep,
+            adaptive_timestep: None,
+            error_estimator: None,
+            diagnostics: SimulationDiagnostics::default(),
+            trail_configuration: trail::TrailConfiguration::default(),
+            plot_sample_interval: plots::DEFAULT_PLOT_SAMPLE_INTERVAL,
+            dirty: false,
+            trails: Arc::default(),
+            next_trail_sample: 0.,
+            plots: Arc::default(),
+            next_plot_sample: 0.,
+            next_body_id: 0,
+            attractors: FastHashMap::default(),
+            contacts: FastHashSet::default(),
+            events: Vec::new(),
+            error_warning_emitted: false,
+            history: history::EditHistory::default(),
+            recording: None,
+        }
+    }
+
+    pub fn from_objects(objects: &[SimulationObject], timestep: f64) -> Self {
+        let mut state = Self::new(timestep);
+        state.add_bodies(objects);
+        state
+    }
+
+    /* the bodies of a quicksave with their ids, so the camera and the selection still find them.
+    the trails and plots start over from the restored time */
+    pub fn restore(&mut self, bodies: Vec<SimulationBody>, simulation_time: f64) {
+        self.next_body_id = bodies.iter().map(|body| body.id.0 + 1).max().unwrap_or(0);
+        self.bodies = bodies;
+        self.simulation_time = simulation_time;
+        self.trails = Arc::default();
+        self.next_trail_sample = simulation_time;
+        self.plots = Arc::default();
+        self.next_plot_sample = simulation_time;
+        self.contacts.clear();
+        self.attractors.clear();
+        self.refresh_attractors(false);
+    }
+
+    /* a name that is already taken gets a number appended */
+    pub fn add_body(&mut self, object: &SimulationObject) -> BodyId {
+        let id = self.allocate_body_id();
+        let mut body = SimulationBody::from_object(id, object);
+        body.id_name = self.unique_name(&body.id_name, None);
+        self.bodies.push(body);
+        self.refresh_attractors(false);
+        id
+    }
+
+    /* appended in order with a single refresh of the attractors, for tables of many bodies */
+    pub fn add_bodies(&mut self, objects: &[SimulationObject]) -> Vec<BodyId> {
+        /* the names in a set, looking each up in the bodies would be quadratic */
+        let mut taken: FastHashSet<String> = self
+            .bodies
+            .iter()
+            .map(|body| body.id_name.clone())
+            .collect();
+        let ids = objects
+            .iter()
+            .map(|object| {
+                let id = self.allocate_body_id();
+                let mut body = SimulationBody::from_object(id, object);
+                body.id_name = self::free_name(&body.id_name, |name| taken.contains(name));
+                taken.insert(body.id_name.clone());
+                self.bodies.push(body);
+                id
+            })
+            .collect();
+        self.refresh_attractors(false);
+        ids
+    }
+
+    /* the enter position and speed of the object are offsets from the parent, so a body can be
+    placed in orbit of another one without f32 precision loss at astronomical distances */
+    pub fn add_body_relative(
+        &mut self,
+        object: &SimulationObject,
+        parent: BodyId,
+    ) -> Option<BodyId> {
+        let parent = self.bodies.iter().find(|body| body.id == parent)?;
+        let (position, velocity) = (parent.position, parent.velocity);
+        let id = self.allocate_body_id();
+        let mut body = SimulationBody::from_object(id, object);
+        body.id_name = self.unique_name(&body.id_name, None);
+        body.position += position;
+        body.velocity += velocity;
+        self.bodies.push(body);
+        self.refresh_attractors(false);
+        Some(id)
+    }
+
+    pub fn remove_body(&mut self, id: BodyId) -> Option<SimulationBody> {
+        let index = self.bodies.iter().position(|body| body.id == id)?;
+        self.attractors.remove(&id);
+        self.contacts
+            .retain(|(first, second)| *first != id && *second != id);
+        Arc::make_mut(&mut self.trails).remove(&id);
+        self.events.push(SimulationEvent::BodyRemoved(id));
+        Some(self.bodies.remove(index))
+    }
+
+    /* puts a removed body back with its id, at its old place in the list when that still exists */
+    pub fn insert_body(&mut self, mut body: SimulationBody, index: usize) {
+        body.id_name = self.unique_name(&body.id_name, Some(body.id));
+        let index = index.min(self.bodies.len());
+        self.bodies.insert(index, body);
+        self.refresh_attractors(false);
+    }
+
+    /* the bodies of an undone import again, from the index the first one had */
+    pub fn insert_bodies(&mut self, bodies: Vec<SimulationBody>, index: usize) {
+        let index = index.min(self.bodies.len());
+        for (offset, mut body) in bodies.into_iter().enumerate() {
+            body.id_name = self.unique_name(&body.id_name, Some(body.id));
+            self.bodies.insert(index + offset, body);
+        }
+        self.refresh_attractors(false);
+    }
+
+    /* false when there is nothing to undo */
+    pub fn undo(&mut self) -> bool {
+        let Some(command) = self.history.undo() else {
+            return false;
+        };
+        match command {
+            history::EditCommand::Edit { id, before, .. } => {
+                self.edit_body(id, before);
+            }
+            history::EditCommand::Add { body, .. } => {
+                self.remove_body(body.id);
+            }
+            history::EditCommand::Remove { body, index } => self.insert_body(body, index),
+            history::EditCommand::Import { bodies, .. } => {
+                for body in bodies {
+                    self.remove_body(body.id);
+                }
+            }
+        }
+        self.dirty = true;
+        true
+    }
+
+    /* false when there is nothing to redo */
+    pub fn redo(&mut self) -> bool {
+        let Some(command) = self.history.redo() else {
+            return false;
+        };
+        match command {
+            history::EditCommand::Edit { id, after, .. } => {
+                self.edit_body(id, after);
+            }
+            history::EditCommand::Add { body, index } => self.insert_body(body, index),
+            history::EditCommand::Remove { body, .. } => {
+                self.remove_body(body.id);
+            }
+            history::EditCommand::Import { bodies, index, .. } => self.insert_bodies(bodies, index),
+        }
+        self.dirty = true;
+        true
+    }
+
+    /* false for an unknown body. mass and position move the spheres of influence, which are
+    refreshed silently like after adding a body */
+    pub fn edit_body(&mut self, id: BodyId, edit: BodyEdit) -> bool {
+        let Some(index) = self.bodies.iter().position(|body| body.id == id) else {
+            return false;
+        };
+        let refresh_attractors = matches!(edit, BodyEdit::Mass(_) | BodyEdit::Position(_));
+        let body = &mut self.bodies[index];
+        match edit {
+            BodyEdit::Name(name) => {
+                let name = self.unique_name(&name, Some(id));
+                self.bodies[index].id_name = name;
+            }
+            BodyEdit::Mass(mass) => body.mass = mass.max(0.),
+            BodyEdit::Radius(radius) => body.radius = radius.max(0.),
+            BodyEdit::Position(position) => body.position = position,
+            BodyEdit::Velocity(velocity) => body.velocity = velocity,
+            BodyEdit::Appearance(appearance) => body.appearance = appearance,
+        }
+        if refresh_attractors {
+            self.refresh_attractors(false);
+        }
+        self.dirty = true;
+        true
+    }
+
+    /* takes the mass, radius and appearance of the objects onto the bodies of the same name, their
+    motion stays as simulated. the state stays as dirty as it was, returns the bodies matched */
+    #[cfg(feature = "scenario-hot-reload")]
+    pub fn tweak_bodies(&mut self, objects: &[SimulationObject]) -> usize {
+        let dirty = self.dirty;
+        let mut tweaked = 0;
+        for object in objects {
+            let Some(id) = self
+                .bodies
+                .iter()
+                .find(|body| body.id_name == object.id_name)
+                .map(|body| body.id)
+            else {
+                continue;
+            };
+            let template = SimulationBody::from_object(id, object);
+            self.edit_body(id, BodyEdit::Mass(template.mass));
+            self.edit_body(id, BodyEdit::Radius(template.radius));
+            self.edit_body(id, BodyEdit::Appearance(template.appearance));
+            tweaked += 1;
+        }
+        self.dirty = dirty;
+        tweaked
+    }
+
+    /* "Moon", "Moon 2", "Moon 3", ... ignoring the name of the body being renamed */
+    pub fn unique_name(&self, name: &str, renamed: Option<BodyId>) -> String {
+        self::free_name(name, |candidate| {
+            self.bodies
+                .iter()
+                .any(|body| Some(body.id) != renamed && body.id_name == candidate)
+        })
+    }
+
+    pub fn allocate_body_id(&mut self) -> BodyId {
+        let id = BodyId(self.next_body_id);
+        self.next_body_id += 1;
+        id
+    }
+
+    /* sphere of influence radius of a body relative to its primary, None for the root attractor */
+    pub fn sphere_of_influence(&self, id: BodyId) -> Option<f64> {
+        soi::compute_hierarchy(&self.bodies)
+            .get(&id)
+            .and_then(|node| node.radius)
+    }
+
+    /* innermost sphere of influence (other than its own) that contains the body */
+    pub fn dominant_attractor(&self, id: BodyId) -> Option<BodyId> {
+        let hierarchy = soi::compute_hierarchy(&self.bodies);
+        soi::dominant_attractor(&self.bodies, &hierarchy, id)
+    }
+
+    pub fn drain_events(&mut self) -> Vec<SimulationEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /* advances the state by one fixed timestep */
+    pub fn step(&mut self) {
+        if let Some(configuration) = self.error_estimator
+            && self
+                .diagnostics
+                .step_count
+                .is_multiple_of(configuration.sample_interval.max(1))
+        {
+            self.diagnostics.latest_error_estimate =
+                error_estimate::estimate_step_error(&self.bodies, self.timestep);
+            self.check_error_tolerance(configuration.tolerance);
+        }
+        match self.adaptive_timestep {
+            Some(configuration) => self.adaptive_substeps(&configuration),
+            None => leapfrog_step(&mut self.bodies, self.timestep),
+        }
+        self.simulation_time += self.timestep;
+        self.diagnostics.step_count += 1;
+        self.refresh_attractors(true);
+        self.detect_collisions();
+        if self.simulation_time >= self.next_trail_sample {
+            self.record_trails();
+        }
+        if self.simulation_time >= self.next_plot_sample {
+            self.record_plots();
+        }
+        if let Some(recording) = self.recording.as_ref() {
+            recording
+                .lock()
+                .unwrap()
+                .record(self.simulation_time, &self.bodies);
+        }
+    }
+
+    pub fn trails(&self) -> Arc<FastHashMap<BodyId, trail::Trail>> {
+        self.trails.clone()
+    }
+
+    pub fn plots(&self) -> Arc<plots::DiagnosticPlots> {
+        self.plots.clone()
+    }
+
+    /* the body whose speed and altitude are plotted, usually the selected one */
+    pub fn set_plotted_body(&mut self, body: Option<BodyId>) {
+        if self.plots.body() != body {
+            Arc::make_mut(&mut self.plots).set_body(body);
+        }
+    }
+
+    fn record_plots(&mut self) {
+        let attractors = &self.attractors;
+        Arc::make_mut(&mut self.plots)
+            .record(&self.bodies, |id| attractors.get(&id).copied().flatten());
+        self.next_plot_sample = self.simulation_time + self.plot_sample_interval.max(self.timestep);
+    }
+
+    fn record_trails(&mut self) {
+        let capacity = self.trail_configuration.capacity;
+        let trails = Arc::make_mut(&mut self.trails);
+        self.bodies.iter().for_each(|body| {
+            trails
+                .entry(body.id)
+                .or_insert_with(|| trail::Trail::new(capacity))
+                .push(body.position);
+        });
+        self.next_trail_sample =
+            self.simulation_time + self.trail_configuration.sample_interval.max(self.timestep);
+    }
+
+    /* sub-steps accumulate to exactly one tick so presentation timing is unaffected */
+    fn adaptive_substeps(&mut self, configuration: &adaptive::AdaptiveTimestepConfiguration) {
+        let mut remaining = self.timestep;
+        let mut substep_count = 0;
+        let mut smallest_substep = f64::INFINITY;
+        while remaining > self.timestep * f64::EPSILON {
+            let dt = adaptive::suggest_timestep(&self.bodies, configuration).min(remaining);
+            leapfrog_step(&mut self.bodies, dt);
+            remaining -= dt;
+            substep_count += 1;
+            smallest_substep = smallest_substep.min(dt);
+        }
+        self.diagnostics.latest_substep_count = substep_count;
+        self.diagnostics.latest_smallest_substep =
+            smallest_substep.is_finite().then_some(smallest_substep);
+    }
+
+    /* warns only once per state so a bad timestep doesn't flood the event queue */
+    fn check_error_tolerance(&mut self, tolerance: f64) {
+        if let Some(estimate) = self.diagnostics.latest_error_estimate
+            && estimate > tolerance
+            && !self.error_warning_emitted
+        {
+            self.error_warning_emitted = true;
+            self.events.push(SimulationEvent::IntegrationErrorExceeded {
+                estimate,
+                tolerance,
+            });
+        }
+    }
+
+    fn detect_collisions(&mut self) {
+        let mut contacts = FastHashSet::default();
+        for (index, body) in self.bodies.iter().enumerate() {
+            for other in &self.bodies[index + 1..] {
+                let offset = other.position - body.position;
+                let distance = offset.length();
+                if distance >= body.radius + other.radius {
+                    continue;
+                }
+                let (first, second) = match body.id < other.id {
+                    true => (body, other),
+                    false => (other, body),
+                };
+                let pair = (first.id, second.id);
+                contacts.insert(pair);
+                if self.contacts.contains(&pair) {
+                    continue;
+                }
+                let normal = (second.position - first.position)
+                    .try_normalize()
+                    .unwrap_or(DVec3::Y);
+                /* between the two surfaces, weighted by the radii */
+                let point = first.position
+                    + normal * (distance * first.radius / (first.radius + second.radius));
+                self.events.push(SimulationEvent::BodiesCollided {
+                    first: first.id,
+                    second: second.id,
+                    point,
+                    normal,
+                    relative_speed: (second.velocity - first.velocity).length(),
+                });
+            }
+        }
+        self.contacts = contacts;
+    }
+
+    fn refresh_attractors(&mut self, emit_events: bool) {
+        let hierarchy = soi::compute_hierarchy(&self.bodies);
+        let attractors = soi::dominant_attractors(&self.bodies, &hierarchy);
+        for (body, current) in self.bodies.iter().zip(attractors) {
+            let previous = self.attractors.insert(body.id, current);
+            if !emit_events || previous.is_none_or(|previous| previous == current) {
+                continue;
+            }
+            if let Some(attractor) = previous.flatten() {
+                self.events.push(SimulationEvent::SphereOfInfluenceExited {
+                    body: body.id,
+                    attractor,
+                });
+            }
+            if let Some(attractor) = current {
+                self.events.push(SimulationEvent::SphereOfInfluenceEntered {
+                    body: body.id,
+                    attractor,
+                });
+            }
+        }
+    }
+}
+
+/* the name, or the name with the first number appended that is not taken */
+fn free_name(name: &str, taken: impl Fn(&str) -> bool) -> String {
+    if !taken(name) {
+        return name.to_string();
+    }
+    (2..)
+        .map(|number| format!("{} {}", name, number))
+        .find(|candidate| !taken(candidate))
+        .expect("some number is free")
+}
+
+/* sun, earth and moon on the ecliptic (xz) plane, used until scenarios can be loaded from disk */
+pub fn sun_earth_moon_objects() -> Vec<SimulationObject> {
+    vec![
+        SimulationObject {
+            appearance: BodyAppearance {
+                emissive: true,
+                tags: vec!["star".to_string()],
+                ..Default::default()
+            },
+            ..SimulationObject::new(
+                "Sun".to_string(),
+                SimulationPhysicsObject::new(1.989e30, 6.957e8),
+                SimulationEnterConfiguration::new([0., 0., 0.], [0., 0., 0.]),
+            )
+        },
+        SimulationObject {
+            appearance: BodyAppearance {
+                tags: vec!["planet".to_string()],
+                ..Default::default()
+            },
+            ..SimulationObject::new(
+                "Earth".to_string(),
+                SimulationPhysicsObject::new(5.972e24, 6.371e6),
+                SimulationEnterConfiguration::new([0., 0., 29_780.], [1.496e11, 0., 0.]),
+            )
+        },
+        SimulationObject {
+            appearance: BodyAppearance {
+                tags: vec!["moon".to_string()],
+                ..Default::default()
+            },
+            ..SimulationObject::new(
+                "Moon".to_string(),
+                SimulationPhysicsObject::new(7.342e22, 1.737e6),
+                SimulationEnterConfiguration::new([0., 0., 30_802.], [1.499_844e11, 0., 0.]),
+            )
+        },
+    ]
+}
+
+/* depth precision test: a five meter probe beside the earth-moon pair, seen from the solar
+system scale framing. the moon passes in front of the earth roughly twice a month */
+pub fn extreme_scale_objects() -> Vec<SimulationObject> {
+    let mut objects = self::sun_earth_moon_objects();
+    objects.push(SimulationObject {
+        appearance: BodyAppearance {
+            tags: vec!["spacecraft".to_string()],
+            ..Default::default()
+        },
+        ..SimulationObject::new(
+            "Probe".to_string(),
+            SimulationPhysicsObject::new(500., 5.),
+            SimulationEnterConfiguration::new([0., 0., 29_780.], [1.496e11, 2e6, 1e9]),
+        )
+    });
+    objects
+}
+
+/* a saturn analogue with rings and its real axial tilt next to the sun, for the ring renderer */
+pub fn ringed_planet_objects() -> Vec<SimulationObject> {
+    let tilt = 26.73f32.to_radians();
+    let mut objects = self::sun_earth_moon_objects();
+    objects.truncate(1);
+    objects.push(SimulationObject {
+        appearance: BodyAppearance {
+            color: Some([0.82, 0.7, 0.48]),
+            tags: vec!["planet".to_string()],
+            spin_axis: Some([tilt.sin(), tilt.cos(), 0.]),
+            rings: Some(RingDefinition {
+                inner_radius: 1.24,
+                outer_radius: 2.27,
+                texture: None,
+                bands: Vec::new(),
+                opacity: 0.9,
+            }),
+            ..Default::default()
+        },
+        ..SimulationObject::new(
+            "Saturn".to_string(),
+            SimulationPhysicsObject::new(5.683e26, 5.8232e7),
+            SimulationEnterConfiguration::new([0., 0., 9_620.], [1.4335e12, 0., 0.]),
+        )
+    });
+    objects
+}
+
+/* kick-drift-kick leapfrog, symplectic and cheap (two force evaluations per step) */
+pub fn leapfrog_step(bodies: &mut [SimulationBody], dt: f64) {
+    let accelerations = compute_accelerations(bodies);
+    bodies
+        .iter_mut()
+        .zip(accelerations.iter())
+        .for_each(|(body, acceleration)| {
+            body.velocity += *acceleration * (dt * 0.5);
+            body.position += body.velocity * dt;
+        });
+    let accelerations = compute_accelerations(bodies);
+    bodies
+        .iter_mut()
+        .zip(accelerations.iter())
+        .for_each(|(body, acceleration)| body.velocity += *acceleration * (dt * 0.5));
+}
+
+/* net gravitational acceleration of a single body, for inspecting without a full evaluation */
+pub fn acceleration_on(bodies: &[SimulationBody], index: usize) -> DVec3 {
+    let body = &bodies[index];
+    bodies
+        .iter()
+        .enumerate()
+        .filter(|(other_index, _)| *other_index != index)
+        .fold(DVec3::ZERO, |acceleration, (_, other)| {
+            let offset = other.position - body.position;
+            let distance_squared = offset.length_squared();
+            if distance_squared == 0. {
+                return acceleration;
+            }
+            acceleration
+                + offset / distance_squared.sqrt()
+                    * (GRAVITATIONAL_CONSTANT * other.mass / distance_squared)
+        })
+}
+
+pub fn compute_accelerations(bodies: &[SimulationBody]) -> Vec<DVec3> {
+    let mut accelerations = vec![DVec3::ZERO; bodies.len()];
+    for i in 0..bodies.len() {
+        for j in (i + 1)..bodies.len() {
+            let offset = bodies[j].position - bodies[i].position;
+            let distance_squared = offset.length_squared();
+            if distance_squared == 0. {
+                continue;
+            }
+            let direction = offset / distance_squared.sqrt();
+            let magnitude = GRAVITATIONAL_CONSTANT / distance_squared;
+            accelerations[i] += direction * magnitude * bodies[j].mass;
+            accelerations[j] -= direction * magnitude * bodies[i].mass;
+        }
+    }
+    accelerations
+}
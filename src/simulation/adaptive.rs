@@ -0,0 +1,131 @@
+use crate::simulation::{GRAVITATIONAL_CONSTANT, SimulationBody};
+
+/* lower bound for any configured minimum so the controller can never stall on a zero timestep */
+pub const SMALLEST_ADAPTIVE_TIMESTEP: f64 = 1e-6;
+
+#[derive(Debug, Clone, Copy, derive_new::new)]
+pub struct AdaptiveTimestepConfiguration {
+    pub min_timestep: f64,
+    pub max_timestep: f64,
+    /* fraction of the shortest pair timescale used as timestep */
+    pub safety_factor: f64,
+}
+
+impl AdaptiveTimestepConfiguration {
+    pub fn bounds(&self) -> (f64, f64) {
+        let min = self.min_timestep.max(SMALLEST_ADAPTIVE_TIMESTEP);
+        (min, self.max_timestep.max(min))
+    }
+}
+
+/* the shortest of the crossing time (distance / relative speed) and the free-fall time
+ * sqrt(distance / acceleration) over all pairs, scaled by the safety factor and clamped */
+pub fn suggest_timestep(
+    bodies: &[SimulationBody],
+    configuration: &AdaptiveTimestepConfiguration,
+) -> f64 {
+    let (min, max) = configuration.bounds();
+    let mut shortest = f64::INFINITY;
+    for i in 0..bodies.len() {
+        for j in (i + 1)..bodies.len() {
+            let distance = bodies[i].position.distance(bodies[j].position);
+            if distance == 0. {
+                continue;
+            }
+            let relative_speed = bodies[i].velocity.distance(bodies[j].velocity);
+            if relative_speed > 0. {
+                shortest = shortest.min(distance / relative_speed);
+            }
+            let acceleration =
+                GRAVITATIONAL_CONSTANT * (bodies[i].mass + bodies[j].mass) / (distance * distance);
+            if acceleration > 0. {
+                shortest = shortest.min((distance / acceleration).sqrt());
+            }
+        }
+    }
+    let suggested = shortest * configuration.safety_factor;
+    if suggested.is_nan() {
+        return max;
+    }
+    suggested.clamp(min, max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{
+        SimulationEnterConfiguration, SimulationObject, SimulationPhysicsObject, SimulationState,
+        plots,
+    };
+
+    const SOLAR_MASS: f64 = 1.989e30;
+    const ASTRONOMICAL_UNIT: f64 = 1.496e11;
+    const DAY: f64 = 86400.;
+
+    /* a sun grazer of eccentricity 0.98 started at its aphelion of 2 au, its perihelion passage
+    takes hours while the tick is a day */
+    fn comet(adaptive_timestep: Option<AdaptiveTimestepConfiguration>) -> SimulationState {
+        let (perihelion, aphelion) = (0.02 * ASTRONOMICAL_UNIT, 2. * ASTRONOMICAL_UNIT);
+        let speed = (GRAVITATIONAL_CONSTANT * SOLAR_MASS * 2. * perihelion
+            / (aphelion * (aphelion + perihelion)))
+            .sqrt();
+        let objects = [
+            SimulationObject::new(
+                "Sun".to_string(),
+                SimulationPhysicsObject::new(SOLAR_MASS as f32, 6.957e8),
+                SimulationEnterConfiguration::new([0., 0., 0.], [0., 0., 0.]),
+            ),
+            SimulationObject::new(
+                "Comet".to_string(),
+                SimulationPhysicsObject::new(1e13, 5e3),
+                SimulationEnterConfiguration::new(
+                    [0., 0., speed as f32],
+                    [aphelion as f32, 0., 0.],
+                ),
+            ),
+        ];
+        let mut state = SimulationState::from_objects(&objects, DAY);
+        state.adaptive_timestep = adaptive_timestep;
+        state
+    }
+
+    /* the largest relative deviation from the initial energy over one orbit */
+    fn energy_spike(mut state: SimulationState) -> f64 {
+        let initial = plots::total_energy(&state.bodies);
+        (0..400)
+            .map(|_| {
+                state.step();
+                ((plots::total_energy(&state.bodies) - initial) / initial).abs()
+            })
+            .fold(0., f64::max)
+    }
+
+    #[test]
+    fn adaptive_run_resolves_the_perihelion() {
+        let fixed = self::energy_spike(self::comet(None));
+        let adaptive = self::energy_spike(self::comet(Some(AdaptiveTimestepConfiguration::new(
+            1., DAY, 0.01,
+        ))));
+        assert!(fixed > 1., "the fixed run shows no spike: {}", fixed);
+        assert!(adaptive < 1e-2, "the adaptive run spikes: {}", adaptive);
+    }
+
+    #[test]
+    fn timestep_is_clamped_to_the_bounds() {
+        let configuration = AdaptiveTimestepConfiguration::new(10., 100., 0.01);
+        let state = self::comet(None);
+        assert_eq!(suggest_timestep(&state.bodies, &configuration), 100.);
+        assert_eq!(suggest_timestep(&state.bodies[..1], &configuration), 100.);
+        let tight = AdaptiveTimestepConfiguration::new(10., 100., 1e-9);
+        assert_eq!(suggest_timestep(&state.bodies, &tight), 10.);
+    }
+
+    #[test]
+    fn bounds_never_reach_zero() {
+        let configuration = AdaptiveTimestepConfiguration::new(0., -1., 1.);
+        assert_eq!(
+            configuration.bounds(),
+            (SMALLEST_ADAPTIVE_TIMESTEP, SMALLEST_ADAPTIVE_TIMESTEP)
+        );
+    }
+}
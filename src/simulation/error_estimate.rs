@@ -0,0 +1,97 @@
+use crate::simulation::{self, SimulationBody};
+
+#[derive(Debug, Clone, Copy, derive_new::new)]
+pub struct ErrorEstimatorConfiguration {
+    /* the estimate triples force evaluations, so only every n-th step is sampled */
+    pub sample_interval: u64,
+    pub tolerance: f64,
+}
+
+impl Default for ErrorEstimatorConfiguration {
+    fn default() -> Self {
+        Self {
+            sample_interval: 100,
+            tolerance: 1e-6,
+        }
+    }
+}
+
+/* step doubling: compares one full step against two half steps. the discrepancy of every body is
+ * taken relative to the distance to its nearest neighbour so the value is scale independent */
+pub fn estimate_step_error(bodies: &[SimulationBody], dt: f64) -> Option<f64> {
+    if bodies.len() < 2 {
+        return None;
+    }
+    let mut full_step = bodies.to_vec();
+    simulation::leapfrog_step(&mut full_step, dt);
+    let mut half_steps = bodies.to_vec();
+    simulation::leapfrog_step(&mut half_steps, dt * 0.5);
+    simulation::leapfrog_step(&mut half_steps, dt * 0.5);
+
+    full_step
+        .iter()
+        .zip(half_steps.iter())
+        .enumerate()
+        .map(|(index, (full, half))| {
+            let separation = nearest_neighbour_distance(&half_steps, index);
+            full.position.distance(half.position) / separation
+        })
+        .filter(|error| error.is_finite())
+        .reduce(f64::max)
+}
+
+fn nearest_neighbour_distance(bodies: &[SimulationBody], index: usize) -> f64 {
+    bodies
+        .iter()
+        .enumerate()
+        .filter(|(other, _)| *other != index)
+        .map(|(_, other)| other.position.distance(bodies[index].position))
+        .fold(f64::INFINITY, f64::min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{SimulationEvent, SimulationState};
+
+    /* the earth and the moon sampled on every step, a tick of days folds the orbit of the moon */
+    fn run(timestep: f64, steps: usize) -> (SimulationState, Vec<SimulationEvent>) {
+        let mut state =
+            SimulationState::from_objects(&simulation::sun_earth_moon_objects(), timestep);
+        state.error_estimator = Some(ErrorEstimatorConfiguration::new(1, 1e-6));
+        let mut events = Vec::new();
+        for _ in 0..steps {
+            state.step();
+            events.extend(state.drain_events());
+        }
+        (state, events)
+    }
+
+    fn warnings(events: &[SimulationEvent]) -> usize {
+        events
+            .iter()
+            .filter(|event| matches!(event, SimulationEvent::IntegrationErrorExceeded { .. }))
+            .count()
+    }
+
+    #[test]
+    fn huge_timestep_trips_the_warning_once() {
+        let (state, events) = self::run(5. * 86400., 10);
+        assert_eq!(self::warnings(&events), 1);
+        assert!(state.diagnostics.latest_error_estimate.unwrap() > 1e-6);
+    }
+
+    #[test]
+    fn small_timestep_stays_quiet() {
+        let (state, events) = self::run(60., 10);
+        assert_eq!(self::warnings(&events), 0);
+        assert!(state.diagnostics.latest_error_estimate.unwrap() < 1e-6);
+    }
+
+    #[test]
+    fn single_body_has_no_estimate() {
+        let bodies =
+            SimulationState::from_objects(&simulation::sun_earth_moon_objects()[..1], 1.).bodies;
+        assert_eq!(estimate_step_error(&bodies, 1.), None);
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::VecDeque;
+
+use crate::simulation::{BodyEdit, BodyId, SimulationBody};
+
+/* oldest entries are dropped beyond this many */
+pub const HISTORY_DEPTH: usize = 100;
+
+/* a scenario edit made through the ui, holding what is needed to apply it in both directions */
+#[derive(Debug, Clone)]
+pub enum EditCommand {
+    Edit {
+        id: BodyId,
+        /* the body name when the edit was made, only used for the description */
+        name: String,
+        before: BodyEdit,
+        after: BodyEdit,
+    },
+    Add {
+        body: SimulationBody,
+        index: usize,
+    },
+    Remove {
+        body: SimulationBody,
+        index: usize,
+    },
+    /* bodies appended together, undone in one step. the index is the one of the first */
+    Import {
+        bodies: Vec<SimulationBody>,
+        index: usize,
+        /* the file name, only used for the description */
+        source: String,
+    },
+}
+
+impl EditCommand {
+    /* "Edit mass of Earth", shown next to undo and redo */
+    pub fn description(&self) -> String {
+        match self {
+            EditCommand::Edit { name, after, .. } => {
+                format!("Edit {} of {}", after.field_name(), name)
+            }
+            EditCommand::Add { body, .. } => format!("Add {}", body.id_name),
+            EditCommand::Remove { body, .. } => format!("Delete {}", body.id_name),
+            EditCommand::Import { bodies, source, .. } => {
+                format!("Import {} bodies from {}", bodies.len(), source)
+            }
+        }
+    }
+}
+
+/* undo and redo stacks of the simulation thread. a drag sends an edit every frame, those merge
+into the newest entry while it is open, so undoing a drag restores the value before it */
+#[derive(Debug, Default)]
+pub struct EditHistory {
+    undo: VecDeque<EditCommand>,
+    redo: Vec<EditCommand>,
+    /* the newest entry still takes further edits of the same field of the same body */
+    open: bool,
+}
+
+impl EditHistory {
+    /* a new edit makes the undone ones unreachable */
+    pub fn record(&mut self, command: EditCommand) {
+        self.redo.clear();
+        if self.open
+            && let (
+                Some(EditCommand::Edit {
+                    id: open_id,
+                    after: open_after,
+                    ..
+                }),
+                EditCommand::Edit { id, after, .. },
+            ) = (self.undo.back_mut(), &command)
+            && *open_id == *id
+            && open_after.same_field(after)
+        {
+            *open_after = after.clone();
+            return;
+        }
+        self.open = matches!(command, EditCommand::Edit { .. });
+        self.undo.push_back(command);
+        if self.undo.len() > HISTORY_DEPTH {
+            self.undo.pop_front();
+        }
+    }
+
+    /* closes the newest entry, the next edit starts a new one */
+    pub fn finish(&mut self) {
+        self.open = false;
+    }
+
+    /* the returned command is to be reverted by the caller */
+    pub fn undo(&mut self) -> Option<EditCommand> {
+        self.open = false;
+        let command = self.undo.pop_back()?;
+        self.redo.push(command.clone());
+        Some(command)
+    }
+
+    /* the returned command is to be applied again by the caller */
+    pub fn redo(&mut self) -> Option<EditCommand> {
+        self.open = false;
+        let command = self.redo.pop()?;
+        self.undo.push_back(command.clone());
+        Some(command)
+    }
+
+    pub fn undo_description(&self) -> Option<String> {
+        self.undo.back().map(EditCommand::description)
+    }
+
+    pub fn redo_description(&self) -> Option<String> {
+        self.redo.last().map(EditCommand::description)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::DVec3;
+
+    use super::*;
+
+    fn mass(id: u32, before: f64, after: f64) -> EditCommand {
+        EditCommand::Edit {
+            id: BodyId(id),
+            name: "Earth".to_string(),
+            before: BodyEdit::Mass(before),
+            after: BodyEdit::Mass(after),
+        }
+    }
+
+    fn add(name: &str) -> EditCommand {
+        EditCommand::Add {
+            body: SimulationBody {
+                id: BodyId(7),
+                id_name: name.to_string(),
+                mass: 1.,
+                radius: 1.,
+                position: DVec3::ZERO,
+                velocity: DVec3::ZERO,
+                appearance: Default::default(),
+            },
+            index: 0,
+        }
+    }
+
+    fn masses(command: Option<EditCommand>) -> Option<(f64, f64)> {
+        match command? {
+            EditCommand::Edit {
+                before: BodyEdit::Mass(before),
+                after: BodyEdit::Mass(after),
+                ..
+            } => Some((before, after)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn a_drag_coalesces_into_one_entry() {
+        let mut history = EditHistory::default();
+        history.record(self::mass(0, 1., 2.));
+        history.record(self::mass(0, 2., 3.));
+        history.record(self::mass(0, 3., 4.));
+        history.finish();
+        /* after the drag ended the next edit is an entry of its own */
+        history.record(self::mass(0, 4., 5.));
+        assert_eq!(self::masses(history.undo()), Some((4., 5.)));
+        assert_eq!(self::masses(history.undo()), Some((1., 4.)));
+        assert!(history.undo().is_none());
+    }
+
+    #[test]
+    fn other_fields_or_bodies_start_a_new_entry() {
+        let mut history = EditHistory::default();
+        history.record(self::mass(0, 1., 2.));
+        history.record(self::mass(1, 1., 2.));
+        history.record(EditCommand::Edit {
+            id: BodyId(1),
+            name: "Moon".to_string(),
+            before: BodyEdit::Radius(1.),
+            after: BodyEdit::Radius(2.),
+        });
+        assert_eq!(
+            history.undo_description().as_deref(),
+            Some("Edit radius of Moon")
+        );
+        history.undo();
+        assert_eq!(self::masses(history.undo()), Some((1., 2.)));
+        assert_eq!(self::masses(history.undo()), Some((1., 2.)));
+        assert!(history.undo().is_none());
+    }
+
+    #[test]
+    fn an_add_between_edits_breaks_the_coalescing() {
+        let mut history = EditHistory::default();
+        history.record(self::mass(0, 1., 2.));
+        history.record(self::add("Probe"));
+        history.record(self::mass(0, 2., 3.));
+        assert_eq!(self::masses(history.undo()), Some((2., 3.)));
+        assert_eq!(history.undo_description().as_deref(), Some("Add Probe"));
+    }
+
+    #[test]
+    fn undo_and_redo_move_between_the_stacks() {
+        let mut history = EditHistory::default();
+        history.record(self::mass(0, 1., 2.));
+        history.finish();
+        history.record(self::mass(0, 2., 3.));
+        history.undo();
+        assert_eq!(
+            history.redo_description().as_deref(),
+            Some("Edit mass of Earth")
+        );
+        assert_eq!(self::masses(history.redo()), Some((2., 3.)));
+        assert!(history.redo().is_none());
+        /* a redone entry is closed, an edit afterwards does not merge into it */
+        history.record(self::mass(0, 3., 4.));
+        assert_eq!(self::masses(history.undo()), Some((3., 4.)));
+        assert_eq!(self::masses(history.undo()), Some((2., 3.)));
+    }
+
+    #[test]
+    fn a_new_edit_invalidates_the_redo_stack() {
+        let mut history = EditHistory::default();
+        history.record(self::add("Probe"));
+        history.undo();
+        assert!(history.redo_description().is_some());
+        history.record(self::add("Probe 2"));
+        assert!(history.redo_description().is_none());
+        assert!(history.redo().is_none());
+    }
+
+    #[test]
+    fn the_oldest_entries_are_dropped() {
+        let mut history = EditHistory::default();
+        for index in 0..HISTORY_DEPTH + 5 {
+            history.record(self::add(&format!("Probe {}", index)));
+        }
+        let mut undone = 0;
+        while history.undo().is_some() {
+            undone += 1;
+        }
+        assert_eq!(undone, HISTORY_DEPTH);
+        assert_eq!(history.redo_description().as_deref(), Some("Add Probe 5"));
+    }
+}
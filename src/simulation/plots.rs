@@ -0,0 +1,167 @@
+use std::collections::VecDeque;
+
+use glam::DVec3;
+
+use crate::simulation::{BodyId, GRAVITATIONAL_CONSTANT, SimulationBody};
+
+/* one hour of simulation time between samples, 1024 samples cover six weeks */
+pub const DEFAULT_PLOT_SAMPLE_INTERVAL: f64 = 3600.;
+pub const PLOT_CAPACITY: usize = 1024;
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, strum_macros::EnumIter, strum_macros::Display,
+)]
+pub enum PlotSeries {
+    #[strum(to_string = "Total energy (J)")]
+    TotalEnergy,
+    #[strum(to_string = "Momentum (kg m/s)")]
+    Momentum,
+    #[strum(to_string = "Speed (m/s)")]
+    BodySpeed,
+    /* above the surface of the dominant attractor */
+    #[strum(to_string = "Altitude (m)")]
+    BodyAltitude,
+}
+
+impl PlotSeries {
+    /* drift only shows in the last digits of these */
+    pub fn conserved(self) -> bool {
+        matches!(self, PlotSeries::TotalEnergy | PlotSeries::Momentum)
+    }
+
+    /* of the plotted body rather than of the whole system */
+    pub fn per_body(self) -> bool {
+        matches!(self, PlotSeries::BodySpeed | PlotSeries::BodyAltitude)
+    }
+}
+
+/* ring buffer of samples, the oldest is dropped once the capacity is reached */
+#[derive(Debug, Clone)]
+pub struct PlotBuffer {
+    samples: VecDeque<f64>,
+    capacity: usize,
+}
+
+impl PlotBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, sample: f64) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    /* oldest first */
+    pub fn samples(&self) -> impl ExactSizeIterator<Item = &f64> + DoubleEndedIterator {
+        self.samples.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+}
+
+/* the diagnostics over time, shared with snapshots like the trails */
+#[derive(Debug, Clone)]
+pub struct DiagnosticPlots {
+    total_energy: PlotBuffer,
+    momentum: PlotBuffer,
+    body_speed: PlotBuffer,
+    body_altitude: PlotBuffer,
+    /* the body speed and altitude are recorded of */
+    body: Option<BodyId>,
+}
+
+impl Default for DiagnosticPlots {
+    fn default() -> Self {
+        Self::new(PLOT_CAPACITY)
+    }
+}
+
+impl DiagnosticPlots {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            total_energy: PlotBuffer::new(capacity),
+            momentum: PlotBuffer::new(capacity),
+            body_speed: PlotBuffer::new(capacity),
+            body_altitude: PlotBuffer::new(capacity),
+            body: None,
+        }
+    }
+
+    pub fn series(&self, series: PlotSeries) -> &PlotBuffer {
+        match series {
+            PlotSeries::TotalEnergy => &self.total_energy,
+            PlotSeries::Momentum => &self.momentum,
+            PlotSeries::BodySpeed => &self.body_speed,
+            PlotSeries::BodyAltitude => &self.body_altitude,
+        }
+    }
+
+    pub fn body(&self) -> Option<BodyId> {
+        self.body
+    }
+
+    /* the samples of the previous body are dropped */
+    pub fn set_body(&mut self, body: Option<BodyId>) {
+        if self.body != body {
+            self.body = body;
+            self.body_speed.clear();
+            self.body_altitude.clear();
+        }
+    }
+
+    /* the altitude is skipped while the body has no attractor */
+    pub fn record(
+        &mut self,
+        bodies: &[SimulationBody],
+        attractor: impl Fn(BodyId) -> Option<BodyId>,
+    ) {
+        self.total_energy.push(self::total_energy(bodies));
+        self.momentum.push(self::total_momentum(bodies).length());
+        let find = |id| bodies.iter().find(|body| body.id == id);
+        let Some(body) = self.body.and_then(find) else {
+            return;
+        };
+        self.body_speed.push(body.velocity.length());
+        if let Some(primary) = attractor(body.id).and_then(find) {
+            self.body_altitude
+                .push(body.position.distance(primary.position) - primary.radius);
+        }
+    }
+}
+
+/* kinetic plus pairwise potential energy */
+pub fn total_energy(bodies: &[SimulationBody]) -> f64 {
+    let kinetic = bodies
+        .iter()
+        .map(|body| 0.5 * body.mass * body.velocity.length_squared())
+        .sum::<f64>();
+    let potential = bodies
+        .iter()
+        .enumerate()
+        .flat_map(|(index, first)| {
+            bodies[index + 1..].iter().map(move |second| {
+                let distance = first.position.distance(second.position);
+                match distance > 0. {
+                    true => -GRAVITATIONAL_CONSTANT * first.mass * second.mass / distance,
+                    false => 0.,
+                }
+            })
+        })
+        .sum::<f64>();
+    kinetic + potential
+}
+
+pub fn total_momentum(bodies: &[SimulationBody]) -> DVec3 {
+    bodies.iter().map(|body| body.mass * body.velocity).sum()
+}
@@ -0,0 +1,359 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Read, Write},
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use anyhow::{Context, Result};
+use glam::DVec3;
+use tracing::{info, warn};
+use wgpu::naga::FastHashMap;
+
+use crate::simulation::{BodyId, SimulationBody, plots};
+
+/* the units are part of the names, the energy is the one of the whole system at that time */
+pub const TRAJECTORY_CSV_HEADER: &str = "time (s),body,position x (m),position y (m),position z (m),velocity x (m/s),velocity y (m/s),velocity z (m/s),total energy (J)";
+/* rows kept in memory, about 15 mb. the older ones go to a temporary file beyond that */
+pub const MAXIMUM_BUFFERED_ROWS: usize = 200_000;
+/* an hour of simulation time, recording is started with it when nothing else is chosen */
+pub const DEFAULT_RECORDING_INTERVAL: f64 = 3600.;
+
+/* numbers the temporary files of the recordings of one process */
+static SPILL_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone)]
+pub struct TrajectoryRow {
+    pub time: f64,
+    /* shared by the rows of a body until it is renamed */
+    pub body: Arc<str>,
+    pub position: DVec3,
+    pub velocity: DVec3,
+    pub energy: f64,
+}
+
+/* the body states every interval of simulation time, fed by the simulation thread after each
+step and exported from another one. the samples sit on multiples of the interval after the start,
+rounded up to the next step, so the warp does not change them */
+#[derive(Debug)]
+pub struct TrajectoryRecording {
+    interval: f64,
+    next_sample: f64,
+    rows: Vec<TrajectoryRow>,
+    names: FastHashMap<BodyId, Arc<str>>,
+    spill: Option<Spill>,
+    sample_count: usize,
+    row_count: usize,
+}
+
+#[derive(Debug)]
+struct Spill {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+/* what an export writes, taken from the recording so writing does not block the recording */
+pub struct TrajectoryExport {
+    /* the temporary file and how much of it belongs to the export, it keeps growing */
+    spilled: Option<(PathBuf, u64)>,
+    rows: Vec<TrajectoryRow>,
+}
+
+impl TrajectoryRecording {
+    pub fn new(interval: f64, start_time: f64) -> Self {
+        Self {
+            interval: interval.max(f64::MIN_POSITIVE),
+            next_sample: start_time,
+            rows: Vec::new(),
+            names: FastHashMap::default(),
+            spill: None,
+            sample_count: 0,
+            row_count: 0,
+        }
+    }
+
+    pub fn interval(&self) -> f64 {
+        self.interval
+    }
+
+    pub fn sample_count(&self) -> usize {
+        self.sample_count
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.row_count
+    }
+
+    /* some of the rows were moved to a temporary file */
+    pub fn spilled(&self) -> bool {
+        self.spill.is_some()
+    }
+
+    /* a sample once the time reached the next one, a long step skips the ones it passed */
+    pub fn record(&mut self, simulation_time: f64, bodies: &[SimulationBody]) {
+        if simulation_time < self.next_sample {
+            return;
+        }
+        let energy = plots::total_energy(bodies);
+        for body in bodies {
+            let name = self
+                .names
+                .entry(body.id)
+                .or_insert_with(|| Arc::from(body.id_name.as_str()));
+            if **name != *body.id_name {
+                *name = Arc::from(body.id_name.as_str());
+            }
+            self.rows.push(TrajectoryRow {
+                time: simulation_time,
+                body: name.clone(),
+                position: body.position,
+                velocity: body.velocity,
+                energy,
+            });
+        }
+        self.sample_count += 1;
+        self.row_count += bodies.len();
+        let passed = ((simulation_time - self.next_sample) / self.interval).floor() + 1.;
+        self.next_sample += passed * self.interval;
+        if self.rows.len() >= MAXIMUM_BUFFERED_ROWS
+            && let Err(error) = self.spill_rows()
+        {
+            /* kept in memory instead, better than losing them */
+            warn!("Failed to move the trajectory samples to disk: {:#}", error);
+        }
+    }
+
+    fn spill_rows(&mut self) -> Result<()> {
+        let spill = match self.spill.as_mut() {
+            Some(spill) => spill,
+            None => {
+                let path = std::env::temp_dir().join(format!(
+                    "spaces-trajectories-{}-{}.csv",
+                    std::process::id(),
+                    SPILL_COUNTER.fetch_add(1, Ordering::Relaxed)
+                ));
+                let file = File::create(&path)
+                    .with_context(|| format!("Failed to create {}", path.display()))?;
+                info!(
+                    "Recording more than {} trajectory rows, moving them to {}",
+                    MAXIMUM_BUFFERED_ROWS,
+                    path.display()
+                );
+                self.spill.insert(Spill {
+                    path,
+                    writer: BufWriter::new(file),
+                })
+            }
+        };
+        for row in &self.rows {
+            self::write_row(&mut spill.writer, row)?;
+        }
+        self.rows.clear();
+        Ok(())
+    }
+
+    /* flushes the temporary file so the export can read what was spilled up to now */
+    pub fn export(&mut self) -> Result<TrajectoryExport> {
+        let spilled = match self.spill.as_mut() {
+            Some(spill) => {
+                spill
+                    .writer
+                    .flush()
+                    .context("Failed to write the spilled samples")?;
+                let length = spill.writer.get_ref().metadata()?.len();
+                Some((spill.path.clone(), length))
+            }
+            None => None,
+        };
+        Ok(TrajectoryExport {
+            spilled,
+            rows: self.rows.clone(),
+        })
+    }
+}
+
+impl Drop for TrajectoryRecording {
+    fn drop(&mut self) {
+        if let Some(spill) = self.spill.take() {
+            drop(spill.writer);
+            if let Err(error) = std::fs::remove_file(&spill.path) {
+                warn!("Failed to delete {}: {}", spill.path.display(), error);
+            }
+        }
+    }
+}
+
+impl TrajectoryExport {
+    /* streamed, the spilled part is copied over without reading it into memory */
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let file =
+            File::create(path).with_context(|| format!("Failed to create {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        self::write_header(&mut writer)?;
+        if let Some((spilled_path, length)) = self.spilled.as_ref() {
+            let spilled = File::open(spilled_path)
+                .with_context(|| format!("Failed to open {}", spilled_path.display()))?;
+            std::io::copy(&mut spilled.take(*length), &mut writer)
+                .context("Failed to copy the spilled samples")?;
+        }
+        for row in &self.rows {
+            self::write_row(&mut writer, row)?;
+        }
+        writer.flush().context("Failed to write the trajectories")?;
+        Ok(())
+    }
+}
+
+pub fn write_header(writer: &mut impl Write) -> Result<()> {
+    writeln!(writer, "{}", TRAJECTORY_CSV_HEADER).context("Failed to write the trajectories")
+}
+
+/* one row per body, the energy of the whole system repeats in each */
+pub fn write_sample(
+    writer: &mut impl Write,
+    simulation_time: f64,
+    bodies: &[SimulationBody],
+) -> Result<()> {
+    let energy = plots::total_energy(bodies);
+    for body in bodies {
+        self::write_row(
+            writer,
+            &TrajectoryRow {
+                time: simulation_time,
+                body: Arc::from(body.id_name.as_str()),
+                position: body.position,
+                velocity: body.velocity,
+                energy,
+            },
+        )?;
+    }
+    Ok(())
+}
+
+fn write_row(writer: &mut impl Write, row: &TrajectoryRow) -> Result<()> {
+    writeln!(
+        writer,
+        "{},{},{:e},{:e},{:e},{:e},{:e},{:e},{:e}",
+        row.time,
+        self::csv_field(&row.body),
+        row.position.x,
+        row.position.y,
+        row.position.z,
+        row.velocity.x,
+        row.velocity.y,
+        row.velocity.z,
+        row.energy
+    )
+    .context("Failed to write the trajectories")
+}
+
+/* quoted when it holds a separator or a quote, quotes are doubled */
+fn csv_field(value: &str) -> String {
+    match value.contains([',', '"', '\n']) {
+        true => format!("\"{}\"", value.replace('"', "\"\"")),
+        false => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::Mutex,
+        time::{Duration, Instant},
+    };
+
+    use super::*;
+    use crate::{
+        scenario,
+        simulation::{
+            self,
+            thread::{SimulationCommand, SimulationThreadHandle},
+        },
+        storage::Settings,
+    };
+
+    const INTERVAL: f64 = 3600.;
+    /* longest a test waits for the thread to record */
+    const PATIENCE: Duration = Duration::from_secs(10);
+
+    fn bodies() -> Vec<SimulationBody> {
+        scenario::simulation_state(&simulation::sun_earth_moon_objects(), &Settings::default())
+            .bodies
+    }
+
+    fn sample_times(recording: &mut TrajectoryRecording) -> Vec<f64> {
+        let mut times: Vec<f64> = recording
+            .export()
+            .unwrap()
+            .rows
+            .iter()
+            .map(|row| row.time)
+            .collect();
+        times.dedup();
+        times
+    }
+
+    #[test]
+    fn short_and_long_steps_sample_on_the_interval() {
+        let bodies = self::bodies();
+        let mut recording = TrajectoryRecording::new(10., 0.);
+        for time in [0., 3., 6., 9., 12., 15., 18., 21., 55., 59., 60.5] {
+            recording.record(time, &bodies);
+        }
+        /* the long step to 55 passed three samples and records one */
+        assert_eq!(
+            self::sample_times(&mut recording),
+            [0., 12., 21., 55., 60.5]
+        );
+        assert_eq!(recording.sample_count(), 5);
+        assert_eq!(recording.row_count(), 5 * bodies.len());
+    }
+
+    /* the warp changes how many steps the thread takes per wall second, not which of them are
+    sampled */
+    #[test]
+    fn the_interval_holds_across_warp_changes() {
+        let settings = Settings::default();
+        let state = scenario::simulation_state(&simulation::sun_earth_moon_objects(), &settings);
+        let timestep = state.timestep;
+        let recording = Arc::new(Mutex::new(TrajectoryRecording::new(INTERVAL, 0.)));
+        let mut handle = SimulationThreadHandle::spawn(state, 1e5, |_| {}).unwrap();
+        handle.send(SimulationCommand::SetRecording(Some(recording.clone())));
+        let wait_for_samples = |count: usize| {
+            let start = Instant::now();
+            while recording.lock().unwrap().sample_count() < count {
+                assert!(start.elapsed() < PATIENCE, "the thread did not record");
+                std::thread::sleep(Duration::from_millis(1));
+            }
+        };
+        wait_for_samples(5);
+        handle.send(SimulationCommand::SetWarp(3e6));
+        wait_for_samples(40);
+        handle.send(SimulationCommand::SetWarp(3e4));
+        wait_for_samples(45);
+        handle.shutdown().unwrap();
+
+        let times = self::sample_times(&mut recording.lock().unwrap());
+        let hours: Vec<f64> = times[1..]
+            .iter()
+            .map(|time| {
+                let hour = (time / INTERVAL).round();
+                /* the first step at or after the sample time */
+                let late = time - hour * INTERVAL;
+                assert!(
+                    (-1e-6..timestep).contains(&late),
+                    "{} is {} s late",
+                    time,
+                    late
+                );
+                hour
+            })
+            .collect();
+        for pair in hours.windows(2) {
+            assert_eq!(pair[1], pair[0] + 1., "{:?}", times);
+        }
+    }
+}
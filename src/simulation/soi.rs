@@ -0,0 +1,261 @@
+use glam::DVec3;
+use wgpu::naga::FastHashMap;
+
+use crate::simulation::{BodyId, SimulationBody};
+
+/* exponent of the laplace sphere of influence r = a * (m / M)^(2/5) */
+const SPHERE_OF_INFLUENCE_EXPONENT: f64 = 0.4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SphereOfInfluenceNode {
+    pub primary: Option<BodyId>,
+    /* None means unbounded (root attractor of the system) */
+    pub radius: Option<f64>,
+}
+
+pub fn sphere_of_influence_radius(distance_to_primary: f64, mass: f64, primary_mass: f64) -> f64 {
+    distance_to_primary * (mass / primary_mass).powf(SPHERE_OF_INFLUENCE_EXPONENT)
+}
+
+/* builds the patched-conic hierarchy: heavier bodies are resolved first and every body is assigned
+ * the innermost already resolved sphere that contains it as primary */
+pub fn compute_hierarchy(bodies: &[SimulationBody]) -> FastHashMap<BodyId, SphereOfInfluenceNode> {
+    let mut ordered: Vec<&SimulationBody> = bodies.iter().filter(|body| body.mass > 0.).collect();
+    ordered.sort_by(|a, b| b.mass.total_cmp(&a.mass).then(a.id.cmp(&b.id)));
+
+    let mut hierarchy: FastHashMap<BodyId, SphereOfInfluenceNode> = FastHashMap::default();
+    let mut resolved: Vec<Sphere> = Vec::with_capacity(ordered.len());
+    for body in ordered {
+        let node = match innermost_containing(&resolved, body) {
+            Some(primary) => SphereOfInfluenceNode {
+                primary: Some(primary.id),
+                radius: Some(sphere_of_influence_radius(
+                    body.position.distance(primary.position),
+                    body.mass,
+                    primary.mass,
+                )),
+            },
+            None => SphereOfInfluenceNode {
+                primary: None,
+                radius: None,
+            },
+        };
+        hierarchy.insert(body.id, node);
+        resolved.push(Sphere::new(body, node));
+    }
+    hierarchy
+}
+
+/* the attractor whose sphere a body currently sits in, nested spheres resolve to the innermost one */
+pub fn dominant_attractor(
+    bodies: &[SimulationBody],
+    hierarchy: &FastHashMap<BodyId, SphereOfInfluenceNode>,
+    id: BodyId,
+) -> Option<BodyId> {
+    let body = bodies.iter().find(|body| body.id == id)?;
+    innermost_containing(&self::spheres(bodies, hierarchy), body).map(|attractor| attractor.id)
+}
+
+/* the dominant attractor of every body in their order, the spheres are gathered once */
+pub fn dominant_attractors(
+    bodies: &[SimulationBody],
+    hierarchy: &FastHashMap<BodyId, SphereOfInfluenceNode>,
+) -> Vec<Option<BodyId>> {
+    let spheres = self::spheres(bodies, hierarchy);
+    bodies
+        .iter()
+        .map(|body| innermost_containing(&spheres, body).map(|attractor| attractor.id))
+        .collect()
+}
+
+/* what the containment test reads of a body, packed so the loops over every pair of bodies
+stay in the cache instead of reading the whole bodies */
+#[derive(Debug, Clone, Copy)]
+struct Sphere {
+    id: BodyId,
+    position: DVec3,
+    mass: f64,
+    /* infinite for the root */
+    radius: f64,
+}
+
+impl Sphere {
+    fn new(body: &SimulationBody, node: SphereOfInfluenceNode) -> Self {
+        Self {
+            id: body.id,
+            position: body.position,
+            mass: body.mass,
+            radius: node.radius.unwrap_or(f64::INFINITY),
+        }
+    }
+}
+
+fn spheres(
+    bodies: &[SimulationBody],
+    hierarchy: &FastHashMap<BodyId, SphereOfInfluenceNode>,
+) -> Vec<Sphere> {
+    bodies
+        .iter()
+        .filter_map(|body| Some(Sphere::new(body, *hierarchy.get(&body.id)?)))
+        .collect()
+}
+
+/* the body itself is never its own attractor */
+fn innermost_containing(spheres: &[Sphere], body: &SimulationBody) -> Option<Sphere> {
+    spheres
+        .iter()
+        .filter(|sphere| {
+            sphere.id != body.id
+                && sphere.position.distance_squared(body.position) < sphere.radius * sphere.radius
+        })
+        .min_by(|a, b| a.radius.total_cmp(&b.radius))
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{
+        SimulationEnterConfiguration, SimulationEvent, SimulationObject, SimulationPhysicsObject,
+        SimulationState,
+    };
+
+    /* the laplace radii of the textbooks, in meters */
+    const EARTH_SPHERE_OF_INFLUENCE: f64 = 9.25e8;
+    const MOON_SPHERE_OF_INFLUENCE: f64 = 6.61e7;
+
+    fn assert_within_percent(value: f64, reference: f64) {
+        assert!(
+            ((value - reference) / reference).abs() < 0.01,
+            "{} is not within 1 % of {}",
+            value,
+            reference
+        );
+    }
+
+    fn sun_earth_moon() -> SimulationState {
+        SimulationState::from_objects(&crate::simulation::sun_earth_moon_objects(), 60.)
+    }
+
+    fn id_of(state: &SimulationState, name: &str) -> BodyId {
+        state
+            .bodies
+            .iter()
+            .find(|body| body.id_name == name)
+            .map(|body| body.id)
+            .unwrap()
+    }
+
+    #[test]
+    fn radii_match_the_textbook_values() {
+        let state = self::sun_earth_moon();
+        let hierarchy = compute_hierarchy(&state.bodies);
+        let (sun, earth, moon) = (
+            self::id_of(&state, "Sun"),
+            self::id_of(&state, "Earth"),
+            self::id_of(&state, "Moon"),
+        );
+        assert_eq!(
+            hierarchy[&sun],
+            SphereOfInfluenceNode {
+                primary: None,
+                radius: None
+            }
+        );
+        assert_eq!(hierarchy[&earth].primary, Some(sun));
+        assert_eq!(hierarchy[&moon].primary, Some(earth));
+        assert_within_percent(hierarchy[&earth].radius.unwrap(), EARTH_SPHERE_OF_INFLUENCE);
+        assert_within_percent(hierarchy[&moon].radius.unwrap(), MOON_SPHERE_OF_INFLUENCE);
+    }
+
+    #[test]
+    fn massless_bodies_have_no_sphere() {
+        let mut state = self::sun_earth_moon();
+        let earth = self::id_of(&state, "Earth");
+        let probe = state
+            .add_body_relative(
+                &SimulationObject::new(
+                    "Probe".to_string(),
+                    SimulationPhysicsObject::new(0., 1.),
+                    SimulationEnterConfiguration::new([0., 0., 0.], [1e7, 0., 0.]),
+                ),
+                earth,
+            )
+            .unwrap();
+        assert!(!compute_hierarchy(&state.bodies).contains_key(&probe));
+        assert_eq!(state.dominant_attractor(probe), Some(earth));
+    }
+
+    #[test]
+    fn every_attractor_at_once_matches_the_single_lookup() {
+        let state = self::sun_earth_moon();
+        let hierarchy = compute_hierarchy(&state.bodies);
+        let single: Vec<Option<BodyId>> = state
+            .bodies
+            .iter()
+            .map(|body| dominant_attractor(&state.bodies, &hierarchy, body.id))
+            .collect();
+        assert_eq!(dominant_attractors(&state.bodies, &hierarchy), single);
+        assert_eq!(
+            single,
+            [
+                None,
+                Some(self::id_of(&state, "Sun")),
+                Some(self::id_of(&state, "Earth"))
+            ]
+        );
+    }
+
+    /* a fast probe leaving the earth past the moon, fast enough to fly nearly straight. it
+    passes the moon outside of its surface but well inside of its sphere */
+    #[test]
+    fn transfer_fires_the_events_in_order() {
+        let mut state = self::sun_earth_moon();
+        let (sun, earth, moon) = (
+            self::id_of(&state, "Sun"),
+            self::id_of(&state, "Earth"),
+            self::id_of(&state, "Moon"),
+        );
+        let probe = state
+            .add_body_relative(
+                &SimulationObject::new(
+                    "Probe".to_string(),
+                    SimulationPhysicsObject::new(0., 1.),
+                    SimulationEnterConfiguration::new([1e5, 0., 0.], [1e8, 2e7, 0.]),
+                ),
+                earth,
+            )
+            .unwrap();
+        assert_eq!(state.dominant_attractor(probe), Some(earth));
+        let mut events = Vec::new();
+        for _ in 0..300 {
+            state.step();
+            events.extend(state.drain_events().into_iter().filter(|event| {
+                matches!(
+                    event,
+                    SimulationEvent::SphereOfInfluenceEntered { body, .. }
+                    | SimulationEvent::SphereOfInfluenceExited { body, .. } if *body == probe
+                )
+            }));
+        }
+        let exited = |attractor| SimulationEvent::SphereOfInfluenceExited {
+            body: probe,
+            attractor,
+        };
+        let entered = |attractor| SimulationEvent::SphereOfInfluenceEntered {
+            body: probe,
+            attractor,
+        };
+        assert_eq!(
+            events,
+            vec![
+                exited(earth),
+                entered(moon),
+                exited(moon),
+                entered(earth),
+                exited(earth),
+                entered(sun),
+            ]
+        );
+    }
+}
@@ -0,0 +1,581 @@
+use std::{
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use wgpu::naga::FastHashMap;
+
+use crate::simulation::{
+    BodyEdit, BodyId, SimulationBody, SimulationEvent, SimulationObject, SimulationState,
+    history::EditCommand, plots::DiagnosticPlots, recording::TrajectoryRecording, trail::Trail,
+};
+
+/* how often the thread publishes snapshots while running */
+pub const SIMULATION_PUBLISH_INTERVAL: Duration = Duration::from_millis(8);
+/* upper bound of ticks per publish, excess simulation time is dropped instead of spiralling */
+pub const MAXIMUM_STEPS_PER_PUBLISH: u32 = 512;
+/* wall time the steps per second are averaged over */
+pub const STEP_RATE_INTERVAL: Duration = Duration::from_secs(1);
+/* longest hidden wall time that is fast-forwarded, longer absences lose the rest */
+pub const MAXIMUM_CATCH_UP: Duration = Duration::from_secs(60);
+
+/* what the physics do while the window is minimized or covered */
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    strum_macros::EnumIter,
+    strum_macros::Display,
+)]
+pub enum HiddenPolicy {
+    #[default]
+    #[serde(rename = "keep running")]
+    #[strum(to_string = "Keep running")]
+    KeepRunning,
+    /* resumes where it paused */
+    #[serde(rename = "pause")]
+    #[strum(to_string = "Pause")]
+    Pause,
+    /* pauses, then fast-forwards the hidden time bounded by MAXIMUM_CATCH_UP */
+    #[serde(rename = "pause and catch up")]
+    #[strum(to_string = "Pause and catch up")]
+    CatchUp,
+}
+
+pub enum SimulationCommand {
+    SetPaused(bool),
+    /* simulated seconds per wall second */
+    SetWarp(f64),
+    /* advances a paused simulation by one tick, ignored while running */
+    Step,
+    /* simulated seconds integrated on top of the real time, spread over the publishes so there is
+    no single giant step. dropped by pausing */
+    CatchUp(f64),
+    /* the enter position and speed are offsets from the relative_to body when there is one */
+    AddBody {
+        object: Box<SimulationObject>,
+        relative_to: Option<BodyId>,
+    },
+    /* appended as one undo step, the source names it */
+    ImportBodies {
+        objects: Vec<SimulationObject>,
+        source: String,
+    },
+    RemoveBody(BodyId),
+    EditBody(BodyId, BodyEdit),
+    /* the edits of the body so far are one undo step, sent when a drag is released */
+    FinishEdit,
+    Undo,
+    Redo,
+    /* the scenario file changed, its masses, radii and appearances replace those of the bodies
+    of the same name */
+    #[cfg(feature = "scenario-hot-reload")]
+    TweakBodies(Vec<SimulationObject>),
+    /* the body whose speed and altitude are plotted */
+    SetPlottedBody(Option<BodyId>),
+    /* starts sampling into the recording right away, none stops it */
+    SetRecording(Option<Arc<Mutex<TrajectoryRecording>>>),
+    /* the bodies were written to a scenario file, they count as unedited from here */
+    MarkSaved,
+    Shutdown,
+}
+
+/* what the simulation thread needs from the state it owns, lets the plumbing run without physics */
+pub trait SimulationStepper: Send + 'static {
+    fn step(&mut self);
+    fn timestep(&self) -> f64;
+    fn apply(&mut self, command: SimulationCommand);
+    fn snapshot(&self) -> SimulationSnapshot;
+    fn drain_events(&mut self) -> Vec<SimulationEvent>;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SimulationSnapshot {
+    pub bodies: Vec<SimulationBody>,
+    pub simulation_time: f64,
+    pub trails: Arc<FastHashMap<BodyId, Trail>>,
+    pub plots: Arc<DiagnosticPlots>,
+    pub paused: bool,
+    pub warp: f64,
+    /* ticks integrated per wall second, averaged over STEP_RATE_INTERVAL */
+    pub steps_per_second: f64,
+    /* the bodies were edited since the scenario was loaded */
+    pub dirty: bool,
+    /* what undo and redo would do, none when there is nothing to */
+    pub undo_description: Option<String>,
+    pub redo_description: Option<String>,
+    /* the trajectory recording being fed, none when not recording */
+    pub recording: Option<Arc<Mutex<TrajectoryRecording>>>,
+}
+
+/* watch-style single slot, readers always observe the latest published snapshot */
+#[derive(Clone, Default)]
+pub struct SnapshotSlot {
+    latest: Arc<Mutex<Arc<SimulationSnapshot>>>,
+}
+
+impl SnapshotSlot {
+    pub fn publish(&self, snapshot: SimulationSnapshot) {
+        *self.latest.lock().unwrap() = Arc::new(snapshot);
+    }
+
+    pub fn latest(&self) -> Arc<SimulationSnapshot> {
+        self.latest.lock().unwrap().clone()
+    }
+}
+
+pub struct SimulationThreadHandle {
+    commands: Sender<SimulationCommand>,
+    snapshots: SnapshotSlot,
+    join_handle: Option<JoinHandle<()>>,
+}
+
+impl SimulationThreadHandle {
+    /* the notifier is invoked from the simulation thread after every publish with the drained events */
+    pub fn spawn<S, N>(stepper: S, warp: f64, notifier: N) -> Result<Self>
+    where
+        S: SimulationStepper,
+        N: FnMut(Vec<SimulationEvent>) + Send + 'static,
+    {
+        let (commands, receiver) = mpsc::channel();
+        let snapshots = SnapshotSlot::default();
+        let mut initial_snapshot = stepper.snapshot();
+        initial_snapshot.warp = warp;
+        snapshots.publish(initial_snapshot);
+        let thread_snapshots = snapshots.clone();
+        let join_handle = std::thread::Builder::new()
+            .name("simulation".to_string())
+            .spawn(move || self::run(stepper, warp, receiver, thread_snapshots, notifier))
+            .context("Failed to spawn the simulation thread")?;
+        Ok(Self {
+            commands,
+            snapshots,
+            join_handle: Some(join_handle),
+        })
+    }
+
+    pub fn send(&self, command: SimulationCommand) {
+        if self.commands.send(command).is_err() {
+            warn!("Simulation thread is no longer running, command dropped");
+        }
+    }
+
+    pub fn snapshot(&self) -> Arc<SimulationSnapshot> {
+        self.snapshots.latest()
+    }
+
+    pub fn shutdown(&mut self) -> Result<()> {
+        let Some(join_handle) = self.join_handle.take() else {
+            return Ok(());
+        };
+        let _ = self.commands.send(SimulationCommand::Shutdown);
+        join_handle
+            .join()
+            .map_err(|_| anyhow!("Simulation thread panicked"))?;
+        info!("Simulation thread joined");
+        Ok(())
+    }
+}
+
+impl Drop for SimulationThreadHandle {
+    fn drop(&mut self) {
+        if let Err(error) = self.shutdown() {
+            warn!("{error}");
+        }
+    }
+}
+
+fn run<S, N>(
+    mut stepper: S,
+    mut warp: f64,
+    receiver: Receiver<SimulationCommand>,
+    snapshots: SnapshotSlot,
+    mut notifier: N,
+) where
+    S: SimulationStepper,
+    N: FnMut(Vec<SimulationEvent>),
+{
+    let mut paused = false;
+    let mut pending_time = 0.;
+    let mut catch_up_time = 0.;
+    let mut last_tick = Instant::now();
+    let mut rate_window = (Instant::now(), 0u32);
+    let mut steps_per_second = 0.;
+    loop {
+        /* a paused thread sleeps on the channel instead of spinning */
+        let timeout = if paused {
+            Duration::from_secs(3600)
+        } else {
+            SIMULATION_PUBLISH_INTERVAL.saturating_sub(last_tick.elapsed())
+        };
+        let mut received = match receiver.recv_timeout(timeout) {
+            Ok(command) => Some(command),
+            Err(RecvTimeoutError::Timeout) => None,
+            Err(RecvTimeoutError::Disconnected) => return,
+        };
+        let mut dirty = false;
+        while let Some(command) = received.take().or_else(|| receiver.try_recv().ok()) {
+            match command {
+                SimulationCommand::Shutdown => return,
+                SimulationCommand::SetPaused(state) => {
+                    paused = state;
+                    pending_time = 0.;
+                    catch_up_time = 0.;
+                    rate_window = (Instant::now(), 0);
+                    steps_per_second = 0.;
+                }
+                SimulationCommand::SetWarp(factor) => warp = factor.max(0.),
+                SimulationCommand::CatchUp(seconds) => {
+                    if !paused {
+                        catch_up_time += seconds.max(0.);
+                    }
+                }
+                SimulationCommand::Step => {
+                    if paused {
+                        stepper.step();
+                    }
+                }
+                command => stepper.apply(command),
+            }
+            dirty = true;
+        }
+
+        let now = Instant::now();
+        if !paused {
+            pending_time += now.duration_since(last_tick).as_secs_f64() * warp;
+        }
+        last_tick = now;
+
+        let timestep = stepper.timestep();
+        let mut steps = 0;
+        while timestep > 0. && pending_time >= timestep && steps < MAXIMUM_STEPS_PER_PUBLISH {
+            stepper.step();
+            pending_time -= timestep;
+            steps += 1;
+        }
+        if steps == MAXIMUM_STEPS_PER_PUBLISH {
+            pending_time = pending_time.min(timestep);
+        }
+        /* the real time goes first so catching up never drops it */
+        while timestep > 0. && catch_up_time >= timestep && steps < MAXIMUM_STEPS_PER_PUBLISH {
+            stepper.step();
+            catch_up_time -= timestep;
+            steps += 1;
+        }
+        rate_window.1 += steps;
+        let window_length = now.duration_since(rate_window.0);
+        if !paused && window_length >= STEP_RATE_INTERVAL {
+            steps_per_second = rate_window.1 as f64 / window_length.as_secs_f64();
+            rate_window = (now, 0);
+        }
+
+        if steps > 0 || dirty {
+            let mut snapshot = stepper.snapshot();
+            snapshot.paused = paused;
+            snapshot.warp = warp;
+            snapshot.steps_per_second = steps_per_second;
+            snapshots.publish(snapshot);
+            notifier(stepper.drain_events());
+        }
+    }
+}
+
+impl SimulationStepper for SimulationState {
+    fn step(&mut self) {
+        SimulationState::step(self);
+    }
+
+    fn timestep(&self) -> f64 {
+        self.timestep
+    }
+
+    fn apply(&mut self, command: SimulationCommand) {
+        match command {
+            SimulationCommand::AddBody {
+                object,
+                relative_to: None,
+            } => {
+                let id = self.add_body(&object);
+                self::record_added(self, id);
+                self.dirty = true;
+            }
+            SimulationCommand::AddBody {
+                object,
+                relative_to: Some(parent),
+            } => {
+                let added = self.add_body_relative(&object, parent);
+                match added {
+                    Some(id) => {
+                        self::record_added(self, id);
+                        self.dirty = true;
+                    }
+                    None => warn!(
+                        "Cannot place {} relative to unknown body {:?}",
+                        object.id_name, parent
+                    ),
+                }
+            }
+            SimulationCommand::ImportBodies { objects, source } => {
+                let index = self.bodies.len();
+                self.add_bodies(&objects);
+                let bodies = self.bodies[index..].to_vec();
+                self.history.record(EditCommand::Import {
+                    bodies,
+                    index,
+                    source,
+                });
+                self.dirty = true;
+            }
+            SimulationCommand::RemoveBody(id) => {
+                let index = self.bodies.iter().position(|body| body.id == id);
+                match index.zip(self.remove_body(id)) {
+                    Some((index, body)) => {
+                        self.history.record(EditCommand::Remove { body, index });
+                        self.dirty = true;
+                    }
+                    None => warn!("Cannot remove unknown body {:?}", id),
+                }
+            }
+            SimulationCommand::EditBody(id, edit) => {
+                let Some(body) = self.bodies.iter().find(|body| body.id == id) else {
+                    warn!("Cannot edit unknown body {:?}", id);
+                    return;
+                };
+                let (name, before) = (body.id_name.clone(), edit.read(body));
+                self.edit_body(id, edit.clone());
+                /* the applied value, names are made unique and masses clamped */
+                let after = self
+                    .bodies
+                    .iter()
+                    .find(|body| body.id == id)
+                    .map_or(edit, |body| before.read(body));
+                if after == before {
+                    return;
+                }
+                self.history.record(EditCommand::Edit {
+                    id,
+                    name,
+                    before,
+                    after,
+                });
+            }
+            SimulationCommand::FinishEdit => self.history.finish(),
+            SimulationCommand::SetPlottedBody(body) => self.set_plotted_body(body),
+            SimulationCommand::SetRecording(recording) => {
+                if let Some(recording) = recording.as_ref() {
+                    recording
+                        .lock()
+                        .unwrap()
+                        .record(self.simulation_time, &self.bodies);
+                }
+                self.recording = recording;
+            }
+            SimulationCommand::MarkSaved => self.dirty = false,
+            #[cfg(feature = "scenario-hot-reload")]
+            SimulationCommand::TweakBodies(objects) => {
+                let tweaked = self.tweak_bodies(&objects);
+                info!("Tweaked {} of {} bodies", tweaked, objects.len());
+            }
+            SimulationCommand::Undo => {
+                let undone = self.undo();
+                if !undone {
+                    info!("Nothing to undo");
+                }
+            }
+            SimulationCommand::Redo => {
+                let redone = self.redo();
+                if !redone {
+                    info!("Nothing to redo");
+                }
+            }
+            /* run by the thread itself, never passed on */
+            SimulationCommand::SetPaused(_)
+            | SimulationCommand::SetWarp(_)
+            | SimulationCommand::Step
+            | SimulationCommand::CatchUp(_)
+            | SimulationCommand::Shutdown => {}
+        }
+    }
+
+    fn snapshot(&self) -> SimulationSnapshot {
+        SimulationSnapshot {
+            bodies: self.bodies.clone(),
+            simulation_time: self.simulation_time,
+            trails: self.trails(),
+            plots: self.plots(),
+            dirty: self.dirty,
+            undo_description: self.history.undo_description(),
+            redo_description: self.history.redo_description(),
+            recording: self.recording.clone(),
+            ..Default::default()
+        }
+    }
+
+    fn drain_events(&mut self) -> Vec<SimulationEvent> {
+        SimulationState::drain_events(self)
+    }
+}
+
+/* the body as it was added, redoing puts it back in that state */
+fn record_added(state: &mut SimulationState, id: BodyId) {
+    if let Some(index) = state.bodies.iter().position(|body| body.id == id) {
+        let body = state.bodies[index].clone();
+        state.history.record(EditCommand::Add { body, index });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    const TIMESTEP: f64 = 1.;
+    /* longest a test waits for the thread to publish */
+    const PATIENCE: Duration = Duration::from_secs(5);
+
+    /* counts what the thread asks of it. every step pretends to raise one event */
+    #[derive(Clone, Default)]
+    struct CountingStepper {
+        steps: Arc<AtomicU32>,
+        applied: Arc<AtomicU32>,
+        undrained: u32,
+    }
+
+    impl SimulationStepper for CountingStepper {
+        fn step(&mut self) {
+            self.steps.fetch_add(1, Ordering::SeqCst);
+            self.undrained += 1;
+        }
+
+        fn timestep(&self) -> f64 {
+            TIMESTEP
+        }
+
+        fn apply(&mut self, _command: SimulationCommand) {
+            self.applied.fetch_add(1, Ordering::SeqCst);
+        }
+
+        fn snapshot(&self) -> SimulationSnapshot {
+            SimulationSnapshot {
+                simulation_time: self.steps.load(Ordering::SeqCst) as f64 * TIMESTEP,
+                ..Default::default()
+            }
+        }
+
+        fn drain_events(&mut self) -> Vec<SimulationEvent> {
+            (0..std::mem::take(&mut self.undrained))
+                .map(|_| SimulationEvent::BodyRemoved(BodyId(0)))
+                .collect()
+        }
+    }
+
+    fn wait_for(condition: impl Fn() -> bool) {
+        let start = Instant::now();
+        while !condition() {
+            assert!(start.elapsed() < PATIENCE, "the thread did not get there");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn initial_snapshot_has_the_warp() {
+        let handle = SimulationThreadHandle::spawn(CountingStepper::default(), 5., |_| {}).unwrap();
+        assert_eq!(handle.snapshot().warp, 5.);
+    }
+
+    #[test]
+    fn paused_thread_steps_one_tick_per_command() {
+        let stepper = CountingStepper::default();
+        let steps = stepper.steps.clone();
+        let handle = SimulationThreadHandle::spawn(stepper, 1e6, |_| {}).unwrap();
+        handle.send(SimulationCommand::SetPaused(true));
+        wait_for(|| handle.snapshot().paused);
+        let paused_at = steps.load(Ordering::SeqCst);
+        handle.send(SimulationCommand::Step);
+        handle.send(SimulationCommand::Step);
+        wait_for(|| handle.snapshot().simulation_time == (paused_at + 2) as f64 * TIMESTEP);
+        std::thread::sleep(SIMULATION_PUBLISH_INTERVAL * 4);
+        assert_eq!(steps.load(Ordering::SeqCst), paused_at + 2);
+    }
+
+    #[test]
+    fn step_is_ignored_while_running() {
+        let stepper = CountingStepper::default();
+        let steps = stepper.steps.clone();
+        let handle = SimulationThreadHandle::spawn(stepper, 0., |_| {}).unwrap();
+        handle.send(SimulationCommand::Step);
+        /* the snapshot with this warp is published after the step was handled, at the warp the
+        next tick is a thousand seconds of wall time away */
+        handle.send(SimulationCommand::SetWarp(1e-3));
+        wait_for(|| handle.snapshot().warp == 1e-3);
+        assert_eq!(handle.snapshot().simulation_time, 0.);
+        assert_eq!(steps.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn other_commands_reach_the_stepper() {
+        let stepper = CountingStepper::default();
+        let applied = stepper.applied.clone();
+        let handle = SimulationThreadHandle::spawn(stepper, 0., |_| {}).unwrap();
+        handle.send(SimulationCommand::MarkSaved);
+        handle.send(SimulationCommand::FinishEdit);
+        handle.send(SimulationCommand::SetPaused(true));
+        handle.send(SimulationCommand::SetWarp(2.));
+        wait_for(|| handle.snapshot().warp == 2.);
+        assert_eq!(applied.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn warp_advances_with_the_wall_time() {
+        let handle =
+            SimulationThreadHandle::spawn(CountingStepper::default(), 1e3, |_| {}).unwrap();
+        wait_for(|| handle.snapshot().simulation_time >= 10. * TIMESTEP);
+        assert!(!handle.snapshot().paused);
+    }
+
+    #[test]
+    fn catch_up_is_integrated_on_top_of_the_warp() {
+        let stepper = CountingStepper::default();
+        let steps = stepper.steps.clone();
+        let handle = SimulationThreadHandle::spawn(stepper, 0., |_| {}).unwrap();
+        handle.send(SimulationCommand::CatchUp(
+            3. * MAXIMUM_STEPS_PER_PUBLISH as f64 * TIMESTEP,
+        ));
+        wait_for(|| steps.load(Ordering::SeqCst) == 3 * MAXIMUM_STEPS_PER_PUBLISH);
+        std::thread::sleep(SIMULATION_PUBLISH_INTERVAL * 4);
+        assert_eq!(steps.load(Ordering::SeqCst), 3 * MAXIMUM_STEPS_PER_PUBLISH);
+    }
+
+    #[test]
+    fn events_reach_the_notifier_and_shutdown_joins() {
+        let stepper = CountingStepper::default();
+        let steps = stepper.steps.clone();
+        let notified = Arc::new(AtomicU32::new(0));
+        let counter = notified.clone();
+        let mut handle = SimulationThreadHandle::spawn(stepper, 0., move |events| {
+            counter.fetch_add(events.len() as u32, Ordering::SeqCst);
+        })
+        .unwrap();
+        handle.send(SimulationCommand::SetPaused(true));
+        handle.send(SimulationCommand::Step);
+        wait_for(|| notified.load(Ordering::SeqCst) == 1);
+        handle.shutdown().unwrap();
+        assert_eq!(steps.load(Ordering::SeqCst), 1);
+        handle.send(SimulationCommand::Step);
+        assert_eq!(steps.load(Ordering::SeqCst), 1);
+    }
+}
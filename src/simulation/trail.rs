@@ -0,0 +1,56 @@
+use std::collections::VecDeque;
+
+use derive_new::new;
+use glam::DVec3;
+
+/* six hours of simulation time between samples, 2048 samples cover well over one earth year */
+pub const DEFAULT_TRAIL_SAMPLE_INTERVAL: f64 = 6. * 3600.;
+pub const DEFAULT_TRAIL_CAPACITY: usize = 2048;
+
+#[derive(Debug, Clone, Copy, new)]
+pub struct TrailConfiguration {
+    /* simulation seconds between two recorded positions */
+    pub sample_interval: f64,
+    pub capacity: usize,
+}
+
+impl Default for TrailConfiguration {
+    fn default() -> Self {
+        Self::new(DEFAULT_TRAIL_SAMPLE_INTERVAL, DEFAULT_TRAIL_CAPACITY)
+    }
+}
+
+/* ring buffer of past positions, the oldest sample is dropped once the capacity is reached */
+#[derive(Debug, Clone)]
+pub struct Trail {
+    points: VecDeque<DVec3>,
+    capacity: usize,
+}
+
+impl Trail {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            points: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, position: DVec3) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.points.len() == self.capacity {
+            self.points.pop_front();
+        }
+        self.points.push_back(position);
+    }
+
+    /* oldest first */
+    pub fn points(&self) -> impl ExactSizeIterator<Item = &DVec3> + DoubleEndedIterator {
+        self.points.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+}
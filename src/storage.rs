@@ -0,0 +1,1321 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_yml::Value;
+use strum::{EnumIter, IntoEnumIterator};
+use tracing::warn;
+
+use crate::{
+    graphics::{
+        billboards::BillboardSettings,
+        camera::CameraPose,
+        fonts,
+        labels::LabelSettings,
+        post::BloomSettings,
+        starfield::StarfieldConfiguration,
+        surface::{self, PresentPreference},
+        targets,
+        tonemap::TonemapSettings,
+        toolbar, viewport,
+    },
+    input::Keymap,
+    platform,
+    simulation::{Integrator, SimulationBody, SimulationObject, thread::HiddenPolicy, trail},
+    storage::validation::ValidationError,
+};
+
+mod assets;
+mod binary;
+mod csv;
+mod horizons;
+mod metadata;
+mod migration;
+pub mod presets;
+mod schema;
+mod slots;
+mod units;
+pub mod validation;
+
+pub use self::{
+    assets::AssetResolver,
+    csv::{CsvField, CsvMapping, import_csv},
+    horizons::{import_horizons, is_horizons_table, merge_horizons},
+    metadata::{EMBEDDED_THUMBNAIL_PREFIX, ScenarioMetadata, Thumbnail},
+    schema::scenario_schema_json,
+    slots::{
+        SaveSlot, SaveSlotKind, delete_save_slot, is_save_slot_name, list_save_slots,
+        rename_save_slot, save_slot_path, saves_directory, thumbnail_path,
+    },
+    units::ScenarioUnits,
+};
+
+pub const SETTINGS_FILE_NAME: &str = "settings.yml";
+/* where an edited scenario is saved to when it was not loaded from a file */
+pub const SCENARIO_FILE_NAME: &str = "scenario.yml";
+/* the window positions and sizes imgui writes itself */
+pub const UI_LAYOUT_FILE_NAME: &str = "imgui.ini";
+/* the slots of the quicksave and quickload keys in the saves directory, with the extension of the
+save format. quicksave, then quicksave_1 and quicksave_2, the oldest is overwritten */
+pub const QUICKSAVE_FILE_STEM: &str = "quicksave";
+pub const QUICKSAVE_SLOTS: usize = 3;
+/* autosave_0 to autosave_2 next to the quicksaves, the oldest is overwritten */
+pub const AUTOSAVE_SLOTS: usize = 3;
+/* minutes of wall time */
+pub const DEFAULT_AUTOSAVE_INTERVAL: u32 = 5;
+/* in the data directory while the application runs, left behind when it does not shut down */
+pub const SESSION_MARKER_FILE_NAME: &str = "session.lock";
+/* in the data directory, listed by the scenario browser */
+pub const SCENARIOS_DIRECTORY_NAME: &str = "scenarios";
+/* written into the file, readers of any version accept it */
+pub const SETTINGS_VERSION: u32 = 1;
+/* unlike the settings a scenario or a save of a newer version is refused, it could not be read
+as intended. a new version adds the migration from the one before */
+pub const SCENARIO_VERSION: u32 = SCENARIO_MIGRATIONS.len() as u32 + 1;
+const SCENARIO_MIGRATIONS: [migration::Migration; 1] = [metadata::move_into_section];
+pub const SAVE_GAME_VERSION: u32 = SAVE_GAME_MIGRATIONS.len() as u32 + 1;
+const SAVE_GAME_MIGRATIONS: [migration::Migration; 0] = [];
+pub const DEFAULT_UI_SCALE: f32 = 1.;
+pub const MINIMUM_UI_SCALE: f32 = 0.5;
+pub const MAXIMUM_UI_SCALE: f32 = 3.;
+/* frames per second, 0 leaves the pacing to the present mode */
+pub const UNLIMITED_FRAME_RATE: u32 = 0;
+pub const MINIMUM_FRAME_RATE_LIMIT: u32 = 10;
+/* fraction of the stick travel that is ignored, worn sticks rest off center */
+pub const DEFAULT_GAMEPAD_DEADZONE: f32 = 0.15;
+pub const MAXIMUM_GAMEPAD_DEADZONE: f32 = 0.9;
+pub const DEFAULT_GAMEPAD_SENSITIVITY: f32 = 1.;
+pub const MINIMUM_GAMEPAD_SENSITIVITY: f32 = 0.1;
+pub const MAXIMUM_GAMEPAD_SENSITIVITY: f32 = 5.;
+pub const DEFAULT_LANGUAGE: &str = "en";
+/* scenarios are yaml like the settings, the other formats are for writing them by hand */
+pub const SCENARIO_EXTENSIONS: [&str; 5] = ["yml", "yaml", "json", "toml", "ron"];
+/* the field names of the scenarios have spaces, so ron writes every mapping as a map */
+const RON_INDENT: &str = "    ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, strum_macros::Display)]
+pub enum ScenarioFormat {
+    #[strum(to_string = "yaml")]
+    Yaml,
+    #[strum(to_string = "json")]
+    Json,
+    #[strum(to_string = "toml")]
+    Toml,
+    #[strum(to_string = "ron")]
+    Ron,
+}
+
+impl ScenarioFormat {
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            ScenarioFormat::Yaml => &SCENARIO_EXTENSIONS[..2],
+            ScenarioFormat::Json => &SCENARIO_EXTENSIONS[2..3],
+            ScenarioFormat::Toml => &SCENARIO_EXTENSIONS[3..4],
+            ScenarioFormat::Ron => &SCENARIO_EXTENSIONS[4..],
+        }
+    }
+
+    /* case insensitive like the extensions, "yml" is taken for yaml as well */
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::iter().find(|format| {
+            format.to_string().eq_ignore_ascii_case(name)
+                || format
+                    .extensions()
+                    .iter()
+                    .any(|extension| extension.eq_ignore_ascii_case(name))
+        })
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?;
+        Self::iter().find(|format| {
+            format
+                .extensions()
+                .iter()
+                .any(|known| extension.eq_ignore_ascii_case(known))
+        })
+    }
+}
+
+/* the bodies of a scenario file, in the units of the built-in scenes. the rest may be left out */
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Scenario {
+    /* SCENARIO_VERSION once loaded, the older ones are migrated */
+    #[serde(rename = "version", default)]
+    pub version: u32,
+    /* shown in the scenario browser */
+    #[serde(
+        rename = "metadata",
+        default,
+        skip_serializing_if = "ScenarioMetadata::is_empty"
+    )]
+    pub metadata: ScenarioMetadata,
+    /* the utc date at simulation time zero like 2000-01-01 12:00, what the calendar counts from.
+    j2000 when missing */
+    #[serde(rename = "epoch", default, skip_serializing_if = "Option::is_none")]
+    pub epoch: Option<String>,
+    /* of the numbers in the file, the bodies below are always in si units once loaded */
+    #[serde(
+        rename = "units",
+        default,
+        skip_serializing_if = "ScenarioUnits::is_si"
+    )]
+    pub units: ScenarioUnits,
+    #[serde(rename = "bodies")]
+    pub objects: Vec<SimulationObject>,
+    /* fields of a newer version or of another tool, written back when saving */
+    #[serde(skip)]
+    pub unknown_fields: serde_yml::Mapping,
+}
+
+impl Scenario {
+    pub fn new(objects: Vec<SimulationObject>) -> Self {
+        Self {
+            version: SCENARIO_VERSION,
+            metadata: ScenarioMetadata::default(),
+            epoch: None,
+            units: ScenarioUnits::default(),
+            objects,
+            unknown_fields: serde_yml::Mapping::new(),
+        }
+    }
+
+    /* run on loading, a scenario with errors is refused */
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = validation::validate(&self.objects);
+        if let Some(epoch) = self.epoch.as_ref()
+            && toolbar::parse_date(epoch).is_none()
+        {
+            errors.push(ValidationError {
+                body: "The scenario".to_string(),
+                field: "epoch",
+                message: format!("must be a date like 2000-01-01 12:00, got {}", epoch),
+            });
+        }
+        for (field, date) in self.metadata.invalid_dates() {
+            errors.push(ValidationError {
+                body: "The scenario".to_string(),
+                field,
+                message: format!("must be a date like 2000-01-01 12:00, got {}", date),
+            });
+        }
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+
+    /* the textures are looked up through the resolver of the scenario file */
+    pub fn warnings(&self, assets: &AssetResolver) -> Vec<ValidationError> {
+        let mut warnings = self.units.warnings();
+        warnings.extend(validation::warnings(&self.objects, assets));
+        warnings
+    }
+
+    /* the unix time of simulation time zero, a validated scenario always has one */
+    pub fn epoch_seconds(&self) -> i64 {
+        self.epoch
+            .as_deref()
+            .and_then(toolbar::parse_date)
+            .unwrap_or(toolbar::SIMULATION_EPOCH_UNIX_SECONDS)
+    }
+}
+
+/* how quicksaves and autosaves are written, any of them is read back */
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Default,
+    EnumIter,
+    strum_macros::Display,
+)]
+pub enum SaveFormat {
+    #[default]
+    #[serde(rename = "yaml")]
+    #[strum(to_string = "Yaml")]
+    Yaml,
+    /* for states of many bodies, a fraction of the size and much faster to read */
+    #[serde(rename = "binary")]
+    #[strum(to_string = "Compressed binary")]
+    Binary,
+}
+
+impl SaveFormat {
+    /* the first one is written */
+    pub fn extensions(&self) -> &'static [&'static str] {
+        match self {
+            SaveFormat::Yaml => &["yml", "yaml"],
+            SaveFormat::Binary => &["sps", "bin"],
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?;
+        Self::iter().find(|format| {
+            format
+                .extensions()
+                .iter()
+                .any(|known| extension.eq_ignore_ascii_case(known))
+        })
+    }
+}
+
+/* the running simulation as it is, where a scenario only has the bodies to start from. the
+trails are not saved, loading clears them and they grow again from the restored time. there is
+no random state to keep, the debris is seeded from the simulation time */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SaveGame {
+    #[serde(rename = "version")]
+    pub version: u32,
+    /* shown in the status bar, and the file that is watched and saved to after loading */
+    #[serde(rename = "scenario")]
+    pub scenario_label: String,
+    #[serde(
+        rename = "scenario path",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub scenario_path: Option<PathBuf>,
+    #[serde(rename = "simulation time")]
+    pub simulation_time: f64,
+    #[serde(rename = "warp")]
+    pub warp: f64,
+    #[serde(rename = "paused")]
+    pub paused: bool,
+    /* the bodies were edited since the scenario was loaded */
+    #[serde(rename = "edited")]
+    pub dirty: bool,
+    /* the unix time of simulation time zero, of the scenario the state was loaded from */
+    #[serde(rename = "epoch", default = "SaveGame::default_epoch")]
+    pub epoch: i64,
+    #[serde(rename = "camera")]
+    pub camera: CameraPose,
+    #[serde(rename = "bodies")]
+    pub bodies: Vec<SimulationBody>,
+}
+
+impl SaveGame {
+    fn default_epoch() -> i64 {
+        toolbar::SIMULATION_EPOCH_UNIX_SECONDS
+    }
+}
+
+/* where the window was when last moved or resized, in physical pixels. position and size are the
+ones before maximizing or entering fullscreen */
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct WindowGeometry {
+    #[serde(rename = "position")]
+    pub position: Option<[i32; 2]>,
+    #[serde(rename = "size")]
+    pub size: Option<[u32; 2]>,
+    #[serde(rename = "maximized")]
+    pub maximized: bool,
+    #[serde(rename = "fullscreen")]
+    pub fullscreen: bool,
+    /* name of the monitor, the primary one is used when it is gone */
+    #[serde(rename = "monitor")]
+    pub monitor: String,
+}
+
+/* what the settings window edits, missing fields take their default */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    #[serde(rename = "version")]
+    pub version: u32,
+    #[serde(rename = "present mode")]
+    pub present_preference: PresentPreference,
+    #[serde(rename = "msaa samples")]
+    pub sample_count: u32,
+    #[serde(rename = "render scale")]
+    pub render_scale: f32,
+    /* off draws straight into the swapchain, without bloom and exposure. read on the next start */
+    #[serde(rename = "tonemapping")]
+    pub tonemapping: bool,
+    #[serde(rename = "tonemap")]
+    pub tonemap: TonemapSettings,
+    #[serde(rename = "bloom")]
+    pub bloom: BloomSettings,
+    #[serde(rename = "lighting")]
+    pub lighting: bool,
+    #[serde(rename = "starfield")]
+    pub starfield: StarfieldConfiguration,
+    #[serde(rename = "labels")]
+    pub labels: LabelSettings,
+    #[serde(rename = "billboards")]
+    pub billboards: BillboardSettings,
+    #[serde(rename = "ui scale")]
+    pub ui_scale: f32,
+    /* file name in the fonts directory, empty for the embedded font */
+    #[serde(rename = "font")]
+    pub font: String,
+    /* merged in for the glyphs of the language the font lacks, like a cjk font */
+    #[serde(rename = "fallback font")]
+    pub fallback_font: String,
+    #[serde(rename = "font size")]
+    pub font_size: f32,
+    #[serde(rename = "frame rate limit")]
+    pub frame_rate_limit: u32,
+    /* like de or pt-BR, the language of the system when empty */
+    #[serde(rename = "language")]
+    pub language: String,
+    #[serde(rename = "remember ui layout")]
+    pub remember_ui_layout: bool,
+    #[serde(rename = "gamepad deadzone")]
+    pub gamepad_deadzone: f32,
+    /* scales the camera speeds of the sticks and triggers */
+    #[serde(rename = "gamepad sensitivity")]
+    pub gamepad_sensitivity: f32,
+    #[serde(rename = "integrator")]
+    pub integrator: Integrator,
+    /* recorded positions per trail */
+    #[serde(rename = "trail length")]
+    pub trail_length: usize,
+    /* minutes, 0 turns autosaving off */
+    #[serde(rename = "autosave interval")]
+    pub autosave_interval: u32,
+    /* of quicksaves and autosaves */
+    #[serde(rename = "save format")]
+    pub save_format: SaveFormat,
+    #[serde(rename = "when hidden")]
+    pub hidden_policy: HiddenPolicy,
+    #[serde(rename = "keymap")]
+    pub keymap: Keymap,
+    /* where the file dialogs open, the directory of the file chosen last */
+    #[serde(rename = "last directory")]
+    pub last_directory: Option<PathBuf>,
+    /* not edited in the settings window, remembered from the window itself */
+    #[serde(rename = "window")]
+    pub window: WindowGeometry,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            present_preference: surface::DEFAULT_PRESENT_PREFERENCE,
+            sample_count: targets::DEFAULT_SAMPLE_COUNT,
+            render_scale: viewport::DEFAULT_RENDER_SCALE,
+            tonemapping: true,
+            tonemap: TonemapSettings::default(),
+            bloom: BloomSettings::default(),
+            lighting: true,
+            starfield: StarfieldConfiguration::default(),
+            labels: LabelSettings::default(),
+            billboards: BillboardSettings::default(),
+            ui_scale: DEFAULT_UI_SCALE,
+            font: String::new(),
+            fallback_font: String::new(),
+            font_size: fonts::DEFAULT_FONT_SIZE,
+            frame_rate_limit: UNLIMITED_FRAME_RATE,
+            language: String::new(),
+            remember_ui_layout: true,
+            gamepad_deadzone: DEFAULT_GAMEPAD_DEADZONE,
+            gamepad_sensitivity: DEFAULT_GAMEPAD_SENSITIVITY,
+            integrator: Integrator::default(),
+            trail_length: trail::DEFAULT_TRAIL_CAPACITY,
+            autosave_interval: DEFAULT_AUTOSAVE_INTERVAL,
+            save_format: SaveFormat::default(),
+            hidden_policy: HiddenPolicy::default(),
+            keymap: Keymap::default(),
+            last_directory: None,
+            window: WindowGeometry::default(),
+        }
+    }
+}
+
+pub fn settings_path() -> PathBuf {
+    platform::config_directory().join(SETTINGS_FILE_NAME)
+}
+
+/* defaults for a missing or unreadable file, so a broken file never keeps the application from
+starting */
+pub fn load_settings(path: &Path) -> Settings {
+    if !path.exists() {
+        return Settings::default();
+    }
+    self::read_settings(path).unwrap_or_else(|error| {
+        warn!("Ignoring the settings in {}: {:#}", path.display(), error);
+        Settings::default()
+    })
+}
+
+pub fn save_settings(path: &Path, settings: &Settings) -> Result<()> {
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)
+            .with_context(|| format!("Failed to create {}", directory.display()))?;
+    }
+    let text = serde_yml::to_string(settings).context("Failed to serialize the settings")?;
+    std::fs::write(path, text).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+pub fn ui_layout_path() -> PathBuf {
+    platform::config_directory().join(UI_LAYOUT_FILE_NAME)
+}
+
+/* imgui fails silently when it cannot write the file, so the directory is created and probed
+before the path is handed to it */
+pub fn prepare_ui_layout_path(path: &Path) -> Result<()> {
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)
+            .with_context(|| format!("Failed to create {}", directory.display()))?;
+    }
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {} for writing", path.display()))?;
+    Ok(())
+}
+
+/* the first slot keeps the name of the single quicksave of before */
+pub fn quicksave_stem(slot: usize) -> String {
+    match slot {
+        0 => QUICKSAVE_FILE_STEM.to_string(),
+        slot => format!("{}_{}", QUICKSAVE_FILE_STEM, slot),
+    }
+}
+
+pub fn quicksave_path(slot: usize, format: SaveFormat) -> PathBuf {
+    self::save_path(&self::quicksave_stem(slot), format)
+}
+
+/* the first empty slot, or the one written longest ago */
+pub fn next_quicksave_slot() -> usize {
+    self::oldest_slot(
+        &self::save_directories(),
+        QUICKSAVE_SLOTS,
+        self::quicksave_stem,
+    )
+}
+
+/* of any slot and either format, the settings may have changed since it was written */
+pub fn latest_quicksave() -> Option<PathBuf> {
+    (0..QUICKSAVE_SLOTS)
+        .filter_map(|slot| self::newest_save(&self::quicksave_stem(slot)))
+        .max_by_key(|(_, modified)| *modified)
+        .map(|(path, _)| path)
+}
+
+pub fn autosave_stem(slot: usize) -> String {
+    format!("autosave_{}", slot)
+}
+
+pub fn autosave_path(slot: usize, format: SaveFormat) -> PathBuf {
+    self::save_path(&self::autosave_stem(slot), format)
+}
+
+/* the first empty slot, or the one written longest ago */
+pub fn next_autosave_slot() -> usize {
+    self::oldest_slot(
+        &self::save_directories(),
+        AUTOSAVE_SLOTS,
+        self::autosave_stem,
+    )
+}
+
+pub fn newest_autosave() -> Option<(PathBuf, SystemTime)> {
+    (0..AUTOSAVE_SLOTS)
+        .filter_map(|slot| self::newest_save(&self::autosave_stem(slot)))
+        .max_by_key(|(_, modified)| *modified)
+}
+
+/* the slot written last in the other formats, after switching them */
+pub fn remove_other_formats(path: &Path) {
+    let Some(written) = SaveFormat::from_path(path) else {
+        return;
+    };
+    for format in SaveFormat::iter().filter(|format| *format != written) {
+        for extension in format.extensions() {
+            let other = path.with_extension(extension);
+            if let Err(error) = std::fs::remove_file(&other)
+                && error.kind() != std::io::ErrorKind::NotFound
+            {
+                warn!("Failed to delete {}: {}", other.display(), error);
+            }
+        }
+    }
+}
+
+fn save_path(stem: &str, format: SaveFormat) -> PathBuf {
+    self::saves_directory().join(format!("{}.{}", stem, format.extensions()[0]))
+}
+
+/* the saves written before the saves directory lay in the data directory, they are still read
+until a newer one is written */
+fn save_directories() -> [PathBuf; 2] {
+    [self::saves_directory(), platform::data_directory()]
+}
+
+fn newest_save(stem: &str) -> Option<(PathBuf, SystemTime)> {
+    self::newest_save_in(&self::save_directories(), stem)
+}
+
+/* an empty slot sorts first since none is older than every time */
+fn oldest_slot(directories: &[PathBuf], slots: usize, stem: fn(usize) -> String) -> usize {
+    (0..slots)
+        .min_by_key(|slot| {
+            self::newest_save_in(directories, &stem(*slot)).map(|(_, modified)| modified)
+        })
+        .unwrap_or(0)
+}
+
+fn newest_save_in(directories: &[PathBuf], stem: &str) -> Option<(PathBuf, SystemTime)> {
+    directories
+        .iter()
+        .flat_map(|directory| {
+            SaveFormat::iter()
+                .flat_map(|format| format.extensions().iter())
+                .map(move |extension| directory.join(format!("{}.{}", stem, extension)))
+        })
+        .filter_map(|path| Some((path.clone(), self::modified(&path)?)))
+        .max_by_key(|(_, modified)| *modified)
+}
+
+/* none for a missing file, which sorts before every written one */
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/* through a file next to it that is renamed over it, a crash while writing leaves the previous
+save intact. the format overrides the extension, yaml without either */
+pub fn save_state(path: &Path, save_game: &SaveGame, format: Option<SaveFormat>) -> Result<()> {
+    let format = format
+        .or_else(|| SaveFormat::from_path(path))
+        .unwrap_or_default();
+    let bytes = match format {
+        SaveFormat::Yaml => serde_yml::to_string(save_game)
+            .context("Failed to serialize the state")?
+            .into_bytes(),
+        SaveFormat::Binary => binary::to_bytes(save_game).context("Failed to encode the state")?,
+    };
+    self::write_atomically(path, &bytes)
+}
+
+pub fn write_atomically(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)
+            .with_context(|| format!("Failed to create {}", directory.display()))?;
+    }
+    let mut temporary_name = path.file_name().unwrap_or_default().to_os_string();
+    temporary_name.push(".tmp");
+    let temporary_path = path.with_file_name(temporary_name);
+    let written = std::fs::File::create(&temporary_path)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .with_context(|| format!("Failed to write {}", temporary_path.display()));
+    if let Err(error) = written {
+        let _ = std::fs::remove_file(&temporary_path);
+        return Err(error);
+    }
+    std::fs::rename(&temporary_path, path).with_context(|| {
+        format!(
+            "Failed to replace {} with {}",
+            path.display(),
+            temporary_path.display()
+        )
+    })
+}
+
+pub fn session_marker_path() -> PathBuf {
+    platform::data_directory().join(SESSION_MARKER_FILE_NAME)
+}
+
+/* true when the marker of the previous session is still there, it crashed or was killed. the
+marker is written again for this one */
+pub fn begin_session() -> Result<bool> {
+    let path = self::session_marker_path();
+    let unclean = path.exists();
+    self::write_atomically(&path, std::process::id().to_string().as_bytes())?;
+    Ok(unclean)
+}
+
+pub fn end_session() {
+    let path = self::session_marker_path();
+    if let Err(error) = std::fs::remove_file(&path)
+        && error.kind() != std::io::ErrorKind::NotFound
+    {
+        warn!("Failed to delete {}: {}", path.display(), error);
+    }
+}
+
+pub fn load_state(path: &Path) -> Result<SaveGame> {
+    let bytes =
+        std::fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    /* the header tells, not the extension */
+    let read = || -> Result<SaveGame> {
+        if binary::is_binary(&bytes) {
+            return binary::from_bytes(&bytes);
+        }
+        let mut value: serde_yml::Value =
+            serde_yml::from_str(std::str::from_utf8(&bytes).context("The save is not text")?)?;
+        migration::migrate(&mut value, "save", &SAVE_GAME_MIGRATIONS)?;
+        Ok(serde_yml::from_value(value)?)
+    };
+    read().with_context(|| format!("{} is not a valid save", path.display()))
+}
+
+pub fn scenarios_directory() -> PathBuf {
+    platform::data_directory().join(SCENARIOS_DIRECTORY_NAME)
+}
+
+pub fn default_scenario_path() -> PathBuf {
+    self::scenarios_directory().join(SCENARIO_FILE_NAME)
+}
+
+pub fn is_scenario_path(path: &Path) -> bool {
+    ScenarioFormat::from_path(path).is_some()
+}
+
+/* the format overrides the extension, for a path without a known one. stamps the modified date
+of the scenario */
+pub fn save_scenario_as(
+    path: &Path,
+    scenario: &mut Scenario,
+    format: Option<ScenarioFormat>,
+) -> Result<()> {
+    scenario.metadata.touch();
+    if let Some(directory) = path.parent() {
+        std::fs::create_dir_all(directory)
+            .with_context(|| format!("Failed to create {}", directory.display()))?;
+    }
+    let format = self::scenario_format(path, format)?;
+    let text = self::format_scenario(scenario, format)
+        .with_context(|| format!("Failed to write the scenario as {}", format))?;
+    std::fs::write(path, text).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/* the given format, or the one of the extension */
+fn scenario_format(path: &Path, format: Option<ScenarioFormat>) -> Result<ScenarioFormat> {
+    match format.or_else(|| ScenarioFormat::from_path(path)) {
+        Some(format) => Ok(format),
+        None => bail!(
+            "{} is not a scenario, scenarios are {} files",
+            path.display(),
+            SCENARIO_EXTENSIONS.join(", ")
+        ),
+    }
+}
+
+/* every format is read into the value tree of serde_yml, so they deserialize the same structs
+with the same renames. an older file is migrated on that tree too. ron structs become mappings
+like its maps, a bare enum variant has no value in the tree and is written as a string instead */
+fn parse_scenario(text: &str, format: ScenarioFormat) -> Result<(Scenario, Value)> {
+    let mut value = match format {
+        ScenarioFormat::Yaml => serde_yml::from_str(text)?,
+        ScenarioFormat::Json => serde_json::from_str(text)?,
+        ScenarioFormat::Toml => toml::from_str(text)?,
+        ScenarioFormat::Ron => ron::from_str(text)?,
+    };
+    let current = migration::version(&value)? == SCENARIO_VERSION;
+    migration::migrate(&mut value, "scenario", &SCENARIO_MIGRATIONS)?;
+    let mut scenario: Scenario = match format {
+        /* the errors of the text name the line and column, those of the tree cannot */
+        ScenarioFormat::Yaml if current => serde_yml::from_str(text)?,
+        ScenarioFormat::Json if current => serde_json::from_str(text)?,
+        _ => serde_yml::from_value(value.clone())?,
+    };
+    scenario.version = SCENARIO_VERSION;
+    let overflows = scenario.units.convert_into_si(&mut scenario.objects);
+    if !overflows.is_empty() {
+        bail!(
+            "The units do not convert:\n{}",
+            overflows
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
+    if let Value::Mapping(fields) = &value {
+        scenario.unknown_fields = migration::unknown_fields::<Scenario>(fields);
+    }
+    Ok((scenario, value))
+}
+
+/* the ignored fields of the bodies, which are not kept like those of the scenario */
+fn unknown_body_fields(value: &Value) -> Vec<(String, String)> {
+    let Some(Value::Sequence(bodies)) = value.get("bodies") else {
+        return Vec::new();
+    };
+    bodies
+        .iter()
+        .filter_map(|body| {
+            let Value::Mapping(fields) = body else {
+                return None;
+            };
+            let unknown = migration::unknown_fields::<SimulationObject>(fields);
+            let name = body.get("name").and_then(Value::as_str).unwrap_or("a body");
+            (!unknown.is_empty()).then(|| (name.to_string(), migration::field_names(&unknown)))
+        })
+        .collect()
+}
+
+/* the bodies are written in the units of the scenario */
+fn format_scenario(scenario: &Scenario, format: ScenarioFormat) -> Result<String> {
+    let mut value = serde_yml::to_value(scenario)?;
+    if let Value::Mapping(fields) = &mut value {
+        if !scenario.units.is_si() {
+            fields.insert(
+                Value::from("bodies"),
+                serde_yml::to_value(scenario.units.written_from_si(&scenario.objects))?,
+            );
+        }
+        for (key, field) in &scenario.unknown_fields {
+            fields.entry(key.clone()).or_insert_with(|| field.clone());
+        }
+    }
+    match format {
+        ScenarioFormat::Yaml => Ok(serde_yml::to_string(&value)?),
+        ScenarioFormat::Json => Ok(serde_json::to_string_pretty(&value)? + "\n"),
+        ScenarioFormat::Toml => Ok(toml::to_string(&self::without_nulls(value))?),
+        ScenarioFormat::Ron => Ok(ron::ser::to_string_pretty(
+            &value,
+            ron::ser::PrettyConfig::new().indentor(RON_INDENT),
+        )? + "\n"),
+    }
+}
+
+/* toml has no null, the fields that are none are left out of the tables */
+fn without_nulls(value: Value) -> Value {
+    match value {
+        Value::Mapping(fields) => Value::Mapping(
+            fields
+                .into_iter()
+                .filter(|(_, field)| !field.is_null())
+                .map(|(key, field)| (key, self::without_nulls(field)))
+                .collect(),
+        ),
+        Value::Sequence(values) => {
+            Value::Sequence(values.into_iter().map(self::without_nulls).collect())
+        }
+        value => value,
+    }
+}
+
+/* unlike the settings a scenario is all or nothing, a body that cannot be read fails the file */
+pub fn load_scenario(path: &Path) -> Result<Scenario> {
+    self::load_scenario_as(path, None)
+}
+
+pub fn load_scenario_as(path: &Path, format: Option<ScenarioFormat>) -> Result<Scenario> {
+    let format = self::scenario_format(path, format)?;
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let (mut scenario, value) = self::parse_scenario(&text, format)
+        .with_context(|| format!("{} is not a valid {} scenario", path.display(), format))?;
+    scenario.metadata.fill_from_path(path);
+    if scenario.objects.is_empty() {
+        bail!("{} has no bodies", path.display());
+    }
+    if let Err(errors) = scenario.validate() {
+        bail!(
+            "{} cannot be simulated:\n{}",
+            path.display(),
+            errors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+    }
+    for warning in scenario.warnings(&AssetResolver::for_scenario(Some(path))) {
+        warn!("{}: {}", path.display(), warning);
+    }
+    if !scenario.unknown_fields.is_empty() {
+        warn!(
+            "{} has fields this version does not read, they are kept when saving: {}",
+            path.display(),
+            migration::field_names(&scenario.unknown_fields)
+        );
+    }
+    for (body, fields) in self::unknown_body_fields(&value) {
+        warn!(
+            "{} has fields this version does not read on {}, they are ignored: {}",
+            path.display(),
+            body,
+            fields
+        );
+    }
+    Ok(scenario)
+}
+
+/* fields are taken one by one on top of the defaults. fields of a newer version are ignored and
+a value this version cannot read only resets its own field */
+fn read_settings(path: &Path) -> Result<Settings> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let serde_yml::Value::Mapping(fields) =
+        serde_yml::from_str(&text).context("The settings are not valid yaml")?
+    else {
+        bail!("The settings are not a mapping");
+    };
+    let serde_yml::Value::Mapping(mut merged) = serde_yml::to_value(Settings::default())? else {
+        unreachable!("settings serialize to a mapping");
+    };
+    for (key, value) in fields {
+        let Some(slot) = merged.get_mut(&key) else {
+            continue;
+        };
+        let default = std::mem::replace(slot, value);
+        if serde_yml::from_value::<Settings>(serde_yml::Value::Mapping(merged.clone())).is_err() {
+            warn!("Ignoring the invalid setting {:?}", key);
+            merged.insert(key, default);
+        }
+    }
+    let mut settings: Settings = serde_yml::from_value(serde_yml::Value::Mapping(merged))?;
+    if settings.version > SETTINGS_VERSION {
+        warn!(
+            "The settings were written by a newer version ({}), unknown fields are dropped on save",
+            settings.version
+        );
+    }
+    settings.version = SETTINGS_VERSION;
+    settings.ui_scale = settings.ui_scale.clamp(MINIMUM_UI_SCALE, MAXIMUM_UI_SCALE);
+    settings.font_size = settings
+        .font_size
+        .clamp(fonts::MINIMUM_FONT_SIZE, fonts::MAXIMUM_FONT_SIZE);
+    settings.gamepad_deadzone = settings
+        .gamepad_deadzone
+        .clamp(0., MAXIMUM_GAMEPAD_DEADZONE);
+    settings.gamepad_sensitivity = settings
+        .gamepad_sensitivity
+        .clamp(MINIMUM_GAMEPAD_SENSITIVITY, MAXIMUM_GAMEPAD_SENSITIVITY);
+    for (binding, actions) in settings.keymap.conflicts() {
+        warn!(
+            "{} is bound to {:?}, it only triggers the first",
+            binding, actions
+        );
+    }
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::tonemap::TonemapCurve;
+    use crate::storage::units::{LengthUnit, MassUnit, TimeUnit};
+    use crate::{graphics::camera::CameraPose, scenario, simulation};
+    use glam::DVec3;
+
+    #[test]
+    fn render_settings_round_trip() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join(SETTINGS_FILE_NAME);
+        let mut settings = Settings {
+            tonemapping: false,
+            lighting: false,
+            ..Settings::default()
+        };
+        settings.tonemap.curve = TonemapCurve::Reinhard;
+        settings.tonemap.exposure = 2.5;
+        settings.tonemap.gamma = 1.4;
+        settings.bloom.intensity = 1.5;
+        settings.bloom.threshold = 2.;
+        settings.starfield.star_count = 12_000;
+        settings.starfield.seed = 7;
+        settings.labels.hidden_tags.insert("moon".to_string());
+        settings.billboards.minimum_pixels = 4.;
+        save_settings(&path, &settings).unwrap();
+        assert_eq!(load_settings(&path), settings);
+    }
+
+    #[test]
+    fn partial_render_settings_keep_their_defaults() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join(SETTINGS_FILE_NAME);
+        std::fs::write(
+            &path,
+            "bloom:\n  intensity: 2\nlighting: maybe\nlabels:\n  hidden tags: [spacecraft]\n",
+        )
+        .unwrap();
+        let settings = load_settings(&path);
+        assert_eq!(settings.bloom.intensity, 2.);
+        assert_eq!(settings.bloom.threshold, BloomSettings::default().threshold);
+        assert!(settings.bloom.enabled);
+        /* the invalid value is ignored by itself */
+        assert!(settings.lighting);
+        assert!(settings.labels.enabled);
+        assert!(settings.labels.hidden_tags.contains("spacecraft"));
+        assert_eq!(settings.starfield, StarfieldConfiguration::default());
+    }
+
+    #[test]
+    fn json_scenario_round_trip() {
+        let mut scenario = presets::solar_system();
+        scenario.metadata.name = "Round trip".to_string();
+        let text = format_scenario(&scenario, ScenarioFormat::Json).unwrap();
+        /* the renames are the keys of the file, written with two spaces of indent */
+        assert!(text.contains("\n  \"bodies\": ["), "{}", text);
+        assert!(text.contains("\"compute values\": {"), "{}", text);
+        assert!(text.contains("\"enter simulation values\": {"), "{}", text);
+        let (parsed, _) = parse_scenario(&text, ScenarioFormat::Json).unwrap();
+        assert_eq!(parsed.metadata.name, "Round trip");
+        assert_eq!(
+            serde_yml::to_value(&parsed.objects).unwrap(),
+            serde_yml::to_value(&scenario.objects).unwrap()
+        );
+        assert_eq!(
+            format_scenario(&parsed, ScenarioFormat::Json).unwrap(),
+            text
+        );
+    }
+
+    #[test]
+    fn json_scenarios_are_not_read_as_yaml() {
+        let yaml = format_scenario(&presets::solar_system(), ScenarioFormat::Yaml).unwrap();
+        assert!(parse_scenario(&yaml, ScenarioFormat::Yaml).is_ok());
+        assert!(parse_scenario(&yaml, ScenarioFormat::Json).is_err());
+        let json = format_scenario(&presets::solar_system(), ScenarioFormat::Json).unwrap();
+        let trailing_comma = json.replacen("\n  ]", ",\n  ]", 1);
+        let error = parse_scenario(&trailing_comma, ScenarioFormat::Json).unwrap_err();
+        /* the error of serde_json names the line and column */
+        assert!(error.to_string().contains("line"), "{}", error);
+    }
+
+    /* the solar system in astronomical units and solar masses, so the units are written too */
+    fn scenario() -> Scenario {
+        let mut scenario = presets::solar_system();
+        scenario.metadata.name = "Round trip".to_string();
+        scenario.units = ScenarioUnits {
+            length: LengthUnit::AstronomicalUnit,
+            mass: MassUnit::SolarMass,
+            time: TimeUnit::Year,
+        };
+        scenario
+    }
+
+    #[test]
+    fn every_format_round_trips() {
+        let scenario = self::scenario();
+        for format in ScenarioFormat::iter() {
+            let text = format_scenario(&scenario, format).unwrap();
+            let (parsed, _) = parse_scenario(&text, format)
+                .unwrap_or_else(|error| panic!("{}: {:#}\n{}", format, error, text));
+            assert_eq!(parsed.metadata.name, "Round trip", "{}", format);
+            assert_eq!(parsed.units, scenario.units, "{}", format);
+            assert_eq!(
+                format_scenario(&parsed, format).unwrap(),
+                text,
+                "{}",
+                format
+            );
+            assert_eq!(parsed.objects.len(), scenario.objects.len(), "{}", format);
+            /* converted out of si and back, a few bits of the mantissa may change */
+            for (parsed, original) in parsed.objects.iter().zip(&scenario.objects) {
+                let (parsed, original) = (&parsed.physics_data, &original.physics_data);
+                assert!(
+                    (parsed.simulation_body_mass - original.simulation_body_mass).abs()
+                        <= original.simulation_body_mass * 1e-6,
+                    "{}: {} != {}",
+                    format,
+                    parsed.simulation_body_mass,
+                    original.simulation_body_mass
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn yaml_through_ron_is_unchanged() {
+        let yaml = format_scenario(&self::scenario(), ScenarioFormat::Yaml).unwrap();
+        let (from_yaml, _) = parse_scenario(&yaml, ScenarioFormat::Yaml).unwrap();
+        let ron = format_scenario(&from_yaml, ScenarioFormat::Ron).unwrap();
+        let (from_ron, _) = parse_scenario(&ron, ScenarioFormat::Ron).unwrap();
+        assert_eq!(
+            format_scenario(&from_ron, ScenarioFormat::Yaml).unwrap(),
+            yaml
+        );
+    }
+
+    #[test]
+    fn handwritten_ron_is_read() {
+        let text = r#"
+            /* structs without their name are read like maps */
+            (
+                version: VERSION,
+                metadata: (name: "Pair"),
+                units: (length: "km"),
+                bodies: [
+                    {
+                        "name": "Primary",
+                        "compute values": (mass: 1e24, radius: 6e3),
+                        "enter simulation values": {
+                            "enter position": [0.0, 0.0, 0.0],
+                            "enter speed": [0.0, 0.0, 0.0], // trailing commas are fine
+                        },
+                    },
+                ],
+            )
+        "#
+        .replace("VERSION", &SCENARIO_VERSION.to_string());
+        let (scenario, _) = parse_scenario(&text, ScenarioFormat::Ron).unwrap();
+        assert_eq!(scenario.metadata.name, "Pair");
+        assert_eq!(scenario.objects[0].id_name, "Primary");
+        assert_eq!(scenario.objects[0].physics_data.simulation_body_radius, 6e6);
+        let error = parse_scenario("(bodies: [", ScenarioFormat::Ron).unwrap_err();
+        assert!(error.to_string().contains("1:"), "{}", error);
+    }
+
+    #[test]
+    fn a_loaded_state_equals_the_saved_one() {
+        let directory = tempfile::tempdir().unwrap();
+        let settings = Settings::default();
+        let mut state =
+            scenario::simulation_state(&simulation::sun_earth_moon_objects(), &settings);
+        for _ in 0..10 {
+            state.step();
+        }
+        let save_game = SaveGame {
+            version: SAVE_GAME_VERSION,
+            scenario_label: "Sun, Earth and Moon".to_string(),
+            scenario_path: Some(directory.path().join("orbit.yaml")),
+            simulation_time: state.simulation_time,
+            warp: 3600.,
+            paused: true,
+            dirty: true,
+            epoch: 946_728_000,
+            camera: CameraPose {
+                target: DVec3::new(1., 2., 3.),
+                distance: 4e8,
+                azimuth: 0.5,
+                elevation: -0.25,
+                follow_target: state.bodies.get(1).map(|body| body.id),
+                follow_offset: DVec3::new(0., 0., 1e7),
+            },
+            bodies: state.bodies.clone(),
+        };
+        for format in SaveFormat::iter() {
+            let path = directory
+                .path()
+                .join(format!("state.{}", format.extensions()[0]));
+            save_state(&path, &save_game, Some(format)).unwrap();
+            let loaded = load_state(&path).unwrap();
+            assert_eq!(loaded, save_game, "{}", format);
+            /* restored without a step in between */
+            let restored = scenario::restored_state(&loaded, &settings);
+            assert_eq!(restored.bodies, state.bodies, "{}", format);
+            assert_eq!(
+                restored.simulation_time, state.simulation_time,
+                "{}",
+                format
+            );
+            assert!(restored.dirty);
+        }
+    }
+
+    /* a save of the slot written the given seconds after an arbitrary start */
+    fn write_slot(directory: &Path, stem: &str, extension: &str, seconds: u64) {
+        let path = directory.join(format!("{}.{}", stem, extension));
+        std::fs::write(&path, "").unwrap();
+        let modified =
+            SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000 + seconds);
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[test]
+    fn autosaves_rotate_through_the_slots() {
+        let directory = tempfile::tempdir().unwrap();
+        let directories = [directory.path().to_path_buf()];
+        let next = || oldest_slot(&directories, AUTOSAVE_SLOTS, autosave_stem);
+        assert_eq!(next(), 0);
+        self::write_slot(directory.path(), &autosave_stem(0), "yml", 10);
+        assert_eq!(next(), 1);
+        self::write_slot(directory.path(), &autosave_stem(1), "yml", 20);
+        self::write_slot(directory.path(), &autosave_stem(2), "yml", 30);
+        /* all written, the oldest is replaced */
+        assert_eq!(next(), 0);
+        self::write_slot(directory.path(), &autosave_stem(0), "yml", 40);
+        assert_eq!(next(), 1);
+        /* the newest format of a slot counts, after switching to the binary one */
+        self::write_slot(directory.path(), &autosave_stem(1), "sps", 50);
+        assert_eq!(next(), 2);
+    }
+
+    #[test]
+    fn old_saves_in_the_data_directory_take_part_in_the_rotation() {
+        let saves = tempfile::tempdir().unwrap();
+        let data = tempfile::tempdir().unwrap();
+        let directories = [saves.path().to_path_buf(), data.path().to_path_buf()];
+        self::write_slot(data.path(), &quicksave_stem(0), "yml", 10);
+        self::write_slot(saves.path(), &quicksave_stem(1), "yml", 20);
+        assert_eq!(
+            oldest_slot(&directories, QUICKSAVE_SLOTS, quicksave_stem),
+            2
+        );
+        self::write_slot(saves.path(), &quicksave_stem(2), "yml", 30);
+        assert_eq!(
+            oldest_slot(&directories, QUICKSAVE_SLOTS, quicksave_stem),
+            0
+        );
+        assert_eq!(
+            newest_save_in(&directories, &quicksave_stem(0)).map(|(path, _)| path),
+            Some(data.path().join(format!("{}.yml", quicksave_stem(0))))
+        );
+    }
+
+    #[test]
+    fn an_atomic_write_replaces_the_file_without_leftovers() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("nested").join("autosave_0.yml");
+        write_atomically(&path, b"first").unwrap();
+        write_atomically(&path, b"second").unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        let names: Vec<_> = std::fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["autosave_0.yml"]);
+    }
+
+    #[test]
+    fn a_failed_atomic_write_keeps_the_previous_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("autosave_0.yml");
+        write_atomically(&path, b"previous").unwrap();
+        /* the temporary file cannot be created where a directory is */
+        std::fs::create_dir(directory.path().join("autosave_0.yml.tmp")).unwrap();
+        assert!(write_atomically(&path, b"next").is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), b"previous");
+    }
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join(name)
+    }
+
+    #[test]
+    fn a_v1_scenario_is_migrated_and_keeps_its_unknown_fields() {
+        let scenario = load_scenario(&self::fixture("scenario_v1.yaml")).unwrap();
+        assert_eq!(scenario.version, SCENARIO_VERSION);
+        assert_eq!(scenario.metadata.name, "Earth and Moon");
+        assert_eq!(scenario.metadata.author, "SpaceS");
+        assert_eq!(scenario.objects.len(), 2);
+        assert_eq!(scenario.objects[1].id_name, "Moon");
+        assert_eq!(
+            migration::field_names(&scenario.unknown_fields),
+            "laboratory notes"
+        );
+        let text = format_scenario(&scenario, ScenarioFormat::Yaml).unwrap();
+        assert!(
+            text.contains("laboratory notes: kept when the scenario is saved again"),
+            "{}",
+            text
+        );
+        assert!(text.starts_with(&format!("version: {}\nmetadata:\n", SCENARIO_VERSION)));
+    }
+
+    #[test]
+    fn a_v1_save_loads() {
+        let save_game = load_state(&self::fixture("save_v1.yml")).unwrap();
+        assert_eq!(save_game.version, SAVE_GAME_VERSION);
+        assert_eq!(save_game.scenario_label, "Earth and Moon");
+        assert_eq!(save_game.scenario_path, None);
+        assert_eq!(save_game.epoch, SaveGame::default_epoch());
+        assert_eq!(save_game.camera.follow_target, None);
+        assert_eq!(save_game.bodies[0].id_name, "Earth");
+    }
+
+    #[test]
+    fn the_dates_of_a_scenario_must_parse() {
+        let table = [
+            (Some("2000-01-01 12:00"), None, Vec::new()),
+            (
+                Some("first of may"),
+                None,
+                vec!["The scenario: epoch must be a date like 2000-01-01 12:00, got first of may"],
+            ),
+            (
+                None,
+                Some("yesterday"),
+                vec!["The scenario: created must be a date like 2000-01-01 12:00, got yesterday"],
+            ),
+        ];
+        for (epoch, created, expected) in table {
+            let mut scenario = Scenario::new(simulation::sun_earth_moon_objects());
+            scenario.epoch = epoch.map(str::to_string);
+            scenario.metadata.created = created.map(str::to_string);
+            let errors: Vec<String> = match scenario.validate() {
+                Result::Ok(()) => Vec::new(),
+                Err(errors) => errors.iter().map(ToString::to_string).collect(),
+            };
+            assert_eq!(errors, expected);
+        }
+    }
+
+    #[test]
+    fn the_metadata_survives_every_format() {
+        let directory = tempfile::tempdir().unwrap();
+        let mut scenario = self::scenario();
+        scenario.metadata = ScenarioMetadata {
+            name: "Sonnensystem \"groß\"".to_string(),
+            author: "A. Author, B. Author".to_string(),
+            description: "Eight planets.\nThe dwarf planets are left out: too small".to_string(),
+            created: Some("1999-12-31 23:59".to_string()),
+            modified: None,
+            thumbnail: Some(format!("{}iVBORw0KGgo=", EMBEDDED_THUMBNAIL_PREFIX)),
+        };
+        for format in ScenarioFormat::iter() {
+            let path = directory
+                .path()
+                .join(format!("system.{}", format.extensions()[0]));
+            save_scenario_as(&path, &mut scenario, Some(format)).unwrap();
+            /* saving stamps the modified date and keeps the created one */
+            assert_eq!(
+                scenario.metadata.created.as_deref(),
+                Some("1999-12-31 23:59")
+            );
+            assert!(scenario.metadata.modified.is_some());
+            let loaded = load_scenario(&path).unwrap();
+            assert_eq!(loaded.metadata, scenario.metadata, "{}", format);
+            assert_eq!(
+                loaded.metadata.thumbnail(&path).unwrap().unwrap(),
+                Thumbnail::Png(b"\x89PNG\r\n\x1a\n".to_vec()),
+                "{}",
+                format
+            );
+        }
+    }
+
+    #[test]
+    fn a_scenario_without_metadata_is_named_after_its_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("binary stars.yaml");
+        let mut scenario = Scenario::new(simulation::sun_earth_moon_objects());
+        let text = format_scenario(&scenario, ScenarioFormat::Yaml).unwrap();
+        assert!(!text.contains("metadata"), "{}", text);
+        std::fs::write(&path, text).unwrap();
+        let loaded = load_scenario(&path).unwrap();
+        assert_eq!(loaded.metadata.name, "binary stars");
+        assert!(loaded.metadata.author.is_empty());
+        assert!(loaded.metadata.modified.is_some());
+        scenario.metadata.name = "Named".to_string();
+        std::fs::write(
+            &path,
+            format_scenario(&scenario, ScenarioFormat::Yaml).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(load_scenario(&path).unwrap().metadata.name, "Named");
+    }
+}
@@ -0,0 +1,86 @@
+use std::path::{Component, Path, PathBuf};
+
+use tracing::warn;
+
+use crate::platform;
+
+/* where the files a scenario references are looked up, like the textures of its bodies. a
+scenario folder moved anywhere keeps finding what lies next to it */
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssetResolver {
+    roots: Vec<PathBuf>,
+}
+
+impl AssetResolver {
+    /* the directory of the scenario file first, then the assets next to the executable and in
+    its parents like platform::asset_path, then the data directory */
+    pub fn for_scenario(scenario_path: Option<&Path>) -> Self {
+        let mut roots = Vec::new();
+        if let Some(directory) = scenario_path.and_then(Path::parent) {
+            roots.push(directory.to_path_buf());
+        }
+        if let Some(directory) = platform::executable_directory() {
+            roots.extend(directory.ancestors().map(Path::to_path_buf));
+        }
+        roots.push(platform::data_directory());
+        Self::with_roots(roots)
+    }
+
+    /* searched in order, a relative root is taken from the working directory */
+    pub fn with_roots(roots: Vec<PathBuf>) -> Self {
+        Self {
+            roots: roots
+                .into_iter()
+                .map(|root| std::path::absolute(&root).unwrap_or(root))
+                .collect(),
+        }
+    }
+
+    /* absolute paths and references without a hit are returned unchanged. a reference climbing
+    out of the root it was found in through .. still resolves, it is only logged */
+    pub fn resolve(&self, reference: impl AsRef<Path>) -> PathBuf {
+        let reference = reference.as_ref();
+        if reference.is_absolute() {
+            return reference.to_path_buf();
+        }
+        for root in &self.roots {
+            let candidate = root.join(reference);
+            if !candidate.exists() {
+                continue;
+            }
+            if !self::normalize(&candidate).starts_with(self::normalize(root)) {
+                warn!(
+                    "{} leaves {}, the asset is loaded from {}",
+                    reference.display(),
+                    root.display(),
+                    candidate.display()
+                );
+            }
+            return candidate;
+        }
+        reference.to_path_buf()
+    }
+}
+
+impl Default for AssetResolver {
+    fn default() -> Self {
+        Self::for_scenario(None)
+    }
+}
+
+/* resolves . and .. without touching the file system, links are not followed */
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push(component);
+                }
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
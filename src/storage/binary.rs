@@ -0,0 +1,473 @@
+use std::{io::Read, path::PathBuf};
+
+use anyhow::{Context, Result, bail};
+use crc32fast::Hasher;
+use glam::DVec3;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    graphics::camera::CameraPose,
+    simulation::{BodyAppearance, BodyId, RingDefinition, SimulationBody},
+    storage::{SAVE_GAME_VERSION, SaveGame},
+};
+
+/* the start of every binary save, what load_state sniffs */
+pub const MAGIC: [u8; 4] = *b"SPSB";
+/* of the encoding below, the structs inside are versioned like the yaml saves. version 1 was a
+tagged value tree */
+const ENCODING_VERSION: u8 = 2;
+const STORED: u8 = 0;
+/* 1 was deflate */
+const ZSTD: u8 = 2;
+/* the default level of zstd */
+const ZSTD_LEVEL: i32 = 3;
+/* magic, encoding version, compression, payload length and checksum */
+const HEADER_SIZE: usize = 4 + 1 + 1 + 8 + 4;
+
+pub fn is_binary(bytes: &[u8]) -> bool {
+    bytes.starts_with(&MAGIC)
+}
+
+/* the save in postcard, compressed with zstd. the checksum covers the payload before
+compression */
+pub fn to_bytes(save_game: &SaveGame) -> Result<Vec<u8>> {
+    let payload = postcard::to_stdvec(&BinarySave::from(save_game))?;
+    let compressed = zstd::bulk::compress(&payload, ZSTD_LEVEL)?;
+    let mut bytes = Vec::with_capacity(HEADER_SIZE + compressed.len());
+    bytes.extend_from_slice(&MAGIC);
+    bytes.push(ENCODING_VERSION);
+    bytes.push(ZSTD);
+    bytes.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&self::checksum(&payload).to_le_bytes());
+    bytes.extend_from_slice(&compressed);
+    Ok(bytes)
+}
+
+pub fn from_bytes(bytes: &[u8]) -> Result<SaveGame> {
+    if bytes.len() < HEADER_SIZE || !self::is_binary(bytes) {
+        bail!("The file is not a binary save");
+    }
+    let (header, body) = bytes.split_at(HEADER_SIZE);
+    if header[4] != ENCODING_VERSION {
+        bail!(
+            "The binary encoding version {} is not supported, this one reads {}",
+            header[4],
+            ENCODING_VERSION
+        );
+    }
+    let length = u64::from_le_bytes(header[6..14].try_into().unwrap());
+    let expected_checksum = u32::from_le_bytes(header[14..18].try_into().unwrap());
+    let payload = match header[5] {
+        STORED => body.to_vec(),
+        ZSTD => {
+            /* one byte more than announced is read, so a longer stream is noticed */
+            let mut payload = Vec::new();
+            zstd::Decoder::new(body)
+                .context("The compressed data is corrupted")?
+                .take(length.saturating_add(1))
+                .read_to_end(&mut payload)
+                .context("The compressed data is corrupted")?;
+            payload
+        }
+        compression => bail!("Unknown compression {}", compression),
+    };
+    if payload.len() as u64 != length {
+        bail!(
+            "The save holds {} bytes, its header announces {}",
+            payload.len(),
+            length
+        );
+    }
+    if self::checksum(&payload) != expected_checksum {
+        bail!("The file is corrupted, its checksum does not match");
+    }
+    /* the version leads the payload, a newer layout would not decode into this one. a migration
+    of the saves comes with the struct of the layout before it, to decode older binary saves */
+    let (version, _) = postcard::take_from_bytes::<u32>(&payload)?;
+    if version > SAVE_GAME_VERSION {
+        bail!(
+            "The save was written by a newer version of SpaceS (version {}, this one reads up to {})",
+            version,
+            SAVE_GAME_VERSION
+        );
+    }
+    let (save, rest) = postcard::take_from_bytes::<BinarySave>(&payload)?;
+    if !rest.is_empty() {
+        bail!("{} bytes follow the save", rest.len());
+    }
+    Ok(save.into())
+}
+
+fn checksum(payload: &[u8]) -> u32 {
+    let mut hasher = Hasher::new();
+    hasher.update(payload);
+    hasher.finalize()
+}
+
+/* the layout of the payload. postcard writes the fields in order without their names, so unlike
+the structs of the yaml saves no field is left out when it has its default */
+#[derive(Serialize, Deserialize)]
+struct BinarySave {
+    version: u32,
+    scenario_label: String,
+    scenario_path: Option<PathBuf>,
+    simulation_time: f64,
+    warp: f64,
+    paused: bool,
+    dirty: bool,
+    epoch: i64,
+    camera: BinaryCamera,
+    bodies: Vec<BinaryBody>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BinaryCamera {
+    target: DVec3,
+    distance: f32,
+    azimuth: f32,
+    elevation: f32,
+    follow_target: Option<u32>,
+    follow_offset: DVec3,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BinaryBody {
+    id: u32,
+    name: String,
+    mass: f64,
+    radius: f64,
+    position: DVec3,
+    velocity: DVec3,
+    texture: Option<String>,
+    color: Option<[f32; 3]>,
+    emissive: bool,
+    luminosity: Option<f64>,
+    tags: Vec<String>,
+    spin_axis: Option<[f32; 3]>,
+    rings: Option<BinaryRings>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BinaryRings {
+    inner_radius: f32,
+    outer_radius: f32,
+    texture: Option<String>,
+    bands: Vec<[f32; 4]>,
+    opacity: f32,
+}
+
+/* destructured completely, a field added to a save struct does not compile until it is here */
+impl From<&SaveGame> for BinarySave {
+    fn from(save_game: &SaveGame) -> Self {
+        let SaveGame {
+            version,
+            scenario_label,
+            scenario_path,
+            simulation_time,
+            warp,
+            paused,
+            dirty,
+            epoch,
+            camera,
+            bodies,
+        } = save_game;
+        let CameraPose {
+            target,
+            distance,
+            azimuth,
+            elevation,
+            follow_target,
+            follow_offset,
+        } = *camera;
+        Self {
+            version: *version,
+            scenario_label: scenario_label.clone(),
+            scenario_path: scenario_path.clone(),
+            simulation_time: *simulation_time,
+            warp: *warp,
+            paused: *paused,
+            dirty: *dirty,
+            epoch: *epoch,
+            camera: BinaryCamera {
+                target,
+                distance,
+                azimuth,
+                elevation,
+                follow_target: follow_target.map(|id| id.0),
+                follow_offset,
+            },
+            bodies: bodies.iter().map(BinaryBody::from).collect(),
+        }
+    }
+}
+
+impl From<BinarySave> for SaveGame {
+    fn from(save: BinarySave) -> Self {
+        let camera = save.camera;
+        Self {
+            version: save.version,
+            scenario_label: save.scenario_label,
+            scenario_path: save.scenario_path,
+            simulation_time: save.simulation_time,
+            warp: save.warp,
+            paused: save.paused,
+            dirty: save.dirty,
+            epoch: save.epoch,
+            camera: CameraPose {
+                target: camera.target,
+                distance: camera.distance,
+                azimuth: camera.azimuth,
+                elevation: camera.elevation,
+                follow_target: camera.follow_target.map(BodyId),
+                follow_offset: camera.follow_offset,
+            },
+            bodies: save.bodies.into_iter().map(SimulationBody::from).collect(),
+        }
+    }
+}
+
+impl From<&SimulationBody> for BinaryBody {
+    fn from(body: &SimulationBody) -> Self {
+        let SimulationBody {
+            id,
+            id_name,
+            mass,
+            radius,
+            position,
+            velocity,
+            appearance,
+        } = body;
+        let BodyAppearance {
+            texture,
+            color,
+            emissive,
+            luminosity,
+            tags,
+            spin_axis,
+            rings,
+        } = appearance;
+        Self {
+            id: id.0,
+            name: id_name.clone(),
+            mass: *mass,
+            radius: *radius,
+            position: *position,
+            velocity: *velocity,
+            texture: texture.clone(),
+            color: *color,
+            emissive: *emissive,
+            luminosity: *luminosity,
+            tags: tags.clone(),
+            spin_axis: *spin_axis,
+            rings: rings.as_ref().map(|rings| {
+                let RingDefinition {
+                    inner_radius,
+                    outer_radius,
+                    texture,
+                    bands,
+                    opacity,
+                } = rings;
+                BinaryRings {
+                    inner_radius: *inner_radius,
+                    outer_radius: *outer_radius,
+                    texture: texture.clone(),
+                    bands: bands.clone(),
+                    opacity: *opacity,
+                }
+            }),
+        }
+    }
+}
+
+impl From<BinaryBody> for SimulationBody {
+    fn from(body: BinaryBody) -> Self {
+        Self {
+            id: BodyId(body.id),
+            id_name: body.name,
+            mass: body.mass,
+            radius: body.radius,
+            position: body.position,
+            velocity: body.velocity,
+            appearance: BodyAppearance {
+                texture: body.texture,
+                color: body.color,
+                emissive: body.emissive,
+                luminosity: body.luminosity,
+                tags: body.tags,
+                spin_axis: body.spin_axis,
+                rings: body.rings.map(|rings| RingDefinition {
+                    inner_radius: rings.inner_radius,
+                    outer_radius: rings.outer_radius,
+                    texture: rings.texture,
+                    bands: rings.bands,
+                    opacity: rings.opacity,
+                }),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    fn body(index: u32) -> SimulationBody {
+        let angle = index as f64 * 0.618;
+        SimulationBody {
+            id: BodyId(index),
+            id_name: format!("Asteroid {}", index),
+            mass: 1e15 * (1. + index as f64),
+            radius: 1e4 + index as f64,
+            position: DVec3::new(angle.cos(), 0.01 * angle.sin(), angle.sin()) * 4e11,
+            velocity: DVec3::new(-angle.sin(), 0., angle.cos()) * 1.8e4,
+            appearance: BodyAppearance::default(),
+        }
+    }
+
+    fn save_game(bodies: Vec<SimulationBody>) -> SaveGame {
+        SaveGame {
+            version: SAVE_GAME_VERSION,
+            scenario_label: "Asteroid belt".to_string(),
+            scenario_path: None,
+            simulation_time: 86_400.,
+            warp: 1.,
+            paused: false,
+            dirty: false,
+            epoch: 946_728_000,
+            camera: CameraPose {
+                target: DVec3::ZERO,
+                distance: 1e12,
+                azimuth: 0.,
+                elevation: 0.5,
+                follow_target: None,
+                follow_offset: DVec3::ZERO,
+            },
+            bodies,
+        }
+    }
+
+    /* the fields the yaml saves leave out when they have their default, set and unset */
+    #[test]
+    fn every_field_survives_the_round_trip() {
+        let mut ringed = self::body(1);
+        ringed.appearance = BodyAppearance {
+            texture: Some("textures/saturn.png".to_string()),
+            color: Some([0.9, 0.8, 0.6]),
+            emissive: true,
+            luminosity: Some(0.5),
+            tags: vec!["planet".to_string(), "gas giant".to_string()],
+            spin_axis: Some([0., 0.9, 0.4]),
+            rings: Some(RingDefinition {
+                inner_radius: 7e7,
+                outer_radius: 1.4e8,
+                texture: None,
+                bands: vec![[0.8, 0.7, 0.6, 1.], [0.5, 0.5, 0.5, 0.2]],
+                opacity: 0.8,
+            }),
+        };
+        let mut save_game = self::save_game(vec![self::body(0), ringed]);
+        let plain = save_game.clone();
+        assert_eq!(from_bytes(&to_bytes(&plain).unwrap()).unwrap(), plain);
+        save_game.scenario_path = Some(PathBuf::from("scenarios/saturn.ron"));
+        save_game.camera.follow_target = Some(BodyId(1));
+        save_game.dirty = true;
+        assert_eq!(
+            from_bytes(&to_bytes(&save_game).unwrap()).unwrap(),
+            save_game
+        );
+    }
+
+    #[test]
+    fn a_damaged_save_is_refused() {
+        let bytes = to_bytes(&self::save_game(vec![self::body(0)])).unwrap();
+        assert!(is_binary(&bytes));
+        let refused = |bytes: &[u8]| from_bytes(bytes).unwrap_err().to_string();
+        assert_eq!(
+            refused(&bytes[..HEADER_SIZE - 1]),
+            "The file is not a binary save"
+        );
+        assert_eq!(refused(b"version: 1\n"), "The file is not a binary save");
+        let mut encoding = bytes.clone();
+        encoding[4] = 1;
+        assert_eq!(
+            refused(&encoding),
+            "The binary encoding version 1 is not supported, this one reads 2"
+        );
+        let mut compression = bytes.clone();
+        compression[5] = 7;
+        assert_eq!(refused(&compression), "Unknown compression 7");
+        let mut checksum = bytes.clone();
+        checksum[HEADER_SIZE - 1] ^= 0xff;
+        assert_eq!(
+            refused(&checksum),
+            "The file is corrupted, its checksum does not match"
+        );
+        let truncated = refused(&bytes[..bytes.len() - 4]);
+        assert!(
+            truncated.starts_with("The save holds") || truncated.contains("corrupted"),
+            "{}",
+            truncated
+        );
+    }
+
+    #[test]
+    fn a_newer_save_is_refused() {
+        let mut save_game = self::save_game(Vec::new());
+        save_game.version = SAVE_GAME_VERSION + 1;
+        let error = from_bytes(&to_bytes(&save_game).unwrap()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "The save was written by a newer version of SpaceS (version {}, this one reads up to {})",
+                SAVE_GAME_VERSION + 1,
+                SAVE_GAME_VERSION
+            )
+        );
+    }
+
+    /* the times are printed for comparison, run with --nocapture. only the sizes are checked,
+    the times depend on the machine and the build */
+    #[test]
+    fn a_large_state_is_smaller_than_in_the_text_formats() {
+        let save_game = self::save_game((0..10_000).map(self::body).collect());
+        let timed = |name: &str, encode: &dyn Fn() -> Vec<u8>, decode: &dyn Fn(&[u8])| {
+            let start = Instant::now();
+            let bytes = encode();
+            let encoded = start.elapsed();
+            let start = Instant::now();
+            decode(&bytes);
+            eprintln!(
+                "{}: {} bytes, encoded in {:?}, decoded in {:?}",
+                name,
+                bytes.len(),
+                encoded,
+                start.elapsed()
+            );
+            bytes.len()
+        };
+        let binary = timed("binary", &|| to_bytes(&save_game).unwrap(), &|bytes| {
+            assert_eq!(from_bytes(bytes).unwrap().bodies.len(), 10_000)
+        });
+        let json = timed(
+            "json",
+            &|| serde_json::to_vec(&save_game).unwrap(),
+            &|bytes| {
+                let loaded: SaveGame = serde_json::from_slice(bytes).unwrap();
+                assert_eq!(loaded.bodies.len(), 10_000);
+            },
+        );
+        let yaml = timed(
+            "yaml",
+            &|| serde_yml::to_string(&save_game).unwrap().into_bytes(),
+            &|bytes| {
+                let loaded: SaveGame =
+                    serde_yml::from_str(std::str::from_utf8(bytes).unwrap()).unwrap();
+                assert_eq!(loaded.bodies.len(), 10_000);
+            },
+        );
+        assert!(binary * 2 < json, "binary {} bytes, json {}", binary, json);
+        assert!(json < yaml, "json {} bytes, yaml {}", json, yaml);
+    }
+}
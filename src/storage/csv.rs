@@ -0,0 +1,445 @@
+use std::{fmt, path::Path};
+
+use anyhow::{Context, Result, anyhow, bail};
+use tracing::warn;
+use wgpu::naga::FastHashMap;
+
+use crate::{
+    simulation::{SimulationEnterConfiguration, SimulationObject, SimulationPhysicsObject},
+    storage::{presets::ASTRONOMICAL_UNIT, validation},
+};
+
+/* what a column of a body table holds */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CsvField {
+    Name,
+    Mass,
+    Radius,
+    X,
+    Y,
+    Z,
+    VelocityX,
+    VelocityY,
+    VelocityZ,
+}
+
+impl CsvField {
+    pub const ALL: [CsvField; 9] = [
+        CsvField::Name,
+        CsvField::Mass,
+        CsvField::Radius,
+        CsvField::X,
+        CsvField::Y,
+        CsvField::Z,
+        CsvField::VelocityX,
+        CsvField::VelocityY,
+        CsvField::VelocityZ,
+    ];
+
+    /* the header names recognized without a mapping, in snake case. a unit may follow */
+    fn aliases(&self) -> &'static [&'static str] {
+        match self {
+            CsvField::Name => &["name", "body", "designation", "id"],
+            CsvField::Mass => &["mass", "m"],
+            CsvField::Radius => &["radius", "r"],
+            CsvField::X => &["x", "position_x", "pos_x", "px"],
+            CsvField::Y => &["y", "position_y", "pos_y", "py"],
+            CsvField::Z => &["z", "position_z", "pos_z", "pz"],
+            CsvField::VelocityX => &["vx", "velocity_x", "vel_x"],
+            CsvField::VelocityY => &["vy", "velocity_y", "vel_y"],
+            CsvField::VelocityZ => &["vz", "velocity_z", "vel_z"],
+        }
+    }
+
+    /* the unit hints of the field and the factor to the si unit, the first one is assumed
+    without a hint */
+    fn units(&self) -> &'static [(&'static str, f64)] {
+        match self {
+            CsvField::Name => &[("", 1.)],
+            CsvField::Mass => &[
+                ("kg", 1.),
+                ("t", 1e3),
+                ("solar", 1.988_47e30),
+                ("msun", 1.988_47e30),
+                ("jupiter", 1.898_19e27),
+                ("earth", 5.972_17e24),
+            ],
+            CsvField::Radius | CsvField::X | CsvField::Y | CsvField::Z => {
+                &[("m", 1.), ("km", 1e3), ("au", ASTRONOMICAL_UNIT)]
+            }
+            CsvField::VelocityX | CsvField::VelocityY | CsvField::VelocityZ => &[
+                ("m_s", 1.),
+                ("km_s", 1e3),
+                ("au_d", ASTRONOMICAL_UNIT / 86_400.),
+                ("au_day", ASTRONOMICAL_UNIT / 86_400.),
+            ],
+        }
+    }
+
+    /* the velocity defaults to rest, everything else has to be in the table */
+    fn required(&self) -> bool {
+        !matches!(
+            self,
+            CsvField::VelocityX | CsvField::VelocityY | CsvField::VelocityZ
+        )
+    }
+}
+
+impl fmt::Display for CsvField {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(self.aliases()[0])
+    }
+}
+
+/* which header holds which field. the fields left out are detected from the header names,
+the unit is read from the end of the header either way, like mass_solar or vx (km/s) */
+#[derive(Debug, Clone, Default)]
+pub struct CsvMapping {
+    pub columns: FastHashMap<CsvField, String>,
+    /* rows with bad values are left out and logged instead of failing the import */
+    pub skip_invalid_rows: bool,
+}
+
+struct Column {
+    index: usize,
+    factor: f64,
+}
+
+/* the bodies of a table with a header row, relative to the origin. errors name the line of the
+file a row starts on */
+pub fn import_csv(path: &Path, mapping: &CsvMapping) -> Result<Vec<SimulationObject>> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    self::parse(&text, mapping).with_context(|| format!("Failed to import {}", path.display()))
+}
+
+fn parse(text: &str, mapping: &CsvMapping) -> Result<Vec<SimulationObject>> {
+    let mut records = self::records(text)?.into_iter();
+    let Some((_, headers)) = records.next() else {
+        bail!("The file is empty");
+    };
+    let columns = self::columns(&headers, mapping)?;
+    let mut objects = Vec::new();
+    let mut errors = Vec::new();
+    for (line, record) in records {
+        if record.iter().all(|value| value.trim().is_empty()) {
+            continue;
+        }
+        match self::row(&record, &columns) {
+            Ok(object) => objects.push(object),
+            Err(error) if mapping.skip_invalid_rows => {
+                warn!("Skipping line {}: {}", line, error)
+            }
+            Err(error) => errors.push(format!("line {}: {}", line, error)),
+        }
+    }
+    if !errors.is_empty() {
+        bail!("{}", errors.join("\n"));
+    }
+    Ok(objects)
+}
+
+fn columns(headers: &[String], mapping: &CsvMapping) -> Result<FastHashMap<CsvField, Column>> {
+    let headers = headers
+        .iter()
+        .map(|header| self::snake_case(header))
+        .collect::<Vec<_>>();
+    let mut columns = FastHashMap::default();
+    for (field, header) in &mapping.columns {
+        let wanted = self::snake_case(header);
+        let index = headers
+            .iter()
+            .position(|header| *header == wanted)
+            .with_context(|| format!("There is no column {} for the {}", header, field))?;
+        let factor = self::unit_hint(*field, &headers[index]).1;
+        columns.insert(*field, Column { index, factor });
+    }
+    /* a mapped column is not detected again for another field */
+    let mapped = columns
+        .values()
+        .map(|column| column.index)
+        .collect::<Vec<_>>();
+    for field in CsvField::ALL {
+        if columns.contains_key(&field) {
+            continue;
+        }
+        let found = headers
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !mapped.contains(index))
+            .find_map(|(index, header)| {
+                let (name, factor) = self::unit_hint(field, header);
+                field.aliases().contains(&name).then_some((index, factor))
+            });
+        match found {
+            Some((index, factor)) => {
+                columns.insert(field, Column { index, factor });
+            }
+            None if field.required() => bail!(
+                "No column holds the {}, the header has {}",
+                field,
+                headers.join(", ")
+            ),
+            None => {}
+        }
+    }
+    Ok(columns)
+}
+
+/* the header without a known unit at its end, and the factor of that unit */
+fn unit_hint(field: CsvField, header: &str) -> (&str, f64) {
+    field
+        .units()
+        .iter()
+        .filter(|(unit, _)| !unit.is_empty())
+        .find_map(|(unit, factor)| {
+            let name = header.strip_suffix(unit)?.strip_suffix('_')?;
+            Some((name, *factor))
+        })
+        .unwrap_or((header, field.units()[0].1))
+}
+
+fn row(record: &[String], columns: &FastHashMap<CsvField, Column>) -> Result<SimulationObject> {
+    let value = |field: CsvField| -> Result<f64> {
+        let Some(column) = columns.get(&field) else {
+            return Ok(0.);
+        };
+        let text = record.get(column.index).map(|value| value.trim());
+        match text {
+            None | Some("") => bail!("the {} is missing", field),
+            Some(text) => text
+                .parse::<f64>()
+                .map(|value| value * column.factor)
+                .map_err(|_| anyhow!("the {} {:?} is not a number", field, text)),
+        }
+    };
+    let name = record
+        .get(columns[&CsvField::Name].index)
+        .map(|name| name.trim().to_string())
+        .unwrap_or_default();
+    let vector = |x, y, z| -> Result<[f32; 3]> {
+        Ok([value(x)? as f32, value(y)? as f32, value(z)? as f32])
+    };
+    let object = SimulationObject::new(
+        name,
+        SimulationPhysicsObject::new(
+            value(CsvField::Mass)? as f32,
+            value(CsvField::Radius)? as f32,
+        ),
+        SimulationEnterConfiguration::new(
+            vector(
+                CsvField::VelocityX,
+                CsvField::VelocityY,
+                CsvField::VelocityZ,
+            )?,
+            vector(CsvField::X, CsvField::Y, CsvField::Z)?,
+        ),
+    );
+    /* the rules of a scenario, the names are made unique when the bodies are added */
+    let errors = validation::validate(std::slice::from_ref(&object));
+    if !errors.is_empty() {
+        bail!(
+            "{}",
+            errors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    Ok(object)
+}
+
+/* "Mass (kg)" and "mass-kg" alike become mass_kg */
+fn snake_case(header: &str) -> String {
+    header
+        .trim()
+        .to_lowercase()
+        .split(|character: char| !character.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/* the fields of each record with the line it starts on. the csv crate reads the quoted fields
+with separators, line breaks and doubled quotes in them */
+fn records(text: &str) -> Result<Vec<(usize, Vec<String>)>> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    csv::ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes())
+        .into_records()
+        .map(|record| {
+            let record = record?;
+            /* from the offset, the line of the crate does not count the breaks in quotes. the
+            offset may be before the end of the previous line and the empty lines skipped */
+            let offset = record
+                .position()
+                .map_or(0, |position| position.byte() as usize)
+                .min(text.len());
+            let start = text.len() - text[offset..].trim_start_matches(['\r', '\n']).len();
+            let line = text[..start].matches('\n').count() + 1;
+            Ok((line, record.iter().map(str::to_string).collect()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    const SOLAR_MASS: f64 = 1.988_47e30;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join(name)
+    }
+
+    fn assert_close(value: f32, expected: f64) {
+        assert!(
+            (value as f64 - expected).abs() <= expected.abs() * 1e-6,
+            "{} is not {}",
+            value,
+            expected
+        );
+    }
+
+    #[test]
+    fn the_units_of_the_headers_are_converted() {
+        let objects = import_csv(&self::fixture("asteroids.csv"), &CsvMapping::default()).unwrap();
+        let names: Vec<&str> = objects
+            .iter()
+            .map(|object| object.id_name.as_str())
+            .collect();
+        assert_eq!(names, ["Ceres", "Pallas, 2", "Vesta"]);
+        let ceres = &objects[0];
+        self::assert_close(
+            ceres.physics_data.simulation_body_mass,
+            4.72e-10 * SOLAR_MASS,
+        );
+        self::assert_close(ceres.physics_data.simulation_body_radius, 469.7e3);
+        self::assert_close(
+            ceres.enter_configuration.simulation_enter_position[0],
+            2.77 * ASTRONOMICAL_UNIT,
+        );
+        self::assert_close(ceres.enter_configuration.simulation_enter_speed[2], 17.9e3);
+        let pallas = &objects[1];
+        self::assert_close(
+            pallas.enter_configuration.simulation_enter_position[2],
+            0.1 * ASTRONOMICAL_UNIT,
+        );
+        self::assert_close(
+            pallas.enter_configuration.simulation_enter_speed[0],
+            -17.6e3,
+        );
+    }
+
+    #[test]
+    fn every_bad_row_is_reported_with_its_line() {
+        let error = import_csv(
+            &self::fixture("asteroids_malformed.csv"),
+            &CsvMapping::default(),
+        )
+        .unwrap_err();
+        let lines: Vec<String> = error
+            .root_cause()
+            .to_string()
+            .lines()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(lines.len(), 3, "{:?}", lines);
+        assert_eq!(lines[0], "line 3: the mass \"heavy\" is not a number");
+        assert_eq!(lines[1], "line 4: the radius is missing");
+        assert!(
+            lines[2].starts_with("line 5: ") && lines[2].contains("mass"),
+            "{}",
+            lines[2]
+        );
+    }
+
+    #[test]
+    fn bad_rows_can_be_skipped() {
+        let mapping = CsvMapping {
+            skip_invalid_rows: true,
+            ..CsvMapping::default()
+        };
+        let objects = import_csv(&self::fixture("asteroids_malformed.csv"), &mapping).unwrap();
+        let names: Vec<&str> = objects
+            .iter()
+            .map(|object| object.id_name.as_str())
+            .collect();
+        assert_eq!(names, ["Good", "Also good"]);
+        /* without velocity columns the bodies are at rest */
+        assert_eq!(
+            objects[0].enter_configuration.simulation_enter_speed,
+            [0.; 3]
+        );
+    }
+
+    #[test]
+    fn a_mapped_column_wins_over_the_detected_one() {
+        let text = "id,label,weight (t),size_km,x,y,z\n7,Phobos,1.07e13,11.3,9.4e6,0,0\n";
+        let mut mapping = CsvMapping::default();
+        mapping.columns.insert(CsvField::Name, "Label".to_string());
+        mapping
+            .columns
+            .insert(CsvField::Mass, "Weight (t)".to_string());
+        mapping
+            .columns
+            .insert(CsvField::Radius, "size km".to_string());
+        let objects = self::parse(text, &mapping).unwrap();
+        assert_eq!(objects[0].id_name, "Phobos");
+        self::assert_close(objects[0].physics_data.simulation_body_mass, 1.07e16);
+        self::assert_close(objects[0].physics_data.simulation_body_radius, 11.3e3);
+        mapping
+            .columns
+            .insert(CsvField::Radius, "diameter".to_string());
+        assert_eq!(
+            self::parse(text, &mapping).unwrap_err().to_string(),
+            "There is no column diameter for the radius"
+        );
+    }
+
+    /* a quote that is never closed runs to the end of the file, the row lacks its columns */
+    #[test]
+    fn a_missing_column_or_quote_is_an_error() {
+        assert_eq!(
+            self::parse("name,mass,x,y,z\nCeres,1,0,0,0\n", &CsvMapping::default())
+                .unwrap_err()
+                .to_string(),
+            "No column holds the radius, the header has name, mass, x, y, z"
+        );
+        assert_eq!(
+            self::parse(
+                "name,mass,radius,x,y,z\n\"Ceres,1,1,0,0,0\n",
+                &CsvMapping::default()
+            )
+            .unwrap_err()
+            .to_string(),
+            "line 2: the mass is missing"
+        );
+        assert_eq!(
+            self::parse("", &CsvMapping::default())
+                .unwrap_err()
+                .to_string(),
+            "The file is empty"
+        );
+    }
+
+    #[test]
+    fn quoted_fields_keep_their_separators_and_line_breaks() {
+        let records = self::records("a,\"b, \"\"c\"\"\nd\"\r\ne,f").unwrap();
+        assert_eq!(
+            records,
+            [
+                (1, vec!["a".to_string(), "b, \"c\"\nd".to_string()]),
+                (3, vec!["e".to_string(), "f".to_string()]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,418 @@
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use glam::DVec3;
+
+use crate::{
+    graphics::toolbar,
+    simulation::{SimulationEnterConfiguration, SimulationObject, SimulationPhysicsObject},
+    storage::{Scenario, ScenarioMetadata, presets},
+};
+
+pub const HORIZONS_SCENARIO_NAME: &str = "Horizons import";
+/* how far apart in seconds the epochs of merged files may be */
+const EPOCH_TOLERANCE: f64 = 0.5;
+const UNIX_EPOCH_JULIAN_DAY: f64 = 2_440_587.5;
+const SECONDS_PER_DAY: f64 = 86_400.;
+/* of j2000, between the earth mean equator and the ecliptic */
+const OBLIQUITY_DEGREES: f64 = 23.439_291;
+
+/* the first state of a vector table, relative to the center body of the query */
+#[derive(Debug)]
+pub struct HorizonsImport {
+    pub object: SimulationObject,
+    /* barycentric dynamical time, within about a minute of utc */
+    pub julian_day: f64,
+    /* like Sun (10), the target and center names keep the ids horizons gives them */
+    pub center: String,
+}
+
+impl HorizonsImport {
+    pub fn epoch_seconds(&self) -> i64 {
+        ((self.julian_day - UNIX_EPOCH_JULIAN_DAY) * SECONDS_PER_DAY).round() as i64
+    }
+}
+
+/* a vector table of one body as horizons writes it, as text or with the csv option. the mass
+and radius of the sun, the planets and the moon are looked up, the other bodies take the given
+ones */
+pub fn import_horizons(
+    path: &Path,
+    physics: Option<SimulationPhysicsObject>,
+) -> Result<HorizonsImport> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    self::parse(&text, physics).with_context(|| format!("Failed to import {}", path.display()))
+}
+
+/* whether a file is a horizons table rather than a body table, both may end in csv */
+pub fn is_horizons_table(path: &Path) -> bool {
+    std::fs::read_to_string(path).is_ok_and(|text| text.contains("$$SOE"))
+}
+
+/* one scenario of tables of the same epoch and center. the center is added at rest at the
+origin when it is a known body that was not imported itself */
+pub fn merge_horizons(imports: Vec<HorizonsImport>) -> Result<Scenario> {
+    let Some(first) = imports.first() else {
+        bail!("No horizons tables to merge");
+    };
+    let (julian_day, center) = (first.julian_day, first.center.clone());
+    for import in &imports {
+        if (import.julian_day - julian_day).abs() * SECONDS_PER_DAY > EPOCH_TOLERANCE {
+            bail!(
+                "{} is given at JD {} and {} at JD {}, the tables need the same epoch",
+                first.object.id_name,
+                julian_day,
+                import.object.id_name,
+                import.julian_day
+            );
+        }
+        if import.center != center {
+            bail!(
+                "{} is relative to {} and {} to {}, the tables need the same center",
+                first.object.id_name,
+                center,
+                import.object.id_name,
+                import.center
+            );
+        }
+    }
+    let epoch = toolbar::format_date(0., first.epoch_seconds());
+    let center_name = self::body_name(&center);
+    let mut objects = Vec::with_capacity(imports.len() + 1);
+    if !imports
+        .iter()
+        .any(|import| import.object.id_name.eq_ignore_ascii_case(center_name))
+        && let Some(physics) = presets::major_body(center_name)
+    {
+        objects.push(SimulationObject::new(
+            center_name.to_string(),
+            physics,
+            SimulationEnterConfiguration::new([0.; 3], [0.; 3]),
+        ));
+    }
+    objects.extend(imports.into_iter().map(|import| import.object));
+    Ok(Scenario {
+        metadata: ScenarioMetadata {
+            name: HORIZONS_SCENARIO_NAME.to_string(),
+            description: format!("State vectors from JPL Horizons relative to {}.", center),
+            ..ScenarioMetadata::default()
+        },
+        epoch: Some(epoch),
+        ..Scenario::new(objects)
+    })
+}
+
+fn parse(text: &str, physics: Option<SimulationPhysicsObject>) -> Result<HorizonsImport> {
+    let (header, rest) = text
+        .split_once("$$SOE")
+        .context("There is no $$SOE line, it is not a horizons vector table")?;
+    let (block, _) = rest
+        .split_once("$$EOE")
+        .context("The table ends without a $$EOE line")?;
+    let target = self::header_value(header, "Target body name")
+        .context("The header names no target body")?;
+    let center = self::header_value(header, "Center body name")
+        .context("The header names no center body")?;
+    let lines = block
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+    let Some(first) = lines.first() else {
+        bail!("The table holds no states");
+    };
+    let record = match first.contains(',') {
+        true => self::csv_record(header, first)?,
+        false => self::text_record(&lines)?,
+    };
+    let (position_factor, velocity_factor) = self::unit_factors(header)?;
+    let mut position = DVec3::from_array(record.position) * position_factor;
+    let mut velocity = DVec3::from_array(record.velocity) * velocity_factor;
+    if self::is_equatorial(header) {
+        let rotation = glam::DQuat::from_rotation_x(-OBLIQUITY_DEGREES.to_radians());
+        position = rotation * position;
+        velocity = rotation * velocity;
+    }
+    let name = self::body_name(&target).to_string();
+    let physics = match presets::major_body(&name).or(physics) {
+        Some(physics) => physics,
+        None => bail!(
+            "The mass and radius of {} are not known, they have to be given",
+            target
+        ),
+    };
+    /* the ecliptic of horizons has its north towards z, the simulation towards y */
+    let simulation = |vector: DVec3| [vector.x as f32, vector.z as f32, -vector.y as f32];
+    Ok(HorizonsImport {
+        object: SimulationObject::new(
+            name,
+            physics,
+            SimulationEnterConfiguration::new(simulation(velocity), simulation(position)),
+        ),
+        julian_day: record.julian_day,
+        center,
+    })
+}
+
+struct Record {
+    julian_day: f64,
+    position: [f64; 3],
+    velocity: [f64; 3],
+}
+
+/* the columns are named by the line above the first $$SOE, the labels of the csv option */
+fn csv_record(header: &str, line: &str) -> Result<Record> {
+    let names = header
+        .lines()
+        .rev()
+        .find(|line| line.contains("JDTDB"))
+        .context("The csv table has no column names")?
+        .split(',')
+        .map(str::trim)
+        .collect::<Vec<_>>();
+    let values = line.split(',').map(str::trim).collect::<Vec<_>>();
+    let value = |name: &str| -> Result<f64> {
+        let index = names
+            .iter()
+            .position(|column| *column == name)
+            .with_context(|| format!("The table has no {} column", name))?;
+        let value = values.get(index).copied().unwrap_or_default();
+        value
+            .parse::<f64>()
+            .with_context(|| format!("{} is not a number, got {:?}", name, value))
+    };
+    Ok(Record {
+        julian_day: value("JDTDB")?,
+        position: [value("X")?, value("Y")?, value("Z")?],
+        velocity: [value("VX")?, value("VY")?, value("VZ")?],
+    })
+}
+
+/* a state starts with its julian day, followed by lines like X =-2.6E+07 Y = 1.3E+08 */
+fn text_record(lines: &[&str]) -> Result<Record> {
+    let julian_day = lines[0]
+        .split_whitespace()
+        .next()
+        .and_then(|day| day.parse::<f64>().ok())
+        .with_context(|| format!("The state does not start with a julian day: {}", lines[0]))?;
+    let mut fields = Vec::new();
+    for line in lines[1..]
+        .iter()
+        .take_while(|line| !line.starts_with(|first: char| first.is_ascii_digit()))
+    {
+        /* each piece between two equals signs ends with the next label */
+        let pieces = line.split('=').collect::<Vec<_>>();
+        for pair in pieces.windows(2) {
+            if let (Some(label), Some(value)) = (
+                pair[0].split_whitespace().last(),
+                pair[1].split_whitespace().next(),
+            ) {
+                fields.push((label, value));
+            }
+        }
+    }
+    let value = |name: &str| -> Result<f64> {
+        let value = fields
+            .iter()
+            .find(|(label, _)| *label == name)
+            .map(|(_, value)| *value)
+            .with_context(|| match name.starts_with('V') {
+                true => "The table has no velocities, query a state vector table".to_string(),
+                false => format!("The state has no {}", name),
+            })?;
+        value
+            .parse::<f64>()
+            .with_context(|| format!("{} is not a number, got {:?}", name, value))
+    };
+    Ok(Record {
+        julian_day,
+        position: [value("X")?, value("Y")?, value("Z")?],
+        velocity: [value("VX")?, value("VY")?, value("VZ")?],
+    })
+}
+
+/* meters and meters per second from the output units, km and km/s when not given */
+fn unit_factors(header: &str) -> Result<(f64, f64)> {
+    let units = self::header_value(header, "Output units").unwrap_or_default();
+    let units = units.split(',').next().unwrap_or_default().trim();
+    Ok(match units {
+        "" | "KM-S" => (1e3, 1e3),
+        "KM-D" => (1e3, 1e3 / SECONDS_PER_DAY),
+        "AU-D" => (
+            presets::ASTRONOMICAL_UNIT,
+            presets::ASTRONOMICAL_UNIT / SECONDS_PER_DAY,
+        ),
+        units => bail!("The output units {} are not supported", units),
+    })
+}
+
+/* tables against the earth mean equator are turned into the ecliptic of the simulation */
+fn is_equatorial(header: &str) -> bool {
+    ["Reference plane", "Reference frame"].iter().any(|name| {
+        self::header_value(header, name).is_some_and(|plane| {
+            let plane = plane.to_ascii_lowercase();
+            plane.contains("equator") || plane == "frame"
+        })
+    })
+}
+
+/* the text after the colon of a header line like Target body name: Earth (399) {source: DE441} */
+fn header_value(header: &str, name: &str) -> Option<String> {
+    header.lines().find_map(|line| {
+        let value = line
+            .trim()
+            .strip_prefix(name)?
+            .trim_start()
+            .strip_prefix(':')?;
+        let value = value.split('{').next().unwrap_or_default().trim();
+        Some(value.to_string())
+    })
+}
+
+/* Earth (399) to Earth */
+fn body_name(horizons_name: &str) -> &str {
+    match horizons_name.rsplit_once('(') {
+        Some((name, _)) if !name.trim().is_empty() => name.trim(),
+        _ => horizons_name.trim(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .join(name)
+    }
+
+    fn import(name: &str) -> HorizonsImport {
+        import_horizons(&self::fixture(name), None).unwrap()
+    }
+
+    /* the ecliptic km and km/s of horizons in the meters and y up of the simulation */
+    fn assert_state(object: &SimulationObject, position: [f64; 3], velocity: [f64; 3]) {
+        let simulation = |[x, y, z]: [f64; 3]| DVec3::new(x, z, -y) * 1e3;
+        let close = |actual: [f32; 3], expected: DVec3| {
+            let actual = DVec3::from_array(actual.map(f64::from));
+            assert!(
+                actual.distance(expected) <= expected.length() * 1e-6,
+                "{} is not {}",
+                actual,
+                expected
+            );
+        };
+        close(
+            object.enter_configuration.simulation_enter_position,
+            simulation(position),
+        );
+        close(
+            object.enter_configuration.simulation_enter_speed,
+            simulation(velocity),
+        );
+    }
+
+    #[test]
+    fn the_first_state_of_a_text_table_is_read() {
+        let earth = self::import("horizons_earth.txt");
+        assert_eq!(earth.object.id_name, "Earth");
+        assert_eq!(earth.center, "Sun (10)");
+        assert_eq!(earth.julian_day, 2_451_545.);
+        assert_eq!(earth.epoch_seconds(), 946_728_000);
+        let physics = presets::major_body("Earth").unwrap();
+        assert_eq!(
+            earth.object.physics_data.simulation_body_mass,
+            physics.simulation_body_mass
+        );
+        self::assert_state(
+            &earth.object,
+            [
+                -2.649903375682292e7,
+                1.446972967792532e8,
+                -6.112214304122382e2,
+            ],
+            [
+                -2.979426006719171e1,
+                -5.018052308799458,
+                1.842417050355762e-4,
+            ],
+        );
+    }
+
+    /* the mars table is against the equator, the ecliptic values are the ones horizons gives */
+    #[test]
+    fn an_equatorial_csv_table_is_turned_into_the_ecliptic() {
+        let mars = self::import("horizons_mars.csv");
+        assert_eq!(mars.object.id_name, "Mars");
+        assert_eq!(mars.julian_day, 2_451_545.);
+        self::assert_state(
+            &mars.object,
+            [
+                2.081106302580031e8,
+                -2.006886813705592e6,
+                -5.130297351313226e6,
+            ],
+            [1.16290045819986, 2.629596376984437e1, 5.223831625045794e-1],
+        );
+    }
+
+    #[test]
+    fn tables_merge_around_their_center() {
+        let scenario = merge_horizons(vec![
+            self::import("horizons_earth.txt"),
+            self::import("horizons_mars.csv"),
+        ])
+        .unwrap();
+        let names: Vec<&str> = scenario
+            .objects
+            .iter()
+            .map(|object| object.id_name.as_str())
+            .collect();
+        assert_eq!(names, ["Sun", "Earth", "Mars"]);
+        assert_eq!(
+            scenario.objects[0]
+                .enter_configuration
+                .simulation_enter_position,
+            [0.; 3]
+        );
+        assert_eq!(scenario.epoch.as_deref(), Some("2000-01-01 12:00"));
+        assert_eq!(scenario.metadata.name, HORIZONS_SCENARIO_NAME);
+    }
+
+    #[test]
+    fn tables_of_different_epochs_do_not_merge() {
+        let error = merge_horizons(vec![
+            self::import("horizons_earth.txt"),
+            self::import("horizons_mars_later.csv"),
+        ])
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Earth is given at JD 2451545 and Mars at JD 2451555, the tables need the same epoch"
+        );
+    }
+
+    #[test]
+    fn an_unknown_body_needs_its_mass_and_radius() {
+        let text = std::fs::read_to_string(self::fixture("horizons_earth.txt"))
+            .unwrap()
+            .replace("Earth (399)", "1 Ceres (A801 AA)");
+        assert_eq!(
+            parse(&text, None).unwrap_err().to_string(),
+            "The mass and radius of 1 Ceres (A801 AA) are not known, they have to be given"
+        );
+        let ceres = parse(&text, Some(SimulationPhysicsObject::new(9.4e20, 4.7e5))).unwrap();
+        assert_eq!(ceres.object.id_name, "1 Ceres");
+        assert_eq!(ceres.object.physics_data.simulation_body_radius, 4.7e5);
+        let unfinished = text.replace("$$EOE", "");
+        assert_eq!(
+            parse(&unfinished, None).unwrap_err().to_string(),
+            "The table ends without a $$EOE line"
+        );
+    }
+}
@@ -0,0 +1,214 @@
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_yml::{Mapping, Value};
+
+use crate::{graphics::toolbar, storage::AssetResolver};
+
+/* the prefix of a thumbnail embedded into the scenario file instead of lying next to it */
+pub const EMBEDDED_THUMBNAIL_PREFIX: &str = "data:image/png;base64,";
+/* the fields of version 1 that moved into the metadata section with version 2 */
+const MOVED_FIELDS: [&str; 3] = ["name", "author", "description"];
+
+/* what the scenario browser shows of a file. every field may be left out, a file without a
+name is shown by its file name */
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ScenarioMetadata {
+    #[serde(rename = "name", default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    #[serde(rename = "author", default, skip_serializing_if = "String::is_empty")]
+    pub author: String,
+    #[serde(
+        rename = "description",
+        default,
+        skip_serializing_if = "String::is_empty"
+    )]
+    pub description: String,
+    /* utc dates like the epoch, the modified one is stamped on every save */
+    #[serde(rename = "created", default, skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(rename = "modified", default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<String>,
+    /* an image relative to the scenario file, or a png starting with EMBEDDED_THUMBNAIL_PREFIX */
+    #[serde(rename = "thumbnail", default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<String>,
+}
+
+/* where the image of a thumbnail comes from */
+#[derive(Debug, Clone, PartialEq)]
+pub enum Thumbnail {
+    File(PathBuf),
+    Png(Vec<u8>),
+}
+
+impl ScenarioMetadata {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /* the stem of the file for a file without a name, the file times for missing dates */
+    pub fn fill_from_path(&mut self, path: &Path) {
+        if self.name.trim().is_empty() {
+            self.name = path
+                .file_stem()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy()
+                .into_owned();
+        }
+        let metadata = path.metadata().ok();
+        if self.created.is_none() {
+            self.created = metadata
+                .as_ref()
+                .and_then(|metadata| metadata.created().ok())
+                .map(self::format_time);
+        }
+        if self.modified.is_none() {
+            self.modified = metadata
+                .and_then(|metadata| metadata.modified().ok())
+                .map(self::format_time);
+        }
+    }
+
+    /* on saving, a scenario saved the first time is created then too */
+    pub fn touch(&mut self) {
+        let now = self::format_time(SystemTime::now());
+        self.created.get_or_insert_with(|| now.clone());
+        self.modified = Some(now);
+    }
+
+    /* none without a thumbnail, the error of an embedded image that is not base64 */
+    pub fn thumbnail(&self, scenario_path: &Path) -> Option<Result<Thumbnail>> {
+        let thumbnail = self.thumbnail.as_deref()?.trim();
+        Some(match thumbnail.strip_prefix(EMBEDDED_THUMBNAIL_PREFIX) {
+            Some(encoded) => self::decode_base64(encoded).map(Thumbnail::Png),
+            None => Ok(Thumbnail::File(
+                AssetResolver::for_scenario(Some(scenario_path)).resolve(thumbnail),
+            )),
+        })
+    }
+
+    /* the dates that are not dates, by field */
+    pub fn invalid_dates(&self) -> Vec<(&'static str, &str)> {
+        [("created", &self.created), ("modified", &self.modified)]
+            .into_iter()
+            .filter_map(|(field, date)| {
+                let date = date.as_deref()?;
+                toolbar::parse_date(date).is_none().then_some((field, date))
+            })
+            .collect()
+    }
+}
+
+/* version 1 to 2, the describing fields of the top level go into the metadata section */
+pub fn move_into_section(fields: &mut Mapping) -> Result<()> {
+    let mut metadata = Mapping::new();
+    for field in MOVED_FIELDS {
+        if let Some(value) = fields.remove(field) {
+            metadata.insert(Value::from(field), value);
+        }
+    }
+    if !metadata.is_empty() {
+        fields.insert(Value::from("metadata"), Value::Mapping(metadata));
+    }
+    Ok(())
+}
+
+fn format_time(time: SystemTime) -> String {
+    let seconds = match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(since) => since.as_secs() as i64,
+        Err(error) => -(error.duration().as_secs() as i64),
+    };
+    toolbar::format_date(0., seconds)
+}
+
+/* the standard alphabet, padding and line breaks are optional */
+fn decode_base64(encoded: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(encoded.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for character in encoded.trim_end_matches('=').chars() {
+        let value = match character {
+            'A'..='Z' => character as u32 - 'A' as u32,
+            'a'..='z' => character as u32 - 'a' as u32 + 26,
+            '0'..='9' => character as u32 - '0' as u32 + 52,
+            '+' => 62,
+            '/' => 63,
+            character if character.is_whitespace() => continue,
+            character => bail!("The thumbnail holds {:?}, it is not base64", character),
+        };
+        buffer = buffer << 6 | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    if bytes.is_empty() {
+        bail!("The embedded thumbnail is empty");
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_thumbnail_is_embedded_or_next_to_the_scenario() {
+        let scenario_path = Path::new("scenarios").join("system.yaml");
+        let mut metadata = ScenarioMetadata::default();
+        assert!(metadata.thumbnail(&scenario_path).is_none());
+        metadata.thumbnail = Some("thumbnails/system.png".to_string());
+        assert_eq!(
+            metadata.thumbnail(&scenario_path).unwrap().unwrap(),
+            Thumbnail::File(
+                AssetResolver::for_scenario(Some(&scenario_path)).resolve("thumbnails/system.png")
+            )
+        );
+        metadata.thumbnail = Some(format!("{}iVBO\nRw0K\nGgo=", EMBEDDED_THUMBNAIL_PREFIX));
+        assert_eq!(
+            metadata.thumbnail(&scenario_path).unwrap().unwrap(),
+            Thumbnail::Png(b"\x89PNG\r\n\x1a\n".to_vec())
+        );
+        metadata.thumbnail = Some(format!("{}iVBO*", EMBEDDED_THUMBNAIL_PREFIX));
+        assert_eq!(
+            metadata
+                .thumbnail(&scenario_path)
+                .unwrap()
+                .unwrap_err()
+                .to_string(),
+            "The thumbnail holds '*', it is not base64"
+        );
+    }
+
+    #[test]
+    fn touching_keeps_the_created_date() {
+        let mut metadata = ScenarioMetadata::default();
+        metadata.touch();
+        assert!(metadata.created.is_some());
+        assert_eq!(metadata.created, metadata.modified);
+        assert!(metadata.invalid_dates().is_empty());
+        metadata.created = Some("2000-01-01 12:00".to_string());
+        metadata.touch();
+        assert_eq!(metadata.created.as_deref(), Some("2000-01-01 12:00"));
+        assert_ne!(metadata.modified, metadata.created);
+    }
+
+    #[test]
+    fn the_describing_fields_move_into_the_section() {
+        let mut fields: Mapping =
+            serde_yml::from_str("name: Pair\nauthor: Me\nbodies: []\n").unwrap();
+        move_into_section(&mut fields).unwrap();
+        let expected: Mapping =
+            serde_yml::from_str("bodies: []\nmetadata:\n  name: Pair\n  author: Me\n").unwrap();
+        assert_eq!(fields, expected);
+        let mut anonymous: Mapping = serde_yml::from_str("bodies: []\n").unwrap();
+        move_into_section(&mut anonymous).unwrap();
+        assert!(!anonymous.contains_key("metadata"));
+    }
+}
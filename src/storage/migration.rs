@@ -0,0 +1,149 @@
+use anyhow::{Context, Result, bail};
+use serde::de::DeserializeOwned;
+use serde_yml::{Mapping, Value};
+
+/* upgrades the value tree of a file by one version, before it is deserialized */
+pub type Migration = fn(&mut Mapping) -> Result<()>;
+
+/* the version of a file, files written before they carried one are version 1 */
+pub fn version(value: &Value) -> Result<u32> {
+    match value.get("version") {
+        None => Ok(1),
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .filter(|version| *version > 0)
+            .with_context(|| {
+                format!(
+                    "The version {} is not a positive number",
+                    serde_yml::to_string(version).unwrap_or_default().trim()
+                )
+            }),
+    }
+}
+
+/* the migrations take version n to n + 1 starting at 1, so the current version is one more than
+there are of them. each is applied in order from the version of the file */
+pub fn migrate(value: &mut Value, kind: &str, migrations: &[Migration]) -> Result<()> {
+    let current = migrations.len() as u32 + 1;
+    let version = self::version(value)?;
+    if version > current {
+        bail!(
+            "The {} was written by a newer version of SpaceS (version {}, this one reads up to {})",
+            kind,
+            version,
+            current
+        );
+    }
+    let Value::Mapping(fields) = value else {
+        bail!("The {} is not a mapping", kind);
+    };
+    for (index, migration) in migrations.iter().enumerate().skip(version as usize - 1) {
+        migration(fields).with_context(|| {
+            format!(
+                "Failed to upgrade the {} from version {} to {}",
+                kind,
+                index + 1,
+                index + 2
+            )
+        })?;
+    }
+    fields.insert(Value::from("version"), Value::from(current));
+    Ok(())
+}
+
+/* the keys of a flattened catch-all, listed in a warning */
+pub fn field_names(fields: &Mapping) -> String {
+    fields
+        .keys()
+        .map(|key| match key {
+            Value::String(key) => key.clone(),
+            key => format!("{:?}", key),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/* the fields a struct ignores, found by giving each one a value no field accepts. the file
+parsed already, so only an ignored field still parses with it */
+pub fn unknown_fields<T: DeserializeOwned>(fields: &Mapping) -> Mapping {
+    let unreadable = Value::Sequence(vec![Value::Sequence(vec![Value::Bool(true)])]);
+    fields
+        .iter()
+        .filter(|(key, _)| {
+            let mut probe = fields.clone();
+            probe.insert((*key).clone(), unreadable.clone());
+            serde_yml::from_value::<T>(Value::Mapping(probe)).is_ok()
+        })
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::metadata;
+
+    const SCENARIO_V1: &str = include_str!("../../tests/fixtures/scenario_v1.yaml");
+
+    /* version 2 to 3 of a made up layout, the bodies are renamed to objects */
+    fn rename_bodies(fields: &mut Mapping) -> Result<()> {
+        let bodies = fields.remove("bodies").context("There are no bodies")?;
+        fields.insert(Value::from("objects"), bodies);
+        Ok(())
+    }
+
+    fn fixture() -> Value {
+        serde_yml::from_str(SCENARIO_V1).unwrap()
+    }
+
+    #[test]
+    fn a_file_without_a_version_is_version_1() {
+        assert_eq!(version(&self::fixture()).unwrap(), 1);
+        let error = version(&serde_yml::from_str("version: -2").unwrap()).unwrap_err();
+        assert_eq!(error.to_string(), "The version -2 is not a positive number");
+    }
+
+    #[test]
+    fn a_v1_file_goes_through_both_migrations_in_order() {
+        let mut value = self::fixture();
+        let migrations: [Migration; 2] = [metadata::move_into_section, self::rename_bodies];
+        migrate(&mut value, "scenario", &migrations).unwrap();
+        assert_eq!(version(&value).unwrap(), 3);
+        assert_eq!(value["metadata"]["name"], Value::from("Earth and Moon"));
+        assert!(value.get("name").is_none());
+        assert!(value.get("bodies").is_none());
+        assert_eq!(value["objects"].as_sequence().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn a_v2_file_skips_the_first_migration() {
+        let mut value: Value = serde_yml::from_str("version: 2\nname: top\nbodies: []\n").unwrap();
+        let migrations: [Migration; 2] = [metadata::move_into_section, self::rename_bodies];
+        migrate(&mut value, "scenario", &migrations).unwrap();
+        /* moving the name would have been the first one */
+        assert_eq!(value["name"], Value::from("top"));
+        assert!(value.get("objects").is_some());
+    }
+
+    #[test]
+    fn a_failing_migration_names_its_versions() {
+        let mut value: Value = serde_yml::from_str("name: no bodies\n").unwrap();
+        let migrations: [Migration; 2] = [metadata::move_into_section, self::rename_bodies];
+        let error = migrate(&mut value, "scenario", &migrations).unwrap_err();
+        assert_eq!(
+            format!("{:#}", error),
+            "Failed to upgrade the scenario from version 2 to 3: There are no bodies"
+        );
+    }
+
+    #[test]
+    fn a_newer_file_is_refused() {
+        let mut value: Value = serde_yml::from_str("version: 4\nbodies: []\n").unwrap();
+        let error = migrate(&mut value, "save", &[self::rename_bodies]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The save was written by a newer version of SpaceS (version 4, this one reads up to 2)"
+        );
+    }
+}
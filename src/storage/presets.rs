@@ -0,0 +1,368 @@
+use glam::DVec3;
+
+use crate::{
+    scenario::PRESET_AUTHOR,
+    simulation::{
+        BodyAppearance, GRAVITATIONAL_CONSTANT, RingDefinition, SimulationEnterConfiguration,
+        SimulationObject, SimulationPhysicsObject,
+    },
+    storage::{Scenario, ScenarioMetadata},
+};
+
+pub const SOLAR_SYSTEM_NAME: &str = "Solar system";
+pub const SOLAR_SYSTEM_DESCRIPTION: &str =
+    "The sun, the eight planets and the moon where they were at j2000, in real units.";
+/* the elements below are the ones of this date */
+pub const SOLAR_SYSTEM_EPOCH: &str = "2000-01-01 12:00";
+/* meters */
+pub const ASTRONOMICAL_UNIT: f64 = 1.495_978_707e11;
+
+const SUN_MASS: f64 = 1.988_47e30;
+const SUN_RADIUS: f64 = 6.957e8;
+const EARTH_MASS: f64 = 5.972_17e24;
+const MOON_MASS: f64 = 7.342e22;
+const MOON_RADIUS: f64 = 1.7374e6;
+
+/* mean elements at the epoch, angles in degrees against the ecliptic and equinox of j2000 */
+struct OrbitalElements {
+    /* meters */
+    semi_major_axis: f64,
+    eccentricity: f64,
+    inclination: f64,
+    mean_longitude: f64,
+    longitude_of_perihelion: f64,
+    longitude_of_ascending_node: f64,
+}
+
+struct Planet {
+    name: &'static str,
+    mass: f64,
+    radius: f64,
+    orbit: OrbitalElements,
+    color: [f32; 3],
+    /* degrees between the pole and the ecliptic normal */
+    axial_tilt: f32,
+}
+
+/* heliocentric, from the approximate elements of the major planets by jpl (standish). the earth
+and moon share the orbit of their barycenter */
+const PLANETS: [Planet; 8] = [
+    Planet {
+        name: "Mercury",
+        mass: 3.3011e23,
+        radius: 2.4397e6,
+        orbit: OrbitalElements {
+            semi_major_axis: 0.387_099_27 * ASTRONOMICAL_UNIT,
+            eccentricity: 0.205_635_93,
+            inclination: 7.004_979_02,
+            mean_longitude: 252.250_323_50,
+            longitude_of_perihelion: 77.457_796_28,
+            longitude_of_ascending_node: 48.330_765_93,
+        },
+        color: [0.55, 0.52, 0.5],
+        axial_tilt: 0.03,
+    },
+    Planet {
+        name: "Venus",
+        mass: 4.8675e24,
+        radius: 6.0518e6,
+        orbit: OrbitalElements {
+            semi_major_axis: 0.723_335_66 * ASTRONOMICAL_UNIT,
+            eccentricity: 0.006_776_72,
+            inclination: 3.394_676_05,
+            mean_longitude: 181.979_099_50,
+            longitude_of_perihelion: 131.602_467_18,
+            longitude_of_ascending_node: 76.679_842_55,
+        },
+        color: [0.9, 0.82, 0.62],
+        axial_tilt: 177.36,
+    },
+    Planet {
+        name: "Earth",
+        mass: EARTH_MASS,
+        radius: 6.371e6,
+        orbit: OrbitalElements {
+            semi_major_axis: 1.000_002_61 * ASTRONOMICAL_UNIT,
+            eccentricity: 0.016_711_23,
+            inclination: -0.000_015_31,
+            mean_longitude: 100.464_571_66,
+            longitude_of_perihelion: 102.937_681_93,
+            longitude_of_ascending_node: 0.,
+        },
+        color: [0.25, 0.42, 0.75],
+        axial_tilt: 23.44,
+    },
+    Planet {
+        name: "Mars",
+        mass: 6.4171e23,
+        radius: 3.3895e6,
+        orbit: OrbitalElements {
+            semi_major_axis: 1.523_710_34 * ASTRONOMICAL_UNIT,
+            eccentricity: 0.093_394_10,
+            inclination: 1.849_691_42,
+            mean_longitude: -4.553_432_05,
+            longitude_of_perihelion: -23.943_629_59,
+            longitude_of_ascending_node: 49.559_538_91,
+        },
+        color: [0.75, 0.38, 0.22],
+        axial_tilt: 25.19,
+    },
+    Planet {
+        name: "Jupiter",
+        mass: 1.898_19e27,
+        radius: 6.9911e7,
+        orbit: OrbitalElements {
+            semi_major_axis: 5.202_887_00 * ASTRONOMICAL_UNIT,
+            eccentricity: 0.048_386_24,
+            inclination: 1.304_396_95,
+            mean_longitude: 34.396_440_51,
+            longitude_of_perihelion: 14.728_479_83,
+            longitude_of_ascending_node: 100.473_909_09,
+        },
+        color: [0.8, 0.68, 0.55],
+        axial_tilt: 3.13,
+    },
+    Planet {
+        name: "Saturn",
+        mass: 5.6834e26,
+        radius: 5.8232e7,
+        orbit: OrbitalElements {
+            semi_major_axis: 9.536_675_94 * ASTRONOMICAL_UNIT,
+            eccentricity: 0.053_861_79,
+            inclination: 2.485_991_87,
+            mean_longitude: 49.954_244_23,
+            longitude_of_perihelion: 92.598_878_31,
+            longitude_of_ascending_node: 113.662_424_48,
+        },
+        color: [0.82, 0.7, 0.48],
+        axial_tilt: 26.73,
+    },
+    Planet {
+        name: "Uranus",
+        mass: 8.6810e25,
+        radius: 2.5362e7,
+        orbit: OrbitalElements {
+            semi_major_axis: 19.189_164_64 * ASTRONOMICAL_UNIT,
+            eccentricity: 0.047_257_44,
+            inclination: 0.772_637_83,
+            mean_longitude: 313.238_104_51,
+            longitude_of_perihelion: 170.954_276_30,
+            longitude_of_ascending_node: 74.016_925_03,
+        },
+        color: [0.6, 0.8, 0.85],
+        axial_tilt: 97.77,
+    },
+    Planet {
+        name: "Neptune",
+        mass: 1.024_13e26,
+        radius: 2.4622e7,
+        orbit: OrbitalElements {
+            semi_major_axis: 30.069_922_76 * ASTRONOMICAL_UNIT,
+            eccentricity: 0.008_590_48,
+            inclination: 1.770_043_47,
+            mean_longitude: -55.120_029_69,
+            longitude_of_perihelion: 44.964_762_27,
+            longitude_of_ascending_node: 131.784_225_74,
+        },
+        color: [0.3, 0.45, 0.85],
+        axial_tilt: 28.32,
+    },
+];
+
+/* a body in the heliocentric frame of the elements, before it is moved to the barycenter */
+struct PresetBody {
+    name: &'static str,
+    mass: f64,
+    radius: f64,
+    position: DVec3,
+    velocity: DVec3,
+    appearance: BodyAppearance,
+}
+
+/* geocentric mean elements of the moon at j2000 */
+const MOON_ORBIT: OrbitalElements = OrbitalElements {
+    semi_major_axis: 3.844e8,
+    eccentricity: 0.0549,
+    inclination: 5.145,
+    mean_longitude: 218.316_4,
+    longitude_of_perihelion: 83.353_2,
+    longitude_of_ascending_node: 125.044_5,
+};
+
+pub fn solar_system() -> Scenario {
+    Scenario {
+        metadata: ScenarioMetadata {
+            name: SOLAR_SYSTEM_NAME.to_string(),
+            author: PRESET_AUTHOR.to_string(),
+            description: SOLAR_SYSTEM_DESCRIPTION.to_string(),
+            ..ScenarioMetadata::default()
+        },
+        epoch: Some(SOLAR_SYSTEM_EPOCH.to_string()),
+        ..Scenario::new(self::solar_system_objects())
+    }
+}
+
+/* the mass and radius of the bodies of the preset by name, for imported state vectors */
+pub fn major_body(name: &str) -> Option<SimulationPhysicsObject> {
+    let (mass, radius) = match name.to_ascii_lowercase().as_str() {
+        "sun" => (SUN_MASS, SUN_RADIUS),
+        "moon" => (MOON_MASS, MOON_RADIUS),
+        _ => PLANETS
+            .iter()
+            .find(|planet| planet.name.eq_ignore_ascii_case(name))
+            .map(|planet| (planet.mass, planet.radius))?,
+    };
+    Some(SimulationPhysicsObject::new(mass as f32, radius as f32))
+}
+
+/* the state vectors follow from the elements with the gravitational constant of the simulation,
+so the periods come out right. the whole system is moved to rest around its barycenter, the sun
+wobbles about it */
+pub fn solar_system_objects() -> Vec<SimulationObject> {
+    let mut bodies = vec![PresetBody {
+        name: "Sun",
+        mass: SUN_MASS,
+        radius: SUN_RADIUS,
+        position: DVec3::ZERO,
+        velocity: DVec3::ZERO,
+        appearance: BodyAppearance {
+            emissive: true,
+            tags: vec!["star".to_string()],
+            ..Default::default()
+        },
+    }];
+    for planet in &PLANETS {
+        let appearance = BodyAppearance {
+            color: Some(planet.color),
+            tags: vec!["planet".to_string()],
+            spin_axis: Some(self::spin_axis(planet.axial_tilt)),
+            rings: (planet.name == "Saturn").then(|| RingDefinition {
+                inner_radius: 1.24,
+                outer_radius: 2.27,
+                texture: None,
+                bands: Vec::new(),
+                opacity: 0.9,
+            }),
+            ..Default::default()
+        };
+        if planet.name != "Earth" {
+            let (position, velocity) = self::state_vector(
+                &planet.orbit,
+                GRAVITATIONAL_CONSTANT * (SUN_MASS + planet.mass),
+            );
+            bodies.push(PresetBody {
+                name: planet.name,
+                mass: planet.mass,
+                radius: planet.radius,
+                position,
+                velocity,
+                appearance,
+            });
+            continue;
+        }
+        let (barycenter, barycenter_velocity) = self::state_vector(
+            &planet.orbit,
+            GRAVITATIONAL_CONSTANT * (SUN_MASS + EARTH_MASS + MOON_MASS),
+        );
+        let (moon_offset, moon_velocity) = self::state_vector(
+            &MOON_ORBIT,
+            GRAVITATIONAL_CONSTANT * (EARTH_MASS + MOON_MASS),
+        );
+        let moon_share = MOON_MASS / (EARTH_MASS + MOON_MASS);
+        bodies.push(PresetBody {
+            name: "Earth",
+            mass: EARTH_MASS,
+            radius: planet.radius,
+            position: barycenter - moon_offset * moon_share,
+            velocity: barycenter_velocity - moon_velocity * moon_share,
+            appearance,
+        });
+        bodies.push(PresetBody {
+            name: "Moon",
+            mass: MOON_MASS,
+            radius: MOON_RADIUS,
+            position: barycenter + moon_offset * (1. - moon_share),
+            velocity: barycenter_velocity + moon_velocity * (1. - moon_share),
+            appearance: BodyAppearance {
+                color: Some([0.6, 0.6, 0.58]),
+                tags: vec!["moon".to_string()],
+                spin_axis: Some(self::spin_axis(6.68)),
+                ..Default::default()
+            },
+        });
+    }
+
+    let total_mass = bodies.iter().map(|body| body.mass).sum::<f64>();
+    let barycenter = bodies
+        .iter()
+        .map(|body| body.position * body.mass)
+        .sum::<DVec3>()
+        / total_mass;
+    let drift = bodies
+        .iter()
+        .map(|body| body.velocity * body.mass)
+        .sum::<DVec3>()
+        / total_mass;
+    bodies
+        .into_iter()
+        .map(|body| SimulationObject {
+            appearance: body.appearance,
+            ..SimulationObject::new(
+                body.name.to_string(),
+                SimulationPhysicsObject::new(body.mass as f32, body.radius as f32),
+                SimulationEnterConfiguration::new(
+                    (body.velocity - drift).as_vec3().to_array(),
+                    (body.position - barycenter).as_vec3().to_array(),
+                ),
+            )
+        })
+        .collect()
+}
+
+/* position and velocity relative to the focus, in simulation coordinates. the ecliptic lies in
+the xz plane with its north towards +y, the equinox towards +x */
+fn state_vector(orbit: &OrbitalElements, gravitational_parameter: f64) -> (DVec3, DVec3) {
+    let eccentricity = orbit.eccentricity;
+    let semi_major_axis = orbit.semi_major_axis;
+    let node = orbit.longitude_of_ascending_node.to_radians();
+    let argument_of_perihelion =
+        (orbit.longitude_of_perihelion - orbit.longitude_of_ascending_node).to_radians();
+    let mean_anomaly = (orbit.mean_longitude - orbit.longitude_of_perihelion)
+        .to_radians()
+        .rem_euclid(std::f64::consts::TAU);
+    /* kepler's equation by newton's method, converged long before the last iteration */
+    let mut eccentric_anomaly = mean_anomaly + eccentricity * mean_anomaly.sin();
+    for _ in 0..16 {
+        eccentric_anomaly -=
+            (eccentric_anomaly - eccentricity * eccentric_anomaly.sin() - mean_anomaly)
+                / (1. - eccentricity * eccentric_anomaly.cos());
+    }
+    let minor_factor = (1. - eccentricity * eccentricity).sqrt();
+    let rate = (gravitational_parameter / semi_major_axis.powi(3)).sqrt()
+        / (1. - eccentricity * eccentric_anomaly.cos());
+    /* in the orbital plane, the perihelion towards x */
+    let position = DVec3::new(
+        semi_major_axis * (eccentric_anomaly.cos() - eccentricity),
+        semi_major_axis * minor_factor * eccentric_anomaly.sin(),
+        0.,
+    );
+    let velocity = DVec3::new(
+        -semi_major_axis * eccentric_anomaly.sin() * rate,
+        semi_major_axis * minor_factor * eccentric_anomaly.cos() * rate,
+        0.,
+    );
+    let rotation = glam::DQuat::from_rotation_z(node)
+        * glam::DQuat::from_rotation_x(orbit.inclination.to_radians())
+        * glam::DQuat::from_rotation_z(argument_of_perihelion);
+    let ecliptic = |vector: DVec3| {
+        let vector = rotation * vector;
+        DVec3::new(vector.x, vector.z, -vector.y)
+    };
+    (ecliptic(position), ecliptic(velocity))
+}
+
+/* tilted towards +x, the direction of the pole around the normal is not modelled */
+fn spin_axis(axial_tilt: f32) -> [f32; 3] {
+    let tilt = axial_tilt.to_radians();
+    [tilt.sin(), tilt.cos(), 0.]
+}
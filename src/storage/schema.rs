@@ -0,0 +1,415 @@
+use anyhow::Result;
+use serde_yml::{Mapping, Value};
+
+use crate::storage::SCENARIO_VERSION;
+
+const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+/* parse_date reads a date with an optional time, surrounding spaces are trimmed */
+const DATE_PATTERN: &str = r"^\s*[0-9]+-[0-9]{1,2}-[0-9]{1,2}( +[0-9]{1,2}:[0-9]{1,2})?\s*$";
+
+/* the json schema of the scenario files of this version, written by hand along the structs and
+the rules of validation. the rules reaching across fields, like unique names or the outer ring
+radius exceeding the inner one, are only described. yaml, toml and ron scenarios share it since
+they are read into the same tree */
+pub fn scenario_schema() -> Value {
+    let scenario = self::object(
+        "A SpaceS scenario, the bodies in the units of the built-in scenes",
+        [
+            (
+                "version",
+                self::with(
+                    self::typed("integer", "The version of the format, 1 when missing"),
+                    [
+                        ("minimum", Value::from(1)),
+                        ("maximum", Value::from(SCENARIO_VERSION)),
+                    ],
+                ),
+            ),
+            ("metadata", self::reference("metadata")),
+            ("units", self::reference("units")),
+            (
+                "epoch",
+                self::date(
+                    "The utc date at simulation time zero like 2000-01-01 12:00, j2000 when missing",
+                ),
+            ),
+            (
+                "bodies",
+                self::with(
+                    self::typed("array", "At least one body, each with a name of its own"),
+                    [
+                        ("items", self::reference("body")),
+                        ("minItems", Value::from(1)),
+                    ],
+                ),
+            ),
+        ],
+        &["bodies"],
+    );
+    let definitions = [
+        ("metadata", self::metadata()),
+        ("units", self::units()),
+        ("body", self::body()),
+        ("appearance", self::appearance()),
+        ("rings", self::rings()),
+    ];
+    let mut defs = Mapping::new();
+    for (name, definition) in definitions {
+        defs.insert(Value::from(name), definition);
+    }
+    /* the dialect and the title first, for who reads the file */
+    let mut schema = Mapping::new();
+    schema.insert(Value::from("$schema"), Value::from(SCHEMA_DIALECT));
+    schema.insert(Value::from("title"), Value::from("SpaceS scenario"));
+    if let Value::Mapping(fields) = scenario {
+        schema.extend(fields);
+    }
+    schema.insert(Value::from("$defs"), Value::Mapping(defs));
+    Value::Mapping(schema)
+}
+
+/* what --dump-schema prints */
+pub fn scenario_schema_json() -> Result<String> {
+    Ok(serde_json::to_string_pretty(&self::scenario_schema())? + "\n")
+}
+
+fn metadata() -> Value {
+    self::object(
+        "What the scenario browser shows, a file without a name is shown by its file name",
+        [
+            ("name", self::typed("string", "The display name")),
+            ("author", self::typed("string", "Who made the scenario")),
+            (
+                "description",
+                self::typed("string", "A few sentences about it"),
+            ),
+            ("created", self::date("The utc date it was first saved")),
+            (
+                "modified",
+                self::date("The utc date it was saved last, stamped on saving"),
+            ),
+            (
+                "thumbnail",
+                self::typed(
+                    "string",
+                    "An image relative to the scenario file, or a png starting with data:image/png;base64,",
+                ),
+            ),
+        ],
+        &[],
+    )
+}
+
+fn units() -> Value {
+    let unit = |description: &str, units: &[&str]| {
+        self::with(
+            self::typed("string", description),
+            [("enum", Value::from(units.to_vec()))],
+        )
+    };
+    self::object(
+        "What the numbers of the bodies are in, si when missing. speeds are lengths per time",
+        [
+            (
+                "length",
+                unit("Of radii and positions, m when missing", &["m", "km", "AU"]),
+            ),
+            ("mass", unit("kg when missing", &["kg", "solar"])),
+            (
+                "time",
+                unit("Of speeds, s when missing", &["s", "day", "year"]),
+            ),
+        ],
+        &[],
+    )
+}
+
+fn body() -> Value {
+    let physics = self::object(
+        "What the physics reads",
+        [
+            (
+                "mass",
+                self::with(
+                    self::typed(
+                        "number",
+                        "In the mass unit, a massless body is pulled without pulling",
+                    ),
+                    [("minimum", Value::from(0))],
+                ),
+            ),
+            (
+                "radius",
+                self::with(
+                    self::typed("number", "In the length unit"),
+                    [("exclusiveMinimum", Value::from(0))],
+                ),
+            ),
+        ],
+        &["mass", "radius"],
+    );
+    let enter = self::object(
+        "The state the body starts in",
+        [
+            (
+                "enter speed",
+                self::vector("The velocity in lengths per time unit"),
+            ),
+            (
+                "enter position",
+                self::vector("The position in the length unit"),
+            ),
+        ],
+        &["enter speed", "enter position"],
+    );
+    self::object(
+        "A body, the errors of loading name it",
+        [
+            (
+                "name",
+                self::with(
+                    self::typed("string", "Unique among the bodies of the scenario"),
+                    [("pattern", Value::from(r"\S"))],
+                ),
+            ),
+            ("compute values", physics),
+            ("enter simulation values", enter),
+            ("appearance", self::reference("appearance")),
+        ],
+        &["name", "compute values", "enter simulation values"],
+    )
+}
+
+fn appearance() -> Value {
+    let color = self::with(
+        self::vector("Linear rgb, a palette color is picked when missing"),
+        [(
+            "items",
+            self::with(
+                Value::Mapping(Mapping::new()),
+                [("minimum", Value::from(0))],
+            ),
+        )],
+    );
+    let spin_axis = self::with(
+        self::vector("The direction of the north pole, +y when missing"),
+        [(
+            "not",
+            self::with(
+                Value::Mapping(Mapping::new()),
+                [("const", Value::from(vec![0, 0, 0]))],
+            ),
+        )],
+    );
+    self::object(
+        "Purely visual, a body without a texture is drawn in its flat color",
+        [
+            (
+                "texture",
+                self::typed(
+                    "string",
+                    "An equirectangular image, relative to the scenario file or the assets",
+                ),
+            ),
+            ("color", color),
+            ("emissive", self::typed("boolean", "Drawn unlit, for stars")),
+            (
+                "luminosity",
+                self::with(
+                    self::typed("number", "Relative to the sun, read for emissive bodies"),
+                    [("minimum", Value::from(0))],
+                ),
+            ),
+            (
+                "tags",
+                self::with(
+                    self::typed("array", "Free form categories like star or moon"),
+                    [("items", self::typed("string", "A category"))],
+                ),
+            ),
+            ("spin axis", spin_axis),
+            ("rings", self::reference("rings")),
+        ],
+        &[],
+    )
+}
+
+fn rings() -> Value {
+    let band = self::with(
+        self::typed("array", "A linear rgba stop"),
+        [
+            ("items", self::typed("number", "A component")),
+            ("minItems", Value::from(4)),
+            ("maxItems", Value::from(4)),
+        ],
+    );
+    self::object(
+        "A flat annulus in the equatorial plane, the radii are multiples of the body radius",
+        [
+            (
+                "inner radius",
+                self::with(
+                    self::typed("number", "Where the rings start"),
+                    [("exclusiveMinimum", Value::from(0))],
+                ),
+            ),
+            (
+                "outer radius",
+                self::with(
+                    self::typed("number", "Where the rings end, beyond the inner radius"),
+                    [("exclusiveMinimum", Value::from(0))],
+                ),
+            ),
+            (
+                "texture",
+                self::typed("string", "A radial strip, replaces the bands"),
+            ),
+            (
+                "bands",
+                self::with(
+                    self::typed(
+                        "array",
+                        "Stops spread evenly from the inner to the outer radius",
+                    ),
+                    [("items", band)],
+                ),
+            ),
+            (
+                "opacity",
+                self::with(
+                    self::typed("number", "1 when missing"),
+                    [("minimum", Value::from(0)), ("maximum", Value::from(1))],
+                ),
+            ),
+        ],
+        &["inner radius", "outer radius"],
+    )
+}
+
+/* the loader keeps unknown fields with a warning, the schema refuses them to catch misspelt keys */
+fn object<const N: usize>(
+    description: &str,
+    properties: [(&str, Value); N],
+    required: &[&str],
+) -> Value {
+    let mut fields = Mapping::new();
+    for (name, property) in properties {
+        fields.insert(Value::from(name), property);
+    }
+    let mut schema = self::with(
+        self::typed("object", description),
+        [
+            ("properties", Value::Mapping(fields)),
+            ("additionalProperties", Value::from(false)),
+        ],
+    );
+    if !required.is_empty()
+        && let Value::Mapping(fields) = &mut schema
+    {
+        fields.insert(Value::from("required"), Value::from(required.to_vec()));
+    }
+    schema
+}
+
+fn typed(kind: &str, description: &str) -> Value {
+    self::with(
+        Value::Mapping(Mapping::new()),
+        [
+            ("type", Value::from(kind)),
+            ("description", Value::from(description)),
+        ],
+    )
+}
+
+fn with<const N: usize>(mut schema: Value, keywords: [(&str, Value); N]) -> Value {
+    if let Value::Mapping(fields) = &mut schema {
+        for (keyword, value) in keywords {
+            fields.insert(Value::from(keyword), value);
+        }
+    }
+    schema
+}
+
+fn vector(description: &str) -> Value {
+    self::with(
+        self::typed("array", description),
+        [
+            ("items", self::typed("number", "x, y and z in turn")),
+            ("minItems", Value::from(3)),
+            ("maxItems", Value::from(3)),
+        ],
+    )
+}
+
+fn date(description: &str) -> Value {
+    self::with(
+        self::typed("string", description),
+        [("pattern", Value::from(DATE_PATTERN))],
+    )
+}
+
+fn reference(definition: &str) -> Value {
+    self::with(
+        Value::Mapping(Mapping::new()),
+        [("$ref", Value::from(format!("#/$defs/{}", definition)))],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        scenario::SCENARIO_PRESETS,
+        storage::{
+            Scenario, ScenarioFormat, ScenarioUnits, format_scenario,
+            units::{LengthUnit, MassUnit, TimeUnit},
+        },
+    };
+
+    /* through the text --dump-schema prints and the json the scenario is written as */
+    fn validator() -> jsonschema::Validator {
+        let schema: serde_json::Value =
+            serde_json::from_str(&scenario_schema_json().unwrap()).unwrap();
+        jsonschema::validator_for(&schema).unwrap()
+    }
+
+    fn errors(validator: &jsonschema::Validator, scenario: &Scenario) -> Vec<String> {
+        let text = format_scenario(scenario, ScenarioFormat::Json).unwrap();
+        let instance: serde_json::Value = serde_json::from_str(&text).unwrap();
+        validator
+            .iter_errors(&instance)
+            .map(|error| format!("{} at {}", error, error.instance_path()))
+            .collect()
+    }
+
+    #[test]
+    fn every_preset_matches_the_schema() {
+        let validator = self::validator();
+        /* the first preset is the default scenario */
+        for preset in &SCENARIO_PRESETS {
+            let mut scenario = preset.scenario();
+            scenario.metadata.touch();
+            let errors = self::errors(&validator, &scenario);
+            assert!(errors.is_empty(), "{}: {:#?}", preset.key, errors);
+        }
+        let mut scenario = SCENARIO_PRESETS[0].scenario();
+        scenario.epoch = Some("2000-01-01 12:00".to_string());
+        scenario.units = ScenarioUnits {
+            length: LengthUnit::AstronomicalUnit,
+            mass: MassUnit::SolarMass,
+            time: TimeUnit::Year,
+        };
+        assert_eq!(self::errors(&validator, &scenario), Vec::<String>::new());
+    }
+
+    #[test]
+    fn the_schema_refuses_what_validation_refuses() {
+        let validator = self::validator();
+        let mut scenario = SCENARIO_PRESETS[0].scenario();
+        scenario.objects[0].physics_data.simulation_body_mass = -1.;
+        scenario.epoch = Some("first of may".to_string());
+        assert_eq!(self::errors(&validator, &scenario).len(), 2);
+        scenario.objects.clear();
+        assert!(!self::errors(&validator, &scenario).is_empty());
+    }
+}
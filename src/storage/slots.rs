@@ -0,0 +1,156 @@
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::{Context, Result, bail};
+use tracing::warn;
+
+use crate::{
+    platform,
+    storage::{AUTOSAVE_SLOTS, QUICKSAVE_SLOTS, SaveFormat},
+};
+
+/* in the data directory, the quicksaves, the autosaves and the saves named in the saves window */
+pub const SAVES_DIRECTORY_NAME: &str = "saves";
+/* next to a save with the same stem, the scene when it was written */
+pub const THUMBNAIL_EXTENSION: &str = "png";
+/* left out of names, they are not allowed in file names on some platform */
+const RESERVED_CHARACTERS: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveSlotKind {
+    Named,
+    Quicksave,
+    Autosave,
+}
+
+/* a save file in the saves directory, its contents are read by who shows it */
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaveSlot {
+    pub path: PathBuf,
+    /* the file stem */
+    pub name: String,
+    pub kind: SaveSlotKind,
+    pub modified: Option<SystemTime>,
+    /* none until the thumbnail of the slot is written */
+    pub thumbnail: Option<PathBuf>,
+}
+
+pub fn saves_directory() -> PathBuf {
+    platform::data_directory().join(SAVES_DIRECTORY_NAME)
+}
+
+/* a named slot in the saves directory, the name is checked with is_save_slot_name */
+pub fn save_slot_path(name: &str, format: SaveFormat) -> PathBuf {
+    self::saves_directory().join(format!("{}.{}", name.trim(), format.extensions()[0]))
+}
+
+pub fn thumbnail_path(save_path: &Path) -> PathBuf {
+    save_path.with_extension(THUMBNAIL_EXTENSION)
+}
+
+/* a file name without a directory, and none of the quicksave and autosave ones which would be
+overwritten by the next of those */
+pub fn is_save_slot_name(name: &str) -> bool {
+    let name = name.trim();
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(RESERVED_CHARACTERS)
+        && !name.contains(|character: char| character.is_control())
+        && self::kind(name) == SaveSlotKind::Named
+}
+
+/* the files with the extension of a save format, newest first. everything else in the directory
+is left out, a file that is not a valid save is listed and fails once it is read */
+pub fn list_save_slots() -> Vec<SaveSlot> {
+    let directory = self::saves_directory();
+    let entries = match std::fs::read_dir(&directory) {
+        Ok(entries) => entries,
+        Err(error) => {
+            if error.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to list {}: {}", directory.display(), error);
+            }
+            return Vec::new();
+        }
+    };
+    let mut slots: Vec<SaveSlot> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && SaveFormat::from_path(path).is_some())
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_string_lossy().into_owned();
+            let thumbnail = Some(self::thumbnail_path(&path)).filter(|path| path.is_file());
+            Some(SaveSlot {
+                kind: self::kind(&name),
+                modified: path
+                    .metadata()
+                    .and_then(|metadata| metadata.modified())
+                    .ok(),
+                name,
+                thumbnail,
+                path,
+            })
+        })
+        .collect();
+    slots.sort_by(|first, second| {
+        second
+            .modified
+            .cmp(&first.modified)
+            .then_with(|| first.path.cmp(&second.path))
+    });
+    slots
+}
+
+/* keeps the extension, the thumbnail is moved along. an existing slot of the name is not
+replaced */
+pub fn rename_save_slot(path: &Path, name: &str) -> Result<PathBuf> {
+    if !self::is_save_slot_name(name) {
+        bail!("{:?} cannot name a save", name.trim());
+    }
+    let mut file_name = name.trim().to_string();
+    if let Some(extension) = path.extension() {
+        file_name = format!("{}.{}", file_name, extension.to_string_lossy());
+    }
+    let renamed = path.with_file_name(file_name);
+    if renamed.exists() {
+        bail!("There is a save named {} already", name.trim());
+    }
+    std::fs::rename(path, &renamed).with_context(|| {
+        format!(
+            "Failed to rename {} to {}",
+            path.display(),
+            renamed.display()
+        )
+    })?;
+    let thumbnail = self::thumbnail_path(path);
+    if thumbnail.is_file()
+        && let Err(error) = std::fs::rename(&thumbnail, self::thumbnail_path(&renamed))
+    {
+        warn!("Failed to move {}: {}", thumbnail.display(), error);
+    }
+    Ok(renamed)
+}
+
+/* with its thumbnail */
+pub fn delete_save_slot(path: &Path) -> Result<()> {
+    std::fs::remove_file(path).with_context(|| format!("Failed to delete {}", path.display()))?;
+    let thumbnail = self::thumbnail_path(path);
+    if let Err(error) = std::fs::remove_file(&thumbnail)
+        && error.kind() != std::io::ErrorKind::NotFound
+    {
+        warn!("Failed to delete {}: {}", thumbnail.display(), error);
+    }
+    Ok(())
+}
+
+fn kind(stem: &str) -> SaveSlotKind {
+    if (0..QUICKSAVE_SLOTS).any(|slot| super::quicksave_stem(slot) == stem) {
+        SaveSlotKind::Quicksave
+    } else if (0..AUTOSAVE_SLOTS).any(|slot| super::autosave_stem(slot) == stem) {
+        SaveSlotKind::Autosave
+    } else {
+        SaveSlotKind::Named
+    }
+}
@@ -0,0 +1,338 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    simulation::{SimulationEnterConfiguration, SimulationObject, SimulationPhysicsObject},
+    storage::{presets::ASTRONOMICAL_UNIT, validation::ValidationError},
+};
+
+/* kilograms, the nominal one of the iau */
+pub const SOLAR_MASS: f64 = 1.988_47e30;
+/* seconds of a julian year */
+pub const YEAR: f64 = 365.25 * 86_400.;
+/* speeds above this many meters per second are probably not what the units were meant for */
+const SPEED_OF_LIGHT: f64 = 299_792_458.;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LengthUnit {
+    #[default]
+    #[serde(rename = "m")]
+    Meter,
+    #[serde(rename = "km")]
+    Kilometer,
+    #[serde(rename = "AU")]
+    AstronomicalUnit,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MassUnit {
+    #[default]
+    #[serde(rename = "kg")]
+    Kilogram,
+    #[serde(rename = "solar")]
+    SolarMass,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimeUnit {
+    #[default]
+    #[serde(rename = "s")]
+    Second,
+    #[serde(rename = "day")]
+    Day,
+    #[serde(rename = "year")]
+    Year,
+}
+
+/* what the numbers of the bodies of a scenario file are in, speeds are lengths per time. the
+simulation runs in si units with the gravitational constant of those, the bodies are converted
+on loading and back on saving. si when missing */
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct ScenarioUnits {
+    #[serde(rename = "length", default)]
+    pub length: LengthUnit,
+    #[serde(rename = "mass", default)]
+    pub mass: MassUnit,
+    #[serde(rename = "time", default)]
+    pub time: TimeUnit,
+}
+
+impl LengthUnit {
+    pub fn meters(&self) -> f64 {
+        match self {
+            LengthUnit::Meter => 1.,
+            LengthUnit::Kilometer => 1e3,
+            LengthUnit::AstronomicalUnit => ASTRONOMICAL_UNIT,
+        }
+    }
+}
+
+impl MassUnit {
+    pub fn kilograms(&self) -> f64 {
+        match self {
+            MassUnit::Kilogram => 1.,
+            MassUnit::SolarMass => SOLAR_MASS,
+        }
+    }
+}
+
+impl TimeUnit {
+    pub fn seconds(&self) -> f64 {
+        match self {
+            TimeUnit::Second => 1.,
+            TimeUnit::Day => 86_400.,
+            TimeUnit::Year => YEAR,
+        }
+    }
+}
+
+impl ScenarioUnits {
+    pub fn is_si(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /* on loading, a number too large for the simulation in si units fails the body */
+    pub fn convert_into_si(&self, objects: &mut [SimulationObject]) -> Vec<ValidationError> {
+        let mut errors = Vec::new();
+        if self.is_si() {
+            return errors;
+        }
+        for object in objects.iter_mut() {
+            let converted = self.convert(object, true);
+            for (field, before, after) in [
+                (
+                    "mass",
+                    object.physics_data.simulation_body_mass,
+                    converted.physics_data.simulation_body_mass,
+                ),
+                (
+                    "radius",
+                    object.physics_data.simulation_body_radius,
+                    converted.physics_data.simulation_body_radius,
+                ),
+            ] {
+                if before.is_finite() && !after.is_finite() {
+                    errors.push(self::overflow(object, field, before));
+                }
+            }
+            let enter = &object.enter_configuration;
+            let converted_enter = &converted.enter_configuration;
+            for (field, before, after) in [
+                (
+                    "enter speed",
+                    enter.simulation_enter_speed,
+                    converted_enter.simulation_enter_speed,
+                ),
+                (
+                    "enter position",
+                    enter.simulation_enter_position,
+                    converted_enter.simulation_enter_position,
+                ),
+            ] {
+                if let Some(index) =
+                    (0..3).find(|&index| before[index].is_finite() && !after[index].is_finite())
+                {
+                    errors.push(self::overflow(object, field, before[index]));
+                }
+            }
+            object.physics_data = converted.physics_data;
+            object.enter_configuration = converted.enter_configuration;
+        }
+        errors
+    }
+
+    /* the bodies as written to a file in these units */
+    pub fn written_from_si(&self, objects: &[SimulationObject]) -> Vec<SimulationObject> {
+        objects
+            .iter()
+            .map(|object| SimulationObject {
+                appearance: object.appearance.clone(),
+                ..self.convert(object, false)
+            })
+            .collect()
+    }
+
+    /* what the numbers probably do not mean. speeds are lengths per time, so au per second or
+    meters per year are a slip of the time unit more likely than intended */
+    pub fn warnings(&self) -> Vec<ValidationError> {
+        let speed = self.length.meters() / self.time.seconds();
+        let mut warnings = Vec::new();
+        if speed > SPEED_OF_LIGHT {
+            warnings.push(ValidationError {
+                body: "The scenario".to_string(),
+                field: "units",
+                message: format!(
+                    "make a speed of 1 faster than light ({:.3e} m/s), the time unit may be wrong",
+                    speed
+                ),
+            });
+        }
+        if speed < 1e-3 {
+            warnings.push(ValidationError {
+                body: "The scenario".to_string(),
+                field: "units",
+                message: format!(
+                    "make a speed of 1 barely move ({:.3e} m/s), the time unit may be wrong",
+                    speed
+                ),
+            });
+        }
+        warnings
+    }
+
+    /* in double precision, a value written back reads as the one that was loaded */
+    fn convert(&self, object: &SimulationObject, into_si: bool) -> SimulationObject {
+        let scale = |value: f32, factor: f64| match into_si {
+            true => (value as f64 * factor) as f32,
+            false => (value as f64 / factor) as f32,
+        };
+        let length = self.length.meters();
+        let speed = length / self.time.seconds();
+        let physics = &object.physics_data;
+        let enter = &object.enter_configuration;
+        SimulationObject::new(
+            object.id_name.clone(),
+            SimulationPhysicsObject::new(
+                scale(physics.simulation_body_mass, self.mass.kilograms()),
+                scale(physics.simulation_body_radius, length),
+            ),
+            SimulationEnterConfiguration::new(
+                enter
+                    .simulation_enter_speed
+                    .map(|value| scale(value, speed)),
+                enter
+                    .simulation_enter_position
+                    .map(|value| scale(value, length)),
+            ),
+        )
+    }
+}
+
+fn overflow(object: &SimulationObject, field: &'static str, value: f32) -> ValidationError {
+    ValidationError {
+        body: object.id_name.clone(),
+        field,
+        message: format!(
+            "of {} is beyond the range of the simulation in si units",
+            value
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        scenario,
+        storage::{ScenarioFormat, Settings, parse_scenario},
+    };
+
+    const ASTRONOMICAL: &str = "\
+units:
+  length: AU
+  mass: solar
+  time: year
+bodies:
+  - name: Sun
+    compute values: { mass: 1.0, radius: 0.00465047 }
+    enter simulation values: { enter speed: [0, 0, 0], enter position: [0, 0, 0] }
+  - name: Earth
+    compute values: { mass: 3.003e-6, radius: 4.2588e-5 }
+    enter simulation values: { enter speed: [0, 0, 6.283185307179586], enter position: [1, 0, 0] }
+  - name: Moon
+    compute values: { mass: 3.694e-8, radius: 1.1611e-5 }
+    enter simulation values: { enter speed: [0, 0, 6.498785307179586], enter position: [1.0025695, 0, 0] }
+";
+    /* the same bodies, the numbers converted with the constants above */
+    const SI: &str = "\
+bodies:
+  - name: Sun
+    compute values: { mass: 1.98847e30, radius: 695700409.7542291 }
+    enter simulation values: { enter speed: [0, 0, 0], enter position: [0, 0, 0] }
+  - name: Earth
+    compute values: { mass: 5.97137541e24, radius: 6371074.1173716 }
+    enter simulation values: { enter speed: [0, 0, 29785.25436559154], enter position: [149597870700, 0, 0] }
+  - name: Moon
+    compute values: { mass: 7.34540818e22, radius: 1736980.8766977 }
+    enter simulation values: { enter speed: [0, 0, 30807.299797529326], enter position: [149982262428.76364, 0, 0] }
+";
+
+    fn objects(text: &str) -> Vec<SimulationObject> {
+        parse_scenario(text, ScenarioFormat::Yaml)
+            .unwrap()
+            .0
+            .objects
+    }
+
+    #[test]
+    fn the_same_system_in_other_units_moves_the_same() {
+        let settings = Settings::default();
+        let mut astronomical = scenario::simulation_state(&self::objects(ASTRONOMICAL), &settings);
+        let mut si = scenario::simulation_state(&self::objects(SI), &settings);
+        /* a month, the moon goes once around the earth */
+        for _ in 0..(30. * 86_400. / si.timestep) as usize {
+            astronomical.step();
+            si.step();
+        }
+        for (astronomical, si) in astronomical.bodies.iter().zip(&si.bodies) {
+            assert_eq!(astronomical.id_name, si.id_name);
+            assert!(
+                astronomical.position.distance(si.position) < 1e-6 * ASTRONOMICAL_UNIT,
+                "{}: {} and {}",
+                si.id_name,
+                astronomical.position,
+                si.position
+            );
+            assert!(
+                astronomical.velocity.distance(si.velocity) < 1e-6 * si.velocity.length().max(1.),
+                "{}: {} and {}",
+                si.id_name,
+                astronomical.velocity,
+                si.velocity
+            );
+        }
+    }
+
+    #[test]
+    fn bodies_are_written_back_in_their_units() {
+        let units = ScenarioUnits {
+            length: LengthUnit::AstronomicalUnit,
+            mass: MassUnit::SolarMass,
+            time: TimeUnit::Year,
+        };
+        let written = units.written_from_si(&self::objects(SI));
+        let earth = &written[1];
+        assert!((earth.physics_data.simulation_body_mass - 3.003e-6).abs() < 1e-12);
+        assert!((earth.enter_configuration.simulation_enter_position[0] - 1.).abs() < 1e-6);
+        assert!(
+            (earth.enter_configuration.simulation_enter_speed[2] - std::f32::consts::TAU).abs()
+                < 1e-5
+        );
+    }
+
+    #[test]
+    fn a_value_beyond_f32_in_si_is_refused() {
+        let text = ASTRONOMICAL.replace("mass: 1.0,", "mass: 1e20,");
+        let error = parse_scenario(&text, ScenarioFormat::Yaml).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The units do not convert:\nSun: mass of 100000000000000000000 is beyond the range of the simulation in si units"
+        );
+    }
+
+    #[test]
+    fn unlikely_speed_units_warn() {
+        let warned = |length, time| {
+            ScenarioUnits {
+                length,
+                mass: MassUnit::Kilogram,
+                time,
+            }
+            .warnings()
+            .len()
+        };
+        assert_eq!(warned(LengthUnit::AstronomicalUnit, TimeUnit::Year), 0);
+        assert_eq!(warned(LengthUnit::Kilometer, TimeUnit::Second), 0);
+        assert_eq!(warned(LengthUnit::AstronomicalUnit, TimeUnit::Second), 1);
+        assert_eq!(warned(LengthUnit::Meter, TimeUnit::Year), 1);
+    }
+}
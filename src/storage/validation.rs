@@ -0,0 +1,353 @@
+use std::fmt;
+
+use wgpu::naga::FastHashSet;
+
+use crate::{
+    simulation::{BodyAppearance, SimulationObject},
+    storage::AssetResolver,
+};
+
+/* a rule a body of a scenario breaks, named after the field as it is written in the file */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    pub body: String,
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(formatter, "{}: {} {}", self.body, self.field, self.message)
+    }
+}
+
+/* what would break the physics or the rendering. a mass of zero is allowed, such a body is
+pulled without pulling */
+pub fn validate(objects: &[SimulationObject]) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    let mut names = FastHashSet::default();
+    for object in objects {
+        let mut error = |field, message: String| {
+            errors.push(ValidationError {
+                body: self::body_label(object),
+                field,
+                message,
+            })
+        };
+        if object.id_name.trim().is_empty() {
+            error("name", "must not be empty".to_string());
+        } else if !names.insert(object.id_name.as_str()) {
+            error("name", "is used by another body".to_string());
+        }
+        let physics = &object.physics_data;
+        match physics.simulation_body_mass {
+            mass if !mass.is_finite() => error("mass", format!("must be a number, got {}", mass)),
+            mass if mass < 0. => error("mass", format!("must not be negative, got {}", mass)),
+            _ => {}
+        }
+        match physics.simulation_body_radius {
+            radius if !radius.is_finite() => {
+                error("radius", format!("must be a number, got {}", radius))
+            }
+            radius if radius <= 0. => error("radius", format!("must be positive, got {}", radius)),
+            _ => {}
+        }
+        let enter = &object.enter_configuration;
+        for (field, vector) in [
+            ("enter speed", enter.simulation_enter_speed),
+            ("enter position", enter.simulation_enter_position),
+        ] {
+            if !vector.iter().all(|component| component.is_finite()) {
+                error(field, format!("must be numbers, got {:?}", vector));
+            }
+        }
+        self::validate_appearance(&object.appearance, &mut error);
+    }
+    errors
+}
+
+/* what still loads but is probably not what was meant */
+pub fn warnings(objects: &[SimulationObject], assets: &AssetResolver) -> Vec<ValidationError> {
+    let mut warnings = Vec::new();
+    for object in objects {
+        let appearance = &object.appearance;
+        let textures = [
+            ("texture", appearance.texture.as_ref()),
+            (
+                "rings texture",
+                appearance
+                    .rings
+                    .as_ref()
+                    .and_then(|rings| rings.texture.as_ref()),
+            ),
+        ];
+        for (field, texture) in textures {
+            if let Some(texture) = texture
+                && !assets.resolve(texture).exists()
+            {
+                warnings.push(ValidationError {
+                    body: self::body_label(object),
+                    field,
+                    message: format!("{} does not exist, the body is drawn without it", texture),
+                });
+            }
+        }
+    }
+    warnings
+}
+
+fn validate_appearance(appearance: &BodyAppearance, error: &mut impl FnMut(&'static str, String)) {
+    if let Some(color) = appearance.color
+        && !color
+            .iter()
+            .all(|component| component.is_finite() && *component >= 0.)
+    {
+        error("color", format!("must not be negative, got {:?}", color));
+    }
+    if let Some(luminosity) = appearance.luminosity
+        && !(luminosity.is_finite() && luminosity >= 0.)
+    {
+        error(
+            "luminosity",
+            format!("must not be negative, got {}", luminosity),
+        );
+    }
+    if let Some(axis) = appearance.spin_axis
+        && !(axis.iter().all(|component| component.is_finite()) && axis != [0.; 3])
+    {
+        error("spin axis", format!("must be a direction, got {:?}", axis));
+    }
+    let Some(rings) = appearance.rings.as_ref() else {
+        return;
+    };
+    if !(rings.inner_radius.is_finite() && rings.inner_radius > 0.) {
+        error(
+            "rings inner radius",
+            format!("must be positive, got {}", rings.inner_radius),
+        );
+    }
+    if !(rings.outer_radius.is_finite() && rings.outer_radius > rings.inner_radius) {
+        error(
+            "rings outer radius",
+            format!(
+                "must be larger than the inner radius, got {}",
+                rings.outer_radius
+            ),
+        );
+    }
+    if !(0. ..=1.).contains(&rings.opacity) {
+        error(
+            "rings opacity",
+            format!("must be between 0 and 1, got {}", rings.opacity),
+        );
+    }
+    if !rings
+        .bands
+        .iter()
+        .flatten()
+        .all(|component| component.is_finite())
+    {
+        error("rings bands", "must be numbers".to_string());
+    }
+}
+
+/* the name, which the errors of a body without one cannot point to */
+fn body_label(object: &SimulationObject) -> String {
+    match object.id_name.trim().is_empty() {
+        true => "A body without a name".to_string(),
+        false => object.id_name.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::{
+        RingDefinition, SimulationEnterConfiguration, SimulationPhysicsObject,
+    };
+
+    /* breaks a rule of the body, with the field and the message expected of it */
+    type Breakage = (fn(&mut SimulationObject), &'static str, &'static str);
+
+    fn body(name: &str) -> SimulationObject {
+        SimulationObject::new(
+            name.to_string(),
+            SimulationPhysicsObject::new(5.972e24, 6.371e6),
+            SimulationEnterConfiguration::new([0., 0., 29_780.], [1.496e11, 0., 0.]),
+        )
+    }
+
+    fn rings() -> RingDefinition {
+        RingDefinition {
+            inner_radius: 1.2,
+            outer_radius: 2.3,
+            texture: None,
+            bands: Vec::new(),
+            opacity: 1.,
+        }
+    }
+
+    /* each row breaks one rule of an otherwise valid body */
+    #[test]
+    fn each_rule_names_the_body_and_the_field() {
+        let table: [Breakage; 16] = [
+            (
+                |body| body.id_name = " ".to_string(),
+                "name",
+                "must not be empty",
+            ),
+            (
+                |body| body.physics_data.simulation_body_mass = -1.,
+                "mass",
+                "must not be negative, got -1",
+            ),
+            (
+                |body| body.physics_data.simulation_body_mass = f32::NAN,
+                "mass",
+                "must be a number, got NaN",
+            ),
+            (
+                |body| body.physics_data.simulation_body_radius = 0.,
+                "radius",
+                "must be positive, got 0",
+            ),
+            (
+                |body| body.physics_data.simulation_body_radius = f32::INFINITY,
+                "radius",
+                "must be a number, got inf",
+            ),
+            (
+                |body| body.enter_configuration.simulation_enter_speed[1] = f32::NAN,
+                "enter speed",
+                "must be numbers, got [0.0, NaN, 29780.0]",
+            ),
+            (
+                |body| body.enter_configuration.simulation_enter_position[0] = f32::INFINITY,
+                "enter position",
+                "must be numbers, got [inf, 0.0, 0.0]",
+            ),
+            (
+                |body| body.appearance.color = Some([1., -0.5, 0.]),
+                "color",
+                "must not be negative, got [1.0, -0.5, 0.0]",
+            ),
+            (
+                |body| body.appearance.luminosity = Some(-2.),
+                "luminosity",
+                "must not be negative, got -2",
+            ),
+            (
+                |body| body.appearance.luminosity = Some(f64::NAN),
+                "luminosity",
+                "must not be negative, got NaN",
+            ),
+            (
+                |body| body.appearance.spin_axis = Some([0.; 3]),
+                "spin axis",
+                "must be a direction, got [0.0, 0.0, 0.0]",
+            ),
+            (
+                |body| body.appearance.spin_axis = Some([0., f32::NAN, 1.]),
+                "spin axis",
+                "must be a direction, got [0.0, NaN, 1.0]",
+            ),
+            (
+                |body| {
+                    body.appearance.rings = Some(RingDefinition {
+                        inner_radius: 0.,
+                        ..self::rings()
+                    })
+                },
+                "rings inner radius",
+                "must be positive, got 0",
+            ),
+            (
+                |body| {
+                    body.appearance.rings = Some(RingDefinition {
+                        outer_radius: 1.,
+                        ..self::rings()
+                    })
+                },
+                "rings outer radius",
+                "must be larger than the inner radius, got 1",
+            ),
+            (
+                |body| {
+                    body.appearance.rings = Some(RingDefinition {
+                        opacity: 1.5,
+                        ..self::rings()
+                    })
+                },
+                "rings opacity",
+                "must be between 0 and 1, got 1.5",
+            ),
+            (
+                |body| {
+                    body.appearance.rings = Some(RingDefinition {
+                        bands: vec![[1., 1., 1., 1.], [0., f32::NAN, 0., 1.]],
+                        ..self::rings()
+                    })
+                },
+                "rings bands",
+                "must be numbers",
+            ),
+        ];
+        for (breaks, field, message) in table {
+            let mut body = self::body("Earth");
+            breaks(&mut body);
+            let errors = validate(&[body]);
+            let body = match field {
+                "name" => "A body without a name",
+                _ => "Earth",
+            };
+            assert_eq!(
+                errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                [format!("{}: {} {}", body, field, message)]
+            );
+        }
+    }
+
+    #[test]
+    fn a_massless_body_with_rings_is_valid() {
+        let mut body = self::body("Probe");
+        body.physics_data.simulation_body_mass = 0.;
+        body.appearance.rings = Some(self::rings());
+        assert_eq!(validate(&[body]), Vec::new());
+    }
+
+    #[test]
+    fn a_name_may_be_used_once() {
+        let errors = validate(&[self::body("Earth"), self::body("Moon"), self::body("Earth")]);
+        assert_eq!(
+            errors,
+            [ValidationError {
+                body: "Earth".to_string(),
+                field: "name",
+                message: "is used by another body".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn a_missing_texture_is_only_a_warning() {
+        let directory = tempfile::tempdir().unwrap();
+        std::fs::write(directory.path().join("earth.png"), "").unwrap();
+        let assets = AssetResolver::with_roots(vec![directory.path().to_path_buf()]);
+        let mut found = self::body("Earth");
+        found.appearance.texture = Some("earth.png".to_string());
+        let mut missing = self::body("Saturn");
+        missing.appearance.rings = Some(RingDefinition {
+            texture: Some("rings.png".to_string()),
+            ..self::rings()
+        });
+        let objects = [found, missing];
+        assert_eq!(validate(&objects), Vec::new());
+        assert_eq!(
+            warnings(&objects, &assets)
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            ["Saturn: rings texture rings.png does not exist, the body is drawn without it"]
+        );
+    }
+}
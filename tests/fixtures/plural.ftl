@@ -0,0 +1,14 @@
+## polish, it has four plural categories where english has two
+
+-brand = SpaceS
+
+bodies-imported =
+    { $count ->
+        [one] Zaimportowano { $count } ciało
+        [few] Zaimportowano { $count } ciała
+        [many] Zaimportowano { $count } ciał
+       *[other] Zaimportowano { $count } ciała
+    }
+
+window-saves = Zapisy { -brand }
+    .hint = Szybki zapis { $slot } nadpisuje najstarszy
@@ -0,0 +1,87 @@
+use std::{f64::consts::TAU, path::Path, process::Command};
+
+const JULIAN_YEAR: f64 = 365.25 * 86_400.;
+const DAY: f64 = 86_400.;
+
+struct Row {
+    time: f64,
+    body: String,
+    position: [f64; 3],
+    total_energy: f64,
+}
+
+/* runs the binary headless on a built-in preset, the settings of the user are left alone */
+fn run_headless(directory: &Path, preset: &str, duration: f64, sample_interval: f64) -> Vec<Row> {
+    let out = directory.join("run.csv");
+    let output = Command::new(env!("CARGO_BIN_EXE_simulation"))
+        .env("SPACES_SCENE", preset)
+        .arg("--settings")
+        .arg(directory.join("settings.toml"))
+        .arg("--headless")
+        .arg(format!("--duration={}", duration))
+        .arg(format!("--sample-interval={}", sample_interval))
+        .arg("--out")
+        .arg(&out)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let text = std::fs::read_to_string(&out).unwrap();
+    text.lines()
+        .skip(1)
+        .map(|line| {
+            let fields: Vec<&str> = line.split(',').collect();
+            let number = |index: usize| fields[index].parse::<f64>().unwrap();
+            Row {
+                time: number(0),
+                body: fields[1].to_string(),
+                position: [number(2), number(3), number(4)],
+                total_energy: number(8),
+            }
+        })
+        .collect()
+}
+
+/* the time the body has gone once around the center, interpolated between two samples */
+fn orbital_period(rows: &[Row], body: &str, center: &str) -> f64 {
+    let positions = |name: &str| -> Vec<(f64, [f64; 3])> {
+        rows.iter()
+            .filter(|row| row.body == name)
+            .map(|row| (row.time, row.position))
+            .collect()
+    };
+    let mut angle = 0.;
+    let mut previous: Option<(f64, f64)> = None;
+    for ((time, position), (_, center)) in positions(body).into_iter().zip(positions(center)) {
+        let direction = (position[2] - center[2]).atan2(position[0] - center[0]);
+        if let Some((previous_time, previous_direction)) = previous {
+            let step = (direction - previous_direction + TAU / 2.).rem_euclid(TAU) - TAU / 2.;
+            if (angle + step).abs() >= TAU {
+                let fraction = (TAU - angle.abs()) / step.abs();
+                return previous_time + fraction * (time - previous_time);
+            }
+            angle += step;
+        }
+        previous = Some((time, direction));
+    }
+    panic!("{} did not go around {} in the run", body, center);
+}
+
+#[test]
+fn the_earth_goes_around_the_sun_in_a_year() {
+    let directory = tempfile::tempdir().unwrap();
+    let rows = self::run_headless(directory.path(), "sun-earth-moon", 1.05 * JULIAN_YEAR, DAY);
+    let period = self::orbital_period(&rows, "Earth", "Sun");
+    assert!(
+        (period / DAY - 365.25).abs() < 1.,
+        "a year of {} days",
+        period / DAY
+    );
+    let initial_energy = rows.first().unwrap().total_energy;
+    let final_energy = rows.last().unwrap().total_energy;
+    let drift = (final_energy - initial_energy) / initial_energy.abs();
+    assert!(drift.abs() < 1e-9, "relative energy drift {:e}", drift);
+}
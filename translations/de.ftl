@@ -0,0 +1,125 @@
+## die texte der oberfläche auf deutsch, fehlende werden aus en.ftl genommen
+
+-brand = SpaceS
+language-name = Deutsch
+
+window-title = { -brand }
+
+menu-file = Datei
+menu-edit = Bearbeiten
+menu-bodies = Körper
+menu-display = Anzeige
+menu-help = Hilfe
+menu-open = Öffnen...
+menu-save-as = Speichern unter...
+menu-load-state = Zustand laden...
+menu-saves = Spielstände...
+menu-import-csv = Aus csv importieren...
+menu-import-horizons = Aus Horizons importieren...
+menu-export-trajectories = Bahnen exportieren...
+menu-scenario-properties = Szenario-Eigenschaften...
+menu-add-body = Körper hinzufügen...
+menu-scenarios = Szenarien
+menu-settings = Einstellungen
+menu-log = Protokoll
+menu-plots = Diagramme
+menu-about = Über
+menu-undo = Rückgängig
+menu-undo-named = { $action } rückgängig machen
+menu-redo = Wiederholen
+menu-redo-named = { $action } wiederholen
+
+body-menu-focus = Kamera ausrichten
+body-menu-follow = Folgen
+body-menu-edit = Bearbeiten
+body-menu-delete = Löschen
+body-menu-copy-state = Zustandsvektor kopieren
+
+file-dialog-open = Szenario öffnen
+file-dialog-load-state = Zustand laden
+file-dialog-save-as = Szenario speichern unter
+
+window-about = Über { -brand }
+window-add-body = Körper hinzufügen
+window-horizons = Aus Horizons importieren
+window-import = Körper importieren
+window-inspector = Inspektor
+window-log = Protokoll
+window-plots = Diagramme
+window-saves = Spielstände
+    .hint = Schnellspeicherungen wechseln reihum die Plätze, F5 überschreibt den ältesten
+window-scenario-properties = Szenario-Eigenschaften
+window-scenarios = Szenarien
+window-settings = Einstellungen
+window-trajectories = Bahnen
+window-viewport = Ansicht
+
+popup-quit = { -brand } beenden?
+popup-restore-autosave = Automatische Sicherung wiederherstellen?
+popup-replace-scenario = Szenario ersetzen?
+popup-cannot-load-scenario = Szenario kann nicht geladen werden
+popup-delete-scenario = Szenario löschen?
+popup-delete-body = Körper löschen?
+popup-replace-save = Spielstand ersetzen?
+popup-delete-save = Spielstand löschen?
+
+toast-screenshot-saved = Bildschirmfoto unter { $path } gespeichert
+toast-scenario-saved = Szenario gespeichert
+toast-scenario-reloaded = Szenario neu geladen
+toast-scenario-tweaked = Körper aus { $path } angepasst
+toast-scenario-load-failed = { $scenario } konnte nicht geladen werden: { $error }
+toast-autosaved = Automatisch gesichert
+toast-quicksaved = Schnell gespeichert
+toast-saved-slot = { $name } gespeichert
+toast-autosave-failed = Automatische Sicherung fehlgeschlagen: { $error }
+toast-quicksave-failed = Schnellspeichern fehlgeschlagen: { $error }
+toast-save-failed = Zustand konnte nicht gespeichert werden: { $error }
+toast-save-busy = Ein anderer Spielstand wird gerade geschrieben, bitte erneut versuchen
+toast-save-name-invalid = { $name } ist kein gültiger Name für einen Spielstand
+toast-quickloaded = Schnell geladen
+toast-quickload-failed = Schnellladen fehlgeschlagen: { $error }
+toast-state-load-failed = Zustand konnte nicht geladen werden: { $error }
+toast-autosave-restore-failed = Automatische Sicherung konnte nicht wiederhergestellt werden: { $error }
+toast-trajectories-exported = Bahnen nach { $path } exportiert
+toast-bodies-imported =
+    { $count ->
+        [0] Keine Körper aus { $source } importiert
+        [one] Einen Körper aus { $source } importiert
+       *[other] { $count } Körper aus { $source } importiert
+    }
+toast-bodies-imported-skipping =
+    { $count ->
+        [one] Einen Körper aus { $source } importiert, übersprungene Zeilen stehen im Protokoll
+       *[other] { $count } Körper aus { $source } importiert, übersprungene Zeilen stehen im Protokoll
+    }
+toast-gamepad-connected = Gamepad verbunden
+toast-gamepad-disconnected = Gamepad getrennt
+
+unit-gram = g
+unit-kilogram = kg
+unit-tonne = t
+unit-earth-mass =
+    { $value ->
+        [one] Erdmasse
+       *[other] Erdmassen
+    }
+unit-solar-mass =
+    { $value ->
+        [one] Sonnenmasse
+       *[other] Sonnenmassen
+    }
+unit-millimeter = mm
+unit-meter = m
+unit-kilometer = km
+unit-astronomical-unit = AE
+unit-light-year = Lj
+unit-meter-per-second = m/s
+unit-kilometer-per-second = km/s
+unit-millisecond = ms
+unit-second = s
+unit-minute = min
+unit-hour = h
+unit-day = d
+unit-year = a
+
+warning-weak-adapter = Deine Grafikkarte ist möglicherweise nicht leistungsfähig genug, um die Simulation mit PowerPreference::HighPerformance auszuführen. [{ $device }]
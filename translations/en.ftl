@@ -0,0 +1,129 @@
+## the messages of the ui in fluent syntax, { $name } is replaced by the argument of the name.
+## a translation is a file like this one named after its language, like de.ftl, in the
+## translations directory of the data directory. messages it leaves out are looked up here
+
+-brand = SpaceS
+language-name = English
+
+window-title = { -brand }
+
+menu-file = File
+menu-edit = Edit
+menu-bodies = Bodies
+menu-display = Display
+menu-help = Help
+menu-open = Open...
+menu-save-as = Save as...
+menu-load-state = Load state...
+menu-saves = Saves...
+menu-import-csv = Import from csv...
+menu-import-horizons = Import from Horizons...
+menu-export-trajectories = Export trajectories...
+menu-scenario-properties = Scenario properties...
+menu-add-body = Add body...
+menu-scenarios = Scenarios
+menu-settings = Settings
+menu-log = Log
+menu-plots = Plots
+menu-about = About
+menu-undo = Undo
+menu-undo-named = Undo { $action }
+menu-redo = Redo
+menu-redo-named = Redo { $action }
+
+body-menu-focus = Focus camera
+body-menu-follow = Follow
+body-menu-edit = Edit
+body-menu-delete = Delete
+body-menu-copy-state = Copy state vector
+
+file-dialog-open = Open scenario
+file-dialog-load-state = Load state
+file-dialog-save-as = Save scenario as
+
+window-about = About { -brand }
+window-add-body = Add body
+window-horizons = Import from Horizons
+window-import = Import bodies
+window-inspector = Inspector
+window-log = Log
+window-plots = Plots
+window-saves = Saves
+    .hint = Quicksaves rotate through their slots, F5 writes the oldest
+window-scenario-properties = Scenario properties
+window-scenarios = Scenarios
+window-settings = Settings
+window-trajectories = Trajectories
+window-viewport = Viewport
+
+popup-quit = Quit { -brand }?
+popup-restore-autosave = Restore autosave?
+popup-replace-scenario = Replace scenario?
+popup-cannot-load-scenario = Cannot load scenario
+popup-delete-scenario = Delete scenario?
+popup-delete-body = Delete body?
+popup-replace-save = Replace save?
+popup-delete-save = Delete save?
+
+toast-screenshot-saved = Screenshot saved to { $path }
+toast-scenario-saved = Saved the scenario
+toast-scenario-reloaded = Reloaded the scenario
+toast-scenario-tweaked = Tweaked the bodies from { $path }
+toast-scenario-load-failed = Failed to load { $scenario }: { $error }
+toast-autosaved = Autosaved
+toast-quicksaved = Quicksaved
+toast-saved-slot = Saved { $name }
+toast-autosave-failed = Failed to autosave: { $error }
+toast-quicksave-failed = Failed to quicksave: { $error }
+toast-save-failed = Failed to save the state: { $error }
+toast-save-busy = Another save is being written, try again
+toast-save-name-invalid = { $name } cannot name a save
+toast-quickloaded = Quickloaded
+toast-quickload-failed = Failed to quickload: { $error }
+toast-state-load-failed = Failed to load the state: { $error }
+toast-autosave-restore-failed = Failed to restore the autosave: { $error }
+toast-trajectories-exported = Exported the trajectories to { $path }
+toast-bodies-imported =
+    { $count ->
+        [0] Imported no bodies from { $source }
+        [one] Imported one body from { $source }
+       *[other] Imported { $count } bodies from { $source }
+    }
+toast-bodies-imported-skipping =
+    { $count ->
+        [one] Imported one body from { $source }, skipped rows are in the log
+       *[other] Imported { $count } bodies from { $source }, skipped rows are in the log
+    }
+toast-gamepad-connected = Gamepad connected
+toast-gamepad-disconnected = Gamepad disconnected
+
+## the units of format_quantity, the value is the number shown with them
+
+unit-gram = g
+unit-kilogram = kg
+unit-tonne = t
+unit-earth-mass =
+    { $value ->
+        [one] Earth mass
+       *[other] Earth masses
+    }
+unit-solar-mass =
+    { $value ->
+        [one] solar mass
+       *[other] solar masses
+    }
+unit-millimeter = mm
+unit-meter = m
+unit-kilometer = km
+unit-astronomical-unit = AU
+unit-light-year = ly
+unit-meter-per-second = m/s
+unit-kilometer-per-second = km/s
+unit-millisecond = ms
+unit-second = s
+unit-minute = min
+unit-hour = h
+unit-day = d
+unit-year = yr
+
+warning-weak-adapter = Your graphics card may not be capable enough to run the simulation on PowerPreference::HighPerformance. [{ $device }]